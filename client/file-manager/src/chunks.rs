@@ -0,0 +1,83 @@
+use std::ops::{Deref, Range};
+
+use common::types::HashT;
+use hash_db::HashDBRef;
+use sp_trie::{DBValue, TrieDBBuilder, TrieDBRawIterator, TrieLayout};
+use storage_hub_infra::types::{Chunk, ChunkId};
+
+/// Iterator over the chunks of a file within a range, in increasing chunk id order.
+///
+/// The chunks are read with a single iteration over the chunks trie of the file, instead of looking up
+/// every chunk from the root of the trie. Iteration stops at the first chunk that cannot be read.
+pub struct FileChunksIter<T: TrieLayout, DB> {
+    db: DB,
+    root: HashT<T>,
+    /// Position in the trie, `None` once the iteration is over.
+    raw_iter: Option<TrieDBRawIterator<T>>,
+    chunk_range: Range<ChunkId>,
+}
+
+impl<T, DB> FileChunksIter<T, DB>
+where
+    T: TrieLayout,
+    DB: Deref,
+    DB::Target: HashDBRef<T::Hash, DBValue> + Sized,
+{
+    /// Iterate over the chunks within `chunk_range` of the trie with the given `root`.
+    ///
+    /// `root` is `None` when no chunk has been written yet, in which case there is nothing to iterate over.
+    pub(crate) fn new(db: DB, root: Option<HashT<T>>, chunk_range: Range<ChunkId>) -> Self {
+        let root = root.unwrap_or_default();
+
+        let raw_iter = {
+            let trie = TrieDBBuilder::<T>::new(&*db, &root).build();
+            TrieDBRawIterator::new(&trie).ok().and_then(|mut raw_iter| {
+                // Chunk ids are big endian, so the trie keys are in chunk id order.
+                raw_iter
+                    .seek(&trie, &chunk_range.start.to_be_bytes())
+                    .ok()
+                    .map(|_| raw_iter)
+            })
+        };
+
+        Self {
+            db,
+            root,
+            raw_iter: if chunk_range.is_empty() {
+                None
+            } else {
+                raw_iter
+            },
+            chunk_range,
+        }
+    }
+}
+
+impl<T, DB> Iterator for FileChunksIter<T, DB>
+where
+    T: TrieLayout,
+    DB: Deref,
+    DB::Target: HashDBRef<T::Hash, DBValue> + Sized,
+{
+    type Item = (ChunkId, Chunk);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let raw_iter = self.raw_iter.as_mut()?;
+        let trie = TrieDBBuilder::<T>::new(&*self.db, &self.root).build();
+
+        let item = raw_iter.next_item(&trie).and_then(|item| {
+            let (key, chunk) = item.ok()?;
+            let chunk_id = ChunkId::from_be_bytes(key.as_slice().try_into().ok()?);
+            // Chunks past the range may have been dropped, so they are not read.
+            self.chunk_range
+                .contains(&chunk_id)
+                .then_some((chunk_id, chunk))
+        });
+
+        if item.is_none() {
+            self.raw_iter = None;
+        }
+
+        item
+    }
+}
@@ -1,24 +1,42 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
 use common::types::HashT;
 use sp_core::H256;
 use storage_hub_infra::types::{Chunk, ChunkId, FileProof, Key, Leaf, Metadata};
 
-use sp_trie::{recorder::Recorder, MemoryDB, Trie, TrieDBBuilder, TrieLayout, TrieMut};
+use sp_trie::{
+    recorder::Recorder, HashDBT, MemoryDB, Trie, TrieDBBuilder, TrieLayout, TrieMut, EMPTY_PREFIX,
+};
 use trie_db::TrieDBMutBuilder;
 
-use crate::traits::{FileStorage, FileStorageError, FileStorageWriteStatus};
+use crate::{
+    chunks::FileChunksIter,
+    traits::{FileStorage, FileStorageConfig, FileStorageError, FileStorageWriteStatus},
+};
 
 pub struct FileData<T: TrieLayout + 'static> {
     root: HashT<T>,
     memdb: MemoryDB<T::Hash>,
+    /// Range of chunks of the file that are retained.
+    chunk_range: Range<ChunkId>,
+    /// Chunks whose data was dropped because they are outside of `chunk_range`.
+    pruned_chunks: HashSet<ChunkId>,
+    /// Number of chunks in the trie, kept up to date on every write and delete so that it doesn't
+    /// require scanning the trie keys.
+    stored_chunks: u64,
 }
 
 impl<T: TrieLayout + 'static> FileData<T> {
-    fn new() -> Self {
+    fn new(chunk_range: Range<ChunkId>) -> Self {
         Self {
             root: Default::default(),
             memdb: MemoryDB::default(),
+            chunk_range,
+            pruned_chunks: HashSet::new(),
+            stored_chunks: 0,
         }
     }
 
@@ -31,32 +49,114 @@ impl<T: TrieLayout + 'static> FileData<T> {
         )
     }
 
-    pub fn stored_chunks_count(&self) -> u64 {
+    pub fn stored_chunks_count(&self) -> Result<u64, FileStorageError> {
+        Ok(self.stored_chunks)
+    }
+
+    /// Count the chunks in the trie by iterating over all of its keys.
+    ///
+    /// Pruned chunks keep their trie node, so they are still counted.
+    fn count_stored_chunks(&self) -> Result<u64, FileStorageError> {
         let trie = TrieDBBuilder::<T>::new(&self.memdb, &self.root).build();
-        let stored_chunks = trie.key_iter().iter().count() as u64;
-        stored_chunks
+        let stored_chunks = trie
+            .key_iter()
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+            .count() as u64;
+        Ok(stored_chunks)
+    }
+
+    /// Get the ids of the stored chunks within `chunk_range`, in increasing order.
+    pub fn stored_chunk_ids(&self) -> Result<Vec<ChunkId>, FileStorageError> {
+        let trie = TrieDBBuilder::<T>::new(&self.memdb, &self.root).build();
+
+        let mut chunk_ids = Vec::new();
+        for key in trie
+            .key_iter()
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+        {
+            let key = key.map_err(|_| FileStorageError::FailedToGetFileChunk)?;
+            let chunk_id = ChunkId::from_be_bytes(
+                key.as_slice()
+                    .try_into()
+                    .map_err(|_| FileStorageError::FailedToDecodeValue)?,
+            );
+            if self.chunk_range.contains(&chunk_id) {
+                chunk_ids.push(chunk_id);
+            }
+        }
+
+        Ok(chunk_ids)
+    }
+
+    /// Drop the data of the stored chunks outside of `chunk_range`.
+    ///
+    /// The nodes of the trie are kept, so the root still matches the file fingerprint and the chunks within
+    /// the range can still be proven. Only chunks that are big enough to be stored apart from their trie
+    /// node are dropped, which is the case for any chunk of a meaningful size.
+    fn prune_chunks_outside_of_range(&mut self, chunk_count: u64) -> Result<(), FileStorageError> {
+        let trie = TrieDBBuilder::<T>::new(&self.memdb, &self.root).build();
+
+        let mut values_to_prune = Vec::new();
+        for chunk_id in (0..chunk_count).filter(|chunk_id| !self.chunk_range.contains(chunk_id)) {
+            if self.pruned_chunks.contains(&chunk_id) {
+                continue;
+            }
+
+            let value_hash = trie
+                .get_hash(&chunk_id.to_be_bytes())
+                .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+                .ok_or(FileStorageError::FileChunkDoesNotExist)?;
+
+            values_to_prune.push((chunk_id, value_hash));
+        }
+
+        drop(trie);
+
+        for (chunk_id, value_hash) in values_to_prune {
+            // Values inlined in their trie node are not stored apart, so there is nothing to drop.
+            if self.memdb.contains(&value_hash, EMPTY_PREFIX) {
+                self.memdb.remove(&value_hash, EMPTY_PREFIX);
+            }
+            self.pruned_chunks.insert(chunk_id);
+        }
+
+        Ok(())
     }
 }
 
 pub struct InMemoryFileStorage<T: TrieLayout + 'static> {
+    config: FileStorageConfig,
     pub metadata: HashMap<Key, Metadata>,
+    pub metadata_hashes: HashMap<Key, H256>,
     pub file_data: HashMap<Key, FileData<T>>,
 }
 
 impl<T: TrieLayout> InMemoryFileStorage<T> {
+    /// Create an empty file storage with the default [`FileStorageConfig`].
     pub fn new() -> Self {
+        Self::with_config(FileStorageConfig::default())
+    }
+
+    /// Create an empty file storage with the given configuration.
+    pub fn with_config(config: FileStorageConfig) -> Self {
         Self {
+            config,
             metadata: HashMap::new(),
+            metadata_hashes: HashMap::new(),
             file_data: HashMap::new(),
         }
     }
 }
 
 impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
-    fn generate_proof(
+    fn config(&self) -> &FileStorageConfig {
+        &self.config
+    }
+
+    fn generate_proof_multi(
         &self,
         file_key: &Key,
-        chunk_id: &ChunkId,
+        chunk_ids: &[ChunkId],
     ) -> Result<FileProof, FileStorageError> {
         let metadata = self
             .metadata
@@ -71,7 +171,7 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .as_str(),
         );
 
-        if metadata.chunk_count() != file_data.stored_chunks_count() {
+        if metadata.chunk_count() != file_data.stored_chunks_count()? {
             return Err(FileStorageError::IncompleteFile);
         }
 
@@ -79,6 +179,14 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             return Err(FileStorageError::FingerprintAndStoredFileMismatch);
         }
 
+        // Chunks outside of the retained range are not held by this provider.
+        if !chunk_ids
+            .iter()
+            .all(|chunk_id| file_data.chunk_range.contains(chunk_id))
+        {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
         let recorder: Recorder<T::Hash> = Recorder::default();
 
         // A `TrieRecorder` is needed to create a proof of the "visited" leafs, by the end of this process.
@@ -88,11 +196,20 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .with_recorder(&mut trie_recorder)
             .build();
 
-        let chunk: Option<Vec<u8>> = trie
-            .get(&chunk_id.to_be_bytes())
-            .map_err(|_| FileStorageError::FailedToGetFileChunk)?;
+        // All the chunks are recorded against the same recorder, so their common trie nodes are only proven once.
+        let mut proven = Vec::with_capacity(chunk_ids.len());
+        for chunk_id in chunk_ids {
+            let chunk: Option<Vec<u8>> = trie
+                .get(&chunk_id.to_be_bytes())
+                .map_err(|_| FileStorageError::FailedToGetFileChunk)?;
 
-        let chunk = chunk.ok_or(FileStorageError::FileChunkDoesNotExist)?;
+            let chunk = chunk.ok_or(FileStorageError::FileChunkDoesNotExist)?;
+
+            proven.push(Leaf {
+                key: (*chunk_id).clone(),
+                data: chunk,
+            });
+        }
 
         // Drop the `trie_recorder` to release the `recorder`
         drop(trie_recorder);
@@ -104,18 +221,35 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .map_err(|_| FileStorageError::FailedToGenerateCompactProof)?;
 
         Ok(FileProof {
-            proven: Leaf {
-                key: (*chunk_id).clone(),
-                data: chunk,
-            },
+            proven,
             proof,
             root: file_data.get_root(),
         })
     }
 
-    fn delete_file(&mut self, file_key: &Key) {
+    fn delete_file(&mut self, file_key: &Key) -> Result<(), FileStorageError> {
         self.metadata.remove(file_key);
+        self.metadata_hashes.remove(file_key);
         self.file_data.remove(file_key);
+        Ok(())
+    }
+
+    fn stored_files(&self) -> Result<Vec<Key>, FileStorageError> {
+        Ok(self.metadata.keys().cloned().collect())
+    }
+
+    fn stored_chunks_count(&self, file_key: &Key) -> Result<u64, FileStorageError> {
+        self.file_data
+            .get(file_key)
+            .ok_or(FileStorageError::FileDoesNotExist)?
+            .stored_chunks_count()
+    }
+
+    fn stored_chunk_ids(&self, file_key: &Key) -> Result<Vec<ChunkId>, FileStorageError> {
+        self.file_data
+            .get(file_key)
+            .ok_or(FileStorageError::FileDoesNotExist)?
+            .stored_chunk_ids()
     }
 
     fn get_metadata(&self, file_key: &Key) -> Result<Metadata, FileStorageError> {
@@ -125,15 +259,70 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .ok_or(FileStorageError::FileDoesNotExist)
     }
 
-    fn set_metadata(&mut self, file_key: Key, metadata: Metadata) {
+    fn set_metadata(&mut self, file_key: Key, metadata: Metadata) -> Result<(), FileStorageError> {
+        self.file_data
+            .insert(file_key, FileData::new(0..metadata.chunk_count()));
+        self.metadata_hashes.insert(file_key, metadata.hash());
         self.metadata.insert(file_key, metadata);
-        self.file_data.insert(file_key, FileData::new());
+        Ok(())
+    }
+
+    fn metadata_hash(&self, file_key: &Key) -> Result<H256, FileStorageError> {
+        self.metadata_hashes
+            .get(file_key)
+            .copied()
+            .ok_or(FileStorageError::FileDoesNotExist)
+    }
+
+    fn set_chunk_range(
+        &mut self,
+        file_key: &Key,
+        range: Range<ChunkId>,
+    ) -> Result<(), FileStorageError> {
+        let metadata = self
+            .metadata
+            .get(file_key)
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        if range.is_empty() || range.end > metadata.chunk_count() {
+            return Err(FileStorageError::InvalidChunkRange);
+        }
+
+        let file_data = self.file_data.get_mut(file_key).expect(
+            format!(
+                "Invariant broken! Metadata for file key {:?} found but no associated trie",
+                file_key
+            )
+            .as_str(),
+        );
+
+        file_data.chunk_range = range;
+
+        // If the file is already complete, drop the chunks that are no longer retained.
+        let chunk_count = metadata.chunk_count();
+        if chunk_count == file_data.stored_chunks_count()? {
+            file_data.prune_chunks_outside_of_range(chunk_count)?;
+        }
+
+        Ok(())
+    }
+
+    fn get_chunk_range(&self, file_key: &Key) -> Result<Range<ChunkId>, FileStorageError> {
+        self.file_data
+            .get(file_key)
+            .map(|file_data| file_data.chunk_range.clone())
+            .ok_or(FileStorageError::FileDoesNotExist)
     }
 
     fn get_chunk(&self, file_key: &Key, chunk_id: &ChunkId) -> Result<Chunk, FileStorageError> {
         let file_data = self.file_data.get(file_key);
         let file_data = file_data.ok_or(FileStorageError::FileDoesNotExist)?;
 
+        // Chunks outside of the retained range are not held by this provider.
+        if !file_data.chunk_range.contains(chunk_id) {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
         let trie = TrieDBBuilder::<T>::new(&file_data.memdb, &file_data.root).build();
 
         Ok(trie
@@ -142,6 +331,25 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .ok_or(FileStorageError::FileChunkDoesNotExist)?)
     }
 
+    fn chunks(
+        &self,
+        file_key: &Key,
+    ) -> Result<impl Iterator<Item = (ChunkId, Chunk)> + '_, FileStorageError> {
+        let file_data = self
+            .file_data
+            .get(file_key)
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        // Nothing is stored until the first chunk is written.
+        let root = (file_data.root != HashT::<T>::default()).then_some(file_data.root);
+
+        Ok(FileChunksIter::<T, _>::new(
+            &file_data.memdb,
+            root,
+            file_data.chunk_range.clone(),
+        ))
+    }
+
     fn write_chunk(
         &mut self,
         file_key: &Key,
@@ -153,8 +361,28 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .get_mut(file_key)
             .ok_or(FileStorageError::FileDoesNotExist)?;
 
-        let mut trie =
-            TrieDBMutBuilder::<T>::new(&mut file_data.memdb, &mut file_data.root).build();
+        let metadata = self.metadata.get(file_key).expect(
+            format!(
+            "Invariant broken! Metadata for file key {:?} not found but associated trie is present",
+            file_key
+        )
+            .as_str(),
+        );
+
+        // Check that the chunk is part of the file, with the size expected for its position.
+        if *chunk_id >= metadata.chunk_count() {
+            return Err(FileStorageError::InvalidChunkId);
+        }
+        if data.len() as u64 != metadata.expected_chunk_size(*chunk_id) {
+            return Err(FileStorageError::InvalidChunkSize);
+        }
+
+        // Building a new trie resets the root, so the existing trie has to be opened once a chunk was written.
+        let mut trie = if file_data.root == HashT::<T>::default() {
+            TrieDBMutBuilder::<T>::new(&mut file_data.memdb, &mut file_data.root).build()
+        } else {
+            TrieDBMutBuilder::<T>::from_existing(&mut file_data.memdb, &mut file_data.root).build()
+        };
 
         // Check that we don't have a chunk already stored.
         if trie
@@ -170,6 +398,36 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
 
         drop(trie);
 
+        // The chunk was checked to not be stored yet, so it is a new key in the trie.
+        file_data.stored_chunks += 1;
+
+        // Check if we have all the chunks for the file.
+        if metadata.chunk_count() != file_data.stored_chunks_count()? {
+            return Ok(FileStorageWriteStatus::FileIncomplete);
+        }
+
+        // If we have all the chunks, check if the file metadata fingerprint and the file trie
+        // root matches.
+        if file_data.get_root() != metadata.fingerprint {
+            return Err(FileStorageError::FingerprintAndStoredFileMismatch);
+        }
+
+        // Now that the file is verified, drop the chunks that are not retained.
+        file_data.prune_chunks_outside_of_range(metadata.chunk_count())?;
+
+        Ok(FileStorageWriteStatus::FileComplete)
+    }
+
+    fn write_chunk_batch(
+        &mut self,
+        file_key: &Key,
+        chunks: &[(ChunkId, Chunk)],
+    ) -> Result<FileStorageWriteStatus, FileStorageError> {
+        let file_data = self
+            .file_data
+            .get_mut(file_key)
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
         let metadata = self.metadata.get(file_key).expect(
             format!(
             "Invariant broken! Metadata for file key {:?} not found but associated trie is present",
@@ -178,8 +436,49 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             .as_str(),
         );
 
+        // The trie is written to in place, so the whole batch is checked before writing any chunk.
+        {
+            let trie = TrieDBBuilder::<T>::new(&file_data.memdb, &file_data.root).build();
+            let mut batch_chunk_ids = HashSet::with_capacity(chunks.len());
+            for (chunk_id, data) in chunks {
+                // Check that the chunk is part of the file, with the size expected for its position.
+                if *chunk_id >= metadata.chunk_count() {
+                    return Err(FileStorageError::InvalidChunkId);
+                }
+                if data.len() as u64 != metadata.expected_chunk_size(*chunk_id) {
+                    return Err(FileStorageError::InvalidChunkSize);
+                }
+
+                // Check that we don't have the chunk already stored, nor twice in the batch.
+                if !batch_chunk_ids.insert(*chunk_id)
+                    || (file_data.root != HashT::<T>::default()
+                        && trie
+                            .contains(&chunk_id.to_be_bytes())
+                            .map_err(|_| FileStorageError::FailedToGetFileChunk)?)
+                {
+                    return Err(FileStorageError::FileChunkAlreadyExists);
+                }
+            }
+        }
+
+        // Building a new trie resets the root, so the existing trie has to be opened once a chunk was written.
+        let mut trie = if file_data.root == HashT::<T>::default() {
+            TrieDBMutBuilder::<T>::new(&mut file_data.memdb, &mut file_data.root).build()
+        } else {
+            TrieDBMutBuilder::<T>::from_existing(&mut file_data.memdb, &mut file_data.root).build()
+        };
+
+        // Insert all the chunks into the file trie, counting each one as soon as it is in.
+        for (chunk_id, data) in chunks {
+            trie.insert(&chunk_id.to_be_bytes(), data)
+                .map_err(|_| FileStorageError::FailedToInsertFileChunk)?;
+            file_data.stored_chunks += 1;
+        }
+
+        drop(trie);
+
         // Check if we have all the chunks for the file.
-        if metadata.chunk_count() != file_data.stored_chunks_count() {
+        if metadata.chunk_count() != file_data.stored_chunks_count()? {
             return Ok(FileStorageWriteStatus::FileIncomplete);
         }
 
@@ -189,6 +488,629 @@ impl<T: TrieLayout + 'static> FileStorage for InMemoryFileStorage<T> {
             return Err(FileStorageError::FingerprintAndStoredFileMismatch);
         }
 
+        // Now that the file is verified, drop the chunks that are not retained.
+        file_data.prune_chunks_outside_of_range(metadata.chunk_count())?;
+
         Ok(FileStorageWriteStatus::FileComplete)
     }
+
+    fn delete_chunk(&mut self, file_key: &Key, chunk_id: &ChunkId) -> Result<(), FileStorageError> {
+        let file_data = self
+            .file_data
+            .get_mut(file_key)
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        // No chunk has been written yet.
+        if file_data.root == HashT::<T>::default() {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
+        let mut trie =
+            TrieDBMutBuilder::<T>::from_existing(&mut file_data.memdb, &mut file_data.root).build();
+
+        // Check that the chunk is stored.
+        if !trie
+            .contains(&chunk_id.to_be_bytes())
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+        {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
+        // Remove the chunk from the file trie.
+        trie.remove(&chunk_id.to_be_bytes())
+            .map_err(|_| FileStorageError::FailedToDeleteFileChunk)?;
+
+        drop(trie);
+
+        // The chunk was checked to be stored, so it was an existing key in the trie.
+        file_data.stored_chunks -= 1;
+
+        // The chunk is no longer in the trie, so it can't be pruned either.
+        file_data.pruned_chunks.remove(chunk_id);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reference_trie::RefHasher;
+    use sp_trie::LayoutV1;
+
+    type TestLayout = LayoutV1<RefHasher>;
+
+    /// Chunks are kept small to keep the tests fast, but big enough to not be inlined in the trie nodes.
+    const CHUNK_SIZE: u64 = 64;
+
+    /// Build the chunks of a file, along with its metadata whose fingerprint is the root of the chunks trie.
+    fn build_file(chunk_count: u64) -> (Metadata, Vec<Chunk>) {
+        let chunks: Vec<Chunk> = (0..chunk_count)
+            .map(|chunk_id| vec![chunk_id as u8 + 1; CHUNK_SIZE as usize])
+            .collect();
+
+        let mut memdb = MemoryDB::<<TestLayout as TrieLayout>::Hash>::default();
+        let mut root: HashT<TestLayout> = Default::default();
+        {
+            let mut trie = TrieDBMutBuilder::<TestLayout>::new(&mut memdb, &mut root).build();
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                trie.insert(&(chunk_id as u64).to_be_bytes(), chunk)
+                    .unwrap();
+            }
+        }
+
+        let metadata = Metadata::new(
+            H256::from_slice(root.as_ref()),
+            chunk_count * CHUNK_SIZE,
+            CHUNK_SIZE,
+        )
+        .with_owner(String::from("owner"))
+        .with_location(String::from("location"));
+
+        (metadata, chunks)
+    }
+
+    /// Store a file keeping only the chunks in `chunk_range`.
+    fn store_partial_file(
+        file_storage: &mut InMemoryFileStorage<TestLayout>,
+        file_key: Key,
+        chunk_range: Range<ChunkId>,
+    ) -> Vec<Chunk> {
+        let (metadata, chunks) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+        file_storage
+            .set_chunk_range(&file_key, chunk_range)
+            .unwrap();
+
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            file_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        chunks
+    }
+
+    #[test]
+    fn test_prove_held_chunks_of_partial_file() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let chunks = store_partial_file(&mut file_storage, file_key, 1..3);
+        let fingerprint = file_storage.get_metadata(&file_key).unwrap().fingerprint;
+
+        for chunk_id in 1..3u64 {
+            let proof = file_storage.generate_proof(&file_key, &chunk_id).unwrap();
+            assert_eq!(proof.proven[0].key, chunk_id);
+            assert_eq!(proof.proven[0].data, chunks[chunk_id as usize]);
+            assert_eq!(proof.root, fingerprint);
+        }
+    }
+
+    #[test]
+    fn test_multi_chunk_proof_is_smaller_than_individual_proofs() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let chunks = store_partial_file(&mut file_storage, file_key, 0..4);
+        let chunk_ids = [0u64, 1, 2, 3];
+
+        let proof_size = |proof: &FileProof| {
+            proof
+                .proof
+                .encoded_nodes
+                .iter()
+                .map(|node| node.len())
+                .sum::<usize>()
+        };
+
+        let multi_proof = file_storage
+            .generate_proof_multi(&file_key, &chunk_ids)
+            .unwrap();
+        assert_eq!(multi_proof.proven.len(), chunk_ids.len());
+        for (leaf, chunk_id) in multi_proof.proven.iter().zip(chunk_ids) {
+            assert_eq!(leaf.key, chunk_id);
+            assert_eq!(leaf.data, chunks[chunk_id as usize]);
+        }
+
+        let individual_proofs_size: usize = chunk_ids
+            .iter()
+            .map(|chunk_id| proof_size(&file_storage.generate_proof(&file_key, chunk_id).unwrap()))
+            .sum();
+
+        assert!(proof_size(&multi_proof) < individual_proofs_size);
+    }
+
+    #[test]
+    fn test_prove_not_held_chunks_of_partial_file_fails() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        store_partial_file(&mut file_storage, file_key, 1..3);
+
+        for chunk_id in [0u64, 3] {
+            assert!(matches!(
+                file_storage.generate_proof(&file_key, &chunk_id),
+                Err(FileStorageError::FileChunkDoesNotExist)
+            ));
+            assert!(matches!(
+                file_storage.get_chunk(&file_key, &chunk_id),
+                Err(FileStorageError::FileChunkDoesNotExist)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_stored_files() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+        let other_file_key = H256::from_slice(&[2; 32]);
+
+        assert!(file_storage.stored_files().unwrap().is_empty());
+
+        store_partial_file(&mut file_storage, file_key, 0..4);
+        let (metadata, _) = build_file(4);
+        file_storage.set_metadata(other_file_key, metadata).unwrap();
+
+        let mut stored_files = file_storage.stored_files().unwrap();
+        stored_files.sort();
+        assert_eq!(stored_files, vec![file_key, other_file_key]);
+
+        file_storage.delete_file(&file_key).unwrap();
+        assert_eq!(file_storage.stored_files().unwrap(), vec![other_file_key]);
+    }
+
+    #[test]
+    fn test_stored_chunks_progress() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        assert!(matches!(
+            file_storage.stored_chunks_count(&file_key),
+            Err(FileStorageError::FileDoesNotExist)
+        ));
+        assert!(matches!(
+            file_storage.is_complete(&file_key),
+            Err(FileStorageError::FileDoesNotExist)
+        ));
+
+        let (metadata, chunks) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            assert_eq!(
+                file_storage.stored_chunks_count(&file_key).unwrap(),
+                chunk_id as u64
+            );
+            assert!(!file_storage.is_complete(&file_key).unwrap());
+
+            file_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 4);
+        assert!(file_storage.is_complete(&file_key).unwrap());
+    }
+
+    #[test]
+    fn test_stored_chunk_ids() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, chunks) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+        assert!(file_storage.stored_chunk_ids(&file_key).unwrap().is_empty());
+
+        // Chunk ids are returned in increasing order, regardless of the order they were written in.
+        for chunk_id in [3, 1] {
+            file_storage
+                .write_chunk(&file_key, &chunk_id, &chunks[chunk_id as usize])
+                .unwrap();
+        }
+        assert_eq!(
+            file_storage.stored_chunk_ids(&file_key).unwrap(),
+            vec![1, 3]
+        );
+
+        // Chunks outside of the retained range of a complete file are not included.
+        let other_file_key = H256::from_slice(&[2; 32]);
+        store_partial_file(&mut file_storage, other_file_key, 1..3);
+        assert_eq!(
+            file_storage.stored_chunk_ids(&other_file_key).unwrap(),
+            vec![1, 2]
+        );
+    }
+
+    #[test]
+    fn test_delete_chunk() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let chunks = store_partial_file(&mut file_storage, file_key, 0..4);
+        assert!(file_storage.is_complete(&file_key).unwrap());
+
+        file_storage.delete_chunk(&file_key, &2).unwrap();
+
+        assert!(!file_storage.is_complete(&file_key).unwrap());
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 3);
+        assert!(matches!(
+            file_storage.get_chunk(&file_key, &2),
+            Err(FileStorageError::FileChunkDoesNotExist)
+        ));
+        assert!(matches!(
+            file_storage.generate_proof(&file_key, &1),
+            Err(FileStorageError::IncompleteFile)
+        ));
+
+        // Deleting it again fails, since it is no longer stored.
+        assert!(matches!(
+            file_storage.delete_chunk(&file_key, &2),
+            Err(FileStorageError::FileChunkDoesNotExist)
+        ));
+
+        // Writing the chunk back restores the file.
+        assert!(matches!(
+            file_storage.write_chunk(&file_key, &2, &chunks[2]),
+            Ok(FileStorageWriteStatus::FileComplete)
+        ));
+        assert!(file_storage.generate_proof(&file_key, &1).is_ok());
+    }
+
+    #[test]
+    fn test_cached_stored_chunks_count_matches_trie() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, chunks) = build_file(6);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        let assert_count_matches_trie = |file_storage: &InMemoryFileStorage<TestLayout>| {
+            let file_data = file_storage.file_data.get(&file_key).unwrap();
+            assert_eq!(
+                file_data.stored_chunks_count().unwrap(),
+                file_data.count_stored_chunks().unwrap()
+            );
+        };
+
+        file_storage.write_chunk(&file_key, &0, &chunks[0]).unwrap();
+        file_storage
+            .write_chunk_batch(&file_key, &[(2, chunks[2].clone()), (4, chunks[4].clone())])
+            .unwrap();
+        assert_count_matches_trie(&file_storage);
+
+        // Rejected writes leave the count untouched.
+        assert!(file_storage.write_chunk(&file_key, &2, &chunks[2]).is_err());
+        assert!(file_storage
+            .write_chunk_batch(&file_key, &[(1, chunks[1].clone()), (4, chunks[4].clone())])
+            .is_err());
+        assert!(file_storage.delete_chunk(&file_key, &3).is_err());
+        assert_count_matches_trie(&file_storage);
+
+        file_storage.delete_chunk(&file_key, &2).unwrap();
+        file_storage.delete_chunk(&file_key, &0).unwrap();
+        assert_count_matches_trie(&file_storage);
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 1);
+
+        // Completing the file prunes the chunks outside of the range, which are still counted.
+        file_storage.set_chunk_range(&file_key, 0..3).unwrap();
+        for chunk_id in [0, 1, 2, 3, 5] {
+            file_storage
+                .write_chunk(&file_key, &chunk_id, &chunks[chunk_id as usize])
+                .unwrap();
+        }
+        assert!(file_storage.is_complete(&file_key).unwrap());
+        assert_count_matches_trie(&file_storage);
+
+        // Deleting and rewriting a chunk keeps the count in sync.
+        file_storage.delete_chunk(&file_key, &1).unwrap();
+        assert_count_matches_trie(&file_storage);
+        file_storage.write_chunk(&file_key, &1, &chunks[1]).unwrap();
+        assert_count_matches_trie(&file_storage);
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_write_chunk_with_expected_size() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, chunks) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        assert!(matches!(
+            file_storage.write_chunk(&file_key, &0, &chunks[0]),
+            Ok(FileStorageWriteStatus::FileIncomplete)
+        ));
+    }
+
+    #[test]
+    fn test_write_oversized_chunk_fails() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, _) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        for chunk_id in [0u64, 3] {
+            assert!(matches!(
+                file_storage.write_chunk(&file_key, &chunk_id, &vec![1; CHUNK_SIZE as usize + 1]),
+                Err(FileStorageError::InvalidChunkSize)
+            ));
+        }
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_write_short_final_chunk() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        // A file of 2 full chunks and a final chunk of half the chunk size.
+        let chunks: Vec<Chunk> = vec![
+            vec![1; CHUNK_SIZE as usize],
+            vec![2; CHUNK_SIZE as usize],
+            vec![3; CHUNK_SIZE as usize / 2],
+        ];
+
+        let mut memdb = MemoryDB::<<TestLayout as TrieLayout>::Hash>::default();
+        let mut root: HashT<TestLayout> = Default::default();
+        {
+            let mut trie = TrieDBMutBuilder::<TestLayout>::new(&mut memdb, &mut root).build();
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                trie.insert(&(chunk_id as u64).to_be_bytes(), chunk)
+                    .unwrap();
+            }
+        }
+
+        file_storage
+            .set_metadata(
+                file_key,
+                Metadata::new(
+                    H256::from_slice(root.as_ref()),
+                    2 * CHUNK_SIZE + CHUNK_SIZE / 2,
+                    CHUNK_SIZE,
+                )
+                .with_owner(String::from("owner"))
+                .with_location(String::from("location")),
+            )
+            .unwrap();
+
+        // Only the final chunk can be shorter than the chunk size.
+        assert!(matches!(
+            file_storage.write_chunk(&file_key, &1, &chunks[2]),
+            Err(FileStorageError::InvalidChunkSize)
+        ));
+
+        for (chunk_id, chunk) in chunks.iter().enumerate().take(2) {
+            file_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+        assert!(matches!(
+            file_storage.write_chunk(&file_key, &2, &chunks[2]),
+            Ok(FileStorageWriteStatus::FileComplete)
+        ));
+    }
+
+    #[test]
+    fn test_write_chunk_batch_matches_sequential_writes() {
+        let (metadata, chunks) = build_file(4);
+        let fingerprint = metadata.fingerprint;
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let mut sequential_storage = InMemoryFileStorage::<TestLayout>::new();
+        sequential_storage
+            .set_metadata(file_key, metadata.clone())
+            .unwrap();
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            sequential_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        // Write the first chunk alone, and the rest of the file in a single batch.
+        let mut batch_storage = InMemoryFileStorage::<TestLayout>::new();
+        batch_storage.set_metadata(file_key, metadata).unwrap();
+        assert!(matches!(
+            batch_storage.write_chunk_batch(&file_key, &[(0, chunks[0].clone())]),
+            Ok(FileStorageWriteStatus::FileIncomplete)
+        ));
+        let batch: Vec<(ChunkId, Chunk)> = chunks
+            .iter()
+            .cloned()
+            .enumerate()
+            .skip(1)
+            .map(|(chunk_id, chunk)| (chunk_id as u64, chunk))
+            .collect();
+        assert!(matches!(
+            batch_storage.write_chunk_batch(&file_key, &batch),
+            Ok(FileStorageWriteStatus::FileComplete)
+        ));
+
+        let sequential_root = sequential_storage.file_data[&file_key].get_root();
+        let batch_root = batch_storage.file_data[&file_key].get_root();
+        assert_eq!(batch_root, sequential_root);
+        assert_eq!(batch_root, fingerprint);
+        assert!(batch_storage.generate_proof(&file_key, &2).is_ok());
+    }
+
+    #[test]
+    fn test_write_chunk_batch_is_rejected_as_a_whole() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, chunks) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+        file_storage.write_chunk(&file_key, &1, &chunks[1]).unwrap();
+
+        // A chunk that is already stored rejects the whole batch.
+        assert!(matches!(
+            file_storage
+                .write_chunk_batch(&file_key, &[(0, chunks[0].clone()), (1, chunks[1].clone())]),
+            Err(FileStorageError::FileChunkAlreadyExists)
+        ));
+
+        // So does a chunk repeated in the batch.
+        assert!(matches!(
+            file_storage
+                .write_chunk_batch(&file_key, &[(2, chunks[2].clone()), (2, chunks[2].clone())]),
+            Err(FileStorageError::FileChunkAlreadyExists)
+        ));
+
+        // And a chunk of an invalid size.
+        assert!(matches!(
+            file_storage.write_chunk_batch(
+                &file_key,
+                &[
+                    (3, chunks[3].clone()),
+                    (0, vec![1; CHUNK_SIZE as usize + 1])
+                ]
+            ),
+            Err(FileStorageError::InvalidChunkSize)
+        ));
+
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_set_invalid_chunk_range_fails() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, _) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        assert!(matches!(
+            file_storage.set_chunk_range(&file_key, 2..2),
+            Err(FileStorageError::InvalidChunkRange)
+        ));
+        assert!(matches!(
+            file_storage.set_chunk_range(&file_key, 2..5),
+            Err(FileStorageError::InvalidChunkRange)
+        ));
+        assert_eq!(file_storage.get_chunk_range(&file_key).unwrap(), 0..4);
+    }
+
+    #[test]
+    fn test_configured_chunk_size_is_used_for_new_files() {
+        let file_key = H256::from_slice(&[1; 32]);
+        let size = 2 * 1024 * 1024 + 1;
+
+        let mut small_chunks =
+            InMemoryFileStorage::<TestLayout>::with_config(FileStorageConfig::new(1024).unwrap());
+        let mut big_chunks = InMemoryFileStorage::<TestLayout>::with_config(
+            FileStorageConfig::new(1024 * 1024).unwrap(),
+        );
+
+        let metadata = small_chunks.new_metadata(H256::zero(), size);
+        assert_eq!(metadata.chunk_count(), 2049);
+        small_chunks.set_metadata(file_key, metadata).unwrap();
+
+        let metadata = big_chunks.new_metadata(H256::zero(), size);
+        assert_eq!(metadata.chunk_count(), 3);
+        big_chunks.set_metadata(file_key, metadata).unwrap();
+
+        // Chunks of the other storage's chunk size are rejected.
+        assert!(matches!(
+            small_chunks.write_chunk(&file_key, &0, &vec![1; 1024 * 1024]),
+            Err(FileStorageError::InvalidChunkSize)
+        ));
+        assert!(matches!(
+            big_chunks.write_chunk(&file_key, &0, &vec![1; 1024]),
+            Err(FileStorageError::InvalidChunkSize)
+        ));
+    }
+
+    #[test]
+    fn test_zero_chunk_size_config_fails() {
+        assert!(matches!(
+            FileStorageConfig::new(0),
+            Err(FileStorageError::InvalidChunkSize)
+        ));
+    }
+
+    #[test]
+    fn test_write_out_of_range_chunk_id_fails() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, chunks) = build_file(4);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        assert!(matches!(
+            file_storage.write_chunk(&file_key, &4, &chunks[0]),
+            Err(FileStorageError::InvalidChunkId)
+        ));
+        assert!(matches!(
+            file_storage
+                .write_chunk_batch(&file_key, &[(0, chunks[0].clone()), (4, chunks[0].clone())]),
+            Err(FileStorageError::InvalidChunkId)
+        ));
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_chunks_iterates_over_complete_file() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        assert!(matches!(
+            file_storage.chunks(&file_key),
+            Err(FileStorageError::FileDoesNotExist)
+        ));
+
+        let (metadata, chunks) = build_file(4);
+        let chunk_count = metadata.chunk_count();
+        file_storage.set_metadata(file_key, metadata).unwrap();
+        assert_eq!(file_storage.chunks(&file_key).unwrap().count(), 0);
+
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            file_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        let stored_chunks: Vec<_> = file_storage.chunks(&file_key).unwrap().collect();
+        assert_eq!(stored_chunks.len() as u64, chunk_count);
+        for (chunk_id, (stored_chunk_id, chunk)) in stored_chunks.into_iter().enumerate() {
+            assert_eq!(stored_chunk_id, chunk_id as u64);
+            assert_eq!(chunk, chunks[chunk_id]);
+        }
+    }
+
+    #[test]
+    fn test_chunks_of_partial_file_are_within_range() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let chunks = store_partial_file(&mut file_storage, file_key, 1..3);
+
+        let stored_chunks: Vec<_> = file_storage.chunks(&file_key).unwrap().collect();
+        assert_eq!(
+            stored_chunks,
+            vec![(1, chunks[1].clone()), (2, chunks[2].clone())]
+        );
+    }
 }
@@ -1,2 +1,5 @@
+pub mod chunks;
 pub mod in_memory;
+pub mod rocksdb;
 pub mod traits;
+pub mod verify;
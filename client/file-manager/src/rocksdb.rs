@@ -0,0 +1,1123 @@
+use std::{io, marker::PhantomData, ops::Range, path::Path};
+
+use common::types::HashT;
+use hash_db::{AsHashDB, HashDB, HashDBRef, Prefix};
+use kvdb::DBTransaction;
+use kvdb_rocksdb::{Database, DatabaseConfig};
+use sp_core::H256;
+use sp_trie::{recorder::Recorder, DBValue, MemoryDB, Trie, TrieDBBuilder, TrieLayout, TrieMut};
+use storage_hub_infra::types::{Chunk, ChunkId, FileProof, Key, Leaf, Metadata};
+use trie_db::TrieDBMutBuilder;
+
+use crate::{
+    chunks::FileChunksIter,
+    traits::{FileStorage, FileStorageConfig, FileStorageError, FileStorageWriteStatus},
+};
+
+/// Column holding the [`Metadata`] of every file, keyed by file key.
+const METADATA_COLUMN: u32 = 0;
+/// Column holding the root of the chunks trie of every file, keyed by file key.
+const ROOTS_COLUMN: u32 = 1;
+/// Column holding the range of chunks retained of every file, keyed by file key.
+const CHUNK_RANGES_COLUMN: u32 = 2;
+/// Column holding the nodes of the chunks trie of every file, keyed by file key followed by the node hash.
+const TRIE_NODES_COLUMN: u32 = 3;
+/// Column holding the chunks whose data was dropped, keyed by file key followed by the chunk id.
+const PRUNED_CHUNKS_COLUMN: u32 = 4;
+/// Column holding the hash of the [`Metadata`] of every file, keyed by file key.
+const METADATA_HASHES_COLUMN: u32 = 5;
+/// Column holding the number of chunks stored of every file, keyed by file key.
+const STORED_CHUNKS_COLUMN: u32 = 6;
+/// Number of columns of the database.
+const NUM_COLUMNS: u32 = 7;
+
+/// View over the chunks trie of a file stored in the database.
+///
+/// Nodes are read from the database, while changes are kept in an overlay until they are committed
+/// to a database transaction. Nodes are reference counted, the same way a [`MemoryDB`] does.
+struct FileTrieDb<'a, T: TrieLayout> {
+    db: &'a Database,
+    file_key: Key,
+    overlay: MemoryDB<T::Hash>,
+}
+
+impl<'a, T: TrieLayout> FileTrieDb<'a, T> {
+    fn new(db: &'a Database, file_key: Key) -> Self {
+        Self {
+            db,
+            file_key,
+            overlay: MemoryDB::default(),
+        }
+    }
+
+    fn node_key(&self, hash: &HashT<T>) -> Vec<u8> {
+        [self.file_key.as_ref(), hash.as_ref()].concat()
+    }
+
+    /// Get a node and its reference count from the database, ignoring the overlay.
+    fn get_stored(&self, hash: &HashT<T>) -> Option<(DBValue, i32)> {
+        let raw = self
+            .db
+            .get(TRIE_NODES_COLUMN, &self.node_key(hash))
+            .ok()??;
+        decode_node(raw)
+    }
+
+    fn get_node(&self, hash: &HashT<T>, prefix: Prefix) -> Option<DBValue> {
+        let (stored_value, stored_rc) = match self.get_stored(hash) {
+            Some((value, rc)) => (Some(value), rc),
+            None => (None, 0),
+        };
+
+        match self.overlay.raw(hash, prefix) {
+            Some((value, rc)) if stored_rc + rc > 0 => {
+                if rc > 0 {
+                    Some(value.clone())
+                } else {
+                    stored_value
+                }
+            }
+            Some(_) => None,
+            None => stored_value,
+        }
+    }
+
+    /// Count the chunks stored in the trie with the given root by iterating over all of its keys.
+    fn count_stored_chunks(&self, root: &HashT<T>) -> Result<u64, FileStorageError> {
+        let trie = TrieDBBuilder::<T>::new(self, root).build();
+        let stored_chunks = trie
+            .key_iter()
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+            .count() as u64;
+        Ok(stored_chunks)
+    }
+
+    /// Get the ids of the chunks stored in the trie with the given root, in increasing order.
+    fn stored_chunk_ids(&self, root: &HashT<T>) -> Result<Vec<ChunkId>, FileStorageError> {
+        let trie = TrieDBBuilder::<T>::new(self, root).build();
+
+        let mut chunk_ids = Vec::new();
+        for key in trie
+            .key_iter()
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+        {
+            let key = key.map_err(|_| FileStorageError::FailedToGetFileChunk)?;
+            chunk_ids.push(ChunkId::from_be_bytes(
+                key.as_slice()
+                    .try_into()
+                    .map_err(|_| FileStorageError::FailedToDecodeValue)?,
+            ));
+        }
+
+        Ok(chunk_ids)
+    }
+
+    /// Apply the changes in the overlay to the database transaction.
+    fn commit(mut self, transaction: &mut DBTransaction) {
+        for (hash, (value, rc)) in self.overlay.drain() {
+            if rc == 0 {
+                continue;
+            }
+
+            let (stored_value, stored_rc) = match self.get_stored(&hash) {
+                Some((value, rc)) => (Some(value), rc),
+                None => (None, 0),
+            };
+
+            let key = self.node_key(&hash);
+            let new_rc = stored_rc + rc;
+            if new_rc > 0 {
+                let value = if rc > 0 {
+                    value
+                } else {
+                    stored_value.unwrap_or_default()
+                };
+                transaction.put_vec(TRIE_NODES_COLUMN, &key, encode_node(&value, new_rc));
+            } else {
+                transaction.delete(TRIE_NODES_COLUMN, &key);
+            }
+        }
+    }
+}
+
+impl<'a, T: TrieLayout> HashDB<T::Hash, DBValue> for FileTrieDb<'a, T> {
+    fn get(&self, key: &HashT<T>, prefix: Prefix) -> Option<DBValue> {
+        self.get_node(key, prefix)
+    }
+
+    fn contains(&self, key: &HashT<T>, prefix: Prefix) -> bool {
+        self.get_node(key, prefix).is_some()
+    }
+
+    fn insert(&mut self, prefix: Prefix, value: &[u8]) -> HashT<T> {
+        self.overlay.insert(prefix, value)
+    }
+
+    fn emplace(&mut self, key: HashT<T>, prefix: Prefix, value: DBValue) {
+        self.overlay.emplace(key, prefix, value)
+    }
+
+    fn remove(&mut self, key: &HashT<T>, prefix: Prefix) {
+        self.overlay.remove(key, prefix)
+    }
+}
+
+impl<'a, T: TrieLayout> HashDBRef<T::Hash, DBValue> for FileTrieDb<'a, T> {
+    fn get(&self, key: &HashT<T>, prefix: Prefix) -> Option<DBValue> {
+        self.get_node(key, prefix)
+    }
+
+    fn contains(&self, key: &HashT<T>, prefix: Prefix) -> bool {
+        self.get_node(key, prefix).is_some()
+    }
+}
+
+impl<'a, T: TrieLayout> AsHashDB<T::Hash, DBValue> for FileTrieDb<'a, T> {
+    fn as_hash_db(&self) -> &dyn HashDB<T::Hash, DBValue> {
+        self
+    }
+
+    fn as_hash_db_mut<'b>(&'b mut self) -> &'b mut (dyn HashDB<T::Hash, DBValue> + 'b) {
+        self
+    }
+}
+
+/// Encode a trie node along with its reference count.
+fn encode_node(value: &[u8], rc: i32) -> Vec<u8> {
+    [&rc.to_le_bytes()[..], value].concat()
+}
+
+/// Decode a trie node along with its reference count.
+fn decode_node(raw: DBValue) -> Option<(DBValue, i32)> {
+    let rc = i32::from_le_bytes(raw.get(..4)?.try_into().ok()?);
+    Some((raw[4..].to_vec(), rc))
+}
+
+fn encode_chunk_range(range: &Range<ChunkId>) -> Vec<u8> {
+    [range.start.to_be_bytes(), range.end.to_be_bytes()].concat()
+}
+
+fn decode_chunk_range(raw: &[u8]) -> Option<Range<ChunkId>> {
+    let start = ChunkId::from_be_bytes(raw.get(..8)?.try_into().ok()?);
+    let end = ChunkId::from_be_bytes(raw.get(8..16)?.try_into().ok()?);
+    Some(start..end)
+}
+
+fn to_h256<T: TrieLayout>(root: &HashT<T>) -> H256 {
+    H256::from_slice(
+        root.as_ref()
+            .try_into()
+            .expect("trie hash should be 32 bytes"),
+    )
+}
+
+/// File storage persisting the chunks trie and the metadata of every file in a RocksDB database.
+pub struct RocksDbFileStorage<T: TrieLayout + 'static> {
+    config: FileStorageConfig,
+    db: Database,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: TrieLayout> RocksDbFileStorage<T> {
+    /// Open the file storage database at `path` with the default [`FileStorageConfig`], creating it if it
+    /// does not exist.
+    pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::with_config(path, FileStorageConfig::default())
+    }
+
+    /// Open the file storage database at `path` with the given configuration, creating it if it does not exist.
+    ///
+    /// Files already stored keep the chunk size in their metadata.
+    pub fn with_config(path: impl AsRef<Path>, config: FileStorageConfig) -> io::Result<Self> {
+        let db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+        let db = Database::open(&db_config, path.as_ref())?;
+
+        Ok(Self {
+            config,
+            db,
+            _phantom: Default::default(),
+        })
+    }
+
+    fn read(&self, column: u32, key: &[u8]) -> Result<Option<DBValue>, FileStorageError> {
+        self.db
+            .get(column, key)
+            .map_err(|_| FileStorageError::FailedToReadStorage)
+    }
+
+    fn write(&self, transaction: DBTransaction) -> Result<(), FileStorageError> {
+        self.db
+            .write(transaction)
+            .map_err(|_| FileStorageError::FailedToWriteToStorage)
+    }
+
+    fn read_metadata(&self, file_key: &Key) -> Result<Option<Metadata>, FileStorageError> {
+        self.read(METADATA_COLUMN, file_key.as_ref())?
+            .map(|raw| {
+                bincode::deserialize(&raw).map_err(|_| FileStorageError::FailedToDecodeValue)
+            })
+            .transpose()
+    }
+
+    /// Get the root of the chunks trie of a file, if any chunk has been written.
+    fn read_root(&self, file_key: &Key) -> Result<Option<HashT<T>>, FileStorageError> {
+        self.read(ROOTS_COLUMN, file_key.as_ref())?
+            .map(|raw| {
+                let mut root = HashT::<T>::default();
+                if root.as_ref().len() != raw.len() {
+                    return Err(FileStorageError::FailedToDecodeValue);
+                }
+                root.as_mut().copy_from_slice(&raw);
+                Ok(root)
+            })
+            .transpose()
+    }
+
+    /// Get the number of chunks stored of a file.
+    ///
+    /// The count is kept up to date as chunks are written and deleted. Files stored before it was kept
+    /// don't have one, so their chunks trie is counted instead.
+    fn read_stored_chunks_count(&self, file_key: &Key) -> Result<u64, FileStorageError> {
+        if let Some(raw) = self.read(STORED_CHUNKS_COLUMN, file_key.as_ref())? {
+            return raw
+                .as_slice()
+                .try_into()
+                .map(u64::from_be_bytes)
+                .map_err(|_| FileStorageError::FailedToDecodeValue);
+        }
+
+        match self.read_root(file_key)? {
+            Some(root) => FileTrieDb::<T>::new(&self.db, *file_key).count_stored_chunks(&root),
+            None => Ok(0),
+        }
+    }
+
+    fn read_chunk_range(&self, file_key: &Key) -> Result<Range<ChunkId>, FileStorageError> {
+        let raw = self
+            .read(CHUNK_RANGES_COLUMN, file_key.as_ref())?
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+        decode_chunk_range(&raw).ok_or(FileStorageError::FailedToDecodeValue)
+    }
+
+    /// Drop the data of the stored chunks outside of `chunk_range`.
+    ///
+    /// See [`crate::in_memory::FileData`] for the details of what is dropped.
+    fn prune_chunks_outside_of_range(
+        &self,
+        file_key: &Key,
+        file_trie: &mut FileTrieDb<T>,
+        root: &HashT<T>,
+        chunk_range: &Range<ChunkId>,
+        chunk_count: u64,
+        transaction: &mut DBTransaction,
+    ) -> Result<(), FileStorageError> {
+        let trie = TrieDBBuilder::<T>::new(&*file_trie, root).build();
+
+        let mut values_to_prune = Vec::new();
+        for chunk_id in (0..chunk_count).filter(|chunk_id| !chunk_range.contains(chunk_id)) {
+            let pruned_chunk_key = [file_key.as_ref(), &chunk_id.to_be_bytes()].concat();
+            if self
+                .read(PRUNED_CHUNKS_COLUMN, &pruned_chunk_key)?
+                .is_some()
+            {
+                continue;
+            }
+
+            let value_hash = trie
+                .get_hash(&chunk_id.to_be_bytes())
+                .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+                .ok_or(FileStorageError::FileChunkDoesNotExist)?;
+
+            values_to_prune.push((pruned_chunk_key, value_hash));
+        }
+
+        drop(trie);
+
+        for (pruned_chunk_key, value_hash) in values_to_prune {
+            // Values inlined in their trie node are not stored apart, so there is nothing to drop.
+            if HashDB::contains(file_trie, &value_hash, hash_db::EMPTY_PREFIX) {
+                HashDB::remove(file_trie, &value_hash, hash_db::EMPTY_PREFIX);
+            }
+            transaction.put(PRUNED_CHUNKS_COLUMN, &pruned_chunk_key, &[]);
+        }
+
+        Ok(())
+    }
+
+    fn delete_file_in(&self, file_key: &Key, transaction: &mut DBTransaction) {
+        transaction.delete(METADATA_COLUMN, file_key.as_ref());
+        transaction.delete(METADATA_HASHES_COLUMN, file_key.as_ref());
+        transaction.delete(ROOTS_COLUMN, file_key.as_ref());
+        transaction.delete(CHUNK_RANGES_COLUMN, file_key.as_ref());
+        transaction.delete_prefix(TRIE_NODES_COLUMN, file_key.as_ref());
+        transaction.delete_prefix(PRUNED_CHUNKS_COLUMN, file_key.as_ref());
+        transaction.delete(STORED_CHUNKS_COLUMN, file_key.as_ref());
+    }
+}
+
+impl<T: TrieLayout + 'static> FileStorage for RocksDbFileStorage<T> {
+    fn config(&self) -> &FileStorageConfig {
+        &self.config
+    }
+
+    fn generate_proof_multi(
+        &self,
+        file_key: &Key,
+        chunk_ids: &[ChunkId],
+    ) -> Result<FileProof, FileStorageError> {
+        let metadata = self
+            .read_metadata(file_key)?
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        let root = self
+            .read_root(file_key)?
+            .ok_or(FileStorageError::IncompleteFile)?;
+
+        if metadata.chunk_count() != self.read_stored_chunks_count(file_key)? {
+            return Err(FileStorageError::IncompleteFile);
+        }
+
+        if to_h256::<T>(&root) != metadata.fingerprint {
+            return Err(FileStorageError::FingerprintAndStoredFileMismatch);
+        }
+
+        // Chunks outside of the retained range are not held by this provider.
+        let chunk_range = self.read_chunk_range(file_key)?;
+        if !chunk_ids
+            .iter()
+            .all(|chunk_id| chunk_range.contains(chunk_id))
+        {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
+        let file_trie = FileTrieDb::<T>::new(&self.db, *file_key);
+        let recorder: Recorder<T::Hash> = Recorder::default();
+
+        // A `TrieRecorder` is needed to create a proof of the "visited" leafs, by the end of this process.
+        let mut trie_recorder = recorder.as_trie_recorder(root);
+
+        // The trie is loaded from the database.
+        let trie = TrieDBBuilder::<T>::new(&file_trie, &root)
+            .with_recorder(&mut trie_recorder)
+            .build();
+
+        // All the chunks are recorded against the same recorder, so their common trie nodes are only proven once.
+        let mut proven = Vec::with_capacity(chunk_ids.len());
+        for chunk_id in chunk_ids {
+            let chunk: Option<Vec<u8>> = trie
+                .get(&chunk_id.to_be_bytes())
+                .map_err(|_| FileStorageError::FailedToGetFileChunk)?;
+
+            let chunk = chunk.ok_or(FileStorageError::FileChunkDoesNotExist)?;
+
+            proven.push(Leaf {
+                key: *chunk_id,
+                data: chunk,
+            });
+        }
+
+        // Drop the `trie_recorder` to release the `recorder`
+        drop(trie_recorder);
+
+        // Generate proof
+        let proof = recorder
+            .drain_storage_proof()
+            .to_compact_proof::<T::Hash>(root)
+            .map_err(|_| FileStorageError::FailedToGenerateCompactProof)?;
+
+        Ok(FileProof {
+            proven,
+            proof,
+            root: to_h256::<T>(&root),
+        })
+    }
+
+    fn delete_file(&mut self, file_key: &Key) -> Result<(), FileStorageError> {
+        let mut transaction = DBTransaction::new();
+        self.delete_file_in(file_key, &mut transaction);
+        self.write(transaction)
+    }
+
+    fn stored_files(&self) -> Result<Vec<Key>, FileStorageError> {
+        let mut file_keys = Vec::new();
+        for entry in self.db.iter(METADATA_COLUMN) {
+            let (key, _) = entry.map_err(|_| FileStorageError::FailedToReadStorage)?;
+            if key.len() != Key::len_bytes() {
+                return Err(FileStorageError::FailedToDecodeValue);
+            }
+            file_keys.push(Key::from_slice(&key));
+        }
+        Ok(file_keys)
+    }
+
+    fn stored_chunks_count(&self, file_key: &Key) -> Result<u64, FileStorageError> {
+        if self.read_metadata(file_key)?.is_none() {
+            return Err(FileStorageError::FileDoesNotExist);
+        }
+
+        self.read_stored_chunks_count(file_key)
+    }
+
+    fn stored_chunk_ids(&self, file_key: &Key) -> Result<Vec<ChunkId>, FileStorageError> {
+        // Fails if the file does not exist.
+        let chunk_range = self.read_chunk_range(file_key)?;
+
+        let Some(root) = self.read_root(file_key)? else {
+            return Ok(Vec::new());
+        };
+
+        // Chunks outside of the retained range are not held by this provider.
+        let mut chunk_ids = FileTrieDb::<T>::new(&self.db, *file_key).stored_chunk_ids(&root)?;
+        chunk_ids.retain(|chunk_id| chunk_range.contains(chunk_id));
+        Ok(chunk_ids)
+    }
+
+    fn get_metadata(&self, file_key: &Key) -> Result<Metadata, FileStorageError> {
+        self.read_metadata(file_key)?
+            .ok_or(FileStorageError::FileDoesNotExist)
+    }
+
+    fn set_metadata(&mut self, file_key: Key, metadata: Metadata) -> Result<(), FileStorageError> {
+        let mut transaction = DBTransaction::new();
+
+        // Any previously stored chunk of the file is deleted.
+        self.delete_file_in(&file_key, &mut transaction);
+
+        let raw_metadata =
+            bincode::serialize(&metadata).map_err(|_| FileStorageError::FailedToEncodeValue)?;
+        transaction.put_vec(METADATA_COLUMN, file_key.as_ref(), raw_metadata);
+        transaction.put(
+            METADATA_HASHES_COLUMN,
+            file_key.as_ref(),
+            metadata.hash().as_bytes(),
+        );
+        transaction.put_vec(
+            CHUNK_RANGES_COLUMN,
+            file_key.as_ref(),
+            encode_chunk_range(&(0..metadata.chunk_count())),
+        );
+        transaction.put(STORED_CHUNKS_COLUMN, file_key.as_ref(), &0u64.to_be_bytes());
+
+        self.write(transaction)
+    }
+
+    fn metadata_hash(&self, file_key: &Key) -> Result<H256, FileStorageError> {
+        let raw = self
+            .read(METADATA_HASHES_COLUMN, file_key.as_ref())?
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+        if raw.len() != H256::len_bytes() {
+            return Err(FileStorageError::FailedToDecodeValue);
+        }
+        Ok(H256::from_slice(&raw))
+    }
+
+    fn set_chunk_range(
+        &mut self,
+        file_key: &Key,
+        range: Range<ChunkId>,
+    ) -> Result<(), FileStorageError> {
+        let metadata = self
+            .read_metadata(file_key)?
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        if range.is_empty() || range.end > metadata.chunk_count() {
+            return Err(FileStorageError::InvalidChunkRange);
+        }
+
+        let mut transaction = DBTransaction::new();
+        transaction.put_vec(
+            CHUNK_RANGES_COLUMN,
+            file_key.as_ref(),
+            encode_chunk_range(&range),
+        );
+
+        // If the file is already complete, drop the chunks that are no longer retained.
+        if let Some(root) = self.read_root(file_key)? {
+            let mut file_trie = FileTrieDb::<T>::new(&self.db, *file_key);
+            let chunk_count = metadata.chunk_count();
+            if chunk_count == self.read_stored_chunks_count(file_key)? {
+                self.prune_chunks_outside_of_range(
+                    file_key,
+                    &mut file_trie,
+                    &root,
+                    &range,
+                    chunk_count,
+                    &mut transaction,
+                )?;
+            }
+            file_trie.commit(&mut transaction);
+        }
+
+        self.write(transaction)
+    }
+
+    fn get_chunk_range(&self, file_key: &Key) -> Result<Range<ChunkId>, FileStorageError> {
+        self.read_chunk_range(file_key)
+    }
+
+    fn get_chunk(&self, file_key: &Key, chunk_id: &ChunkId) -> Result<Chunk, FileStorageError> {
+        // Chunks outside of the retained range are not held by this provider.
+        let chunk_range = self.read_chunk_range(file_key)?;
+        if !chunk_range.contains(chunk_id) {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
+        let root = self
+            .read_root(file_key)?
+            .ok_or(FileStorageError::FileChunkDoesNotExist)?;
+
+        let file_trie = FileTrieDb::<T>::new(&self.db, *file_key);
+        let trie = TrieDBBuilder::<T>::new(&file_trie, &root).build();
+
+        Ok(trie
+            .get(&chunk_id.to_be_bytes())
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+            .ok_or(FileStorageError::FileChunkDoesNotExist)?)
+    }
+
+    fn chunks(
+        &self,
+        file_key: &Key,
+    ) -> Result<impl Iterator<Item = (ChunkId, Chunk)> + '_, FileStorageError> {
+        // The chunk range is stored along with the metadata, so it is missing if the file does not exist.
+        let chunk_range = self.read_chunk_range(file_key)?;
+        let root = self.read_root(file_key)?;
+
+        let file_trie = Box::new(FileTrieDb::<T>::new(&self.db, *file_key));
+
+        Ok(FileChunksIter::<T, _>::new(file_trie, root, chunk_range))
+    }
+
+    fn write_chunk(
+        &mut self,
+        file_key: &Key,
+        chunk_id: &ChunkId,
+        data: &Chunk,
+    ) -> Result<FileStorageWriteStatus, FileStorageError> {
+        let metadata = self
+            .read_metadata(file_key)?
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        // Check that the chunk is part of the file, with the size expected for its position.
+        if *chunk_id >= metadata.chunk_count() {
+            return Err(FileStorageError::InvalidChunkId);
+        }
+        if data.len() as u64 != metadata.expected_chunk_size(*chunk_id) {
+            return Err(FileStorageError::InvalidChunkSize);
+        }
+
+        let stored_chunks = self.read_stored_chunks_count(file_key)?;
+        let mut file_trie = FileTrieDb::<T>::new(&self.db, *file_key);
+        let stored_root = self.read_root(file_key)?;
+        let mut root = stored_root.unwrap_or_default();
+
+        let mut trie = match stored_root {
+            Some(_) => TrieDBMutBuilder::<T>::from_existing(&mut file_trie, &mut root).build(),
+            None => TrieDBMutBuilder::<T>::new(&mut file_trie, &mut root).build(),
+        };
+
+        // Check that we don't have a chunk already stored.
+        if trie
+            .contains(&chunk_id.to_be_bytes())
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+        {
+            return Err(FileStorageError::FileChunkAlreadyExists);
+        }
+
+        // Insert the chunk into the file trie.
+        trie.insert(&chunk_id.to_be_bytes(), &data)
+            .map_err(|_| FileStorageError::FailedToInsertFileChunk)?;
+
+        drop(trie);
+
+        let stored_chunks = stored_chunks + 1;
+        let chunk_count = metadata.chunk_count();
+        let is_complete = chunk_count == stored_chunks;
+        let fingerprint_matches = to_h256::<T>(&root) == metadata.fingerprint;
+
+        let mut transaction = DBTransaction::new();
+
+        // Now that the file is verified, drop the chunks that are not retained.
+        if is_complete && fingerprint_matches {
+            let chunk_range = self.read_chunk_range(file_key)?;
+            self.prune_chunks_outside_of_range(
+                file_key,
+                &mut file_trie,
+                &root,
+                &chunk_range,
+                chunk_count,
+                &mut transaction,
+            )?;
+        }
+
+        file_trie.commit(&mut transaction);
+        transaction.put(ROOTS_COLUMN, file_key.as_ref(), root.as_ref());
+        transaction.put(
+            STORED_CHUNKS_COLUMN,
+            file_key.as_ref(),
+            &stored_chunks.to_be_bytes(),
+        );
+        self.write(transaction)?;
+
+        // Check if we have all the chunks for the file.
+        if !is_complete {
+            return Ok(FileStorageWriteStatus::FileIncomplete);
+        }
+
+        // If we have all the chunks, check if the file metadata fingerprint and the file trie
+        // root matches.
+        if !fingerprint_matches {
+            return Err(FileStorageError::FingerprintAndStoredFileMismatch);
+        }
+
+        Ok(FileStorageWriteStatus::FileComplete)
+    }
+
+    fn write_chunk_batch(
+        &mut self,
+        file_key: &Key,
+        chunks: &[(ChunkId, Chunk)],
+    ) -> Result<FileStorageWriteStatus, FileStorageError> {
+        let metadata = self
+            .read_metadata(file_key)?
+            .ok_or(FileStorageError::FileDoesNotExist)?;
+
+        // Check that every chunk is part of the file, with the size expected for its position.
+        if chunks
+            .iter()
+            .any(|(chunk_id, _)| *chunk_id >= metadata.chunk_count())
+        {
+            return Err(FileStorageError::InvalidChunkId);
+        }
+        if chunks
+            .iter()
+            .any(|(chunk_id, data)| data.len() as u64 != metadata.expected_chunk_size(*chunk_id))
+        {
+            return Err(FileStorageError::InvalidChunkSize);
+        }
+
+        let stored_chunks = self.read_stored_chunks_count(file_key)?;
+        let mut file_trie = FileTrieDb::<T>::new(&self.db, *file_key);
+        let stored_root = self.read_root(file_key)?;
+        let mut root = stored_root.unwrap_or_default();
+
+        let mut trie = match stored_root {
+            Some(_) => TrieDBMutBuilder::<T>::from_existing(&mut file_trie, &mut root).build(),
+            None => TrieDBMutBuilder::<T>::new(&mut file_trie, &mut root).build(),
+        };
+
+        // Changes are kept in the overlay until committed, so returning early discards the whole batch.
+        for (chunk_id, data) in chunks {
+            // Check that we don't have the chunk already stored, nor earlier in the batch.
+            if trie
+                .contains(&chunk_id.to_be_bytes())
+                .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+            {
+                return Err(FileStorageError::FileChunkAlreadyExists);
+            }
+
+            trie.insert(&chunk_id.to_be_bytes(), data)
+                .map_err(|_| FileStorageError::FailedToInsertFileChunk)?;
+        }
+
+        drop(trie);
+
+        let stored_chunks = stored_chunks + chunks.len() as u64;
+        let chunk_count = metadata.chunk_count();
+        let is_complete = chunk_count == stored_chunks;
+        let fingerprint_matches = to_h256::<T>(&root) == metadata.fingerprint;
+
+        let mut transaction = DBTransaction::new();
+
+        // Now that the file is verified, drop the chunks that are not retained.
+        if is_complete && fingerprint_matches {
+            let chunk_range = self.read_chunk_range(file_key)?;
+            self.prune_chunks_outside_of_range(
+                file_key,
+                &mut file_trie,
+                &root,
+                &chunk_range,
+                chunk_count,
+                &mut transaction,
+            )?;
+        }
+
+        file_trie.commit(&mut transaction);
+        transaction.put(ROOTS_COLUMN, file_key.as_ref(), root.as_ref());
+        transaction.put(
+            STORED_CHUNKS_COLUMN,
+            file_key.as_ref(),
+            &stored_chunks.to_be_bytes(),
+        );
+        self.write(transaction)?;
+
+        // Check if we have all the chunks for the file.
+        if !is_complete {
+            return Ok(FileStorageWriteStatus::FileIncomplete);
+        }
+
+        // If we have all the chunks, check if the file metadata fingerprint and the file trie
+        // root matches.
+        if !fingerprint_matches {
+            return Err(FileStorageError::FingerprintAndStoredFileMismatch);
+        }
+
+        Ok(FileStorageWriteStatus::FileComplete)
+    }
+
+    fn delete_chunk(&mut self, file_key: &Key, chunk_id: &ChunkId) -> Result<(), FileStorageError> {
+        if self.read_metadata(file_key)?.is_none() {
+            return Err(FileStorageError::FileDoesNotExist);
+        }
+
+        let stored_chunks = self.read_stored_chunks_count(file_key)?;
+
+        let mut root = self
+            .read_root(file_key)?
+            .ok_or(FileStorageError::FileChunkDoesNotExist)?;
+
+        let mut file_trie = FileTrieDb::<T>::new(&self.db, *file_key);
+        let mut trie = TrieDBMutBuilder::<T>::from_existing(&mut file_trie, &mut root).build();
+
+        // Check that the chunk is stored.
+        if !trie
+            .contains(&chunk_id.to_be_bytes())
+            .map_err(|_| FileStorageError::FailedToGetFileChunk)?
+        {
+            return Err(FileStorageError::FileChunkDoesNotExist);
+        }
+
+        // Remove the chunk from the file trie.
+        trie.remove(&chunk_id.to_be_bytes())
+            .map_err(|_| FileStorageError::FailedToDeleteFileChunk)?;
+
+        drop(trie);
+
+        let mut transaction = DBTransaction::new();
+        file_trie.commit(&mut transaction);
+        transaction.put(ROOTS_COLUMN, file_key.as_ref(), root.as_ref());
+        transaction.put(
+            STORED_CHUNKS_COLUMN,
+            file_key.as_ref(),
+            &stored_chunks.saturating_sub(1).to_be_bytes(),
+        );
+        // The chunk is no longer in the trie, so it can't be pruned either.
+        transaction.delete(
+            PRUNED_CHUNKS_COLUMN,
+            &[file_key.as_ref(), &chunk_id.to_be_bytes()].concat(),
+        );
+        self.write(transaction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reference_trie::RefHasher;
+    use sp_trie::LayoutV1;
+
+    type TestLayout = LayoutV1<RefHasher>;
+
+    /// Chunks are kept small to keep the tests fast, but big enough to not be inlined in the trie nodes.
+    const CHUNK_SIZE: u64 = 64;
+
+    /// Build the chunks of a file, along with its metadata whose fingerprint is the root of the chunks trie.
+    fn build_file(chunk_count: u64) -> (Metadata, Vec<Chunk>) {
+        let chunks: Vec<Chunk> = (0..chunk_count)
+            .map(|chunk_id| vec![chunk_id as u8 + 1; CHUNK_SIZE as usize])
+            .collect();
+
+        let mut memdb = MemoryDB::<<TestLayout as TrieLayout>::Hash>::default();
+        let mut root: HashT<TestLayout> = Default::default();
+        {
+            let mut trie = TrieDBMutBuilder::<TestLayout>::new(&mut memdb, &mut root).build();
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                trie.insert(&(chunk_id as u64).to_be_bytes(), chunk)
+                    .unwrap();
+            }
+        }
+
+        let metadata = Metadata::new(
+            H256::from_slice(root.as_ref()),
+            chunk_count * CHUNK_SIZE,
+            CHUNK_SIZE,
+        )
+        .with_owner(String::from("owner"))
+        .with_location(String::from("location"));
+
+        (metadata, chunks)
+    }
+
+    #[test]
+    fn test_reopen_and_prove_stored_file() {
+        let path = tempfile::tempdir().unwrap();
+        let file_key = H256::from_slice(&[1; 32]);
+        let (metadata, chunks) = build_file(4);
+
+        {
+            let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+            file_storage
+                .set_metadata(file_key, metadata.clone())
+                .unwrap();
+
+            for (chunk_id, chunk) in chunks.iter().enumerate().take(3) {
+                assert!(matches!(
+                    file_storage.write_chunk(&file_key, &(chunk_id as u64), chunk),
+                    Ok(FileStorageWriteStatus::FileIncomplete)
+                ));
+            }
+            assert!(matches!(
+                file_storage.write_chunk(&file_key, &3, &chunks[3]),
+                Ok(FileStorageWriteStatus::FileComplete)
+            ));
+        }
+
+        // Reopen the file storage from disk.
+        let file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+        assert_eq!(file_storage.stored_files().unwrap(), vec![file_key]);
+        assert_eq!(
+            file_storage.get_metadata(&file_key).unwrap().fingerprint,
+            metadata.fingerprint
+        );
+
+        for chunk_id in 0..4u64 {
+            let proof = file_storage.generate_proof(&file_key, &chunk_id).unwrap();
+            assert_eq!(proof.proven[0].key, chunk_id);
+            assert_eq!(proof.proven[0].data, chunks[chunk_id as usize]);
+            assert_eq!(proof.root, metadata.fingerprint);
+
+            // The compact proof is enough to read the chunk from the trie with the file fingerprint as root.
+            let (memdb, root) = proof
+                .proof
+                .to_memory_db::<RefHasher>(Some(&metadata.fingerprint.to_fixed_bytes()))
+                .unwrap();
+            let trie = TrieDBBuilder::<TestLayout>::new(&memdb, &root).build();
+            assert_eq!(
+                trie.get(&chunk_id.to_be_bytes()).unwrap(),
+                Some(chunks[chunk_id as usize].clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_reopen_keeps_metadata_hash() {
+        let path = tempfile::tempdir().unwrap();
+        let file_key = H256::from_slice(&[1; 32]);
+        let (metadata, _) = build_file(4);
+
+        {
+            let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+            file_storage
+                .set_metadata(file_key, metadata.clone())
+                .unwrap();
+            assert_eq!(
+                file_storage.metadata_hash(&file_key).unwrap(),
+                metadata.hash()
+            );
+        }
+
+        // Reopen the file storage from disk, and re-hash the metadata read from it.
+        let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+        let metadata_hash = file_storage.metadata_hash(&file_key).unwrap();
+        assert_eq!(metadata_hash, metadata.hash());
+        assert_eq!(
+            file_storage.get_metadata(&file_key).unwrap().hash(),
+            metadata_hash
+        );
+
+        file_storage.delete_file(&file_key).unwrap();
+        assert!(matches!(
+            file_storage.metadata_hash(&file_key),
+            Err(FileStorageError::FileDoesNotExist)
+        ));
+    }
+
+    #[test]
+    fn test_reopen_keeps_chunk_range_of_partial_file() {
+        let path = tempfile::tempdir().unwrap();
+        let file_key = H256::from_slice(&[1; 32]);
+        let (metadata, chunks) = build_file(4);
+
+        {
+            let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+            file_storage.set_metadata(file_key, metadata).unwrap();
+            file_storage.set_chunk_range(&file_key, 1..3).unwrap();
+
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                file_storage
+                    .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                    .unwrap();
+            }
+        }
+
+        // Reopen the file storage from disk.
+        let file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+        assert_eq!(file_storage.get_chunk_range(&file_key).unwrap(), 1..3);
+
+        for chunk_id in 1..3u64 {
+            assert_eq!(
+                file_storage.get_chunk(&file_key, &chunk_id).unwrap(),
+                chunks[chunk_id as usize]
+            );
+        }
+        for chunk_id in [0u64, 3] {
+            assert!(matches!(
+                file_storage.generate_proof(&file_key, &chunk_id),
+                Err(FileStorageError::FileChunkDoesNotExist)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_write_chunk_batch_matches_sequential_writes() {
+        let file_key = H256::from_slice(&[1; 32]);
+        let (metadata, chunks) = build_file(4);
+
+        let sequential_path = tempfile::tempdir().unwrap();
+        let mut sequential_storage =
+            RocksDbFileStorage::<TestLayout>::new(sequential_path.path()).unwrap();
+        sequential_storage
+            .set_metadata(file_key, metadata.clone())
+            .unwrap();
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            sequential_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        let batch_path = tempfile::tempdir().unwrap();
+        let mut batch_storage = RocksDbFileStorage::<TestLayout>::new(batch_path.path()).unwrap();
+        batch_storage
+            .set_metadata(file_key, metadata.clone())
+            .unwrap();
+        let batch: Vec<(ChunkId, Chunk)> = chunks
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(chunk_id, chunk)| (chunk_id as u64, chunk))
+            .collect();
+
+        // A batch with a repeated chunk is discarded as a whole.
+        assert!(matches!(
+            batch_storage.write_chunk_batch(&file_key, &[batch[0].clone(), batch[0].clone()]),
+            Err(FileStorageError::FileChunkAlreadyExists)
+        ));
+        assert_eq!(batch_storage.stored_chunks_count(&file_key).unwrap(), 0);
+
+        assert!(matches!(
+            batch_storage.write_chunk_batch(&file_key, &batch),
+            Ok(FileStorageWriteStatus::FileComplete)
+        ));
+
+        assert_eq!(
+            batch_storage.read_root(&file_key).unwrap(),
+            sequential_storage.read_root(&file_key).unwrap()
+        );
+        assert_eq!(
+            batch_storage.generate_proof(&file_key, &1).unwrap().root,
+            metadata.fingerprint
+        );
+    }
+
+    #[test]
+    fn test_delete_chunk_is_persisted() {
+        let path = tempfile::tempdir().unwrap();
+        let file_key = H256::from_slice(&[1; 32]);
+        let (metadata, chunks) = build_file(4);
+
+        {
+            let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+            file_storage.set_metadata(file_key, metadata).unwrap();
+
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                file_storage
+                    .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                    .unwrap();
+            }
+            assert!(file_storage.is_complete(&file_key).unwrap());
+
+            file_storage.delete_chunk(&file_key, &2).unwrap();
+        }
+
+        // Reopen the file storage from disk.
+        let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+        assert!(!file_storage.is_complete(&file_key).unwrap());
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 3);
+        assert!(matches!(
+            file_storage.generate_proof(&file_key, &1),
+            Err(FileStorageError::IncompleteFile)
+        ));
+        assert!(matches!(
+            file_storage.delete_chunk(&file_key, &2),
+            Err(FileStorageError::FileChunkDoesNotExist)
+        ));
+
+        // Writing the chunk back restores the file.
+        assert!(matches!(
+            file_storage.write_chunk(&file_key, &2, &chunks[2]),
+            Ok(FileStorageWriteStatus::FileComplete)
+        ));
+        assert!(file_storage.generate_proof(&file_key, &1).is_ok());
+    }
+
+    #[test]
+    fn test_cached_stored_chunks_count_matches_trie() {
+        let path = tempfile::tempdir().unwrap();
+        let mut file_storage = RocksDbFileStorage::<TestLayout>::new(path.path()).unwrap();
+        let file_key = H256::from_slice(&[1; 32]);
+
+        let (metadata, chunks) = build_file(6);
+        file_storage.set_metadata(file_key, metadata).unwrap();
+
+        let assert_count_matches_trie = |file_storage: &RocksDbFileStorage<TestLayout>| {
+            let root = file_storage.read_root(&file_key).unwrap().unwrap();
+            assert_eq!(
+                file_storage.stored_chunks_count(&file_key).unwrap(),
+                FileTrieDb::<TestLayout>::new(&file_storage.db, file_key)
+                    .count_stored_chunks(&root)
+                    .unwrap()
+            );
+        };
+
+        file_storage.write_chunk(&file_key, &0, &chunks[0]).unwrap();
+        file_storage
+            .write_chunk_batch(&file_key, &[(2, chunks[2].clone()), (4, chunks[4].clone())])
+            .unwrap();
+        assert_count_matches_trie(&file_storage);
+
+        // Rejected writes leave the count untouched.
+        assert!(file_storage.write_chunk(&file_key, &2, &chunks[2]).is_err());
+        assert!(file_storage
+            .write_chunk_batch(&file_key, &[(1, chunks[1].clone()), (4, chunks[4].clone())])
+            .is_err());
+        assert!(file_storage.delete_chunk(&file_key, &3).is_err());
+        assert_count_matches_trie(&file_storage);
+
+        file_storage.delete_chunk(&file_key, &2).unwrap();
+        file_storage.delete_chunk(&file_key, &0).unwrap();
+        assert_count_matches_trie(&file_storage);
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 1);
+
+        // Completing the file prunes the chunks outside of the range, which are still counted.
+        file_storage.set_chunk_range(&file_key, 0..3).unwrap();
+        for chunk_id in [0, 1, 2, 3, 5] {
+            file_storage
+                .write_chunk(&file_key, &chunk_id, &chunks[chunk_id as usize])
+                .unwrap();
+        }
+        assert!(file_storage.is_complete(&file_key).unwrap());
+        assert_count_matches_trie(&file_storage);
+
+        // Resetting the metadata resets the count along with the chunks.
+        let metadata = file_storage.get_metadata(&file_key).unwrap();
+        file_storage.set_metadata(file_key, metadata).unwrap();
+        assert_eq!(file_storage.stored_chunks_count(&file_key).unwrap(), 0);
+
+        file_storage.delete_file(&file_key).unwrap();
+        assert!(matches!(
+            file_storage.stored_chunks_count(&file_key),
+            Err(FileStorageError::FileDoesNotExist)
+        ));
+    }
+}
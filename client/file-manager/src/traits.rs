@@ -1,4 +1,10 @@
-use storage_hub_infra::types::{Chunk, ChunkId, FileProof, Key, Metadata};
+use std::ops::Range;
+
+use sp_core::H256;
+use storage_hub_infra::{
+    constants::FILE_CHUNK_SIZE,
+    types::{Chunk, ChunkId, FileProof, Key, Metadata},
+};
 
 #[derive(Debug)]
 pub enum FileStorageError {
@@ -8,6 +14,8 @@ pub enum FileStorageError {
     FileChunkDoesNotExist,
     /// Failed to insert the file chunk.
     FailedToInsertFileChunk,
+    /// Failed to remove the file chunk.
+    FailedToDeleteFileChunk,
     /// Failed to get file chunk.
     FailedToGetFileChunk,
     /// Failed to generate proof.
@@ -18,6 +26,20 @@ pub enum FileStorageError {
     FingerprintAndStoredFileMismatch,
     /// The requested file is incomplete and a proof is impossible to generate.
     IncompleteFile,
+    /// The chunk range is empty or does not fall within the chunks of the file.
+    InvalidChunkRange,
+    /// The size of the chunk does not match the size expected for its position in the file.
+    InvalidChunkSize,
+    /// The chunk id is not lower than the number of chunks of the file.
+    InvalidChunkId,
+    /// Failed to read from the underlying storage.
+    FailedToReadStorage,
+    /// Failed to write to the underlying storage.
+    FailedToWriteToStorage,
+    /// Failed to decode a value read from the underlying storage.
+    FailedToDecodeValue,
+    /// Failed to encode a value to be written to the underlying storage.
+    FailedToEncodeValue,
 }
 
 #[derive(Debug)]
@@ -29,31 +51,164 @@ pub enum FileStorageWriteStatus {
     FileIncomplete,
 }
 
+/// Configuration of a [`FileStorage`].
+///
+/// The default configuration uses the [`FILE_CHUNK_SIZE`] of the protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStorageConfig {
+    /// Size in bytes of the chunks new files are split in, the last chunk of a file being possibly shorter.
+    ///
+    /// Smaller chunks make smaller proofs, at the cost of more chunks, and so bigger tries, for the same file.
+    chunk_size: usize,
+}
+
+impl FileStorageConfig {
+    /// Create a configuration splitting new files in chunks of `chunk_size` bytes.
+    ///
+    /// The storage of a provider has to use the chunk size of the runtime (`FileChunkSize` of the file system
+    /// pallet), since the proofs it submits are checked against it.
+    ///
+    /// Fails with [`FileStorageError::InvalidChunkSize`] if `chunk_size` is zero.
+    pub fn new(chunk_size: usize) -> Result<Self, FileStorageError> {
+        if chunk_size == 0 {
+            return Err(FileStorageError::InvalidChunkSize);
+        }
+
+        Ok(Self { chunk_size })
+    }
+
+    /// Size in bytes of the chunks new files are split in.
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+}
+
+impl Default for FileStorageConfig {
+    fn default() -> Self {
+        Self {
+            chunk_size: FILE_CHUNK_SIZE,
+        }
+    }
+}
+
 /// Storage interface to be implemented by the storage providers.
 pub trait FileStorage: 'static {
+    /// Get the configuration the storage was created with.
+    fn config(&self) -> &FileStorageConfig;
+
+    /// Build the metadata of a file of `size` bytes with the given fingerprint, split in chunks of the
+    /// size set in the [`FileStorageConfig`].
+    ///
+    /// The chunk size is stored in the metadata, so the file keeps being chunked the same way even if
+    /// the storage is later configured with another chunk size.
+    fn new_metadata(&self, fingerprint: H256, size: u64) -> Metadata {
+        Metadata::new(fingerprint, size, self.config().chunk_size() as u64)
+    }
+
     /// Generate proof for a chunk of a file. If the file does not exists or any chunk is missing,
-    /// no proof will be returned.
-    fn generate_proof(&self, key: &Key, chunk_id: &ChunkId) -> Result<FileProof, FileStorageError>;
+    /// no proof will be returned. Chunks outside of the file's retained chunk range cannot be proven.
+    fn generate_proof(&self, key: &Key, chunk_id: &ChunkId) -> Result<FileProof, FileStorageError> {
+        self.generate_proof_multi(key, core::slice::from_ref(chunk_id))
+    }
+
+    /// Generate a single proof for multiple chunks of a file, with the same conditions as [`Self::generate_proof`].
+    ///
+    /// The trie nodes shared by the chunks are only included once, so the proof is smaller than the proofs
+    /// of each chunk put together.
+    fn generate_proof_multi(
+        &self,
+        key: &Key,
+        chunk_ids: &[ChunkId],
+    ) -> Result<FileProof, FileStorageError>;
 
     /// Remove a file from storage.
-    fn delete_file(&mut self, key: &Key);
+    fn delete_file(&mut self, key: &Key) -> Result<(), FileStorageError>;
+
+    /// Get the keys of all the files held in storage, complete or not.
+    ///
+    /// The order of the keys is unspecified.
+    fn stored_files(&self) -> Result<Vec<Key>, FileStorageError>;
+
+    /// Get the number of chunks of a file that are stored.
+    ///
+    /// This allows resuming an interrupted upload by only requesting the missing chunks.
+    fn stored_chunks_count(&self, key: &Key) -> Result<u64, FileStorageError>;
+
+    /// Get the ids of the chunks of a file that are stored and can be served, in increasing order.
+    ///
+    /// Unlike [`Self::stored_chunks_count`], chunks outside of the retained chunk range are not included,
+    /// as their data is dropped once the file is complete.
+    fn stored_chunk_ids(&self, key: &Key) -> Result<Vec<ChunkId>, FileStorageError>;
+
+    /// Check whether all the chunks of a file are stored.
+    fn is_complete(&self, key: &Key) -> Result<bool, FileStorageError> {
+        Ok(self.stored_chunks_count(key)? == self.get_metadata(key)?.chunk_count())
+    }
 
     /// Get metadata for a file.
     fn get_metadata(&self, key: &Key) -> Result<Metadata, FileStorageError>;
 
     /// Set metadata for a file. This should be called before you start adding chunks since it
     /// will overwrite any previous Metadata and delete already stored file chunks.
-    fn set_metadata(&mut self, key: Key, metadata: Metadata);
+    fn set_metadata(&mut self, key: Key, metadata: Metadata) -> Result<(), FileStorageError>;
+
+    /// Get the hash of the [`Metadata`] of a file, stored along with it by [`Self::set_metadata`].
+    ///
+    /// See [`Metadata::hash`]. It can be presented alongside the chunk proofs of the file, to bind
+    /// them to the metadata the file was registered with.
+    fn metadata_hash(&self, key: &Key) -> Result<H256, FileStorageError>;
+
+    /// Set the range of chunks of a file that this provider retains. By default, all the chunks of a file are retained.
+    ///
+    /// All the chunks of the file still have to be written so it can be verified against its fingerprint.
+    /// Once the file is complete, the data of the chunks outside of this range is dropped, and those chunks
+    /// can no longer be read or proven.
+    fn set_chunk_range(&mut self, key: &Key, range: Range<ChunkId>)
+        -> Result<(), FileStorageError>;
+
+    /// Get the range of chunks of a file that this provider retains.
+    fn get_chunk_range(&self, key: &Key) -> Result<Range<ChunkId>, FileStorageError>;
 
     /// Get a file chunk from storage.
     fn get_chunk(&self, key: &Key, chunk_id: &ChunkId) -> Result<Chunk, FileStorageError>;
 
+    /// Iterate over the stored chunks of a file that can be served, in increasing chunk id order.
+    ///
+    /// The chunks are read in a single pass over the file trie, which is much cheaper than calling
+    /// [`Self::get_chunk`] for every chunk when streaming a whole file. As with [`Self::stored_chunk_ids`],
+    /// chunks outside of the retained chunk range are not included. The storage is only borrowed immutably,
+    /// so other reads can happen while iterating.
+    fn chunks(
+        &self,
+        key: &Key,
+    ) -> Result<impl Iterator<Item = (ChunkId, Chunk)> + '_, FileStorageError>;
+
     /// Write a file chunk in storage. It is expected that you verify the associated proof that the
     /// [`Chunk`] is part of the file before writing it.
+    ///
+    /// The chunk id must be within the chunk count of the file, and the chunk must have the size expected
+    /// for its position according to the chunk size in the file [`Metadata`].
     fn write_chunk(
         &mut self,
         key: &Key,
         chunk_id: &ChunkId,
         data: &Chunk,
     ) -> Result<FileStorageWriteStatus, FileStorageError>;
+
+    /// Write multiple file chunks in storage at once, with the same expectations as [`Self::write_chunk`].
+    ///
+    /// The batch is written atomically: if any of the chunks is already stored (or repeated in the batch)
+    /// or has an invalid size, none of them are written. The completion of the file and its fingerprint
+    /// are only checked once, after all the chunks are written, which makes it much cheaper than writing
+    /// the chunks one by one when ingesting a whole file.
+    fn write_chunk_batch(
+        &mut self,
+        key: &Key,
+        chunks: &[(ChunkId, Chunk)],
+    ) -> Result<FileStorageWriteStatus, FileStorageError>;
+
+    /// Remove a single chunk of a file from storage, updating the root of the file trie.
+    ///
+    /// The file is left incomplete, so it can no longer be proven until the chunk is written again.
+    fn delete_chunk(&mut self, key: &Key, chunk_id: &ChunkId) -> Result<(), FileStorageError>;
 }
@@ -0,0 +1,160 @@
+use common::types::HashT;
+use sp_core::H256;
+use sp_trie::{Trie, TrieDBBuilder, TrieLayout};
+use storage_hub_infra::types::{Chunk, FileProof};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The root of the proof is not the fingerprint of the file.
+    FingerprintMismatch,
+    /// The proof does not prove exactly one chunk.
+    UnexpectedProvenChunks,
+    /// The compact proof could not be decoded or does not reconstruct the fingerprint.
+    InvalidProof,
+    /// The proven chunk does not match the chunk in the proof's trie.
+    ChunkMismatch,
+}
+
+/// Verify a proof of a single file chunk, as served by a remote peer, against the known
+/// fingerprint of the file.
+///
+/// The trie is reconstructed from the compact proof, and both its root and the proven leaf are
+/// checked, so the returned chunk can be trusted to be part of the file.
+pub fn verify_file_proof<T: TrieLayout>(
+    proof: &FileProof,
+    expected_fingerprint: &H256,
+) -> Result<Chunk, VerifyError> {
+    if proof.root != *expected_fingerprint {
+        return Err(VerifyError::FingerprintMismatch);
+    }
+
+    let [leaf] = proof.proven.as_slice() else {
+        return Err(VerifyError::UnexpectedProvenChunks);
+    };
+
+    let mut expected_root = HashT::<T>::default();
+    if expected_root.as_ref().len() != expected_fingerprint.as_bytes().len() {
+        return Err(VerifyError::InvalidProof);
+    }
+    expected_root
+        .as_mut()
+        .copy_from_slice(expected_fingerprint.as_bytes());
+
+    // Decoding fails if the proof does not hash up to the expected root.
+    let (memdb, root) = proof
+        .proof
+        .to_memory_db::<T::Hash>(Some(&expected_root))
+        .map_err(|_| VerifyError::InvalidProof)?;
+
+    let trie = TrieDBBuilder::<T>::new(&memdb, &root).build();
+    match trie.get(&leaf.key.to_be_bytes()) {
+        Ok(Some(data)) if data == leaf.data => Ok(leaf.data.clone()),
+        Ok(_) => Err(VerifyError::ChunkMismatch),
+        Err(_) => Err(VerifyError::InvalidProof),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use reference_trie::RefHasher;
+    use sp_trie::{LayoutV1, MemoryDB, TrieDBMutBuilder, TrieMut};
+    use storage_hub_infra::types::Metadata;
+
+    use crate::{in_memory::InMemoryFileStorage, traits::FileStorage};
+
+    type TestLayout = LayoutV1<RefHasher>;
+
+    /// Chunks are kept big enough to not be inlined in the trie nodes.
+    const CHUNK_SIZE: u64 = 64;
+
+    /// Store a complete file of 4 chunks, returning its key and chunks.
+    fn store_file(file_storage: &mut InMemoryFileStorage<TestLayout>) -> (H256, Vec<Chunk>) {
+        let chunks: Vec<Chunk> = (0..4u8).map(|i| vec![i + 1; CHUNK_SIZE as usize]).collect();
+
+        let mut memdb = MemoryDB::<RefHasher>::default();
+        let mut root: HashT<TestLayout> = Default::default();
+        {
+            let mut trie = TrieDBMutBuilder::<TestLayout>::new(&mut memdb, &mut root).build();
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                trie.insert(&(chunk_id as u64).to_be_bytes(), chunk)
+                    .unwrap();
+            }
+        }
+
+        let file_key = H256::from_slice(&[1; 32]);
+        file_storage
+            .set_metadata(
+                file_key,
+                Metadata::new(
+                    H256::from_slice(root.as_ref()),
+                    chunks.len() as u64 * CHUNK_SIZE,
+                    CHUNK_SIZE,
+                )
+                .with_owner(String::from("owner"))
+                .with_location(String::from("location")),
+            )
+            .unwrap();
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            file_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        (file_key, chunks)
+    }
+
+    #[test]
+    fn valid_proof_returns_chunk() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let (file_key, chunks) = store_file(&mut file_storage);
+        let fingerprint = file_storage.get_metadata(&file_key).unwrap().fingerprint;
+
+        let proof = file_storage.generate_proof(&file_key, &2).unwrap();
+
+        assert_eq!(
+            verify_file_proof::<TestLayout>(&proof, &fingerprint),
+            Ok(chunks[2].clone())
+        );
+    }
+
+    #[test]
+    fn tampered_leaf_is_rejected() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let (file_key, _) = store_file(&mut file_storage);
+        let fingerprint = file_storage.get_metadata(&file_key).unwrap().fingerprint;
+
+        let mut proof = file_storage.generate_proof(&file_key, &2).unwrap();
+        proof.proven[0].data[0] ^= 0xff;
+        assert_eq!(
+            verify_file_proof::<TestLayout>(&proof, &fingerprint),
+            Err(VerifyError::ChunkMismatch)
+        );
+
+        // Claiming the data is another chunk of the file does not verify either.
+        let mut proof = file_storage.generate_proof(&file_key, &2).unwrap();
+        proof.proven[0].key = 3;
+        assert!(verify_file_proof::<TestLayout>(&proof, &fingerprint).is_err());
+    }
+
+    #[test]
+    fn wrong_root_is_rejected() {
+        let mut file_storage = InMemoryFileStorage::<TestLayout>::new();
+        let (file_key, _) = store_file(&mut file_storage);
+
+        let proof = file_storage.generate_proof(&file_key, &2).unwrap();
+        assert_eq!(
+            verify_file_proof::<TestLayout>(&proof, &H256::zero()),
+            Err(VerifyError::FingerprintMismatch)
+        );
+
+        // A proof claiming the expected root, but whose nodes hash up to another root.
+        let mut proof = proof;
+        proof.root = H256::zero();
+        assert_eq!(
+            verify_file_proof::<TestLayout>(&proof, &H256::zero()),
+            Err(VerifyError::InvalidProof)
+        );
+    }
+}
@@ -98,7 +98,7 @@ mod tests {
     use reference_trie::RefHasher;
     use sp_core::H256;
     use sp_trie::{LayoutV1, MemoryDB};
-    use storage_hub_infra::types::Metadata;
+    use storage_hub_infra::{constants::FILE_CHUNK_SIZE, types::Metadata};
     use trie_db::{Hasher, TrieDBBuilder, TrieDBMutBuilder, TrieMut};
 
     /// Build a Merkle Patricia Forest Trie.
@@ -130,12 +130,9 @@ mod tests {
 
             let fingerprint = H256::from_slice(&[0; 32]);
 
-            let metadata = Metadata {
-                owner: String::from("owner"),
-                location: file_path,
-                size: 0,
-                fingerprint,
-            };
+            let metadata = Metadata::new(fingerprint, 0, FILE_CHUNK_SIZE as u64)
+                .with_owner(String::from("owner"))
+                .with_location(file_path);
 
             let metadata = bincode::serialize(&metadata).unwrap();
             let metadata_hash = T::Hash::hash(&metadata);
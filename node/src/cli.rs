@@ -1,8 +1,12 @@
-use std::{path::PathBuf, str::FromStr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use clap::{Parser, ValueEnum};
+use frame_support::traits::Get;
 
-use crate::command::ProviderOptions;
+use crate::command::{provider_keypair_from_seed_file, ProviderOptions};
 
 /// Sub-commands supported by the collator.
 #[derive(Debug, clap::Subcommand)]
@@ -46,6 +50,38 @@ pub enum Subcommand {
     /// [CLI](<https://github.com/paritytech/try-runtime-cli>). The subcommand exists as a stub and
     /// deprecation notice. It will be removed entirely some time after January 2024.
     TryRuntime,
+
+    /// Print the peer id a provider gets from its seed file.
+    ProviderPeerId(ProviderPeerIdCmd),
+}
+
+/// Print the deterministic peer id and libp2p public key derived from a provider seed file.
+///
+/// This is the peer id the node runs with when given the same `--seed-file`, so it can be used in
+/// the multiaddresses registered on-chain before the node is started.
+#[derive(Debug, Parser)]
+pub struct ProviderPeerIdCmd {
+    /// File with the seed of the provider, as given to `--seed-file`.
+    #[clap(long, value_name = "SEED_FILE")]
+    pub seed_file: PathBuf,
+}
+
+impl ProviderPeerIdCmd {
+    pub fn run(&self) -> Result<(), String> {
+        let keypair = provider_keypair_from_seed_file(&self.seed_file)?;
+        let public_key = keypair.public();
+
+        println!(
+            "Peer id: {}",
+            libp2p_identity::PublicKey::from(public_key.clone()).to_peer_id()
+        );
+        println!(
+            "Public key (ed25519): {}",
+            array_bytes::bytes2hex("0x", public_key.to_bytes())
+        );
+
+        Ok(())
+    }
 }
 
 #[derive(ValueEnum, Clone, Debug)]
@@ -72,20 +108,50 @@ pub struct ProviderConfigurations {
     )]
     pub provider_type: Option<ProviderType>,
 
-    /// Fixed value to generate deterministic peer id.
+    /// File with a fixed value to generate deterministic peer id.
+    ///
+    /// The node key derived from it replaces the one given with `--node-key`.
     #[clap(long, value_name = "SEED_FILE", required_if_eq("provider", "true"))]
-    pub seed_file: Option<String>,
+    pub seed_file: Option<PathBuf>,
+
+    /// Directory where the provider stores the files.
+    ///
+    /// Defaults to the `storage` directory under the node base path.
+    #[clap(long, value_name = "DIR")]
+    pub storage_path: Option<PathBuf>,
+
+    /// Maximum storage capacity of the provider, in bytes.
+    ///
+    /// Must be at least the minimum capacity of a Storage Provider in the runtime.
+    #[clap(long, value_name = "BYTES", required_if_eq("provider", "true"))]
+    pub max_capacity: Option<u64>,
 }
 
 impl ProviderConfigurations {
-    pub fn provider_options(&self) -> ProviderOptions {
-        ProviderOptions {
+    pub fn provider_options(&self, base_path: &Path) -> Result<ProviderOptions, String> {
+        let max_capacity = self.max_capacity.expect("Max capacity is required");
+        let min_capacity =
+            <storage_hub_runtime::Runtime as pallet_storage_providers::Config>::SpMinCapacity::get(
+            );
+        if max_capacity < u64::from(min_capacity) {
+            return Err(format!(
+                "Max capacity of {} bytes is below the minimum capacity of a Storage Provider of {} bytes.",
+                max_capacity, min_capacity
+            ));
+        }
+
+        Ok(ProviderOptions {
             provider_type: self
                 .provider_type
                 .clone()
                 .expect("Provider type is required"),
             seed_file: self.seed_file.clone().expect("Seed file is required"),
-        }
+            storage_path: self
+                .storage_path
+                .clone()
+                .unwrap_or_else(|| base_path.join("storage")),
+            max_capacity,
+        })
     }
 }
 
@@ -209,3 +275,147 @@ impl RelayChainCli {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seed_file_is_required_to_run_as_provider() {
+        let args = [
+            "storage-hub-node",
+            "--provider",
+            "--provider-type",
+            "bsp",
+            "--max-capacity",
+            "1024",
+        ];
+
+        let result = Cli::try_parse_from(args);
+        assert_eq!(
+            result.unwrap_err().kind(),
+            clap::error::ErrorKind::MissingRequiredArgument
+        );
+
+        let cli = Cli::try_parse_from(args.into_iter().chain(["--seed-file", "seed.txt"])).unwrap();
+        let provider_options = cli
+            .provider_config
+            .provider_options(Path::new("/base"))
+            .unwrap();
+        assert_eq!(provider_options.seed_file, Path::new("seed.txt"));
+    }
+
+    #[test]
+    fn storage_path_defaults_under_base_path() {
+        let args = [
+            "storage-hub-node",
+            "--provider",
+            "--provider-type",
+            "bsp",
+            "--max-capacity",
+            "1024",
+            "--seed-file",
+            "seed.txt",
+        ];
+
+        let cli = Cli::try_parse_from(args).unwrap();
+        let provider_options = cli
+            .provider_config
+            .provider_options(Path::new("/base"))
+            .unwrap();
+        assert_eq!(provider_options.storage_path, Path::new("/base/storage"));
+        assert_eq!(provider_options.max_capacity, 1024);
+
+        let cli =
+            Cli::try_parse_from(args.into_iter().chain(["--storage-path", "/files"])).unwrap();
+        let provider_options = cli
+            .provider_config
+            .provider_options(Path::new("/base"))
+            .unwrap();
+        assert_eq!(provider_options.storage_path, Path::new("/files"));
+    }
+
+    #[test]
+    fn max_capacity_below_min_capacity_is_rejected() {
+        let min_capacity =
+            <storage_hub_runtime::Runtime as pallet_storage_providers::Config>::SpMinCapacity::get(
+            );
+        let max_capacity = (u64::from(min_capacity) - 1).to_string();
+
+        let cli = Cli::try_parse_from([
+            "storage-hub-node",
+            "--provider",
+            "--provider-type",
+            "bsp",
+            "--max-capacity",
+            max_capacity.as_str(),
+            "--seed-file",
+            "seed.txt",
+        ])
+        .unwrap();
+        assert!(cli
+            .provider_config
+            .provider_options(Path::new("/base"))
+            .is_err());
+    }
+
+    #[test]
+    fn sealing_parses_instant_manual_and_interval() {
+        assert!(matches!(Sealing::from_str("instant"), Ok(Sealing::Instant)));
+        assert!(matches!(Sealing::from_str("manual"), Ok(Sealing::Manual)));
+        assert!(matches!(
+            Sealing::from_str("2000"),
+            Ok(Sealing::Interval(2000))
+        ));
+        assert!(Sealing::from_str("sometimes").is_err());
+
+        let cli = Cli::try_parse_from(["storage-hub-node", "--dev", "--sealing", "2000"]).unwrap();
+        assert!(matches!(cli.run.sealing, Sealing::Interval(2000)));
+    }
+
+    #[test]
+    fn provider_peer_id_subcommand_parses_seed_file() {
+        let cli = Cli::try_parse_from([
+            "storage-hub-node",
+            "provider-peer-id",
+            "--seed-file",
+            "seed.txt",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.subcommand,
+            Some(Subcommand::ProviderPeerId(cmd)) if cmd.seed_file == Path::new("seed.txt")
+        ));
+    }
+
+    #[test]
+    fn provider_keypair_is_derived_from_seed_file() {
+        let dir = std::env::temp_dir().join(format!("sh-seed-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let seed = "01".repeat(32);
+
+        // The `0x` prefix and surrounding whitespace are optional.
+        let prefixed = dir.join("prefixed");
+        std::fs::write(&prefixed, format!("0x{}\n", seed)).unwrap();
+        let unprefixed = dir.join("unprefixed");
+        std::fs::write(&unprefixed, &seed).unwrap();
+        assert_eq!(
+            provider_keypair_from_seed_file(&prefixed).unwrap().public(),
+            provider_keypair_from_seed_file(&unprefixed)
+                .unwrap()
+                .public()
+        );
+
+        // Seeds that are not 32 bytes of hex are rejected.
+        let malformed = dir.join("malformed");
+        std::fs::write(&malformed, "not a seed").unwrap();
+        assert!(provider_keypair_from_seed_file(&malformed).is_err());
+        let short = dir.join("short");
+        std::fs::write(&short, "0x0101").unwrap();
+        assert!(provider_keypair_from_seed_file(&short).is_err());
+
+        assert!(provider_keypair_from_seed_file(&dir.join("missing")).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
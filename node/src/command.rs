@@ -1,13 +1,18 @@
-use std::net::SocketAddr;
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
 
 use cumulus_client_service::storage_proof_size::HostFunctions as ReclaimHostFunctions;
 use cumulus_primitives_core::ParaId;
 use frame_benchmarking_cli::{BenchmarkCmd, SUBSTRATE_REFERENCE_HARDWARE};
+use libp2p_identity::ed25519;
 use log::info;
 use sc_cli::{
     ChainSpec, CliConfiguration, DefaultConfigurationValues, ImportParams, KeystoreParams,
     NetworkParams, Result, SharedParams, SubstrateCli,
 };
+use sc_network::config::{identity, NodeKeyConfig, Secret};
 use sc_service::config::{BasePath, PrometheusConfig};
 use sp_runtime::traits::AccountIdConversion;
 use storage_hub_runtime::Block;
@@ -23,8 +28,44 @@ use crate::{
 pub struct ProviderOptions {
     /// Provider type.
     pub provider_type: ProviderType,
-    /// Seed to generate deterministic peer id.
-    pub seed_file: String,
+    /// File with the seed to generate deterministic peer id.
+    pub seed_file: PathBuf,
+    /// Directory where the provider stores the files.
+    pub storage_path: PathBuf,
+    /// Maximum storage capacity of the provider, in bytes.
+    pub max_capacity: u64,
+}
+
+/// Derive the libp2p keypair of a provider from its seed file.
+///
+/// The seed file holds a 32 bytes seed, hex encoded with an optional `0x` prefix. Surrounding whitespace is ignored.
+pub fn provider_keypair_from_seed_file(
+    path: &Path,
+) -> std::result::Result<ed25519::Keypair, String> {
+    let seed = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read seed file {}: {}", path.display(), e))?;
+    let seed: [u8; 32] = array_bytes::hex2array(seed.trim()).map_err(|e| {
+        format!(
+            "Seed file {} must hold a hex encoded 32 bytes seed: {:?}",
+            path.display(),
+            e
+        )
+    })?;
+    let secret_key = ed25519::SecretKey::try_from_bytes(seed)
+        .map_err(|e| format!("Invalid seed in seed file {}: {}", path.display(), e))?;
+
+    Ok(ed25519::Keypair::from(secret_key))
+}
+
+/// Network node key of a provider, derived from its seed file, so that the node runs with the peer id printed by
+/// the `provider-peer-id` subcommand. It takes precedence over the node key given with `--node-key`.
+fn provider_node_key(seed_file: &Path) -> std::result::Result<NodeKeyConfig, String> {
+    let keypair = provider_keypair_from_seed_file(seed_file)?;
+    let secret_key =
+        identity::ed25519::SecretKey::try_from_bytes(keypair.secret().as_ref().to_vec())
+            .map_err(|e| format!("Invalid seed in seed file {}: {}", seed_file.display(), e))?;
+
+    Ok(NodeKeyConfig::Ed25519(Secret::Input(secret_key)))
 }
 
 fn load_spec(id: &str) -> std::result::Result<Box<dyn ChainSpec>, String> {
@@ -230,16 +271,24 @@ pub fn run() -> Result<()> {
 				_ => Err("Benchmarking sub-command unsupported".into()),
 			}
 		},
+		Some(Subcommand::ProviderPeerId(cmd)) => cmd.run().map_err(Into::into),
 		Some(Subcommand::TryRuntime) => Err("The `try-runtime` subcommand has been migrated to a standalone CLI (https://github.com/paritytech/try-runtime-cli). It is no longer being maintained here and will be removed entirely some time after January 2024. Please remove this subcommand from your runtime and use the standalone CLI.".into()),
 		None => {
 			let runner = cli.create_runner(&cli.run.normalize())?;
             let provider_options = if cli.provider_config.provider {
-                Some(cli.provider_config.provider_options())
+                Some(
+                    cli.provider_config
+                        .provider_options(runner.config().base_path.path())?,
+                )
             } else {
                 None
             };
 
-			runner.run_node_until_exit(|config| async move {
+			runner.run_node_until_exit(|mut config| async move {
+                if let Some(provider_options) = &provider_options {
+                    config.network.node_key = provider_node_key(&provider_options.seed_file)?;
+                }
+
 				let hwbench = (!cli.no_hardware_benchmarks)
 					.then_some(config.database.path().map(|database_path| {
 						let _ = std::fs::create_dir_all(database_path);
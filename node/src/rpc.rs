@@ -5,13 +5,18 @@
 
 #![warn(missing_docs)]
 
+mod provider_status;
+
 use std::sync::Arc;
 
+use file_manager::traits::FileStorage;
+use sc_client_api::{Backend, StorageProvider};
 use sc_consensus_manual_seal::{
     rpc::{ManualSeal, ManualSealApiServer},
     EngineCommand,
 };
 use sp_core::H256;
+use sp_keystore::KeystorePtr;
 use storage_hub_runtime::{opaque::Block, AccountId, Balance, Nonce};
 
 pub use sc_rpc::DenyUnsafe;
@@ -19,12 +24,15 @@ use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use tokio::sync::RwLock;
+
+use self::provider_status::{ProviderStatusApiServer, ProviderStatusRpc};
 
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpsee::RpcModule<()>;
 
 /// Full client dependencies
-pub struct FullDeps<C, P> {
+pub struct FullDeps<C, P, FL> {
     /// The client instance to use.
     pub client: Arc<C>,
     /// Transaction pool instance.
@@ -33,16 +41,21 @@ pub struct FullDeps<C, P> {
     pub command_sink: Option<futures::channel::mpsc::Sender<EngineCommand<H256>>>,
     /// Whether to deny unsafe calls
     pub deny_unsafe: DenyUnsafe,
+    /// File Storage of the node, only present if it is running as a Storage Provider.
+    pub file_storage: Option<Arc<RwLock<FL>>>,
+    /// Keystore holding the key the Storage Provider signs extrinsics with.
+    pub keystore: KeystorePtr,
 }
 
 /// Instantiate all RPC extensions.
-pub fn create_full<C, P>(
-    deps: FullDeps<C, P>,
+pub fn create_full<C, P, B, FL>(
+    deps: FullDeps<C, P, FL>,
 ) -> Result<RpcExtension, Box<dyn std::error::Error + Send + Sync>>
 where
     C: ProvideRuntimeApi<Block>
         + HeaderBackend<Block>
         + HeaderMetadata<Block, Error = BlockChainError>
+        + StorageProvider<Block, B>
         + Send
         + Sync
         + 'static,
@@ -50,6 +63,8 @@ where
     C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Nonce>,
     C::Api: BlockBuilder<Block>,
     P: TransactionPool + Sync + Send + 'static,
+    B: Backend<Block> + Send + Sync + 'static,
+    FL: FileStorage + Send + Sync,
 {
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApiServer};
     use substrate_frame_rpc_system::{System, SystemApiServer};
@@ -60,10 +75,17 @@ where
         pool,
         command_sink,
         deny_unsafe,
+        file_storage,
+        keystore,
     } = deps;
 
     io.merge(System::new(client.clone(), pool, deny_unsafe).into_rpc())?;
-    io.merge(TransactionPayment::new(client).into_rpc())?;
+    io.merge(TransactionPayment::new(client.clone()).into_rpc())?;
+
+    // The provider status is only available if the node is running as a Storage Provider.
+    if let Some(file_storage) = file_storage {
+        io.merge(ProviderStatusRpc::new(client, file_storage, keystore, deny_unsafe).into_rpc())?;
+    }
 
     if let Some(command_sink) = command_sink {
         io.merge(
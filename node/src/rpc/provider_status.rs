@@ -0,0 +1,202 @@
+//! RPC to report the status of the files stored locally by a Storage Provider node.
+//!
+//! This allows operators to compare what the node physically holds against what the
+//! Storage Provider advertises on-chain, to detect drift between both.
+
+use std::{marker::PhantomData, sync::Arc};
+
+use codec::Decode;
+use file_manager::traits::FileStorage;
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+    types::error::{ErrorObject, ErrorObjectOwned},
+};
+use sc_client_api::{Backend, HeaderBackend, StorageProvider};
+use sc_rpc::DenyUnsafe;
+use serde::{Deserialize, Serialize};
+use sp_core::storage::StorageKey;
+use sp_keystore::{Keystore, KeystorePtr};
+use storage_hub_infra::types::Key;
+use storage_hub_runtime::{opaque::Block, AccountId, Runtime};
+use tokio::sync::RwLock;
+
+use crate::services::blockchain::KEY_TYPE;
+
+/// Data type for the measurement of storage size, as used on-chain.
+type StorageData = <Runtime as pallet_storage_providers::Config>::StorageData;
+
+/// Status of the storage of a Storage Provider node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderStatus {
+    /// Number of files held by the node, complete or not.
+    pub stored_files: u64,
+    /// Number of bytes of file data held by the node.
+    ///
+    /// Only the retained chunks of complete files are counted. Chunks of incomplete files are counted as full chunks.
+    pub stored_bytes: u64,
+    /// Keys of the files that are missing chunks.
+    pub incomplete_files: Vec<Key>,
+    /// Capacity of the Storage Provider on-chain, if it is registered.
+    pub capacity: Option<StorageData>,
+    /// Data used by the Storage Provider on-chain, if it is registered.
+    pub data_used: Option<StorageData>,
+}
+
+/// StorageHub Storage Provider RPC methods.
+#[rpc(server, namespace = "storagehub")]
+pub trait ProviderStatusApi {
+    /// Get the status of the files stored by this node, alongside the on-chain capacity and data used
+    /// of the Storage Provider it runs.
+    ///
+    /// This is an unsafe RPC method, as it exposes the local state of the node.
+    #[method(name = "providerStatus")]
+    async fn provider_status(&self) -> RpcResult<ProviderStatus>;
+}
+
+/// Implementation of [`ProviderStatusApiServer`].
+pub struct ProviderStatusRpc<C, B, FL> {
+    client: Arc<C>,
+    file_storage: Arc<RwLock<FL>>,
+    keystore: KeystorePtr,
+    deny_unsafe: DenyUnsafe,
+    _backend: PhantomData<B>,
+}
+
+impl<C, B, FL> ProviderStatusRpc<C, B, FL> {
+    pub fn new(
+        client: Arc<C>,
+        file_storage: Arc<RwLock<FL>>,
+        keystore: KeystorePtr,
+        deny_unsafe: DenyUnsafe,
+    ) -> Self {
+        Self {
+            client,
+            file_storage,
+            keystore,
+            deny_unsafe,
+            _backend: PhantomData,
+        }
+    }
+}
+
+impl<C, B, FL> ProviderStatusRpc<C, B, FL>
+where
+    C: HeaderBackend<Block> + StorageProvider<Block, B>,
+    B: Backend<Block>,
+{
+    /// Read and decode a storage element at the best block.
+    ///
+    /// Returns `None` if there is no value stored under `key`.
+    fn get_best_block_storage_element<T: Decode>(
+        &self,
+        key: Vec<u8>,
+    ) -> Result<Option<T>, ErrorObjectOwned> {
+        let best_hash = self.client.info().best_hash;
+        let raw_storage_opt = self
+            .client
+            .storage(best_hash, &StorageKey(key))
+            .map_err(internal_error)?;
+
+        raw_storage_opt
+            .map(|raw_storage| T::decode(&mut raw_storage.0.as_slice()))
+            .transpose()
+            .map_err(internal_error)
+    }
+
+    /// Get the on-chain capacity and data used of the Storage Provider this node signs extrinsics as.
+    ///
+    /// Returns `None` if there is no key in the keystore or the account is not registered as a MSP or BSP.
+    fn get_capacity_and_data_used(
+        &self,
+    ) -> Result<Option<(StorageData, StorageData)>, ErrorObjectOwned> {
+        let Some(public_key) = self.keystore.sr25519_public_keys(KEY_TYPE).pop() else {
+            return Ok(None);
+        };
+        let account: AccountId = public_key.into();
+
+        let msp_id_key =
+            pallet_storage_providers::AccountIdToMainStorageProviderId::<Runtime>::hashed_key_for(
+                &account,
+            );
+        if let Some(msp_id) = self.get_best_block_storage_element(msp_id_key)? {
+            let msp_key =
+                pallet_storage_providers::MainStorageProviders::<Runtime>::hashed_key_for(&msp_id);
+            let msp: Option<pallet_storage_providers::types::MainStorageProvider<Runtime>> =
+                self.get_best_block_storage_element(msp_key)?;
+            return Ok(msp.map(|msp| (msp.capacity, msp.data_used)));
+        }
+
+        let bsp_id_key =
+            pallet_storage_providers::AccountIdToBackupStorageProviderId::<Runtime>::hashed_key_for(
+                &account,
+            );
+        if let Some(bsp_id) = self.get_best_block_storage_element(bsp_id_key)? {
+            let bsp_key =
+                pallet_storage_providers::BackupStorageProviders::<Runtime>::hashed_key_for(
+                    &bsp_id,
+                );
+            let bsp: Option<pallet_storage_providers::types::BackupStorageProvider<Runtime>> =
+                self.get_best_block_storage_element(bsp_key)?;
+            return Ok(bsp.map(|bsp| (bsp.capacity, bsp.data_used)));
+        }
+
+        Ok(None)
+    }
+}
+
+#[async_trait]
+impl<C, B, FL> ProviderStatusApiServer for ProviderStatusRpc<C, B, FL>
+where
+    C: HeaderBackend<Block> + StorageProvider<Block, B> + Send + Sync + 'static,
+    B: Backend<Block> + Send + Sync + 'static,
+    FL: FileStorage + Send + Sync,
+{
+    async fn provider_status(&self) -> RpcResult<ProviderStatus> {
+        // Exposes the local state of the node, so it is only available to trusted callers.
+        self.deny_unsafe.check_if_safe()?;
+
+        let mut stored_bytes = 0u64;
+        let mut incomplete_files = Vec::new();
+
+        let file_storage = self.file_storage.read().await;
+        let stored_files = file_storage.stored_files().map_err(internal_error)?;
+        for key in &stored_files {
+            let metadata = file_storage.get_metadata(key).map_err(internal_error)?;
+
+            if file_storage.is_complete(key).map_err(internal_error)? {
+                let chunk_range = file_storage.get_chunk_range(key).map_err(internal_error)?;
+                stored_bytes += chunk_range
+                    .map(|chunk_id| metadata.expected_chunk_size(chunk_id))
+                    .sum::<u64>();
+            } else {
+                let stored_chunks = file_storage
+                    .stored_chunks_count(key)
+                    .map_err(internal_error)?;
+                stored_bytes += (stored_chunks * metadata.chunk_size).min(metadata.size);
+                incomplete_files.push(*key);
+            }
+        }
+        drop(file_storage);
+
+        let (capacity, data_used) = self.get_capacity_and_data_used()?.unzip();
+
+        Ok(ProviderStatus {
+            stored_files: stored_files.len() as u64,
+            stored_bytes,
+            incomplete_files,
+            capacity,
+            data_used,
+        })
+    }
+}
+
+/// Map any error to an internal JSON-RPC error.
+fn internal_error(e: impl std::fmt::Debug) -> ErrorObjectOwned {
+    ErrorObject::owned(
+        jsonrpsee::types::error::INTERNAL_ERROR_CODE,
+        jsonrpsee::types::error::INTERNAL_ERROR_MSG,
+        Some(format!("{:?}", e)),
+    )
+}
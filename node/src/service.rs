@@ -6,20 +6,20 @@ use std::{sync::Arc, time::Duration};
 use codec::Encode;
 use cumulus_client_cli::CollatorOptions;
 use cumulus_client_parachain_inherent::{MockValidationDataInherentDataProvider, MockXcmConfig};
-use file_manager::in_memory::InMemoryFileStorage;
+use file_manager::{rocksdb::RocksDbFileStorage, traits::FileStorageConfig};
 use forest_manager::in_memory::InMemoryForestStorage;
 use futures::{Stream, StreamExt};
 use polkadot_primitives::{HeadData, ValidationCode};
 use reference_trie::RefHasher;
 use sc_consensus_manual_seal::consensus::aura::AuraConsensusDataProvider;
 use sp_consensus_aura::Slot;
-use sp_core::H256;
+use sp_core::{Get, H256};
 use sp_trie::LayoutV1;
 use storage_hub_infra::actor::TaskSpawner;
 // Local Runtime Types
 use storage_hub_runtime::{
     opaque::{Block, Hash},
-    RuntimeApi,
+    Runtime, RuntimeApi,
 };
 
 // Cumulus Imports
@@ -55,7 +55,7 @@ use crate::{
     command::ProviderOptions,
     services::{
         blockchain::{spawn_blockchain_service, KEY_TYPE},
-        file_transfer::spawn_file_transfer_service,
+        file_transfer::{configure_file_transfer_service, spawn_file_transfer_service},
         StorageHubHandler, StorageHubHandlerConfig,
     },
 };
@@ -195,6 +195,43 @@ pub fn new_partial(
     })
 }
 
+/// Storage of a provider node: files are persisted under the storage path of the provider.
+struct ProviderStorageHubConfig {}
+
+impl StorageHubHandlerConfig for ProviderStorageHubConfig {
+    type FileStorage = RocksDbFileStorage<LayoutV1<RefHasher>>;
+    type ForestStorage = InMemoryForestStorage<LayoutV1<RefHasher>>;
+}
+
+/// Open the File Storage of a provider in its storage path.
+///
+/// New files are split in chunks of the size set by the runtime, against which the proofs of the provider are checked.
+fn open_file_storage(
+    provider_options: &ProviderOptions,
+) -> sc_service::error::Result<Arc<RwLock<RocksDbFileStorage<LayoutV1<RefHasher>>>>> {
+    let chunk_size = <Runtime as pallet_file_system::Config>::FileChunkSize::get();
+    let config = usize::try_from(chunk_size)
+        .ok()
+        .and_then(|chunk_size| FileStorageConfig::new(chunk_size).ok())
+        .ok_or_else(|| {
+            sc_service::Error::Other(format!(
+                "Invalid file chunk size of the runtime: {}",
+                chunk_size
+            ))
+        })?;
+
+    let file_storage = RocksDbFileStorage::with_config(&provider_options.storage_path, config)
+        .map_err(|e| {
+            sc_service::Error::Other(format!(
+                "Failed to open the file storage at {}: {}",
+                provider_options.storage_path.display(),
+                e
+            ))
+        })?;
+
+    Ok(Arc::new(RwLock::new(file_storage)))
+}
+
 /// Start a development node with the given solo chain `Configuration`.
 #[sc_tracing::logging::prefix_logs_with("Solo chain 💾")]
 async fn start_dev_impl(
@@ -236,16 +273,15 @@ async fn start_dev_impl(
         .flatten()
         .expect("Genesis block exists; qed");
 
-    // Spawning File Transfer Service if node is running as a Storage Provider.
+    // Configuring the File Transfer Service protocol if node is running as a Storage Provider.
     // This is done here because the File Transfer Service modifies the network configuration.
-    let mut file_transfer_service_handle = None;
+    let mut file_transfer_request_protocol = None;
     if provider_options.is_some() {
-        let task_spawner = TaskSpawner::new(task_manager.spawn_handle(), "generic");
-
-        file_transfer_service_handle = Some(
-            spawn_file_transfer_service(&task_spawner, genesis_hash, &config, &mut net_config)
-                .await,
-        );
+        file_transfer_request_protocol = Some(configure_file_transfer_service(
+            genesis_hash,
+            &config,
+            &mut net_config,
+        ));
     }
 
     let (network, system_rpc_tx, tx_handler_controller, network_starter, sync_service) =
@@ -326,9 +362,18 @@ async fn start_dev_impl(
             }
         };
 
+    // The File Storage is shared with the File Transfer Service, which writes the uploaded chunks,
+    // and with the RPC, which reports its status.
+    let file_storage = provider_options
+        .as_ref()
+        .map(open_file_storage)
+        .transpose()?;
+
     let rpc_builder = {
         let client = client.clone();
         let transaction_pool = transaction_pool.clone();
+        let file_storage = file_storage.clone();
+        let keystore = keystore.clone();
 
         Box::new(move |deny_unsafe, _| {
             let deps = crate::rpc::FullDeps {
@@ -336,6 +381,8 @@ async fn start_dev_impl(
                 pool: transaction_pool.clone(),
                 command_sink: command_sink.clone(),
                 deny_unsafe,
+                file_storage: file_storage.clone(),
+                keystore: keystore.clone(),
             };
 
             crate::rpc::create_full(deps).map_err(Into::into)
@@ -359,13 +406,30 @@ async fn start_dev_impl(
 
     // Spawning the Blockchain Service if node is running as a Storage Provider.
     if let Some(provider_options) = provider_options {
-        // File Transfer Service handle is expected to be present when the node is running as a Storage Provider.
-        let file_transfer_service_handle = file_transfer_service_handle.expect(
-            "File Transfer Service handle is expected to be present when the node is running as a Storage Provider. qed",
-        );
+        // File Transfer Service protocol is expected to be configured when the node is running as a Storage Provider.
+        let (file_transfer_request_protocol_name, file_transfer_request_receiver) =
+            file_transfer_request_protocol.expect(
+                "File Transfer Service protocol is expected to be configured when the node is running as a Storage Provider. qed",
+            );
 
         let task_spawner = TaskSpawner::new(task_manager.spawn_handle(), "generic");
 
+        // File Storage is expected to be created when the node is running as a Storage Provider.
+        let file_storage = file_storage.expect(
+            "File Storage is expected to be created when the node is running as a Storage Provider. qed",
+        );
+
+        // Spawn the File Transfer Service, now that the network is built.
+        let file_transfer_service_handle = spawn_file_transfer_service(
+            &task_spawner,
+            file_transfer_request_protocol_name,
+            file_transfer_request_receiver,
+            network.clone(),
+            file_storage.clone(),
+            prometheus_registry.clone(),
+        )
+        .await;
+
         // Initialise seed for signing transactions using blockchain service.
         // In dev mode we use a well known dev account.
         keystore
@@ -381,25 +445,21 @@ async fn start_dev_impl(
         )
         .await;
 
-        let file_storage = Arc::new(RwLock::new(InMemoryFileStorage::new()));
         let forest_storage = Arc::new(RwLock::new(InMemoryForestStorage::new()));
 
-        struct InMemoryStorageHubConfig {}
-
-        impl StorageHubHandlerConfig for InMemoryStorageHubConfig {
-            type FileStorage = InMemoryFileStorage<LayoutV1<RefHasher>>;
-            type ForestStorage = InMemoryForestStorage<LayoutV1<RefHasher>>;
-        }
-
         // Initialise the StorageHubHandler, for tasks to have access to the services.
-        let sh_handler = StorageHubHandler::<InMemoryStorageHubConfig>::new(
+        let sh_handler = StorageHubHandler::<ProviderStorageHubConfig>::new(
             task_spawner,
             file_transfer_service_handle,
             blockchain_service_handle,
             file_storage,
             forest_storage,
+            provider_options.max_capacity,
         );
 
+        // Any node can upload the files it issued storage requests for.
+        sh_handler.start_user_tasks();
+
         // Starting the tasks according to the provider type.
         match provider_options.provider_type {
             ProviderType::Bsp => sh_handler.start_bsp_tasks(),
@@ -553,21 +613,15 @@ async fn start_node_impl(
         .flatten()
         .expect("Genesis block exists; qed");
 
-    // Spawning File Transfer Service if node is running as a Storage Provider.
+    // Configuring the File Transfer Service protocol if node is running as a Storage Provider.
     // This is done here because the File Transfer Service modifies the network configuration.
-    let mut file_transfer_service_handle = None;
+    let mut file_transfer_request_protocol = None;
     if provider_options.is_some() {
-        let task_spawner = TaskSpawner::new(task_manager.spawn_handle(), "generic");
-
-        file_transfer_service_handle = Some(
-            spawn_file_transfer_service(
-                &task_spawner,
-                genesis_hash,
-                &parachain_config,
-                &mut net_config,
-            )
-            .await,
-        );
+        file_transfer_request_protocol = Some(configure_file_transfer_service(
+            genesis_hash,
+            &parachain_config,
+            &mut net_config,
+        ));
     }
 
     let (relay_chain_interface, collator_key) = build_relay_chain_interface(
@@ -623,9 +677,18 @@ async fn start_node_impl(
         );
     }
 
+    // The File Storage is shared with the File Transfer Service, which writes the uploaded chunks,
+    // and with the RPC, which reports its status.
+    let file_storage = provider_options
+        .as_ref()
+        .map(open_file_storage)
+        .transpose()?;
+
     let rpc_builder = {
         let client = client.clone();
         let transaction_pool = transaction_pool.clone();
+        let file_storage = file_storage.clone();
+        let keystore = keystore.clone();
 
         Box::new(move |deny_unsafe, _| {
             let deps = crate::rpc::FullDeps {
@@ -633,6 +696,8 @@ async fn start_node_impl(
                 pool: transaction_pool.clone(),
                 command_sink: None,
                 deny_unsafe,
+                file_storage: file_storage.clone(),
+                keystore: keystore.clone(),
             };
 
             crate::rpc::create_full(deps).map_err(Into::into)
@@ -656,13 +721,30 @@ async fn start_node_impl(
 
     // Spawning the Blockchain Service if node is running as a Storage Provider.
     if let Some(provider_options) = provider_options {
-        // File Transfer Service handle is expected to be present when the node is running as a Storage Provider.
-        let file_transfer_service_handle = file_transfer_service_handle.expect(
-            "File Transfer Service handle is expected to be present when the node is running as a Storage Provider. qed",
-        );
+        // File Transfer Service protocol is expected to be configured when the node is running as a Storage Provider.
+        let (file_transfer_request_protocol_name, file_transfer_request_receiver) =
+            file_transfer_request_protocol.expect(
+                "File Transfer Service protocol is expected to be configured when the node is running as a Storage Provider. qed",
+            );
 
         let task_spawner = TaskSpawner::new(task_manager.spawn_handle(), "generic");
 
+        // File Storage is expected to be created when the node is running as a Storage Provider.
+        let file_storage = file_storage.expect(
+            "File Storage is expected to be created when the node is running as a Storage Provider. qed",
+        );
+
+        // Spawn the File Transfer Service, now that the network is built.
+        let file_transfer_service_handle = spawn_file_transfer_service(
+            &task_spawner,
+            file_transfer_request_protocol_name,
+            file_transfer_request_receiver,
+            network.clone(),
+            file_storage.clone(),
+            prometheus_registry.clone(),
+        )
+        .await;
+
         // Initialise seed for signing transactions using blockchain service.
         // TODO: Modify this to use a key in the keystore of the node.
         // TODO: Typically these keys should be inserted with RPC calls to `author_insertKey`.
@@ -679,25 +761,21 @@ async fn start_node_impl(
         )
         .await;
 
-        let file_storage = Arc::new(RwLock::new(InMemoryFileStorage::new()));
         let forest_storage = Arc::new(RwLock::new(InMemoryForestStorage::new()));
 
-        struct InMemoryStorageHubConfig {}
-
-        impl StorageHubHandlerConfig for InMemoryStorageHubConfig {
-            type FileStorage = InMemoryFileStorage<LayoutV1<RefHasher>>;
-            type ForestStorage = InMemoryForestStorage<LayoutV1<RefHasher>>;
-        }
-
         // Initialise the StorageHubHandler, for tasks to have access to the services.
-        let sh_handler = StorageHubHandler::<InMemoryStorageHubConfig>::new(
+        let sh_handler = StorageHubHandler::<ProviderStorageHubConfig>::new(
             task_spawner,
             file_transfer_service_handle,
             blockchain_service_handle,
             file_storage,
             forest_storage,
+            provider_options.max_capacity,
         );
 
+        // Any node can upload the files it issued storage requests for.
+        sh_handler.start_user_tasks();
+
         // Starting the tasks according to the provider type.
         match provider_options.provider_type {
             ProviderType::Bsp => sh_handler.start_bsp_tasks(),
@@ -1,20 +1,34 @@
+use std::{str::FromStr, time::Duration};
+
 use anyhow::Result;
+use futures::future::{self, Either};
+use log::{debug, warn};
 use serde_json::Number;
 use sp_core::H256;
+use sp_runtime::{AccountId32, DispatchError};
 use storage_hub_infra::actor::ActorHandle;
 
 use super::{
     handler::BlockchainService,
-    types::{Extrinsic, ExtrinsicHash, ExtrinsicResult, RpcJsonResponse},
+    types::{
+        BackupStorageProviderId, Extrinsic, ExtrinsicHash, ExtrinsicResult, FileLocation,
+        RpcJsonResponse, ThresholdType, WatchExtrinsicError,
+    },
 };
 
+const LOG_TARGET: &str = "blockchain-service-interface";
+
 /// Commands that can be sent to the BlockchainService actor.
 #[derive(Debug)]
 pub enum BlockchainServiceCommand {
     SendExtrinsic {
         call: storage_hub_runtime::RuntimeCall,
         callback: tokio::sync::oneshot::Sender<
-            Result<(tokio::sync::mpsc::Receiver<RpcJsonResponse>, ExtrinsicHash)>,
+            Result<(
+                tokio::sync::mpsc::Receiver<RpcJsonResponse>,
+                ExtrinsicHash,
+                Number,
+            )>,
         >,
     },
     GetExtrinsicFromBlock {
@@ -26,15 +40,32 @@ pub enum BlockchainServiceCommand {
         subscription_id: Number,
         callback: tokio::sync::oneshot::Sender<Result<()>>,
     },
+    GetStorageRequestThreshold {
+        location: FileLocation,
+        callback: tokio::sync::oneshot::Sender<Result<Option<ThresholdType>>>,
+    },
+    GetBspId {
+        callback: tokio::sync::oneshot::Sender<Result<Option<BackupStorageProviderId>>>,
+    },
+    GetAccountId {
+        callback: tokio::sync::oneshot::Sender<AccountId32>,
+    },
 }
 
 /// Interface for interacting with the BlockchainService actor.
 pub trait BlockchainServiceInterface {
     /// Send an extrinsic to the runtime.
+    ///
+    /// Returns the receiver of the updates of the extrinsic, its hash, and the ID of the subscription
+    /// watching it, to be given to [`Self::unwatch_extrinsic`].
     async fn send_extrinsic(
         &self,
         call: impl Into<storage_hub_runtime::RuntimeCall>,
-    ) -> Result<(tokio::sync::mpsc::Receiver<RpcJsonResponse>, ExtrinsicHash)>;
+    ) -> Result<(
+        tokio::sync::mpsc::Receiver<RpcJsonResponse>,
+        ExtrinsicHash,
+        Number,
+    )>;
 
     /// Get an extrinsic from a block.
     async fn get_extrinsic_from_block(
@@ -46,6 +77,33 @@ pub trait BlockchainServiceInterface {
     /// Unwatch an extrinsic.
     async fn unwatch_extrinsic(&self, subscription_id: Number) -> Result<()>;
 
+    /// Get the threshold BSPs need to be under to volunteer for the storage request at `location`,
+    /// as of the best block.
+    ///
+    /// Returns `None` if there is no storage request at `location`.
+    async fn get_storage_request_threshold(
+        &self,
+        location: FileLocation,
+    ) -> Result<Option<ThresholdType>>;
+
+    /// Get the BSP ID of the account this node signs extrinsics with, as of the best block.
+    ///
+    /// Returns `None` if the account is not registered as a BSP.
+    async fn get_bsp_id(&self) -> Result<Option<BackupStorageProviderId>>;
+
+    /// Get the account this node signs extrinsics with.
+    async fn get_account_id(&self) -> AccountId32;
+
+    /// Send an extrinsic to the runtime and wait for it to be included in a block.
+    ///
+    /// Returns the extrinsic as found in the block, with its events, or [`WatchExtrinsicError::TimedOut`]
+    /// if it is not included within `timeout`. In both cases the extrinsic is unwatched.
+    async fn send_extrinsic_and_wait_for_inclusion(
+        &self,
+        call: impl Into<storage_hub_runtime::RuntimeCall>,
+        timeout: Duration,
+    ) -> Result<Extrinsic, WatchExtrinsicError>;
+
     /// Helper function to check if an extrinsic failed or succeeded in a block.
     fn extrinsic_result(extrinsic: Extrinsic) -> Result<ExtrinsicResult>;
 }
@@ -55,7 +113,11 @@ impl BlockchainServiceInterface for ActorHandle<BlockchainService> {
     async fn send_extrinsic(
         &self,
         call: impl Into<storage_hub_runtime::RuntimeCall>,
-    ) -> Result<(tokio::sync::mpsc::Receiver<RpcJsonResponse>, ExtrinsicHash)> {
+    ) -> Result<(
+        tokio::sync::mpsc::Receiver<RpcJsonResponse>,
+        ExtrinsicHash,
+        Number,
+    )> {
         let (callback, rx) = tokio::sync::oneshot::channel();
         // Build command to send to blockchain service.
         let message = BlockchainServiceCommand::SendExtrinsic {
@@ -93,6 +155,79 @@ impl BlockchainServiceInterface for ActorHandle<BlockchainService> {
         rx.await.expect("Failed to receive response from BlockchainService. Probably means BlockchainService has crashed.")
     }
 
+    async fn get_storage_request_threshold(
+        &self,
+        location: FileLocation,
+    ) -> Result<Option<ThresholdType>> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to blockchain service.
+        let message = BlockchainServiceCommand::GetStorageRequestThreshold { location, callback };
+        self.send(message).await;
+        rx.await.expect("Failed to receive response from BlockchainService. Probably means BlockchainService has crashed.")
+    }
+
+    async fn get_bsp_id(&self) -> Result<Option<BackupStorageProviderId>> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to blockchain service.
+        let message = BlockchainServiceCommand::GetBspId { callback };
+        self.send(message).await;
+        rx.await.expect("Failed to receive response from BlockchainService. Probably means BlockchainService has crashed.")
+    }
+
+    async fn get_account_id(&self) -> AccountId32 {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to blockchain service.
+        let message = BlockchainServiceCommand::GetAccountId { callback };
+        self.send(message).await;
+        rx.await.expect("Failed to receive response from BlockchainService. Probably means BlockchainService has crashed.")
+    }
+
+    async fn send_extrinsic_and_wait_for_inclusion(
+        &self,
+        call: impl Into<storage_hub_runtime::RuntimeCall>,
+        timeout: Duration,
+    ) -> Result<Extrinsic, WatchExtrinsicError> {
+        let (mut tx_watcher, tx_hash, subscription_id) = self.send_extrinsic(call).await?;
+
+        // Wait for the transaction to be included in a block.
+        let wait_for_inclusion = Box::pin(async {
+            while let Some(tx_result) = tx_watcher.recv().await {
+                // Parse the JSONRPC string, now that we know it is not an error.
+                let json: serde_json::Value = serde_json::from_str(&tx_result)
+                    .expect("The result, if not an error, can only be a JSONRPC string; qed");
+
+                debug!(target: LOG_TARGET, "Transaction information: {:?}", json);
+
+                // Checking if the transaction is included in a block.
+                // TODO: Consider if we might want to wait for "finalized".
+                // TODO: Handle other lifetime extrinsic edge cases. See https://github.com/paritytech/polkadot-sdk/blob/master/substrate/client/transaction-pool/api/src/lib.rs#L131
+                if let Some(in_block) = json["params"]["result"]["inBlock"].as_str() {
+                    return Ok(Some(H256::from_str(in_block)?));
+                }
+            }
+
+            // The watcher can be closed before inclusion, e.g. if the transaction is dropped from the pool.
+            Ok::<_, anyhow::Error>(None)
+        });
+
+        let block_hash =
+            match future::select(wait_for_inclusion, futures_timer::Delay::new(timeout)).await {
+                Either::Left((block_hash, _)) => block_hash.map_err(WatchExtrinsicError::Other),
+                Either::Right(_) => Err(WatchExtrinsicError::TimedOut(tx_hash)),
+            };
+
+        // Unwatch extrinsic to release tx_watcher, whether it was included or not. The subscription ID is
+        // known from the submission, so this also happens when no update was received before timing out.
+        if let Err(e) = self.unwatch_extrinsic(subscription_id).await {
+            warn!(target: LOG_TARGET, "Failed to unwatch extrinsic {:?}: {:?}", tx_hash, e);
+        }
+
+        let block_hash = block_hash?.ok_or(WatchExtrinsicError::WatcherClosed(tx_hash))?;
+
+        // Get the extrinsic from the block, with its events.
+        Ok(self.get_extrinsic_from_block(block_hash, tx_hash).await?)
+    }
+
     fn extrinsic_result(extrinsic: Extrinsic) -> Result<ExtrinsicResult> {
         for ev in extrinsic.events {
             match ev.event {
@@ -104,6 +239,7 @@ impl BlockchainServiceInterface for ActorHandle<BlockchainService> {
                 ) => {
                     return Ok(ExtrinsicResult::Failure {
                         dispatch_info,
+                        decoded_error: decode_dispatch_error(dispatch_error),
                         dispatch_error,
                     });
                 }
@@ -121,3 +257,47 @@ impl BlockchainServiceInterface for ActorHandle<BlockchainService> {
         ))
     }
 }
+
+/// Decode a [`DispatchError`] into the error of the pallet that raised it, using the error types of the runtime.
+///
+/// The pallet is found from the module index of the error and the error from its first byte, as the runtime does when
+/// encoding it. Returns `None` if the dispatch error was not raised by a pallet (e.g. `BadOrigin`) or if it does not
+/// decode as an error of the pallet.
+pub fn decode_dispatch_error(
+    dispatch_error: DispatchError,
+) -> Option<storage_hub_runtime::RuntimeError> {
+    storage_hub_runtime::RuntimeError::from_dispatch_error(dispatch_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use codec::{Decode, Encode};
+
+    use super::*;
+
+    #[test]
+    fn file_system_dispatch_error_is_decoded() {
+        let dispatch_error: DispatchError =
+            pallet_file_system::Error::<storage_hub_runtime::Runtime>::StorageRequestNotFound
+                .into();
+        // The message of a module error is not encoded, so it is missing from the events of a block.
+        let dispatch_error = DispatchError::decode(&mut &dispatch_error.encode()[..]).unwrap();
+
+        let decoded_error = decode_dispatch_error(dispatch_error);
+        assert!(matches!(
+            decoded_error,
+            Some(storage_hub_runtime::RuntimeError::FileSystem(
+                pallet_file_system::Error::StorageRequestNotFound
+            ))
+        ));
+        assert_eq!(
+            format!("{:?}", decoded_error.unwrap()),
+            "FileSystem(StorageRequestNotFound)"
+        );
+    }
+
+    #[test]
+    fn non_module_dispatch_error_is_not_decoded() {
+        assert!(decode_dispatch_error(DispatchError::BadOrigin).is_none());
+    }
+}
@@ -1,18 +1,31 @@
 use sp_core::H256;
 use sp_runtime::AccountId32;
 use storage_hub_infra::event_bus::{EventBus, EventBusMessage, ProvidesEventBus};
+use storage_hub_runtime::BlockNumber;
 
 type StorageData = pallet_file_system::types::StorageData<storage_hub_runtime::Runtime>;
 type FileLocation = pallet_file_system::types::FileLocation<storage_hub_runtime::Runtime>;
 type PeerIds = pallet_file_system::types::PeerIds<storage_hub_runtime::Runtime>;
+type MultiAddresses = pallet_file_system::types::MultiAddresses<storage_hub_runtime::Runtime>;
+type ChallengedKey = pallet_proofs_dealer::types::KeyFor<storage_hub_runtime::Runtime>;
 
-// TODO: use proper types
+/// New challenge event.
+///
+/// This event is emitted when a manual challenge is submitted on-chain.
 #[derive(Debug, Clone)]
-pub struct ChallengeRequest {
-    pub location: String,
+pub struct NewChallenge {
+    /// Account ID of the challenger.
+    pub who: AccountId32,
+    /// Key that was challenged (the file key).
+    pub key_challenged: ChallengedKey,
+    /// Block in which the challenge was submitted.
+    pub challenge_block: BlockNumber,
+    /// Hash of the block in which the challenge was submitted, from which the random challenges of that block
+    /// are derived.
+    pub challenge_block_hash: H256,
 }
 
-impl EventBusMessage for ChallengeRequest {}
+impl EventBusMessage for NewChallenge {}
 
 /// New storage request event.
 ///
@@ -29,32 +42,47 @@ pub struct NewStorageRequest {
     pub size: StorageData,
     /// lib2p peer IDs from where the user would send the file.
     pub user_peer_ids: PeerIds,
+    /// Multiaddresses of the user, if announced, to dial it without discovering its peer IDs first.
+    pub user_multiaddresses: MultiAddresses,
 }
 
 impl EventBusMessage for NewStorageRequest {}
 
-// TODO: use proper types
+/// Accepted BSP volunteer event.
+///
+/// This event is emitted when a BSP volunteers to store a file and is accepted on-chain.
 #[derive(Debug, Clone)]
 pub struct AcceptedBspVolunteer {
-    pub who: String,
-    pub location: String,
-    pub fingerprint: String,
-    pub multiaddresses: Vec<String>,
+    /// Account ID of the BSP.
+    pub who: AccountId32,
+    /// Location of the file (as a file path).
+    pub location: FileLocation,
+    /// Fingerprint of the file (root hash of the merklised file).
+    pub fingerprint: H256,
+    /// Multiaddresses of the BSP, where the file should be sent to.
+    pub multiaddresses: MultiAddresses,
 }
 
 impl EventBusMessage for AcceptedBspVolunteer {}
 
-// TODO: use proper types
+/// Storage request revoked event.
+///
+/// This event is emitted when a storage request is revoked on-chain by the user who issued it.
 #[derive(Debug, Clone)]
 pub struct StorageRequestRevoked {
-    pub location: String,
+    /// Location of the file (as a file path).
+    pub location: FileLocation,
+    /// Fingerprint of the file (root hash of the merklised file).
+    pub fingerprint: H256,
+    /// Account IDs of the BSPs that had volunteered for the storage request, which should stop storing the file.
+    pub bsps: Vec<AccountId32>,
 }
 
 impl EventBusMessage for StorageRequestRevoked {}
 
 #[derive(Clone, Debug, Default)]
 pub struct BlockchainServiceEventBusProvider {
-    challenge_request_event_bus: EventBus<ChallengeRequest>,
+    new_challenge_event_bus: EventBus<NewChallenge>,
     new_storage_request_event_bus: EventBus<NewStorageRequest>,
     accepted_bsp_volunteer_event_bus: EventBus<AcceptedBspVolunteer>,
     storage_request_revoked_event_bus: EventBus<StorageRequestRevoked>,
@@ -63,7 +91,7 @@ pub struct BlockchainServiceEventBusProvider {
 impl BlockchainServiceEventBusProvider {
     pub fn new() -> Self {
         Self {
-            challenge_request_event_bus: EventBus::new(),
+            new_challenge_event_bus: EventBus::new(),
             new_storage_request_event_bus: EventBus::new(),
             accepted_bsp_volunteer_event_bus: EventBus::new(),
             storage_request_revoked_event_bus: EventBus::new(),
@@ -71,9 +99,9 @@ impl BlockchainServiceEventBusProvider {
     }
 }
 
-impl ProvidesEventBus<ChallengeRequest> for BlockchainServiceEventBusProvider {
-    fn event_bus(&self) -> &EventBus<ChallengeRequest> {
-        &self.challenge_request_event_bus
+impl ProvidesEventBus<NewChallenge> for BlockchainServiceEventBusProvider {
+    fn event_bus(&self) -> &EventBus<NewChallenge> {
+        &self.new_challenge_event_bus
     }
 }
 
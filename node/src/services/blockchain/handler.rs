@@ -37,14 +37,18 @@ use sp_core::{Blake2Hasher, Hasher, H256};
 use sp_keystore::{Keystore, KeystorePtr};
 use sp_runtime::{
     generic::{self, SignedPayload},
+    traits::Header as HeaderT,
     SaturatedConversion,
 };
 use storage_hub_infra::actor::{Actor, ActorEventLoop};
-use storage_hub_runtime::{RuntimeEvent, SignedExtra, UncheckedExtrinsic};
+use storage_hub_runtime::{AccountId, Runtime, RuntimeEvent, SignedExtra, UncheckedExtrinsic};
 
 use crate::{
     service::ParachainClient,
-    services::blockchain::{events::NewStorageRequest, types::EventsVec},
+    services::blockchain::{
+        events::{AcceptedBspVolunteer, NewChallenge, NewStorageRequest, StorageRequestRevoked},
+        types::{BackupStorageProviderId, EventsVec, FileLocation, ThresholdType},
+    },
 };
 
 use super::{
@@ -103,7 +107,11 @@ impl Actor for BlockchainService {
                     match self.send_extrinsic(call).await {
                         Ok(output) => {
                             debug!(target: LOG_TARGET, "Extrinsic sent successfully: {:?}", output);
-                            match callback.send(Ok((output.receiver, output.hash))) {
+                            match callback.send(Ok((
+                                output.receiver,
+                                output.hash,
+                                output.subscription_id,
+                            ))) {
                                 Ok(_) => {
                                     trace!(target: LOG_TARGET, "Receiver sent successfully");
                                 }
@@ -186,6 +194,45 @@ impl Actor for BlockchainService {
                         }
                     }
                 },
+                BlockchainServiceCommand::GetStorageRequestThreshold { location, callback } => {
+                    let threshold = self.get_storage_request_threshold(&location);
+                    if let Err(e) = &threshold {
+                        warn!(target: LOG_TARGET, "Failed to get storage request threshold: {:?}", e);
+                    }
+                    match callback.send(threshold) {
+                        Ok(_) => {
+                            trace!(target: LOG_TARGET, "Storage request threshold sent successfully");
+                        }
+                        Err(e) => {
+                            error!(target: LOG_TARGET, "Failed to send storage request threshold: {:?}", e);
+                        }
+                    }
+                }
+                BlockchainServiceCommand::GetBspId { callback } => {
+                    let bsp_id = self.get_bsp_id();
+                    if let Err(e) = &bsp_id {
+                        warn!(target: LOG_TARGET, "Failed to get BSP ID: {:?}", e);
+                    }
+                    match callback.send(bsp_id) {
+                        Ok(_) => {
+                            trace!(target: LOG_TARGET, "BSP ID sent successfully");
+                        }
+                        Err(e) => {
+                            error!(target: LOG_TARGET, "Failed to send BSP ID: {:?}", e);
+                        }
+                    }
+                }
+                BlockchainServiceCommand::GetAccountId { callback } => {
+                    let account_id: AccountId = self.caller_pub_key().into();
+                    match callback.send(account_id) {
+                        Ok(_) => {
+                            trace!(target: LOG_TARGET, "Account ID sent successfully");
+                        }
+                        Err(e) => {
+                            error!(target: LOG_TARGET, "Failed to send account ID: {:?}", e);
+                        }
+                    }
+                }
             }
         }
     }
@@ -251,6 +298,8 @@ pub struct RpcExtrinsicOutput {
     pub hash: H256,
     /// The output string of the transaction if any.
     pub result: String,
+    /// ID of the subscription watching the extrinsic, needed to unwatch it.
+    pub subscription_id: Number,
     /// An async receiver if data will be returned via a callback.
     pub receiver: tokio::sync::mpsc::Receiver<String>,
 }
@@ -259,8 +308,8 @@ impl std::fmt::Debug for RpcExtrinsicOutput {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(
             f,
-            "RpcExtrinsicOutput {{ hash: {:?}, result: {:?}, receiver }}",
-            self.hash, self.result
+            "RpcExtrinsicOutput {{ hash: {:?}, result: {:?}, subscription_id: {:?}, receiver }}",
+            self.hash, self.result, self.subscription_id
         )
     }
 }
@@ -290,6 +339,9 @@ impl BlockchainService {
     {
         debug!(target: LOG_TARGET, "Import notification: {}", notification.hash);
 
+        let block_number: storage_hub_runtime::BlockNumber =
+            (*notification.header.number()).saturated_into();
+
         // Get events from storage.
         match self.get_events_storage_element(notification.hash) {
             Ok(block_events) => {
@@ -304,6 +356,7 @@ impl BlockchainService {
                                 fingerprint,
                                 size,
                                 peer_ids,
+                                user_multiaddresses,
                             },
                         ) => self.emit(NewStorageRequest {
                             who,
@@ -311,6 +364,43 @@ impl BlockchainService {
                             fingerprint,
                             size,
                             user_peer_ids: peer_ids,
+                            user_multiaddresses,
+                        }),
+                        // Accepted BSP volunteer event coming from pallet-file-system.
+                        RuntimeEvent::FileSystem(
+                            pallet_file_system::Event::AcceptedBspVolunteer {
+                                who,
+                                location,
+                                fingerprint,
+                                multiaddresses,
+                            },
+                        ) => self.emit(AcceptedBspVolunteer {
+                            who,
+                            location,
+                            fingerprint,
+                            multiaddresses,
+                        }),
+                        // Storage request revoked event coming from pallet-file-system.
+                        RuntimeEvent::FileSystem(
+                            pallet_file_system::Event::StorageRequestRevoked {
+                                location,
+                                fingerprint,
+                                bsps,
+                            },
+                        ) => self.emit(StorageRequestRevoked {
+                            location,
+                            fingerprint,
+                            bsps: bsps.into_inner(),
+                        }),
+                        // New challenge event coming from pallet-proofs-dealer.
+                        RuntimeEvent::ProofsDealer(pallet_proofs_dealer::Event::NewChallenge {
+                            who,
+                            key_challenged,
+                        }) => self.emit(NewChallenge {
+                            who,
+                            key_challenged,
+                            challenge_block: block_number,
+                            challenge_block_hash: notification.hash,
                         }),
                         // Ignore all other events.
                         _ => {}
@@ -373,9 +463,16 @@ impl BlockchainService {
             return Err(anyhow::anyhow!("Error in RPC call: {}", error.to_string()));
         }
 
+        // The result of the subscription is the ID of the subscription watching the extrinsic.
+        let subscription_id = json["result"]
+            .as_number()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Missing subscription ID in RPC result: {}", result))?;
+
         Ok(RpcExtrinsicOutput {
             hash: id_hash,
             result,
+            subscription_id,
             receiver: rx,
         })
     }
@@ -436,13 +533,7 @@ impl BlockchainService {
         );
 
         // Getting signer public key.
-        let caller_pub_key = self.keystore.sr25519_public_keys(KEY_TYPE).pop().expect(
-            format!(
-                "There should be at least one sr25519 key in the keystore with key type '{:?}' ; qed",
-                KEY_TYPE
-            )
-            .as_str(),
-        );
+        let caller_pub_key = self.caller_pub_key();
 
         // Sign the payload.
         let signature = raw_payload
@@ -545,6 +636,80 @@ impl BlockchainService {
         Ok(result)
     }
 
+    /// Get the public key this node signs extrinsics with.
+    fn caller_pub_key(&self) -> sp_core::sr25519::Public {
+        self.keystore.sr25519_public_keys(KEY_TYPE).pop().expect(
+            format!(
+                "There should be at least one sr25519 key in the keystore with key type '{:?}' ; qed",
+                KEY_TYPE
+            )
+            .as_str(),
+        )
+    }
+
+    /// Read and decode a storage element at the best block.
+    ///
+    /// Returns `None` if there is no value stored under `key`.
+    fn get_best_block_storage_element<T: Decode>(&self, key: Vec<u8>) -> Result<Option<T>> {
+        let best_hash = self.client.info().best_hash;
+        let raw_storage_opt = self.client.storage(best_hash, &StorageKey(key))?;
+
+        raw_storage_opt
+            .map(|raw_storage| T::decode(&mut raw_storage.0.as_slice()))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Failed to decode storage element: {:?}", e))
+    }
+
+    /// Get the current global threshold BSPs need to be under to volunteer for a storage request.
+    fn get_bsps_assignment_threshold(&self) -> Result<ThresholdType> {
+        let key = pallet_file_system::BspsAssignmentThreshold::<Runtime>::hashed_key().to_vec();
+
+        // `BspsAssignmentThreshold` is a `ValueQuery`, so an empty storage means the default value.
+        Ok(self
+            .get_best_block_storage_element(key)?
+            .unwrap_or_default())
+    }
+
+    /// Get the threshold BSPs need to be under to volunteer for the storage request at `location`, if any.
+    ///
+    /// This is computed in the same way as when volunteering on-chain, assuming the volunteering is included
+    /// in the block after the best one.
+    fn get_storage_request_threshold(
+        &self,
+        location: &FileLocation,
+    ) -> Result<Option<ThresholdType>> {
+        let key = pallet_file_system::StorageRequests::<Runtime>::hashed_key_for(location);
+        let Some(storage_request) = self
+            .get_best_block_storage_element::<pallet_file_system::types::StorageRequestMetadata<Runtime>>(
+                key,
+            )?
+        else {
+            return Ok(None);
+        };
+
+        let base_threshold = self.get_bsps_assignment_threshold()?;
+        let volunteer_block = self.client.info().best_number.saturating_add(1);
+
+        pallet_file_system::Pallet::<Runtime>::compute_request_threshold(
+            base_threshold,
+            storage_request.requested_at,
+            volunteer_block,
+        )
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("Failed to compute storage request threshold: {:?}", e))
+    }
+
+    /// Get the BSP ID of the account this node signs extrinsics with, if it is registered as a BSP.
+    fn get_bsp_id(&self) -> Result<Option<BackupStorageProviderId>> {
+        let account: AccountId = self.caller_pub_key().into();
+        let key =
+            pallet_storage_providers::AccountIdToBackupStorageProviderId::<Runtime>::hashed_key_for(
+                &account,
+            );
+
+        self.get_best_block_storage_element(key)
+    }
+
     /// Get the events storage element in a block.
     fn get_events_storage_element(&self, block_hash: H256) -> Result<EventsVec> {
         // Get the events storage.
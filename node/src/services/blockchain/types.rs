@@ -16,6 +16,17 @@ pub type EventsVec = Vec<
     >,
 >;
 
+/// Type alias for the threshold BSPs are compared against when volunteering for a storage request.
+pub type ThresholdType =
+    <storage_hub_runtime::Runtime as pallet_file_system::Config>::ThresholdType;
+
+/// Type alias for the location of a file in a storage request.
+pub type FileLocation = pallet_file_system::types::FileLocation<storage_hub_runtime::Runtime>;
+
+/// Type alias for the ID of a Backup Storage Provider.
+pub type BackupStorageProviderId =
+    pallet_storage_providers::types::BackupStorageProviderId<storage_hub_runtime::Runtime>;
+
 /// Extrinsic struct.
 ///
 /// This struct represents an extrinsic in the blockchain.
@@ -46,6 +57,11 @@ pub enum ExtrinsicResult {
     Failure {
         /// Dispatch error.
         dispatch_error: DispatchError,
+        /// Error of the pallet that raised the dispatch error, if any, decoded with the runtime's error types.
+        ///
+        /// Its debug representation is the name of the pallet and of the error, e.g.
+        /// `FileSystem(StorageRequestNotFound)`, and tasks can match on it to handle specific errors.
+        decoded_error: Option<storage_hub_runtime::RuntimeError>,
         /// Dispatch info.
         dispatch_info: DispatchInfo,
     },
@@ -56,3 +72,18 @@ pub type RpcJsonResponse = String;
 
 /// Type alias for the extrinsic hash.
 pub type ExtrinsicHash = H256;
+
+/// Errors that can occur while waiting for an extrinsic to be included in a block.
+#[derive(Debug, thiserror::Error)]
+pub enum WatchExtrinsicError {
+    /// The extrinsic was not included in a block within the given timeout. It might still be
+    /// included later, so callers can retry or check the chain before resubmitting.
+    #[error("extrinsic {0:?} was not included in a block within the timeout")]
+    TimedOut(ExtrinsicHash),
+    /// The extrinsic stopped being watched before being included in a block, e.g. because it was
+    /// dropped from the transaction pool.
+    #[error("extrinsic {0:?} stopped being watched before being included in a block")]
+    WatcherClosed(ExtrinsicHash),
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
@@ -0,0 +1,675 @@
+use std::{fmt, future::Future, ops::Range, time::Duration};
+
+use anyhow::Result;
+use file_manager::traits::FileStorage;
+use futures::{
+    future::{self, Either, Shared},
+    stream::BoxStream,
+};
+use libp2p_identity::PeerId;
+use prost::Message;
+use sc_network::{
+    multiaddr::Protocol,
+    request_responses::{OutboundFailure, RequestFailure},
+    Multiaddr, ProtocolName,
+};
+use sc_tracing::tracing::debug;
+use sp_trie::CompactProof;
+use storage_hub_infra::{
+    actor::ActorHandle,
+    types::{Chunk, ChunkId, Key},
+};
+
+use super::{schema, FileTransferService};
+
+const LOG_TARGET: &str = "file-transfer-service";
+
+/// Stream of the responses to the download requests of the chunks of a file, in the order of the chunks.
+pub type ChunkDownloadStream = BoxStream<
+    'static,
+    (
+        ChunkId,
+        Result<schema::v1::provider::RemoteDownloadDataResponse, RequestError>,
+    ),
+>;
+
+/// Receiver of the raw response to a request sent to a peer.
+pub(super) type ResponseReceiver =
+    futures::channel::oneshot::Receiver<Result<(Vec<u8>, ProtocolName), RequestFailure>>;
+
+/// Signal shared by all the requests in flight for a file with a peer, resolving once their transfer is
+/// aborted with [`FileTransferServiceCommand::AbortTransfer`].
+pub(super) type AbortSignal = Shared<futures::channel::oneshot::Receiver<()>>;
+
+/// Receiver of the raw response to a request sent to a peer, which can be aborted before the peer answers.
+pub struct TransferReceiver {
+    response_receiver: ResponseReceiver,
+    abort_signal: AbortSignal,
+}
+
+impl TransferReceiver {
+    pub(super) fn new(response_receiver: ResponseReceiver, abort_signal: AbortSignal) -> Self {
+        Self {
+            response_receiver,
+            abort_signal,
+        }
+    }
+}
+
+impl fmt::Debug for TransferReceiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransferReceiver").finish_non_exhaustive()
+    }
+}
+
+/// Commands that can be sent to the FileTransferService actor.
+#[derive(Debug)]
+pub enum FileTransferServiceCommand {
+    UploadRequest {
+        peer_id: PeerId,
+        file_key: Key,
+        location: String,
+        chunk_id: ChunkId,
+        chunk: Chunk,
+        proof: CompactProof,
+        callback: tokio::sync::oneshot::Sender<TransferReceiver>,
+    },
+    DownloadRequest {
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_id: ChunkId,
+        callback: tokio::sync::oneshot::Sender<TransferReceiver>,
+    },
+    ChunkAvailabilityRequest {
+        peer_id: PeerId,
+        file_key: Key,
+        callback: tokio::sync::oneshot::Sender<TransferReceiver>,
+    },
+    DownloadFileRequest {
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_range: Range<ChunkId>,
+        callback: tokio::sync::oneshot::Sender<ChunkDownloadStream>,
+    },
+    RegisterNewFile {
+        peer_id: PeerId,
+        file_key: Key,
+        callback: tokio::sync::oneshot::Sender<Result<()>>,
+    },
+    UnregisterFile {
+        file_key: Key,
+        callback: tokio::sync::oneshot::Sender<Result<()>>,
+    },
+    AddKnownAddress {
+        peer_id: PeerId,
+        multiaddress: Multiaddr,
+    },
+    AbortTransfer {
+        peer_id: PeerId,
+        file_key: Key,
+    },
+}
+
+/// Error when connecting to a Storage Provider from its multiaddresses.
+#[derive(Debug, PartialEq, Eq)]
+pub enum ConnectProviderError {
+    /// No multiaddresses were given.
+    NoMultiAddresses,
+    /// A multiaddress does not have a `/p2p/` component, so the peer it belongs to is unknown.
+    MissingPeerId(Multiaddr),
+    /// The `/p2p/` component of a multiaddress is not a valid peer ID.
+    InvalidPeerId(Multiaddr),
+    /// The multiaddresses belong to different peers.
+    MismatchedPeerIds(PeerId, PeerId),
+}
+
+/// Error of a request sent to a peer.
+#[derive(Debug, thiserror::Error)]
+pub enum RequestError {
+    /// The request could not be sent or the peer did not answer it.
+    #[error("request to peer failed: {0}")]
+    RequestFailure(#[source] RequestFailure),
+    /// The response of the peer could not be decoded.
+    #[error("failed to decode the response of the peer: {0}")]
+    DecodeError(#[source] prost::DecodeError),
+    /// The peer answered with a response that does not match the request.
+    #[error("the peer answered with a response that does not match the request")]
+    UnexpectedResponse,
+    /// The transfer was aborted before the peer answered.
+    #[error("the transfer was aborted")]
+    Aborted,
+}
+
+impl RequestError {
+    /// Whether the request may succeed if sent again.
+    ///
+    /// Only failures to reach the peer over the network are transient. A peer that does not support the
+    /// protocol, refuses the request or answers with an invalid response is not expected to behave differently
+    /// on a retry.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            RequestError::RequestFailure(RequestFailure::Network(
+                OutboundFailure::UnsupportedProtocols,
+            )) => false,
+            RequestError::RequestFailure(RequestFailure::Network(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Interface for interacting with the FileTransferService actor.
+pub trait FileTransferServiceInterface {
+    /// Upload a chunk of a file, along with the proof of the chunk in the file trie, to a peer.
+    async fn upload_request(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        location: String,
+        chunk_id: ChunkId,
+        chunk: Chunk,
+        proof: CompactProof,
+    ) -> Result<schema::v1::provider::RemoteUploadDataResponse, RequestError>;
+
+    /// Same as [`Self::upload_request`], retrying up to `max_attempts` times in total when the request
+    /// fails because of the network, doubling the wait between attempts starting from `base_backoff`.
+    ///
+    /// Errors that would not be solved by retrying, like an undecodable response, are returned right away.
+    async fn upload_request_with_retry(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        location: String,
+        chunk_id: ChunkId,
+        chunk: Chunk,
+        proof: CompactProof,
+        max_attempts: u32,
+        base_backoff: Duration,
+    ) -> Result<schema::v1::provider::RemoteUploadDataResponse, RequestError>;
+
+    /// Download a chunk of a file from a peer, along with the proof of the chunk in the file trie.
+    async fn download_request(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_id: ChunkId,
+    ) -> Result<schema::v1::provider::RemoteDownloadDataResponse, RequestError>;
+
+    /// Same as [`Self::download_request`], retrying like [`Self::upload_request_with_retry`].
+    async fn download_request_with_retry(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_id: ChunkId,
+        max_attempts: u32,
+        base_backoff: Duration,
+    ) -> Result<schema::v1::provider::RemoteDownloadDataResponse, RequestError>;
+
+    /// Get which chunks of a file a peer holds, indexed by chunk id, as decoded by [`decode_chunk_bitmap`].
+    ///
+    /// This allows requesting only the chunks held by the peer, e.g. when it is still receiving the file.
+    async fn chunk_availability(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+    ) -> Result<Vec<bool>, RequestError>;
+
+    /// Download a range of chunks of a file from a peer, along with their proofs.
+    ///
+    /// The requests of the chunks are pipelined, with a cap on the number of requests in flight.
+    /// The failure of a chunk is yielded in the stream without aborting the download of the others.
+    async fn download_file_request(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_range: Range<ChunkId>,
+    ) -> ChunkDownloadStream;
+
+    /// Allow a peer to upload the chunks of a file.
+    async fn register_new_file(&self, peer_id: PeerId, file_key: Key) -> Result<()>;
+
+    /// Stop accepting uploads of the chunks of a file, from any of the peers it was registered for.
+    async fn unregister_file(&self, file_key: Key) -> Result<()>;
+
+    /// Register the multiaddresses of a Storage Provider in the network, as advertised on-chain, and
+    /// return its peer ID to send it requests.
+    ///
+    /// All multiaddresses must end with the same `/p2p/<peer id>` component.
+    async fn connect_provider(
+        &self,
+        multiaddresses: Vec<Multiaddr>,
+    ) -> Result<PeerId, ConnectProviderError>;
+
+    /// Abort all the requests in flight for a file with a peer, e.g. because its storage request was revoked.
+    ///
+    /// The aborted requests fail right away with [`RequestError::Aborted`], without waiting for the peer
+    /// to answer. Requests sent afterwards are not affected.
+    async fn abort_transfer(&self, peer_id: PeerId, file_key: Key);
+}
+
+/// Implement the FileTransferServiceInterface for the ActorHandle<FileTransferService>.
+impl<FS: FileStorage + Send + Sync> FileTransferServiceInterface
+    for ActorHandle<FileTransferService<FS>>
+{
+    async fn upload_request(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        location: String,
+        chunk_id: ChunkId,
+        chunk: Chunk,
+        proof: CompactProof,
+    ) -> Result<schema::v1::provider::RemoteUploadDataResponse, RequestError> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::UploadRequest {
+            peer_id,
+            file_key,
+            location,
+            chunk_id,
+            chunk,
+            proof,
+            callback,
+        };
+        self.send(message).await;
+        let transfer_receiver = rx.await.expect("Failed to receive response from FileTransferService. Probably means FileTransferService has crashed.");
+
+        match decode_response(transfer_receiver).await? {
+            schema::v1::provider::response::Response::RemoteUploadDataResponse(response) => {
+                Ok(response)
+            }
+            _ => Err(RequestError::UnexpectedResponse),
+        }
+    }
+
+    async fn upload_request_with_retry(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        location: String,
+        chunk_id: ChunkId,
+        chunk: Chunk,
+        proof: CompactProof,
+        max_attempts: u32,
+        base_backoff: Duration,
+    ) -> Result<schema::v1::provider::RemoteUploadDataResponse, RequestError> {
+        retry_with_backoff(max_attempts, base_backoff, || {
+            self.upload_request(
+                peer_id,
+                file_key,
+                location.clone(),
+                chunk_id,
+                chunk.clone(),
+                proof.clone(),
+            )
+        })
+        .await
+    }
+
+    async fn download_request(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_id: ChunkId,
+    ) -> Result<schema::v1::provider::RemoteDownloadDataResponse, RequestError> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::DownloadRequest {
+            peer_id,
+            file_key,
+            chunk_id,
+            callback,
+        };
+        self.send(message).await;
+        let transfer_receiver = rx.await.expect("Failed to receive response from FileTransferService. Probably means FileTransferService has crashed.");
+
+        decode_download_response(transfer_receiver).await
+    }
+
+    async fn download_request_with_retry(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_id: ChunkId,
+        max_attempts: u32,
+        base_backoff: Duration,
+    ) -> Result<schema::v1::provider::RemoteDownloadDataResponse, RequestError> {
+        retry_with_backoff(max_attempts, base_backoff, || {
+            self.download_request(peer_id, file_key, chunk_id)
+        })
+        .await
+    }
+
+    async fn chunk_availability(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+    ) -> Result<Vec<bool>, RequestError> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::ChunkAvailabilityRequest {
+            peer_id,
+            file_key,
+            callback,
+        };
+        self.send(message).await;
+        let transfer_receiver = rx.await.expect("Failed to receive response from FileTransferService. Probably means FileTransferService has crashed.");
+
+        match decode_response(transfer_receiver).await? {
+            schema::v1::provider::response::Response::ChunkAvailabilityResponse(response) => {
+                Ok(decode_chunk_bitmap(&response.bitmap))
+            }
+            _ => Err(RequestError::UnexpectedResponse),
+        }
+    }
+
+    async fn download_file_request(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_range: Range<ChunkId>,
+    ) -> ChunkDownloadStream {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::DownloadFileRequest {
+            peer_id,
+            file_key,
+            chunk_range,
+            callback,
+        };
+        self.send(message).await;
+        rx.await.expect("Failed to receive response from FileTransferService. Probably means FileTransferService has crashed.")
+    }
+
+    async fn register_new_file(&self, peer_id: PeerId, file_key: Key) -> Result<()> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::RegisterNewFile {
+            peer_id,
+            file_key,
+            callback,
+        };
+        self.send(message).await;
+        rx.await.expect("Failed to receive response from FileTransferService. Probably means FileTransferService has crashed.")
+    }
+
+    async fn unregister_file(&self, file_key: Key) -> Result<()> {
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::UnregisterFile { file_key, callback };
+        self.send(message).await;
+        rx.await.expect("Failed to receive response from FileTransferService. Probably means FileTransferService has crashed.")
+    }
+
+    async fn connect_provider(
+        &self,
+        multiaddresses: Vec<Multiaddr>,
+    ) -> Result<PeerId, ConnectProviderError> {
+        let peer_id = peer_id_from_multiaddresses(&multiaddresses)?;
+
+        for multiaddress in multiaddresses {
+            // Build command to send to file transfer service.
+            let message = FileTransferServiceCommand::AddKnownAddress {
+                peer_id,
+                multiaddress,
+            };
+            self.send(message).await;
+        }
+
+        Ok(peer_id)
+    }
+
+    async fn abort_transfer(&self, peer_id: PeerId, file_key: Key) {
+        // Build command to send to file transfer service.
+        let message = FileTransferServiceCommand::AbortTransfer { peer_id, file_key };
+        self.send(message).await;
+    }
+}
+
+/// Get the peer ID shared by all the multiaddresses, from their `/p2p/` component.
+fn peer_id_from_multiaddresses(
+    multiaddresses: &[Multiaddr],
+) -> Result<PeerId, ConnectProviderError> {
+    let mut peer_id = None;
+
+    for multiaddress in multiaddresses {
+        let multihash = multiaddress
+            .iter()
+            .find_map(|protocol| match protocol {
+                Protocol::P2p(multihash) => Some(multihash),
+                _ => None,
+            })
+            .ok_or_else(|| ConnectProviderError::MissingPeerId(multiaddress.clone()))?;
+        let address_peer_id = PeerId::from_multihash(multihash)
+            .map_err(|_| ConnectProviderError::InvalidPeerId(multiaddress.clone()))?;
+
+        match peer_id {
+            Some(peer_id) if peer_id != address_peer_id => {
+                return Err(ConnectProviderError::MismatchedPeerIds(
+                    peer_id,
+                    address_peer_id,
+                ))
+            }
+            _ => peer_id = Some(address_peer_id),
+        }
+    }
+
+    peer_id.ok_or(ConnectProviderError::NoMultiAddresses)
+}
+
+/// Wait for the raw response of a peer and decode it, unless the transfer is aborted first.
+async fn decode_response(
+    transfer_receiver: TransferReceiver,
+) -> Result<schema::v1::provider::response::Response, RequestError> {
+    let response = match future::select(
+        transfer_receiver.response_receiver,
+        transfer_receiver.abort_signal,
+    )
+    .await
+    {
+        Either::Left((response, _)) => response,
+        Either::Right(_) => return Err(RequestError::Aborted),
+    };
+
+    let (response, _) = response
+        .map_err(|_| RequestError::RequestFailure(RequestFailure::Obsolete))?
+        .map_err(RequestError::RequestFailure)?;
+
+    schema::v1::provider::Response::decode(&response[..])
+        .map_err(RequestError::DecodeError)?
+        .response
+        .ok_or(RequestError::UnexpectedResponse)
+}
+
+/// Wait for the raw response of a peer to a download request and decode it.
+pub(super) async fn decode_download_response(
+    transfer_receiver: TransferReceiver,
+) -> Result<schema::v1::provider::RemoteDownloadDataResponse, RequestError> {
+    match decode_response(transfer_receiver).await? {
+        schema::v1::provider::response::Response::RemoteDownloadDataResponse(response) => {
+            Ok(response)
+        }
+        _ => Err(RequestError::UnexpectedResponse),
+    }
+}
+
+/// Decode the bitmap of a [`schema::v1::provider::ChunkAvailabilityResponse`] into whether each chunk is
+/// held, indexed by chunk id.
+///
+/// The bitmap is padded to a whole number of bytes, so chunk ids past the end of the file are not held.
+pub fn decode_chunk_bitmap(bitmap: &[u8]) -> Vec<bool> {
+    bitmap
+        .iter()
+        .flat_map(|byte| (0..8).map(move |bit| byte & (1 << bit) != 0))
+        .collect()
+}
+
+/// Send a request up to `max_attempts` times, as long as it fails with a [retryable](RequestError::is_retryable) error.
+///
+/// The wait between two attempts starts at `base_backoff` and doubles after every attempt.
+async fn retry_with_backoff<T, F, Fut>(
+    max_attempts: u32,
+    base_backoff: Duration,
+    mut request: F,
+) -> Result<T, RequestError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, RequestError>>,
+{
+    let mut backoff = base_backoff;
+    let mut attempt = 1;
+
+    loop {
+        match request().await {
+            Err(e) if e.is_retryable() && attempt < max_attempts => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Request attempt {} of {} failed: {}. Retrying in {:?}.",
+                    attempt,
+                    max_attempts,
+                    e,
+                    backoff
+                );
+
+                futures_timer::Delay::new(backoff).await;
+                backoff = backoff.saturating_mul(2);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn request_is_retried_on_network_failures() {
+        let attempts = Cell::new(0);
+
+        let result =
+            futures::executor::block_on(retry_with_backoff(5, Duration::from_millis(1), || {
+                attempts.set(attempts.get() + 1);
+                let attempt = attempts.get();
+                async move {
+                    if attempt <= 2 {
+                        Err(RequestError::RequestFailure(RequestFailure::Network(
+                            OutboundFailure::Timeout,
+                        )))
+                    } else {
+                        Ok(attempt)
+                    }
+                }
+            }));
+
+        // Fails twice, then succeeds on the third attempt.
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn request_is_retried_up_to_max_attempts() {
+        let attempts = Cell::new(0);
+
+        let result: Result<(), _> =
+            futures::executor::block_on(retry_with_backoff(3, Duration::from_millis(1), || {
+                attempts.set(attempts.get() + 1);
+                async {
+                    Err(RequestError::RequestFailure(RequestFailure::Network(
+                        OutboundFailure::ConnectionClosed,
+                    )))
+                }
+            }));
+
+        assert!(matches!(
+            result,
+            Err(RequestError::RequestFailure(RequestFailure::Network(_)))
+        ));
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn request_is_not_retried_on_unexpected_response() {
+        let attempts = Cell::new(0);
+
+        let result: Result<(), _> =
+            futures::executor::block_on(retry_with_backoff(5, Duration::from_millis(1), || {
+                attempts.set(attempts.get() + 1);
+                async { Err(RequestError::UnexpectedResponse) }
+            }));
+
+        assert!(matches!(result, Err(RequestError::UnexpectedResponse)));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    fn peer_id_is_parsed_from_multiaddresses() {
+        let peer_id = PeerId::random();
+        let multiaddresses: Vec<Multiaddr> = [
+            format!("/ip4/127.0.0.1/tcp/30333/p2p/{}", peer_id),
+            format!("/dns/provider.example/tcp/30333/p2p/{}", peer_id),
+        ]
+        .iter()
+        .map(|multiaddress| multiaddress.parse().unwrap())
+        .collect();
+
+        assert_eq!(peer_id_from_multiaddresses(&multiaddresses), Ok(peer_id));
+    }
+
+    #[test]
+    fn multiaddresses_without_peer_id_are_rejected() {
+        let multiaddress: Multiaddr = "/ip4/127.0.0.1/tcp/30333".parse().unwrap();
+
+        assert_eq!(
+            peer_id_from_multiaddresses(&[multiaddress.clone()]),
+            Err(ConnectProviderError::MissingPeerId(multiaddress))
+        );
+        assert_eq!(
+            peer_id_from_multiaddresses(&[]),
+            Err(ConnectProviderError::NoMultiAddresses)
+        );
+    }
+
+    #[test]
+    fn multiaddresses_of_different_peers_are_rejected() {
+        let (alice, bob) = (PeerId::random(), PeerId::random());
+        let multiaddresses: Vec<Multiaddr> = [alice, bob]
+            .iter()
+            .map(|peer_id| {
+                format!("/ip4/127.0.0.1/tcp/30333/p2p/{}", peer_id)
+                    .parse()
+                    .unwrap()
+            })
+            .collect();
+
+        assert_eq!(
+            peer_id_from_multiaddresses(&multiaddresses),
+            Err(ConnectProviderError::MismatchedPeerIds(alice, bob))
+        );
+    }
+
+    #[test]
+    fn only_transient_network_failures_are_retryable() {
+        assert!(
+            RequestError::RequestFailure(RequestFailure::Network(OutboundFailure::Timeout))
+                .is_retryable()
+        );
+        assert!(RequestError::RequestFailure(RequestFailure::Network(
+            OutboundFailure::ConnectionClosed
+        ))
+        .is_retryable());
+        assert!(!RequestError::RequestFailure(RequestFailure::Network(
+            OutboundFailure::UnsupportedProtocols
+        ))
+        .is_retryable());
+        assert!(!RequestError::RequestFailure(RequestFailure::Refused).is_retryable());
+        assert!(!RequestError::UnexpectedResponse.is_retryable());
+
+        let decode_error = schema::v1::provider::Response::decode(&[0xff][..]).unwrap_err();
+        let error = RequestError::DecodeError(decode_error.clone());
+        assert!(!error.is_retryable());
+        assert!(error.to_string().contains(&decode_error.to_string()));
+    }
+}
@@ -1,14 +1,24 @@
-use storage_hub_infra::event_bus::{EventBus, EventBusMessage, ProvidesEventBus};
+use libp2p_identity::PeerId;
+use storage_hub_infra::{
+    event_bus::{EventBus, EventBusMessage, ProvidesEventBus},
+    types::Key,
+};
 
 #[derive(Clone, Debug, Default)]
 pub struct FileTransferServiceEventBusProvider {
     remote_upload_request_event_bus: EventBus<RemoteUploadRequest>,
+    remote_upload_complete_event_bus: EventBus<RemoteUploadComplete>,
+    backpressure_active_event_bus: EventBus<BackpressureActive>,
+    backpressure_cleared_event_bus: EventBus<BackpressureCleared>,
 }
 
 impl FileTransferServiceEventBusProvider {
     pub fn new() -> Self {
         Self {
             remote_upload_request_event_bus: EventBus::new(),
+            remote_upload_complete_event_bus: EventBus::new(),
+            backpressure_active_event_bus: EventBus::new(),
+            backpressure_cleared_event_bus: EventBus::new(),
         }
     }
 }
@@ -19,9 +29,71 @@ impl ProvidesEventBus<RemoteUploadRequest> for FileTransferServiceEventBusProvid
     }
 }
 
+/// Remote upload request event.
+///
+/// This event is emitted once a chunk uploaded by a remote peer has been verified and written in
+/// the file storage.
 #[derive(Debug, Clone)]
 pub struct RemoteUploadRequest {
     pub location: String,
+    /// Key of the file the stored chunk belongs to.
+    pub file_key: Key,
 }
 
 impl EventBusMessage for RemoteUploadRequest {}
+
+impl ProvidesEventBus<RemoteUploadComplete> for FileTransferServiceEventBusProvider {
+    fn event_bus(&self) -> &EventBus<RemoteUploadComplete> {
+        &self.remote_upload_complete_event_bus
+    }
+}
+
+/// Remote upload complete event.
+///
+/// This event is emitted when a chunk uploaded by a remote peer completes the file, that is, all
+/// the chunks of the file are stored and they match its fingerprint.
+#[derive(Debug, Clone)]
+pub struct RemoteUploadComplete {
+    /// Peer that uploaded the last chunk of the file.
+    pub peer: PeerId,
+    /// Key of the completed file.
+    pub file_key: Key,
+}
+
+impl EventBusMessage for RemoteUploadComplete {}
+
+impl ProvidesEventBus<BackpressureActive> for FileTransferServiceEventBusProvider {
+    fn event_bus(&self) -> &EventBus<BackpressureActive> {
+        &self.backpressure_active_event_bus
+    }
+}
+
+impl ProvidesEventBus<BackpressureCleared> for FileTransferServiceEventBusProvider {
+    fn event_bus(&self) -> &EventBus<BackpressureCleared> {
+        &self.backpressure_cleared_event_bus
+    }
+}
+
+/// Backpressure active event.
+///
+/// This event is emitted when the number of queued incoming requests reaches the high-water mark,
+/// signaling that the network layer should slow down incoming requests.
+#[derive(Debug, Clone)]
+pub struct BackpressureActive {
+    /// Number of incoming requests waiting to be handled.
+    pub queued_requests: usize,
+}
+
+impl EventBusMessage for BackpressureActive {}
+
+/// Backpressure cleared event.
+///
+/// This event is emitted when the number of queued incoming requests drops to the low-water mark
+/// after backpressure was activated, signaling that the network layer can resume normal flow.
+#[derive(Debug, Clone)]
+pub struct BackpressureCleared {
+    /// Number of incoming requests waiting to be handled.
+    pub queued_requests: usize,
+}
+
+impl EventBusMessage for BackpressureCleared {}
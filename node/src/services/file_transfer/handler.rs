@@ -22,47 +22,327 @@
 //! `crate::request_responses::RequestResponsesBehaviour` with
 //! [`LightClientRequestHandler`](handler::LightClientRequestHandler).
 
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    sync::Arc,
+    time::Instant,
+};
+
 use anyhow::Result;
+use codec::{Decode, Encode};
+use file_manager::{
+    traits::{FileStorage, FileStorageError, FileStorageWriteStatus},
+    verify::verify_file_proof,
+};
+use futures::future::{Fuse, WeakShared};
 use futures::prelude::*;
-use futures::stream::select;
+use futures::stream::{self, FuturesUnordered};
 use libp2p_identity::PeerId;
 use prost::Message;
+use reference_trie::RefHasher;
 use sc_network::{
-    request_responses::{IncomingRequest, OutgoingResponse, ProtocolConfig},
-    ReputationChange,
+    request_responses::{IfDisconnected, IncomingRequest, OutgoingResponse},
+    Multiaddr, NetworkPeers, NetworkRequest, ProtocolName, ReputationChange,
 };
 use sc_tracing::tracing::{debug, info, trace, warn};
-use sp_core::hexdisplay::HexDisplay;
-use storage_hub_infra::actor::{Actor, ActorEventLoop};
+use sp_core::{hexdisplay::HexDisplay, H256};
+use sp_trie::{CompactProof, LayoutV1};
+use storage_hub_infra::{
+    actor::{Actor, ActorEventLoop},
+    event_bus::ProvidesEventBus,
+    types::{Chunk, ChunkId, FileProof, Key, Leaf},
+};
+use substrate_prometheus_endpoint::Registry;
+use tokio::sync::RwLock;
 
-use crate::services::file_transfer::events::RemoteUploadRequest;
+use crate::services::file_transfer::events::{
+    BackpressureActive, BackpressureCleared, RemoteUploadComplete, RemoteUploadRequest,
+};
 
-use super::{events::FileTransferServiceEventBusProvider, schema};
+use super::{
+    commands::{
+        decode_download_response, AbortSignal, ChunkDownloadStream, FileTransferServiceCommand,
+        ResponseReceiver, TransferReceiver,
+    },
+    events::FileTransferServiceEventBusProvider,
+    metrics::{
+        Metrics, REQUEST_KIND_CHUNK_AVAILABILITY, REQUEST_KIND_DOWNLOAD, REQUEST_KIND_READ,
+        REQUEST_KIND_UNKNOWN, REQUEST_KIND_UPLOAD,
+    },
+    schema,
+};
 
 const LOG_TARGET: &str = "file-transfer-service";
 
 /// Max number of queued requests.
-const MAX_FILE_TRANSFER_REQUESTS_QUEUE: usize = 500;
+pub(super) const MAX_FILE_TRANSFER_REQUESTS_QUEUE: usize = 500;
 
-#[derive(Debug)]
-pub enum FileTransferServiceCommand {}
+/// Number of queued requests at which backpressure is activated.
+const BACKPRESSURE_HIGH_WATER_MARK: usize = MAX_FILE_TRANSFER_REQUESTS_QUEUE * 4 / 5;
 
-#[derive(Debug)]
-pub struct FileTransferService {
+/// Number of queued requests at which backpressure is cleared, once activated.
+const BACKPRESSURE_LOW_WATER_MARK: usize = MAX_FILE_TRANSFER_REQUESTS_QUEUE / 5;
+
+/// Max number of chunk download requests in flight for a single file download.
+const MAX_IN_FLIGHT_CHUNK_DOWNLOADS: usize = 8;
+
+/// Layout of the tries whose root is the fingerprint of a file.
+pub(crate) type FileTrieLayout = LayoutV1<RefHasher>;
+
+/// Network used by the [`FileTransferService`] to send requests to other peers, and to learn the
+/// addresses where they can be reached.
+pub trait FileTransferNetwork: NetworkRequest + Send + Sync {
+    /// Register an address of a peer, for it to be dialed when sending it requests.
+    fn add_known_address(&self, peer_id: PeerId, address: Multiaddr);
+}
+
+impl<N: NetworkRequest + NetworkPeers + Send + Sync> FileTransferNetwork for N {
+    fn add_known_address(&self, peer_id: PeerId, address: Multiaddr) {
+        NetworkPeers::add_known_address(self, peer_id, address)
+    }
+}
+
+pub struct FileTransferService<FS: FileStorage + Send + Sync> {
+    /// Name of the provider requests protocol, used to send requests to other peers.
+    protocol_name: ProtocolName,
     request_receiver: async_channel::Receiver<IncomingRequest>,
+    /// Network used to send requests to other peers.
+    network: Arc<dyn FileTransferNetwork>,
+    event_bus_provider: FileTransferServiceEventBusProvider,
+    /// Tracks whether the service is currently under backpressure.
+    backpressure: BackpressureTracker,
+    /// Storage where the file chunks uploaded by remote peers are written.
+    file_storage: Arc<RwLock<FS>>,
+    /// Max size in bytes of an incoming request payload. Bigger requests are rejected before being decoded.
+    max_request_size: u64,
+    /// Max number of incoming requests handled concurrently.
+    ///
+    /// Past this limit, new requests are left in the inbound queue until a request in flight is answered.
+    max_concurrent_requests: usize,
+    /// Pairs of peer and file key for which uploads are accepted.
+    ///
+    /// A file can be registered for multiple peers, each of them being allowed to upload its chunks.
+    /// It is shared with the requests in flight, which read it as they are handled, so files registered or
+    /// unregistered in the meantime are taken into account.
+    peer_file_allow_list: Arc<RwLock<HashSet<(PeerId, Key)>>>,
+    /// Abort signals of the requests in flight, by peer and file key.
+    ///
+    /// Sending through (or dropping) the sender of a pair aborts all the requests in flight for the file with
+    /// the peer. Only a weak reference to the signal is kept, so that pairs without requests in flight are forgotten.
+    active_transfers: HashMap<
+        (PeerId, Key),
+        (
+            futures::channel::oneshot::Sender<()>,
+            WeakShared<futures::channel::oneshot::Receiver<()>>,
+        ),
+    >,
+    /// Prometheus metrics, if a registry was provided.
+    metrics: Option<Metrics>,
+}
+
+/// State needed to answer incoming requests.
+///
+/// It is cloned out of the [`FileTransferService`] for every incoming request, so that requests are
+/// handled concurrently with each other and with the commands received by the service.
+struct RequestHandler<FS: FileStorage + Send + Sync> {
     event_bus_provider: FileTransferServiceEventBusProvider,
+    file_storage: Arc<RwLock<FS>>,
+    max_request_size: u64,
+    /// Allow list of the service, shared with it.
+    peer_file_allow_list: Arc<RwLock<HashSet<(PeerId, Key)>>>,
+    metrics: Option<Metrics>,
 }
 
-impl Actor for FileTransferService {
+/// Transition of the backpressure state of the [`FileTransferService`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BackpressureTransition {
+    /// The high-water mark was reached.
+    Activated,
+    /// The low-water mark was reached after backpressure was activated.
+    Cleared,
+}
+
+/// Tracks the backpressure state using a high and low water mark of queued requests.
+///
+/// Using two different marks avoids flapping between states when the queue size oscillates
+/// around a single threshold.
+#[derive(Debug)]
+struct BackpressureTracker {
+    high_water_mark: usize,
+    low_water_mark: usize,
+    active: bool,
+}
+
+impl BackpressureTracker {
+    fn new(high_water_mark: usize, low_water_mark: usize) -> Self {
+        Self {
+            high_water_mark,
+            low_water_mark,
+            active: false,
+        }
+    }
+
+    /// Update the state with the current number of queued requests, returning the transition if any.
+    fn update(&mut self, queued_requests: usize) -> Option<BackpressureTransition> {
+        if !self.active && queued_requests >= self.high_water_mark {
+            self.active = true;
+            return Some(BackpressureTransition::Activated);
+        }
+
+        if self.active && queued_requests <= self.low_water_mark {
+            self.active = false;
+            return Some(BackpressureTransition::Cleared);
+        }
+
+        None
+    }
+}
+
+impl<FS: FileStorage + Send + Sync> Actor for FileTransferService<FS> {
     type Message = FileTransferServiceCommand;
-    type EventLoop = FileTransferServiceEventLoop;
+    type EventLoop = FileTransferServiceEventLoop<FS>;
     type EventBusProvider = FileTransferServiceEventBusProvider;
 
     fn handle_message(
         &mut self,
-        _message: Self::Message,
+        message: Self::Message,
     ) -> impl std::future::Future<Output = ()> + Send {
-        async {}
+        async {
+            match message {
+                FileTransferServiceCommand::UploadRequest {
+                    peer_id,
+                    file_key,
+                    location,
+                    chunk_id,
+                    chunk,
+                    proof,
+                    callback,
+                } => {
+                    let request = schema::v1::provider::Request {
+                        request: Some(
+                            schema::v1::provider::request::Request::RemoteUploadDataRequest(
+                                schema::v1::provider::RemoteUploadDataRequest {
+                                    location,
+                                    data: chunk,
+                                    file_key: file_key.as_bytes().to_vec(),
+                                    chunk_id,
+                                    proof: proof.encode(),
+                                },
+                            ),
+                        ),
+                    };
+
+                    let rx = self.start_transfer(peer_id, file_key, request);
+                    if callback.send(rx).is_err() {
+                        warn!(target: LOG_TARGET, "Failed to send upload request receiver");
+                    }
+                }
+                FileTransferServiceCommand::DownloadRequest {
+                    peer_id,
+                    file_key,
+                    chunk_id,
+                    callback,
+                } => {
+                    let request = download_data_request(&file_key, chunk_id);
+
+                    let rx = self.start_transfer(peer_id, file_key, request);
+                    if callback.send(rx).is_err() {
+                        warn!(target: LOG_TARGET, "Failed to send download request receiver");
+                    }
+                }
+                FileTransferServiceCommand::ChunkAvailabilityRequest {
+                    peer_id,
+                    file_key,
+                    callback,
+                } => {
+                    let request = schema::v1::provider::Request {
+                        request: Some(
+                            schema::v1::provider::request::Request::ChunkAvailabilityRequest(
+                                schema::v1::provider::ChunkAvailabilityRequest {
+                                    file_key: file_key.as_bytes().to_vec(),
+                                },
+                            ),
+                        ),
+                    };
+
+                    let rx = self.start_transfer(peer_id, file_key, request);
+                    if callback.send(rx).is_err() {
+                        warn!(target: LOG_TARGET, "Failed to send chunk availability request receiver");
+                    }
+                }
+                FileTransferServiceCommand::DownloadFileRequest {
+                    peer_id,
+                    file_key,
+                    chunk_range,
+                    callback,
+                } => {
+                    let stream = self.download_file_stream(peer_id, file_key, chunk_range);
+                    if callback.send(stream).is_err() {
+                        warn!(target: LOG_TARGET, "Failed to send download file stream");
+                    }
+                }
+                FileTransferServiceCommand::RegisterNewFile {
+                    peer_id,
+                    file_key,
+                    callback,
+                } => {
+                    let result = if self
+                        .peer_file_allow_list
+                        .write()
+                        .await
+                        .insert((peer_id, file_key))
+                    {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!(
+                            "File {:?} is already registered for peer {}.",
+                            file_key,
+                            peer_id
+                        ))
+                    };
+
+                    if callback.send(result).is_err() {
+                        warn!(target: LOG_TARGET, "Failed to send register new file result");
+                    }
+                }
+                FileTransferServiceCommand::AddKnownAddress {
+                    peer_id,
+                    multiaddress,
+                } => {
+                    debug!(target: LOG_TARGET, "Adding known address {} for peer {}", multiaddress, peer_id);
+                    self.network.add_known_address(peer_id, multiaddress);
+                }
+                FileTransferServiceCommand::UnregisterFile { file_key, callback } => {
+                    let mut peer_file_allow_list = self.peer_file_allow_list.write().await;
+                    let registered_pairs = peer_file_allow_list.len();
+                    peer_file_allow_list
+                        .retain(|(_, registered_file_key)| *registered_file_key != file_key);
+
+                    let result = if peer_file_allow_list.len() < registered_pairs {
+                        Ok(())
+                    } else {
+                        Err(anyhow::anyhow!("File {:?} is not registered.", file_key))
+                    };
+
+                    if callback.send(result).is_err() {
+                        warn!(target: LOG_TARGET, "Failed to send unregister file result");
+                    }
+                }
+                FileTransferServiceCommand::AbortTransfer { peer_id, file_key } => {
+                    // The requests in flight are aborted as soon as the sender of their signal is used.
+                    match self.active_transfers.remove(&(peer_id, file_key)) {
+                        Some((abort_sender, _)) => {
+                            debug!(target: LOG_TARGET, "Aborting transfer of file {:?} with peer {}", file_key, peer_id);
+                            let _ = abort_sender.send(());
+                        }
+                        None => {
+                            debug!(target: LOG_TARGET, "No transfer of file {:?} with peer {} to abort", file_key, peer_id);
+                        }
+                    }
+                }
+            }
+        }
     }
 
     fn get_event_bus_provider(&self) -> &Self::EventBusProvider {
@@ -71,21 +351,18 @@ impl Actor for FileTransferService {
 }
 
 /// Event loop for the FileTransferService actor.
-pub struct FileTransferServiceEventLoop {
+pub struct FileTransferServiceEventLoop<FS: FileStorage + Send + Sync> {
     receiver: sc_utils::mpsc::TracingUnboundedReceiver<FileTransferServiceCommand>,
-    actor: FileTransferService,
-}
-
-enum MergedEventLoopMessage {
-    Command(FileTransferServiceCommand),
-    Request(IncomingRequest),
+    actor: FileTransferService<FS>,
 }
 
 /// Since this actor is a network service, it needs to handle both incoming network events and
 /// messages from other actors, hence the need for a custom `ActorEventLoop`.
-impl ActorEventLoop<FileTransferService> for FileTransferServiceEventLoop {
+impl<FS: FileStorage + Send + Sync> ActorEventLoop<FileTransferService<FS>>
+    for FileTransferServiceEventLoop<FS>
+{
     fn new(
-        actor: FileTransferService,
+        actor: FileTransferService<FS>,
         receiver: sc_utils::mpsc::TracingUnboundedReceiver<FileTransferServiceCommand>,
     ) -> Self {
         Self { actor, receiver }
@@ -94,79 +371,37 @@ impl ActorEventLoop<FileTransferService> for FileTransferServiceEventLoop {
     async fn run(mut self) {
         info!(target: LOG_TARGET, "FileTransferService starting up!");
 
-        let mut merged_stream = select(
-            self.receiver.map(MergedEventLoopMessage::Command),
-            self.actor
-                .request_receiver
-                .clone()
-                .map(MergedEventLoopMessage::Request),
-        );
+        let mut commands = self.receiver.fuse();
+        let mut requests = self.actor.request_receiver.clone().fuse();
+
+        // Requests are handled concurrently, while commands are handled as they are received.
+        let mut requests_in_flight = FuturesUnordered::new();
 
         loop {
-            match merged_stream.next().await {
-                Some(MergedEventLoopMessage::Command(command)) => {
-                    self.actor.handle_message(command).await;
-                }
-                Some(MergedEventLoopMessage::Request(request)) => {
-                    let IncomingRequest {
-                        peer,
-                        payload,
-                        pending_response,
-                    } = request;
-
-                    match self.actor.handle_request(peer, payload) {
-                        Ok(response_data) => {
-                            let response = OutgoingResponse {
-                                result: Ok(response_data),
-                                reputation_changes: Vec::new(),
-                                sent_feedback: None,
-                            };
-
-                            match pending_response.send(response) {
-                                Ok(()) => trace!(
-                                    target: LOG_TARGET,
-                                    "Handled provider client request from {}.",
-                                    peer,
-                                ),
-                                Err(_) => debug!(
-                                    target: LOG_TARGET,
-                                    "Failed to handle provider request from {}: {}",
-                                    peer,
-                                    HandleRequestError::SendResponse,
-                                ),
-                            };
-                        }
-                        Err(e) => {
-                            debug!(
-                                target: LOG_TARGET,
-                                "Failed to handle provider client request from {}: {}", peer, e,
-                            );
-
-                            let reputation_changes = match e {
-                                HandleRequestError::BadRequest(_) => {
-                                    vec![ReputationChange::new(-(1 << 12), "bad request")]
-                                }
-                                _ => Vec::new(),
-                            };
-
-                            let response = OutgoingResponse {
-                                result: Err(()),
-                                reputation_changes,
-                                sent_feedback: None,
-                            };
-
-                            if pending_response.send(response).is_err() {
-                                debug!(
-                                    target: LOG_TARGET,
-                                    "Failed to handle provider client request from {}: {}",
-                                    peer,
-                                    HandleRequestError::SendResponse,
-                                );
-                            };
-                        }
+            // New requests are not taken while the maximum number of requests is in flight, so that they are kept
+            // in the bounded inbound queue. Commands are still handled in the meantime.
+            let mut next_request = if requests_in_flight.len() < self.actor.max_concurrent_requests
+            {
+                requests.next().fuse()
+            } else {
+                Fuse::terminated()
+            };
+
+            futures::select! {
+                command = commands.next() => {
+                    if let Some(command) = command {
+                        self.actor.handle_message(command).await;
                     }
-                }
-                None => {
+                },
+                request = next_request => {
+                    if let Some(request) = request {
+                        self.actor.update_backpressure();
+                        requests_in_flight
+                            .push(self.actor.request_handler().handle_incoming_request(request));
+                    }
+                },
+                () = requests_in_flight.select_next_some() => {},
+                complete => {
                     warn!(target: LOG_TARGET, "FileTransferService event loop terminated.");
                     break;
                 }
@@ -175,40 +410,282 @@ impl ActorEventLoop<FileTransferService> for FileTransferServiceEventLoop {
     }
 }
 
-impl FileTransferService {
+impl<FS: FileStorage + Send + Sync> FileTransferService<FS> {
     /// Create a new [`FileTransferService`].
-    pub fn new<Hash: AsRef<[u8]>>(
-        genesis_hash: Hash,
-        fork_id: Option<&str>,
-    ) -> (Self, ProtocolConfig) {
-        let (tx, request_receiver) = async_channel::bounded(MAX_FILE_TRANSFER_REQUESTS_QUEUE);
+    ///
+    /// The provider requests protocol must have been registered in the network under `protocol_name`,
+    /// with `request_receiver` as its inbound queue.
+    ///
+    /// Metrics are registered in `metrics_registry` if provided. Failing to register them is not fatal,
+    /// the service then runs without metrics.
+    pub fn new(
+        protocol_name: ProtocolName,
+        request_receiver: async_channel::Receiver<IncomingRequest>,
+        network: Arc<dyn FileTransferNetwork>,
+        file_storage: Arc<RwLock<FS>>,
+        max_request_size: u64,
+        max_concurrent_requests: usize,
+        metrics_registry: Option<Registry>,
+    ) -> Self {
+        let metrics = metrics_registry.and_then(|registry| match Metrics::register(&registry) {
+            Ok(metrics) => Some(metrics),
+            Err(e) => {
+                warn!(target: LOG_TARGET, "Failed to register file transfer metrics: {}", e);
+                None
+            }
+        });
+
+        Self {
+            protocol_name,
+            request_receiver,
+            network,
+            event_bus_provider: FileTransferServiceEventBusProvider::new(),
+            backpressure: BackpressureTracker::new(
+                BACKPRESSURE_HIGH_WATER_MARK,
+                BACKPRESSURE_LOW_WATER_MARK,
+            ),
+            file_storage,
+            max_request_size,
+            max_concurrent_requests,
+            peer_file_allow_list: Arc::new(RwLock::new(HashSet::new())),
+            active_transfers: HashMap::new(),
+            metrics,
+        }
+    }
 
-        let mut protocol_config = super::generate_protocol_config(genesis_hash, fork_id);
-        protocol_config.inbound_queue = Some(tx);
+    /// Build the handler of an incoming request, from the current state of the service.
+    fn request_handler(&self) -> RequestHandler<FS> {
+        RequestHandler {
+            event_bus_provider: self.event_bus_provider.clone(),
+            file_storage: self.file_storage.clone(),
+            max_request_size: self.max_request_size,
+            peer_file_allow_list: self.peer_file_allow_list.clone(),
+            metrics: self.metrics.clone(),
+        }
+    }
 
-        (
-            Self {
-                request_receiver,
-                event_bus_provider: FileTransferServiceEventBusProvider::new(),
-            },
-            protocol_config,
-        )
+    /// Send a request for a file to a peer, returning the receiver of its raw response, which is aborted along
+    /// with the rest of the transfer of the file with the peer.
+    fn start_transfer(
+        &mut self,
+        peer_id: PeerId,
+        file_key: Key,
+        request: schema::v1::provider::Request,
+    ) -> TransferReceiver {
+        let abort_signal = self.abort_signal(peer_id, file_key);
+        let response_receiver =
+            start_request(&*self.network, self.protocol_name.clone(), peer_id, request);
+
+        TransferReceiver::new(response_receiver, abort_signal)
     }
 
-    fn handle_request(
+    /// Get the signal aborting the requests in flight for a file with a peer, creating it if there are none.
+    fn abort_signal(&mut self, peer_id: PeerId, file_key: Key) -> AbortSignal {
+        // Forget the transfers whose requests are no longer in flight.
+        self.active_transfers
+            .retain(|_, (_, abort_signal)| abort_signal.upgrade().is_some());
+
+        if let Some(abort_signal) = self
+            .active_transfers
+            .get(&(peer_id, file_key))
+            .and_then(|(_, abort_signal)| abort_signal.upgrade())
+        {
+            return abort_signal;
+        }
+
+        let (abort_sender, abort_receiver) = futures::channel::oneshot::channel();
+        let abort_signal = abort_receiver.shared();
+        let weak_abort_signal = abort_signal
+            .downgrade()
+            .expect("The signal was just created, so it has not resolved yet");
+        self.active_transfers
+            .insert((peer_id, file_key), (abort_sender, weak_abort_signal));
+
+        abort_signal
+    }
+
+    /// Build the stream of the responses to the download requests of a range of chunks of a file.
+    ///
+    /// Requests are only sent as the stream is polled, with at most [`MAX_IN_FLIGHT_CHUNK_DOWNLOADS`]
+    /// of them in flight, and the responses are yielded in the order of the chunks.
+    fn download_file_stream(
         &mut self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_range: Range<ChunkId>,
+    ) -> ChunkDownloadStream {
+        let network = self.network.clone();
+        let protocol_name = self.protocol_name.clone();
+        let abort_signal = self.abort_signal(peer_id, file_key);
+
+        stream::iter(chunk_range)
+            .map(move |chunk_id| {
+                let request = download_data_request(&file_key, chunk_id);
+                let response_receiver =
+                    start_request(&*network, protocol_name.clone(), peer_id, request);
+                let transfer_receiver =
+                    TransferReceiver::new(response_receiver, abort_signal.clone());
+
+                async move { (chunk_id, decode_download_response(transfer_receiver).await) }
+            })
+            .buffered(MAX_IN_FLIGHT_CHUNK_DOWNLOADS)
+            .boxed()
+    }
+
+    /// Check the number of queued incoming requests and signal backpressure changes to the
+    /// network layer through the event bus.
+    fn update_backpressure(&mut self) {
+        let queued_requests = self.request_receiver.len();
+
+        match self.backpressure.update(queued_requests) {
+            Some(BackpressureTransition::Activated) => {
+                warn!(
+                    target: LOG_TARGET,
+                    "Backpressure activated with {} queued requests", queued_requests
+                );
+                self.emit(BackpressureActive { queued_requests });
+            }
+            Some(BackpressureTransition::Cleared) => {
+                info!(
+                    target: LOG_TARGET,
+                    "Backpressure cleared with {} queued requests", queued_requests
+                );
+                self.emit(BackpressureCleared { queued_requests });
+            }
+            None => {}
+        }
+    }
+}
+
+impl<FS: FileStorage + Send + Sync> RequestHandler<FS> {
+    /// Handle an incoming request, sending the response (or the failure) to the requester.
+    async fn handle_incoming_request(self, request: IncomingRequest) {
+        let IncomingRequest {
+            peer,
+            payload,
+            pending_response,
+        } = request;
+
+        match self.handle_request(peer, payload).await {
+            Ok(response_data) => {
+                let response = OutgoingResponse {
+                    result: Ok(response_data),
+                    reputation_changes: Vec::new(),
+                    sent_feedback: None,
+                };
+
+                match pending_response.send(response) {
+                    Ok(()) => trace!(
+                        target: LOG_TARGET,
+                        "Handled provider client request from {}.",
+                        peer,
+                    ),
+                    Err(_) => debug!(
+                        target: LOG_TARGET,
+                        "Failed to handle provider request from {}: {}",
+                        peer,
+                        HandleRequestError::SendResponse,
+                    ),
+                };
+            }
+            Err(e) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Failed to handle provider client request from {}: {}", peer, e,
+                );
+
+                let reputation_changes = match e {
+                    HandleRequestError::BadRequest(_) => {
+                        if let Some(metrics) = &self.metrics {
+                            metrics.on_bad_request_penalty();
+                        }
+                        vec![ReputationChange::new(-(1 << 12), "bad request")]
+                    }
+                    _ => Vec::new(),
+                };
+
+                let response = OutgoingResponse {
+                    result: Err(()),
+                    reputation_changes,
+                    sent_feedback: None,
+                };
+
+                if pending_response.send(response).is_err() {
+                    debug!(
+                        target: LOG_TARGET,
+                        "Failed to handle provider client request from {}: {}",
+                        peer,
+                        HandleRequestError::SendResponse,
+                    );
+                };
+            }
+        }
+    }
+
+    /// Answer an incoming request, recording it in the metrics.
+    async fn handle_request(
+        &self,
         peer: PeerId,
         payload: Vec<u8>,
     ) -> Result<Vec<u8>, HandleRequestError> {
-        let request = schema::v1::provider::Request::decode(&payload[..])?;
+        let started = Instant::now();
+
+        let (kind, result) = match self.decode_request(&payload) {
+            Ok(request) => (
+                request_kind(&request),
+                self.answer_request(peer, &request).await,
+            ),
+            Err(e) => (REQUEST_KIND_UNKNOWN, Err(e)),
+        };
+
+        trace!(
+            target: LOG_TARGET,
+            peer = %peer,
+            kind,
+            success = result.is_ok(),
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Handled provider request",
+        );
+
+        if let Some(metrics) = &self.metrics {
+            metrics.on_request_handled(kind, result.is_ok(), started.elapsed().as_secs_f64());
+        }
 
+        result
+    }
+
+    /// Decode the payload of an incoming request, rejecting it if it exceeds the max request size.
+    fn decode_request(
+        &self,
+        payload: &[u8],
+    ) -> Result<schema::v1::provider::Request, HandleRequestError> {
+        if payload.len() as u64 > self.max_request_size {
+            return Err(HandleRequestError::BadRequest(
+                "Remote request exceeding the max request size.",
+            ));
+        }
+
+        Ok(schema::v1::provider::Request::decode(payload)?)
+    }
+
+    async fn answer_request(
+        &self,
+        peer: PeerId,
+        request: &schema::v1::provider::Request,
+    ) -> Result<Vec<u8>, HandleRequestError> {
         let response = match &request.request {
             Some(schema::v1::provider::request::Request::RemoteUploadDataRequest(r)) => {
-                self.on_remote_upload_data_request(&peer, r)?
+                self.on_remote_upload_data_request(&peer, r).await?
             }
             Some(schema::v1::provider::request::Request::RemoteReadRequest(r)) => {
                 self.on_remote_read_request(&peer, r)?
             }
+            Some(schema::v1::provider::request::Request::RemoteDownloadDataRequest(r)) => {
+                self.on_remote_download_data_request(&peer, r).await?
+            }
+            Some(schema::v1::provider::request::Request::ChunkAvailabilityRequest(r)) => {
+                self.on_chunk_availability_request(&peer, r).await?
+            }
             None => {
                 return Err(HandleRequestError::BadRequest(
                     "Remote request without request data.",
@@ -222,20 +699,83 @@ impl FileTransferService {
         Ok(data)
     }
 
-    fn on_remote_upload_data_request(
-        &mut self,
+    async fn on_remote_upload_data_request(
+        &self,
         peer: &PeerId,
         request: &schema::v1::provider::RemoteUploadDataRequest,
     ) -> Result<schema::v1::provider::Response, HandleRequestError> {
-        trace!("Remote call request from {}.", peer,);
+        trace!(
+            target: LOG_TARGET,
+            peer = %peer,
+            chunk_id = request.chunk_id,
+            size = request.data.len(),
+            "Remote upload request",
+        );
 
-        self.emit(RemoteUploadRequest {
-            location: request.location.clone(),
-        });
+        if request.file_key.len() != H256::len_bytes() {
+            return Err(HandleRequestError::BadRequest(
+                "Remote upload request with invalid file key.",
+            ));
+        }
+        let file_key = Key::from_slice(&request.file_key);
+
+        if !self
+            .peer_file_allow_list
+            .read()
+            .await
+            .contains(&(*peer, file_key))
+        {
+            return Err(HandleRequestError::BadRequest(
+                "Remote upload request for a file not registered for the peer.",
+            ));
+        }
+
+        let proof = CompactProof::decode(&mut &request.proof[..])?;
+
+        let success = match self
+            .store_chunk(&file_key, request.chunk_id, &request.data, &proof)
+            .await
+        {
+            Ok(FileStorageWriteStatus::FileComplete) => {
+                self.event_bus_provider
+                    .event_bus()
+                    .emit(RemoteUploadComplete {
+                        peer: *peer,
+                        file_key,
+                    });
+                true
+            }
+            Ok(FileStorageWriteStatus::FileIncomplete) => true,
+            Err(e) => {
+                debug!(
+                    target: LOG_TARGET,
+                    "Failed to store chunk {} of file {:?} uploaded by {}: {}",
+                    request.chunk_id,
+                    file_key,
+                    peer,
+                    e,
+                );
+                false
+            }
+        };
+
+        if let Some(metrics) = &self.metrics {
+            metrics.on_chunk_uploaded(request.data.len(), success);
+        }
+
+        // Tasks are only notified of chunks that were actually stored.
+        if success {
+            self.event_bus_provider
+                .event_bus()
+                .emit(RemoteUploadRequest {
+                    location: request.location.clone(),
+                    file_key,
+                });
+        }
 
-        // TODO actually save data.
         let response = schema::v1::provider::RemoteUploadDataResponse {
             location: request.location.clone(),
+            success,
         };
 
         Ok(schema::v1::provider::Response {
@@ -245,19 +785,121 @@ impl FileTransferService {
         })
     }
 
+    /// Verify the proof of an uploaded chunk against the fingerprint of the registered file, and
+    /// write the chunk in storage, returning whether the file is now complete.
+    async fn store_chunk(
+        &self,
+        file_key: &Key,
+        chunk_id: ChunkId,
+        chunk: &Chunk,
+        proof: &CompactProof,
+    ) -> Result<FileStorageWriteStatus, StoreChunkError> {
+        let mut file_storage = self.file_storage.write().await;
+
+        let metadata = file_storage
+            .get_metadata(file_key)
+            .map_err(StoreChunkError::FileStorage)?;
+
+        let file_proof = FileProof {
+            proven: vec![Leaf {
+                key: chunk_id,
+                data: chunk.clone(),
+            }],
+            proof: proof.clone(),
+            root: metadata.fingerprint,
+        };
+        verify_file_proof::<FileTrieLayout>(&file_proof, &metadata.fingerprint)
+            .map_err(|_| StoreChunkError::InvalidProof)?;
+
+        file_storage
+            .write_chunk(file_key, &chunk_id, chunk)
+            .map_err(StoreChunkError::FileStorage)
+    }
+
+    async fn on_remote_download_data_request(
+        &self,
+        peer: &PeerId,
+        request: &schema::v1::provider::RemoteDownloadDataRequest,
+    ) -> Result<schema::v1::provider::Response, HandleRequestError> {
+        trace!(
+            target: LOG_TARGET,
+            peer = %peer,
+            chunk_id = request.file_chunk_id,
+            file_key = %HexDisplay::from(&request.file_key),
+            "Remote download request",
+        );
+
+        if request.file_key.len() != H256::len_bytes() {
+            return Err(HandleRequestError::BadRequest(
+                "Remote download request with invalid file key.",
+            ));
+        }
+        let file_key = Key::from_slice(&request.file_key);
+
+        let file_storage = self.file_storage.read().await;
+        let file_chunk_with_proof =
+            read_chunk_with_proof(&*file_storage, &file_key, request.file_chunk_id)?;
+
+        if let Some(metrics) = &self.metrics {
+            metrics.on_chunk_downloaded(file_chunk_with_proof.len());
+        }
+
+        let response = schema::v1::provider::RemoteDownloadDataResponse {
+            file_chunk_with_proof,
+        };
+
+        Ok(schema::v1::provider::Response {
+            response: Some(
+                schema::v1::provider::response::Response::RemoteDownloadDataResponse(response),
+            ),
+        })
+    }
+
+    async fn on_chunk_availability_request(
+        &self,
+        peer: &PeerId,
+        request: &schema::v1::provider::ChunkAvailabilityRequest,
+    ) -> Result<schema::v1::provider::Response, HandleRequestError> {
+        trace!(
+            target: LOG_TARGET,
+            peer = %peer,
+            file_key = %HexDisplay::from(&request.file_key),
+            "Chunk availability request",
+        );
+
+        if request.file_key.len() != H256::len_bytes() {
+            return Err(HandleRequestError::BadRequest(
+                "Chunk availability request with invalid file key.",
+            ));
+        }
+        let file_key = Key::from_slice(&request.file_key);
+
+        let file_storage = self.file_storage.read().await;
+        let bitmap = read_chunk_bitmap(&*file_storage, &file_key)?;
+
+        let response = schema::v1::provider::ChunkAvailabilityResponse { bitmap };
+
+        Ok(schema::v1::provider::Response {
+            response: Some(
+                schema::v1::provider::response::Response::ChunkAvailabilityResponse(response),
+            ),
+        })
+    }
+
     fn on_remote_read_request(
-        &mut self,
+        &self,
         peer: &PeerId,
         request: &schema::v1::provider::RemoteReadRequest,
     ) -> Result<schema::v1::provider::Response, HandleRequestError> {
         if request.locations.is_empty() {
-            debug!("Invalid remote read request sent by {}.", peer);
+            debug!(target: LOG_TARGET, "Invalid remote read request sent by {}.", peer);
             return Err(HandleRequestError::BadRequest(
                 "Remote read request without locations.",
             ));
         }
 
         trace!(
+            target: LOG_TARGET,
             "Remote read request from {} ({}).",
             peer,
             fmt_keys(request.locations.first(), request.locations.last()),
@@ -288,6 +930,125 @@ enum HandleRequestError {
     /// Encoding or decoding of some data failed.
     #[error("codec error: {0}")]
     Codec(#[from] codec::Error),
+    /// Reading from the file storage failed.
+    #[error("file storage error: {0:?}")]
+    FileStorage(FileStorageError),
+}
+
+#[derive(Debug, thiserror::Error)]
+enum StoreChunkError {
+    #[error("the chunk is not proven to be part of the file")]
+    InvalidProof,
+    #[error("file storage error: {0:?}")]
+    FileStorage(FileStorageError),
+}
+
+/// Kind of a decoded request, as recorded in the metrics.
+fn request_kind(request: &schema::v1::provider::Request) -> &'static str {
+    match &request.request {
+        Some(schema::v1::provider::request::Request::RemoteUploadDataRequest(_)) => {
+            REQUEST_KIND_UPLOAD
+        }
+        Some(schema::v1::provider::request::Request::RemoteDownloadDataRequest(_)) => {
+            REQUEST_KIND_DOWNLOAD
+        }
+        Some(schema::v1::provider::request::Request::RemoteReadRequest(_)) => REQUEST_KIND_READ,
+        Some(schema::v1::provider::request::Request::ChunkAvailabilityRequest(_)) => {
+            REQUEST_KIND_CHUNK_AVAILABILITY
+        }
+        None => REQUEST_KIND_UNKNOWN,
+    }
+}
+
+/// Send a request to a peer through the network, returning the receiver of its raw response.
+fn start_request(
+    network: &dyn FileTransferNetwork,
+    protocol_name: ProtocolName,
+    peer_id: PeerId,
+    request: schema::v1::provider::Request,
+) -> ResponseReceiver {
+    let (tx, rx) = futures::channel::oneshot::channel();
+
+    network.start_request(
+        peer_id,
+        protocol_name,
+        request.encode_to_vec(),
+        None,
+        tx,
+        IfDisconnected::ImmediateError,
+    );
+
+    rx
+}
+
+/// Build the request to download a chunk of a file.
+fn download_data_request(file_key: &Key, chunk_id: ChunkId) -> schema::v1::provider::Request {
+    schema::v1::provider::Request {
+        request: Some(
+            schema::v1::provider::request::Request::RemoteDownloadDataRequest(
+                schema::v1::provider::RemoteDownloadDataRequest {
+                    file_key: file_key.as_bytes().to_vec(),
+                    file_chunk_id: chunk_id,
+                },
+            ),
+        ),
+    }
+}
+
+/// Generate the proof of a chunk of a stored file, SCALE-encoded to be sent to the requester.
+///
+/// Fails with a [`HandleRequestError::BadRequest`] if the file or the chunk is not held in storage.
+fn read_chunk_with_proof<FS: FileStorage>(
+    file_storage: &FS,
+    file_key: &Key,
+    chunk_id: ChunkId,
+) -> Result<Vec<u8>, HandleRequestError> {
+    let proof = file_storage
+        .generate_proof(file_key, &chunk_id)
+        .map_err(|e| match e {
+            FileStorageError::FileDoesNotExist => {
+                HandleRequestError::BadRequest("Remote download request for an unknown file.")
+            }
+            FileStorageError::FileChunkDoesNotExist | FileStorageError::IncompleteFile => {
+                HandleRequestError::BadRequest("Remote download request for a missing chunk.")
+            }
+            e => HandleRequestError::FileStorage(e),
+        })?;
+
+    Ok(proof.encode())
+}
+
+/// Build the bitmap of the chunks of a stored file that can be served, as sent in a
+/// [`schema::v1::provider::ChunkAvailabilityResponse`].
+///
+/// The bitmap is bounded by the chunk count of the file. Fails with a [`HandleRequestError::BadRequest`]
+/// if the file is not held in storage.
+fn read_chunk_bitmap<FS: FileStorage>(
+    file_storage: &FS,
+    file_key: &Key,
+) -> Result<Vec<u8>, HandleRequestError> {
+    let map_err = |e| match e {
+        FileStorageError::FileDoesNotExist => {
+            HandleRequestError::BadRequest("Chunk availability request for an unknown file.")
+        }
+        e => HandleRequestError::FileStorage(e),
+    };
+
+    let chunk_count = file_storage
+        .get_metadata(file_key)
+        .map_err(map_err)?
+        .chunk_count();
+    let chunk_ids = file_storage.stored_chunk_ids(file_key).map_err(map_err)?;
+
+    let mut bitmap = vec![0u8; chunk_count.div_ceil(8) as usize];
+    for chunk_id in chunk_ids
+        .into_iter()
+        .filter(|chunk_id| *chunk_id < chunk_count)
+    {
+        bitmap[(chunk_id / 8) as usize] |= 1 << (chunk_id % 8);
+    }
+
+    Ok(bitmap)
 }
 
 fn fmt_keys(first: Option<&Vec<u8>>, last: Option<&Vec<u8>>) -> String {
@@ -301,3 +1062,821 @@ fn fmt_keys(first: Option<&Vec<u8>>, last: Option<&Vec<u8>>) -> String {
         String::from("n/a")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use file_manager::in_memory::InMemoryFileStorage;
+    use sc_network::request_responses::RequestFailure;
+    use sp_trie::{MemoryDB, TrieDBMutBuilder, TrieMut};
+    use storage_hub_infra::types::{FileProof, Metadata};
+
+    use super::super::commands::{decode_chunk_bitmap, RequestError};
+    use super::*;
+
+    const CHUNK_SIZE: u64 = 64;
+
+    const MAX_REQUEST_SIZE: u64 = 1024 * 1024;
+
+    const MAX_CONCURRENT_REQUESTS: usize = 4;
+
+    /// Network recording the requests sent through it, to be answered by the test.
+    #[derive(Default)]
+    struct MockNetwork {
+        sent_requests: std::sync::Mutex<
+            Vec<(
+                Vec<u8>,
+                futures::channel::oneshot::Sender<Result<(Vec<u8>, ProtocolName), RequestFailure>>,
+            )>,
+        >,
+        known_addresses: std::sync::Mutex<Vec<(PeerId, Multiaddr)>>,
+    }
+
+    #[async_trait::async_trait]
+    impl NetworkRequest for MockNetwork {
+        async fn request(
+            &self,
+            _target: PeerId,
+            _protocol: ProtocolName,
+            _request: Vec<u8>,
+            _fallback_request: Option<(Vec<u8>, ProtocolName)>,
+            _connect: IfDisconnected,
+        ) -> Result<(Vec<u8>, ProtocolName), RequestFailure> {
+            // Requests are only answered through `start_request`, so awaiting one here always fails.
+            Err(RequestFailure::Refused)
+        }
+
+        fn start_request(
+            &self,
+            _target: PeerId,
+            _protocol: ProtocolName,
+            request: Vec<u8>,
+            _fallback_request: Option<(Vec<u8>, ProtocolName)>,
+            tx: futures::channel::oneshot::Sender<Result<(Vec<u8>, ProtocolName), RequestFailure>>,
+            _connect: IfDisconnected,
+        ) {
+            self.sent_requests.lock().unwrap().push((request, tx));
+        }
+    }
+
+    impl FileTransferNetwork for MockNetwork {
+        fn add_known_address(&self, peer_id: PeerId, address: Multiaddr) {
+            self.known_addresses
+                .lock()
+                .unwrap()
+                .push((peer_id, address));
+        }
+    }
+
+    fn new_file_transfer_service(
+        file_storage: InMemoryFileStorage<FileTrieLayout>,
+        max_request_size: u64,
+    ) -> (
+        FileTransferService<InMemoryFileStorage<FileTrieLayout>>,
+        Arc<MockNetwork>,
+    ) {
+        let (_, request_receiver) = async_channel::bounded(MAX_FILE_TRANSFER_REQUESTS_QUEUE);
+        let network = Arc::new(MockNetwork::default());
+
+        let file_transfer_service = FileTransferService::new(
+            ProtocolName::from("/storage-hub/provider/1"),
+            request_receiver,
+            network.clone(),
+            Arc::new(RwLock::new(file_storage)),
+            max_request_size,
+            MAX_CONCURRENT_REQUESTS,
+            None,
+        );
+
+        (file_transfer_service, network)
+    }
+
+    /// Build a complete file of 4 chunks in an in-memory file storage.
+    fn build_file_storage() -> (InMemoryFileStorage<FileTrieLayout>, Key, H256, Vec<Chunk>) {
+        let chunks: Vec<Chunk> = (0..4u8).map(|i| vec![i + 1; CHUNK_SIZE as usize]).collect();
+
+        let mut memdb = MemoryDB::<RefHasher>::default();
+        let mut root = Default::default();
+        {
+            let mut trie = TrieDBMutBuilder::<FileTrieLayout>::new(&mut memdb, &mut root).build();
+            for (chunk_id, chunk) in chunks.iter().enumerate() {
+                trie.insert(&(chunk_id as u64).to_be_bytes(), chunk)
+                    .unwrap();
+            }
+        }
+        let fingerprint = H256::from_slice(root.as_ref());
+
+        let file_key = H256::from_slice(&[1; 32]);
+        let mut file_storage = InMemoryFileStorage::<FileTrieLayout>::new();
+        file_storage
+            .set_metadata(
+                file_key,
+                Metadata::new(fingerprint, chunks.len() as u64 * CHUNK_SIZE, CHUNK_SIZE)
+                    .with_owner(String::from("owner"))
+                    .with_location(String::from("location")),
+            )
+            .unwrap();
+        for (chunk_id, chunk) in chunks.iter().enumerate() {
+            file_storage
+                .write_chunk(&file_key, &(chunk_id as u64), chunk)
+                .unwrap();
+        }
+
+        (file_storage, file_key, fingerprint, chunks)
+    }
+
+    #[test]
+    fn uploaded_chunk_is_verified_against_file_fingerprint() {
+        let (file_storage, file_key, _, chunks) = build_file_storage();
+
+        let proof = file_storage.generate_proof(&file_key, &1).unwrap().proof;
+
+        // Register the file, without any of its chunks, in the storage of the service receiving the upload.
+        let mut upload_file_storage = InMemoryFileStorage::<FileTrieLayout>::new();
+        upload_file_storage
+            .set_metadata(file_key, file_storage.get_metadata(&file_key).unwrap())
+            .unwrap();
+        let (file_transfer_service, _) =
+            new_file_transfer_service(upload_file_storage, MAX_REQUEST_SIZE);
+        let request_handler = file_transfer_service.request_handler();
+
+        // The proof does not hold for different data or another chunk.
+        assert!(matches!(
+            futures::executor::block_on(
+                request_handler.store_chunk(&file_key, 1, &chunks[2], &proof)
+            ),
+            Err(StoreChunkError::InvalidProof)
+        ));
+        assert!(matches!(
+            futures::executor::block_on(
+                request_handler.store_chunk(&file_key, 2, &chunks[1], &proof)
+            ),
+            Err(StoreChunkError::InvalidProof)
+        ));
+
+        // It does for the proven chunk.
+        assert!(matches!(
+            futures::executor::block_on(
+                request_handler.store_chunk(&file_key, 1, &chunks[1], &proof)
+            ),
+            Ok(FileStorageWriteStatus::FileIncomplete)
+        ));
+    }
+
+    #[test]
+    fn downloaded_chunk_with_proof_round_trips() {
+        let (file_storage, file_key, fingerprint, chunks) = build_file_storage();
+
+        let file_chunk_with_proof = read_chunk_with_proof(&file_storage, &file_key, 2).unwrap();
+
+        let file_proof = FileProof::decode(&mut &file_chunk_with_proof[..]).unwrap();
+        assert_eq!(file_proof.root, fingerprint);
+        assert_eq!(file_proof.proven.len(), 1);
+        assert_eq!(file_proof.proven[0].key, 2);
+        assert_eq!(file_proof.proven[0].data, chunks[2]);
+        assert_eq!(
+            verify_file_proof::<FileTrieLayout>(&file_proof, &fingerprint),
+            Ok(chunks[2].clone())
+        );
+    }
+
+    #[test]
+    fn oversized_request_is_rejected_before_decoding() {
+        let (file_storage, _, _, _) = build_file_storage();
+        let (mut file_transfer_service, _) = new_file_transfer_service(file_storage, 8);
+
+        // The payload is not a valid request, so it would fail with a decoding error if it was decoded.
+        let payload = vec![0xff; 9];
+        assert!(matches!(
+            futures::executor::block_on(
+                file_transfer_service
+                    .request_handler()
+                    .handle_request(PeerId::random(), payload.clone())
+            ),
+            Err(HandleRequestError::BadRequest(_))
+        ));
+
+        file_transfer_service.max_request_size = 9;
+        assert!(matches!(
+            futures::executor::block_on(
+                file_transfer_service
+                    .request_handler()
+                    .handle_request(PeerId::random(), payload)
+            ),
+            Err(HandleRequestError::DecodeProto(_))
+        ));
+    }
+
+    #[test]
+    fn metrics_are_recorded_when_a_registry_is_provided() {
+        let (file_storage, file_key, _, _) = build_file_storage();
+        let registry = Registry::new();
+        let (_, request_receiver) = async_channel::bounded(MAX_FILE_TRANSFER_REQUESTS_QUEUE);
+        let file_transfer_service = FileTransferService::new(
+            ProtocolName::from("/storage-hub/provider/1"),
+            request_receiver,
+            Arc::new(MockNetwork::default()),
+            Arc::new(RwLock::new(file_storage)),
+            MAX_REQUEST_SIZE,
+            MAX_CONCURRENT_REQUESTS,
+            Some(registry.clone()),
+        );
+
+        let handle = |request: schema::v1::provider::Request| {
+            let (pending_response, mut response_receiver) = futures::channel::oneshot::channel();
+            futures::executor::block_on(
+                file_transfer_service
+                    .request_handler()
+                    .handle_incoming_request(IncomingRequest {
+                        peer: PeerId::random(),
+                        payload: request.encode_to_vec(),
+                        pending_response,
+                    }),
+            );
+            response_receiver.try_recv().unwrap().unwrap()
+        };
+
+        // A chunk is served, and a chunk of an unknown file is refused.
+        assert!(handle(download_data_request(&file_key, 0)).result.is_ok());
+        let response = handle(download_data_request(&H256::repeat_byte(9), 0));
+        assert!(response.result.is_err());
+        assert_eq!(response.reputation_changes.len(), 1);
+
+        let metric = |name: &str| -> Vec<(Vec<String>, f64)> {
+            registry
+                .gather()
+                .into_iter()
+                .find(|family| family.get_name() == name)
+                .map(|family| {
+                    family
+                        .get_metric()
+                        .iter()
+                        .map(|m| {
+                            (
+                                m.get_label()
+                                    .iter()
+                                    .map(|l| l.get_value().to_string())
+                                    .collect(),
+                                m.get_counter().get_value(),
+                            )
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        assert_eq!(
+            metric("storage_hub_file_transfer_downloads_served_total"),
+            vec![(vec![], 1.0)]
+        );
+        assert!(metric("storage_hub_file_transfer_bytes_sent_total")[0].1 > 0.0);
+        assert_eq!(
+            metric("storage_hub_file_transfer_bad_request_penalties_total"),
+            vec![(vec![], 1.0)]
+        );
+        let mut requests = metric("storage_hub_file_transfer_requests_total");
+        requests.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            requests,
+            vec![
+                (vec!["download".to_string(), "failure".to_string()], 1.0),
+                (vec!["download".to_string(), "success".to_string()], 1.0),
+            ]
+        );
+        assert!(metric("storage_hub_file_transfer_uploads_served_total")
+            .iter()
+            .all(|(_, value)| *value == 0.0));
+    }
+
+    /// Build the payload of a request uploading a chunk of a file.
+    fn upload_request_payload(
+        file_key: &Key,
+        chunk_id: ChunkId,
+        chunk: &Chunk,
+        proof: &CompactProof,
+    ) -> Vec<u8> {
+        let request = schema::v1::provider::Request {
+            request: Some(
+                schema::v1::provider::request::Request::RemoteUploadDataRequest(
+                    schema::v1::provider::RemoteUploadDataRequest {
+                        location: String::from("location"),
+                        data: chunk.clone(),
+                        file_key: file_key.as_bytes().to_vec(),
+                        chunk_id,
+                        proof: proof.encode(),
+                    },
+                ),
+            ),
+        };
+        request.encode_to_vec()
+    }
+
+    /// Check whether the response to an upload request reports the chunk as stored.
+    fn upload_succeeded(response: Vec<u8>) -> bool {
+        match schema::v1::provider::Response::decode(&response[..])
+            .unwrap()
+            .response
+        {
+            Some(schema::v1::provider::response::Response::RemoteUploadDataResponse(r)) => {
+                r.success
+            }
+            _ => panic!("Expected a remote upload data response"),
+        }
+    }
+
+    #[test]
+    fn uploads_are_only_accepted_from_registered_peers() {
+        let (complete_file_storage, file_key, _, chunks) = build_file_storage();
+        let proofs: Vec<CompactProof> = (0..chunks.len() as u64)
+            .map(|chunk_id| {
+                complete_file_storage
+                    .generate_proof(&file_key, &chunk_id)
+                    .unwrap()
+                    .proof
+            })
+            .collect();
+
+        // The service stores the file from scratch.
+        let mut file_storage = InMemoryFileStorage::<FileTrieLayout>::new();
+        file_storage
+            .set_metadata(
+                file_key,
+                complete_file_storage.get_metadata(&file_key).unwrap(),
+            )
+            .unwrap();
+        let (mut file_transfer_service, _) =
+            new_file_transfer_service(file_storage, MAX_REQUEST_SIZE);
+
+        let send_command = |service: &mut FileTransferService<_>, command| {
+            futures::executor::block_on(service.handle_message(command))
+        };
+        let upload = |service: &mut FileTransferService<_>, peer_id: PeerId, chunk_id: ChunkId| {
+            futures::executor::block_on(service.request_handler().handle_request(
+                peer_id,
+                upload_request_payload(
+                    &file_key,
+                    chunk_id,
+                    &chunks[chunk_id as usize],
+                    &proofs[chunk_id as usize],
+                ),
+            ))
+        };
+
+        // The same file is registered for two peers.
+        let (alice, bob, charlie) = (PeerId::random(), PeerId::random(), PeerId::random());
+        for peer_id in [alice, bob] {
+            let (callback, mut rx) = tokio::sync::oneshot::channel();
+            send_command(
+                &mut file_transfer_service,
+                FileTransferServiceCommand::RegisterNewFile {
+                    peer_id,
+                    file_key,
+                    callback,
+                },
+            );
+            assert!(rx.try_recv().unwrap().is_ok());
+        }
+
+        // Registering a pair twice fails.
+        let (callback, mut rx) = tokio::sync::oneshot::channel();
+        send_command(
+            &mut file_transfer_service,
+            FileTransferServiceCommand::RegisterNewFile {
+                peer_id: alice,
+                file_key,
+                callback,
+            },
+        );
+        assert!(rx.try_recv().unwrap().is_err());
+
+        // Both registered peers can upload chunks of the file.
+        assert!(upload_succeeded(
+            upload(&mut file_transfer_service, alice, 0).unwrap()
+        ));
+        assert!(upload_succeeded(
+            upload(&mut file_transfer_service, bob, 1).unwrap()
+        ));
+
+        // A peer the file was not registered for is rejected.
+        assert!(matches!(
+            upload(&mut file_transfer_service, charlie, 2),
+            Err(HandleRequestError::BadRequest(_))
+        ));
+
+        // Once unregistered, uploads of the file are rejected for all peers, including the requests that were
+        // already in flight.
+        let request_in_flight = file_transfer_service.request_handler();
+        let (callback, mut rx) = tokio::sync::oneshot::channel();
+        send_command(
+            &mut file_transfer_service,
+            FileTransferServiceCommand::UnregisterFile { file_key, callback },
+        );
+        assert!(rx.try_recv().unwrap().is_ok());
+
+        for peer_id in [alice, bob] {
+            assert!(matches!(
+                upload(&mut file_transfer_service, peer_id, 2),
+                Err(HandleRequestError::BadRequest(_))
+            ));
+        }
+        assert!(matches!(
+            futures::executor::block_on(request_in_flight.handle_request(
+                alice,
+                upload_request_payload(&file_key, 2, &chunks[2], &proofs[2]),
+            )),
+            Err(HandleRequestError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn upload_complete_is_only_emitted_for_the_completing_chunk() {
+        let (complete_file_storage, file_key, _, chunks) = build_file_storage();
+
+        // The service stores the file from scratch.
+        let mut file_storage = InMemoryFileStorage::<FileTrieLayout>::new();
+        file_storage
+            .set_metadata(
+                file_key,
+                complete_file_storage.get_metadata(&file_key).unwrap(),
+            )
+            .unwrap();
+        let (mut file_transfer_service, _) =
+            new_file_transfer_service(file_storage, MAX_REQUEST_SIZE);
+
+        let peer_id = PeerId::random();
+        let (callback, _) = tokio::sync::oneshot::channel();
+        futures::executor::block_on(file_transfer_service.handle_message(
+            FileTransferServiceCommand::RegisterNewFile {
+                peer_id,
+                file_key,
+                callback,
+            },
+        ));
+
+        let mut upload_complete_events = ProvidesEventBus::<RemoteUploadComplete>::event_bus(
+            &file_transfer_service.event_bus_provider,
+        )
+        .subscribe();
+
+        for chunk_id in 0..chunks.len() as u64 {
+            assert!(upload_complete_events.try_recv().is_err());
+
+            let proof = complete_file_storage
+                .generate_proof(&file_key, &chunk_id)
+                .unwrap()
+                .proof;
+            let response = futures::executor::block_on(
+                file_transfer_service.request_handler().handle_request(
+                    peer_id,
+                    upload_request_payload(&file_key, chunk_id, &chunks[chunk_id as usize], &proof),
+                ),
+            );
+            assert!(upload_succeeded(response.unwrap()));
+        }
+
+        // Only the last chunk completes the file.
+        let event = upload_complete_events.try_recv().unwrap();
+        assert_eq!(event.peer, peer_id);
+        assert_eq!(event.file_key, file_key);
+        assert!(upload_complete_events.try_recv().is_err());
+    }
+
+    #[test]
+    fn slow_request_does_not_block_other_requests() {
+        let (file_storage, file_key, _, _) = build_file_storage();
+        let file_storage = Arc::new(RwLock::new(file_storage));
+        let (request_sender, request_receiver) =
+            async_channel::bounded(MAX_FILE_TRANSFER_REQUESTS_QUEUE);
+        let file_transfer_service = FileTransferService::new(
+            ProtocolName::from("/storage-hub/provider/1"),
+            request_receiver,
+            Arc::new(MockNetwork::default()),
+            file_storage.clone(),
+            MAX_REQUEST_SIZE,
+            MAX_CONCURRENT_REQUESTS,
+            None,
+        );
+        let (_command_sender, command_receiver) =
+            sc_utils::mpsc::tracing_unbounded("test-file-transfer-service", 100);
+        let mut event_loop =
+            FileTransferServiceEventLoop::new(file_transfer_service, command_receiver)
+                .run()
+                .boxed();
+
+        let send_request = |request: schema::v1::provider::Request| {
+            let (pending_response, response_receiver) = futures::channel::oneshot::channel();
+            request_sender
+                .try_send(IncomingRequest {
+                    peer: PeerId::random(),
+                    payload: request.encode_to_vec(),
+                    pending_response,
+                })
+                .unwrap();
+            response_receiver
+        };
+
+        // The file storage is held, as it would be by a slow write, so the download request has to wait.
+        let write_guard = file_storage.try_write().unwrap();
+        let mut download_response = send_request(download_data_request(&file_key, 0));
+        let read_response = send_request(schema::v1::provider::Request {
+            request: Some(schema::v1::provider::request::Request::RemoteReadRequest(
+                schema::v1::provider::RemoteReadRequest {
+                    locations: vec![b"location".to_vec()],
+                },
+            )),
+        });
+
+        // The read request is answered while the download request is in flight.
+        match futures::executor::block_on(future::select(&mut event_loop, read_response)) {
+            future::Either::Right((response, _)) => assert!(response.unwrap().result.is_ok()),
+            future::Either::Left(_) => panic!("The event loop terminated"),
+        }
+        assert!(download_response.try_recv().unwrap().is_none());
+
+        // Once the file storage is released, the download request is answered too.
+        drop(write_guard);
+        match futures::executor::block_on(future::select(&mut event_loop, download_response)) {
+            future::Either::Right((response, _)) => assert!(response.unwrap().result.is_ok()),
+            future::Either::Left(_) => panic!("The event loop terminated"),
+        }
+    }
+
+    #[test]
+    fn commands_are_handled_while_requests_are_at_capacity() {
+        let (file_storage, file_key, _, _) = build_file_storage();
+        let file_storage = Arc::new(RwLock::new(file_storage));
+        let (request_sender, request_receiver) =
+            async_channel::bounded(MAX_FILE_TRANSFER_REQUESTS_QUEUE);
+        let file_transfer_service = FileTransferService::new(
+            ProtocolName::from("/storage-hub/provider/1"),
+            request_receiver,
+            Arc::new(MockNetwork::default()),
+            file_storage.clone(),
+            MAX_REQUEST_SIZE,
+            1,
+            None,
+        );
+        let (command_sender, command_receiver) =
+            sc_utils::mpsc::tracing_unbounded("test-file-transfer-service", 100);
+        let mut event_loop =
+            FileTransferServiceEventLoop::new(file_transfer_service, command_receiver)
+                .run()
+                .boxed();
+
+        let send_request = || {
+            let (pending_response, response_receiver) = futures::channel::oneshot::channel();
+            request_sender
+                .try_send(IncomingRequest {
+                    peer: PeerId::random(),
+                    payload: download_data_request(&file_key, 0).encode_to_vec(),
+                    pending_response,
+                })
+                .unwrap();
+            response_receiver
+        };
+
+        // The file storage is held, so the only request allowed in flight can not be answered, and the next
+        // one is left in the queue.
+        let write_guard = file_storage.try_write().unwrap();
+        let mut first_response = send_request();
+        let mut second_response = send_request();
+
+        // A command is still handled in the meantime.
+        let (callback, rx) = tokio::sync::oneshot::channel();
+        command_sender
+            .unbounded_send(FileTransferServiceCommand::RegisterNewFile {
+                peer_id: PeerId::random(),
+                file_key,
+                callback,
+            })
+            .unwrap();
+        match futures::executor::block_on(future::select(&mut event_loop, rx)) {
+            future::Either::Right((result, _)) => assert!(result.unwrap().is_ok()),
+            future::Either::Left(_) => panic!("The event loop terminated"),
+        }
+        assert!(first_response.try_recv().unwrap().is_none());
+        assert!(second_response.try_recv().unwrap().is_none());
+
+        // Once the file storage is released, both requests are answered.
+        drop(write_guard);
+        for response in [first_response, second_response] {
+            match futures::executor::block_on(future::select(&mut event_loop, response)) {
+                future::Either::Right((response, _)) => assert!(response.unwrap().result.is_ok()),
+                future::Either::Left(_) => panic!("The event loop terminated"),
+            }
+        }
+    }
+
+    #[test]
+    fn file_download_streams_chunks_in_order() {
+        let (mut file_transfer_service, network) =
+            new_file_transfer_service(InMemoryFileStorage::new(), MAX_REQUEST_SIZE);
+        let chunk_count = MAX_IN_FLIGHT_CHUNK_DOWNLOADS as u64 + 4;
+        let failing_chunk_id = 2;
+
+        let (callback, mut rx) = tokio::sync::oneshot::channel();
+        futures::executor::block_on(file_transfer_service.handle_message(
+            FileTransferServiceCommand::DownloadFileRequest {
+                peer_id: PeerId::random(),
+                file_key: H256::from_slice(&[1; 32]),
+                chunk_range: 0..chunk_count,
+                callback,
+            },
+        ));
+        let mut stream = rx.try_recv().unwrap();
+
+        // Requests are capped while no response is received.
+        assert!(stream.next().now_or_never().is_none());
+        assert_eq!(
+            network.sent_requests.lock().unwrap().len(),
+            MAX_IN_FLIGHT_CHUNK_DOWNLOADS
+        );
+
+        let mut downloaded = Vec::new();
+        loop {
+            // Answer the requests in flight in reverse order, failing for one of the chunks.
+            let sent_requests: Vec<_> = network.sent_requests.lock().unwrap().drain(..).collect();
+            for (request, tx) in sent_requests.into_iter().rev() {
+                let chunk_id = match schema::v1::provider::Request::decode(&request[..])
+                    .unwrap()
+                    .request
+                {
+                    Some(schema::v1::provider::request::Request::RemoteDownloadDataRequest(r)) => {
+                        r.file_chunk_id
+                    }
+                    _ => panic!("Expected a remote download data request"),
+                };
+
+                let result = if chunk_id == failing_chunk_id {
+                    Err(RequestFailure::Refused)
+                } else {
+                    let response = schema::v1::provider::Response {
+                        response: Some(
+                            schema::v1::provider::response::Response::RemoteDownloadDataResponse(
+                                schema::v1::provider::RemoteDownloadDataResponse {
+                                    file_chunk_with_proof: chunk_id.to_be_bytes().to_vec(),
+                                },
+                            ),
+                        ),
+                    };
+                    Ok((
+                        response.encode_to_vec(),
+                        ProtocolName::from("/storage-hub/provider/1"),
+                    ))
+                };
+                tx.send(result).unwrap();
+            }
+
+            match stream.next().now_or_never() {
+                Some(Some(item)) => downloaded.push(item),
+                Some(None) => break,
+                None => {}
+            }
+        }
+
+        // All the chunks are yielded in order, the failure of a chunk not aborting the download.
+        assert_eq!(downloaded.len(), chunk_count as usize);
+        for (expected_chunk_id, (chunk_id, result)) in downloaded.into_iter().enumerate() {
+            assert_eq!(chunk_id, expected_chunk_id as u64);
+            if chunk_id == failing_chunk_id {
+                assert!(matches!(
+                    result,
+                    Err(RequestError::RequestFailure(RequestFailure::Refused))
+                ));
+            } else {
+                assert_eq!(
+                    result.unwrap().file_chunk_with_proof,
+                    chunk_id.to_be_bytes().to_vec()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn aborted_download_resolves_with_abort_error() {
+        let (mut file_transfer_service, network) =
+            new_file_transfer_service(InMemoryFileStorage::new(), MAX_REQUEST_SIZE);
+        let peer_id = PeerId::random();
+        let (file_key, other_file_key) = (H256::from_slice(&[1; 32]), H256::from_slice(&[2; 32]));
+
+        let mut download = |file_key: Key| {
+            let (callback, mut rx) = tokio::sync::oneshot::channel();
+            futures::executor::block_on(file_transfer_service.handle_message(
+                FileTransferServiceCommand::DownloadRequest {
+                    peer_id,
+                    file_key,
+                    chunk_id: 0,
+                    callback,
+                },
+            ));
+            decode_download_response(rx.try_recv().unwrap()).boxed()
+        };
+        let mut aborted_download = download(file_key);
+        let mut other_download = download(other_file_key);
+
+        // Neither request is answered by the peer.
+        assert!((&mut aborted_download).now_or_never().is_none());
+        assert!((&mut other_download).now_or_never().is_none());
+
+        futures::executor::block_on(
+            file_transfer_service
+                .handle_message(FileTransferServiceCommand::AbortTransfer { peer_id, file_key }),
+        );
+
+        // The aborted download resolves without waiting for the peer, while the other one is still in flight.
+        assert!(matches!(
+            aborted_download.now_or_never(),
+            Some(Err(RequestError::Aborted))
+        ));
+        assert!((&mut other_download).now_or_never().is_none());
+        assert_eq!(network.sent_requests.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn known_addresses_are_added_to_the_network() {
+        let (mut file_transfer_service, network) =
+            new_file_transfer_service(InMemoryFileStorage::new(), MAX_REQUEST_SIZE);
+        let peer_id = PeerId::random();
+        let multiaddress: Multiaddr = format!("/ip4/127.0.0.1/tcp/30333/p2p/{}", peer_id)
+            .parse()
+            .unwrap();
+
+        futures::executor::block_on(file_transfer_service.handle_message(
+            FileTransferServiceCommand::AddKnownAddress {
+                peer_id,
+                multiaddress: multiaddress.clone(),
+            },
+        ));
+
+        assert_eq!(
+            *network.known_addresses.lock().unwrap(),
+            vec![(peer_id, multiaddress)]
+        );
+    }
+
+    #[test]
+    fn chunk_bitmap_marks_held_chunks() {
+        let (complete_file_storage, file_key, _, chunks) = build_file_storage();
+
+        // The file is stored with only some of its chunks.
+        let mut file_storage = InMemoryFileStorage::<FileTrieLayout>::new();
+        file_storage
+            .set_metadata(
+                file_key,
+                complete_file_storage.get_metadata(&file_key).unwrap(),
+            )
+            .unwrap();
+        for chunk_id in [0, 2] {
+            file_storage
+                .write_chunk(&file_key, &chunk_id, &chunks[chunk_id as usize])
+                .unwrap();
+        }
+
+        let bitmap = read_chunk_bitmap(&file_storage, &file_key).unwrap();
+        assert_eq!(bitmap, vec![0b0000_0101]);
+        assert_eq!(
+            decode_chunk_bitmap(&bitmap),
+            vec![true, false, true, false, false, false, false, false]
+        );
+
+        assert!(matches!(
+            read_chunk_bitmap(&file_storage, &H256::zero()),
+            Err(HandleRequestError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn download_of_missing_file_is_bad_request() {
+        let (file_storage, _, _, _) = build_file_storage();
+
+        assert!(matches!(
+            read_chunk_with_proof(&file_storage, &H256::zero(), 0),
+            Err(HandleRequestError::BadRequest(_))
+        ));
+    }
+
+    #[test]
+    fn sustained_load_triggers_and_clears_backpressure() {
+        let mut tracker =
+            BackpressureTracker::new(BACKPRESSURE_HIGH_WATER_MARK, BACKPRESSURE_LOW_WATER_MARK);
+
+        // Load builds up until the high-water mark is reached.
+        for queued in 0..BACKPRESSURE_HIGH_WATER_MARK {
+            assert_eq!(tracker.update(queued), None);
+        }
+        assert_eq!(
+            tracker.update(BACKPRESSURE_HIGH_WATER_MARK),
+            Some(BackpressureTransition::Activated)
+        );
+
+        // Sustained load keeps backpressure active without signaling again.
+        assert_eq!(tracker.update(MAX_FILE_TRANSFER_REQUESTS_QUEUE), None);
+        assert_eq!(tracker.update(BACKPRESSURE_LOW_WATER_MARK + 1), None);
+
+        // Load drains down to the low-water mark.
+        assert_eq!(
+            tracker.update(BACKPRESSURE_LOW_WATER_MARK),
+            Some(BackpressureTransition::Cleared)
+        );
+        assert_eq!(tracker.update(0), None);
+    }
+}
@@ -0,0 +1,143 @@
+use substrate_prometheus_endpoint::{
+    register, Counter, CounterVec, Histogram, HistogramOpts, HistogramVec, Opts, PrometheusError,
+    Registry, U64,
+};
+
+/// Kind of the incoming requests, used as the label of the per-request metrics.
+pub(super) const REQUEST_KIND_UPLOAD: &str = "upload";
+pub(super) const REQUEST_KIND_DOWNLOAD: &str = "download";
+pub(super) const REQUEST_KIND_READ: &str = "read";
+pub(super) const REQUEST_KIND_CHUNK_AVAILABILITY: &str = "chunk_availability";
+pub(super) const REQUEST_KIND_UNKNOWN: &str = "unknown";
+
+/// Prometheus metrics of the [`FileTransferService`](super::FileTransferService).
+///
+/// They are cheap to clone, every clone updating the same registered metrics.
+#[derive(Clone)]
+pub(super) struct Metrics {
+    /// Incoming requests handled, by kind and result.
+    requests: CounterVec<U64>,
+    /// Time taken to handle incoming requests, by kind.
+    request_duration: HistogramVec,
+    /// Chunks uploaded by remote peers and stored.
+    uploads_served: Counter<U64>,
+    /// Chunks downloaded by remote peers.
+    downloads_served: Counter<U64>,
+    /// Bytes of the chunks uploaded by remote peers and stored.
+    bytes_received: Counter<U64>,
+    /// Bytes of the chunks (with their proofs) downloaded by remote peers.
+    bytes_sent: Counter<U64>,
+    /// Reputation penalties applied to peers for sending bad requests.
+    bad_request_penalties: Counter<U64>,
+    /// Size in bytes of the chunks uploaded by remote peers, stored or not.
+    upload_chunk_size: Histogram,
+}
+
+impl Metrics {
+    /// Register the metrics in `registry`.
+    pub(super) fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Self {
+            requests: register(
+                CounterVec::new(
+                    Opts::new(
+                        "storage_hub_file_transfer_requests_total",
+                        "Number of incoming file transfer requests handled, by kind and result",
+                    ),
+                    &["kind", "result"],
+                )?,
+                registry,
+            )?,
+            request_duration: register(
+                HistogramVec::new(
+                    HistogramOpts::new(
+                        "storage_hub_file_transfer_request_duration_seconds",
+                        "Time taken to handle incoming file transfer requests, by kind",
+                    ),
+                    &["kind"],
+                )?,
+                registry,
+            )?,
+            uploads_served: register(
+                Counter::new(
+                    "storage_hub_file_transfer_uploads_served_total",
+                    "Number of chunks uploaded by remote peers and stored",
+                )?,
+                registry,
+            )?,
+            downloads_served: register(
+                Counter::new(
+                    "storage_hub_file_transfer_downloads_served_total",
+                    "Number of chunks downloaded by remote peers",
+                )?,
+                registry,
+            )?,
+            bytes_received: register(
+                Counter::new(
+                    "storage_hub_file_transfer_bytes_received_total",
+                    "Number of bytes of the chunks uploaded by remote peers and stored",
+                )?,
+                registry,
+            )?,
+            bytes_sent: register(
+                Counter::new(
+                    "storage_hub_file_transfer_bytes_sent_total",
+                    "Number of bytes of the chunks and proofs downloaded by remote peers",
+                )?,
+                registry,
+            )?,
+            bad_request_penalties: register(
+                Counter::new(
+                    "storage_hub_file_transfer_bad_request_penalties_total",
+                    "Number of reputation penalties applied to peers for sending bad requests",
+                )?,
+                registry,
+            )?,
+            upload_chunk_size: register(
+                Histogram::with_opts(
+                    HistogramOpts::new(
+                        "storage_hub_file_transfer_upload_chunk_size_bytes",
+                        "Size in bytes of the chunks uploaded by remote peers",
+                    )
+                    .buckets(vec![
+                        1024.0,
+                        16.0 * 1024.0,
+                        64.0 * 1024.0,
+                        256.0 * 1024.0,
+                        1024.0 * 1024.0,
+                        4.0 * 1024.0 * 1024.0,
+                    ]),
+                )?,
+                registry,
+            )?,
+        })
+    }
+
+    /// Record an incoming request of the given kind, handled successfully or not, in `duration_secs`.
+    pub(super) fn on_request_handled(&self, kind: &str, success: bool, duration_secs: f64) {
+        let result = if success { "success" } else { "failure" };
+        self.requests.with_label_values(&[kind, result]).inc();
+        self.request_duration
+            .with_label_values(&[kind])
+            .observe(duration_secs);
+    }
+
+    /// Record a chunk of `size` bytes uploaded by a remote peer, and whether it was stored.
+    pub(super) fn on_chunk_uploaded(&self, size: usize, stored: bool) {
+        self.upload_chunk_size.observe(size as f64);
+        if stored {
+            self.uploads_served.inc();
+            self.bytes_received.inc_by(size as u64);
+        }
+    }
+
+    /// Record a chunk downloaded by a remote peer, sent in `size` bytes along with its proof.
+    pub(super) fn on_chunk_downloaded(&self, size: usize) {
+        self.downloads_served.inc();
+        self.bytes_sent.inc_by(size as u64);
+    }
+
+    /// Record a reputation penalty applied to a peer for sending a bad request.
+    pub(super) fn on_bad_request_penalty(&self) {
+        self.bad_request_penalties.inc();
+    }
+}
@@ -1,17 +1,29 @@
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
-use sc_network::{config::FullNetworkConfiguration, request_responses::ProtocolConfig};
+use file_manager::traits::FileStorage;
+use sc_network::{
+    config::FullNetworkConfiguration,
+    request_responses::{IncomingRequest, ProtocolConfig},
+    ProtocolName,
+};
 use sc_service::Configuration;
 use storage_hub_infra::actor::{ActorHandle, ActorSpawner, TaskSpawner};
+use substrate_prometheus_endpoint::Registry;
+use tokio::sync::RwLock;
 
-pub use self::handler::FileTransferService;
+pub(crate) use self::handler::FileTrieLayout;
+pub use self::handler::{FileTransferNetwork, FileTransferService};
 
+/// For defining the commands that can be sent to the file transfer service.
+pub mod commands;
 /// For defining the events emitted by the file transfer service.
 pub mod events;
 /// For incoming provider requests.
 mod handler;
+/// For the prometheus metrics of the file transfer service.
+mod metrics;
 /// For defining the provider requests protocol schema.
-mod schema;
+pub mod schema;
 
 // TODO determine ideal max request/response sizes (we could technically specify here usize::MAX)
 /// Max size of request packet. (1GB)
@@ -21,24 +33,55 @@ const MAX_REQUEST_PACKET_SIZE_BYTES: u64 = 1 * 1024 * 1024 * 1024;
 /// Max size of response packet. (1GB)
 const MAX_RESPONSE_PACKET_SIZE_BYTES: u64 = 1 * 1024 * 1024 * 1024;
 
-pub async fn spawn_file_transfer_service<Hash: AsRef<[u8]>>(
-    task_spawner: &TaskSpawner,
+// TODO determine the ideal number of concurrent requests once reading and writing chunks involves disk I/O
+/// Max number of incoming requests handled concurrently.
+const MAX_CONCURRENT_REQUESTS: usize = 16;
+
+/// Register the provider requests protocol in the network configuration.
+///
+/// This has to be done before the network is built, while the [`FileTransferService`] can only be
+/// spawned afterwards with [`spawn_file_transfer_service`], since it uses the network to send requests.
+/// Returns the name of the protocol and the receiver of the incoming requests, to be handed to the service.
+pub fn configure_file_transfer_service<Hash: AsRef<[u8]>>(
     genesis_hash: Hash,
     parachain_config: &Configuration,
     net_config: &mut FullNetworkConfiguration,
-) -> ActorHandle<FileTransferService> {
+) -> (ProtocolName, async_channel::Receiver<IncomingRequest>) {
+    let (tx, request_receiver) = async_channel::bounded(handler::MAX_FILE_TRANSFER_REQUESTS_QUEUE);
+
+    let mut protocol_config =
+        generate_protocol_config(genesis_hash, parachain_config.chain_spec.fork_id());
+    protocol_config.inbound_queue = Some(tx);
+
+    let protocol_name = protocol_config.name.clone();
+    net_config.add_request_response_protocol(protocol_config);
+
+    (protocol_name, request_receiver)
+}
+
+pub async fn spawn_file_transfer_service<FS: FileStorage + Send + Sync>(
+    task_spawner: &TaskSpawner,
+    protocol_name: ProtocolName,
+    request_receiver: async_channel::Receiver<IncomingRequest>,
+    network: Arc<dyn FileTransferNetwork>,
+    file_storage: Arc<RwLock<FS>>,
+    metrics_registry: Option<Registry>,
+) -> ActorHandle<FileTransferService<FS>> {
     let task_spawner = task_spawner
         .with_name("file-transfer-service")
         .with_group("network");
 
-    let (file_transfer_service, protocol_config) =
-        FileTransferService::new(genesis_hash, parachain_config.chain_spec.fork_id());
-
-    let file_transfer_service_handle = task_spawner.spawn_actor(file_transfer_service);
-
-    net_config.add_request_response_protocol(protocol_config);
+    let file_transfer_service = FileTransferService::new(
+        protocol_name,
+        request_receiver,
+        network,
+        file_storage,
+        MAX_REQUEST_PACKET_SIZE_BYTES,
+        MAX_CONCURRENT_REQUESTS,
+        metrics_registry,
+    );
 
-    file_transfer_service_handle
+    task_spawner.spawn_actor(file_transfer_service)
 }
 
 /// Generate the provider requests protocol name from the genesis hash and fork id.
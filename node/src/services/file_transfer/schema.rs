@@ -1,7 +1,7 @@
 //! Include sources generated from protobuf definitions.
 
-pub(crate) mod v1 {
-    pub(crate) mod provider {
+pub mod v1 {
+    pub mod provider {
         include!(concat!(env!("OUT_DIR"), "/api.v1.provider.rs"));
     }
 }
@@ -11,21 +11,27 @@ use storage_hub_infra::{
     event_bus::EventHandler,
 };
 
-use crate::tasks::bsp_volunteer_mock::BspVolunteerMockTask;
+use crate::tasks::bsp_delete_file::BspDeleteFileTask;
+use crate::tasks::bsp_volunteer::BspVolunteerTask;
+use crate::tasks::storage_challenge::StorageChallengeTask;
+use crate::tasks::user_upload::UserUploadTask;
 
 use self::{blockchain::handler::BlockchainService, file_transfer::FileTransferService};
 
 pub trait StorageHubHandlerConfig: Send + 'static {
     type FileStorage: FileStorage + Send + Sync;
-    type ForestStorage: ForestStorage + Send + Sync;
+    /// Forest Storage whose keys are the file keys of the files stored, as in the on-chain Forest.
+    type ForestStorage: ForestStorage<LookupKey = [u8; 32]> + Send + Sync;
 }
 
 pub struct StorageHubHandler<S: StorageHubHandlerConfig> {
     pub task_spawner: TaskSpawner,
-    pub file_transfer: ActorHandle<FileTransferService>,
+    pub file_transfer: ActorHandle<FileTransferService<S::FileStorage>>,
     pub blockchain: ActorHandle<BlockchainService>,
     pub file_storage: Arc<RwLock<S::FileStorage>>,
     pub forest_storage: Arc<RwLock<S::ForestStorage>>,
+    /// Maximum number of bytes of files the provider stores, as configured with `--max-capacity`.
+    pub max_capacity: u64,
 }
 
 impl<SHC: StorageHubHandlerConfig> Clone for StorageHubHandler<SHC> {
@@ -36,6 +42,7 @@ impl<SHC: StorageHubHandlerConfig> Clone for StorageHubHandler<SHC> {
             blockchain: self.blockchain.clone(),
             file_storage: self.file_storage.clone(),
             forest_storage: self.forest_storage.clone(),
+            max_capacity: self.max_capacity,
         }
     }
 }
@@ -43,10 +50,11 @@ impl<SHC: StorageHubHandlerConfig> Clone for StorageHubHandler<SHC> {
 impl<S: StorageHubHandlerConfig> StorageHubHandler<S> {
     pub fn new(
         task_spawner: TaskSpawner,
-        file_transfer: ActorHandle<FileTransferService>,
+        file_transfer: ActorHandle<FileTransferService<S::FileStorage>>,
         blockchain: ActorHandle<BlockchainService>,
         file_storage: Arc<RwLock<S::FileStorage>>,
         forest_storage: Arc<RwLock<S::ForestStorage>>,
+        max_capacity: u64,
     ) -> Self {
         Self {
             task_spawner,
@@ -54,14 +62,30 @@ impl<S: StorageHubHandlerConfig> StorageHubHandler<S> {
             blockchain,
             file_storage,
             forest_storage,
+            max_capacity,
         }
     }
 
     pub fn start_bsp_tasks(&self) {
         log::info!("Starting BSP tasks");
 
-        // TODO: Start the actual BSP tasks here and remove mock task.
-        BspVolunteerMockTask::new(self.clone())
+        BspVolunteerTask::new(self.clone())
+            .subscribe_to(&self.task_spawner, &self.blockchain)
+            .start();
+
+        StorageChallengeTask::new(self.clone())
+            .subscribe_to(&self.task_spawner, &self.blockchain)
+            .start();
+
+        BspDeleteFileTask::new(self.clone())
+            .subscribe_to(&self.task_spawner, &self.blockchain)
+            .start();
+    }
+
+    pub fn start_user_tasks(&self) {
+        log::info!("Starting User tasks");
+
+        UserUploadTask::new(self.clone())
             .subscribe_to(&self.task_spawner, &self.blockchain)
             .start();
     }
@@ -0,0 +1,88 @@
+use anyhow::anyhow;
+use file_manager::traits::{FileStorage, FileStorageError};
+use log::{debug, info};
+use storage_hub_infra::{event_bus::EventHandler, types::Key};
+
+use crate::services::{
+    blockchain::{commands::BlockchainServiceInterface, events::StorageRequestRevoked},
+    StorageHubHandler, StorageHubHandlerConfig,
+};
+
+const LOG_TARGET: &str = "bsp-delete-file-task";
+
+/// Task deleting from the File Storage the files of storage requests revoked by their owners.
+///
+/// When a storage request is revoked, the BSPs that volunteered for it are released on-chain, so
+/// the file (or the chunks downloaded so far) no longer needs to be kept by this node if it is one of them.
+pub struct BspDeleteFileTask<SHC: StorageHubHandlerConfig> {
+    storage_hub_handler: StorageHubHandler<SHC>,
+}
+
+impl<SHC: StorageHubHandlerConfig> Clone for BspDeleteFileTask<SHC> {
+    fn clone(&self) -> BspDeleteFileTask<SHC> {
+        Self {
+            storage_hub_handler: self.storage_hub_handler.clone(),
+        }
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> BspDeleteFileTask<SHC> {
+    pub fn new(storage_hub_handler: StorageHubHandler<SHC>) -> Self {
+        Self {
+            storage_hub_handler,
+        }
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> EventHandler<StorageRequestRevoked> for BspDeleteFileTask<SHC> {
+    async fn handle_event(&self, event: StorageRequestRevoked) -> anyhow::Result<()> {
+        // TODO: Use the file key derived from the file metadata once the runtime exposes it.
+        let file_key: Key = event.fingerprint;
+        let location = String::from_utf8_lossy(&event.location).into_owned();
+
+        // Only the BSPs released from the storage request stop storing the file.
+        let account_id = self.storage_hub_handler.blockchain.get_account_id().await;
+        if !event.bsps.contains(&account_id) {
+            debug!(target: LOG_TARGET, "This node was not released from the revoked storage request at {}, ignoring", location);
+            return Ok(());
+        }
+
+        let mut file_storage = self.storage_hub_handler.file_storage.write().await;
+
+        let metadata = match file_storage.get_metadata(&file_key) {
+            Ok(metadata) => metadata,
+            Err(FileStorageError::FileDoesNotExist) => {
+                debug!(target: LOG_TARGET, "File {:?} of revoked storage request is not held by this node, ignoring", file_key);
+                return Ok(());
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Failed to get the metadata of file {:?}: {:?}",
+                    file_key,
+                    e
+                ))
+            }
+        };
+
+        // The same file could be held for a storage request at another location.
+        if metadata.location != location {
+            debug!(target: LOG_TARGET, "File {:?} is held under another location than {}, ignoring", file_key, location);
+            return Ok(());
+        }
+
+        // TODO: Remove the file from this BSP's forest as well, if it was already confirmed.
+        file_storage
+            .delete_file(&file_key)
+            .map_err(|e| anyhow!("Failed to delete file {:?}: {:?}", file_key, e))?;
+
+        info!(
+            target: LOG_TARGET,
+            "Deleted file {:?} of revoked storage request at {} ({} BSPs released)",
+            file_key,
+            location,
+            event.bsps.len()
+        );
+
+        Ok(())
+    }
+}
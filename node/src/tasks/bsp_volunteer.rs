@@ -0,0 +1,437 @@
+use std::{ops::Range, time::Duration};
+
+use anyhow::anyhow;
+use codec::Decode;
+use file_manager::{
+    traits::{FileStorage, FileStorageError},
+    verify::verify_file_proof,
+};
+use futures::StreamExt;
+use libp2p_identity::PeerId;
+use log::{debug, error, info, warn};
+use sc_network::Multiaddr;
+use storage_hub_infra::{
+    actor::ActorHandle,
+    event_bus::EventHandler,
+    types::{ChunkId, FileProof, Key},
+};
+
+use crate::services::{
+    blockchain::{
+        commands::BlockchainServiceInterface, events::NewStorageRequest,
+        handler::BlockchainService, types::ExtrinsicResult,
+    },
+    file_transfer::{commands::FileTransferServiceInterface, FileTrieLayout},
+    StorageHubHandler, StorageHubHandlerConfig,
+};
+
+const LOG_TARGET: &str = "bsp-volunteer-task";
+
+/// Time to wait for the volunteer extrinsic to be included in a block before giving up.
+const VOLUNTEER_INCLUSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Task volunteering this BSP for new storage requests it is eligible for, and downloading the
+/// file from the user once accepted.
+///
+/// A BSP is eligible to volunteer for a storage request when the XOR distance between its ID and
+/// the fingerprint of the file is under the global [`BspsAssignmentThreshold`](pallet_file_system::BspsAssignmentThreshold).
+pub struct BspVolunteerTask<SHC: StorageHubHandlerConfig> {
+    storage_hub_handler: StorageHubHandler<SHC>,
+}
+
+impl<SHC: StorageHubHandlerConfig> Clone for BspVolunteerTask<SHC> {
+    fn clone(&self) -> BspVolunteerTask<SHC> {
+        Self {
+            storage_hub_handler: self.storage_hub_handler.clone(),
+        }
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> BspVolunteerTask<SHC> {
+    pub fn new(storage_hub_handler: StorageHubHandler<SHC>) -> Self {
+        Self {
+            storage_hub_handler,
+        }
+    }
+
+    /// Check whether this node is a BSP eligible to volunteer for the storage request.
+    async fn is_eligible(&self, event: &NewStorageRequest) -> anyhow::Result<bool> {
+        let Some(bsp_id) = self.storage_hub_handler.blockchain.get_bsp_id().await? else {
+            warn!(target: LOG_TARGET, "This node is not registered as a BSP, not volunteering");
+            return Ok(false);
+        };
+
+        let Some(threshold) = self
+            .storage_hub_handler
+            .blockchain
+            .get_storage_request_threshold(event.location.clone())
+            .await?
+        else {
+            warn!(target: LOG_TARGET, "Storage request no longer exists, not volunteering");
+            return Ok(false);
+        };
+
+        let bsp_xor = pallet_file_system::Pallet::<storage_hub_runtime::Runtime>::compute_bsp_xor(
+            &event.fingerprint.to_fixed_bytes(),
+            &bsp_id.to_fixed_bytes(),
+        )
+        .map_err(|e| {
+            anyhow!(
+                "Failed to compute the XOR distance to the fingerprint: {:?}",
+                e
+            )
+        })?;
+
+        // TODO: Volunteer later for requests this BSP becomes eligible for as their threshold grows.
+        debug!(
+            target: LOG_TARGET,
+            "BSP XOR distance: {:?}, threshold: {:?}", bsp_xor, threshold
+        );
+        Ok(bsp_xor <= threshold)
+    }
+
+    /// Check whether a file of `size` bytes fits in the capacity of this node, along with the files it
+    /// already stores.
+    async fn fits_in_capacity(&self, size: u64) -> anyhow::Result<bool> {
+        let file_storage = self.storage_hub_handler.file_storage.read().await;
+
+        let mut stored_bytes = 0u64;
+        for file_key in file_storage
+            .stored_files()
+            .map_err(|e| anyhow!("Failed to get the stored files: {:?}", e))?
+        {
+            let metadata = file_storage.get_metadata(&file_key).map_err(|e| {
+                anyhow!("Failed to get the metadata of file {:?}: {:?}", file_key, e)
+            })?;
+            stored_bytes = stored_bytes.saturating_add(metadata.size);
+        }
+
+        Ok(stored_bytes.saturating_add(size) <= self.storage_hub_handler.max_capacity)
+    }
+
+    /// Get the ranges of chunks of a file held by a peer, so that only those are requested.
+    ///
+    /// If the peer does not tell which chunks it holds, all the chunks of the file are requested.
+    async fn held_chunk_ranges(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        chunk_count: u64,
+    ) -> Vec<Range<ChunkId>> {
+        let availability = match self
+            .storage_hub_handler
+            .file_transfer
+            .chunk_availability(peer_id, file_key)
+            .await
+        {
+            Ok(availability) => availability,
+            Err(e) => {
+                debug!(target: LOG_TARGET, "Failed to get the chunks held by peer {}, requesting all of them: {}", peer_id, e);
+                return vec![0..chunk_count];
+            }
+        };
+
+        let mut chunk_ranges = Vec::new();
+        let mut range_start = None;
+        for chunk_id in 0..chunk_count {
+            let held = availability
+                .get(chunk_id as usize)
+                .copied()
+                .unwrap_or(false);
+            match (held, range_start) {
+                (true, None) => range_start = Some(chunk_id),
+                (false, Some(start)) => {
+                    chunk_ranges.push(start..chunk_id);
+                    range_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = range_start {
+            chunk_ranges.push(start..chunk_count);
+        }
+
+        chunk_ranges
+    }
+
+    /// Register the multiaddresses of the user announced in the storage request, returning the peers they
+    /// belong to.
+    ///
+    /// Multiaddresses are announced in their text form. The ones that cannot be parsed or have no valid `/p2p/`
+    /// component are skipped.
+    async fn connect_user_peers(&self, event: &NewStorageRequest) -> Vec<PeerId> {
+        let mut peer_ids = Vec::new();
+
+        for multiaddress in event.user_multiaddresses.iter() {
+            let multiaddress =
+                match std::str::from_utf8(&multiaddress[..]).map(str::parse::<Multiaddr>) {
+                    Ok(Ok(multiaddress)) => multiaddress,
+                    Ok(Err(e)) => {
+                        warn!(target: LOG_TARGET, "Skipping invalid user multiaddress: {:?}", e);
+                        continue;
+                    }
+                    Err(e) => {
+                        warn!(target: LOG_TARGET, "Skipping non UTF-8 user multiaddress: {:?}", e);
+                        continue;
+                    }
+                };
+
+            match self
+                .storage_hub_handler
+                .file_transfer
+                .connect_provider(vec![multiaddress])
+                .await
+            {
+                Ok(peer_id) if !peer_ids.contains(&peer_id) => peer_ids.push(peer_id),
+                Ok(_) => {}
+                Err(e) => {
+                    warn!(target: LOG_TARGET, "Skipping user multiaddress: {:?}", e);
+                }
+            }
+        }
+
+        peer_ids
+    }
+
+    /// Download the file of a storage request from the peers of the user, verifying every chunk
+    /// against the fingerprint of the file before storing it.
+    async fn download_file(&self, event: &NewStorageRequest) -> anyhow::Result<()> {
+        // TODO: Use the file key derived from the file metadata once the runtime exposes it.
+        let file_key: Key = event.fingerprint;
+        let chunk_count = {
+            let mut file_storage = self.storage_hub_handler.file_storage.write().await;
+
+            let metadata = file_storage
+                .new_metadata(event.fingerprint, event.size as u64)
+                .with_owner(event.who.to_string())
+                .with_location(String::from_utf8_lossy(&event.location).into_owned());
+            let chunk_count = metadata.chunk_count();
+
+            file_storage.set_metadata(file_key, metadata).map_err(|e| {
+                anyhow!("Failed to set the metadata of file {:?}: {:?}", file_key, e)
+            })?;
+            chunk_count
+        };
+
+        let mut peer_ids = Vec::new();
+        for peer_id in event.user_peer_ids.iter() {
+            match PeerId::from_bytes(peer_id) {
+                Ok(peer_id) => peer_ids.push(peer_id),
+                Err(e) => {
+                    warn!(target: LOG_TARGET, "Skipping invalid user peer ID: {:?}", e);
+                }
+            }
+        }
+        for peer_id in self.connect_user_peers(event).await {
+            if !peer_ids.contains(&peer_id) {
+                peer_ids.push(peer_id);
+            }
+        }
+
+        for peer_id in peer_ids {
+            info!(
+                target: LOG_TARGET,
+                "Downloading {} chunks of file {:?} from peer {}", chunk_count, file_key, peer_id
+            );
+
+            // Chunks already downloaded from a previous peer are not requested again.
+            let stored_chunk_ids = self
+                .storage_hub_handler
+                .file_storage
+                .read()
+                .await
+                .stored_chunk_ids(&file_key)
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to get the stored chunks of file {:?}: {:?}",
+                        file_key,
+                        e
+                    )
+                })?;
+            let chunk_ranges = missing_chunk_ranges(
+                self.held_chunk_ranges(peer_id, file_key, chunk_count).await,
+                &stored_chunk_ids,
+            );
+
+            let mut chunk_streams = Vec::new();
+            for chunk_range in chunk_ranges {
+                chunk_streams.push(
+                    self.storage_hub_handler
+                        .file_transfer
+                        .download_file_request(peer_id, file_key, chunk_range)
+                        .await,
+                );
+            }
+            let mut chunks = futures::stream::iter(chunk_streams).flatten();
+
+            while let Some((chunk_id, response)) = chunks.next().await {
+                let response = match response {
+                    Ok(response) => response,
+                    Err(e) => {
+                        warn!(target: LOG_TARGET, "Failed to download chunk {} from peer {}: {}", chunk_id, peer_id, e);
+                        continue;
+                    }
+                };
+
+                let file_proof = match FileProof::decode(
+                    &mut response.file_chunk_with_proof.as_slice(),
+                ) {
+                    Ok(file_proof) => file_proof,
+                    Err(e) => {
+                        warn!(target: LOG_TARGET, "Failed to decode chunk {} from peer {}: {:?}", chunk_id, peer_id, e);
+                        continue;
+                    }
+                };
+
+                if file_proof.proven.first().map(|leaf| leaf.key) != Some(chunk_id) {
+                    warn!(target: LOG_TARGET, "Peer {} served a proof for another chunk than {}", peer_id, chunk_id);
+                    continue;
+                }
+
+                // Never accept a chunk that is not proven to be part of the file being stored.
+                let chunk = match verify_file_proof::<FileTrieLayout>(
+                    &file_proof,
+                    &event.fingerprint,
+                ) {
+                    Ok(chunk) => chunk,
+                    Err(e) => {
+                        warn!(target: LOG_TARGET, "Invalid proof for chunk {} from peer {}: {:?}", chunk_id, peer_id, e);
+                        continue;
+                    }
+                };
+
+                match self
+                    .storage_hub_handler
+                    .file_storage
+                    .write()
+                    .await
+                    .write_chunk(&file_key, &chunk_id, &chunk)
+                {
+                    // The chunk was already stored, which is all that is needed.
+                    Ok(_) | Err(FileStorageError::FileChunkAlreadyExists) => {}
+                    Err(e) => {
+                        return Err(anyhow!(
+                            "Failed to store chunk {} of file {:?}: {:?}",
+                            chunk_id,
+                            file_key,
+                            e
+                        ))
+                    }
+                }
+            }
+
+            if self
+                .storage_hub_handler
+                .file_storage
+                .read()
+                .await
+                .is_complete(&file_key)
+                .map_err(|e| {
+                    anyhow!(
+                        "Failed to check if file {:?} is complete: {:?}",
+                        file_key,
+                        e
+                    )
+                })?
+            {
+                info!(target: LOG_TARGET, "File {:?} downloaded successfully", file_key);
+                return Ok(());
+            }
+        }
+
+        Err(anyhow!(
+            "Failed to download file {:?} from any of the user peers",
+            file_key
+        ))
+    }
+}
+
+/// Split `chunk_ranges` in the ranges of the chunks that are not in `stored_chunk_ids`, given in
+/// increasing order.
+fn missing_chunk_ranges(
+    chunk_ranges: Vec<Range<ChunkId>>,
+    stored_chunk_ids: &[ChunkId],
+) -> Vec<Range<ChunkId>> {
+    let mut missing_ranges = Vec::new();
+    for chunk_range in chunk_ranges {
+        let mut range_start = None;
+        for chunk_id in chunk_range.clone() {
+            let stored = stored_chunk_ids.binary_search(&chunk_id).is_ok();
+            match (stored, range_start) {
+                (false, None) => range_start = Some(chunk_id),
+                (true, Some(start)) => {
+                    missing_ranges.push(start..chunk_id);
+                    range_start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(start) = range_start {
+            missing_ranges.push(start..chunk_range.end);
+        }
+    }
+
+    missing_ranges
+}
+
+impl<SHC: StorageHubHandlerConfig> EventHandler<NewStorageRequest> for BspVolunteerTask<SHC> {
+    async fn handle_event(&self, event: NewStorageRequest) -> anyhow::Result<()> {
+        info!(
+            target: LOG_TARGET,
+            "Received new storage request for location: {:?}, fingerprint: {:?}",
+            event.location,
+            event.fingerprint
+        );
+
+        if !self.is_eligible(&event).await? {
+            info!(
+                target: LOG_TARGET,
+                "Not eligible to volunteer for fingerprint {:?}", event.fingerprint
+            );
+            return Ok(());
+        }
+
+        if !self.fits_in_capacity(event.size as u64).await? {
+            warn!(
+                target: LOG_TARGET,
+                "Not enough capacity left to store {} bytes for fingerprint {:?}, not volunteering",
+                event.size,
+                event.fingerprint
+            );
+            return Ok(());
+        }
+
+        // Build extrinsic.
+        let call =
+            storage_hub_runtime::RuntimeCall::FileSystem(pallet_file_system::Call::bsp_volunteer {
+                location: event.location.clone(),
+                fingerprint: event.fingerprint,
+            });
+
+        let extrinsic_in_block = self
+            .storage_hub_handler
+            .blockchain
+            .send_extrinsic_and_wait_for_inclusion(call, VOLUNTEER_INCLUSION_TIMEOUT)
+            .await?;
+
+        // Check if the extrinsic was successful.
+        let extrinsic_successful = ActorHandle::<BlockchainService>::extrinsic_result(extrinsic_in_block)
+            .expect("Extrinsic does not contain an ExtrinsicFailed nor ExtrinsicSuccess event, which is not possible; qed");
+        match extrinsic_successful {
+            ExtrinsicResult::Success { dispatch_info } => {
+                info!(target: LOG_TARGET, "Volunteered successfully with dispatch info: {:?}", dispatch_info);
+            }
+            ExtrinsicResult::Failure {
+                dispatch_error,
+                decoded_error,
+                dispatch_info,
+            } => {
+                error!(target: LOG_TARGET, "Failed to volunteer with dispatch error: {:?} ({:?}), dispatch info: {:?}", decoded_error, dispatch_error, dispatch_info);
+                return Err(anyhow!("Extrinsic failed"));
+            }
+        }
+
+        // TODO: Confirm storing the file once it is downloaded and added to this BSP's forest.
+        self.download_file(&event).await
+    }
+}
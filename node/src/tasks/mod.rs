@@ -1,6 +1,10 @@
 // TODO: Remove this once we don't need the examples in this file
 #![allow(dead_code)]
+pub mod bsp_delete_file;
+pub mod bsp_volunteer;
 pub mod bsp_volunteer_mock;
+pub mod storage_challenge;
+pub mod user_upload;
 
 use sc_tracing::tracing::info;
 use storage_hub_infra::event_bus::EventHandler;
@@ -36,12 +40,10 @@ where
 {
     async fn handle_event(&self, event: RemoteUploadRequest) -> anyhow::Result<()> {
         info!(
-            "[ResolveRemoteUploadRequest] - file location: {}",
-            event.location
+            "[ResolveRemoteUploadRequest] - file key: {:?}, file location: {}",
+            event.file_key, event.location
         );
 
-        // self.storage_hub_handler.storage.store_chunk().await?;
-
         Ok(())
     }
 }
@@ -0,0 +1,119 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use forest_manager::traits::ForestStorage;
+use log::{error, info};
+use storage_hub_infra::{actor::ActorHandle, event_bus::EventHandler, types::Key};
+
+use crate::services::{
+    blockchain::{
+        commands::BlockchainServiceInterface, events::NewChallenge, handler::BlockchainService,
+        types::ExtrinsicResult,
+    },
+    StorageHubHandler, StorageHubHandlerConfig,
+};
+
+const LOG_TARGET: &str = "storage-challenge-task";
+
+/// Time to wait for the proof extrinsic to be included in a block before giving up.
+const PROOF_INCLUSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Task responding to on-chain storage challenges for files this Storage Provider holds.
+///
+/// When a file key is challenged, a proof of it and of the random challenges of the block it was challenged in is
+/// generated from the Forest Storage and submitted to the proofs-dealer pallet.
+pub struct StorageChallengeTask<SHC: StorageHubHandlerConfig> {
+    storage_hub_handler: StorageHubHandler<SHC>,
+}
+
+impl<SHC: StorageHubHandlerConfig> Clone for StorageChallengeTask<SHC> {
+    fn clone(&self) -> StorageChallengeTask<SHC> {
+        Self {
+            storage_hub_handler: self.storage_hub_handler.clone(),
+        }
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> StorageChallengeTask<SHC> {
+    pub fn new(storage_hub_handler: StorageHubHandler<SHC>) -> Self {
+        Self {
+            storage_hub_handler,
+        }
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> EventHandler<NewChallenge> for StorageChallengeTask<SHC> {
+    async fn handle_event(&self, event: NewChallenge) -> anyhow::Result<()> {
+        let file_key: Key = event.key_challenged;
+
+        // The proof has to answer the random challenges of the challenge block as well, which are derived from its
+        // hash the same way the runtime does.
+        let mut challenges =
+            pallet_proofs_dealer::Pallet::<storage_hub_runtime::Runtime>::random_challenges(
+                &event.challenge_block_hash,
+            );
+        challenges.push(file_key);
+        let challenges: Vec<_> = challenges
+            .into_iter()
+            .map(|challenge| challenge.to_fixed_bytes())
+            .collect();
+
+        // Generate the proof while holding the Forest Storage lock, releasing it before submitting.
+        // The runtime checks the proof against the on-chain root of this Storage Provider, which commits to the file
+        // keys it stores, so the challenged keys are proven against the Forest root. If a key is not in the Forest,
+        // its neighbouring keys are proven instead, showing that it is not stored.
+        let forest_proof = {
+            let forest_storage = self.storage_hub_handler.forest_storage.read().await;
+
+            forest_storage.generate_proof(&challenges).map_err(|e| {
+                anyhow!(
+                    "Failed to generate Forest proof for {:?}: {:?}",
+                    file_key,
+                    e
+                )
+            })?
+        };
+
+        info!(
+            target: LOG_TARGET,
+            "Responding to challenge for file {:?} with a proof against Forest root {:?}",
+            file_key,
+            forest_proof.root
+        );
+
+        // Build extrinsic.
+        let call = storage_hub_runtime::RuntimeCall::ProofsDealer(
+            pallet_proofs_dealer::Call::submit_proof {
+                proof: forest_proof.proof,
+                root: forest_proof.root,
+                challenge_block: event.challenge_block,
+                provider: None,
+            },
+        );
+
+        let extrinsic_in_block = self
+            .storage_hub_handler
+            .blockchain
+            .send_extrinsic_and_wait_for_inclusion(call, PROOF_INCLUSION_TIMEOUT)
+            .await?;
+
+        // Check if the extrinsic was successful.
+        let extrinsic_successful = ActorHandle::<BlockchainService>::extrinsic_result(extrinsic_in_block.clone())
+            .expect("Extrinsic does not contain an ExtrinsicFailed nor ExtrinsicSuccess event, which is not possible; qed");
+        match extrinsic_successful {
+            ExtrinsicResult::Success { dispatch_info } => {
+                info!(target: LOG_TARGET, "Proof submitted successfully with dispatch info: {:?}", dispatch_info);
+            }
+            ExtrinsicResult::Failure {
+                dispatch_error,
+                decoded_error,
+                dispatch_info,
+            } => {
+                error!(target: LOG_TARGET, "Proof submission failed with dispatch error: {:?} ({:?}), dispatch info: {:?}", decoded_error, dispatch_error, dispatch_info);
+                return Err(anyhow!("Proof submission extrinsic failed"));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use file_manager::traits::{FileStorage, FileStorageError};
+use libp2p_identity::PeerId;
+use log::{debug, info, warn};
+use sc_network::Multiaddr;
+use storage_hub_infra::{
+    event_bus::EventHandler,
+    types::{ChunkId, Key},
+};
+
+use crate::services::{
+    blockchain::events::AcceptedBspVolunteer,
+    file_transfer::commands::FileTransferServiceInterface, StorageHubHandler,
+    StorageHubHandlerConfig,
+};
+
+const LOG_TARGET: &str = "user-upload-task";
+
+/// Maximum number of attempts to upload a chunk to a BSP before giving up on it.
+const MAX_UPLOAD_ATTEMPTS: u32 = 3;
+
+/// Wait before the first retry of a failed chunk upload, doubled on every subsequent retry.
+const UPLOAD_BASE_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Task uploading the files this node issued storage requests for to the BSPs accepted to store them.
+///
+/// When a BSP is accepted as volunteer for a file held in the local File Storage, every chunk of
+/// the file is sent to the BSP along with its proof, so that the BSP can verify it against the
+/// fingerprint of the file.
+pub struct UserUploadTask<SHC: StorageHubHandlerConfig> {
+    storage_hub_handler: StorageHubHandler<SHC>,
+}
+
+impl<SHC: StorageHubHandlerConfig> Clone for UserUploadTask<SHC> {
+    fn clone(&self) -> UserUploadTask<SHC> {
+        Self {
+            storage_hub_handler: self.storage_hub_handler.clone(),
+        }
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> UserUploadTask<SHC> {
+    pub fn new(storage_hub_handler: StorageHubHandler<SHC>) -> Self {
+        Self {
+            storage_hub_handler,
+        }
+    }
+
+    /// Parse the multiaddresses of the BSP, which are advertised on-chain in their human-readable form.
+    ///
+    /// Multiaddresses that cannot be parsed are skipped.
+    fn multiaddresses(event: &AcceptedBspVolunteer) -> Vec<Multiaddr> {
+        event
+            .multiaddresses
+            .iter()
+            .filter_map(|multiaddress| {
+                match std::str::from_utf8(&multiaddress[..]).map(str::parse::<Multiaddr>) {
+                    Ok(Ok(multiaddress)) => Some(multiaddress),
+                    Ok(Err(e)) => {
+                        warn!(target: LOG_TARGET, "Skipping invalid BSP multiaddress: {:?}", e);
+                        None
+                    }
+                    Err(e) => {
+                        warn!(target: LOG_TARGET, "Skipping non UTF-8 BSP multiaddress: {:?}", e);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Upload a chunk of the file to the peer, with its proof.
+    async fn upload_chunk(
+        &self,
+        peer_id: PeerId,
+        file_key: Key,
+        location: &str,
+        chunk_id: ChunkId,
+    ) -> anyhow::Result<()> {
+        // Release the File Storage lock before sending the request.
+        let file_proof = self
+            .storage_hub_handler
+            .file_storage
+            .read()
+            .await
+            .generate_proof(&file_key, &chunk_id)
+            .map_err(|e| anyhow!("Failed to generate proof for chunk {}: {:?}", chunk_id, e))?;
+        let chunk = file_proof
+            .proven
+            .first()
+            .map(|leaf| leaf.data.clone())
+            .ok_or_else(|| anyhow!("Proof of chunk {} does not include the chunk", chunk_id))?;
+
+        let response = self
+            .storage_hub_handler
+            .file_transfer
+            .upload_request_with_retry(
+                peer_id,
+                file_key,
+                location.to_string(),
+                chunk_id,
+                chunk,
+                file_proof.proof,
+                MAX_UPLOAD_ATTEMPTS,
+                UPLOAD_BASE_BACKOFF,
+            )
+            .await
+            .map_err(|e| {
+                anyhow!(
+                    "Failed to upload chunk {} of file {:?} to peer {}: {}",
+                    chunk_id,
+                    file_key,
+                    peer_id,
+                    e
+                )
+            })?;
+
+        if !response.success {
+            return Err(anyhow!("Peer rejected chunk {}", chunk_id));
+        }
+
+        Ok(())
+    }
+}
+
+impl<SHC: StorageHubHandlerConfig> EventHandler<AcceptedBspVolunteer> for UserUploadTask<SHC> {
+    async fn handle_event(&self, event: AcceptedBspVolunteer) -> anyhow::Result<()> {
+        // TODO: Use the file key derived from the file metadata once the runtime exposes it.
+        let file_key: Key = event.fingerprint;
+        let location = String::from_utf8_lossy(&event.location).into_owned();
+
+        // Only upload files this node holds in full, as they were requested to be stored by it.
+        let chunk_count = {
+            let file_storage = self.storage_hub_handler.file_storage.read().await;
+
+            let metadata = match file_storage.get_metadata(&file_key) {
+                Ok(metadata) => metadata,
+                Err(FileStorageError::FileDoesNotExist) => {
+                    debug!(target: LOG_TARGET, "File {:?} accepted for BSP {} is not held by this node, ignoring", file_key, event.who);
+                    return Ok(());
+                }
+                Err(e) => {
+                    return Err(anyhow!(
+                        "Failed to get the metadata of file {:?}: {:?}",
+                        file_key,
+                        e
+                    ))
+                }
+            };
+
+            if metadata.location != location {
+                debug!(target: LOG_TARGET, "File {:?} is held under another location than {}, ignoring", file_key, location);
+                return Ok(());
+            }
+
+            if !file_storage.is_complete(&file_key)? {
+                return Err(anyhow!(
+                    "Cannot upload file {:?} as it is not complete in this node",
+                    file_key
+                ));
+            }
+
+            metadata.chunk_count()
+        };
+
+        let peer_id = self
+            .storage_hub_handler
+            .file_transfer
+            .connect_provider(Self::multiaddresses(&event))
+            .await
+            .map_err(|e| anyhow!("Failed to connect to BSP {}: {:?}", event.who, e))?;
+
+        info!(
+            target: LOG_TARGET,
+            "Uploading {} chunks of file {:?} to BSP {} at peer {}",
+            chunk_count,
+            file_key,
+            event.who,
+            peer_id
+        );
+
+        let mut failed_chunks = 0;
+        for chunk_id in 0..chunk_count {
+            if let Err(e) = self
+                .upload_chunk(peer_id, file_key, &location, chunk_id)
+                .await
+            {
+                warn!(target: LOG_TARGET, "Failed to upload file {:?} to peer {}: {:?}", file_key, peer_id, e);
+                failed_chunks += 1;
+            }
+        }
+
+        if failed_chunks > 0 {
+            return Err(anyhow!(
+                "{} of {} chunks of file {:?} failed to upload to BSP {}",
+                failed_chunks,
+                chunk_count,
+                file_key,
+                event.who
+            ));
+        }
+
+        // The BSP has all the chunks of the file.
+        info!(target: LOG_TARGET, "File {:?} uploaded successfully to BSP {}", file_key, event.who);
+        Ok(())
+    }
+}
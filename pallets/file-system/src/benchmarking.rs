@@ -5,7 +5,9 @@ use super::*;
 #[allow(unused)]
 use crate::Pallet as FileSystem;
 use frame_benchmarking::{benchmarks, impl_benchmark_test_suite, whitelisted_caller};
+use frame_support::traits::fungible::Mutate;
 use frame_system::RawOrigin;
+use sp_runtime::SaturatedConversion;
 
 use crate::types::{FileLocation, Fingerprint, PeerIds, StorageData};
 
@@ -13,11 +15,12 @@ benchmarks! {
     issue_storage_request {
         let s in 0 .. 100;
         let caller: T::AccountId = whitelisted_caller();
+        T::NativeBalance::mint_into(&caller, 1_000_000_000_000u128.saturated_into())?;
         let location: FileLocation<T> = Default::default();
         let fingerprint: Fingerprint<T> = Default::default();
-        let size: StorageData<T> = Default::default();
+        let size: StorageData<T> = 1u32.into();
         let peer_ids: PeerIds<T> = Default::default();
-    }: _(RawOrigin::Signed(caller), location.clone(), fingerprint, size, peer_ids)
+    }: _(RawOrigin::Signed(caller), location.clone(), fingerprint, size, peer_ids, false, None, None, None)
     verify {
         assert!(FileSystem::<T>::storage_requests(location).is_some());
     }
@@ -20,7 +20,7 @@
 //!
 //! ## Hooks
 //!
-//! - `on_idle`: Cleanup all expired storage requests.
+//! - `on_idle`: Cleanup all expired storage requests, renewing the ones that opted in to auto-renewal.
 //!
 //! ## Dependencies
 //!
@@ -41,6 +41,9 @@ mod tests;
 #[cfg(feature = "runtime-benchmarks")]
 mod benchmarking;
 
+#[cfg(feature = "runtime-api")]
+pub mod runtime_api;
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::types::*;
@@ -49,6 +52,7 @@ pub mod pallet {
         dispatch::DispatchResult,
         pallet_prelude::{ValueQuery, *},
         sp_runtime::traits::{AtLeast32Bit, CheckEqual, MaybeDisplay, SimpleBitOps},
+        traits::fungible::*,
     };
     use frame_system::pallet_prelude::{BlockNumberFor, *};
     use scale_info::prelude::fmt::Debug;
@@ -66,15 +70,29 @@ pub mod pallet {
         /// Because this pallet emits events, it depends on the runtime's definition of an event.
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
+        /// Type to access the Balances pallet (using the fungible trait from frame_support)
+        type NativeBalance: Inspect<Self::AccountId>
+            + Mutate<Self::AccountId>
+            + hold::Inspect<Self::AccountId, Reason = Self::RuntimeHoldReason>
+            + hold::Mutate<Self::AccountId, Reason = Self::RuntimeHoldReason>;
+
+        /// The overarching hold reason
+        type RuntimeHoldReason: From<HoldReason>;
+
         /// The trait for reading and mutating storage provider data.
-        type Providers: storage_hub_traits::ReadProvidersInterface<AccountId = Self::AccountId, Provider = <Self::Providers as storage_hub_traits::MutateProvidersInterface>::Provider>
-            + storage_hub_traits::MutateProvidersInterface<AccountId = Self::AccountId, MerklePatriciaRoot = <Self::ProofDealer as storage_hub_traits::ProofsDealerInterface>::MerkleHash>;
+        type Providers: storage_hub_traits::ReadProvidersInterface<AccountId = Self::AccountId, Provider = <Self::Providers as storage_hub_traits::MutateProvidersInterface>::Provider, StorageData = <Self::Providers as storage_hub_traits::MutateProvidersInterface>::StorageData, MerkleHash = <Self::ProofDealer as storage_hub_traits::ProofsDealerInterface>::MerkleHash>
+            + storage_hub_traits::MutateProvidersInterface<AccountId = Self::AccountId, BucketId = <Self as frame_system::Config>::Hash, MerklePatriciaRoot = <Self::ProofDealer as storage_hub_traits::ProofsDealerInterface>::MerkleHash>;
 
         /// The trait for issuing challenges and verifying proofs.
         type ProofDealer: storage_hub_traits::ProofsDealerInterface<
             Provider = <Self::Providers as storage_hub_traits::ProvidersInterface>::Provider,
         >;
 
+        /// The trait for inspecting the structure of a file from a proof of it.
+        type FileProofInspector: storage_hub_traits::FileProofInspector<
+            Proof = <Self::ProofDealer as storage_hub_traits::ProofsDealerInterface>::Proof,
+        >;
+
         /// Type for identifying a file, generally a hash.
         type Fingerprint: Parameter
             + Member
@@ -87,7 +105,8 @@ pub mod pallet {
             + CheckEqual
             + AsRef<[u8]>
             + AsMut<[u8]>
-            + MaxEncodedLen;
+            + MaxEncodedLen
+            + Into<<Self::ProofDealer as storage_hub_traits::ProofsDealerInterface>::MerkleHash>;
 
         /// Type representing the storage request bsps size type.
         type StorageRequestBspsRequiredType: Parameter
@@ -128,14 +147,22 @@ pub mod pallet {
 
         /// The multiplier increases the threshold over time (blocks) which increases the
         /// likelihood of a BSP successfully volunteering to store a file.
+        ///
+        /// The threshold of a storage request grows linearly by this amount for every block since it was issued.
         #[pallet::constant]
         type AssignmentThresholdMultiplier: Get<Self::ThresholdType>;
 
         /// Horizontal asymptote which the volunteering threshold approaches as more BSPs are registered in the system.
+        ///
+        /// This is the lowest value the global [`BspsAssignmentThreshold`] can reach, no matter how many BSPs sign up.
         #[pallet::constant]
         type AssignmentThresholdAsymptote: Get<Self::ThresholdType>;
 
         /// Asymptotic decay function for the assignment threshold.
+        ///
+        /// With `n` BSPs registered, the global threshold is `asymptote + decay_factor^n`, so every BSP signing up
+        /// multiplies the distance to the asymptote by this factor and every BSP signing off divides it by it.
+        /// Values closer to zero make the threshold respond more aggressively to the amount of BSPs.
         #[pallet::constant]
         type AssignmentThresholdDecayFactor: Get<Self::ThresholdType>;
 
@@ -152,6 +179,43 @@ pub mod pallet {
         #[pallet::constant]
         type MaxBspsPerStorageRequest: Get<u32>;
 
+        /// Maximum number of BSPs that can be volunteered for a storage request at the same time.
+        ///
+        /// This bounds the number of entries of a storage request in [`StorageRequestBsps`], and so the weight of
+        /// revoking or cleaning up the storage request. It must not exceed [`Config::MaxBspsPerStorageRequest`] for
+        /// revocations to always fit in a single block.
+        ///
+        /// The actual limit of a storage request is derived from its number of BSPs required, see
+        /// [`Config::VolunteersPerRequiredBsp`], and is capped by this value.
+        #[pallet::constant]
+        type MaxVolunteersPerRequest: Get<u32>;
+
+        /// Number of BSPs that can be volunteered for a storage request per BSP it requires.
+        ///
+        /// A storage request accepts at most `bsps_required * VolunteersPerRequiredBsp` volunteers at the same time,
+        /// capped by [`Config::MaxVolunteersPerRequest`].
+        #[pallet::constant]
+        type VolunteersPerRequiredBsp: Get<u32>;
+
+        /// Maximum replication target a user can specify when issuing a storage request.
+        ///
+        /// This bounds the number of BSPs required to store a file when it overrides [`Config::TargetBspsRequired`].
+        #[pallet::constant]
+        type MaxReplicationTarget: Get<Self::StorageRequestBspsRequiredType>;
+
+        /// Size in bytes of the chunks a file is split into.
+        ///
+        /// Used to derive the number of chunks of a file from its declared size.
+        #[pallet::constant]
+        type FileChunkSize: Get<u64>;
+
+        /// Minimum size of a file that can be requested to be stored.
+        ///
+        /// Storage requests for smaller files are rejected, since their per-request overhead makes them uneconomical
+        /// for BSPs to store. It must be greater than zero, so files of size zero are always rejected.
+        #[pallet::constant]
+        type MinStorageRequestSize: Get<StorageData<Self>>;
+
         /// Maximum byte size of a file path.
         #[pallet::constant]
         type MaxFilePathSize: Get<u32>;
@@ -169,12 +233,38 @@ pub mod pallet {
         type MaxDataServerMultiAddresses: Get<u32>;
 
         /// Time-to-live for a storage request.
+        ///
+        /// Must be greater than zero.
         #[pallet::constant]
         type StorageRequestTtl: Get<u32>;
 
         /// Maximum number of expired storage requests to clean up in a single block.
+        ///
+        /// This is also the number of storage request expirations that can be queued at a single block. Once a block
+        /// is full, expirations are queued at the following blocks, so it must be at least
+        /// [`Config::MaxBatchStorageRequests`] for the expirations not to drift further and further from the TTL when
+        /// storage requests are issued at the highest rate. Since `on_idle` can clean up a block over several calls,
+        /// only cleaning up a single expired storage request has to fit in a block.
         #[pallet::constant]
         type MaxExpiredStorageRequests: Get<u32>;
+
+        /// Maximum number of times a storage request can be automatically renewed.
+        #[pallet::constant]
+        type MaxAutoRenewals: Get<u32>;
+
+        /// Deposit held from the owner of a storage request per unit of data requested to be stored.
+        ///
+        /// The deposit is released when the storage request is fulfilled, revoked or expires.
+        #[pallet::constant]
+        type StorageRequestDepositPerData: Get<BalanceOf<Self>>;
+
+        /// Maximum number of storage requests that can be issued in a single batch.
+        #[pallet::constant]
+        type MaxBatchStorageRequests: Get<u32>;
+
+        /// Maximum number of storage confirmations that a BSP can submit in a single batch.
+        #[pallet::constant]
+        type MaxConfirmBatch: Get<u32>;
     }
 
     #[pallet::pallet]
@@ -185,12 +275,29 @@ pub mod pallet {
     pub type StorageRequests<T: Config> =
         StorageMap<_, Blake2_128Concat, FileLocation<T>, StorageRequestMetadata<T>>;
 
+    /// A double map of owner `AccountId`s to the [`storage requests`](FileLocation) they have open.
+    ///
+    /// It is kept in sync with [`StorageRequests`], so an entry is removed whenever the storage request is fulfilled,
+    /// revoked or expires. Used to enumerate the open storage requests of an owner.
+    #[pallet::storage]
+    pub type StorageRequestsByOwner<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        FileLocation<T>,
+        (),
+        OptionQuery,
+    >;
+
     /// A double map of [`storage request`](FileLocation) to BSP `AccountId`s that volunteered to store data.
     ///
     /// Any BSP under a storage request prefix is considered to be a volunteer and can be removed at any time.
-    /// Once a BSP submits a valid proof to the via the `bsp_confirm_storing` extrinsic, the `confirmed` field in [`StorageRequestBspsMetadata`] will be set to `true`.
+    /// Once a BSP submits a valid proof to the via the `bsp_confirm_storing` extrinsic, the `confirmed` field in [`StorageRequestBspsMetadata`] will be set to `true`
+    /// and the root it committed to will be recorded in its `proven_root` field, for as long as the storage request is pending.
     ///
-    /// When a storage request is expired or removed, the corresponding storage request prefix in this map is removed.
+    /// When a storage request is fulfilled, expired or removed, the corresponding storage request prefix in this map is removed. From then on,
+    /// the root each BSP committed to is only kept as its root in the Providers pallet.
     #[pallet::storage]
     #[pallet::getter(fn storage_request_bsps)]
     pub type StorageRequestBsps<T: Config> = StorageDoubleMap<
@@ -203,6 +310,47 @@ pub mod pallet {
         OptionQuery,
     >;
 
+    /// A double map of BSP `AccountId`s to the [`storage requests`](FileLocation) they volunteered for but have not
+    /// confirmed storing yet, along with the size of their files.
+    ///
+    /// Entries are added when a BSP volunteers, and removed when it confirms storing the file or the storage request
+    /// is fulfilled, revoked or expires. The sizes of the entries of a BSP add up to its
+    /// [`PendingVolunteeredStorage`].
+    #[pallet::storage]
+    pub type PendingVolunteeredRequests<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        FileLocation<T>,
+        StorageData<T>,
+        OptionQuery,
+    >;
+
+    /// A double map of BSP `AccountId`s to the [`storage requests`](FileLocation) of the files they confirmed storing,
+    /// along with the size of the files, which was added to their used data.
+    ///
+    /// Entries are added when a BSP confirms storing a file and removed when it stops storing it, so the data
+    /// released is always the one the BSP was accounted for, even after the storage request is gone.
+    #[pallet::storage]
+    pub type BspConfirmedFiles<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        T::AccountId,
+        Blake2_128Concat,
+        FileLocation<T>,
+        StorageData<T>,
+        OptionQuery,
+    >;
+
+    /// A map of BSP `AccountId`s to the total size of the files of their [`PendingVolunteeredRequests`].
+    ///
+    /// This is the storage a BSP has committed to, which is not yet reflected in its used data. It is kept alongside
+    /// the pending requests so it can be read without iterating over them.
+    #[pallet::storage]
+    pub type PendingVolunteeredStorage<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, StorageData<T>, ValueQuery>;
+
     /// A map of blocks to expired storage requests.
     #[pallet::storage]
     #[pallet::getter(fn storage_request_expirations)]
@@ -227,6 +375,10 @@ pub mod pallet {
     /// If this block is behind the current block number, the cleanup algorithm in `on_idle` will
     /// attempt to accelerate this block pointer as close to or up to the current block number. This
     /// will execute provided that there is enough remaining weight to do so.
+    ///
+    /// Together with the expirations left in [`StorageRequestExpirations`] for this block, it is the cursor from
+    /// which the cleanup resumes: the expirations already processed are removed from the block, so a block cleaned
+    /// up over several `on_idle` calls has each of its expirations processed exactly once.
     #[pallet::storage]
     #[pallet::getter(fn next_starting_block_to_clean_up)]
     pub type NextStartingBlockToCleanUp<T: Config> = StorageValue<_, BlockNumberFor<T>, ValueQuery>;
@@ -280,6 +432,7 @@ pub mod pallet {
             fingerprint: Fingerprint<T>,
             size: StorageData<T>,
             peer_ids: PeerIds<T>,
+            user_multiaddresses: MultiAddresses<T>,
         },
         /// Notifies that a BSP has been accepted to store a given file.
         AcceptedBspVolunteer {
@@ -288,15 +441,46 @@ pub mod pallet {
             fingerprint: Fingerprint<T>,
             multiaddresses: MultiAddresses<T>,
         },
+        /// Notifies that a BSP cancelled its volunteering for a storage request before confirming storing the file.
+        BspVolunteerCancelled {
+            who: T::AccountId,
+            location: FileLocation<T>,
+            fingerprint: Fingerprint<T>,
+        },
+        /// Notifies that the owner of a storage request increased the number of BSPs required by it.
+        ReplicationIncreased {
+            location: FileLocation<T>,
+            bsps_required: T::StorageRequestBspsRequiredType,
+        },
         /// Notifies that a BSP confirmed storing a file.
         BspConfirmedStoring {
             who: T::AccountId,
             location: FileLocation<T>,
         },
-        /// Notifies the expiration of a storage request.
-        StorageRequestExpired { location: FileLocation<T> },
+        /// Notifies that a storage request has been fulfilled, all of its required BSPs having confirmed storing the file.
+        StorageRequestFulfilled { location: FileLocation<T> },
+        /// Notifies that the BSPs that volunteered for a storage request but had not confirmed storing the file yet
+        /// were released from it when it was fulfilled, so they can stop downloading the file.
+        StorageRequestVolunteersReleased {
+            location: FileLocation<T>,
+            bsps: BoundedVec<T::AccountId, MaxBspsPerStorageRequest<T>>,
+        },
+        /// Notifies the expiration of a storage request that did not get enough BSPs to confirm storing the file.
+        StorageRequestExpired {
+            location: FileLocation<T>,
+            bsps_confirmed: T::StorageRequestBspsRequiredType,
+            bsps_required: T::StorageRequestBspsRequiredType,
+        },
+        /// Notifies that an expired storage request has been automatically renewed.
+        StorageRequestAutoRenewed { location: FileLocation<T> },
         /// Notifies that a storage request has been revoked by the user who initiated it.
-        StorageRequestRevoked { location: FileLocation<T> },
+        ///
+        /// `bsps` are the BSPs that had volunteered for the storage request, which should stop storing the file.
+        StorageRequestRevoked {
+            location: FileLocation<T>,
+            fingerprint: Fingerprint<T>,
+            bsps: BoundedVec<T::AccountId, MaxBspsPerStorageRequest<T>>,
+        },
         /// Notifies that a BSP has stopped storing a file.
         BspStoppedStoring {
             bsp: T::AccountId,
@@ -304,6 +488,17 @@ pub mod pallet {
             owner: T::AccountId,
             location: FileLocation<T>,
         },
+        /// Notifies that a MSP has been assigned as a data server of a storage request, so uploaders can push the file to it.
+        DataServerAssigned {
+            location: FileLocation<T>,
+            msp_id: ProviderIdFor<T>,
+        },
+        /// Notifies that the MSP designated by a storage request confirmed storing the file, which was added to one of its buckets.
+        MspConfirmedStoring {
+            who: T::AccountId,
+            location: FileLocation<T>,
+            bucket_id: BucketIdFor<T>,
+        },
     }
 
     // Errors inform users that something went wrong.
@@ -317,6 +512,8 @@ pub mod pallet {
         BspsRequiredCannotBeZero,
         /// BSPs required for storage request cannot exceed the maximum allowed.
         BspsRequiredExceedsMax,
+        /// Replication target for storage request cannot exceed the maximum allowed.
+        ReplicationTargetExceedsMax,
         /// BSP already volunteered to store the given file.
         BspVolunteerFailed,
         /// Account is not a BSP.
@@ -327,10 +524,16 @@ pub mod pallet {
         BspNotConfirmed,
         /// BSP has already confirmed storing the given file.
         BspAlreadyConfirmed,
+        /// BSP does not have enough available capacity to store the given file.
+        InsufficientAvailableCapacity,
         /// Number of BSPs required for storage request has been reached.
         StorageRequestBspsRequiredFulfilled,
         /// BSP already volunteered to store the given file.
         BspAlreadyVolunteered,
+        /// The owner of the file cannot volunteer to store it as a BSP.
+        OwnerCannotVolunteer,
+        /// BSP requested to sign off, so it cannot volunteer to store new files.
+        BspSigningOff,
         /// Number of removed BSPs volunteered from storage request prefix did not match the expected number.
         UnexpectedNumberOfRemovedVolunteeredBsps,
         /// No slot available found in blocks to insert storage request expiration time.
@@ -348,6 +551,8 @@ pub mod pallet {
         FailedToDecodeThreshold,
         /// BSP did not succeed threshold check.
         AboveThreshold,
+        /// Fingerprint provided does not match the one of the storage request.
+        FingerprintMismatch,
         /// Failed to convert block number to threshold.
         FailedToConvertBlockNumber,
         /// Arithmetic error in threshold calculation.
@@ -358,6 +563,35 @@ pub mod pallet {
         DividedByZero,
         /// Failed to get value when just checked it existed.
         ImpossibleFailedToGetValue,
+        /// The file structure implied by the proof does not match the size declared in the storage request.
+        SizeMismatch,
+        /// The proof does not reconstruct the fingerprint of the file.
+        InvalidProof,
+        /// Size of the file being requested to be stored is below the minimum allowed.
+        FileTooSmall,
+        /// Owner of the storage request does not have enough balance to pay the deposit.
+        NotEnoughBalanceForDeposit,
+        /// The Storage Provider designated to store the file in a bucket is not a MSP.
+        NotAMsp,
+        /// The MSP is already a data server of the storage request.
+        DataServerAlreadyAssigned,
+        /// The storage request already has the maximum number of data servers.
+        TooManyDataServers,
+        /// The MSP is not the one designated by the owner of the storage request to store the file.
+        MspNotDesignated,
+        /// The storage request has more volunteered BSPs than can be released in a single block.
+        TooManyVolunteersToRevoke,
+        /// The storage request already has the maximum number of volunteered BSPs.
+        TooManyVolunteers,
+        /// The file key does not match the hash of the metadata of the file.
+        FileKeyMismatch,
+    }
+
+    /// This enum holds the HoldReasons for this pallet, allowing the runtime to identify each held balance with different reasons separately
+    #[pallet::composite_enum]
+    pub enum HoldReason {
+        /// Deposit that a user has to pay to issue a storage request
+        StorageRequestDeposit,
     }
 
     #[pallet::call]
@@ -369,14 +603,31 @@ pub mod pallet {
         }
 
         /// Issue a new storage request for a file
+        ///
+        /// If `auto_renew` is set, the storage request is renewed for another [`Config::StorageRequestTtl`] blocks
+        /// every time it expires, up to [`Config::MaxAutoRenewals`] times or until it is revoked.
+        ///
+        /// If `replication_target` is set, it overrides [`Config::TargetBspsRequired`] as the number of BSPs
+        /// required to store the file. It cannot be zero nor exceed [`Config::MaxReplicationTarget`].
+        ///
+        /// If `msp_id` is set, that MSP can add the file to one of its buckets with `msp_confirm_storing` while the
+        /// storage request is open.
+        ///
+        /// If `user_multiaddresses` is set, BSPs can dial the user at these multiaddresses to fetch the file, on top of
+        /// looking the user up by its `peer_ids`. Each multiaddress is bounded by the maximum multiaddress size of the
+        /// providers pallet.
         #[pallet::call_index(1)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + Pallet::<T>::issue_storage_request_weight())]
         pub fn issue_storage_request(
             origin: OriginFor<T>,
             location: FileLocation<T>,
             fingerprint: Fingerprint<T>,
             size: StorageData<T>,
             peer_ids: PeerIds<T>,
+            auto_renew: bool,
+            replication_target: Option<T::StorageRequestBspsRequiredType>,
+            msp_id: Option<ProviderIdFor<T>>,
+            user_multiaddresses: Option<MultiAddresses<T>>,
         ) -> DispatchResult {
             // Check that the extrinsic was signed and get the signer
             let who = ensure_signed(origin)?;
@@ -387,9 +638,13 @@ pub mod pallet {
                 location.clone(),
                 fingerprint,
                 size,
-                None,
+                replication_target,
+                msp_id,
                 Some(peer_ids.clone()),
+                user_multiaddresses.clone(),
                 Default::default(),
+                auto_renew,
+                true,
             )?;
 
             // BSPs listen to this event and volunteer to store the file
@@ -399,14 +654,23 @@ pub mod pallet {
                 fingerprint,
                 size,
                 peer_ids,
+                user_multiaddresses: user_multiaddresses.unwrap_or_default(),
             });
 
             Ok(())
         }
 
         /// Revoke storage request
+        ///
+        /// The BSPs that volunteered for the storage request are released from it and listed in the
+        /// [`Event::StorageRequestRevoked`] event, so they can stop storing the file. The ones that confirmed storing
+        /// it have its size released from their used data. Fails if there are more volunteers than
+        /// [`Config::MaxBspsPerStorageRequest`].
         #[pallet::call_index(2)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(
+            2 + 8 * T::MaxBspsPerStorageRequest::get() as u64,
+            3 + 6 * T::MaxBspsPerStorageRequest::get() as u64,
+        ))]
         pub fn revoke_storage_request(
             origin: OriginFor<T>,
             location: FileLocation<T>,
@@ -416,10 +680,15 @@ pub mod pallet {
             let who = ensure_signed(origin)?;
 
             // Perform validations and revoke storage request
-            Self::do_revoke_storage_request(who, location.clone(), file_key)?;
+            let (fingerprint, bsps) =
+                Self::do_revoke_storage_request(who, location.clone(), file_key)?;
 
             // Emit event.
-            Self::deposit_event(Event::StorageRequestRevoked { location });
+            Self::deposit_event(Event::StorageRequestRevoked {
+                location,
+                fingerprint,
+                bsps,
+            });
 
             Ok(())
         }
@@ -456,22 +725,33 @@ pub mod pallet {
         }
 
         /// Used by a BSP to confirm they are storing data of a storage request.
+        ///
+        /// `root_proof` shows the transition of the root of the BSP to the one including the file, which becomes its
+        /// new root.
         #[pallet::call_index(5)]
-        #[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + Pallet::<T>::bsp_confirm_storing_weight())]
         pub fn bsp_confirm_storing(
             origin: OriginFor<T>,
             location: FileLocation<T>,
-            root: FileKey<T>,
+            root_proof: RootTransitionProof<T>,
             proof: Proof<T>,
         ) -> DispatchResult {
             // Check that the extrinsic was signed and get the signer.
             let who = ensure_signed(origin)?;
 
             // Perform validations and confirm storage.
-            Self::do_bsp_confirm_storing(who.clone(), location.clone(), root, proof.clone())?;
+            let fulfilled =
+                Self::do_bsp_confirm_storing(who.clone(), location.clone(), root_proof, proof)?;
 
             // Emit event.
-            Self::deposit_event(Event::BspConfirmedStoring { who, location });
+            Self::deposit_event(Event::BspConfirmedStoring {
+                who,
+                location: location.clone(),
+            });
+
+            if fulfilled {
+                Self::deposit_event(Event::StorageRequestFulfilled { location });
+            }
 
             Ok(())
         }
@@ -484,8 +764,14 @@ pub mod pallet {
         /// the BSP gets the data it needs is up to it, but one example could be the assigned MSP.
         /// This metadata is necessary since it is needed to reconstruct the leaf node key in the storage
         /// provider's Merkle Forest.
+        ///
+        /// The BSP has to provide a `proof` that it was storing the file, which must show `file_key` in the Forest committed
+        /// to by its on-chain root, and `file_key` must be the hash of the metadata provided. The data released from the BSP
+        /// is the size of the file recorded when it confirmed storing it, not the `size` provided, which is only used to open
+        /// a new storage request for the file. The root of the BSP is then changed to the one resulting from the transition
+        /// shown by `root_proof`, which should remove `file_key` from its Forest.
         #[pallet::call_index(6)]
-        #[pallet::weight(10_000 + T::DbWeight::get().reads_writes(1,1).ref_time())]
+        #[pallet::weight(10_000 + T::DbWeight::get().reads_writes(4,4).ref_time())]
         pub fn bsp_stop_storing(
             origin: OriginFor<T>,
             file_key: FileKey<T>,
@@ -494,6 +780,8 @@ pub mod pallet {
             fingerprint: Fingerprint<T>,
             size: StorageData<T>,
             can_serve: bool,
+            root_proof: RootTransitionProof<T>,
+            proof: Proof<T>,
         ) -> DispatchResult {
             let who = ensure_signed(origin)?;
 
@@ -506,6 +794,8 @@ pub mod pallet {
                 fingerprint,
                 size,
                 can_serve,
+                root_proof,
+                proof,
             )?;
 
             // Emit event.
@@ -518,6 +808,188 @@ pub mod pallet {
 
             Ok(())
         }
+
+        /// Issue new storage requests for multiple files at once
+        ///
+        /// All the storage requests are issued atomically: if any of them is invalid (e.g. its location is already
+        /// registered or its file is too small), none of them is issued.
+        #[pallet::call_index(7)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + T::DbWeight::get().reads_writes(2, 2).saturating_mul(requests.len() as u64)
+        )]
+        pub fn batch_issue_storage_request(
+            origin: OriginFor<T>,
+            requests: StorageRequestBatch<T>,
+        ) -> DispatchResult {
+            // Check that the extrinsic was signed and get the signer
+            let who = ensure_signed(origin)?;
+
+            // Perform validations and register all the storage requests
+            Self::do_batch_request_storage(who.clone(), requests.clone())?;
+
+            // BSPs listen to these events and volunteer to store the files
+            for (location, fingerprint, size, peer_ids) in requests {
+                Self::deposit_event(Event::NewStorageRequest {
+                    who: who.clone(),
+                    location,
+                    fingerprint,
+                    size,
+                    peer_ids,
+                    user_multiaddresses: Default::default(),
+                });
+            }
+
+            Ok(())
+        }
+
+        /// Used by a BSP to confirm they are storing the data of multiple storage requests at once.
+        ///
+        /// All the confirmations are applied atomically: if any of them is invalid (e.g. the BSP did not volunteer
+        /// for one of the files or one of the proofs is invalid), none of them is applied.
+        #[pallet::call_index(8)]
+        #[pallet::weight(
+            Weight::from_parts(10_000, 0)
+                + Pallet::<T>::bsp_confirm_storing_weight().saturating_mul(confirmations.len() as u64)
+        )]
+        pub fn bsp_confirm_storing_batch(
+            origin: OriginFor<T>,
+            confirmations: BspConfirmationBatch<T>,
+        ) -> DispatchResult {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Perform validations and confirm storage of all the files.
+            let fulfilled = Self::do_bsp_confirm_storing_batch(who.clone(), confirmations.clone())?;
+
+            // Emit events.
+            for ((location, _, _), fulfilled) in confirmations.into_iter().zip(fulfilled) {
+                Self::deposit_event(Event::BspConfirmedStoring {
+                    who: who.clone(),
+                    location: location.clone(),
+                });
+
+                if fulfilled {
+                    Self::deposit_event(Event::StorageRequestFulfilled { location });
+                }
+            }
+
+            Ok(())
+        }
+
+        /// Assign a MSP as a data server of a storage request, from which BSPs can download the file.
+        ///
+        /// Can be called by the owner of the storage request, or by the MSP itself.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(4, 1))]
+        pub fn assign_data_server(
+            origin: OriginFor<T>,
+            location: FileLocation<T>,
+            msp_id: ProviderIdFor<T>,
+        ) -> DispatchResult {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Perform validations and assign the data server.
+            Self::do_assign_data_server(who, location.clone(), msp_id)?;
+
+            // Emit event.
+            Self::deposit_event(Event::DataServerAssigned { location, msp_id });
+
+            Ok(())
+        }
+
+        /// Used by the MSP designated by a storage request to confirm it is storing the file.
+        ///
+        /// This is the MSP analog of `bsp_confirm_storing`: the `proof` must reconstruct the fingerprint of the file, and
+        /// the file is added to the bucket of the MSP whose ID is derived from `bucket_salt`, which is created if it does
+        /// not exist yet. `root_proof` shows the transition of the root of the bucket to the one including the file,
+        /// which becomes its new root. Both happen atomically, so if the file cannot be added to the bucket, the
+        /// confirmation is reverted.
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(6, 5))]
+        pub fn msp_confirm_storing(
+            origin: OriginFor<T>,
+            location: FileLocation<T>,
+            root_proof: RootTransitionProof<T>,
+            proof: Proof<T>,
+            bucket_salt: BucketSalt<T>,
+        ) -> DispatchResult {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Perform validations, confirm storage and add the file to the bucket.
+            let bucket_id = Self::do_msp_confirm_storing(
+                who.clone(),
+                location.clone(),
+                root_proof,
+                proof,
+                bucket_salt,
+            )?;
+
+            // Emit event.
+            Self::deposit_event(Event::MspConfirmedStoring {
+                who,
+                location,
+                bucket_id,
+            });
+
+            Ok(())
+        }
+
+        /// Used by a BSP to cancel its volunteering for a storage request before confirming storing the file.
+        ///
+        /// This is meant for a BSP that cannot get the file, for example because the user is offline, and frees its slot
+        /// for another BSP to volunteer. It fails with `BspAlreadyConfirmed` if the BSP already confirmed storing the
+        /// file, in which case `bsp_stop_storing` has to be used instead.
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(4, 3))]
+        pub fn bsp_cancel_volunteer(
+            origin: OriginFor<T>,
+            location: FileLocation<T>,
+        ) -> DispatchResult {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Perform validations and remove the BSP from the volunteers of the storage request.
+            let fingerprint = Self::do_bsp_cancel_volunteer(who.clone(), location.clone())?;
+
+            // Emit event.
+            Self::deposit_event(Event::BspVolunteerCancelled {
+                who,
+                location,
+                fingerprint,
+            });
+
+            Ok(())
+        }
+
+        /// Used by the owner of a storage request to increase the number of BSPs required to store the file by `additional`.
+        ///
+        /// The storage request keeps accepting volunteers until the new number of BSPs required confirm storing the file.
+        /// The new number of BSPs required cannot exceed [`Config::MaxReplicationTarget`] nor
+        /// [`Config::MaxBspsPerStorageRequest`].
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(1, 1))]
+        pub fn increase_replication(
+            origin: OriginFor<T>,
+            location: FileLocation<T>,
+            additional: T::StorageRequestBspsRequiredType,
+        ) -> DispatchResult {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Perform validations and increase the number of BSPs required by the storage request.
+            let bsps_required = Self::do_increase_replication(who, location.clone(), additional)?;
+
+            // Emit event.
+            Self::deposit_event(Event::ReplicationIncreased {
+                location,
+                bsps_required,
+            });
+
+            Ok(())
+        }
     }
 
     #[pallet::hooks]
@@ -525,6 +997,11 @@ pub mod pallet {
     where
         u32: TryFrom<BlockNumberFor<T>>,
     {
+        /// Clean up the expired storage requests of the blocks not cleaned up yet, up to the current block.
+        ///
+        /// Expirations are processed in pages bounded by the remaining weight, so a block with more expirations than
+        /// fit in the remaining weight is partially cleaned up, and the next call resumes from its first expiration
+        /// not processed yet.
         fn on_idle(current_block: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
             let db_weight = T::DbWeight::get();
 
@@ -533,43 +1010,60 @@ pub mod pallet {
             let start_block = NextStartingBlockToCleanUp::<T>::get();
             let mut block_to_clean = start_block;
 
-            let writes = match T::MaxExpiredStorageRequests::get().checked_add(1) {
-                Some(writes) => writes,
-                // This should never happen. It would mean that MaxExpiredStorageRequests is u32::MAX,
-                // which is an irrational number to set as a limit.
-                None => return Weight::zero(),
-            };
-
             // Total weight used to avoid exceeding the remaining weight.
-            // We count one write for the `NextBlockToCleanup` storage item updated at the end.
             let mut total_used_weight = Weight::zero();
 
-            let required_weight_for_iteration = db_weight.reads_writes(1, writes.into());
+            // One write is always kept aside for the `NextStartingBlockToCleanUp` storage item updated at the end.
+            let weight_to_persist_progress = db_weight.writes(1);
+
+            // Taking the expirations of a block, and writing back the ones not processed if any.
+            let weight_per_block = Self::expired_storage_requests_cleanup_weight(0);
+            // Upper bound of the weight of processing a single expiration.
+            let weight_per_request =
+                Self::expired_storage_requests_cleanup_weight(1).saturating_sub(weight_per_block);
+
+            // Iterate over blocks from the start block to the current block, processing the expirations of each block
+            // until the remaining weight is insufficient to process the next one.
+            while block_to_clean <= current_block {
+                if !remaining_weight.all_gte(
+                    total_used_weight
+                        .saturating_add(weight_per_block)
+                        .saturating_add(weight_to_persist_progress),
+                ) {
+                    break;
+                }
 
-            // Iterate over blocks from the start block to the current block,
-            // cleaning up storage requests until the remaining weight is insufficient
-            while block_to_clean <= current_block
-                && remaining_weight
-                    .all_gte(total_used_weight.saturating_add(required_weight_for_iteration))
-            {
-                let mut used_weight = db_weight.reads(1);
+                total_used_weight += weight_per_block;
                 let expired_requests = StorageRequestExpirations::<T>::take(&block_to_clean);
 
-                // Remove expired storage requests for the block
-                for location in expired_requests {
-                    StorageRequests::<T>::remove(&location);
-                    used_weight += db_weight.writes(1);
-                    Self::deposit_event(Event::StorageRequestExpired { location });
+                let mut processed = 0;
+                for location in expired_requests.iter() {
+                    if !remaining_weight.all_gte(
+                        total_used_weight
+                            .saturating_add(weight_per_request)
+                            .saturating_add(weight_to_persist_progress),
+                    ) {
+                        break;
+                    }
+
+                    total_used_weight += Self::process_expired_storage_request(location.clone());
+                    processed += 1;
+                }
+
+                // Keep the expirations not processed for the next call, which resumes from this block.
+                if processed < expired_requests.len() {
+                    let unprocessed_requests: BoundedVec<_, T::MaxExpiredStorageRequests> =
+                        BoundedVec::truncate_from(
+                            expired_requests.into_inner().split_off(processed),
+                        );
+                    StorageRequestExpirations::<T>::insert(&block_to_clean, unprocessed_requests);
+                    break;
                 }
 
-                // Accumulate the weight used for cleanup operations
-                total_used_weight += used_weight;
                 // Increment the block to clean up for the next iteration
                 block_to_clean = match block_to_clean.checked_add(&1u8.into()) {
                     Some(block) => block,
-                    None => {
-                        return total_used_weight;
-                    }
+                    None => break,
                 };
             }
 
@@ -581,5 +1075,51 @@ pub mod pallet {
 
             total_used_weight
         }
+
+        fn integrity_test() {
+            assert!(
+                T::StorageRequestTtl::get() > 0,
+                "StorageRequestTtl must be greater than zero"
+            );
+
+            assert!(
+                T::MaxVolunteersPerRequest::get() > 0,
+                "MaxVolunteersPerRequest must be greater than zero, otherwise no BSP can volunteer for a storage request"
+            );
+
+            assert!(
+                T::MaxVolunteersPerRequest::get() <= T::MaxBspsPerStorageRequest::get(),
+                "MaxVolunteersPerRequest must not exceed MaxBspsPerStorageRequest, otherwise storage requests with too many volunteers cannot be revoked"
+            );
+
+            assert!(
+                T::VolunteersPerRequiredBsp::get() > 0,
+                "VolunteersPerRequiredBsp must be greater than zero, otherwise no BSP can volunteer for a storage request"
+            );
+
+            assert!(
+                !T::MinStorageRequestSize::get().is_zero(),
+                "MinStorageRequestSize must be greater than zero, otherwise files of size zero can be requested to be stored"
+            );
+
+            assert!(
+                T::MaxExpiredStorageRequests::get() > 0,
+                "MaxExpiredStorageRequests must be greater than zero, otherwise no storage request expiration can be queued"
+            );
+
+            // Storage requests can be issued at a rate of up to a full batch per block, so a block must be able to hold
+            // their expirations for the expiration insertion block not to run away from the TTL.
+            assert!(
+                T::MaxExpiredStorageRequests::get() >= T::MaxBatchStorageRequests::get(),
+                "MaxExpiredStorageRequests must be at least MaxBatchStorageRequests, otherwise storage request expirations are queued further and further away from the StorageRequestTtl"
+            );
+
+            // Blocks can be partially cleaned up, but a single expiration must fit in a block for `on_idle` to make progress.
+            let max_cleanup_weight = Self::expired_storage_requests_cleanup_weight(1);
+            assert!(
+                T::BlockWeights::get().max_block.all_gte(max_cleanup_weight),
+                "Cleaning up a single expired storage request exceeds the maximum block weight"
+            );
+        }
     }
 }
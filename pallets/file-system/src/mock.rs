@@ -5,12 +5,12 @@ use frame_support::{
 };
 use frame_system as system;
 use pallet_proofs_dealer::CompactProof;
-use sp_core::{hashing::blake2_256, ConstU128, ConstU32, ConstU64, Get, H256};
+use sp_core::{hashing::blake2_256, ConstI32, ConstU128, ConstU32, ConstU64, Get, H256};
 use sp_runtime::{
     traits::{BlakeTwo256, Bounded, IdentityLookup},
-    AccountId32, BuildStorage, DispatchResult, FixedU128,
+    AccountId32, BuildStorage, DispatchError, DispatchResult, FixedU128,
 };
-use storage_hub_traits::CommitmentVerifier;
+use storage_hub_traits::{CommitmentVerifier, FileProofInspector, RootTransitionVerifier};
 
 type Block = frame_system::mocking::MockBlock<Test>;
 pub(crate) type BlockNumber = u64;
@@ -67,7 +67,7 @@ construct_runtime!(
     {
         System: frame_system::{Pallet, Call, Config<T>, Storage, Event<T>},
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
-        FileSystem: crate::{Pallet, Call, Storage, Event<T>},
+        FileSystem: crate::{Pallet, Call, Storage, Event<T>, HoldReason},
         Providers: pallet_storage_providers::{Pallet, Call, Storage, Event<T>, HoldReason},
         ProofsDealer: pallet_proofs_dealer::{Pallet, Call, Storage, Event<T>},
     }
@@ -77,6 +77,7 @@ parameter_types! {
     pub const BlockHashCount: u64 = 250;
     pub const SS58Prefix: u8 = 42;
     pub const StorageProvidersHoldReason: RuntimeHoldReason = RuntimeHoldReason::Providers(pallet_storage_providers::HoldReason::StorageProviderDeposit);
+    pub const StorageRequestHoldReason: RuntimeHoldReason = RuntimeHoldReason::FileSystem(crate::HoldReason::StorageRequestDeposit);
 }
 
 #[derive_impl(frame_system::config_preludes::TestDefaultConfig)]
@@ -146,8 +147,15 @@ impl pallet_storage_providers::Config for Test {
     type SpMinCapacity = ConstU32<2>;
     type DepositPerData = ConstU128<2>;
     type Subscribers = FileSystem;
+    type StorageCommitments = FileSystem;
+    type RootTransitionVerifier = MockVerifier;
     type MaxBlocksForRandomness = ConstU64<{ EPOCH_DURATION_IN_BLOCKS * 2 }>;
     type MinBlocksBetweenCapacityChanges = ConstU64<10>;
+    type SignOffCooldown = ConstU64<10>;
+    type MinReputation = ConstI32<-100>;
+    type MaxReputation = ConstI32<100>;
+    type SlashReputationPenalty = ConstI32<10>;
+    type LivenessTimeout = ConstU64<20>;
     type ProvidersRandomness = MockRandomness;
 }
 
@@ -166,12 +174,14 @@ impl pallet_proofs_dealer::Config for Test {
     type MerkleHash = H256;
     type KeyVerifier = MockVerifier;
     type MaxChallengesPerBlock = ConstU32<10>;
+    type RandomChallengesPerBlock = ConstU32<10>;
     type MaxProvidersChallengedPerBlock = ConstU32<10>;
     type ChallengeHistoryLength = ConstU32<10>;
     type ChallengesQueueLength = ConstU32<10>;
     type CheckpointChallengePeriod = ConstU32<10>;
     type ChallengesFee = ConstU128<1_000_000>;
     type Treasury = TreasuryAccount;
+    type SlashAmountPerFailedProof = ConstU128<5>;
 }
 
 /// Structure to mock a verifier that returns `true` when `proof` is not empty
@@ -196,6 +206,55 @@ impl CommitmentVerifier for MockVerifier {
     }
 }
 
+/// Implement the `RootTransitionVerifier` trait for the `MockVerifier` struct, accepting any transition with a non-empty proof
+/// and taking the last encoded node of the proof as the new root.
+impl RootTransitionVerifier for MockVerifier {
+    type Proof = CompactProof;
+    type Key = H256;
+
+    fn apply_transition(
+        _old_root: &Self::Key,
+        proof: &CompactProof,
+    ) -> Result<Self::Key, DispatchError> {
+        match proof.encoded_nodes.last() {
+            Some(new_root) if new_root.len() == 32 => Ok(H256::from_slice(new_root)),
+            _ => Err("Proof does not end with the new root".into()),
+        }
+    }
+
+    fn verify_transition(
+        _old_root: &Self::Key,
+        _new_root: &Self::Key,
+        proof: &CompactProof,
+    ) -> DispatchResult {
+        if proof.encoded_nodes.len() > 0 {
+            Ok(())
+        } else {
+            Err("Proof is empty".into())
+        }
+    }
+}
+
+/// Build the proof of a transition to `new_root` accepted by the [`MockVerifier`].
+pub fn root_transition_proof(new_root: H256) -> CompactProof {
+    CompactProof {
+        encoded_nodes: vec![new_root.as_ref().to_vec()],
+    }
+}
+
+/// Structure to mock a file proof inspector that considers every encoded node in the `proof`
+/// to be a chunk of the file. An empty `proof` is considered to belong to a single chunk file.
+pub struct MockFileProofInspector;
+
+/// Implement the `FileProofInspector` trait for the `MockFileProofInspector` struct.
+impl FileProofInspector for MockFileProofInspector {
+    type Proof = CompactProof;
+
+    fn chunk_count(proof: &CompactProof) -> Option<u64> {
+        Some(proof.encoded_nodes.len().max(1) as u64)
+    }
+}
+
 pub(crate) type ThresholdType = FixedU128;
 
 parameter_types! {
@@ -206,8 +265,11 @@ parameter_types! {
 
 impl crate::Config for Test {
     type RuntimeEvent = RuntimeEvent;
+    type NativeBalance = Balances;
+    type RuntimeHoldReason = RuntimeHoldReason;
     type Providers = Providers;
     type ProofDealer = ProofsDealer;
+    type FileProofInspector = MockFileProofInspector;
     type Fingerprint = H256;
     type StorageRequestBspsRequiredType = u32;
     type ThresholdType = ThresholdType;
@@ -216,12 +278,21 @@ impl crate::Config for Test {
     type AssignmentThresholdMultiplier = ThresholdMultiplier;
     type TargetBspsRequired = ConstU32<3>;
     type MaxBspsPerStorageRequest = ConstU32<5>;
+    type MaxVolunteersPerRequest = ConstU32<5>;
+    type VolunteersPerRequiredBsp = ConstU32<2>;
+    type MaxReplicationTarget = ConstU32<4>;
     type MaxPeerIdSize = ConstU32<100>;
     type MaxNumberOfPeerIds = MaxNumberOfPeerIds;
     type MaxDataServerMultiAddresses = ConstU32<5>; // TODO: this should probably be a multiplier of the number of maximum multiaddresses per storage provider
     type MaxFilePathSize = ConstU32<512u32>;
+    type FileChunkSize = ConstU64<1024>;
+    type MinStorageRequestSize = ConstU32<4>;
     type StorageRequestTtl = ConstU32<40u32>;
     type MaxExpiredStorageRequests = ConstU32<100u32>;
+    type MaxAutoRenewals = ConstU32<2u32>;
+    type MaxBatchStorageRequests = ConstU32<5u32>;
+    type MaxConfirmBatch = ConstU32<5u32>;
+    type StorageRequestDepositPerData = ConstU128<2>;
 }
 
 // Build genesis storage according to the mock runtime.
@@ -245,6 +316,7 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
             (AccountId32::new([5; 32]), 1_000_000_000_000_000),
             (AccountId32::new([6; 32]), 1_000_000_000_000_000),
             (AccountId32::new([7; 32]), 1_000_000_000_000_000),
+            (AccountId32::new([8; 32]), 1_000_000_000_000_000),
         ],
     }
     .assimilate_storage(&mut t)
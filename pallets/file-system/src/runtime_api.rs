@@ -0,0 +1,22 @@
+//! Runtime API to query the BSP assignment logic of this pallet
+//!
+//! Allows node-side BSP tasks to check whether they can volunteer for a storage request with the same logic used
+//! on-chain, instead of replicating it off-chain.
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+    /// API to query the BSP assignment threshold and the eligibility of BSPs to volunteer for a storage request
+    pub trait FileSystemApi<ProviderId, FileLocation, Threshold>
+    where
+        ProviderId: Codec,
+        FileLocation: Codec,
+        Threshold: Codec,
+    {
+        /// Check whether a BSP is eligible to volunteer for the storage request at the given location under its
+        /// current threshold
+        fn is_bsp_eligible(bsp_id: ProviderId, location: FileLocation) -> bool;
+
+        /// Get the current BSP assignment threshold
+        fn current_assignment_threshold() -> Threshold;
+    }
+}
@@ -1,20 +1,25 @@
 use crate::{
     mock::*,
     types::{
-        FileLocation, PeerIds, StorageData, StorageRequestBspsMetadata, StorageRequestMetadata,
-        TargetBspsRequired,
+        BucketSalt, FileLocation, PeerIds, StorageData, StorageRequestBspsMetadata,
+        StorageRequestMetadata, TargetBspsRequired,
     },
-    Config, Error, Event, StorageRequestExpirations,
+    BspConfirmedFiles, Config, Error, Event, PendingVolunteeredRequests, PendingVolunteeredStorage,
+    StorageRequestExpirations,
 };
 use frame_support::{
-    assert_noop, assert_ok, dispatch::DispatchResultWithPostInfo, traits::Hooks, weights::Weight,
+    assert_noop, assert_ok,
+    dispatch::DispatchResultWithPostInfo,
+    traits::{fungible::InspectHold, Hooks},
+    weights::Weight,
 };
+use pallet_storage_providers::types::ValueProposition;
 use sp_core::H256;
 use sp_runtime::{
-    traits::{BlakeTwo256, Get, Hash, Zero},
+    traits::{BlakeTwo256, Bounded, Get, Hash, Zero},
     AccountId32, BoundedVec, FixedU128,
 };
-use storage_hub_traits::SubscribeProvidersInterface;
+use storage_hub_traits::{ProvidersInterface, SubscribeProvidersInterface};
 
 #[test]
 fn request_storage_success() {
@@ -35,6 +40,10 @@ fn request_storage_success() {
             fingerprint,
             size,
             peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
         ));
 
         // Assert that the storage was updated
@@ -46,10 +55,15 @@ fn request_storage_success() {
                 fingerprint,
                 size,
                 user_peer_ids: peer_ids.clone(),
+                user_multiaddresses: Default::default(),
                 data_server_sps: BoundedVec::default(),
                 bsps_required: TargetBspsRequired::<Test>::get(),
                 bsps_confirmed: 0,
                 bsps_volunteered: 0,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
             })
         );
 
@@ -61,12 +75,206 @@ fn request_storage_success() {
                 fingerprint,
                 size: 4,
                 peer_ids,
+                user_multiaddresses: Default::default(),
             }
             .into(),
         );
     });
 }
 
+#[test]
+fn request_storage_with_user_multiaddresses_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let user = RuntimeOrigin::signed(owner_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let user_multiaddresses = create_sp_multiaddresses();
+
+        assert_ok!(FileSystem::issue_storage_request(
+            user.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            Some(user_multiaddresses.clone()),
+        ));
+
+        // The multiaddresses are kept along with the peer ids of the user
+        let storage_request = FileSystem::storage_requests(location.clone()).unwrap();
+        assert_eq!(storage_request.user_peer_ids, peer_ids);
+        assert_eq!(storage_request.user_multiaddresses, user_multiaddresses);
+
+        // And announced to the BSPs
+        System::assert_last_event(
+            Event::NewStorageRequest {
+                who: owner_account_id,
+                location,
+                fingerprint,
+                size: 4,
+                peer_ids,
+                user_multiaddresses,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn request_storage_with_replication_target_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let user = RuntimeOrigin::signed(owner_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let replication_target: u32 = <Test as Config>::MaxReplicationTarget::get();
+
+        // Dispatch a signed extrinsic.
+        assert_ok!(FileSystem::issue_storage_request(
+            user.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            Some(replication_target),
+            None,
+            None,
+        ));
+
+        // Assert that the replication target overrides the default
+        assert_ne!(replication_target, TargetBspsRequired::<Test>::get());
+        assert_eq!(
+            FileSystem::storage_requests(location)
+                .unwrap()
+                .bsps_required,
+            replication_target
+        );
+    });
+}
+
+#[test]
+fn request_storage_with_zero_replication_target_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let user = RuntimeOrigin::signed(owner_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        assert_noop!(
+            FileSystem::issue_storage_request(
+                user.clone(),
+                location.clone(),
+                fingerprint,
+                4,
+                peer_ids,
+                false,
+                Some(0),
+                None,
+                None,
+            ),
+            Error::<Test>::BspsRequiredCannotBeZero
+        );
+    });
+}
+
+#[test]
+fn request_storage_with_replication_target_exceeding_max_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let user = RuntimeOrigin::signed(owner_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let replication_target: u32 = <Test as Config>::MaxReplicationTarget::get() + 1;
+
+        assert_noop!(
+            FileSystem::issue_storage_request(
+                user.clone(),
+                location.clone(),
+                fingerprint,
+                4,
+                peer_ids,
+                false,
+                Some(replication_target),
+                None,
+                None,
+            ),
+            Error::<Test>::ReplicationTargetExceedsMax
+        );
+    });
+}
+
+#[test]
+fn request_storage_below_min_size_fail() {
+    new_test_ext().execute_with(|| {
+        let user = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let min_size: u32 = <Test as Config>::MinStorageRequestSize::get();
+
+        assert_noop!(
+            FileSystem::issue_storage_request(
+                user.clone(),
+                location.clone(),
+                fingerprint,
+                min_size - 1,
+                Default::default(),
+                false,
+                None,
+                None,
+                None,
+            ),
+            Error::<Test>::FileTooSmall
+        );
+
+        // A file of size zero is rejected as well.
+        assert_noop!(
+            FileSystem::issue_storage_request(
+                user.clone(),
+                location.clone(),
+                fingerprint,
+                0,
+                Default::default(),
+                false,
+                None,
+                None,
+                None,
+            ),
+            Error::<Test>::FileTooSmall
+        );
+
+        // A file of exactly the minimum size is accepted.
+        assert_ok!(FileSystem::issue_storage_request(
+            user,
+            location.clone(),
+            fingerprint,
+            min_size,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+        assert_eq!(
+            FileSystem::storage_requests(location).unwrap().size,
+            min_size
+        );
+    });
+}
+
 #[test]
 fn request_storage_expiration_clear_success() {
     new_test_ext().execute_with(|| {
@@ -86,6 +294,10 @@ fn request_storage_expiration_clear_success() {
             fingerprint,
             size,
             peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
         ));
 
         // Assert that the storage was updated
@@ -97,10 +309,15 @@ fn request_storage_expiration_clear_success() {
                 fingerprint,
                 size,
                 user_peer_ids: peer_ids.clone(),
+                user_multiaddresses: Default::default(),
                 data_server_sps: BoundedVec::default(),
                 bsps_required: TargetBspsRequired::<Test>::get(),
                 bsps_confirmed: 0,
                 bsps_volunteered: 0,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
             })
         );
 
@@ -152,6 +369,10 @@ fn request_storage_expiration_current_block_increment_success() {
             fingerprint,
             4,
             peer_ids,
+            false,
+            None,
+            None,
+            None,
         ));
 
         // Assert that the storage request expirations storage is at max capacity
@@ -210,6 +431,10 @@ fn request_storage_clear_old_expirations_success() {
             fingerprint,
             4,
             peer_ids,
+            false,
+            None,
+            None,
+            None,
         ));
 
         System::set_block_number(expected_expiration_block_number);
@@ -228,8 +453,7 @@ fn request_storage_clear_old_expirations_success() {
         // Assert that the weight used is zero
         assert_eq!(used_weight, Weight::zero());
 
-        // Assert that the storage request expirations storage is at max capacity
-        // TODO: Fix this test...
+        // Assert that the storage request expirations storage is still at max capacity
         assert_eq!(
             FileSystem::storage_request_expirations(expected_expiration_block_number).len(),
             max_storage_request_expiry as usize
@@ -238,14 +462,30 @@ fn request_storage_clear_old_expirations_success() {
         // Assert that the `NextExpirationInsertionBlockNumber` storage did not update
         assert_eq!(FileSystem::next_starting_block_to_clean_up(), 0);
 
-        // Go to block number after which the storage request expirations should be removed
-        roll_to(expected_expiration_block_number + 1);
+        // Run the cleanup with enough weight to go through all the blocks up to the current one
+        let used_weight = FileSystem::on_idle(System::block_number(), Weight::MAX);
 
-        // Assert that the storage request expiration was removed from the list at `StorageRequestTtl`
+        // Assert that the weight used accounts for every block and every expired storage request
+        let db_weight = <Test as frame_system::Config>::DbWeight::get();
+        assert_eq!(
+            used_weight,
+            db_weight
+                .reads_writes(1, 1)
+                .saturating_mul(expected_expiration_block_number + 1)
+                .saturating_add(
+                    db_weight
+                        .reads_writes(3, 4)
+                        .saturating_mul(max_storage_request_expiry.into())
+                )
+                .saturating_add(db_weight.writes(1))
+        );
+
+        // Assert that the storage request expirations were removed and the storage request expired
         assert_eq!(
             FileSystem::storage_request_expirations(expected_expiration_block_number),
             vec![]
         );
+        assert!(FileSystem::storage_requests(location).is_none());
 
         // Assert that the `NextExpirationInsertionBlockNumber` storage is set to the next block number
         assert_eq!(
@@ -256,132 +496,204 @@ fn request_storage_clear_old_expirations_success() {
 }
 
 #[test]
-fn revoke_request_storage_success() {
+fn request_storage_clear_expirations_in_pages_success() {
     new_test_ext().execute_with(|| {
         let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
-        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let file_content = b"test".to_vec();
-        let fingerprint = BlakeTwo256::hash(&file_content);
-        let file_key = H256::zero();
+        let db_weight = <Test as frame_system::Config>::DbWeight::get();
 
-        // Dispatch a signed extrinsic.
-        assert_ok!(FileSystem::issue_storage_request(
-            owner.clone(),
-            location.clone(),
-            fingerprint,
-            4,
-            Default::default()
-        ));
+        let expiration_block: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
 
-        // Assert that the storage request expiration was appended to the list at `StorageRequestTtl`
+        // Queue three storage request expirations in the same block
+        let locations: Vec<FileLocation<Test>> = (0..3u8)
+            .map(|i| FileLocation::<Test>::try_from(vec![i]).unwrap())
+            .collect();
+        for location in &locations {
+            assert_ok!(FileSystem::issue_storage_request(
+                owner.clone(),
+                location.clone(),
+                H256::zero(),
+                4,
+                Default::default(),
+                false,
+                None,
+                None,
+                None,
+            ));
+        }
         assert_eq!(
-            FileSystem::storage_request_expirations(
-                FileSystem::next_expiration_insertion_block_number()
-            ),
-            vec![location.clone()]
+            FileSystem::storage_request_expirations(expiration_block).len(),
+            locations.len()
         );
 
-        // Dispatch a signed extrinsic.
-        assert_ok!(FileSystem::revoke_storage_request(
-            owner.clone(),
-            location.clone(),
-            file_key
-        ));
+        System::set_block_number(expiration_block);
 
-        // Assert that the correct event was deposited
-        System::assert_last_event(Event::StorageRequestRevoked { location }.into());
-    });
-}
+        // Only give enough weight to process two of the three expirations of the block
+        let weight_for_two_expirations = db_weight
+            .reads_writes(1, 1)
+            .saturating_mul(expiration_block + 1)
+            .saturating_add(db_weight.reads_writes(3, 4).saturating_mul(2))
+            .saturating_add(db_weight.writes(1));
 
-#[test]
-fn revoke_non_existing_storage_request_fail() {
-    new_test_ext().execute_with(|| {
-        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
-        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let file_key = H256::zero();
+        let used_weight = FileSystem::on_idle(System::block_number(), weight_for_two_expirations);
+        assert_eq!(used_weight, weight_for_two_expirations);
 
-        assert_noop!(
-            FileSystem::revoke_storage_request(owner.clone(), location.clone(), file_key),
-            Error::<Test>::StorageRequestNotFound
+        // Assert that only the last expiration is left to be processed, from the same block
+        assert!(FileSystem::storage_requests(locations[0].clone()).is_none());
+        assert!(FileSystem::storage_requests(locations[1].clone()).is_none());
+        assert!(FileSystem::storage_requests(locations[2].clone()).is_some());
+        assert_eq!(
+            FileSystem::storage_request_expirations(expiration_block),
+            vec![locations[2].clone()]
+        );
+        assert_eq!(
+            FileSystem::next_starting_block_to_clean_up(),
+            expiration_block
+        );
+
+        // The next call resumes from the expiration left
+        FileSystem::on_idle(System::block_number(), Weight::MAX);
+
+        assert!(FileSystem::storage_requests(locations[2].clone()).is_none());
+        assert_eq!(
+            FileSystem::storage_request_expirations(expiration_block),
+            vec![]
+        );
+        assert_eq!(
+            FileSystem::next_starting_block_to_clean_up(),
+            expiration_block + 1
         );
+
+        // Every storage request expired exactly once
+        let expired_count = System::events()
+            .iter()
+            .filter(|record| {
+                matches!(
+                    record.event,
+                    RuntimeEvent::FileSystem(Event::StorageRequestExpired { .. })
+                )
+            })
+            .count();
+        assert_eq!(expired_count, locations.len());
     });
 }
 
 #[test]
-fn revoke_storage_request_not_owner_fail() {
+fn request_storage_clear_expirations_resumes_on_next_block_success() {
     new_test_ext().execute_with(|| {
-        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
-        let not_owner = RuntimeOrigin::signed(AccountId32::new([2; 32]));
-        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let file_content = b"test".to_vec();
-        let fingerprint = BlakeTwo256::hash(&file_content);
-        let file_key = H256::zero();
+        let first_location = FileLocation::<Test>::try_from(b"first".to_vec()).unwrap();
+        let second_location = FileLocation::<Test>::try_from(b"second".to_vec()).unwrap();
+        let db_weight = <Test as frame_system::Config>::DbWeight::get();
 
-        // Dispatch a signed extrinsic.
-        assert_ok!(FileSystem::issue_storage_request(
-            owner.clone(),
-            location.clone(),
-            fingerprint,
-            4,
-            Default::default()
+        let first_block: BlockNumber = FileSystem::next_expiration_insertion_block_number().into();
+        let second_block = first_block + 1;
+
+        // Queue one storage request expiration in each block
+        assert_ok!(StorageRequestExpirations::<Test>::try_append(
+            first_block,
+            first_location
+        ));
+        assert_ok!(StorageRequestExpirations::<Test>::try_append(
+            second_block,
+            second_location.clone()
         ));
 
-        assert_noop!(
-            FileSystem::revoke_storage_request(not_owner.clone(), location.clone(), file_key),
-            Error::<Test>::StorageRequestNotAuthorized
+        System::set_block_number(second_block);
+
+        // Only give enough weight to clean up to the first block, keeping one write to persist progress
+        let weight_up_to_first_block = db_weight
+            .reads_writes(1, 1)
+            .saturating_mul(first_block + 1)
+            .saturating_add(db_weight.reads_writes(3, 4))
+            .saturating_add(db_weight.writes(1));
+
+        let used_weight = FileSystem::on_idle(System::block_number(), weight_up_to_first_block);
+        assert_eq!(used_weight, weight_up_to_first_block);
+
+        // Assert that the first block was cleaned up but not the second one
+        assert_eq!(FileSystem::storage_request_expirations(first_block), vec![]);
+        assert_eq!(
+            FileSystem::storage_request_expirations(second_block),
+            vec![second_location]
+        );
+        assert_eq!(FileSystem::next_starting_block_to_clean_up(), second_block);
+
+        // The next call resumes from the second block without skipping it
+        FileSystem::on_idle(System::block_number(), Weight::MAX);
+
+        assert_eq!(
+            FileSystem::storage_request_expirations(second_block),
+            vec![]
+        );
+        assert_eq!(
+            FileSystem::next_starting_block_to_clean_up(),
+            second_block + 1
         );
     });
 }
 
 #[test]
-fn bsp_volunteer_success() {
+fn request_storage_auto_renew_success() {
     new_test_ext().execute_with(|| {
-        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        // TODO: right now we are bypassing the volunteer assignment threshold
-        let fingerprint = H256::zero();
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
         let peer_id = BoundedVec::try_from(vec![1]).unwrap();
         let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
-        let storage_amount: StorageData<Test> = 100;
+        let storage_request_ttl: u32 = <Test as Config>::StorageRequestTtl::get();
+        let max_auto_renewals: u32 = <Test as Config>::MaxAutoRenewals::get();
 
-        // Dispatch storage request.
+        // Dispatch a signed extrinsic opting in to auto-renewal.
         assert_ok!(FileSystem::issue_storage_request(
-            owner.clone(),
+            owner_signed.clone(),
             location.clone(),
             fingerprint,
             4,
             peer_ids.clone(),
+            true,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        let mut expiration_block_number: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
 
-        // Dispatch BSP volunteer.
-        assert_ok!(FileSystem::bsp_volunteer(
-            bsp_signed.clone(),
-            location.clone(),
-            fingerprint,
-        ));
+        for renewal in 1..=max_auto_renewals {
+            roll_to(expiration_block_number);
 
-        // Assert that the RequestStorageBsps has the correct value
-        assert_eq!(
-            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone())
-                .expect("BSP should exist in storage"),
-            StorageRequestBspsMetadata::<Test> {
-                confirmed: false,
-                _phantom: Default::default()
-            }
-        );
+            // Assert that the storage request was renewed instead of removed
+            let storage_request = FileSystem::storage_requests(location.clone())
+                .expect("Storage request should have been renewed");
+            assert_eq!(storage_request.auto_renewals, renewal);
+
+            System::assert_last_event(
+                Event::StorageRequestAutoRenewed {
+                    location: location.clone(),
+                }
+                .into(),
+            );
+
+            // Assert that the storage request expiration was queued again
+            expiration_block_number += storage_request_ttl as BlockNumber;
+            assert_eq!(
+                FileSystem::storage_request_expirations(expiration_block_number),
+                vec![location.clone()]
+            );
+        }
+
+        // Once the maximum number of renewals is reached, the storage request expires.
+        roll_to(expiration_block_number);
+
+        assert!(FileSystem::storage_requests(location.clone()).is_none());
 
-        // Assert that the correct event was deposited
         System::assert_last_event(
-            Event::AcceptedBspVolunteer {
-                who: bsp_account_id,
+            Event::StorageRequestExpired {
                 location,
-                fingerprint,
-                multiaddresses: create_sp_multiaddresses(),
+                bsps_confirmed: 0,
+                bsps_required: TargetBspsRequired::<Test>::get(),
             }
             .into(),
         );
@@ -389,174 +701,279 @@ fn bsp_volunteer_success() {
 }
 
 #[test]
-fn bsp_volunteer_storage_request_not_found_fail() {
+fn request_storage_auto_renew_stops_on_revoke_success() {
     new_test_ext().execute_with(|| {
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let fingerprint = H256::zero();
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let file_key = H256::zero();
 
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100,));
+        // Dispatch a signed extrinsic opting in to auto-renewal.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            true,
+            None,
+            None,
+            None,
+        ));
 
-        assert_noop!(
-            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
-            Error::<Test>::StorageRequestNotFound
+        let expiration_block_number: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+
+        // Revoke the storage request before it expires.
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            file_key
+        ));
+
+        roll_to(expiration_block_number);
+
+        // Assert that the storage request was not renewed
+        assert!(FileSystem::storage_requests(location.clone()).is_none());
+        assert_eq!(
+            FileSystem::storage_request_expirations(
+                expiration_block_number + <Test as Config>::StorageRequestTtl::get() as BlockNumber
+            ),
+            vec![]
+        );
+
+        // The revoked storage request is not reported as expired.
+        System::assert_last_event(
+            Event::StorageRequestRevoked {
+                location,
+                fingerprint,
+                bsps: BoundedVec::default(),
+            }
+            .into(),
         );
     });
 }
 
 #[test]
-fn bsp_already_volunteered_failed() {
+fn batch_request_storage_success() {
     new_test_ext().execute_with(|| {
         let owner_account_id = AccountId32::new([1; 32]);
         let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
-        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
-        let file_content = b"test".to_vec();
-        let fingerprint = BlakeTwo256::hash(&file_content);
         let peer_id = BoundedVec::try_from(vec![1]).unwrap();
         let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
-        let storage_amount: StorageData<Test> = 100;
+        let size = 4;
 
-        // Dispatch storage request.
-        assert_ok!(FileSystem::issue_storage_request(
+        let requests: Vec<_> = [b"test1".to_vec(), b"test2".to_vec(), b"test3".to_vec()]
+            .into_iter()
+            .map(|file_content| {
+                (
+                    FileLocation::<Test>::try_from(file_content.clone()).unwrap(),
+                    BlakeTwo256::hash(&file_content),
+                    size,
+                    peer_ids.clone(),
+                )
+            })
+            .collect();
+
+        let expected_expiration_inserted_at_block_number: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+
+        // Dispatch a batch of storage requests.
+        assert_ok!(FileSystem::batch_issue_storage_request(
             owner_signed.clone(),
-            location.clone(),
-            fingerprint,
-            size,
-            peer_ids.clone(),
+            BoundedVec::try_from(requests.clone()).unwrap(),
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        for (location, fingerprint, size, peer_ids) in requests.iter().cloned() {
+            // Assert that the storage was updated
+            assert_eq!(
+                FileSystem::storage_requests(location.clone()),
+                Some(StorageRequestMetadata {
+                    requested_at: 1,
+                    owner: owner_account_id.clone(),
+                    fingerprint,
+                    size,
+                    user_peer_ids: peer_ids.clone(),
+                    user_multiaddresses: Default::default(),
+                    data_server_sps: BoundedVec::default(),
+                    bsps_required: TargetBspsRequired::<Test>::get(),
+                    bsps_confirmed: 0,
+                    bsps_volunteered: 0,
+                    msp: None,
+                    auto_renew: false,
+                    auto_renewals: 0,
+                    deposit_paid: FileSystem::compute_storage_request_deposit(size),
+                })
+            );
+
+            // Assert that an event was deposited for each file
+            System::assert_has_event(
+                Event::NewStorageRequest {
+                    who: owner_account_id.clone(),
+                    location,
+                    fingerprint,
+                    size,
+                    peer_ids,
+                    user_multiaddresses: Default::default(),
+                }
+                .into(),
+            );
+        }
 
-        // Dispatch BSP volunteer.
-        assert_ok!(FileSystem::bsp_volunteer(
-            bsp_signed.clone(),
-            location.clone(),
-            fingerprint,
-        ));
+        // Assert that all the storage request expirations were appended to the same block
+        assert_eq!(
+            FileSystem::storage_request_expirations(expected_expiration_inserted_at_block_number),
+            requests
+                .into_iter()
+                .map(|(location, _, _, _)| location)
+                .collect::<Vec<_>>()
+        );
+    });
+}
+
+#[test]
+fn batch_request_storage_reverts_on_invalid_request_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let location = FileLocation::<Test>::try_from(b"test1".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"test2".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
 
+        // A batch with a duplicated location should not issue any storage request.
         assert_noop!(
-            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
-            Error::<Test>::BspAlreadyVolunteered
+            FileSystem::batch_issue_storage_request(
+                owner_signed.clone(),
+                BoundedVec::try_from(vec![
+                    (location.clone(), fingerprint, 4, peer_ids.clone()),
+                    (other_location.clone(), fingerprint, 4, peer_ids.clone()),
+                    (location.clone(), fingerprint, 4, peer_ids.clone()),
+                ])
+                .unwrap(),
+            ),
+            Error::<Test>::StorageRequestAlreadyRegistered
+        );
+
+        // A batch with a zero sized file should not issue any storage request.
+        assert_noop!(
+            FileSystem::batch_issue_storage_request(
+                owner_signed.clone(),
+                BoundedVec::try_from(vec![
+                    (location.clone(), fingerprint, 4, peer_ids.clone()),
+                    (other_location.clone(), fingerprint, 0, peer_ids.clone()),
+                ])
+                .unwrap(),
+            ),
+            Error::<Test>::FileTooSmall
         );
+
+        assert!(FileSystem::storage_requests(location).is_none());
+        assert!(FileSystem::storage_requests(other_location).is_none());
     });
 }
 
 #[test]
-fn bsp_volunteer_above_threshold_high_fail() {
+fn revoke_request_storage_success() {
     new_test_ext().execute_with(|| {
-        let owner_account_id = AccountId32::new([1; 32]);
-        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
         let file_content = b"test".to_vec();
         let fingerprint = BlakeTwo256::hash(&file_content);
-        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
-        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
-        let storage_amount: StorageData<Test> = 100;
+        let file_key = H256::zero();
 
-        // Dispatch storage request.
+        // Dispatch a signed extrinsic.
         assert_ok!(FileSystem::issue_storage_request(
-            owner_signed.clone(),
+            owner.clone(),
             location.clone(),
             fingerprint,
-            size,
-            peer_ids.clone(),
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        // Assert that the storage request expiration was appended to the list at `StorageRequestTtl`
+        assert_eq!(
+            FileSystem::storage_request_expirations(
+                FileSystem::next_expiration_insertion_block_number()
+            ),
+            vec![location.clone()]
+        );
 
-        crate::BspsAssignmentThreshold::<Test>::put(FixedU128::zero());
+        // Dispatch a signed extrinsic.
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner.clone(),
+            location.clone(),
+            file_key
+        ));
 
-        // Dispatch BSP volunteer.
-        assert_noop!(
-            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
-            Error::<Test>::AboveThreshold
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::StorageRequestRevoked {
+                location,
+                fingerprint,
+                bsps: BoundedVec::default(),
+            }
+            .into(),
         );
     });
 }
 
 #[test]
-fn bsp_confirm_storing_success() {
+fn revoke_request_storage_releases_volunteered_bsps() {
     new_test_ext().execute_with(|| {
-        let owner_account_id = AccountId32::new([1; 32]);
-        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
         let bsp_account_id = AccountId32::new([2; 32]);
         let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
         let fingerprint = H256::zero();
-        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
-        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
-        let storage_amount: StorageData<Test> = 100;
+        let file_key = H256::zero();
 
-        // Dispatch storage request.
         assert_ok!(FileSystem::issue_storage_request(
-            owner_signed.clone(),
+            owner.clone(),
             location.clone(),
             fingerprint,
-            size,
-            peer_ids.clone(),
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
-
-        // Dispatch BSP volunteer.
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
         assert_ok!(FileSystem::bsp_volunteer(
             bsp_signed.clone(),
             location.clone(),
             fingerprint,
         ));
 
-        // Dispatch BSP confirm storing.
-        assert_ok!(FileSystem::bsp_confirm_storing(
-            bsp_signed.clone(),
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner.clone(),
             location.clone(),
-            H256::zero(), // TODO construct a real proof
-            pallet_proofs_dealer::CompactProof {
-                encoded_nodes: vec![],
-            }
+            file_key
         ));
 
-        // Assert that the storage was updated
-        assert_eq!(
-            FileSystem::storage_requests(location.clone()),
-            Some(StorageRequestMetadata {
-                requested_at: 1,
-                owner: owner_account_id.clone(),
-                fingerprint,
-                size,
-                user_peer_ids: peer_ids.clone(),
-                data_server_sps: BoundedVec::default(),
-                bsps_required: TargetBspsRequired::<Test>::get(),
-                bsps_confirmed: 1,
-                bsps_volunteered: 1,
-            })
-        );
-
-        // Assert that the RequestStorageBsps was updated
-        assert_eq!(
-            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone())
-                .expect("BSP should exist in storage"),
-            StorageRequestBspsMetadata::<Test> {
-                confirmed: true,
-                _phantom: Default::default()
-            }
+        // The volunteer is no longer tracked for the revoked storage request.
+        assert!(
+            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone()).is_none()
         );
+        assert!(!PendingVolunteeredRequests::<Test>::contains_key(
+            &bsp_account_id,
+            &location
+        ));
 
-        // Assert that the correct event was deposited
+        // And it is notified to stop storing the file.
         System::assert_last_event(
-            Event::BspConfirmedStoring {
-                who: bsp_account_id,
+            Event::StorageRequestRevoked {
                 location,
+                fingerprint,
+                bsps: BoundedVec::try_from(vec![bsp_account_id]).unwrap(),
             }
             .into(),
         );
@@ -564,180 +981,310 @@ fn bsp_confirm_storing_success() {
 }
 
 #[test]
-fn bsp_confirm_storing_storage_request_not_found_fail() {
+fn revoke_request_storage_releases_data_of_confirmed_bsps() {
     new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
         let bsp_account_id = AccountId32::new([2; 32]);
         let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let file_key = H256::zero();
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100,));
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
 
-        assert_noop!(
-            FileSystem::bsp_confirm_storing(
-                bsp_signed.clone(),
-                location.clone(),
-                H256::zero(), // TODO construct a real proof
-                pallet_proofs_dealer::CompactProof {
-                    encoded_nodes: vec![],
-                }
-            ),
-            Error::<Test>::StorageRequestNotFound
-        );
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+        let bsp_id = Providers::get_provider(bsp_account_id.clone()).unwrap();
+        assert_eq!(Providers::get_used_storage_of_bsp(&bsp_id).unwrap(), size);
+
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner.clone(),
+            location.clone(),
+            file_key
+        ));
+
+        // The data of the file is released from the BSP that confirmed storing it.
+        assert_eq!(Providers::get_used_storage_of_bsp(&bsp_id).unwrap(), 0);
+        assert!(!BspConfirmedFiles::<Test>::contains_key(
+            &bsp_account_id,
+            &location
+        ));
     });
 }
 
 #[test]
-fn bsp_confirm_storing_not_volunteered_fail() {
+fn revoke_request_storage_with_max_volunteers_success() {
     new_test_ext().execute_with(|| {
-        let owner_account_id = AccountId32::new([1; 32]);
-        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
-        let file_content = b"test".to_vec();
-        let fingerprint = BlakeTwo256::hash(&file_content);
-        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
-        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
-        let storage_amount: StorageData<Test> = 100;
+        let fingerprint = H256::zero();
+        let file_key = H256::zero();
 
-        // Dispatch storage request.
         assert_ok!(FileSystem::issue_storage_request(
-            owner_signed.clone(),
+            owner.clone(),
             location.clone(),
             fingerprint,
-            size,
-            peer_ids.clone(),
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
-
-        assert_noop!(
-            FileSystem::bsp_confirm_storing(
-                bsp_signed.clone(),
+        // Volunteer with as many BSPs as allowed, which can always be released in a single block.
+        let max_volunteers: u8 = <Test as Config>::MaxVolunteersPerRequest::get()
+            .try_into()
+            .unwrap();
+        for i in 0..max_volunteers {
+            let bsp_signed = RuntimeOrigin::signed(AccountId32::new([i + 2; 32]));
+            assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+            assert_ok!(FileSystem::bsp_volunteer(
+                bsp_signed,
                 location.clone(),
-                H256::zero(), // TODO construct a real proof
-                pallet_proofs_dealer::CompactProof {
-                    encoded_nodes: vec![],
-                }
-            ),
-            Error::<Test>::BspNotVolunteered
-        );
+                fingerprint,
+            ));
+        }
+
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner,
+            location.clone(),
+            file_key
+        ));
+        assert!(FileSystem::storage_requests(location).is_none());
     });
 }
 
 #[test]
-fn bsp_already_confirmed_fail() {
+fn request_storage_holds_deposit_success() {
     new_test_ext().execute_with(|| {
         let owner_account_id = AccountId32::new([1; 32]);
-        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
         let size = 4;
-        let file_content = b"test".to_vec();
-        let fingerprint = BlakeTwo256::hash(&file_content);
-        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
-        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
-        let storage_amount: StorageData<Test> = 100;
 
-        // Dispatch storage request.
+        // Dispatch a signed extrinsic.
         assert_ok!(FileSystem::issue_storage_request(
-            owner_signed.clone(),
+            owner.clone(),
             location.clone(),
             fingerprint,
             size,
-            peer_ids.clone(),
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        // Assert that the deposit proportional to the size was held from the owner
+        let deposit = FileSystem::compute_storage_request_deposit(size);
+        assert!(deposit > 0);
+        assert_eq!(
+            Balances::balance_on_hold(&StorageRequestHoldReason::get(), &owner_account_id),
+            deposit
+        );
+    });
+}
 
-        // Dispatch BSP volunteer.
-        assert_ok!(FileSystem::bsp_volunteer(
-            bsp_signed.clone(),
+#[test]
+fn revoke_request_storage_releases_deposit_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+
+        // Dispatch a signed extrinsic.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
             location.clone(),
             fingerprint,
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Dispatch BSP confirm storing.
-        assert_ok!(FileSystem::bsp_confirm_storing(
-            bsp_signed.clone(),
+        // Dispatch a signed extrinsic.
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner.clone(),
             location.clone(),
-            H256::zero(), // TODO construct a real proof
-            pallet_proofs_dealer::CompactProof {
-                encoded_nodes: vec![],
-            }
+            H256::zero()
         ));
 
-        assert_noop!(
-            FileSystem::bsp_confirm_storing(
-                bsp_signed.clone(),
-                location.clone(),
-                H256::zero(), // TODO construct a real proof
-                pallet_proofs_dealer::CompactProof {
-                    encoded_nodes: vec![],
-                }
-            ),
-            Error::<Test>::BspAlreadyConfirmed
+        // Assert that the deposit was released back to the owner
+        assert_eq!(
+            Balances::balance_on_hold(&StorageRequestHoldReason::get(), &owner_account_id),
+            0
         );
     });
 }
 
 #[test]
-fn bsp_actions_not_a_bsp_fail() {
+fn expired_storage_request_releases_deposit_success() {
     new_test_ext().execute_with(|| {
         let owner_account_id = AccountId32::new([1; 32]);
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
-        let file_content = b"test".to_vec();
-        let fingerprint = BlakeTwo256::hash(&file_content);
-        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
-        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
 
-        // Dispatch storage request.
+        // Dispatch a signed extrinsic.
         assert_ok!(FileSystem::issue_storage_request(
-            RuntimeOrigin::signed(owner_account_id.clone()),
+            owner.clone(),
             location.clone(),
             fingerprint,
-            size,
-            peer_ids.clone(),
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        let expiration_block: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+        roll_to(expiration_block + 1);
+
+        // Assert that the storage request expired and its deposit was released back to the owner
+        assert!(FileSystem::storage_requests(location).is_none());
+        assert_eq!(
+            Balances::balance_on_hold(&StorageRequestHoldReason::get(), &owner_account_id),
+            0
+        );
+    });
+}
+
+#[test]
+fn storage_requests_for_owner_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let other_owner = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let location_1 = FileLocation::<Test>::try_from(b"test1".to_vec()).unwrap();
+        let location_2 = FileLocation::<Test>::try_from(b"test2".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"other".to_vec()).unwrap();
+
+        for (origin, location) in [
+            (owner.clone(), location_1.clone()),
+            (owner.clone(), location_2.clone()),
+            (other_owner, other_location),
+        ] {
+            assert_ok!(FileSystem::issue_storage_request(
+                origin,
+                location,
+                fingerprint,
+                4,
+                Default::default(),
+                false,
+                None,
+                None,
+                None,
+            ));
+        }
+
+        // Assert that only the storage requests of the owner are listed
+        let mut open_requests = FileSystem::storage_requests_for(&owner_account_id);
+        open_requests.sort();
+        assert_eq!(open_requests, vec![location_1.clone(), location_2.clone()]);
+
+        // Revoke one of the storage requests
+        assert_ok!(FileSystem::revoke_storage_request(
+            owner.clone(),
+            location_1,
+            H256::zero()
         ));
+        assert_eq!(
+            FileSystem::storage_requests_for(&owner_account_id),
+            vec![location_2]
+        );
+
+        // Let the remaining storage request expire
+        let expiration_block: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+        roll_to(expiration_block + 1);
+        assert!(FileSystem::storage_requests_for(&owner_account_id).is_empty());
+    });
+}
+
+#[test]
+fn revoke_non_existing_storage_request_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let file_key = H256::zero();
 
         assert_noop!(
-            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
-            Error::<Test>::NotABsp
+            FileSystem::revoke_storage_request(owner.clone(), location.clone(), file_key),
+            Error::<Test>::StorageRequestNotFound
         );
+    });
+}
+
+#[test]
+fn revoke_storage_request_not_owner_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let not_owner = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let file_key = H256::zero();
+
+        // Dispatch a signed extrinsic.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
 
         assert_noop!(
-            FileSystem::bsp_confirm_storing(
-                bsp_signed.clone(),
-                location.clone(),
-                H256::zero(), // TODO construct a real proof
-                pallet_proofs_dealer::CompactProof {
-                    encoded_nodes: vec![],
-                }
-            ),
-            Error::<Test>::NotABsp
+            FileSystem::revoke_storage_request(not_owner.clone(), location.clone(), file_key),
+            Error::<Test>::StorageRequestNotAuthorized
         );
     });
 }
 
 #[test]
-fn bsp_stop_storing_success() {
+fn bsp_volunteer_success() {
     new_test_ext().execute_with(|| {
-        let owner_account_id = AccountId32::new([1; 32]);
-        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
         let bsp_account_id = AccountId32::new([2; 32]);
         let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
-        let file_key = H256::from_slice(&[1; 32]);
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
-        // TODO: right now we are bypassing the volunteer assignment threshold
+        // The mock genesis sets the volunteer assignment threshold to its maximum, so any BSP can volunteer
         let fingerprint = H256::zero();
         let peer_id = BoundedVec::try_from(vec![1]).unwrap();
         let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
@@ -748,8 +1295,12 @@ fn bsp_stop_storing_success() {
             owner.clone(),
             location.clone(),
             fingerprint,
-            size,
+            4,
             peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
         ));
 
         // Sign up account as a Backup Storage Provider
@@ -762,75 +1313,2726 @@ fn bsp_stop_storing_success() {
             fingerprint,
         ));
 
-        // Dispatch BSP confirm storing.
-        assert_ok!(FileSystem::bsp_confirm_storing(
-            bsp_signed.clone(),
-            location.clone(),
-            H256::zero(), // TODO construct a real proof
-            pallet_proofs_dealer::CompactProof {
-                encoded_nodes: vec![],
-            }
-        ));
-
-        // Assert that the RequestStorageBsps now contains the BSP under the location
+        // Assert that the RequestStorageBsps has the correct value
         assert_eq!(
             FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone())
                 .expect("BSP should exist in storage"),
             StorageRequestBspsMetadata::<Test> {
-                confirmed: true,
+                confirmed: false,
+                proven_root: None,
                 _phantom: Default::default()
             }
         );
 
-        // Assert that the storage was updated
-        assert_eq!(
-            FileSystem::storage_requests(location.clone()),
-            Some(StorageRequestMetadata {
-                requested_at: 1,
-                owner: owner_account_id.clone(),
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::AcceptedBspVolunteer {
+                who: bsp_account_id,
+                location,
                 fingerprint,
-                size,
-                user_peer_ids: peer_ids.clone(),
-                data_server_sps: BoundedVec::default(),
-                bsps_required: TargetBspsRequired::<Test>::get(),
-                bsps_confirmed: 1,
-                bsps_volunteered: 1,
-            })
+                multiaddresses: create_sp_multiaddresses(),
+            }
+            .into(),
         );
+    });
+}
 
-        // Dispatch BSP stop storing.
-        assert_ok!(FileSystem::bsp_stop_storing(
-            bsp_signed.clone(),
-            file_key,
+#[test]
+fn bsp_confirm_storing_fulfilled_releases_unconfirmed_volunteers_success() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let confirming_bsp_account_id = AccountId32::new([2; 32]);
+        let confirming_bsp_signed = RuntimeOrigin::signed(confirming_bsp_account_id.clone());
+        let late_bsp_account_id = AccountId32::new([3; 32]);
+        let late_bsp_signed = RuntimeOrigin::signed(late_bsp_account_id.clone());
+        let other_bsp_signed = RuntimeOrigin::signed(AccountId32::new([4; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+
+        // Dispatch storage request requiring a single BSP.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
             location.clone(),
-            owner_account_id.clone(),
             fingerprint,
-            size,
-            false
+            4,
+            Default::default(),
+            false,
+            Some(1),
+            None,
+            None,
         ));
 
-        // Assert that the RequestStorageBsps has the correct value
-        assert!(
-            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone()).is_none()
+        // Two BSPs volunteer, more than required, but only one of them confirms storing the file.
+        for bsp_signed in [
+            confirming_bsp_signed.clone(),
+            late_bsp_signed.clone(),
+            other_bsp_signed.clone(),
+        ] {
+            assert_ok!(bsp_sign_up(bsp_signed, 100));
+        }
+        for bsp_signed in [confirming_bsp_signed.clone(), late_bsp_signed.clone()] {
+            assert_ok!(FileSystem::bsp_volunteer(
+                bsp_signed,
+                location.clone(),
+                fingerprint
+            ));
+        }
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            confirming_bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // The volunteer that did not confirm storing the file is released and notified.
+        System::assert_has_event(
+            Event::StorageRequestVolunteersReleased {
+                location: location.clone(),
+                bsps: BoundedVec::try_from(vec![late_bsp_account_id.clone()]).unwrap(),
+            }
+            .into(),
+        );
+        assert!(!PendingVolunteeredRequests::<Test>::contains_key(
+            &late_bsp_account_id,
+            &location
+        ));
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                late_bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::StorageRequestNotFound
         );
 
-        // Assert that the storage was updated
-        assert_eq!(
-            FileSystem::storage_requests(location.clone()),
-            Some(StorageRequestMetadata {
-                requested_at: 1,
-                owner: owner_account_id.clone(),
-                fingerprint,
-                size,
-                user_peer_ids: peer_ids.clone(),
-                data_server_sps: BoundedVec::default(),
-                bsps_required: TargetBspsRequired::<Test>::get(),
-                bsps_confirmed: 0,
-                bsps_volunteered: 0,
-            })
+        // BSPs can not volunteer for the fulfilled storage request anymore.
+        assert_noop!(
+            FileSystem::bsp_volunteer(other_bsp_signed.clone(), location.clone(), fingerprint),
+            Error::<Test>::StorageRequestNotFound
         );
+    });
+}
 
-        // Assert that the correct event was deposited
+#[test]
+fn bsp_volunteer_above_max_volunteers_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Volunteer with as many BSPs as allowed.
+        let max_volunteers: u8 = <Test as Config>::MaxVolunteersPerRequest::get()
+            .try_into()
+            .unwrap();
+        for i in 0..max_volunteers {
+            let bsp_signed = RuntimeOrigin::signed(AccountId32::new([i + 2; 32]));
+            assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+            assert_ok!(FileSystem::bsp_volunteer(
+                bsp_signed,
+                location.clone(),
+                fingerprint,
+            ));
+        }
+
+        // One more BSP is rejected.
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([max_volunteers + 2; 32]));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed, location.clone(), fingerprint),
+            Error::<Test>::TooManyVolunteers
+        );
+        assert_eq!(
+            FileSystem::storage_requests(location)
+                .unwrap()
+                .bsps_volunteered,
+            max_volunteers as u32
+        );
+    });
+}
+
+#[test]
+fn bsp_volunteer_above_volunteers_per_required_bsp_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let bsps_required = 1;
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            Some(bsps_required),
+            None,
+            None,
+        ));
+
+        // The limit is derived from the number of BSPs required, below the maximum.
+        let max_volunteers: u8 = (bsps_required
+            * <Test as Config>::VolunteersPerRequiredBsp::get())
+        .try_into()
+        .unwrap();
+        assert!((max_volunteers as u32) < <Test as Config>::MaxVolunteersPerRequest::get());
+        for i in 0..max_volunteers {
+            let bsp_signed = RuntimeOrigin::signed(AccountId32::new([i + 2; 32]));
+            assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+            assert_ok!(FileSystem::bsp_volunteer(
+                bsp_signed,
+                location.clone(),
+                fingerprint,
+            ));
+        }
+
+        // One more BSP is rejected.
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([max_volunteers + 2; 32]));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed, location.clone(), fingerprint),
+            Error::<Test>::TooManyVolunteers
+        );
+    });
+}
+
+#[test]
+fn bsp_volunteer_after_confirmed_bsp_stopped_storing_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let size = 4;
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Volunteer with as many BSPs as allowed.
+        let max_volunteers: u8 = <Test as Config>::MaxVolunteersPerRequest::get()
+            .try_into()
+            .unwrap();
+        for i in 0..max_volunteers {
+            let bsp_signed = RuntimeOrigin::signed(AccountId32::new([i + 2; 32]));
+            assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+            assert_ok!(FileSystem::bsp_volunteer(
+                bsp_signed,
+                location.clone(),
+                fingerprint,
+            ));
+        }
+
+        // The first BSP confirms storing the file, then stops storing it.
+        let confirmed_bsp = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            confirmed_bsp.clone(),
+            location.clone(),
+            root_transition_proof(fingerprint),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+        assert_ok!(FileSystem::bsp_stop_storing(
+            confirmed_bsp,
+            FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint),
+            location.clone(),
+            owner_account_id,
+            fingerprint,
+            size,
+            false,
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // The BSP that stopped storing the file no longer counts against the limit, so a new BSP can volunteer.
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([max_volunteers + 2; 32]));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed,
+            location.clone(),
+            fingerprint,
+        ));
+        assert_eq!(
+            FileSystem::storage_requests(location)
+                .unwrap()
+                .bsps_volunteered,
+            max_volunteers as u32
+        );
+    });
+}
+
+#[test]
+fn bsp_cancel_volunteer_success() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // The BSP cannot get the file, so it cancels its volunteering.
+        assert_ok!(FileSystem::bsp_cancel_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+        ));
+
+        // The BSP is no longer a volunteer of the storage request, and its slot is freed.
+        assert!(
+            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone()).is_none()
+        );
+        assert!(!PendingVolunteeredRequests::<Test>::contains_key(
+            &bsp_account_id,
+            &location
+        ));
+        assert_eq!(
+            FileSystem::storage_requests(location.clone())
+                .unwrap()
+                .bsps_volunteered,
+            0
+        );
+
+        System::assert_last_event(
+            Event::BspVolunteerCancelled {
+                who: bsp_account_id,
+                location: location.clone(),
+                fingerprint,
+            }
+            .into(),
+        );
+
+        // The BSP can volunteer again.
+        assert_ok!(FileSystem::bsp_volunteer(bsp_signed, location, fingerprint));
+    });
+}
+
+#[test]
+fn bsp_cancel_volunteer_after_confirming_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(fingerprint),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // A BSP that confirmed storing the file has to stop storing it instead.
+        assert_noop!(
+            FileSystem::bsp_cancel_volunteer(bsp_signed, location.clone()),
+            Error::<Test>::BspAlreadyConfirmed
+        );
+        assert!(
+            FileSystem::storage_request_bsps(location, bsp_account_id)
+                .unwrap()
+                .confirmed
+        );
+    });
+}
+
+#[test]
+fn bsp_cancel_volunteer_not_volunteered_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            H256::zero(),
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+
+        assert_noop!(
+            FileSystem::bsp_cancel_volunteer(bsp_signed, location),
+            Error::<Test>::BspNotVolunteered
+        );
+    });
+}
+
+#[test]
+fn increase_replication_accepts_new_volunteers_success() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let other_bsp_signed = RuntimeOrigin::signed(AccountId32::new([3; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let proof = pallet_proofs_dealer::CompactProof {
+            encoded_nodes: vec![vec![0]],
+        };
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            Some(1),
+            None,
+            None,
+        ));
+
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(bsp_sign_up(other_bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // The owner wants one more BSP to store the file.
+        assert_ok!(FileSystem::increase_replication(owner, location.clone(), 1));
+        System::assert_last_event(
+            Event::ReplicationIncreased {
+                location: location.clone(),
+                bsps_required: 2,
+            }
+            .into(),
+        );
+
+        // The confirmation of the BSP no longer fulfills the storage request, so another BSP can still volunteer.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed,
+            location.clone(),
+            root_transition_proof(fingerprint),
+            proof.clone(),
+        ));
+        assert_eq!(
+            FileSystem::storage_requests(location.clone())
+                .unwrap()
+                .bsps_confirmed,
+            1
+        );
+        assert_ok!(FileSystem::bsp_volunteer(
+            other_bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Once the second BSP confirms, the storage request is fulfilled.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            other_bsp_signed,
+            location.clone(),
+            root_transition_proof(fingerprint),
+            proof,
+        ));
+        assert!(FileSystem::storage_requests(location).is_none());
+    });
+}
+
+#[test]
+fn increase_replication_fail() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let not_owner = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+
+        assert_noop!(
+            FileSystem::increase_replication(owner.clone(), location.clone(), 1),
+            Error::<Test>::StorageRequestNotFound
+        );
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            H256::zero(),
+            4,
+            Default::default(),
+            false,
+            Some(1),
+            None,
+            None,
+        ));
+
+        assert_noop!(
+            FileSystem::increase_replication(not_owner, location.clone(), 1),
+            Error::<Test>::StorageRequestNotAuthorized
+        );
+
+        // The replication target is bounded as when issuing the storage request.
+        let max_additional = <Test as Config>::MaxReplicationTarget::get() - 1;
+        assert_noop!(
+            FileSystem::increase_replication(owner.clone(), location.clone(), max_additional + 1),
+            Error::<Test>::ReplicationTargetExceedsMax
+        );
+        assert_ok!(FileSystem::increase_replication(
+            owner,
+            location.clone(),
+            max_additional
+        ));
+        assert_eq!(
+            FileSystem::storage_requests(location)
+                .unwrap()
+                .bsps_required,
+            <Test as Config>::MaxReplicationTarget::get()
+        );
+    });
+}
+
+#[test]
+fn bsps_for_request_lists_volunteered_and_confirmed_bsps() {
+    new_test_ext().execute_with(|| {
+        let owner = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let bsp_account_ids: Vec<AccountId32> =
+            (2..=4).map(|i| AccountId32::new([i; 32])).collect();
+
+        assert_ok!(FileSystem::issue_storage_request(
+            owner,
+            location.clone(),
+            fingerprint,
+            4,
+            Default::default(),
+            false,
+            Some(3),
+            None,
+            None,
+        ));
+        assert!(FileSystem::bsps_for_request(&location).is_empty());
+        assert_eq!(FileSystem::bsps_count_for_request(&location), (0, 0));
+
+        for bsp_account_id in &bsp_account_ids {
+            let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+            assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+            assert_ok!(FileSystem::bsp_volunteer(
+                bsp_signed,
+                location.clone(),
+                fingerprint,
+            ));
+        }
+
+        // Only the first BSP confirms storing the file.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            RuntimeOrigin::signed(bsp_account_ids[0].clone()),
+            location.clone(),
+            root_transition_proof(fingerprint),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        let mut bsps = FileSystem::bsps_for_request(&location);
+        bsps.sort_by(|(a, _), (b, _)| a.cmp(b));
+        assert_eq!(
+            bsps.iter()
+                .map(|(who, bsp)| (who.clone(), bsp.confirmed))
+                .collect::<Vec<_>>(),
+            vec![
+                (bsp_account_ids[0].clone(), true),
+                (bsp_account_ids[1].clone(), false),
+                (bsp_account_ids[2].clone(), false),
+            ]
+        );
+        assert_eq!(FileSystem::bsps_count_for_request(&location), (1, 3));
+    });
+}
+
+#[test]
+fn bsp_volunteer_storage_request_not_found_fail() {
+    new_test_ext().execute_with(|| {
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100,));
+
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
+            Error::<Test>::StorageRequestNotFound
+        );
+    });
+}
+
+#[test]
+fn bsp_already_volunteered_failed() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
+            Error::<Test>::BspAlreadyVolunteered
+        );
+    });
+}
+
+#[test]
+fn bsp_volunteer_owner_cannot_volunteer_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up both the owner and another account as Backup Storage Providers
+        assert_ok!(bsp_sign_up(owner_signed.clone(), storage_amount,));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // The owner's BSP should not be able to volunteer for its own file.
+        assert_noop!(
+            FileSystem::bsp_volunteer(owner_signed.clone(), location.clone(), fingerprint,),
+            Error::<Test>::OwnerCannotVolunteer
+        );
+
+        // A different BSP should be able to volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+    });
+}
+
+#[test]
+fn bsp_volunteer_above_threshold_high_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        crate::BspsAssignmentThreshold::<Test>::put(FixedU128::zero());
+
+        // Dispatch BSP volunteer.
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
+            Error::<Test>::AboveThreshold
+        );
+    });
+}
+
+#[test]
+fn bsp_volunteer_near_fingerprint_success() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        let bsp_id = <Providers as ProvidersInterface>::get_provider(bsp_account_id.clone())
+            .expect("BSP should be registered");
+
+        // Use the real assignment threshold instead of the maximum one set at genesis.
+        compute_set_get_initial_threshold();
+
+        // The fingerprint is the BSP id itself, so the XOR distance between them is zero.
+        let fingerprint = bsp_id;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        assert!(FileSystem::storage_request_bsps(location, bsp_account_id).is_some());
+    });
+}
+
+#[test]
+fn bsp_volunteer_far_from_fingerprint_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        let bsp_id = <Providers as ProvidersInterface>::get_provider(bsp_account_id.clone())
+            .expect("BSP should be registered");
+
+        // Use the real assignment threshold instead of the maximum one set at genesis.
+        compute_set_get_initial_threshold();
+
+        // The fingerprint is the BSP id with every bit flipped, so the XOR distance between them is maximal.
+        let fingerprint = H256::from_slice(
+            &bsp_id
+                .as_bytes()
+                .iter()
+                .map(|byte| !byte)
+                .collect::<Vec<_>>(),
+        );
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Dispatch BSP volunteer.
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
+            Error::<Test>::AboveThreshold
+        );
+    });
+}
+
+#[test]
+fn bsp_eligibility_matches_volunteer_threshold() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+        let bsp_id = <Providers as ProvidersInterface>::get_provider(bsp_account_id.clone())
+            .expect("BSP should be registered");
+
+        // Use the real assignment threshold instead of the maximum one set at genesis.
+        let threshold = compute_set_get_initial_threshold();
+        assert_eq!(FileSystem::current_assignment_threshold(), threshold);
+
+        // Issue a storage request for a fingerprint at zero XOR distance, and one at the maximal XOR distance.
+        let near_location = FileLocation::<Test>::try_from(b"near".to_vec()).unwrap();
+        let far_location = FileLocation::<Test>::try_from(b"far".to_vec()).unwrap();
+        let far_fingerprint = H256::from_slice(
+            &bsp_id
+                .as_bytes()
+                .iter()
+                .map(|byte| !byte)
+                .collect::<Vec<_>>(),
+        );
+        for (location, fingerprint) in [
+            (near_location.clone(), bsp_id),
+            (far_location.clone(), far_fingerprint),
+        ] {
+            assert_ok!(FileSystem::issue_storage_request(
+                owner_signed.clone(),
+                location,
+                fingerprint,
+                4,
+                Default::default(),
+                false,
+                None,
+                None,
+                None,
+            ));
+        }
+
+        // The BSP is eligible for the near storage request, but not for the far one.
+        assert!(FileSystem::is_bsp_eligible(&bsp_id, &near_location));
+        assert!(!FileSystem::is_bsp_eligible(&bsp_id, &far_location));
+
+        // Accounts that are not registered as BSPs are never eligible, and neither is anyone for unknown locations.
+        let unknown_bsp_id = H256::repeat_byte(7);
+        assert!(!FileSystem::is_bsp_eligible(
+            &unknown_bsp_id,
+            &near_location
+        ));
+        let unknown_location = FileLocation::<Test>::try_from(b"unknown".to_vec()).unwrap();
+        assert!(!FileSystem::is_bsp_eligible(&bsp_id, &unknown_location));
+
+        // The threshold of a storage request grows with the blocks since it was issued, as when volunteering.
+        let requested_at = System::block_number();
+        roll_to(requested_at + 2);
+        assert_eq!(
+            FileSystem::storage_request_threshold(requested_at).unwrap(),
+            threshold + ThresholdMultiplier::get() * FixedU128::from_rational(2, 1)
+        );
+
+        // With the threshold at zero, the BSP is only eligible for a storage request issued in the current block
+        // for a fingerprint equal to its id.
+        crate::BspsAssignmentThreshold::<Test>::put(FixedU128::zero());
+        assert_eq!(
+            FileSystem::current_assignment_threshold(),
+            FixedU128::zero()
+        );
+        let current_location = FileLocation::<Test>::try_from(b"current".to_vec()).unwrap();
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            current_location.clone(),
+            bsp_id,
+            4,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+        assert!(FileSystem::is_bsp_eligible(&bsp_id, &current_location));
+        assert!(!FileSystem::is_bsp_eligible(&bsp_id, &far_location));
+    });
+}
+
+#[test]
+fn bsp_volunteer_fingerprint_mismatch_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer with a fingerprint different from the one of the storage request.
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), H256::zero(),),
+            Error::<Test>::FingerprintMismatch
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+        let new_root = H256::repeat_byte(7);
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(new_root),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that the storage was updated
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()),
+            Some(StorageRequestMetadata {
+                requested_at: 1,
+                owner: owner_account_id.clone(),
+                fingerprint,
+                size,
+                user_peer_ids: peer_ids.clone(),
+                user_multiaddresses: Default::default(),
+                data_server_sps: BoundedVec::default(),
+                bsps_required: TargetBspsRequired::<Test>::get(),
+                bsps_confirmed: 1,
+                bsps_volunteered: 1,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
+            })
+        );
+
+        // Assert that the RequestStorageBsps was updated
+        assert_eq!(
+            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone())
+                .expect("BSP should exist in storage"),
+            StorageRequestBspsMetadata::<Test> {
+                confirmed: true,
+                proven_root: Some(new_root),
+                _phantom: Default::default()
+            }
+        );
+
+        // Assert that the reputation of the BSP increased
+        let bsp_id = Providers::get_provider(bsp_account_id.clone()).unwrap();
+        assert_eq!(Providers::get_reputation(&bsp_id), 1);
+
+        // Assert that the root of the BSP is the one resulting from the transition
+        assert_eq!(Providers::get_root(bsp_id), Some(new_root));
+
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::BspConfirmedStoring {
+                who: bsp_account_id,
+                location,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn bsp_change_capacity_below_pending_volunteered_storage_fails() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let confirmed_location = FileLocation::<Test>::try_from(b"confirmed".to_vec()).unwrap();
+        let pending_location = FileLocation::<Test>::try_from(b"pending".to_vec()).unwrap();
+        let confirmed_size = 4;
+        let pending_size = 6;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch both storage requests.
+        for (location, size) in [
+            (confirmed_location.clone(), confirmed_size),
+            (pending_location.clone(), pending_size),
+        ] {
+            assert_ok!(FileSystem::issue_storage_request(
+                owner_signed.clone(),
+                location,
+                fingerprint,
+                size,
+                peer_ids.clone(),
+                false,
+                None,
+                None,
+                None,
+            ));
+        }
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount));
+
+        // Volunteer for both storage requests, but only confirm storing one of them.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            confirmed_location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            pending_location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            confirmed_location.clone(),
+            root_transition_proof(H256::zero()), // TODO construct a real proof
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Wait until the BSP is allowed to change its capacity.
+        roll_to(frame_system::Pallet::<Test>::block_number() + 10);
+
+        // The capacity can not be shrunk below the stored data plus the pending volunteered storage.
+        assert_noop!(
+            Providers::change_capacity(bsp_signed.clone(), confirmed_size + pending_size - 1),
+            pallet_storage_providers::Error::<Test>::CapacityBelowCommitment
+        );
+
+        // It can be shrunk down to exactly cover both files.
+        assert_ok!(Providers::change_capacity(
+            bsp_signed.clone(),
+            confirmed_size + pending_size
+        ));
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_fulfilled_does_not_add_file_to_msp_bucket() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let msp_account_id = AccountId32::new([3; 32]);
+        let msp_signed = RuntimeOrigin::signed(msp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up accounts as a Main Storage Provider and a Backup Storage Provider
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+
+        let msp_id = <Providers as ProvidersInterface>::get_provider(msp_account_id).unwrap();
+
+        // Dispatch storage request designating the MSP, requiring a single BSP.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            Some(1),
+            Some(msp_id),
+            None,
+        ));
+
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()).map(|metadata| metadata.msp),
+            Some(Some(msp_id))
+        );
+
+        // Dispatch BSP volunteer and confirm storing, fulfilling the storage request.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that no bucket was created for the MSP, since only the MSP can prove the root of its buckets
+        assert!(pallet_storage_providers::Pallet::<Test>::buckets_of_msp(&msp_id).is_empty());
+        assert!(
+            pallet_storage_providers::Pallet::<Test>::buckets_of_user(&owner_account_id).is_empty()
+        );
+    });
+}
+
+#[test]
+fn msp_confirm_storing_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let msp_account_id = AccountId32::new([3; 32]);
+        let msp_signed = RuntimeOrigin::signed(msp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let bucket_salt = BucketSalt::<Test>::try_from(b"bucket".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let bucket_root = H256::repeat_byte(1);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up accounts as a Main Storage Provider and a Backup Storage Provider
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+
+        let msp_id =
+            <Providers as ProvidersInterface>::get_provider(msp_account_id.clone()).unwrap();
+
+        // Dispatch storage request designating the MSP, requiring a single BSP.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            Some(1),
+            Some(msp_id),
+            None,
+        ));
+
+        // Dispatch MSP confirm storing.
+        assert_ok!(FileSystem::msp_confirm_storing(
+            msp_signed.clone(),
+            location.clone(),
+            root_transition_proof(bucket_root),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            },
+            bucket_salt.clone(),
+        ));
+
+        // Assert that the file was added to a new bucket of the MSP, derived from the salt, with the root resulting
+        // from the transition
+        let bucket_id = pallet_storage_providers::Pallet::<Test>::derive_bucket_id(
+            &msp_id,
+            &owner_account_id,
+            &bucket_salt,
+        );
+        let bucket = pallet_storage_providers::Buckets::<Test>::get(bucket_id)
+            .expect("Bucket should exist once the MSP confirmed storing the file");
+        assert_eq!(bucket.msp_id, msp_id);
+        assert_eq!(bucket.user_id, owner_account_id);
+        assert_eq!(bucket.root, bucket_root);
+        assert_eq!(bucket.size, size);
+
+        // Assert that the MSP was cleared from the storage request
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()).map(|metadata| metadata.msp),
+            Some(None)
+        );
+
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::MspConfirmedStoring {
+                who: msp_account_id,
+                location: location.clone(),
+                bucket_id,
+            }
+            .into(),
+        );
+
+        // Fulfilling the storage request does not create another bucket for the file
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+        assert_eq!(
+            pallet_storage_providers::Pallet::<Test>::buckets_of_msp(&msp_id),
+            vec![bucket_id]
+        );
+    });
+}
+
+#[test]
+fn msp_confirm_storing_not_designated_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let msp_signed = RuntimeOrigin::signed(AccountId32::new([3; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up account as a Main Storage Provider
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+
+        // Dispatch storage request without designating any MSP.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            H256::zero(),
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // The MSP cannot confirm storing the file
+        assert_noop!(
+            FileSystem::msp_confirm_storing(
+                msp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::repeat_byte(1)),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                },
+                BucketSalt::<Test>::try_from(b"bucket".to_vec()).unwrap(),
+            ),
+            Error::<Test>::MspNotDesignated
+        );
+    });
+}
+
+#[test]
+fn msp_confirm_storing_same_salt_adds_files_to_one_bucket_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let msp_account_id = AccountId32::new([3; 32]);
+        let msp_signed = RuntimeOrigin::signed(msp_account_id.clone());
+        let bucket_salt = BucketSalt::<Test>::try_from(b"bucket".to_vec()).unwrap();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up account as a Main Storage Provider
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+
+        let msp_id = <Providers as ProvidersInterface>::get_provider(msp_account_id).unwrap();
+
+        // Dispatch two storage requests designating the MSP.
+        let locations: Vec<FileLocation<Test>> = vec![
+            FileLocation::<Test>::try_from(b"first".to_vec()).unwrap(),
+            FileLocation::<Test>::try_from(b"second".to_vec()).unwrap(),
+        ];
+        for location in locations.iter() {
+            assert_ok!(FileSystem::issue_storage_request(
+                owner_signed.clone(),
+                location.clone(),
+                H256::zero(),
+                4,
+                peer_ids.clone(),
+                false,
+                None,
+                Some(msp_id),
+                None,
+            ));
+        }
+
+        // Confirm storing both files with the same salt
+        let roots = [H256::repeat_byte(1), H256::repeat_byte(2)];
+        for (location, root) in locations.iter().zip(roots) {
+            assert_ok!(FileSystem::msp_confirm_storing(
+                msp_signed.clone(),
+                location.clone(),
+                root_transition_proof(root),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                },
+                bucket_salt.clone(),
+            ));
+        }
+
+        // Assert that both files are in the same bucket, whose root follows the last transition
+        let bucket_id = pallet_storage_providers::Pallet::<Test>::derive_bucket_id(
+            &msp_id,
+            &owner_account_id,
+            &bucket_salt,
+        );
+        assert_eq!(
+            pallet_storage_providers::Pallet::<Test>::buckets_of_msp(&msp_id),
+            vec![bucket_id]
+        );
+        let bucket = pallet_storage_providers::Buckets::<Test>::get(bucket_id)
+            .expect("Bucket should exist once the MSP confirmed storing the files");
+        assert_eq!(bucket.root, roots[1]);
+        assert_eq!(bucket.size, 8);
+    });
+}
+
+#[test]
+fn msp_confirm_storing_reverts_on_invalid_root_transition_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let msp_account_id = AccountId32::new([3; 32]);
+        let msp_signed = RuntimeOrigin::signed(msp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up account as a Main Storage Provider
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+
+        let msp_id = <Providers as ProvidersInterface>::get_provider(msp_account_id).unwrap();
+
+        // Dispatch storage request designating the MSP.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            H256::zero(),
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            Some(msp_id),
+            None,
+        ));
+
+        // Confirming with a root transition that does not end with a new root fails
+        assert_noop!(
+            FileSystem::msp_confirm_storing(
+                msp_signed.clone(),
+                location.clone(),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![],
+                },
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                },
+                BucketSalt::<Test>::try_from(b"bucket".to_vec()).unwrap(),
+            ),
+            pallet_storage_providers::Error::<Test>::InvalidRootTransition
+        );
+
+        // Assert that the bucket was not created and the MSP can still confirm storing the file
+        assert!(pallet_storage_providers::Pallet::<Test>::buckets_of_msp(&msp_id).is_empty());
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()).map(|metadata| metadata.msp),
+            Some(Some(msp_id))
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_fulfilled_before_expiry_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Dispatch storage request requiring a single BSP.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            Some(1),
+            None,
+            None,
+        ));
+
+        let expiration_block_number: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that the storage request was fulfilled
+        System::assert_has_event(
+            Event::BspConfirmedStoring {
+                who: bsp_account_id,
+                location: location.clone(),
+            }
+            .into(),
+        );
+        System::assert_last_event(
+            Event::StorageRequestFulfilled {
+                location: location.clone(),
+            }
+            .into(),
+        );
+
+        roll_to(expiration_block_number);
+
+        // Assert that the fulfilled storage request is not reported as expired
+        assert!(!System::events().iter().any(|record| matches!(
+            record.event,
+            RuntimeEvent::FileSystem(Event::StorageRequestExpired { .. })
+        )));
+    });
+}
+
+#[test]
+fn storage_request_expired_unfulfilled_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Dispatch storage request requiring the default number of BSPs.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        let expiration_block_number: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+
+        // A single BSP confirms storing the file.
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        roll_to(expiration_block_number);
+
+        // Assert that the storage request expired without enough confirmations
+        assert!(FileSystem::storage_requests(location.clone()).is_none());
+        System::assert_last_event(
+            Event::StorageRequestExpired {
+                location,
+                bsps_confirmed: 1,
+                bsps_required: TargetBspsRequired::<Test>::get(),
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn storage_request_expired_releases_pending_volunteers_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        let expiration_block_number: BlockNumber =
+            FileSystem::next_expiration_insertion_block_number().into();
+
+        // The BSP volunteers but never confirms storing the file.
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_eq!(
+            PendingVolunteeredStorage::<Test>::get(&bsp_account_id),
+            size
+        );
+
+        roll_to(expiration_block_number);
+
+        // The BSP is no longer committed to the expired storage request.
+        assert!(FileSystem::storage_requests(location.clone()).is_none());
+        assert!(!PendingVolunteeredRequests::<Test>::contains_key(
+            &bsp_account_id,
+            &location
+        ));
+        assert!(!PendingVolunteeredStorage::<Test>::contains_key(
+            &bsp_account_id
+        ));
+        assert_eq!(
+            FileSystem::storage_request_bsps(&location, &bsp_account_id),
+            None
+        );
+    });
+}
+
+#[test]
+fn request_storage_msp_not_registered_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+
+        let bsp_id = <Providers as ProvidersInterface>::get_provider(bsp_account_id).unwrap();
+
+        // A BSP cannot be designated to store the file in a bucket
+        assert_noop!(
+            FileSystem::issue_storage_request(
+                owner_signed.clone(),
+                location.clone(),
+                H256::zero(),
+                4,
+                peer_ids.clone(),
+                false,
+                None,
+                Some(bsp_id),
+                None,
+            ),
+            Error::<Test>::NotAMsp
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_batch_success() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test1".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"test2".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Dispatch a storage request requiring a single BSP and another one requiring the default number of BSPs.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            Some(1),
+            None,
+            None,
+        ));
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            other_location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider and volunteer for both files
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            other_location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing for both files at once.
+        let proof = pallet_proofs_dealer::CompactProof {
+            encoded_nodes: vec![vec![0]],
+        };
+        assert_ok!(FileSystem::bsp_confirm_storing_batch(
+            bsp_signed.clone(),
+            BoundedVec::try_from(vec![
+                (
+                    location.clone(),
+                    root_transition_proof(H256::zero()),
+                    proof.clone()
+                ),
+                (
+                    other_location.clone(),
+                    root_transition_proof(H256::zero()),
+                    proof
+                ),
+            ])
+            .unwrap(),
+        ));
+
+        // Assert that the first storage request was fulfilled and the second one got a confirmation
+        assert!(FileSystem::storage_requests(location.clone()).is_none());
+        assert_eq!(
+            FileSystem::storage_requests(other_location.clone()).map(|r| r.bsps_confirmed),
+            Some(1)
+        );
+
+        System::assert_has_event(
+            Event::BspConfirmedStoring {
+                who: bsp_account_id.clone(),
+                location: location.clone(),
+            }
+            .into(),
+        );
+        System::assert_has_event(Event::StorageRequestFulfilled { location }.into());
+        System::assert_last_event(
+            Event::BspConfirmedStoring {
+                who: bsp_account_id,
+                location: other_location,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_batch_reverts_on_invalid_confirmation_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test1".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"test2".to_vec()).unwrap();
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        for location in [location.clone(), other_location.clone()] {
+            assert_ok!(FileSystem::issue_storage_request(
+                owner_signed.clone(),
+                location,
+                fingerprint,
+                4,
+                peer_ids.clone(),
+                false,
+                None,
+                None,
+                None,
+            ));
+        }
+
+        // The BSP only volunteers for the first file.
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // A batch including a file the BSP did not volunteer for should not apply any confirmation.
+        let proof = pallet_proofs_dealer::CompactProof {
+            encoded_nodes: vec![vec![0]],
+        };
+        assert_noop!(
+            FileSystem::bsp_confirm_storing_batch(
+                bsp_signed.clone(),
+                BoundedVec::try_from(vec![
+                    (
+                        location.clone(),
+                        root_transition_proof(H256::zero()),
+                        proof.clone()
+                    ),
+                    (
+                        other_location.clone(),
+                        root_transition_proof(H256::zero()),
+                        proof
+                    ),
+                ])
+                .unwrap(),
+            ),
+            Error::<Test>::BspNotVolunteered
+        );
+
+        assert_eq!(
+            FileSystem::storage_requests(location).map(|r| r.bsps_confirmed),
+            Some(0)
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_storage_request_not_found_fail() {
+    new_test_ext().execute_with(|| {
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100,));
+
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::StorageRequestNotFound
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_not_volunteered_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::BspNotVolunteered
+        );
+    });
+}
+
+#[test]
+fn bsp_already_confirmed_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()), // TODO construct a real proof
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::BspAlreadyConfirmed
+        );
+    });
+}
+
+#[test]
+fn bsps_still_needed_after_confirmation_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // There is no storage request yet.
+        assert_eq!(FileSystem::bsps_still_needed(&location), None);
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // No BSP has confirmed yet, so all of the target BSPs are still needed.
+        assert_eq!(FileSystem::bsps_still_needed(&location), Some(3));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()), // TODO construct a real proof
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        assert_eq!(FileSystem::bsps_still_needed(&location), Some(2));
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_with_matching_size_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        // A file of 2 full chunks and a partial one.
+        let size = 2 * 1024 + 1;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 10_000;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing with a proof implying 3 chunks.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()), // TODO construct a real proof
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0], vec![1], vec![2]],
+            }
+        ));
+
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::BspConfirmedStoring {
+                who: bsp_account_id,
+                location,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_size_mismatch_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing with a proof implying more chunks than the declared size.
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0], vec![1]],
+                }
+            ),
+            Error::<Test>::SizeMismatch
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_insufficient_capacity_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        // Less capacity than the size of the file.
+        let storage_amount: StorageData<Test> = 2;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing, which fails since the BSP does not have room for the file.
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::InsufficientAvailableCapacity
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_invalid_proof_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing with a proof that does not reconstruct the file's fingerprint.
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![],
+                }
+            ),
+            Error::<Test>::InvalidProof
+        );
+    });
+}
+
+#[test]
+fn bsp_confirm_storing_invalid_root_transition_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing with a proof that does not show a transition of the BSP's root.
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![],
+                },
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            pallet_storage_providers::Error::<Test>::InvalidRootTransition
+        );
+    });
+}
+
+#[test]
+fn bsp_actions_not_a_bsp_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let file_content = b"test".to_vec();
+        let fingerprint = BlakeTwo256::hash(&file_content);
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            RuntimeOrigin::signed(owner_account_id.clone()),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint,),
+            Error::<Test>::NotABsp
+        );
+
+        assert_noop!(
+            FileSystem::bsp_confirm_storing(
+                bsp_signed.clone(),
+                location.clone(),
+                root_transition_proof(H256::zero()), // TODO construct a real proof
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::NotABsp
+        );
+    });
+}
+
+#[test]
+fn bsp_stop_storing_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        // The mock genesis sets the volunteer assignment threshold to its maximum, so any BSP can volunteer
+        let fingerprint = H256::zero();
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            fingerprint,
+        ));
+
+        // Dispatch BSP confirm storing.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()), // TODO construct a real proof
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that the data used by the BSP grew with the confirmed file
+        let bsp_id = Providers::get_provider(bsp_account_id.clone()).unwrap();
+        assert_eq!(Providers::get_used_storage_of_bsp(&bsp_id).unwrap(), size);
+
+        // Assert that the RequestStorageBsps now contains the BSP under the location
+        assert_eq!(
+            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone())
+                .expect("BSP should exist in storage"),
+            StorageRequestBspsMetadata::<Test> {
+                confirmed: true,
+                proven_root: Some(H256::zero()),
+                _phantom: Default::default()
+            }
+        );
+
+        // Assert that the storage was updated
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()),
+            Some(StorageRequestMetadata {
+                requested_at: 1,
+                owner: owner_account_id.clone(),
+                fingerprint,
+                size,
+                user_peer_ids: peer_ids.clone(),
+                user_multiaddresses: Default::default(),
+                data_server_sps: BoundedVec::default(),
+                bsps_required: TargetBspsRequired::<Test>::get(),
+                bsps_confirmed: 1,
+                bsps_volunteered: 1,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
+            })
+        );
+
+        // Dispatch BSP stop storing.
+        let file_key =
+            FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint);
+        assert_ok!(FileSystem::bsp_stop_storing(
+            bsp_signed.clone(),
+            file_key,
+            location.clone(),
+            owner_account_id.clone(),
+            fingerprint,
+            size,
+            false,
+            root_transition_proof(H256::repeat_byte(2)),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that the data of the file was released from the BSP
+        assert_eq!(Providers::get_used_storage_of_bsp(&bsp_id).unwrap(), 0);
+
+        // Assert that the root of the BSP is the one resulting from removing the file
+        assert_eq!(Providers::get_root(bsp_id), Some(H256::repeat_byte(2)));
+
+        // Assert that the RequestStorageBsps has the correct value
+        assert!(
+            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone()).is_none()
+        );
+
+        // Assert that the storage was updated
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()),
+            Some(StorageRequestMetadata {
+                requested_at: 1,
+                owner: owner_account_id.clone(),
+                fingerprint,
+                size,
+                user_peer_ids: peer_ids.clone(),
+                user_multiaddresses: Default::default(),
+                data_server_sps: BoundedVec::default(),
+                bsps_required: TargetBspsRequired::<Test>::get(),
+                bsps_confirmed: 0,
+                bsps_volunteered: 0,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
+            })
+        );
+
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::BspStoppedStoring {
+                bsp: bsp_account_id,
+                file_key,
+                owner: owner_account_id,
+                location,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn bsp_stop_storing_while_storage_request_open_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            H256::zero(),
+            size,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP volunteer.
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            location.clone(),
+            H256::zero(),
+        ));
+
+        // Dispatch BSP confirm storing.
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Dispatch BSP stop storing.
+        let file_key =
+            FileSystem::compute_file_key(&owner_account_id, &location, size, &H256::zero());
+        assert_ok!(FileSystem::bsp_stop_storing(
+            bsp_signed.clone(),
+            file_key,
+            location.clone(),
+            owner_account_id.clone(),
+            H256::zero(),
+            size,
+            false,
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that the RequestStorageBsps has the correct value
+        assert!(
+            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone()).is_none()
+        );
+
+        // Assert that the storage was updated
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()),
+            Some(StorageRequestMetadata {
+                requested_at: 1,
+                owner: owner_account_id.clone(),
+                fingerprint: H256::zero(),
+                size,
+                user_peer_ids: Default::default(),
+                user_multiaddresses: Default::default(),
+                data_server_sps: BoundedVec::default(),
+                bsps_required: TargetBspsRequired::<Test>::get(),
+                bsps_confirmed: 0,
+                bsps_volunteered: 0,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
+            })
+        );
+
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::BspStoppedStoring {
+                bsp: bsp_account_id,
+                file_key,
+                owner: owner_account_id,
+                location,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn bsp_stop_storing_not_volunteered_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP stop storing.
+        let file_key =
+            FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint);
+        assert_ok!(FileSystem::bsp_stop_storing(
+            bsp_signed.clone(),
+            file_key,
+            location.clone(),
+            owner_account_id.clone(),
+            fingerprint,
+            size,
+            false,
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        let current_bsps_required: <Test as Config>::StorageRequestBspsRequiredType =
+            TargetBspsRequired::<Test>::get();
+
+        // Assert that the storage request bsps_required was incremented
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()),
+            Some(StorageRequestMetadata {
+                requested_at: 1,
+                owner: owner_account_id.clone(),
+                fingerprint,
+                size,
+                user_peer_ids: Default::default(),
+                user_multiaddresses: Default::default(),
+                data_server_sps: BoundedVec::default(),
+                bsps_required: current_bsps_required.checked_add(1).unwrap(),
+                bsps_confirmed: 0,
+                bsps_volunteered: 0,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: FileSystem::compute_storage_request_deposit(size),
+            })
+        );
+
+        // Assert that the correct event was deposited
+        System::assert_last_event(
+            Event::BspStoppedStoring {
+                bsp: bsp_account_id,
+                file_key,
+                owner: owner_account_id,
+                location,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn bsp_stop_storing_no_storage_request_success() {
+    new_test_ext().execute_with(|| {
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let owner_account_id = AccountId32::new([1; 32]);
+        let size = 4;
+        let fingerprint = H256::zero();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP stop storing.
+        let file_key =
+            FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint);
+        assert_ok!(FileSystem::bsp_stop_storing(
+            bsp_signed.clone(),
+            file_key,
+            location.clone(),
+            owner_account_id.clone(),
+            fingerprint,
+            size,
+            false,
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+
+        // Assert that the storage request was created with one bsps_required
+        assert_eq!(
+            FileSystem::storage_requests(location.clone()),
+            Some(StorageRequestMetadata {
+                requested_at: 1,
+                owner: owner_account_id.clone(),
+                fingerprint,
+                size,
+                user_peer_ids: Default::default(),
+                user_multiaddresses: Default::default(),
+                data_server_sps: BoundedVec::default(),
+                bsps_required: 1,
+                bsps_confirmed: 0,
+                bsps_volunteered: 0,
+                msp: None,
+                auto_renew: false,
+                auto_renewals: 0,
+                deposit_paid: 0,
+            })
+        );
+
+        // Assert that the correct event was deposited
         System::assert_last_event(
             Event::BspStoppedStoring {
                 bsp: bsp_account_id,
@@ -844,206 +4046,403 @@ fn bsp_stop_storing_success() {
 }
 
 #[test]
-fn bsp_stop_storing_while_storage_request_open_success() {
+fn bsp_stop_storing_only_releases_confirmed_data_success() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let confirmed_location = FileLocation::<Test>::try_from(b"confirmed".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"other".to_vec()).unwrap();
+        let confirmed_size = 4;
+        let other_size = 6;
+        let fingerprint = H256::zero();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch both storage requests.
+        for (location, size) in [
+            (confirmed_location.clone(), confirmed_size),
+            (other_location.clone(), other_size),
+        ] {
+            assert_ok!(FileSystem::issue_storage_request(
+                owner.clone(),
+                location,
+                fingerprint,
+                size,
+                Default::default(),
+                false,
+                None,
+                None,
+                None,
+            ));
+        }
+
+        // The BSP only confirms storing one of the files.
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount));
+        assert_ok!(FileSystem::bsp_volunteer(
+            bsp_signed.clone(),
+            confirmed_location.clone(),
+            fingerprint,
+        ));
+        assert_ok!(FileSystem::bsp_confirm_storing(
+            bsp_signed.clone(),
+            confirmed_location.clone(),
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+        let bsp_id = Providers::get_provider(bsp_account_id.clone()).unwrap();
+        assert_eq!(
+            Providers::get_used_storage_of_bsp(&bsp_id).unwrap(),
+            confirmed_size
+        );
+
+        // Stopping to store a file it never confirmed storing does not release any data.
+        assert_ok!(FileSystem::bsp_stop_storing(
+            bsp_signed.clone(),
+            FileSystem::compute_file_key(
+                &owner_account_id,
+                &other_location,
+                other_size,
+                &fingerprint
+            ),
+            other_location.clone(),
+            owner_account_id.clone(),
+            fingerprint,
+            other_size,
+            false,
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+        assert_eq!(
+            Providers::get_used_storage_of_bsp(&bsp_id).unwrap(),
+            confirmed_size
+        );
+
+        // Stopping to store the confirmed file releases the size it was accounted for, whatever the size provided.
+        assert_ok!(FileSystem::bsp_stop_storing(
+            bsp_signed.clone(),
+            FileSystem::compute_file_key(
+                &owner_account_id,
+                &confirmed_location,
+                storage_amount,
+                &fingerprint
+            ),
+            confirmed_location.clone(),
+            owner_account_id.clone(),
+            fingerprint,
+            storage_amount,
+            false,
+            root_transition_proof(H256::zero()),
+            pallet_proofs_dealer::CompactProof {
+                encoded_nodes: vec![vec![0]],
+            }
+        ));
+        assert_eq!(Providers::get_used_storage_of_bsp(&bsp_id).unwrap(), 0);
+        assert!(!BspConfirmedFiles::<Test>::contains_key(
+            &bsp_account_id,
+            &confirmed_location
+        ));
+    });
+}
+
+#[test]
+fn bsp_stop_storing_invalid_proof_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"other".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner.clone(),
+            location.clone(),
+            fingerprint,
+            size,
+            Default::default(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // A BSP that never stored the file can't increase the bsps required of its storage request.
+        assert_noop!(
+            FileSystem::bsp_stop_storing(
+                bsp_signed.clone(),
+                FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint),
+                location.clone(),
+                owner_account_id.clone(),
+                fingerprint,
+                size,
+                false,
+                root_transition_proof(H256::zero()),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![],
+                }
+            ),
+            Error::<Test>::InvalidProof
+        );
+
+        // Nor open a new storage request for it.
+        assert_noop!(
+            FileSystem::bsp_stop_storing(
+                bsp_signed.clone(),
+                FileSystem::compute_file_key(
+                    &owner_account_id,
+                    &other_location,
+                    size,
+                    &fingerprint
+                ),
+                other_location.clone(),
+                owner_account_id.clone(),
+                fingerprint,
+                size,
+                false,
+                root_transition_proof(H256::zero()),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![],
+                }
+            ),
+            Error::<Test>::InvalidProof
+        );
+        assert!(FileSystem::storage_requests(other_location).is_none());
+    });
+}
+
+#[test]
+fn bsp_stop_storing_file_key_mismatch_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let other_location = FileLocation::<Test>::try_from(b"other".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // The BSP can't prove the key of a file it holds to stop storing another one.
+        assert_noop!(
+            FileSystem::bsp_stop_storing(
+                bsp_signed,
+                FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint),
+                other_location,
+                owner_account_id,
+                fingerprint,
+                size,
+                false,
+                root_transition_proof(H256::zero()),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::FileKeyMismatch
+        );
+    });
+}
+
+#[test]
+fn bsp_stop_storing_invalid_root_transition_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let size = 4;
+        let fingerprint = H256::zero();
+        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up account as a Backup Storage Provider
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
+
+        // Dispatch BSP stop storing with a proof that does not show a transition of the BSP's root.
+        assert_noop!(
+            FileSystem::bsp_stop_storing(
+                bsp_signed,
+                FileSystem::compute_file_key(&owner_account_id, &location, size, &fingerprint),
+                location,
+                owner_account_id,
+                fingerprint,
+                size,
+                false,
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![],
+                },
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            pallet_storage_providers::Error::<Test>::InvalidRootTransition
+        );
+    });
+}
+
+#[test]
+fn bsp_stop_storing_not_a_bsp_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_account_id = AccountId32::new([1; 32]);
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+
+        assert_noop!(
+            FileSystem::bsp_stop_storing(
+                bsp_signed,
+                FileSystem::compute_file_key(&owner_account_id, &location, 4, &H256::zero()),
+                location,
+                owner_account_id,
+                H256::zero(),
+                4,
+                false,
+                root_transition_proof(H256::zero()),
+                pallet_proofs_dealer::CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                }
+            ),
+            Error::<Test>::NotABsp
+        );
+    });
+}
+
+#[test]
+fn assign_data_server_success() {
     new_test_ext().execute_with(|| {
         let owner_account_id = AccountId32::new([1; 32]);
-        let owner = RuntimeOrigin::signed(owner_account_id.clone());
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
-        let file_key = H256::from_slice(&[1; 32]);
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
+        let msp_account_id = AccountId32::new([3; 32]);
+        let msp_signed = RuntimeOrigin::signed(msp_account_id.clone());
+        let other_msp_account_id = AccountId32::new([4; 32]);
+        let other_msp_signed = RuntimeOrigin::signed(other_msp_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
-        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up accounts as Main Storage Providers
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+        assert_ok!(msp_sign_up(other_msp_signed.clone(), 100));
+        let msp_id =
+            <Providers as ProvidersInterface>::get_provider(msp_account_id.clone()).unwrap();
+        let other_msp_id =
+            <Providers as ProvidersInterface>::get_provider(other_msp_account_id.clone()).unwrap();
 
         // Dispatch storage request.
         assert_ok!(FileSystem::issue_storage_request(
-            owner.clone(),
+            owner_signed.clone(),
             location.clone(),
             H256::zero(),
-            size,
+            4,
             Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
-
-        // Dispatch BSP volunteer.
-        assert_ok!(FileSystem::bsp_volunteer(
-            bsp_signed.clone(),
+        // The owner assigns a MSP as data server.
+        assert_ok!(FileSystem::assign_data_server(
+            owner_signed.clone(),
             location.clone(),
-            H256::zero(),
+            msp_id,
         ));
-
-        // Dispatch BSP confirm storing.
-        assert_ok!(FileSystem::bsp_confirm_storing(
-            bsp_signed.clone(),
-            location.clone(),
-            H256::zero(),
-            pallet_proofs_dealer::CompactProof {
-                encoded_nodes: vec![],
+        System::assert_last_event(
+            Event::DataServerAssigned {
+                location: location.clone(),
+                msp_id,
             }
-        ));
+            .into(),
+        );
 
-        // Dispatch BSP stop storing.
-        assert_ok!(FileSystem::bsp_stop_storing(
-            bsp_signed.clone(),
-            file_key,
+        // A MSP assigns itself as data server.
+        assert_ok!(FileSystem::assign_data_server(
+            other_msp_signed.clone(),
             location.clone(),
-            owner_account_id.clone(),
-            H256::zero(),
-            size,
-            false
+            other_msp_id,
         ));
 
-        // Assert that the RequestStorageBsps has the correct value
-        assert!(
-            FileSystem::storage_request_bsps(location.clone(), bsp_account_id.clone()).is_none()
-        );
-
-        // Assert that the storage was updated
         assert_eq!(
-            FileSystem::storage_requests(location.clone()),
-            Some(StorageRequestMetadata {
-                requested_at: 1,
-                owner: owner_account_id.clone(),
-                fingerprint: H256::zero(),
-                size,
-                user_peer_ids: Default::default(),
-                data_server_sps: BoundedVec::default(),
-                bsps_required: TargetBspsRequired::<Test>::get(),
-                bsps_confirmed: 0,
-                bsps_volunteered: 0,
-            })
-        );
-
-        // Assert that the correct event was deposited
-        System::assert_last_event(
-            Event::BspStoppedStoring {
-                bsp: bsp_account_id,
-                file_key,
-                owner: owner_account_id,
-                location,
-            }
-            .into(),
+            FileSystem::storage_requests(location)
+                .map(|metadata| metadata.data_server_sps.into_inner()),
+            Some(vec![msp_account_id, other_msp_account_id])
         );
     });
 }
 
 #[test]
-fn bsp_stop_storing_not_volunteered_success() {
+fn assign_data_server_fail() {
     new_test_ext().execute_with(|| {
         let owner_account_id = AccountId32::new([1; 32]);
-        let owner = RuntimeOrigin::signed(owner_account_id.clone());
+        let owner_signed = RuntimeOrigin::signed(owner_account_id.clone());
         let bsp_account_id = AccountId32::new([2; 32]);
         let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
-        let file_key = H256::from_slice(&[1; 32]);
+        let msp_account_id = AccountId32::new([3; 32]);
+        let msp_signed = RuntimeOrigin::signed(msp_account_id.clone());
         let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let size = 4;
-        let fingerprint = H256::zero();
-        let storage_amount: StorageData<Test> = 100;
+
+        // Sign up accounts as a Main Storage Provider and a Backup Storage Provider
+        assert_ok!(msp_sign_up(msp_signed.clone(), 100));
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        let msp_id = <Providers as ProvidersInterface>::get_provider(msp_account_id).unwrap();
+        let bsp_id = <Providers as ProvidersInterface>::get_provider(bsp_account_id).unwrap();
+
+        // There is no storage request yet.
+        assert_noop!(
+            FileSystem::assign_data_server(owner_signed.clone(), location.clone(), msp_id),
+            Error::<Test>::StorageRequestNotFound
+        );
 
         // Dispatch storage request.
         assert_ok!(FileSystem::issue_storage_request(
-            owner.clone(),
+            owner_signed.clone(),
             location.clone(),
-            fingerprint,
-            size,
+            H256::zero(),
+            4,
             Default::default(),
+            false,
+            None,
+            None,
+            None,
         ));
 
-        // Sign up account as a Backup Storage Provider
-        assert_ok!(bsp_sign_up(bsp_signed.clone(), storage_amount,));
-
-        // Dispatch BSP stop storing.
-        assert_ok!(FileSystem::bsp_stop_storing(
-            bsp_signed.clone(),
-            file_key,
-            location.clone(),
-            owner_account_id.clone(),
-            fingerprint,
-            size,
-            false
-        ));
-
-        let current_bsps_required: <Test as Config>::StorageRequestBspsRequiredType =
-            TargetBspsRequired::<Test>::get();
-
-        // Assert that the storage request bsps_required was incremented
-        assert_eq!(
-            FileSystem::storage_requests(location.clone()),
-            Some(StorageRequestMetadata {
-                requested_at: 1,
-                owner: owner_account_id.clone(),
-                fingerprint,
-                size,
-                user_peer_ids: Default::default(),
-                data_server_sps: BoundedVec::default(),
-                bsps_required: current_bsps_required.checked_add(1).unwrap(),
-                bsps_confirmed: 0,
-                bsps_volunteered: 0,
-            })
+        // Only the owner or the MSP itself can assign the MSP.
+        assert_noop!(
+            FileSystem::assign_data_server(bsp_signed, location.clone(), msp_id),
+            Error::<Test>::StorageRequestNotAuthorized
         );
 
-        // Assert that the correct event was deposited
-        System::assert_last_event(
-            Event::BspStoppedStoring {
-                bsp: bsp_account_id,
-                file_key,
-                owner: owner_account_id,
-                location,
-            }
-            .into(),
+        // Only MSPs can be data servers.
+        assert_noop!(
+            FileSystem::assign_data_server(owner_signed.clone(), location.clone(), bsp_id),
+            Error::<Test>::NotAMsp
+        );
+        assert_noop!(
+            FileSystem::assign_data_server(
+                owner_signed.clone(),
+                location.clone(),
+                H256::repeat_byte(5),
+            ),
+            Error::<Test>::NotAMsp
         );
-    });
-}
-
-#[test]
-fn bsp_stop_storing_no_storage_request_success() {
-    new_test_ext().execute_with(|| {
-        let bsp_account_id = AccountId32::new([2; 32]);
-        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
-        let file_key = H256::from_slice(&[1; 32]);
-        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
-        let owner_account_id = AccountId32::new([1; 32]);
-        let size = 4;
-        let fingerprint = H256::zero();
 
-        // Dispatch BSP stop storing.
-        assert_ok!(FileSystem::bsp_stop_storing(
-            bsp_signed.clone(),
-            file_key,
+        // A MSP can't be assigned twice.
+        assert_ok!(FileSystem::assign_data_server(
+            owner_signed.clone(),
             location.clone(),
-            owner_account_id.clone(),
-            fingerprint,
-            size,
-            false
+            msp_id,
         ));
-
-        // Assert that the storage request was created with one bsps_required
-        assert_eq!(
-            FileSystem::storage_requests(location.clone()),
-            Some(StorageRequestMetadata {
-                requested_at: 1,
-                owner: owner_account_id.clone(),
-                fingerprint,
-                size,
-                user_peer_ids: Default::default(),
-                data_server_sps: BoundedVec::default(),
-                bsps_required: 1,
-                bsps_confirmed: 0,
-                bsps_volunteered: 0,
-            })
-        );
-
-        // Assert that the correct event was deposited
-        System::assert_last_event(
-            Event::BspStoppedStoring {
-                bsp: bsp_account_id,
-                file_key,
-                owner: owner_account_id,
-                location,
-            }
-            .into(),
+        assert_noop!(
+            FileSystem::assign_data_server(msp_signed, location, msp_id),
+            Error::<Test>::DataServerAlreadyAssigned
         );
     });
 }
@@ -1064,6 +4463,55 @@ fn compute_asymptotic_threshold_point_success() {
     });
 }
 
+#[test]
+fn compute_file_key_commits_to_metadata_success() {
+    new_test_ext().execute_with(|| {
+        let owner = AccountId32::new([1; 32]);
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(b"test");
+
+        let file_key = FileSystem::compute_file_key(&owner, &location, 4, &fingerprint);
+
+        // Changing any field of the metadata changes the file key.
+        assert_ne!(
+            file_key,
+            FileSystem::compute_file_key(&AccountId32::new([2; 32]), &location, 4, &fingerprint)
+        );
+        assert_ne!(
+            file_key,
+            FileSystem::compute_file_key(
+                &owner,
+                &FileLocation::<Test>::try_from(b"other".to_vec()).unwrap(),
+                4,
+                &fingerprint
+            )
+        );
+        assert_ne!(
+            file_key,
+            FileSystem::compute_file_key(&owner, &location, 5, &fingerprint)
+        );
+        assert_ne!(
+            file_key,
+            FileSystem::compute_file_key(&owner, &location, 4, &BlakeTwo256::hash(b"other"))
+        );
+    });
+}
+
+#[test]
+fn file_chunk_challenge_is_a_chunk_of_the_file_success() {
+    new_test_ext().execute_with(|| {
+        for (i, chunk_count) in [1u64, 2, 7, 1024].into_iter().enumerate() {
+            let file_key = BlakeTwo256::hash(&i.to_le_bytes());
+            let challenge = FileSystem::file_chunk_challenge(&file_key, chunk_count);
+
+            // The challenge is the big-endian encoded id of a chunk of the file, padded with zeros.
+            let chunk_id = u64::from_be_bytes(challenge.as_bytes()[..8].try_into().unwrap());
+            assert!(chunk_id < chunk_count);
+            assert!(challenge.as_bytes()[8..].iter().all(|byte| *byte == 0));
+        }
+    });
+}
+
 #[test]
 fn subscribe_bsp_sign_up_decreases_threshold_success() {
     new_test_ext().execute_with(|| {
@@ -1122,6 +4570,75 @@ fn threshold_does_not_exceed_asymptote_success() {
     });
 }
 
+#[test]
+fn bsp_sign_up_and_sign_off_request_update_threshold_success() {
+    new_test_ext().execute_with(|| {
+        let bsp_signed = RuntimeOrigin::signed(AccountId32::new([2; 32]));
+        let initial_threshold = compute_set_get_initial_threshold();
+
+        // Sign up account as a Backup Storage Provider, which notifies the file system pallet
+        assert_ok!(Providers::request_bsp_sign_up(
+            bsp_signed.clone(),
+            100,
+            create_sp_multiaddresses(),
+        ));
+        roll_to(frame_system::Pallet::<Test>::block_number() + 4);
+        assert_ok!(Providers::confirm_sign_up(bsp_signed.clone(), None));
+
+        let signed_up_threshold = FileSystem::bsps_assignment_threshold();
+        assert!(
+            signed_up_threshold < initial_threshold,
+            "Threshold should decrease after BSP sign up"
+        );
+
+        // Requesting to sign off notifies the file system pallet right away, without waiting for the cooldown
+        assert_ok!(Providers::request_sign_off(bsp_signed.clone()));
+        assert!(
+            FileSystem::bsps_assignment_threshold() > signed_up_threshold,
+            "Threshold should increase after BSP sign off request"
+        );
+    });
+}
+
+#[test]
+fn bsp_volunteer_signing_off_fail() {
+    new_test_ext().execute_with(|| {
+        let owner_signed = RuntimeOrigin::signed(AccountId32::new([1; 32]));
+        let bsp_account_id = AccountId32::new([2; 32]);
+        let bsp_signed = RuntimeOrigin::signed(bsp_account_id.clone());
+        let location = FileLocation::<Test>::try_from(b"test".to_vec()).unwrap();
+        let fingerprint = BlakeTwo256::hash(&b"test".to_vec());
+        let peer_id = BoundedVec::try_from(vec![1]).unwrap();
+        let peer_ids: PeerIds<Test> = BoundedVec::try_from(vec![peer_id]).unwrap();
+
+        // Sign up account as a Backup Storage Provider and request to sign off
+        assert_ok!(bsp_sign_up(bsp_signed.clone(), 100));
+        assert_ok!(Providers::request_sign_off(bsp_signed.clone()));
+        let bsp_id = <Providers as ProvidersInterface>::get_provider(bsp_account_id)
+            .expect("BSP should still be registered during the sign off cooldown");
+
+        // Dispatch storage request.
+        assert_ok!(FileSystem::issue_storage_request(
+            owner_signed.clone(),
+            location.clone(),
+            fingerprint,
+            4,
+            peer_ids.clone(),
+            false,
+            None,
+            None,
+            None,
+        ));
+
+        // The BSP is not eligible and cannot volunteer while its sign off is pending
+        assert!(!FileSystem::is_bsp_eligible(&bsp_id, &location));
+        assert_noop!(
+            FileSystem::bsp_volunteer(bsp_signed.clone(), location.clone(), fingerprint),
+            Error::<Test>::BspSigningOff
+        );
+    });
+}
+
 /// Helper function that registers an account as a Backup Storage Provider
 fn bsp_sign_up(
     bsp_signed: RuntimeOrigin,
@@ -1142,6 +4659,39 @@ fn bsp_sign_up(
     // Confirm the sign up of the account as a Backup Storage Provider
     assert_ok!(Providers::confirm_sign_up(bsp_signed.clone(), None));
 
+    // Signing up lowers the assignment threshold, so restore the maximum one set at genesis to let the BSP volunteer
+    // for any file. Tests of the threshold set it explicitly.
+    crate::BspsAssignmentThreshold::<Test>::put(FixedU128::max_value());
+
+    Ok(().into())
+}
+
+/// Helper function that registers an account as a Main Storage Provider
+fn msp_sign_up(
+    msp_signed: RuntimeOrigin,
+    storage_amount: StorageData<Test>,
+) -> DispatchResultWithPostInfo {
+    let multiaddresses = create_sp_multiaddresses();
+    let value_prop = ValueProposition::<Test> {
+        identifier: Default::default(),
+        data_limit: storage_amount,
+        protocols: BoundedVec::new(),
+    };
+
+    // Request to sign up the account as a Main Storage Provider
+    assert_ok!(Providers::request_msp_sign_up(
+        msp_signed.clone(),
+        storage_amount,
+        multiaddresses,
+        value_prop,
+    ));
+
+    // Advance enough blocks for randomness to be valid
+    roll_to(frame_system::Pallet::<Test>::block_number() + 4);
+
+    // Confirm the sign up of the account as a Main Storage Provider
+    assert_ok!(Providers::confirm_sign_up(msp_signed.clone(), None));
+
     Ok(().into())
 }
 
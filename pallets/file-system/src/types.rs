@@ -9,7 +9,6 @@ use crate::Config;
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, Debug, PartialEq, Eq, Clone)]
 #[scale_info(skip_type_params(T))]
 pub struct StorageRequestMetadata<T: Config> {
-    // TODO: Add MSP
     /// Block number at which the storage request was made.
     ///
     /// Used primarily for tracking the age of the request which is useful for
@@ -28,6 +27,10 @@ pub struct StorageRequestMetadata<T: Config> {
     ///
     /// SPs will expect a connection request to be initiated by the user with this Peer Id.
     pub user_peer_ids: PeerIds<T>,
+    /// Multiaddresses of the user who requested the storage, if announced.
+    ///
+    /// SPs can dial the user at these addresses right away instead of discovering them from the user peer ids.
+    pub user_multiaddresses: MultiAddresses<T>,
     /// List of storage providers that can serve the data that is requested to be stored.
     ///
     /// This is useful when a BSP stops serving data and automatically creates a new storage request with no user multiaddresses, since
@@ -48,6 +51,22 @@ pub struct StorageRequestMetadata<T: Config> {
     ///
     /// There can be more than `bsps_required` volunteers, but it is essentially a race for BSPs to confirm that they are storing the data.
     pub bsps_volunteered: T::StorageRequestBspsRequiredType,
+    /// MSP designated by the owner to store the data in one of its buckets.
+    ///
+    /// If set, this MSP can add the file to one of its buckets by confirming it stores it while the storage request is
+    /// open, after which this is cleared.
+    pub msp: Option<ProviderIdFor<T>>,
+    /// Whether the storage request should be automatically renewed once it expires.
+    ///
+    /// Renewals stop once [`Config::MaxAutoRenewals`](crate::Config::MaxAutoRenewals) is reached or when
+    /// the storage request is revoked.
+    pub auto_renew: bool,
+    /// Number of times the storage request has been automatically renewed.
+    pub auto_renewals: u32,
+    /// Deposit held from the owner for this storage request.
+    ///
+    /// It is released back to the owner once the storage request is fulfilled, revoked or expires.
+    pub deposit_paid: BalanceOf<T>,
 }
 
 /// Ephemeral BSP storage request tracking metadata.
@@ -58,9 +77,21 @@ pub struct StorageRequestBspsMetadata<T: Config> {
     ///
     /// This is normally when the BSP submits a proof of storage to the `pallet-proofs-dealer-trie`.
     pub confirmed: bool,
+    /// Root the BSP committed to when confirming it is storing the data, computed from its previous root and the
+    /// proof of the transition to the root including the data.
+    ///
+    /// This is `None` until the BSP confirms. It is dropped with the rest of the metadata once the storage request is
+    /// fulfilled, after which the root is only kept as the root of the BSP in the Providers pallet.
+    pub proven_root: Option<FileKey<T>>,
     pub _phantom: core::marker::PhantomData<T>,
 }
 
+/// Alias for the `Balance` type used in the FileSystem pallet.
+pub type BalanceOf<T> =
+    <<T as crate::Config>::NativeBalance as frame_support::traits::fungible::Inspect<
+        <T as frame_system::Config>::AccountId,
+    >>::Balance;
+
 /// Alias for the `MerkleHash` type used in the ProofsDealerInterface.
 pub type FileKey<T> =
     <<T as crate::Config>::ProofDealer as storage_hub_traits::ProofsDealerInterface>::MerkleHash;
@@ -78,16 +109,30 @@ pub type MaxFilePathSize<T> = <T as crate::Config>::MaxFilePathSize;
 /// Alias for the `Fingerprint` type used in the FileSystem pallet.
 pub type Fingerprint<T> = <T as crate::Config>::Fingerprint;
 
+/// Alias for the `RootTransitionProof` type used in the MutateProvidersInterface.
+pub type RootTransitionProof<T> = <<T as crate::Config>::Providers as storage_hub_traits::MutateProvidersInterface>::RootTransitionProof;
+
 /// Alias for the `StorageData` type used in the MutateProvidersInterface.
 pub type StorageData<T> =
     <<T as crate::Config>::Providers as storage_hub_traits::MutateProvidersInterface>::StorageData;
 
+/// Alias for the `Provider` type used in the MutateProvidersInterface.
+pub type ProviderIdFor<T> =
+    <<T as crate::Config>::Providers as storage_hub_traits::MutateProvidersInterface>::Provider;
+
+/// Alias for the `BucketId` type used in the MutateProvidersInterface.
+pub type BucketIdFor<T> =
+    <<T as crate::Config>::Providers as storage_hub_traits::MutateProvidersInterface>::BucketId;
+
 /// Alias for the `TargetBspsRequired` type used in the FileSystem pallet.
 pub type TargetBspsRequired<T> = <T as crate::Config>::TargetBspsRequired;
 
 /// Byte array representing the file path.
 pub type FileLocation<T> = BoundedVec<u8, MaxFilePathSize<T>>;
 
+/// Byte array used as salt to derive the ID of a bucket. It is bounded like a [`FileLocation`].
+pub type BucketSalt<T> = BoundedVec<u8, MaxFilePathSize<T>>;
+
 /// Alias for the `MaxPeerIdSize` type used in the FileSystem pallet.
 pub type MaxPeerIdSize<T> = <T as crate::Config>::MaxPeerIdSize;
 
@@ -110,3 +155,22 @@ pub type MaxMultiAddresses<T> =
 
 /// Alias for a bounded vector of [`MultiAddress`].
 pub type MultiAddresses<T> = BoundedVec<MultiAddress<T>, MaxMultiAddresses<T>>;
+
+/// Alias for the `MaxBatchStorageRequests` type used in the FileSystem pallet.
+pub type MaxBatchStorageRequests<T> = <T as crate::Config>::MaxBatchStorageRequests;
+
+/// Alias for a bounded vector of storage requests to be issued in a single batch, each one being the
+/// location, fingerprint, size and user peer ids of a file.
+pub type StorageRequestBatch<T> = BoundedVec<
+    (FileLocation<T>, Fingerprint<T>, StorageData<T>, PeerIds<T>),
+    MaxBatchStorageRequests<T>,
+>;
+
+/// Alias for the `MaxConfirmBatch` type used in the FileSystem pallet.
+pub type MaxConfirmBatch<T> = <T as crate::Config>::MaxConfirmBatch;
+
+/// Alias for a bounded vector of storage confirmations to be submitted by a BSP in a single batch, each one being
+/// the location of a file, the proof of the transition of the BSP's root to the one including the file and the proof
+/// of storing it.
+pub type BspConfirmationBatch<T> =
+    BoundedVec<(FileLocation<T>, RootTransitionProof<T>, Proof<T>), MaxConfirmBatch<T>>;
@@ -1,11 +1,23 @@
 use core::cmp::max;
 
 use codec::{Decode, Encode};
-use frame_support::{ensure, pallet_prelude::DispatchResult, traits::Get};
+use frame_support::{
+    ensure,
+    pallet_prelude::DispatchResult,
+    storage::with_storage_layer,
+    traits::{
+        fungible::{Inspect, MutateHold},
+        tokens::{Fortitude, Precision, Preservation},
+        Get,
+    },
+    weights::Weight,
+};
 use frame_system::pallet_prelude::BlockNumberFor;
 use sp_runtime::{
-    traits::{CheckedAdd, CheckedDiv, CheckedMul, EnsureFrom, One, Saturating, Zero},
-    ArithmeticError, BoundedVec, DispatchError,
+    traits::{
+        CheckedAdd, CheckedDiv, CheckedMul, CheckedSub, EnsureFrom, Hash, One, Saturating, Zero,
+    },
+    ArithmeticError, BoundedVec, DispatchError, SaturatedConversion,
 };
 use sp_std::{vec, vec::Vec};
 use storage_hub_traits::ReadProvidersInterface;
@@ -13,11 +25,14 @@ use storage_hub_traits::ReadProvidersInterface;
 use crate::{
     pallet,
     types::{
-        FileLocation, Fingerprint, MaxBspsPerStorageRequest, MultiAddresses, PeerIds, Proof,
-        StorageData, StorageRequestBspsMetadata, StorageRequestMetadata,
+        BalanceOf, BspConfirmationBatch, BucketIdFor, BucketSalt, FileLocation, Fingerprint,
+        MaxBspsPerStorageRequest, MultiAddresses, PeerIds, Proof, ProviderIdFor,
+        RootTransitionProof, StorageData, StorageRequestBatch, StorageRequestBspsMetadata,
+        StorageRequestMetadata,
     },
-    Error, NextAvailableExpirationInsertionBlock, Pallet, StorageRequestBsps,
-    StorageRequestExpirations, StorageRequests,
+    BspConfirmedFiles, Error, Event, HoldReason, NextAvailableExpirationInsertionBlock, Pallet,
+    PendingVolunteeredRequests, PendingVolunteeredStorage, StorageRequestBsps,
+    StorageRequestExpirations, StorageRequests, StorageRequestsByOwner,
 };
 use crate::{
     types::{FileKey, TargetBspsRequired},
@@ -63,76 +78,197 @@ where
     /// In the event that a storage request is created without any user multiaddresses (checkout `do_bsp_stop_storing`),
     /// it is expected that storage providers that do have this file in storage already, will be able to send a
     /// transaction to the chain to add themselves as a data server for the storage request.
+    ///
+    /// If `hold_deposit` is set, a deposit proportional to `size` is held from the owner until the storage request
+    /// is fulfilled, revoked or expires.
     pub(crate) fn do_request_storage(
         owner: T::AccountId,
         location: FileLocation<T>,
         fingerprint: Fingerprint<T>,
         size: StorageData<T>,
         bsps_required: Option<T::StorageRequestBspsRequiredType>,
+        msp: Option<ProviderIdFor<T>>,
+        user_peer_ids: Option<PeerIds<T>>,
+        user_multiaddresses: Option<MultiAddresses<T>>,
+        data_server_sps: BoundedVec<T::AccountId, MaxBspsPerStorageRequest<T>>,
+        auto_renew: bool,
+        hold_deposit: bool,
+    ) -> DispatchResult {
+        Self::register_storage_request(
+            owner,
+            location.clone(),
+            fingerprint,
+            size,
+            bsps_required,
+            msp,
+            user_peer_ids,
+            user_multiaddresses,
+            data_server_sps,
+            auto_renew,
+            hold_deposit,
+        )?;
+
+        Self::queue_storage_request_expiration(location)
+    }
+
+    /// Request storage for multiple files at once.
+    ///
+    /// Every storage request is validated and registered before queueing all of their expirations together,
+    /// so the next available expiration block is only looked up once for the whole batch. The dispatchable
+    /// calling this is transactional, so if any storage request is invalid, none of them is registered.
+    pub(crate) fn do_batch_request_storage(
+        owner: T::AccountId,
+        requests: StorageRequestBatch<T>,
+    ) -> DispatchResult {
+        let mut locations = Vec::with_capacity(requests.len());
+
+        for (location, fingerprint, size, peer_ids) in requests {
+            Self::register_storage_request(
+                owner.clone(),
+                location.clone(),
+                fingerprint,
+                size,
+                None,
+                None,
+                Some(peer_ids),
+                None,
+                Default::default(),
+                false,
+                true,
+            )?;
+
+            locations.push(location);
+        }
+
+        Self::queue_storage_request_expirations(locations)
+    }
+
+    /// Validate and register a storage request, without queueing its expiration.
+    fn register_storage_request(
+        owner: T::AccountId,
+        location: FileLocation<T>,
+        fingerprint: Fingerprint<T>,
+        size: StorageData<T>,
+        bsps_required: Option<T::StorageRequestBspsRequiredType>,
+        msp: Option<ProviderIdFor<T>>,
         user_peer_ids: Option<PeerIds<T>>,
+        user_multiaddresses: Option<MultiAddresses<T>>,
         data_server_sps: BoundedVec<T::AccountId, MaxBspsPerStorageRequest<T>>,
+        auto_renew: bool,
+        hold_deposit: bool,
     ) -> DispatchResult {
-        // TODO: Check user funds and lock them for the storage request.
-        // TODO: Check storage capacity of chosen MSP (when we support MSPs)
+        // TODO: Check storage capacity of chosen MSP
         // TODO: Return error if the file is already stored and overwrite is false.
 
+        if let Some(msp_id) = &msp {
+            ensure!(
+                <T::Providers as storage_hub_traits::ReadProvidersInterface>::is_msp(msp_id),
+                Error::<T>::NotAMsp
+            );
+        }
+
         let bsps_required = bsps_required.unwrap_or(TargetBspsRequired::<T>::get());
 
         if bsps_required.is_zero() {
             return Err(Error::<T>::BspsRequiredCannotBeZero)?;
         }
 
+        ensure!(
+            bsps_required <= T::MaxReplicationTarget::get(),
+            Error::<T>::ReplicationTargetExceedsMax
+        );
+
         if bsps_required > MaxBspsPerStorageRequest::<T>::get().into() {
             return Err(Error::<T>::BspsRequiredExceedsMax)?;
         }
 
+        // The minimum size is never zero, so this also rejects files of size zero.
+        ensure!(
+            size >= T::MinStorageRequestSize::get(),
+            Error::<T>::FileTooSmall
+        );
+
+        // TODO: if we add the overwrite flag, this would only fail if the overwrite flag is false.
+        // Check that storage request is not already registered.
+        ensure!(
+            !<StorageRequests<T>>::contains_key(&location),
+            Error::<T>::StorageRequestAlreadyRegistered
+        );
+
+        // Hold the deposit for the storage request from the owner.
+        let deposit_paid = if hold_deposit {
+            let deposit = Self::compute_storage_request_deposit(size);
+            Self::hold_storage_request_deposit(&owner, deposit)?;
+            deposit
+        } else {
+            BalanceOf::<T>::zero()
+        };
+
         let file_metadata = StorageRequestMetadata::<T> {
             requested_at: <frame_system::Pallet<T>>::block_number(),
             owner,
             fingerprint,
             size,
             user_peer_ids: user_peer_ids.unwrap_or_default(),
+            user_multiaddresses: user_multiaddresses.unwrap_or_default(),
             data_server_sps,
             bsps_required,
             bsps_confirmed: T::StorageRequestBspsRequiredType::zero(),
             bsps_volunteered: T::StorageRequestBspsRequiredType::zero(),
+            msp,
+            auto_renew,
+            auto_renewals: 0,
+            deposit_paid,
         };
 
-        // TODO: if we add the overwrite flag, this would only fail if the overwrite flag is false.
-        // Check that storage request is not already registered.
-        ensure!(
-            !<StorageRequests<T>>::contains_key(&location),
-            Error::<T>::StorageRequestAlreadyRegistered
-        );
-
         // Register storage request.
+        <StorageRequestsByOwner<T>>::insert(&file_metadata.owner, &location, ());
         <StorageRequests<T>>::insert(&location, file_metadata);
 
-        let mut block_to_insert_expiration = Self::next_expiration_insertion_block_number();
+        Ok(())
+    }
 
-        // Get current storage request expirations vec.
-        let storage_request_expirations =
-            <StorageRequestExpirations<T>>::get(block_to_insert_expiration);
+    /// Queue the expiration of a storage request.
+    ///
+    /// The expiration is added at the next block with an available slot, starting from the current block
+    /// plus [`crate::Config::StorageRequestTtl`].
+    pub(crate) fn queue_storage_request_expiration(location: FileLocation<T>) -> DispatchResult {
+        Self::queue_storage_request_expirations(vec![location])
+    }
 
-        // Check size of storage request expirations vec.
-        if storage_request_expirations.len() >= T::MaxExpiredStorageRequests::get() as usize {
-            block_to_insert_expiration = match block_to_insert_expiration.checked_add(&1u8.into()) {
-                Some(block) => block,
-                None => {
-                    return Err(Error::<T>::MaxBlockNumberReached.into());
-                }
-            };
+    /// Queue the expirations of multiple storage requests, looking up the next available expiration block only once.
+    pub(crate) fn queue_storage_request_expirations(
+        locations: Vec<FileLocation<T>>,
+    ) -> DispatchResult {
+        let mut block_to_insert_expiration = Self::next_expiration_insertion_block_number();
 
-            <NextAvailableExpirationInsertionBlock<T>>::set(block_to_insert_expiration);
-        }
+        for location in locations {
+            // Get current number of storage request expirations.
+            let storage_request_expirations =
+                <StorageRequestExpirations<T>>::decode_len(block_to_insert_expiration)
+                    .unwrap_or_default();
+
+            // Check size of storage request expirations vec.
+            if storage_request_expirations >= T::MaxExpiredStorageRequests::get() as usize {
+                block_to_insert_expiration =
+                    match block_to_insert_expiration.checked_add(&1u8.into()) {
+                        Some(block) => block,
+                        None => {
+                            return Err(Error::<T>::MaxBlockNumberReached.into());
+                        }
+                    };
+
+                <NextAvailableExpirationInsertionBlock<T>>::set(block_to_insert_expiration);
+            }
 
-        // Add storage request expiration at next available block.
-        expect_or_err!(
-            // TODO: Verify that try_append gets an empty BoundedVec when appending a first element.
-            <StorageRequestExpirations<T>>::try_append(block_to_insert_expiration, location).ok(),
-            "Storage request expiration should have enough slots available since it was just checked.",
-            Error::<T>::StorageRequestExpiredNoSlotAvailable
-        );
+            // Add storage request expiration at next available block.
+            expect_or_err!(
+                // TODO: Verify that try_append gets an empty BoundedVec when appending a first element.
+                <StorageRequestExpirations<T>>::try_append(block_to_insert_expiration, location).ok(),
+                "Storage request expiration should have enough slots available since it was just checked.",
+                Error::<T>::StorageRequestExpiredNoSlotAvailable
+            );
+        }
 
         Ok(())
     }
@@ -141,7 +277,7 @@ where
     ///
     /// *Callable only by BSP accounts*
     ///
-    /// A BSP can only volunteer for a storage request if it is eligible based on the XOR of the `fingerprint` and the BSP's account ID and if it evaluates to a value
+    /// A BSP can only volunteer for a storage request if it is eligible based on the XOR of the storage request's `fingerprint` and the BSP's ID and if it evaluates to a value
     /// less than the [globally computed threshold](BspsAssignmentThreshold). As the number of BSPs signed up increases, the threshold decreases, meaning there is a
     /// lower chance of a BSP being eligible to volunteer for a storage request.
     ///
@@ -162,12 +298,20 @@ where
             Error::<T>::NotABsp
         );
 
+        // Check that the BSP is not signing off, since it could not confirm storing the file.
+        ensure!(
+            !<T::Providers as storage_hub_traits::ReadProvidersInterface>::is_signing_off(&bsp),
+            Error::<T>::BspSigningOff
+        );
+
         // TODO: Verify BSP has enough storage capacity to store the file
 
         // Check that the storage request exists.
         let mut file_metadata =
             <StorageRequests<T>>::get(&location).ok_or(Error::<T>::StorageRequestNotFound)?;
 
+        // Storage requests are deleted once fulfilled, so the one found still needs confirmations. Volunteers are
+        // accepted past `bsps_required` while confirmations are pending, and released if it gets fulfilled first.
         expect_or_err!(
             file_metadata.bsps_confirmed < file_metadata.bsps_required,
             "Storage request should never have confirmed bsps equal to or greater than required bsps, since they are deleted when it is reached.",
@@ -175,50 +319,56 @@ where
             bool
         );
 
+        // Check that the BSP is not controlled by the owner of the file, since that would defeat the
+        // redundancy guarantees. The BSP id was resolved from `who`, so `who` is its controlling account.
+        ensure!(who != file_metadata.owner, Error::<T>::OwnerCannotVolunteer);
+
         // Check if the BSP is already volunteered for this storage request.
         ensure!(
             !<StorageRequestBsps<T>>::contains_key(&location, &who),
             Error::<T>::BspAlreadyVolunteered
         );
 
-        // Compute BSP's threshold
-        let bsp_threshold: T::ThresholdType = Self::compute_bsp_xor(
-            fingerprint
-                .as_ref()
-                .try_into()
-                .map_err(|_| Error::<T>::FailedToEncodeFingerprint)?,
-            &who.encode()
-                .try_into()
-                .map_err(|_| Error::<T>::FailedToEncodeBsp)?,
-        )?;
-
-        // Get number of blocks since the storage request was issued.
-        let blocks_since_requested: u128 = <frame_system::Pallet<T>>::block_number()
-            .saturating_sub(file_metadata.requested_at)
+        // Check that the storage request can take one more volunteer, so a single party controlling many BSPs
+        // cannot crowd it out. BSPs that stop storing the file are no longer counted.
+        let bsps_volunteered: u32 = file_metadata
+            .bsps_volunteered
             .try_into()
-            .map_err(|_| Error::<T>::FailedToConvertBlockNumber)?;
+            .map_err(|_| Error::<T>::FailedTypeConversion)?;
+        let bsps_required: u32 = file_metadata
+            .bsps_required
+            .try_into()
+            .map_err(|_| Error::<T>::FailedTypeConversion)?;
+        ensure!(
+            bsps_volunteered < Self::max_volunteers(bsps_required),
+            Error::<T>::TooManyVolunteers
+        );
 
-        // Note. This should never fail since the storage request expiration would never reach such a high number.
-        // Storage requests are cleared after reaching `StorageRequestTtl` blocks which is defined in the pallet Config.
-        let blocks_since_requested: T::ThresholdType =
-            T::ThresholdType::ensure_from(blocks_since_requested)?;
+        // Check that the fingerprint matches the one of the storage request, so a BSP cannot choose one that favours it.
+        ensure!(
+            fingerprint == file_metadata.fingerprint,
+            Error::<T>::FingerprintMismatch
+        );
 
-        // Compute the threshold increasing rate.
-        let rate_increase =
-            blocks_since_requested.saturating_mul(T::AssignmentThresholdMultiplier::get());
+        // Compute BSP's threshold as the XOR distance between its ID and the fingerprint of the storage request.
+        let bsp_threshold = Self::compute_bsp_threshold(&bsp, &fingerprint)?;
 
         // Compute current threshold needed to volunteer.
-        let threshold = rate_increase.saturating_add(BspsAssignmentThreshold::<T>::get());
+        let threshold = Self::storage_request_threshold(file_metadata.requested_at)?;
 
         // Check that the BSP's threshold is under the threshold required to qualify as BSP for the storage request.
         ensure!(bsp_threshold <= (threshold), Error::<T>::AboveThreshold);
 
+        // Keep track of the storage the BSP commits to until it confirms storing the file.
+        Self::add_pending_volunteered_request(&who, &location, file_metadata.size);
+
         // Add BSP to storage request metadata.
         <StorageRequestBsps<T>>::insert(
             &location,
             &who,
             StorageRequestBspsMetadata::<T> {
                 confirmed: false,
+                proven_root: None,
                 _phantom: Default::default(),
             },
         );
@@ -243,22 +393,122 @@ where
         Ok(multiaddresses)
     }
 
+    /// Cancel the volunteering of a BSP for a storage request it has not confirmed storing yet.
+    ///
+    /// *Callable only by BSP accounts*
+    ///
+    /// The BSP is removed from the volunteers of the storage request, freeing its slot for another BSP, and the storage it
+    /// committed to is released. A BSP that already confirmed storing the file has to use `bsp_stop_storing` instead.
+    pub(crate) fn do_bsp_cancel_volunteer(
+        who: T::AccountId,
+        location: FileLocation<T>,
+    ) -> Result<Fingerprint<T>, DispatchError> {
+        let bsp =
+            <T::Providers as storage_hub_traits::ProvidersInterface>::get_provider(who.clone())
+                .ok_or(Error::<T>::NotABsp)?;
+
+        // Check that the provider is indeed a BSP.
+        ensure!(
+            <T::Providers as storage_hub_traits::ReadProvidersInterface>::is_bsp(&bsp),
+            Error::<T>::NotABsp
+        );
+
+        // Check that the storage request exists.
+        let mut file_metadata =
+            <StorageRequests<T>>::get(&location).ok_or(Error::<T>::StorageRequestNotFound)?;
+
+        // Check that the BSP has volunteered for the storage request.
+        let bsp_metadata =
+            <StorageRequestBsps<T>>::get(&location, &who).ok_or(Error::<T>::BspNotVolunteered)?;
+
+        // Check that the BSP has not confirmed storing the file, since it would then be accounted as storing it.
+        ensure!(!bsp_metadata.confirmed, Error::<T>::BspAlreadyConfirmed);
+
+        // Remove the BSP from the volunteers of the storage request, releasing the storage it committed to.
+        <StorageRequestBsps<T>>::remove(&location, &who);
+        Self::remove_pending_volunteered_request(&who, &location);
+
+        // Decrement the number of bsps volunteered.
+        match file_metadata
+            .bsps_volunteered
+            .checked_sub(&T::StorageRequestBspsRequiredType::one())
+        {
+            Some(dec_bsps_volunteered) => {
+                file_metadata.bsps_volunteered = dec_bsps_volunteered;
+            }
+            None => {
+                return Err(ArithmeticError::Underflow.into());
+            }
+        }
+
+        let fingerprint = file_metadata.fingerprint;
+        <StorageRequests<T>>::set(&location, Some(file_metadata));
+
+        Ok(fingerprint)
+    }
+
+    /// Increase the number of BSPs required by an open storage request.
+    ///
+    /// *Callable only by the owner of the storage request*
+    ///
+    /// The storage request stays open until the new number of BSPs required confirm storing the file, so new BSPs can
+    /// volunteer for it. The new number of BSPs required is subject to the same limits as when issuing a storage request,
+    /// and it is returned.
+    pub(crate) fn do_increase_replication(
+        who: T::AccountId,
+        location: FileLocation<T>,
+        additional: T::StorageRequestBspsRequiredType,
+    ) -> Result<T::StorageRequestBspsRequiredType, DispatchError> {
+        // Check that the storage request exists.
+        let mut file_metadata =
+            <StorageRequests<T>>::get(&location).ok_or(Error::<T>::StorageRequestNotFound)?;
+
+        // Check that the sender is the same as the one who requested the storage.
+        ensure!(
+            file_metadata.owner == who,
+            Error::<T>::StorageRequestNotAuthorized
+        );
+
+        let bsps_required = file_metadata
+            .bsps_required
+            .checked_add(&additional)
+            .ok_or(Error::<T>::ReplicationTargetExceedsMax)?;
+
+        ensure!(
+            bsps_required <= T::MaxReplicationTarget::get(),
+            Error::<T>::ReplicationTargetExceedsMax
+        );
+
+        if bsps_required > MaxBspsPerStorageRequest::<T>::get().into() {
+            return Err(Error::<T>::BspsRequiredExceedsMax)?;
+        }
+
+        file_metadata.bsps_required = bsps_required;
+        <StorageRequests<T>>::set(&location, Some(file_metadata));
+
+        Ok(bsps_required)
+    }
+
     /// Confirm storing a file.
     ///
     /// *Callable only by BSP accounts*
     ///
     /// This function can only be called after a BSP has volunteered for the storage request. The BSP must provide a merkle proof of the file
-    /// and a proof of inclusion of the `file_key` in their merkle patricia trie.
+    /// and a proof of the transition of their merkle patricia trie to the one including the `file_key`. The merkle proof of the file must answer
+    /// the chunk challenge derived from the `file_key`, and the number of chunks implied by it must match the number of chunks derived from the
+    /// `size` declared in the storage request and [`crate::Config::FileChunkSize`], as checked by [`Self::verify_file_proof`].
+    ///
+    /// If the proofs are valid, the root of the BSP is updated to the new root of the merkle patricia trie, computed from its current root and
+    /// the proof of the transition, and the number of `bsps_confirmed` is incremented. If the number of `bsps_confirmed` reaches the number of
+    /// `bsps_required`, the storage request is deleted. Finally the BSP's data used is incremented by the size of the file.
     ///
-    /// If the proof is valid, the root of the BSP is updated to reflect the new root of the merkle patricia trie and the number of `bsps_confirmed` is
-    /// incremented. If the number of `bsps_confirmed` reaches the number of `bsps_required`, the storage request is deleted. Finally the BSP's data
-    /// used is incremented by the size of the file.
+    /// Returns whether the storage request was fulfilled by this confirmation.
     pub(crate) fn do_bsp_confirm_storing(
         who: T::AccountId,
         location: FileLocation<T>,
-        root: FileKey<T>,
+        root_proof: RootTransitionProof<T>,
         proof: Proof<T>,
-    ) -> DispatchResult {
+    ) -> Result<bool, DispatchError> {
         let bsp =
             <T::Providers as storage_hub_traits::ProvidersInterface>::get_provider(who.clone())
                 .ok_or(Error::<T>::NotABsp)?;
@@ -273,13 +523,6 @@ where
         let mut file_metadata =
             <StorageRequests<T>>::get(&location).ok_or(Error::<T>::StorageRequestNotFound)?;
 
-        expect_or_err!(
-                    file_metadata.bsps_confirmed < file_metadata.bsps_required,
-                    "Storage request should never have confirmed bsps equal to or greater than required bsps, since they are deleted when it is reached.",
-                    Error::<T>::StorageRequestBspsRequiredFulfilled,
-                    bool
-                );
-
         // Check that the BSP has volunteered for the storage request.
         ensure!(
             <StorageRequestBsps<T>>::contains_key(&location, &who),
@@ -295,6 +538,17 @@ where
         // Check that the storage provider has not already confirmed storing the file.
         ensure!(!requests.confirmed, Error::<T>::BspAlreadyConfirmed);
 
+        // Check that the BSP has enough available capacity to store the file.
+        let available_capacity =
+            <T::Providers as storage_hub_traits::ReadProvidersInterface>::get_available_capacity(
+                &bsp,
+            )
+            .ok_or(Error::<T>::NotABsp)?;
+        ensure!(
+            available_capacity >= file_metadata.size,
+            Error::<T>::InsufficientAvailableCapacity
+        );
+
         // Check that the number of confirmed bsps is less than the required bsps and increment it.
         expect_or_err!(
             file_metadata.bsps_confirmed < file_metadata.bsps_required,
@@ -316,15 +570,39 @@ where
             }
         }
 
-        // Check that the proof is valid.
-        <T::ProofDealer as storage_hub_traits::ProofsDealerInterface>::verify_proof(
-            &bsp, &root, &proof,
+        // Check that the proof shows the BSP holds the file of the storage request.
+        let file_key = Self::compute_file_key(
+            &file_metadata.owner,
+            &location,
+            file_metadata.size,
+            &file_metadata.fingerprint,
+        );
+        Self::verify_file_proof(
+            &bsp,
+            &file_key,
+            &file_metadata.fingerprint,
+            file_metadata.size,
+            &proof,
         )?;
 
+        // Update the root of the BSP to the one resulting from the transition, which fails if it is invalid.
+        let root = <T::Providers as storage_hub_traits::MutateProvidersInterface>::change_root_bsp(
+            bsp.clone(),
+            &root_proof,
+        )?;
+
+        // The storage the BSP committed to is now part of its used data.
+        Self::remove_pending_volunteered_request(&who, &location);
+
         // Remove storage request if we reached the required number of bsps.
-        if file_metadata.bsps_confirmed == file_metadata.bsps_required {
+        let fulfilled = file_metadata.bsps_confirmed == file_metadata.bsps_required;
+        if fulfilled {
             // Remove storage request metadata.
             <StorageRequests<T>>::remove(&location);
+            <StorageRequestsByOwner<T>>::remove(&file_metadata.owner, &location);
+
+            // Release the deposit of the storage request back to its owner.
+            Self::release_storage_request_deposit(&file_metadata)?;
 
             // There should only be the number of bsps volunteered under the storage request prefix.
             let remove_limit: u32 = file_metadata
@@ -332,7 +610,16 @@ where
                 .try_into()
                 .map_err(|_| Error::<T>::FailedTypeConversion)?;
 
+            // The volunteers that have not confirmed storing the file yet can no longer do it, so they are notified.
+            let unconfirmed_bsps: Vec<T::AccountId> =
+                <StorageRequestBsps<T>>::iter_prefix(&location)
+                    .take(remove_limit as usize)
+                    .filter(|(bsp, bsp_metadata)| *bsp != who && !bsp_metadata.confirmed)
+                    .map(|(bsp, _)| bsp)
+                    .collect();
+
             // Remove storage request bsps
+            Self::clear_pending_volunteered_requests(&location);
             let removed = <StorageRequestBsps<T>>::clear_prefix(&location, remove_limit, None);
 
             // Make sure that the expected number of bsps were removed.
@@ -342,6 +629,13 @@ where
                 Error::<T>::UnexpectedNumberOfRemovedVolunteeredBsps,
                 bool
             );
+
+            if !unconfirmed_bsps.is_empty() {
+                Self::deposit_event(Event::StorageRequestVolunteersReleased {
+                    location: location.clone(),
+                    bsps: BoundedVec::truncate_from(unconfirmed_bsps),
+                });
+            }
         } else {
             // Update storage request metadata.
             <StorageRequests<T>>::set(&location, Some(file_metadata.clone()));
@@ -350,20 +644,121 @@ where
             <StorageRequestBsps<T>>::mutate(&location, &who, |bsp| {
                 if let Some(bsp) = bsp {
                     bsp.confirmed = true;
+                    bsp.proven_root = Some(root);
                 }
             });
         }
 
-        // Update root of bsp.
-        <T::Providers as storage_hub_traits::MutateProvidersInterface>::change_root_bsp(bsp, root)?;
+        // Reward the BSP for storing the file with a reputation increase.
+        <T::Providers as storage_hub_traits::MutateProvidersInterface>::change_reputation(&bsp, 1)?;
 
-        // Add data to storage provider.
+        // Add data to storage provider, recording it so the same amount is released when it stops storing the file.
         <T::Providers as storage_hub_traits::MutateProvidersInterface>::increase_data_used(
             &who,
             file_metadata.size,
         )?;
+        <BspConfirmedFiles<T>>::insert(&who, &location, file_metadata.size);
 
-        Ok(())
+        Ok(fulfilled)
+    }
+
+    /// Confirm storing multiple files at once.
+    ///
+    /// Each confirmation goes through the same validations as [`Self::do_bsp_confirm_storing`]. The dispatchable
+    /// calling this is transactional, so if any confirmation is invalid, none of them is applied.
+    ///
+    /// Returns, for each confirmation, whether the storage request was fulfilled by it.
+    pub(crate) fn do_bsp_confirm_storing_batch(
+        who: T::AccountId,
+        confirmations: BspConfirmationBatch<T>,
+    ) -> Result<Vec<bool>, DispatchError> {
+        confirmations
+            .into_iter()
+            .map(|(location, root_proof, proof)| {
+                Self::do_bsp_confirm_storing(who.clone(), location, root_proof, proof)
+            })
+            .collect()
+    }
+
+    /// Confirm storing a file as the MSP designated by its storage request.
+    ///
+    /// *Callable only by the MSP designated by the owner of the storage request*
+    ///
+    /// The MSP must provide a merkle proof of the file, which must reconstruct its fingerprint, answer the chunk challenge
+    /// derived from its file key and imply the number of chunks derived from the `size` declared in the storage request,
+    /// same as for [`Self::do_bsp_confirm_storing`].
+    ///
+    /// If the proof is valid, the file is added to the bucket of the MSP owned by the owner of the file with its ID
+    /// derived from `bucket_salt`, which is created if it does not exist yet, so the same salt can be used to keep many
+    /// files in one bucket. The root of the bucket is changed to the one resulting from the transition shown by
+    /// `root_proof`, and the MSP's data used is incremented by the size of the file. The MSP is then cleared from the
+    /// storage request, so it can not confirm the file twice. The dispatchable calling this is transactional, so if the
+    /// file cannot be added to the bucket (e.g. the root transition is invalid), the confirmation is reverted.
+    ///
+    /// Returns the ID of the bucket holding the file.
+    pub(crate) fn do_msp_confirm_storing(
+        who: T::AccountId,
+        location: FileLocation<T>,
+        root_proof: RootTransitionProof<T>,
+        proof: Proof<T>,
+        bucket_salt: BucketSalt<T>,
+    ) -> Result<BucketIdFor<T>, DispatchError> {
+        let msp =
+            <T::Providers as storage_hub_traits::ProvidersInterface>::get_provider(who.clone())
+                .ok_or(Error::<T>::NotAMsp)?;
+
+        // Check that the provider is indeed a MSP.
+        ensure!(
+            <T::Providers as storage_hub_traits::ReadProvidersInterface>::is_msp(&msp),
+            Error::<T>::NotAMsp
+        );
+
+        // Check that the storage request exists.
+        let mut file_metadata =
+            <StorageRequests<T>>::get(&location).ok_or(Error::<T>::StorageRequestNotFound)?;
+
+        // Check that the MSP is the one designated by the owner to store the file.
+        ensure!(
+            file_metadata.msp.as_ref() == Some(&msp),
+            Error::<T>::MspNotDesignated
+        );
+
+        // Check that the proof shows the MSP holds the file of the storage request, proving the chunk challenged by its file key.
+        let file_key = Self::compute_file_key(
+            &file_metadata.owner,
+            &location,
+            file_metadata.size,
+            &file_metadata.fingerprint,
+        );
+        Self::verify_file_proof(
+            &msp,
+            &file_key,
+            &file_metadata.fingerprint,
+            file_metadata.size,
+            &proof,
+        )?;
+
+        // Add the file to the bucket of the MSP, updating its root.
+        let bucket_id =
+            <T::Providers as storage_hub_traits::MutateProvidersInterface>::add_file_to_bucket(
+                msp,
+                file_metadata.owner.clone(),
+                &bucket_salt,
+                file_metadata.size,
+                &root_proof,
+            )?;
+
+        // Add data to the MSP, which fails if it does not have enough available capacity.
+        <T::Providers as storage_hub_traits::MutateProvidersInterface>::increase_data_used(
+            &who,
+            file_metadata.size,
+        )?;
+
+        // The file is already in a bucket of the MSP, so it can not be confirmed again.
+        file_metadata.msp = None;
+        <StorageRequests<T>>::set(&location, Some(file_metadata));
+
+        Ok(bucket_id)
     }
 
     /// Revoke a storage request.
@@ -373,12 +768,21 @@ where
     /// When the owner revokes a storage request which has already been confirmed by some BSPs, a challenge (with priority) is
     /// issued to force the BSPs to update their storage root to uninclude the file from their storage.
     ///
-    /// All BSPs that have volunteered to store the file are removed from the storage request and the storage request is deleted.
+    /// All BSPs that have volunteered to store the file are released from the storage request, and the ones that confirmed
+    /// storing it have the size of the file released from their used data. The storage request is then deleted.
+    ///
+    /// Returns the fingerprint of the file and the BSPs released, so they can be notified to stop storing it.
     pub(crate) fn do_revoke_storage_request(
         who: T::AccountId,
         location: FileLocation<T>,
         file_key: FileKey<T>,
-    ) -> DispatchResult {
+    ) -> Result<
+        (
+            Fingerprint<T>,
+            BoundedVec<T::AccountId, MaxBspsPerStorageRequest<T>>,
+        ),
+        DispatchError,
+    > {
         // Check that the storage request exists.
         ensure!(
             <StorageRequests<T>>::contains_key(&location),
@@ -412,7 +816,41 @@ where
             .try_into()
             .map_err(|_| Error::<T>::FailedTypeConversion)?;
 
+        // The volunteers are released in this block, so their number has to be bounded.
+        ensure!(
+            remove_limit <= MaxBspsPerStorageRequest::<T>::get(),
+            Error::<T>::TooManyVolunteersToRevoke
+        );
+
+        // Collect the volunteers before removing them, so they can be notified to stop storing the file.
+        let volunteers: Vec<(T::AccountId, StorageRequestBspsMetadata<T>)> =
+            <StorageRequestBsps<T>>::iter_prefix(&location)
+                .take(remove_limit as usize)
+                .collect();
+
+        // The BSPs that confirmed storing the file no longer store it, so the data they were accounted for is released.
+        for (bsp, bsp_metadata) in volunteers.iter() {
+            if !bsp_metadata.confirmed {
+                continue;
+            }
+
+            if let Some(confirmed_size) = <BspConfirmedFiles<T>>::take(bsp, &location) {
+                <T::Providers as storage_hub_traits::MutateProvidersInterface>::decrease_data_used(
+                    bsp,
+                    confirmed_size,
+                )?;
+            }
+        }
+
+        let bsps: BoundedVec<T::AccountId, MaxBspsPerStorageRequest<T>> = volunteers
+            .into_iter()
+            .map(|(bsp, _)| bsp)
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| Error::<T>::TooManyVolunteersToRevoke)?;
+
         // Remove storage request bsps
+        Self::clear_pending_volunteered_requests(&location);
         let removed = <StorageRequestBsps<T>>::clear_prefix(&location, remove_limit, None);
 
         // Make sure that the expected number of bsps were removed.
@@ -425,10 +863,156 @@ where
 
         // Remove storage request.
         <StorageRequests<T>>::remove(&location);
+        <StorageRequestsByOwner<T>>::remove(&who, &location);
+
+        // Release the deposit of the storage request back to its owner.
+        Self::release_storage_request_deposit(&file_metadata)?;
+
+        Ok((file_metadata.fingerprint, bsps))
+    }
+
+    /// Assign a MSP as a data server of a storage request.
+    ///
+    /// Checks and logic:
+    /// 1. The storage request exists.
+    /// 2. The provider being assigned is a registered MSP.
+    /// 3. The caller is either the owner of the storage request or the owner of the MSP being assigned.
+    /// 4. The MSP is not already a data server of the storage request.
+    /// 5. The account of the MSP is added to the `data_server_sps` of the storage request, which are bounded.
+    pub(crate) fn do_assign_data_server(
+        who: T::AccountId,
+        location: FileLocation<T>,
+        msp_id: ProviderIdFor<T>,
+    ) -> DispatchResult {
+        // Check that the storage request exists.
+        let mut file_metadata =
+            <StorageRequests<T>>::get(&location).ok_or(Error::<T>::StorageRequestNotFound)?;
+
+        // Check that the provider is a MSP.
+        ensure!(
+            <T::Providers as storage_hub_traits::ReadProvidersInterface>::is_msp(&msp_id),
+            Error::<T>::NotAMsp
+        );
+        let msp = expect_or_err!(
+            <T::Providers as storage_hub_traits::ReadProvidersInterface>::get_owner_account(
+                &msp_id
+            ),
+            "Registered MSP should have an owner account",
+            Error::<T>::NotAMsp
+        );
+
+        // Check that the caller is allowed to assign the data server.
+        ensure!(
+            file_metadata.owner == who || msp == who,
+            Error::<T>::StorageRequestNotAuthorized
+        );
+
+        // Check that the MSP is not already a data server.
+        ensure!(
+            !file_metadata.data_server_sps.contains(&msp),
+            Error::<T>::DataServerAlreadyAssigned
+        );
+
+        file_metadata
+            .data_server_sps
+            .try_push(msp)
+            .map_err(|_| Error::<T>::TooManyDataServers)?;
+
+        <StorageRequests<T>>::insert(&location, file_metadata);
 
         Ok(())
     }
 
+    /// Automatically renew an expired storage request.
+    ///
+    /// A storage request is renewed only if it still exists, its owner opted in to auto-renewal and it has not
+    /// been renewed [`crate::Config::MaxAutoRenewals`] times yet. A revoked storage request no longer exists, so
+    /// it is never renewed.
+    ///
+    /// Returns `true` if the storage request was renewed, in which case its expiration is queued again.
+    ///
+    /// This is called from the `on_idle` hook, which is not transactional, so it must be called within a storage layer
+    /// to not leave a partial renewal behind if it fails.
+    pub(crate) fn do_auto_renew_storage_request(
+        location: &FileLocation<T>,
+    ) -> Result<bool, DispatchError> {
+        let Some(mut file_metadata) = <StorageRequests<T>>::get(location) else {
+            return Ok(false);
+        };
+
+        if !file_metadata.auto_renew || file_metadata.auto_renewals >= T::MaxAutoRenewals::get() {
+            return Ok(false);
+        }
+
+        file_metadata.auto_renewals = file_metadata.auto_renewals.saturating_add(1);
+
+        Self::queue_storage_request_expiration(location.clone())?;
+
+        <StorageRequests<T>>::insert(location, file_metadata);
+
+        Ok(true)
+    }
+
+    /// Process the expiration of the storage request at `location`, renewing it if it opted in to auto-renewal and
+    /// removing it otherwise.
+    ///
+    /// Returns the weight used, which is at most `expired_storage_requests_cleanup_weight(1)` without the weight of
+    /// taking the expirations of the block.
+    pub(crate) fn process_expired_storage_request(location: FileLocation<T>) -> Weight {
+        let db_weight = T::DbWeight::get();
+
+        // Renew within a storage layer so a failed renewal leaves the storage request untouched.
+        match with_storage_layer(|| Self::do_auto_renew_storage_request(&location)) {
+            Ok(true) => {
+                Self::deposit_event(Event::StorageRequestAutoRenewed { location });
+                return db_weight.reads_writes(3, 3);
+            }
+            Ok(false) => {}
+            // The storage request is left to expire, as it would if it was not auto-renewed.
+            Err(error) => log::error!(
+                "Failed to auto-renew storage request for location {:?}, letting it expire: {:?}",
+                location,
+                error
+            ),
+        }
+
+        let mut volunteers: u64 = 0;
+
+        // Release the deposit of the expired storage request back to its owner.
+        // Storage requests that were already fulfilled or revoked are no longer around.
+        if let Some(file_metadata) = <StorageRequests<T>>::take(&location) {
+            <StorageRequestsByOwner<T>>::remove(&file_metadata.owner, &location);
+
+            // The volunteers are no longer committed to storing the file. There are at most
+            // `MaxVolunteersPerRequest` of them, which is accounted for in the weight of the expiration.
+            Self::clear_pending_volunteered_requests(&location);
+            volunteers = <StorageRequestBsps<T>>::clear_prefix(
+                &location,
+                T::MaxVolunteersPerRequest::get(),
+                None,
+            )
+            .backend
+            .into();
+
+            if let Err(error) = Self::release_storage_request_deposit(&file_metadata) {
+                log::error!(
+                    "Failed to release the deposit of expired storage request for location {:?}: {:?}",
+                    location,
+                    error
+                );
+            }
+            Self::deposit_event(Event::StorageRequestExpired {
+                location,
+                bsps_confirmed: file_metadata.bsps_confirmed,
+                bsps_required: file_metadata.bsps_required,
+            });
+        }
+
+        db_weight
+            .reads_writes(3, 4)
+            .saturating_add(db_weight.reads_writes(3, 3).saturating_mul(volunteers))
+    }
+
     /// BSP stops storing a file.
     ///
     /// *Callable only by BSP accounts*
@@ -453,24 +1037,54 @@ where
     ///
     /// *This function does not give BSPs the possibility to remove themselves from being a __volunteer__ of a storage request.*
     ///
-    /// A proof of storing the file is required to record the new root of the BSPs merkle patricia trie. First we validate the proof
-    /// and ensure the `file_key` is indeed part of the merkle patricia trie. Then finally we re-compute the new merkle patricia trie root
-    /// without the `file_key` and update the root of the BSP.
+    /// A proof of storing the file is required before any of the above is applied, so a BSP can't alter the number of `bsps_required`
+    /// of a file it never stored. The `file_key` has to be the hash of the metadata provided, so the BSP can't prove a file it holds to
+    /// stop storing another one, and the proof has to show that `file_key` is part of the Forest of the BSP, whose root is the one it has
+    /// on-chain.
+    ///
+    /// Once the file is released, the root of the BSP is changed to the one computed from its current root and the `root_proof` of the
+    /// transition removing the `file_key` from its merkle patricia trie.
     ///
     /// `can_serve`: A flag that indicates if the BSP can serve the file to other BSPs. If the BSP can serve the file, then
     /// they are added to the storage request as a data server.
     pub(crate) fn do_bsp_stop_storing(
         who: T::AccountId,
-        _file_key: FileKey<T>,
+        file_key: FileKey<T>,
         location: FileLocation<T>,
         owner: T::AccountId,
         fingerprint: Fingerprint<T>,
         size: StorageData<T>,
         can_serve: bool,
+        root_proof: RootTransitionProof<T>,
+        proof: Proof<T>,
     ) -> DispatchResult {
         // TODO: charge SP for this action.
-        // TODO: Require & verify proof that the file key is indeed stored by the BSP.
-        // TODO: Check that the hash of all the metadata is equal to the `file_key` hash.
+        let bsp =
+            <T::Providers as storage_hub_traits::ProvidersInterface>::get_provider(who.clone())
+                .ok_or(Error::<T>::NotABsp)?;
+
+        // Check that the provider is indeed a BSP.
+        ensure!(
+            <T::Providers as storage_hub_traits::ReadProvidersInterface>::is_bsp(&bsp),
+            Error::<T>::NotABsp
+        );
+
+        // Check that the file key is the one of the file described by the metadata provided.
+        ensure!(
+            Self::compute_file_key(&owner, &location, size, &fingerprint) == file_key,
+            Error::<T>::FileKeyMismatch
+        );
+
+        // Check that the proof shows the file key in the Forest committed to by the on-chain root of the BSP, proving
+        // the BSP is storing the file.
+        let bsp_root =
+            <T::Providers as storage_hub_traits::ProvidersInterface>::get_root(bsp.clone())
+                .ok_or(Error::<T>::NotABsp)?;
+        <T::ProofDealer as storage_hub_traits::ProofsDealerInterface>::verify_key_proof(
+            &bsp, &bsp_root, &file_key, &proof,
+        )
+        .map_err(|_| Error::<T>::InvalidProof)?;
+
         match <StorageRequests<T>>::get(&location) {
             Some(mut metadata) => {
                 match <StorageRequestBsps<T>>::get(&location, &who) {
@@ -512,20 +1126,84 @@ where
                     size,
                     Some(1u32.into()),
                     None,
+                    None,
+                    None,
                     if can_serve {
                         BoundedVec::try_from(vec![who.clone()]).unwrap()
                     } else {
                         BoundedVec::default()
                     },
+                    false,
+                    false,
                 )?;
             }
         };
 
-        // TODO: compute new root from proof and update the storage root of bsp.
+        // The BSP no longer stores the file, so the data it was accounted for when confirming storing it is released.
+        // Nothing is released if it never confirmed storing the file at `location`, since the size provided by the
+        // caller can not be trusted.
+        if let Some(confirmed_size) = <BspConfirmedFiles<T>>::take(&who, &location) {
+            <T::Providers as storage_hub_traits::MutateProvidersInterface>::decrease_data_used(
+                &who,
+                confirmed_size,
+            )?;
+        }
+
+        // Remove the file from the Forest of the BSP, updating its root to the one resulting from the transition,
+        // which fails if it is invalid.
+        <T::Providers as storage_hub_traits::MutateProvidersInterface>::change_root_bsp(
+            bsp,
+            &root_proof,
+        )?;
 
         Ok(())
     }
 
+    /// Maximum number of BSPs that can be volunteered at the same time for a storage request requiring `bsps_required` BSPs.
+    ///
+    /// This is `bsps_required` times [`crate::Config::VolunteersPerRequiredBsp`], capped by
+    /// [`crate::Config::MaxVolunteersPerRequest`].
+    pub(crate) fn max_volunteers(bsps_required: u32) -> u32 {
+        bsps_required
+            .saturating_mul(T::VolunteersPerRequiredBsp::get())
+            .min(T::MaxVolunteersPerRequest::get())
+    }
+
+    /// Weight of cleaning up `expired_requests_count` expired storage requests of a block.
+    ///
+    /// Taking the expirations of a block, and writing back the ones not processed, is 1 read and 1 write, and each
+    /// expired storage request takes at most 3 reads and 4 writes, which is the case when it is removed, plus 3 reads
+    /// and 3 writes to release each of its volunteers, of which there are at most
+    /// [`crate::Config::MaxVolunteersPerRequest`].
+    pub(crate) fn expired_storage_requests_cleanup_weight(expired_requests_count: u64) -> Weight {
+        let db_weight = T::DbWeight::get();
+        let max_volunteers: u64 = T::MaxVolunteersPerRequest::get().into();
+        db_weight.reads_writes(1, 1).saturating_add(
+            db_weight
+                .reads_writes(3 + 3 * max_volunteers, 4 + 3 * max_volunteers)
+                .saturating_mul(expired_requests_count),
+        )
+    }
+
+    /// Upper bound of the weight of issuing a storage request.
+    ///
+    /// Checking the designated MSP, the default number of BSPs required and that the location is free takes 3 reads, holding
+    /// the deposit from the owner takes 2 reads and 2 writes, registering the storage request takes 2 writes, and queueing
+    /// its expiration takes 2 reads and 2 writes.
+    pub(crate) fn issue_storage_request_weight() -> Weight {
+        T::DbWeight::get().reads_writes(7, 6)
+    }
+
+    /// Upper bound of the weight of a BSP confirming storing a file.
+    ///
+    /// The worst case is a confirmation fulfilling the storage request, which takes 18 reads and 11 writes, plus 4 reads
+    /// and 3 writes for each BSP that volunteered for the storage request, of which there are at most
+    /// [`crate::Config::MaxBspsPerStorageRequest`].
+    pub(crate) fn bsp_confirm_storing_weight() -> Weight {
+        let max_volunteers: u64 = T::MaxBspsPerStorageRequest::get().into();
+        T::DbWeight::get().reads_writes(18 + 4 * max_volunteers, 11 + 3 * max_volunteers)
+    }
+
     /// Get the block number at which the storage request will expire.
     ///
     /// This will also update the [`CurrentExpirationBlock`] if the current expiration block pointer is lower then the [`crate::Config::StorageRequestTtl`].
@@ -548,10 +1226,101 @@ where
         block_to_insert_expiration
     }
 
+    /// Compute the deposit held from the owner of a storage request for a file of the given `size`.
+    pub(crate) fn compute_storage_request_deposit(size: StorageData<T>) -> BalanceOf<T> {
+        let size: u128 = size.saturated_into();
+        T::StorageRequestDepositPerData::get().saturating_mul(size.saturated_into())
+    }
+
+    /// Hold the deposit of a storage request from its owner.
+    fn hold_storage_request_deposit(owner: &T::AccountId, deposit: BalanceOf<T>) -> DispatchResult {
+        // Check if the owner has enough balance to pay the deposit
+        let owner_balance =
+            T::NativeBalance::reducible_balance(owner, Preservation::Preserve, Fortitude::Polite);
+        ensure!(
+            owner_balance >= deposit,
+            Error::<T>::NotEnoughBalanceForDeposit
+        );
+
+        T::NativeBalance::hold(&HoldReason::StorageRequestDeposit.into(), owner, deposit)
+    }
+
+    /// Release the deposit held for a storage request back to its owner.
+    pub(crate) fn release_storage_request_deposit(
+        file_metadata: &StorageRequestMetadata<T>,
+    ) -> DispatchResult {
+        if file_metadata.deposit_paid.is_zero() {
+            return Ok(());
+        }
+
+        T::NativeBalance::release(
+            &HoldReason::StorageRequestDeposit.into(),
+            &file_metadata.owner,
+            file_metadata.deposit_paid,
+            Precision::BestEffort,
+        )?;
+
+        Ok(())
+    }
+
+    /// Track the storage request at `location` of a file of `size` as a pending commitment of the BSP `who`.
+    pub(crate) fn add_pending_volunteered_request(
+        who: &T::AccountId,
+        location: &FileLocation<T>,
+        size: StorageData<T>,
+    ) {
+        <PendingVolunteeredRequests<T>>::insert(who, location, size);
+        <PendingVolunteeredStorage<T>>::mutate(who, |total| *total = total.saturating_add(size));
+    }
+
+    /// Stop tracking the storage request at `location` as a pending commitment of the BSP `who`, if it was.
+    pub(crate) fn remove_pending_volunteered_request(
+        who: &T::AccountId,
+        location: &FileLocation<T>,
+    ) {
+        if let Some(size) = <PendingVolunteeredRequests<T>>::take(who, location) {
+            <PendingVolunteeredStorage<T>>::mutate_exists(who, |total| {
+                *total = total
+                    .map(|total| total.saturating_sub(size))
+                    .filter(|total| !total.is_zero());
+            });
+        }
+    }
+
+    /// Stop tracking the storage request at `location` as a pending commitment of the BSPs that volunteered for it.
+    ///
+    /// Must be called before the volunteers are removed from [`StorageRequestBsps`].
+    pub(crate) fn clear_pending_volunteered_requests(location: &FileLocation<T>) {
+        for bsp in <StorageRequestBsps<T>>::iter_key_prefix(location) {
+            Self::remove_pending_volunteered_request(&bsp, location);
+        }
+    }
+
+    /// Get the locations of all the open storage requests of `owner`.
+    pub fn storage_requests_for(owner: &T::AccountId) -> Vec<FileLocation<T>> {
+        <StorageRequestsByOwner<T>>::iter_key_prefix(owner).collect()
+    }
+
+    /// Get the number of BSPs that still need to confirm storing the file for the storage request at `location`
+    /// to be fulfilled.
+    ///
+    /// Returns `None` if there is no open storage request for the given location.
+    pub fn bsps_still_needed(location: &FileLocation<T>) -> Option<u32> {
+        <StorageRequests<T>>::get(location).map(|file_metadata| {
+            file_metadata
+                .bsps_required
+                .saturating_sub(file_metadata.bsps_confirmed)
+                .saturated_into::<u32>()
+        })
+    }
+
     /// Compute the asymptotic threshold point for the given number of total BSPs.
     ///
     /// This function calculates the threshold at which the decay factor stabilizes,
     /// representing an horizontal asymptote.
+    ///
+    /// The shape of the curve is defined by the runtime through [`crate::Config::AssignmentThresholdAsymptote`] and
+    /// [`crate::Config::AssignmentThresholdDecayFactor`].
     pub(crate) fn compute_asymptotic_threshold_point(
         total_bsps: u32,
     ) -> Result<T::ThresholdType, Error<T>> {
@@ -564,8 +1333,208 @@ where
         Ok(T::AssignmentThresholdAsymptote::get().saturating_add(asymptotic_decay_factor))
     }
 
+    /// Compute the number of chunks a file of the given `size` is split into.
+    ///
+    /// The last chunk of a file can be smaller than [`crate::Config::FileChunkSize`], so it is counted as a full chunk.
+    pub(crate) fn compute_chunk_count(size: StorageData<T>) -> Result<u64, Error<T>> {
+        let size: u64 = size
+            .try_into()
+            .map_err(|_| Error::<T>::FailedTypeConversion)?;
+        let chunk_size = T::FileChunkSize::get();
+
+        let full_chunks = size
+            .checked_div(chunk_size)
+            .ok_or(Error::<T>::DividedByZero)?;
+
+        if size % chunk_size > 0 {
+            return Ok(full_chunks.saturating_add(1));
+        }
+
+        Ok(full_chunks)
+    }
+
+    /// Compute the key of a file, which identifies it in the Forest of the Storage Providers storing it.
+    ///
+    /// It is the hash of the SCALE encoded metadata of the file, so it commits to its owner, location, size and fingerprint.
+    pub fn compute_file_key(
+        owner: &T::AccountId,
+        location: &FileLocation<T>,
+        size: StorageData<T>,
+        fingerprint: &Fingerprint<T>,
+    ) -> FileKey<T> {
+        let hash = T::Hashing::hash_of(&(owner, location, size, fingerprint));
+
+        let mut file_key = FileKey::<T>::default();
+        let len = file_key.as_ref().len().min(hash.as_ref().len());
+        file_key.as_mut()[..len].copy_from_slice(&hash.as_ref()[..len]);
+
+        file_key
+    }
+
+    /// Get the challenge a proof of storing the file with key `file_key` and `chunk_count` chunks has to answer.
+    ///
+    /// Chunks are keyed by their big-endian encoded chunk id in the trie of a file, so the challenge is the key of the chunk
+    /// with id `file_key mod chunk_count`, padded with zeros to the size of a file key. The padded challenge sorts right after
+    /// the key of that chunk, so a proof answering it shows both the challenged chunk and the following one, if any.
+    pub(crate) fn file_chunk_challenge(file_key: &FileKey<T>, chunk_count: u64) -> FileKey<T> {
+        let mut seed = [0u8; 8];
+        let len = seed.len().min(file_key.as_ref().len());
+        seed[..len].copy_from_slice(&file_key.as_ref()[..len]);
+        let chunk_id = u64::from_be_bytes(seed) % chunk_count.max(1);
+
+        let mut challenge = FileKey::<T>::default();
+        let len = challenge.as_ref().len().min(8);
+        challenge.as_mut()[..len].copy_from_slice(&chunk_id.to_be_bytes()[..len]);
+
+        challenge
+    }
+
+    /// Check that `proof` shows `provider` holds the file with key `file_key`, `fingerprint` and `size`.
+    ///
+    /// The proof has to reconstruct the fingerprint of the file, which is the root of its chunks trie, answer the chunk
+    /// challenge derived from `file_key` by [`Self::file_chunk_challenge`], and imply the number of chunks derived from
+    /// `size` and [`crate::Config::FileChunkSize`].
+    pub(crate) fn verify_file_proof(
+        provider: &<T::Providers as storage_hub_traits::ProvidersInterface>::Provider,
+        file_key: &FileKey<T>,
+        fingerprint: &Fingerprint<T>,
+        size: StorageData<T>,
+        proof: &Proof<T>,
+    ) -> DispatchResult {
+        let chunk_count = Self::compute_chunk_count(size)?;
+
+        <T::ProofDealer as storage_hub_traits::ProofsDealerInterface>::verify_key_proof(
+            provider,
+            &(*fingerprint).into(),
+            &Self::file_chunk_challenge(file_key, chunk_count),
+            proof,
+        )
+        .map_err(|_| Error::<T>::InvalidProof)?;
+
+        // Check that the file structure implied by the proof matches the declared size.
+        ensure!(
+            <T::FileProofInspector as storage_hub_traits::FileProofInspector>::chunk_count(proof)
+                == Some(chunk_count),
+            Error::<T>::SizeMismatch
+        );
+
+        Ok(())
+    }
+
+    /// Get the current BSP assignment threshold, which is the threshold a BSP has to be under to volunteer for a
+    /// storage request issued in the current block.
+    ///
+    /// The threshold to volunteer for a storage request grows with the number of blocks since it was issued.
+    pub fn current_assignment_threshold() -> T::ThresholdType {
+        BspsAssignmentThreshold::<T>::get()
+    }
+
+    /// Compute the threshold a BSP has to be under to volunteer, as of block `current_block`, for a storage request
+    /// issued at block `requested_at`, given the global assignment threshold `base_threshold`.
+    ///
+    /// The threshold grows by [`Config::AssignmentThresholdMultiplier`](crate::Config::AssignmentThresholdMultiplier)
+    /// with every block since the storage request was issued.
+    pub fn compute_request_threshold(
+        base_threshold: T::ThresholdType,
+        requested_at: BlockNumberFor<T>,
+        current_block: BlockNumberFor<T>,
+    ) -> Result<T::ThresholdType, DispatchError> {
+        // Get number of blocks since the storage request was issued.
+        let blocks_since_requested: u128 = current_block
+            .saturating_sub(requested_at)
+            .try_into()
+            .map_err(|_| Error::<T>::FailedToConvertBlockNumber)?;
+
+        // Note. This should never fail since the storage request expiration would never reach such a high number.
+        // Storage requests are cleared after reaching `StorageRequestTtl` blocks which is defined in the pallet Config.
+        let blocks_since_requested: T::ThresholdType =
+            T::ThresholdType::ensure_from(blocks_since_requested)?;
+
+        // Compute the threshold increasing rate.
+        let rate_increase =
+            blocks_since_requested.saturating_mul(T::AssignmentThresholdMultiplier::get());
+
+        Ok(rate_increase.saturating_add(base_threshold))
+    }
+
+    /// Compute the threshold a BSP has to be under to volunteer, in the current block, for a storage request issued at
+    /// block `requested_at`.
+    pub(crate) fn storage_request_threshold(
+        requested_at: BlockNumberFor<T>,
+    ) -> Result<T::ThresholdType, DispatchError> {
+        Self::compute_request_threshold(
+            BspsAssignmentThreshold::<T>::get(),
+            requested_at,
+            <frame_system::Pallet<T>>::block_number(),
+        )
+    }
+
+    /// Check whether the BSP `bsp_id` is eligible to volunteer for the storage request at `location` in the current
+    /// block, using the same XOR distance and threshold as [`bsp_volunteer`](crate::dispatchables::bsp_volunteer).
+    ///
+    /// Returns `false` if there is no storage request at `location`, or if `bsp_id` is not a registered BSP or if it is
+    /// signing off.
+    pub fn is_bsp_eligible(bsp_id: &ProviderIdFor<T>, location: &FileLocation<T>) -> bool {
+        if !<T::Providers as storage_hub_traits::ReadProvidersInterface>::is_bsp(bsp_id)
+            || <T::Providers as storage_hub_traits::ReadProvidersInterface>::is_signing_off(bsp_id)
+        {
+            return false;
+        }
+
+        let Some(file_metadata) = <StorageRequests<T>>::get(location) else {
+            return false;
+        };
+
+        match (
+            Self::compute_bsp_threshold(bsp_id, &file_metadata.fingerprint),
+            Self::storage_request_threshold(file_metadata.requested_at),
+        ) {
+            (Ok(bsp_threshold), Ok(threshold)) => bsp_threshold <= threshold,
+            _ => false,
+        }
+    }
+
+    /// Get the BSPs that volunteered for the storage request at `location`, whether they already confirmed storing the file
+    /// or not, along with their metadata.
+    ///
+    /// At most [`Config::MaxVolunteersPerRequest`](crate::Config::MaxVolunteersPerRequest) BSPs are returned, which is
+    /// the most that can volunteer for a storage request.
+    pub fn bsps_for_request(
+        location: &FileLocation<T>,
+    ) -> Vec<(T::AccountId, StorageRequestBspsMetadata<T>)> {
+        <StorageRequestBsps<T>>::iter_prefix(location)
+            .take(T::MaxVolunteersPerRequest::get() as usize)
+            .collect()
+    }
+
+    /// Get the number of BSPs that confirmed storing the file of the storage request at `location`, and the number of BSPs
+    /// that volunteered for it (including the ones that confirmed), as returned by [`Self::bsps_for_request`].
+    pub fn bsps_count_for_request(location: &FileLocation<T>) -> (u32, u32) {
+        let bsps = Self::bsps_for_request(location);
+        let confirmed = bsps.iter().filter(|(_, bsp)| bsp.confirmed).count() as u32;
+
+        (confirmed, bsps.len() as u32)
+    }
+
+    /// Compute the threshold of a BSP for a file, as the XOR distance between the BSP ID and the fingerprint of the file.
+    pub(crate) fn compute_bsp_threshold(
+        bsp_id: &ProviderIdFor<T>,
+        fingerprint: &Fingerprint<T>,
+    ) -> Result<T::ThresholdType, Error<T>> {
+        Self::compute_bsp_xor(
+            fingerprint
+                .as_ref()
+                .try_into()
+                .map_err(|_| Error::<T>::FailedToEncodeFingerprint)?,
+            &bsp_id
+                .encode()
+                .try_into()
+                .map_err(|_| Error::<T>::FailedToEncodeBsp)?,
+        )
+    }
+
     /// Calculate the XOR of the fingerprint and the BSP.
-    fn compute_bsp_xor(
+    pub fn compute_bsp_xor(
         fingerprint: &[u8; 32],
         bsp: &[u8; 32],
     ) -> Result<T::ThresholdType, Error<T>> {
@@ -580,6 +1549,15 @@ where
     }
 }
 
+impl<T: crate::Config> storage_hub_traits::ReadStorageCommitmentsInterface for Pallet<T> {
+    type AccountId = T::AccountId;
+    type StorageData = StorageData<T>;
+
+    fn pending_volunteered_storage(who: &Self::AccountId) -> Self::StorageData {
+        <PendingVolunteeredStorage<T>>::get(who)
+    }
+}
+
 impl<T: crate::Config> storage_hub_traits::SubscribeProvidersInterface for Pallet<T> {
     type Provider = T::AccountId;
 
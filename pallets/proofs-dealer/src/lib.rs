@@ -44,8 +44,11 @@ pub mod pallet {
         type RuntimeEvent: From<Event<Self>> + IsType<<Self as frame_system::Config>::RuntimeEvent>;
 
         /// The Providers pallet.
-        /// To check if whoever submits a proof is a registered Provider.
-        type ProvidersPallet: ProvidersInterface<AccountId = Self::AccountId>;
+        /// To check if whoever submits a proof is a registered Provider, and to slash it if the proof fails.
+        type ProvidersPallet: ProvidersInterface<
+            AccountId = Self::AccountId,
+            MerkleHash = Self::MerkleHash,
+        >;
 
         /// Type to access the Balances Pallet.
         type NativeBalance: fungible::Inspect<Self::AccountId>
@@ -73,12 +76,17 @@ pub mod pallet {
 
         /// The type used to verify Merkle Patricia Trie proofs.
         /// Something that implements the `CommitmentVerifier` trait.
-        type KeyVerifier: CommitmentVerifier;
+        type KeyVerifier: CommitmentVerifier<Proof = CompactProof, Key = Self::MerkleHash>;
 
         /// The maximum number of challenges that can be made in a single block.
         #[pallet::constant]
         type MaxChallengesPerBlock: Get<u32>;
 
+        /// The number of random challenges derived from the hash of a block, which a Provider has to answer
+        /// when submitting a proof for that block.
+        #[pallet::constant]
+        type RandomChallengesPerBlock: Get<u32>;
+
         /// The maximum number of Providers that can be challenged in block.
         #[pallet::constant]
         type MaxProvidersChallengedPerBlock: Get<u32>;
@@ -114,6 +122,11 @@ pub mod pallet {
         /// - The slashed funds are transferred.
         #[pallet::constant]
         type Treasury: Get<Self::AccountId>;
+
+        /// The amount slashed from the stake of a Provider every time it submits a proof of its own
+        /// root that fails to verify.
+        #[pallet::constant]
+        type SlashAmountPerFailedProof: Get<ProviderBalanceFor<Self>>;
     }
 
     #[pallet::pallet]
@@ -234,6 +247,13 @@ pub mod pallet {
 
         /// The fee for submitting a challenge could not be charged.
         FeeChargeFailed,
+
+        /// The proof does not reconstruct the expected root.
+        InvalidProof,
+
+        /// The block the proof is submitted for is not a past block within the challenge history, or
+        /// the Provider already submitted a proof for it or for a later block.
+        InvalidChallengeBlock,
     }
 
     #[pallet::call]
@@ -268,9 +288,12 @@ pub mod pallet {
         ///
         /// Checks that `provider` is a registered Provider. If none
         /// is provided, the proof submitter is considered to be the Provider.
+        /// A Provider submitting a proof for itself that does not match its root is slashed
+        /// `SlashAmountPerFailedProof`, and the proof is rejected with a `ProofRejected` event.
         /// Relies on a File System pallet to check if the root is valid for the Provider.
         /// Validates that the proof corresponds to a challenge that was made in the past,
-        /// by checking the `BlockToChallenges` StorageMap. The block number that the
+        /// by checking it against the random challenges derived from the hash of `challenge_block`
+        /// and the ones in the `BlockToChallenges` StorageMap for it. The block number that the
         /// Provider should have submitted a proof is calculated based on the last block they
         /// submitted a proof for (`LastBlockSpSubmittedProofFor`), and the proving period for
         /// that Provider, which is a function of their stake.
@@ -307,11 +330,14 @@ pub mod pallet {
                 }
             };
 
-            // TODO: Handle result of verification.
-            Self::do_submit_proof(&provider, &proof)?;
-
-            // TODO: Emit correct event.
-            Self::deposit_event(Event::ProofAccepted { provider, proof });
+            match Self::do_submit_proof(&who, &provider, &root, challenge_block, &proof)? {
+                None => Self::deposit_event(Event::ProofAccepted { provider, proof }),
+                Some(reason) => Self::deposit_event(Event::ProofRejected {
+                    provider,
+                    proof,
+                    reason,
+                }),
+            }
 
             // Return a successful DispatchResultWithPostInfo
             Ok(().into())
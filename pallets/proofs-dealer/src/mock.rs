@@ -6,13 +6,16 @@ use frame_support::{
     weights::constants::RocksDbWeight,
 };
 use frame_system as system;
-use sp_core::{hashing::blake2_256, ConstU128, ConstU32, ConstU64, H256};
+use sp_core::{hashing::blake2_256, ConstI32, ConstU128, ConstU32, ConstU64, H256};
 use sp_runtime::{
     traits::{BlakeTwo256, IdentityLookup},
-    BuildStorage, DispatchResult,
+    BuildStorage, DispatchError, DispatchResult,
 };
 use sp_trie::CompactProof;
-use storage_hub_traits::{CommitmentVerifier, SubscribeProvidersInterface};
+use storage_hub_primitives::TrieVerifier;
+use storage_hub_traits::{
+    ReadStorageCommitmentsInterface, RootTransitionVerifier, SubscribeProvidersInterface,
+};
 use system::pallet_prelude::BlockNumberFor;
 
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -122,8 +125,15 @@ impl pallet_storage_providers::Config for Test {
     type SpMinCapacity = ConstU32<2>;
     type DepositPerData = ConstU128<2>;
     type Subscribers = MockedProvidersSubscriber;
+    type StorageCommitments = MockedStorageCommitments;
+    type RootTransitionVerifier = MockVerifier;
     type MaxBlocksForRandomness = ConstU64<{ EPOCH_DURATION_IN_BLOCKS * 2 }>;
     type MinBlocksBetweenCapacityChanges = ConstU64<10>;
+    type SignOffCooldown = ConstU64<10>;
+    type MinReputation = ConstI32<-100>;
+    type MaxReputation = ConstI32<100>;
+    type SlashReputationPenalty = ConstI32<10>;
+    type LivenessTimeout = ConstU64<20>;
     type ProvidersRandomness = MockRandomness;
 }
 impl crate::Config for Test {
@@ -131,14 +141,16 @@ impl crate::Config for Test {
     type ProvidersPallet = Providers;
     type NativeBalance = Balances;
     type MerkleHash = H256;
-    type KeyVerifier = MockVerifier;
+    type KeyVerifier = TrieVerifier<BlakeTwo256>;
     type MaxChallengesPerBlock = ConstU32<10>;
+    type RandomChallengesPerBlock = ConstU32<10>;
     type MaxProvidersChallengedPerBlock = ConstU32<10>;
     type ChallengeHistoryLength = ConstU32<10>;
     type ChallengesQueueLength = ConstU32<10>;
     type CheckpointChallengePeriod = ConstU32<2>;
     type ChallengesFee = ConstU128<1_000_000>;
     type Treasury = ConstU64<181222>;
+    type SlashAmountPerFailedProof = ConstU128<5>;
 }
 
 pub struct MockedProvidersSubscriber;
@@ -153,18 +165,39 @@ impl SubscribeProvidersInterface for MockedProvidersSubscriber {
     }
 }
 
-/// Structure to mock a verifier that returns `true` when `proof` is not empty
-/// and `false` otherwise.
+/// Mocked storage commitments, where BSPs never have pending volunteered storage
+pub struct MockedStorageCommitments;
+impl ReadStorageCommitmentsInterface for MockedStorageCommitments {
+    type AccountId = u64;
+    type StorageData = u32;
+
+    fn pending_volunteered_storage(_who: &Self::AccountId) -> Self::StorageData {
+        0
+    }
+}
+
+/// Structure to mock a verifier of root transitions of the Providers.
 pub struct MockVerifier;
 
-/// Implement the `TrieVerifier` trait for the `MockVerifier` struct.
-impl CommitmentVerifier for MockVerifier {
+/// Implement the `RootTransitionVerifier` trait for the `MockVerifier` struct, accepting any transition with a non-empty proof
+/// and taking the last encoded node of the proof as the new root.
+impl RootTransitionVerifier for MockVerifier {
     type Proof = CompactProof;
     type Key = H256;
 
-    fn verify_proof(
-        _root: &Self::Key,
-        _challenges: &[Self::Key],
+    fn apply_transition(
+        _old_root: &Self::Key,
+        proof: &CompactProof,
+    ) -> Result<Self::Key, DispatchError> {
+        match proof.encoded_nodes.last() {
+            Some(new_root) if new_root.len() == 32 => Ok(H256::from_slice(new_root)),
+            _ => Err("Proof does not end with the new root".into()),
+        }
+    }
+
+    fn verify_transition(
+        _old_root: &Self::Key,
+        _new_root: &Self::Key,
         proof: &CompactProof,
     ) -> DispatchResult {
         if proof.encoded_nodes.len() > 0 {
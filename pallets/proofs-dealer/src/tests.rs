@@ -1,8 +1,96 @@
 use crate::mock::*;
 use crate::pallet::Event;
-use frame_support::{assert_noop, assert_ok, traits::fungible::Mutate};
-use sp_core::{Get, Hasher};
+use crate::types::ProofRejectionReason;
+use frame_support::{
+    assert_noop, assert_ok,
+    traits::fungible::{InspectHold, Mutate, MutateHold},
+    BoundedVec,
+};
+use sp_core::{Get, Hasher, H256};
 use sp_runtime::{traits::BlakeTwo256, DispatchError};
+use sp_trie::{
+    recorder::Recorder, CompactProof, LayoutV1, MemoryDB, Trie, TrieDBBuilder, TrieDBMutBuilder,
+    TrieMut,
+};
+use storage_hub_traits::ProofsDealerInterface;
+
+/// Register `account` as a BSP with the given root, holding `deposit` from it as its stake.
+fn register_bsp(account: u64, root: H256, deposit: u128) -> H256 {
+    let bsp_id = BlakeTwo256::hash(&account.to_le_bytes());
+
+    assert_ok!(<Test as crate::Config>::NativeBalance::mint_into(
+        &account,
+        deposit * 2
+    ));
+    assert_ok!(<Test as crate::Config>::NativeBalance::hold(
+        &RuntimeHoldReason::Providers(pallet_storage_providers::HoldReason::StorageProviderDeposit),
+        &account,
+        deposit
+    ));
+
+    pallet_storage_providers::AccountIdToBackupStorageProviderId::<Test>::insert(account, bsp_id);
+    pallet_storage_providers::BackupStorageProviders::<Test>::insert(
+        bsp_id,
+        pallet_storage_providers::types::BackupStorageProvider {
+            capacity: 100,
+            data_used: 0,
+            multiaddresses: BoundedVec::default(),
+            root,
+            last_capacity_change: 0,
+            owner_account: account,
+        },
+    );
+
+    bsp_id
+}
+
+/// Get the deposit held from `account` as the stake of its Storage Provider.
+fn provider_deposit(account: u64) -> u128 {
+    <Test as crate::Config>::NativeBalance::balance_on_hold(
+        &RuntimeHoldReason::Providers(pallet_storage_providers::HoldReason::StorageProviderDeposit),
+        &account,
+    )
+}
+
+/// Build a Forest holding the given file keys, returning its root and a proof of the whole Forest, which answers any
+/// challenge.
+fn forest_proof(file_keys: &[H256]) -> (H256, CompactProof) {
+    let mut memdb = MemoryDB::<BlakeTwo256>::default();
+    let mut root = Default::default();
+    {
+        let mut trie =
+            TrieDBMutBuilder::<LayoutV1<BlakeTwo256>>::new(&mut memdb, &mut root).build();
+        for file_key in file_keys {
+            trie.insert(file_key.as_ref(), b"file_metadata").unwrap();
+        }
+    }
+
+    // Record every node of the Forest while iterating over all of its leaves.
+    let recorder: Recorder<BlakeTwo256> = Recorder::default();
+    {
+        let mut trie_recorder = recorder.as_trie_recorder(root);
+        let trie = TrieDBBuilder::<LayoutV1<BlakeTwo256>>::new(&memdb, &root)
+            .with_recorder(&mut trie_recorder)
+            .build();
+        for leaf in trie.iter().unwrap() {
+            leaf.unwrap();
+        }
+    }
+
+    let proof = recorder
+        .drain_storage_proof()
+        .to_compact_proof::<BlakeTwo256>(root)
+        .unwrap();
+
+    (root, proof)
+}
+
+/// File keys of a Forest used in the tests.
+fn file_keys(forest: &[u8]) -> Vec<H256> {
+    (0u8..4)
+        .map(|i| BlakeTwo256::hash(&[forest, &[i][..]].concat()))
+        .collect()
+}
 
 fn run_n_blocks(n: u64) {
     while System::block_number() < n {
@@ -349,16 +437,75 @@ fn challenge_overflow_challenges_queue_fail() {
 #[test]
 fn proofs_dealer_trait_verify_proof_succeed() {
     new_test_ext().execute_with(|| {
-        // TODO
-        assert!(true)
+        let (root, proof) = forest_proof(&file_keys(b"forest"));
+
+        // Verify the proof through the ProofsDealerInterface.
+        assert_ok!(<ProofsDealer as ProofsDealerInterface>::verify_proof(
+            &BlakeTwo256::hash(b"provider"),
+            &root,
+            &[BlakeTwo256::hash(b"challenge")],
+            &proof
+        ));
     });
 }
 
 #[test]
 fn proofs_dealer_trait_verify_proof_fail() {
     new_test_ext().execute_with(|| {
-        // TODO
-        assert!(true)
+        // An empty proof does not reconstruct any root.
+        let (root, _) = forest_proof(&file_keys(b"forest"));
+        let proof = CompactProof {
+            encoded_nodes: vec![],
+        };
+
+        // Verify the proof through the ProofsDealerInterface.
+        assert_noop!(
+            <ProofsDealer as ProofsDealerInterface>::verify_proof(
+                &BlakeTwo256::hash(b"provider"),
+                &root,
+                &[BlakeTwo256::hash(b"challenge")],
+                &proof
+            ),
+            crate::Error::<Test>::InvalidProof
+        );
+    });
+}
+
+#[test]
+fn proofs_dealer_trait_verify_proof_for_another_root_fail() {
+    new_test_ext().execute_with(|| {
+        // The proof is valid, but for another Forest.
+        let (root, _) = forest_proof(&file_keys(b"forest"));
+        let (_, proof) = forest_proof(&file_keys(b"another_forest"));
+
+        // Verify the proof through the ProofsDealerInterface.
+        assert_noop!(
+            <ProofsDealer as ProofsDealerInterface>::verify_proof(
+                &BlakeTwo256::hash(b"provider"),
+                &root,
+                &[BlakeTwo256::hash(b"challenge")],
+                &proof
+            ),
+            crate::Error::<Test>::InvalidProof
+        );
+    });
+}
+
+#[test]
+fn proofs_dealer_trait_verify_proof_without_challenges_fail() {
+    new_test_ext().execute_with(|| {
+        let (root, proof) = forest_proof(&file_keys(b"forest"));
+
+        // A proof that does not answer any challenge is not accepted.
+        assert_noop!(
+            <ProofsDealer as ProofsDealerInterface>::verify_proof(
+                &BlakeTwo256::hash(b"provider"),
+                &root,
+                &[],
+                &proof
+            ),
+            crate::Error::<Test>::InvalidProof
+        );
     });
 }
 
@@ -444,3 +591,290 @@ fn proofs_dealer_trait_challenge_with_priority_overflow_challenges_queue_fail()
         );
     });
 }
+
+#[test]
+fn submit_proof_valid_proof_succeed() {
+    new_test_ext().execute_with(|| {
+        // Go past genesis block so events get deposited, and past the block the proof is for.
+        System::set_block_number(2);
+
+        let (root, proof) = forest_proof(&file_keys(b"forest"));
+        let bsp_id = register_bsp(1, root, 100);
+
+        assert_ok!(ProofsDealer::submit_proof(
+            RuntimeOrigin::signed(1),
+            proof.clone(),
+            root,
+            1,
+            None
+        ));
+
+        System::assert_last_event(
+            Event::ProofAccepted {
+                provider: bsp_id,
+                proof,
+            }
+            .into(),
+        );
+
+        // The Provider is not slashed, and the block is registered as the last one it submitted a proof for.
+        assert_eq!(provider_deposit(1), 100);
+        assert_eq!(
+            crate::LastBlockSpSubmittedProofFor::<Test>::get(bsp_id),
+            Some(1)
+        );
+    });
+}
+
+#[test]
+fn submit_proof_failed_proof_slashes_provider() {
+    new_test_ext().execute_with(|| {
+        // Go past genesis block so events get deposited, and past the block the proof is for.
+        System::set_block_number(2);
+
+        let root = BlakeTwo256::hash(b"root");
+        let bsp_id = register_bsp(1, root, 100);
+
+        // The proof does not verify against the root of the Provider.
+        let proof = CompactProof {
+            encoded_nodes: vec![],
+        };
+        assert_ok!(ProofsDealer::submit_proof(
+            RuntimeOrigin::signed(1),
+            proof.clone(),
+            root,
+            1,
+            None
+        ));
+
+        System::assert_has_event(
+            Event::ProofRejected {
+                provider: bsp_id,
+                proof,
+                reason: ProofRejectionReason::RootMismatch,
+            }
+            .into(),
+        );
+
+        let slash_amount: u128 = <Test as crate::Config>::SlashAmountPerFailedProof::get();
+        assert_eq!(provider_deposit(1), 100 - slash_amount);
+    });
+}
+
+#[test]
+fn submit_proof_for_another_root_slashes_provider() {
+    new_test_ext().execute_with(|| {
+        // Go past genesis block so events get deposited, and past the block the proof is for.
+        System::set_block_number(2);
+
+        let bsp_id = register_bsp(1, BlakeTwo256::hash(b"root"), 100);
+
+        let proof = CompactProof {
+            encoded_nodes: vec![vec![0]],
+        };
+        assert_ok!(ProofsDealer::submit_proof(
+            RuntimeOrigin::signed(1),
+            proof.clone(),
+            BlakeTwo256::hash(b"another_root"),
+            1,
+            None
+        ));
+
+        System::assert_has_event(
+            Event::ProofRejected {
+                provider: bsp_id,
+                proof,
+                reason: ProofRejectionReason::RootMismatch,
+            }
+            .into(),
+        );
+
+        let slash_amount: u128 = <Test as crate::Config>::SlashAmountPerFailedProof::get();
+        assert_eq!(provider_deposit(1), 100 - slash_amount);
+    });
+}
+
+#[test]
+fn submit_proof_of_another_forest_slashes_provider() {
+    new_test_ext().execute_with(|| {
+        // Go past genesis block so events get deposited, and past the block the proof is for.
+        System::set_block_number(2);
+
+        let (root, _) = forest_proof(&file_keys(b"forest"));
+        let bsp_id = register_bsp(1, root, 100);
+
+        // The proof is valid, but for another Forest than the one committed to by the root of the Provider.
+        let (_, proof) = forest_proof(&file_keys(b"another_forest"));
+        assert_ok!(ProofsDealer::submit_proof(
+            RuntimeOrigin::signed(1),
+            proof.clone(),
+            root,
+            1,
+            None
+        ));
+
+        System::assert_has_event(
+            Event::ProofRejected {
+                provider: bsp_id,
+                proof,
+                reason: ProofRejectionReason::RootMismatch,
+            }
+            .into(),
+        );
+
+        let slash_amount: u128 = <Test as crate::Config>::SlashAmountPerFailedProof::get();
+        assert_eq!(provider_deposit(1), 100 - slash_amount);
+        assert_eq!(
+            crate::LastBlockSpSubmittedProofFor::<Test>::get(bsp_id),
+            None
+        );
+    });
+}
+
+#[test]
+fn submit_proof_failed_proof_for_another_provider_does_not_slash() {
+    new_test_ext().execute_with(|| {
+        // Go past genesis block so events get deposited, and past the block the proof is for.
+        System::set_block_number(2);
+
+        let root = BlakeTwo256::hash(b"root");
+        let bsp_id = register_bsp(1, root, 100);
+
+        // Someone else submits a failing proof on behalf of the Provider.
+        let proof = CompactProof {
+            encoded_nodes: vec![],
+        };
+        assert_ok!(ProofsDealer::submit_proof(
+            RuntimeOrigin::signed(2),
+            proof.clone(),
+            root,
+            1,
+            Some(bsp_id)
+        ));
+
+        System::assert_last_event(
+            Event::ProofRejected {
+                provider: bsp_id,
+                proof,
+                reason: ProofRejectionReason::RootMismatch,
+            }
+            .into(),
+        );
+
+        // The Provider is not slashed.
+        assert_eq!(provider_deposit(1), 100);
+    });
+}
+
+#[test]
+fn submit_proof_for_current_block_fail() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        let root = BlakeTwo256::hash(b"root");
+        register_bsp(1, root, 100);
+
+        // The challenges of the current block are not known yet.
+        assert_noop!(
+            ProofsDealer::submit_proof(
+                RuntimeOrigin::signed(1),
+                CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                },
+                root,
+                1,
+                None
+            ),
+            crate::Error::<Test>::InvalidChallengeBlock
+        );
+    });
+}
+
+#[test]
+fn submit_proof_for_block_out_of_history_fail() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(1);
+
+        let root = BlakeTwo256::hash(b"root");
+        register_bsp(1, root, 100);
+
+        // Go past the challenge history of block 1.
+        let history_length: u32 = <Test as crate::Config>::ChallengeHistoryLength::get();
+        run_n_blocks(history_length as u64 + 2);
+
+        assert_noop!(
+            ProofsDealer::submit_proof(
+                RuntimeOrigin::signed(1),
+                CompactProof {
+                    encoded_nodes: vec![vec![0]],
+                },
+                root,
+                1,
+                None
+            ),
+            crate::Error::<Test>::InvalidChallengeBlock
+        );
+    });
+}
+
+#[test]
+fn submit_proof_for_already_proven_block_fail() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(3);
+
+        let (root, proof) = forest_proof(&file_keys(b"forest"));
+        register_bsp(1, root, 100);
+
+        assert_ok!(ProofsDealer::submit_proof(
+            RuntimeOrigin::signed(1),
+            proof.clone(),
+            root,
+            2,
+            None
+        ));
+
+        // Neither the same block nor an earlier one can be proven again.
+        for challenge_block in [1, 2] {
+            assert_noop!(
+                ProofsDealer::submit_proof(
+                    RuntimeOrigin::signed(1),
+                    proof.clone(),
+                    root,
+                    challenge_block,
+                    None
+                ),
+                crate::Error::<Test>::InvalidChallengeBlock
+            );
+        }
+    });
+}
+
+#[test]
+fn challenges_for_block_include_random_and_block_challenges() {
+    new_test_ext().execute_with(|| {
+        System::set_block_number(2);
+
+        let custom_challenge = BlakeTwo256::hash(b"file_key");
+        crate::BlockToChallenges::<Test>::insert(
+            1,
+            BoundedVec::try_from(vec![custom_challenge]).unwrap(),
+        );
+
+        let challenges = ProofsDealer::challenges_for_block(1);
+
+        // The random challenges are derived from the hash of the block.
+        let random_challenges = ProofsDealer::random_challenges(&System::block_hash(1));
+        let random_challenges_per_block: u32 =
+            <Test as crate::Config>::RandomChallengesPerBlock::get();
+        assert_eq!(
+            random_challenges.len(),
+            random_challenges_per_block as usize
+        );
+        assert!(random_challenges.iter().all(|c| challenges.contains(c)));
+
+        // The challenges of the block are included, and all of them are sorted.
+        assert!(challenges.contains(&custom_challenge));
+        assert_eq!(challenges.len(), random_challenges.len() + 1);
+        assert!(challenges.windows(2).all(|pair| pair[0] < pair[1]));
+    });
+}
@@ -50,6 +50,12 @@ pub type ProvidersPalletFor<T> = <T as crate::Config>::ProvidersPallet;
 /// Syntactic sugar for the Provider type used in the proofs pallet.
 pub type ProviderFor<T> = <<T as crate::Config>::ProvidersPallet as ProvidersInterface>::Provider;
 
+/// Syntactic sugar for the type of Balance of the stake of a Provider, used when slashing it.
+pub type ProviderBalanceFor<T> =
+    <<<T as crate::Config>::ProvidersPallet as ProvidersInterface>::Balance as fungible::Inspect<
+        <T as frame_system::Config>::AccountId,
+    >>::Balance;
+
 /// Syntactic sugar for the type of NativeBalance pallet.
 pub type BalancePalletFor<T> = <T as crate::Config>::NativeBalance;
 
@@ -3,20 +3,23 @@
 #![allow(unused_variables)]
 use frame_support::{
     ensure,
-    pallet_prelude::DispatchResult,
+    pallet_prelude::{DispatchError, DispatchResult},
+    sp_runtime::traits::Hash,
     traits::{fungible::Mutate, tokens::Preservation, Get},
 };
 use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::prelude::vec::Vec;
 use sp_trie::CompactProof;
-use storage_hub_traits::{ProofsDealerInterface, ProvidersInterface};
+use storage_hub_traits::{CommitmentVerifier, ProofsDealerInterface, ProvidersInterface};
 
 use crate::{
     pallet,
     types::{
-        AccountIdFor, BalanceFor, BalancePalletFor, ChallengesFeeFor, KeyFor, ProviderFor,
-        ProvidersPalletFor, TreasuryAccountFor,
+        AccountIdFor, BalanceFor, BalancePalletFor, ChallengesFeeFor, KeyFor, ProofRejectionReason,
+        ProviderFor, ProvidersPalletFor, TreasuryAccountFor,
     },
-    ChallengesQueue, Error, Pallet, PriorityChallengesQueue,
+    BlockToChallenges, ChallengesQueue, Error, LastBlockSpSubmittedProofFor, Pallet,
+    PriorityChallengesQueue,
 };
 
 impl<T> Pallet<T>
@@ -49,22 +52,110 @@ where
         Self::enqueue_challenge(key)
     }
 
-    // TODO: Document and add proper parameters.
-    // TODO: Remove unused variable allow attribute.
-    #[allow(unused_variables)]
-    pub fn do_submit_proof(submitter: &ProviderFor<T>, proof: &CompactProof) -> DispatchResult {
-        // Check if submitter is a registered Provider.
+    /// Check a proof submitted by `who` for `provider` against the root of `provider` and the challenges of
+    /// `challenge_block`.
+    ///
+    /// `challenge_block` has to be a past block within the last `ChallengeHistoryLength` blocks, after the last
+    /// block `provider` submitted a proof for. The proof has to answer the challenges of that block, as returned
+    /// by [`Self::challenges_for_block`].
+    ///
+    /// Returns the reason the proof is rejected for, if it is. A proof that does not match the on-chain root of
+    /// `provider` is rejected, and `provider` is slashed `SlashAmountPerFailedProof` if it submitted the proof
+    /// itself. Failed proofs submitted on behalf of `provider` by another account don't slash it, so they can't
+    /// be used to grief it. Accepted proofs register `challenge_block` as the last block `provider` submitted a
+    /// proof for.
+    ///
+    /// Failures:
+    /// - `NotProvider`: If `provider` is not a registered Provider with a root.
+    /// - `InvalidChallengeBlock`: If `challenge_block` is not a past block within the challenge history, or
+    /// `provider` already submitted a proof for it or for a later block.
+    pub fn do_submit_proof(
+        who: &AccountIdFor<T>,
+        provider: &ProviderFor<T>,
+        root: &KeyFor<T>,
+        challenge_block: BlockNumberFor<T>,
+        proof: &CompactProof,
+    ) -> Result<Option<ProofRejectionReason>, DispatchError> {
+        // Check if the Provider is registered.
         ensure!(
-            ProvidersPalletFor::<T>::is_provider(submitter.clone()),
+            ProvidersPalletFor::<T>::is_provider(provider.clone()),
             Error::<T>::NotProvider
         );
+        let provider_root =
+            ProvidersPalletFor::<T>::get_root(provider.clone()).ok_or(Error::<T>::NotProvider)?;
+
+        // Check that the challenges of `challenge_block` are already known and still in the history.
+        let current_block = frame_system::Pallet::<T>::block_number();
+        ensure!(
+            challenge_block < current_block
+                && current_block - challenge_block <= T::ChallengeHistoryLength::get().into(),
+            Error::<T>::InvalidChallengeBlock
+        );
 
-        // TODO: Check that the proof corresponds to a correct challenge block.
+        // Check that the Provider did not already submit a proof for this block or a later one.
+        if let Some(last_block_proven) = LastBlockSpSubmittedProofFor::<T>::get(provider) {
+            ensure!(
+                challenge_block > last_block_proven,
+                Error::<T>::InvalidChallengeBlock
+            );
+        }
 
-        // TODO: Verify proof.
+        let challenges = Self::challenges_for_block(challenge_block);
+        let rejection_reason = if *root != provider_root
+            || Self::verify_proof(provider, &provider_root, &challenges, proof).is_err()
+        {
+            Some(ProofRejectionReason::RootMismatch)
+        } else {
+            None
+        };
 
-        // TODO
-        unimplemented!()
+        match rejection_reason {
+            Some(_) => {
+                if ProvidersPalletFor::<T>::get_provider(who.clone()).as_ref() == Some(provider) {
+                    ProvidersPalletFor::<T>::slash(provider, T::SlashAmountPerFailedProof::get())?;
+                }
+            }
+            None => LastBlockSpSubmittedProofFor::<T>::insert(provider, challenge_block),
+        }
+
+        // TODO: Push the Provider forward in `BlockToChallengedSps`.
+        // TODO: Slash the Providers that fail to submit a proof in time in `do_new_challenges_round`.
+        Ok(rejection_reason)
+    }
+
+    /// Get the challenges a proof submitted for `block` has to answer.
+    ///
+    /// These are the random challenges derived from the hash of `block`, together with the challenges registered
+    /// for it in `BlockToChallenges`, sorted in ascending order and without repetitions, as expected by the
+    /// `KeyVerifier`. The hash of `block` is only known once `block` is finalised, so this should only be called
+    /// for past blocks.
+    pub fn challenges_for_block(block: BlockNumberFor<T>) -> Vec<KeyFor<T>> {
+        let seed = frame_system::Pallet::<T>::block_hash(block);
+
+        let mut challenges = Self::random_challenges(&seed);
+        challenges.extend(BlockToChallenges::<T>::get(block).unwrap_or_default());
+        challenges.sort();
+        challenges.dedup();
+
+        challenges
+    }
+
+    /// Derive `RandomChallengesPerBlock` challenges from `seed`.
+    ///
+    /// The `i`-th challenge is the hash of `(seed, i)`. This does not read any storage, so it can be used
+    /// off-chain by Providers to know which keys they have to prove for a block with hash `seed`.
+    pub fn random_challenges(seed: &T::Hash) -> Vec<KeyFor<T>> {
+        (0..T::RandomChallengesPerBlock::get())
+            .map(|index| {
+                let hash = T::Hashing::hash_of(&(seed, index));
+
+                let mut challenge = KeyFor::<T>::default();
+                let len = challenge.as_ref().len().min(hash.as_ref().len());
+                challenge.as_mut()[..len].copy_from_slice(&hash.as_ref()[..len]);
+
+                challenge
+            })
+            .collect()
     }
 
     // TODO: Document and add proper parameters.
@@ -120,14 +211,21 @@ where
         Ok(())
     }
 
-    // TODO: Document.
+    /// Verify that a proof submitted by a Provider reconstructs the given root and answers `challenges`.
+    ///
+    /// The proof is checked by the configured `KeyVerifier`, so `challenges` should be sorted in ascending
+    /// order and not repeated.
+    ///
+    /// Failures:
+    /// - `InvalidProof`: If the proof does not reconstruct `root` or does not answer `challenges`.
     fn verify_proof(
         who: &ProviderFor<T>,
         root: &T::MerkleHash,
+        challenges: &[T::MerkleHash],
         proof: &CompactProof,
     ) -> DispatchResult {
-        // TODO
-        Ok(())
+        <T::KeyVerifier as CommitmentVerifier>::verify_proof(root, challenges, proof)
+            .map_err(|_| Error::<T>::InvalidProof.into())
     }
 }
 
@@ -139,9 +237,20 @@ impl<T: pallet::Config> ProofsDealerInterface for Pallet<T> {
     fn verify_proof(
         who: &Self::Provider,
         root: &Self::MerkleHash,
+        challenges: &[Self::MerkleHash],
+        proof: &Self::Proof,
+    ) -> DispatchResult {
+        Self::verify_proof(who, root, challenges, proof)
+    }
+
+    fn verify_key_proof(
+        _who: &Self::Provider,
+        root: &Self::MerkleHash,
+        key: &Self::MerkleHash,
         proof: &Self::Proof,
     ) -> DispatchResult {
-        Self::verify_proof(who, root, proof)
+        <T::KeyVerifier as CommitmentVerifier>::verify_proof(root, &[*key], proof)
+            .map_err(|_| Error::<T>::InvalidProof.into())
     }
 
     fn challenge(key_challenged: &Self::MerkleHash) -> DispatchResult {
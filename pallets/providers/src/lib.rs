@@ -9,7 +9,8 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-mod types;
+mod multiaddress;
+pub mod types;
 mod utils;
 
 #[cfg(feature = "runtime-benchmarks")]
@@ -21,14 +22,23 @@ mod mock;
 #[cfg(test)]
 mod tests;
 
+use codec::Encode;
+use frame_support::traits::Randomness;
 use frame_system::pallet_prelude::BlockNumberFor;
 pub use pallet::*;
+use scale_info::prelude::vec::Vec;
 pub use scale_info::Type;
+use sp_runtime::traits::Saturating;
 use types::{
     BackupStorageProvider, BackupStorageProviderId, BalanceOf, BucketId, HashId,
-    MainStorageProviderId, MerklePatriciaRoot, StorageData, StorageProvider,
+    MainStorageProviderId, MerklePatriciaRoot, Reputation, RootTransitionProof, StorageData,
+    StorageProvider,
 };
 
+/// Domain separator prepended to the encoded AccountId of a user to get the randomness its Storage Provider ID is
+/// derived from, so that it does not match the randomness other pallets get for the same subject.
+pub const PROVIDER_ID_RANDOMNESS_DOMAIN: &[u8] = b"provider-id";
+
 #[frame_support::pallet]
 pub mod pallet {
     use super::types::*;
@@ -44,8 +54,10 @@ pub mod pallet {
         Blake2_128Concat,
     };
     use frame_system::pallet_prelude::{BlockNumberFor, *};
-    use scale_info::prelude::fmt::Debug;
-    use storage_hub_traits::SubscribeProvidersInterface;
+    use scale_info::prelude::{fmt::Debug, vec::Vec};
+    use storage_hub_traits::{
+        ReadStorageCommitmentsInterface, RootTransitionVerifier, SubscribeProvidersInterface,
+    };
 
     /// Configure the pallet by specifying the parameters and types on which it depends.
     #[pallet::config]
@@ -127,7 +139,16 @@ pub mod pallet {
             + FullCodec;
 
         /// Subscribers to important updates
-        type Subscribers: SubscribeProvidersInterface;
+        type Subscribers: SubscribeProvidersInterface<Provider = Self::AccountId>;
+
+        /// The trait to read the storage that BSPs have committed to but is not yet part of their used data
+        type StorageCommitments: ReadStorageCommitmentsInterface<
+            AccountId = Self::AccountId,
+            StorageData = Self::StorageData,
+        >;
+
+        /// The trait to verify that the new root of a bucket is a valid successor of its current root
+        type RootTransitionVerifier: RootTransitionVerifier<Key = Self::MerklePatriciaRoot>;
 
         /// The minimum amount that an account has to deposit to become a storage provider.
         #[pallet::constant]
@@ -174,6 +195,26 @@ pub mod pallet {
         /// The minimum amount of blocks between capacity changes for a SP
         #[pallet::constant]
         type MinBlocksBetweenCapacityChanges: Get<BlockNumberFor<Self>>;
+
+        /// The amount of blocks that a SP has to wait after requesting to sign off before it can finalize it and get its deposit back
+        #[pallet::constant]
+        type SignOffCooldown: Get<BlockNumberFor<Self>>;
+
+        /// The minimum reputation a SP can have. It has to be at most zero, the reputation SPs start with
+        #[pallet::constant]
+        type MinReputation: Get<Reputation>;
+
+        /// The maximum reputation a SP can have. It has to be at least zero, the reputation SPs start with
+        #[pallet::constant]
+        type MaxReputation: Get<Reputation>;
+
+        /// The amount of reputation that a SP loses every time it is slashed
+        #[pallet::constant]
+        type SlashReputationPenalty: Get<Reputation>;
+
+        /// The amount of blocks after its last heartbeat (or its registration) after which a SP is considered stale
+        #[pallet::constant]
+        type LivenessTimeout: Get<BlockNumberFor<Self>>;
     }
 
     #[pallet::pallet]
@@ -190,18 +231,29 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [request_msp_sign_up](crate::dispatchables::request_msp_sign_up) and [request_bsp_sign_up](crate::dispatchables::request_bsp_sign_up), which add a new entry to the map.
-    /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up) and [cancel_sign_up](crate::dispatchables::cancel_sign_up), which remove an existing entry from the map.
+    /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), [confirm_sign_up_for](crate::dispatchables::confirm_sign_up_for) and [cancel_sign_up](crate::dispatchables::cancel_sign_up), which remove an existing entry from the map.
+    /// - `on_idle`, which removes the entries of the requests that expired, releasing their deposits.
     #[pallet::storage]
     pub type SignUpRequests<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, (StorageProvider<T>, BlockNumberFor<T>)>;
 
+    /// The account of the last sign up request checked by the sweep of expired sign up requests.
+    ///
+    /// This is used to resume the sweep in the next block from where it stopped, since only as many requests as the
+    /// remaining weight of a block allows are checked. It is `None` when the next sweep has to start from the beginning.
+    ///
+    /// This storage is updated in:
+    /// - `on_idle`, which sets it to the last checked request, or clears it once all requests have been checked.
+    #[pallet::storage]
+    pub type SignUpRequestsSweepCursor<T: Config> = StorageValue<_, T::AccountId>;
+
     /// The mapping from an AccountId to a MainStorageProviderId.
     ///
     /// This is used to get a Main Storage Provider's unique identifier needed to access its metadata.
     ///
     /// This storage is updated in:
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds a new entry to the map if the account to confirm is a Main Storage Provider.
-    /// - [msp_sign_off](crate::dispatchables::msp_sign_off), which removes the corresponding entry from the map.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
     #[pallet::storage]
     pub type AccountIdToMainStorageProviderId<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, MainStorageProviderId<T>>;
@@ -213,9 +265,11 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds a new entry to the map if the account to confirm is a Main Storage Provider.
-    /// - [msp_sign_off](crate::dispatchables::msp_sign_off), which removes the corresponding entry from the map.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
     /// - [change_capacity](crate::dispatchables::change_capacity), which changes the entry's `capacity`.
+    /// - [update_multiaddresses](crate::dispatchables::update_multiaddresses), which changes the entry's `multiaddresses`.
     /// - [add_value_prop](crate::dispatchables::add_value_prop), which appends a new value proposition to the entry's existing `value_prop` bounded vector.
+    /// - [update_value_prop](crate::dispatchables::update_value_prop), which replaces the entry's `value_prop`.
     #[pallet::storage]
     pub type MainStorageProviders<T: Config> =
         StorageMap<_, Blake2_128Concat, MainStorageProviderId<T>, MainStorageProvider<T>>;
@@ -227,11 +281,58 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [add_bucket](storage_hub_traits::MutateProvidersInterface::add_bucket), which adds a new entry to the map.
+    /// - [add_file_to_bucket](storage_hub_traits::MutateProvidersInterface::add_file_to_bucket), which adds a new entry to the map or changes the root and size of the corresponding bucket.
     /// - [change_root_bucket](storage_hub_traits::MutateProvidersInterface::change_root_bucket), which changes the corresponding bucket's root.
+    /// - [accept_bucket_transfer](crate::dispatchables::accept_bucket_transfer), which changes the MSP of the corresponding bucket.
     /// - [remove_root_bucket](storage_hub_traits::MutateProvidersInterface::remove_root_bucket), which removes the entry of the corresponding bucket.
     #[pallet::storage]
     pub type Buckets<T: Config> = StorageMap<_, Blake2_128Concat, BucketId<T>, Bucket<T>>;
 
+    /// The double mapping from a MainStorageProviderId to the BucketIds of the buckets it holds.
+    ///
+    /// This is used to enumerate the buckets of a Main Storage Provider.
+    ///
+    /// This storage is updated in:
+    /// - [add_bucket](storage_hub_traits::MutateProvidersInterface::add_bucket), which adds a new entry to the map.
+    /// - [add_file_to_bucket](storage_hub_traits::MutateProvidersInterface::add_file_to_bucket), which adds a new entry to the map if the bucket did not exist.
+    /// - [accept_bucket_transfer](crate::dispatchables::accept_bucket_transfer), which moves the entry of the corresponding bucket to the new MSP.
+    /// - [remove_root_bucket](storage_hub_traits::MutateProvidersInterface::remove_root_bucket), which removes the entry of the corresponding bucket.
+    #[pallet::storage]
+    pub type BucketsByMsp<T: Config> = StorageDoubleMap<
+        _,
+        Blake2_128Concat,
+        MainStorageProviderId<T>,
+        Blake2_128Concat,
+        BucketId<T>,
+        (),
+    >;
+
+    /// The double mapping from an AccountId to the BucketIds of the buckets it owns.
+    ///
+    /// This is used to enumerate the buckets of a user.
+    ///
+    /// This storage is updated in:
+    /// - [add_bucket](storage_hub_traits::MutateProvidersInterface::add_bucket), which adds a new entry to the map.
+    /// - [add_file_to_bucket](storage_hub_traits::MutateProvidersInterface::add_file_to_bucket), which adds a new entry to the map if the bucket did not exist.
+    /// - [remove_root_bucket](storage_hub_traits::MutateProvidersInterface::remove_root_bucket), which removes the entry of the corresponding bucket.
+    #[pallet::storage]
+    pub type BucketsByUser<T: Config> =
+        StorageDoubleMap<_, Blake2_128Concat, T::AccountId, Blake2_128Concat, BucketId<T>, ()>;
+
+    /// The mapping from a BucketId to the MainStorageProviderId its owner requested to transfer it to.
+    ///
+    /// This is used for the two-step process of transferring a bucket: the owner of the bucket requests the transfer,
+    /// and the new MSP has to accept it before the bucket and its data are moved to it.
+    ///
+    /// This storage is updated in:
+    /// - [transfer_bucket](crate::dispatchables::transfer_bucket), which adds a new entry to the map or replaces the
+    /// MSP of the existing one.
+    /// - [accept_bucket_transfer](crate::dispatchables::accept_bucket_transfer), which removes the corresponding entry from the map.
+    /// - [remove_root_bucket](storage_hub_traits::MutateProvidersInterface::remove_root_bucket), which removes the entry of the corresponding bucket.
+    #[pallet::storage]
+    pub type PendingBucketTransfers<T: Config> =
+        StorageMap<_, Blake2_128Concat, BucketId<T>, MainStorageProviderId<T>>;
+
     /// The mapping from an AccountId to a BackupStorageProviderId.
     ///
     /// This is used to get a Backup Storage Provider's unique identifier needed to access its metadata.
@@ -239,7 +340,7 @@ pub mod pallet {
     /// This storage is updated in:
     ///
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds a new entry to the map if the account to confirm is a Backup Storage Provider.
-    /// - [bsp_sign_off](crate::dispatchables::bsp_sign_off), which removes the corresponding entry from the map.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
     #[pallet::storage]
     pub type AccountIdToBackupStorageProviderId<T: Config> =
         StorageMap<_, Blake2_128Concat, T::AccountId, BackupStorageProviderId<T>>;
@@ -251,8 +352,9 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds a new entry to the map if the account to confirm is a Backup Storage Provider.
-    /// - [bsp_sign_off](crate::dispatchables::bsp_sign_off), which removes the corresponding entry from the map.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
     /// - [change_capacity](crate::dispatchables::change_capacity), which changes the entry's `capacity`.
+    /// - [update_multiaddresses](crate::dispatchables::update_multiaddresses), which changes the entry's `multiaddresses`.
     #[pallet::storage]
     pub type BackupStorageProviders<T: Config> =
         StorageMap<_, Blake2_128Concat, BackupStorageProviderId<T>, BackupStorageProvider<T>>;
@@ -263,7 +365,7 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds one to this storage if the account to confirm is a Main Storage Provider.
-    /// - [msp_sign_off](crate::dispatchables::msp_sign_off), which subtracts one from this storage.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which subtracts one from this storage if the account signing off is a Main Storage Provider.
     #[pallet::storage]
     pub type MspCount<T: Config> = StorageValue<_, T::SpCount, ValueQuery>;
 
@@ -273,7 +375,8 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds one to this storage if the account to confirm is a Backup Storage Provider.
-    /// - [bsp_sign_off](crate::dispatchables::bsp_sign_off), which subtracts one from this storage.
+    /// - [request_sign_off](crate::dispatchables::request_sign_off), which subtracts one from this storage if the account requesting to sign off is a Backup Storage Provider,
+    /// so it stops counting towards the BSPs assignment threshold during its sign off cooldown.
     #[pallet::storage]
     pub type BspCount<T: Config> = StorageValue<_, T::SpCount, ValueQuery>;
 
@@ -284,10 +387,123 @@ pub mod pallet {
     ///
     /// This storage is updated in:
     /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), which adds the capacity of the registered Storage Provider to this storage if the account to confirm is a Backup Storage Provider.
-    /// - [bsp_sign_off](crate::dispatchables::bsp_sign_off), which subtracts the capacity of the Backup Storage Provider to sign off from this storage.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which subtracts the capacity of the Backup Storage Provider to sign off from this storage.
     #[pallet::storage]
     pub type TotalBspsCapacity<T: Config> = StorageValue<_, StorageData<T>, ValueQuery>;
 
+    /// The total amount of storage used by all BSPs.
+    ///
+    /// This is the sum of the `data_used` of all BSPs, kept up to date as their data used changes so that the free
+    /// space of the network can be read without iterating over all BSPs.
+    ///
+    /// This storage is updated in:
+    /// - [increase_data_used](storage_hub_traits::MutateProvidersInterface::increase_data_used), which adds the increase of the data used by a Backup Storage Provider.
+    /// - [decrease_data_used](storage_hub_traits::MutateProvidersInterface::decrease_data_used), which subtracts the decrease of the data used by a Backup Storage Provider.
+    #[pallet::storage]
+    pub type UsedBspsCapacity<T: Config> = StorageValue<_, StorageData<T>, ValueQuery>;
+
+    /// The set of Storage Providers whose deposit dropped below the minimum required by the runtime after being slashed.
+    ///
+    /// This is used to mark Storage Providers for potential removal, as they no longer back their capacity with enough stake.
+    ///
+    /// This storage is updated in:
+    /// - [slash](storage_hub_traits::ProvidersInterface::slash), which adds an entry to the set if the remaining deposit of the slashed Storage Provider is below `SpMinDeposit`.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the set.
+    #[pallet::storage]
+    pub type InsolventProviders<T: Config> = StorageMap<_, Blake2_128Concat, HashId<T>, ()>;
+
+    /// The mapping from the AccountId of a Storage Provider that requested to sign off to the block number when the request was made.
+    ///
+    /// This is used for the two-step process of signing off: when a SP requests to sign off, its deposit stays held
+    /// until `SignOffCooldown` blocks have passed, after which it can finalize the sign off and get the deposit back.
+    ///
+    /// This storage is updated in:
+    /// - [request_sign_off](crate::dispatchables::request_sign_off), which adds a new entry to the map.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
+    #[pallet::storage]
+    pub type PendingSignOffs<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BlockNumberFor<T>>;
+
+    /// The mapping from an AccountId to the deposit held from it to sign up and stay registered as a Storage Provider.
+    ///
+    /// This is the amount that is expected to be returned when the deposit is released, so that a release of less than
+    /// it (for example, because part of the deposit was slashed) can be told apart.
+    ///
+    /// This storage is updated in:
+    /// - [request_msp_sign_up](crate::dispatchables::request_msp_sign_up) and [request_bsp_sign_up](crate::dispatchables::request_bsp_sign_up),
+    /// which add a new entry to the map with the deposit held.
+    /// - [change_capacity](crate::dispatchables::change_capacity), which updates the entry of the signer to the new deposit.
+    /// - [cancel_sign_up](crate::dispatchables::cancel_sign_up), [finalize_sign_off](crate::dispatchables::finalize_sign_off)
+    /// and the sweep of expired sign up requests, which remove the corresponding entry from the map.
+    #[pallet::storage]
+    pub type ProviderDeposits<T: Config> =
+        StorageMap<_, Blake2_128Concat, T::AccountId, BalanceOf<T>, ValueQuery>;
+
+    /// The mapping from a Storage Provider ID to its reputation.
+    ///
+    /// Storage Providers start with a reputation of zero, which increases as they fulfill their duties (e.g. confirming
+    /// the storing of files) and decreases when they are slashed. It is always within `MinReputation` and `MaxReputation`.
+    ///
+    /// This storage is updated in:
+    /// - [change_reputation](storage_hub_traits::MutateProvidersInterface::change_reputation), which is used by other pallets
+    /// to reward or penalise a Storage Provider.
+    /// - [slash](storage_hub_traits::ProvidersInterface::slash), which decreases the reputation by `SlashReputationPenalty`.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
+    #[pallet::storage]
+    pub type ProviderReputation<T: Config> =
+        StorageMap<_, Blake2_128Concat, HashId<T>, Reputation, ValueQuery>;
+
+    /// The mapping from a Storage Provider ID to the last block in which it was known to be operational.
+    ///
+    /// This is used to tell apart the Storage Providers that are still running from the stale ones, which have not
+    /// sent a heartbeat in the last `LivenessTimeout` blocks.
+    ///
+    /// This storage is updated in:
+    /// - [confirm_sign_up](crate::dispatchables::confirm_sign_up), [confirm_sign_up_for](crate::dispatchables::confirm_sign_up_for) and
+    /// the genesis build, which add a new entry to the map with the block of the registration.
+    /// - [heartbeat](crate::dispatchables::heartbeat), which updates the entry of the signer to the current block.
+    /// - [finalize_sign_off](crate::dispatchables::finalize_sign_off), which removes the corresponding entry from the map.
+    #[pallet::storage]
+    pub type LastSeen<T: Config> = StorageMap<_, Blake2_128Concat, HashId<T>, BlockNumberFor<T>>;
+
+    // Genesis:
+
+    /// The genesis config of this pallet, which allows to register Storage Providers at genesis without going through
+    /// the sign up process.
+    ///
+    /// Each Storage Provider is defined by its account, its kind, its capacity, its multiaddresses and optionally its id,
+    /// for deployments that need deterministic ids. If no id is given, it is derived by hashing its account, since
+    /// randomness is not available at genesis. No deposit is held from the Storage Providers registered at genesis.
+    ///
+    /// Neither accounts nor ids can be repeated, in which case building the genesis fails.
+    #[pallet::genesis_config]
+    #[derive(frame_support::DefaultNoBound)]
+    pub struct GenesisConfig<T: Config> {
+        pub providers: Vec<(
+            T::AccountId,
+            GenesisProviderKind,
+            StorageData<T>,
+            Vec<Vec<u8>>,
+            Option<HashId<T>>,
+        )>,
+    }
+
+    #[pallet::genesis_build]
+    impl<T: Config> BuildGenesisConfig for GenesisConfig<T> {
+        fn build(&self) {
+            for (who, kind, capacity, multiaddresses, sp_id) in &self.providers {
+                Pallet::<T>::do_genesis_register_provider(
+                    who,
+                    *kind,
+                    *capacity,
+                    multiaddresses.clone(),
+                    *sp_id,
+                )
+                .expect("Genesis Storage Provider should be valid");
+            }
+        }
+    }
+
     // Events & Errors:
 
     /// The events that can be emitted by this pallet
@@ -332,6 +548,17 @@ pub mod pallet {
         /// the account id of the user that canceled the request.
         SignUpRequestCanceled { who: T::AccountId },
 
+        /// Event emitted when a sign up request has been removed because it expired without being confirmed, releasing
+        /// its deposit. Provides information about the account id of the user that requested to sign up.
+        SignUpRequestExpired { who: T::AccountId },
+
+        /// Event emitted when a SP has requested to sign off successfully. Provides information about
+        /// that SP's account id and the block from which it will be able to finalize its sign off.
+        SignOffRequested {
+            who: T::AccountId,
+            finalizable_at: BlockNumberFor<T>,
+        },
+
         /// Event emitted when a Main Storage Provider has signed off successfully. Provides information about
         /// that MSP's account id.
         MspSignOffSuccess { who: T::AccountId },
@@ -340,6 +567,15 @@ pub mod pallet {
         /// that BSP's account id.
         BspSignOffSuccess { who: T::AccountId },
 
+        /// Event emitted when the deposit of a user that signed off or canceled its sign up request could not be fully
+        /// returned, for example because part of it was slashed. Provides information about the account id of the user,
+        /// the amount returned and the amount that was expected to be returned.
+        PartialDepositReturned {
+            who: T::AccountId,
+            returned: BalanceOf<T>,
+            expected: BalanceOf<T>,
+        },
+
         /// Event emitted when a SP has changed its capacity successfully. Provides information about
         /// that SP's account id, its old total data that could store, and the new total data.
         CapacityChanged {
@@ -348,6 +584,51 @@ pub mod pallet {
             new_capacity: StorageData<T>,
             next_block_when_change_allowed: BlockNumberFor<T>,
         },
+
+        /// Event emitted when a SP has been slashed. Provides information about that SP's id, the amount of its
+        /// deposit that was slashed, and whether it has been marked for removal because its remaining deposit is too low.
+        ProviderSlashed {
+            provider_id: HashId<T>,
+            amount_slashed: BalanceOf<T>,
+            marked_for_removal: bool,
+        },
+
+        /// Event emitted when a user has requested to transfer one of its buckets to another MSP. Provides information
+        /// about the bucket's id and the MSP that has to accept the transfer.
+        BucketTransferRequested {
+            bucket_id: BucketId<T>,
+            new_msp_id: MainStorageProviderId<T>,
+        },
+
+        /// Event emitted when a MSP has accepted the transfer of a bucket to it. Provides information about the
+        /// bucket's id, the MSP that was storing it and the MSP that is storing it now.
+        BucketTransferred {
+            bucket_id: BucketId<T>,
+            old_msp_id: MainStorageProviderId<T>,
+            new_msp_id: MainStorageProviderId<T>,
+        },
+
+        /// Event emitted when a SP has updated its multiaddresses successfully. Provides information about
+        /// that SP's account id and its new multiaddresses.
+        MultiaddressesUpdated {
+            who: T::AccountId,
+            new_multiaddresses: BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>>,
+        },
+
+        /// Event emitted when the reputation of a SP has changed. Provides information about that SP's id, and its
+        /// reputation before and after the change.
+        ReputationChanged {
+            provider_id: HashId<T>,
+            old_reputation: Reputation,
+            new_reputation: Reputation,
+        },
+
+        /// Event emitted when a MSP has updated its value proposition successfully. Provides information about
+        /// that MSP's account id and its new value proposition.
+        ValuePropUpdated {
+            who: T::AccountId,
+            new_value_prop: ValueProposition<T>,
+        },
     }
 
     /// The errors that can be thrown by this pallet to inform users about what went wrong
@@ -356,6 +637,8 @@ pub mod pallet {
         // Sign up errors:
         /// Error thrown when a user tries to sign up as a SP but is already registered as a MSP or BSP.
         AlreadyRegistered,
+        /// Error thrown when a SP is registered at genesis with an id that is already used by another SP.
+        ProviderIdAlreadyInUse,
         /// Error thrown when a user tries to sign up as a BSP but the maximum amount of BSPs has been reached.
         MaxBspsReached,
         /// Error thrown when a user tries to sign up as a MSP but the maximum amount of MSPs has been reached.
@@ -370,6 +653,8 @@ pub mod pallet {
         InvalidMultiAddress,
         /// Error thrown when a user tries to sign up or change its capacity to store less storage than the minimum required by the runtime.
         StorageTooLow,
+        /// Error thrown when a MSP tries to update its value proposition with an empty or repeated protocol.
+        InvalidValueProposition,
 
         // Deposit errors:
         /// Error thrown when a user does not have enough balance to pay the deposit that it would incur by signing up as a SP or changing its capacity.
@@ -380,22 +665,37 @@ pub mod pallet {
         // Sign off errors:
         /// Error thrown when a user tries to sign off as a SP but still has used storage.
         StorageStillInUse,
+        /// Error thrown when a SP tries to request to sign off when it already has a sign off request pending.
+        SignOffAlreadyRequested,
+        /// Error thrown when a SP tries to finalize a sign off that was not requested previously.
+        SignOffNotRequested,
+        /// Error thrown when a SP tries to finalize its sign off before the sign off cooldown has elapsed.
+        SignOffCooldownNotElapsed,
+        /// Error thrown when a SP that has requested to sign off would get new storage assigned to it.
+        SignOffPending,
 
         // Randomness errors:
         /// Error thrown when a user tries to confirm a sign up but the randomness is too fresh to be used yet.
         RandomnessNotValidYet,
+        /// Error thrown when a user tries to confirm a sign up but there is no randomness available at all yet.
+        RandomnessNotAvailable,
         /// Error thrown when a user tries to confirm a sign up but too much time has passed since the request.
         SignUpRequestExpired,
 
         // Capacity change errors:
         /// Error thrown when a user tries to change its capacity to less than its used storage.
         NewCapacityLessThanUsedStorage,
+        /// Error thrown when a BSP tries to change its capacity to less than its used storage plus the storage of the
+        /// requests it volunteered for but has not confirmed yet.
+        CapacityBelowCommitment,
         /// Error thrown when a user tries to change its capacity to the same value it already has.
         NewCapacityEqualsCurrentCapacity,
         /// Error thrown when a user tries to change its capacity to zero (there are specific extrinsics to sign off as a SP).
         NewCapacityCantBeZero,
         /// Error thrown when a SP tries to change its capacity but it has not been enough time since the last time it changed it.
         NotEnoughTimePassed,
+        /// Error thrown when trying to increase the data used by a SP beyond its capacity.
+        ExceedsCapacity,
 
         // General errors:
         /// Error thrown when a user tries to interact as a SP but is not registered as a MSP or BSP.
@@ -406,6 +706,25 @@ pub mod pallet {
         NoBucketId,
         /// Error thrown when a user has a SP ID assigned to it but the SP data does not exist in storage (Inconsistency error).
         SpRegisteredButDataNotFound,
+
+        // Bucket errors:
+        /// Error thrown when trying to add a bucket whose derived ID is already taken by another bucket.
+        BucketAlreadyExists,
+        /// Error thrown when trying to change the root of a bucket from a MSP that is not the one storing it.
+        NotBucketMsp,
+        /// Error thrown when the proof given to change the root of a bucket does not show that the new root is a valid
+        /// successor of its current root.
+        InvalidRootTransition,
+        /// Error thrown when trying to operate on a bucket that does not exist.
+        BucketNotFound,
+        /// Error thrown when a user tries to operate on a bucket it does not own.
+        NotBucketOwner,
+        /// Error thrown when trying to transfer a bucket to the MSP that is already storing it.
+        BucketAlreadyInMsp,
+        /// Error thrown when a MSP tries to accept the transfer of a bucket that its owner did not request.
+        BucketTransferNotRequested,
+        /// Error thrown when a MSP tries to accept the transfer of a bucket that was requested to another MSP.
+        NotBucketTransferTarget,
     }
 
     /// This enum holds the HoldReasons for this pallet, allowing the runtime to identify each held balance with different reasons separately
@@ -420,9 +739,29 @@ pub mod pallet {
         AnotherUnrelatedHold,
     }
 
-    /// The hooks that this pallet utilizes (TODO: Check this, we might not need any)
+    /// The hooks that this pallet utilizes
     #[pallet::hooks]
-    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {}
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        /// Sweep the sign up requests that expired without being confirmed, releasing their deposits.
+        ///
+        /// Only as many requests as the remaining weight of the block allows are checked, resuming in the next
+        /// block from where the sweep stopped.
+        fn on_idle(_n: BlockNumberFor<T>, remaining_weight: Weight) -> Weight {
+            Self::do_sweep_expired_sign_up_requests(remaining_weight)
+        }
+
+        fn integrity_test() {
+            // Storage Providers start with a reputation of zero, so it must be within the allowed range
+            assert!(
+                T::MinReputation::get() <= 0 && T::MaxReputation::get() >= 0,
+                "MinReputation must be at most zero and MaxReputation at least zero"
+            );
+            assert!(
+                T::SlashReputationPenalty::get() >= 0,
+                "SlashReputationPenalty must not be negative"
+            );
+        }
+    }
 
     /// Dispatchables (extrinsics) exposed by this pallet
     #[pallet::call]
@@ -473,6 +812,7 @@ pub mod pallet {
                 multiaddresses: multiaddresses.clone(),
                 value_prop: value_prop.clone(),
                 last_capacity_change: frame_system::Pallet::<T>::block_number(),
+                owner_account: who.clone(),
             };
 
             // Sign up the new MSP (if possible), updating storage
@@ -531,6 +871,7 @@ pub mod pallet {
                 multiaddresses: multiaddresses.clone(),
                 root: MerklePatriciaRoot::<T>::default(),
                 last_capacity_change: frame_system::Pallet::<T>::block_number(),
+                owner_account: who.clone(),
             };
 
             // Sign up the new BSP (if possible), updating storage
@@ -600,7 +941,7 @@ pub mod pallet {
         /// 1. Check that the extrinsic was signed and get the signer.
         /// 2. Check that the signer has requested to sign up as a SP
         /// 3. Delete the request from the Sign Up Requests storage
-        /// 4. Return the deposit to the signer
+        /// 4. Return the deposit to the signer (what is left of it, emitting `PartialDepositReturned` if part of it is gone)
         ///
         /// Emits `SignUpRequestCanceled` event when successful.
         #[pallet::call_index(3)]
@@ -618,62 +959,70 @@ pub mod pallet {
             Ok(().into())
         }
 
-        /// Dispatchable extrinsic that allows users to sign off as a Main Storage Provider.
+        /// Dispatchable extrinsic that allows a Storage Provider, either MSP or BSP, to request to sign off.
         ///
         /// The dispatch origin for this call must be Signed.
-        /// The origin must be the account that wants to sign off as a Main Storage Provider.
+        /// The origin must be the account that wants to sign off as a Storage Provider.
+        ///
+        /// The deposit of the Storage Provider stays held during the sign off cooldown, after which the sign off can be
+        /// finalized with [finalize_sign_off](crate::dispatchables::finalize_sign_off). This prevents Storage Providers from
+        /// churning in and out of the network to game the BSPs assignment threshold.
         ///
         /// This extrinsic will perform the following checks and logic:
         /// 1. Check that the extrinsic was signed and get the signer.
-        /// 2. Check that the signer is registered as a MSP
-        /// 3. Check that the MSP has no storage assigned to it (no buckets or data used by it)
-        /// 4. Update the MSPs storage, removing the signer as an MSP
-        /// 5. Return the deposit to the signer
-        /// 6. Decrement the storage that holds total amount of MSPs currently in the system
+        /// 2. Check that the signer is registered as a MSP or BSP
+        /// 3. Check that the signer does not already have a sign off request pending
+        /// 4. Check that the SP has no storage assigned to it (no buckets or data used by it)
+        /// 5. Update the Pending Sign Offs storage to add the signer as requesting to sign off
+        /// 6. If the signer is a BSP, decrement the storage that holds total amount of BSPs currently in the system
         ///
-        /// Emits `MspSignOffSuccess` event when successful.
+        /// Emits `SignOffRequested` event when successful.
         #[pallet::call_index(4)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
-        pub fn msp_sign_off(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(6, 3))]
+        pub fn request_sign_off(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
             // Check that the extrinsic was signed and get the signer.
             let who = ensure_signed(origin)?;
 
             // Execute checks and logic, update storage
-            Self::do_msp_sign_off(&who)?;
+            let finalizable_at = Self::do_request_sign_off(&who)?;
 
             // Emit the corresponding event
-            Self::deposit_event(Event::<T>::MspSignOffSuccess { who });
+            Self::deposit_event(Event::<T>::SignOffRequested {
+                who,
+                finalizable_at,
+            });
 
             // Return a successful DispatchResultWithPostInfo
             Ok(().into())
         }
 
-        /// Dispatchable extrinsic that allows users to sign off as a Backup Storage Provider.
+        /// Dispatchable extrinsic that allows a Storage Provider, either MSP or BSP, to finalize its sign off once the
+        /// sign off cooldown has elapsed, getting its deposit back.
         ///
         /// The dispatch origin for this call must be Signed.
-        /// The origin must be the account that wants to sign off as a Backup Storage Provider.
+        /// The origin must be the account that requested to sign off as a Storage Provider.
         ///
         /// This extrinsic will perform the following checks and logic:
         /// 1. Check that the extrinsic was signed and get the signer.
-        /// 2. Check that the signer is registered as a BSP
-        /// 3. Check that the BSP has no storage assigned to it
-        /// 4. Update the BSPs storage, removing the signer as an BSP
-        /// 5. Update the total capacity of all BSPs, removing the capacity of the signer
-        /// 6. Return the deposit to the signer
-        /// 7. Decrement the storage that holds total amount of BSPs currently in the system
-        ///
-        /// Emits `BspSignOffSuccess` event when successful.
+        /// 2. Check that the signer has requested to sign off
+        /// 3. Check that the sign off cooldown has elapsed since the request
+        /// 4. Check that the SP still has no storage assigned to it
+        /// 5. Update the MSPs or BSPs storage, removing the signer as a SP
+        /// 6. If the signer is a BSP, update the total capacity of all BSPs, removing the capacity of the signer
+        /// 7. Return the deposit to the signer (what is left of it, emitting `PartialDepositReturned` if part of it is gone)
+        /// 8. If the signer is a MSP, decrement the storage that holds total amount of MSPs currently in the system
+        /// 9. Delete the request from the Pending Sign Offs storage
+        ///
+        /// Emits `MspSignOffSuccess` or `BspSignOffSuccess` event when successful, depending on the type of SP.
         #[pallet::call_index(5)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
-        pub fn bsp_sign_off(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(9, 10))]
+        pub fn finalize_sign_off(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
             // Check that the extrinsic was signed and get the signer.
             let who = ensure_signed(origin)?;
 
-            // Execute checks and logic, update storage
-            Self::do_bsp_sign_off(&who)?;
-
-            // Emit the corresponding event
-            Self::deposit_event(Event::<T>::BspSignOffSuccess { who });
+            // Execute checks and logic, update storage and emit event
+            // We emit the event in the interior logic to not have to check again which type of SP it is outside of it
+            Self::do_finalize_sign_off(&who)?;
 
             // Return a successful DispatchResultWithPostInfo
             Ok(().into())
@@ -704,7 +1053,7 @@ pub mod pallet {
         ///
         /// Emits `CapacityChanged` event when successful.
         #[pallet::call_index(6)]
-        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(1, 2))]
         pub fn change_capacity(
             origin: OriginFor<T>,
             new_capacity: StorageData<T>,
@@ -754,6 +1103,233 @@ pub mod pallet {
 
             Ok(().into())
         }
+
+        /// Dispatchable extrinsic that allows a Storage Provider to replace the multiaddresses it can be reached at
+        ///
+        /// The dispatch origin for this call must be Signed.
+        /// The origin must be the account of a registered Storage Provider.
+        ///
+        /// Parameters:
+        /// - `new_multiaddresses`: The multiaddresses that will replace the current ones of the Storage Provider.
+        ///
+        /// This extrinsic will perform the following checks and logic:
+        /// 1. Check that the extrinsic was signed and get the signer.
+        /// 2. Check that the signer is registered as a SP
+        /// 3. Check that the new multiaddresses vector is not empty
+        /// 4. Check that the new multiaddresses are valid and not repeated
+        /// 5. Update the SPs storage to replace its multiaddresses, keeping its id and the rest of its information
+        ///
+        /// Emits `MultiaddressesUpdated` event when successful.
+        #[pallet::call_index(8)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn update_multiaddresses(
+            origin: OriginFor<T>,
+            new_multiaddresses: BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>>,
+        ) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Execute checks and logic, update storage
+            Self::do_update_multiaddresses(&who, new_multiaddresses.clone())?;
+
+            // Emit the corresponding event
+            Self::deposit_event(Event::<T>::MultiaddressesUpdated {
+                who,
+                new_multiaddresses,
+            });
+
+            // Return a successful DispatchResultWithPostInfo
+            Ok(().into())
+        }
+
+        /// Dispatchable extrinsic that allows a Main Storage Provider to replace its value proposition
+        ///
+        /// The dispatch origin for this call must be Signed.
+        /// The origin must be the account of a registered Main Storage Provider.
+        ///
+        /// Parameters:
+        /// - `new_value_prop`: The value proposition that will replace the current one of the Main Storage Provider.
+        ///
+        /// This extrinsic will perform the following checks and logic:
+        /// 1. Check that the extrinsic was signed and get the signer.
+        /// 2. Check that the signer is registered as a MSP
+        /// 3. Check that the protocols of the new value proposition are not empty nor repeated (their amount and
+        /// size are already bounded by MaxProtocols)
+        /// 4. Update the MSPs storage to replace its value proposition, keeping its id, buckets and the rest of its information
+        ///
+        /// Emits `ValuePropUpdated` event when successful.
+        #[pallet::call_index(9)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(2, 1))]
+        pub fn update_value_prop(
+            origin: OriginFor<T>,
+            new_value_prop: ValueProposition<T>,
+        ) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Execute checks and logic, update storage
+            Self::do_update_value_prop(&who, new_value_prop.clone())?;
+
+            // Emit the corresponding event
+            Self::deposit_event(Event::<T>::ValuePropUpdated {
+                who,
+                new_value_prop,
+            });
+
+            // Return a successful DispatchResultWithPostInfo
+            Ok(().into())
+        }
+
+        /// Dispatchable extrinsic that allows any account to confirm the sign up of another account as a Storage Provider,
+        /// either MSP or BSP.
+        ///
+        /// The dispatch origin for this call must be Signed, but it does not have to be the account that requested to sign up.
+        /// This allows, for example, a watchtower or the provider's node running with a different key to finalize the
+        /// registration once the randomness is ready, without the provider having to keep its main key online.
+        ///
+        /// Parameters:
+        /// - `provider_account`: The account that requested to sign up as a Storage Provider.
+        ///
+        /// This extrinsic performs the same checks and logic as [confirm_sign_up](crate::dispatchables::confirm_sign_up),
+        /// registering `provider_account` (and not the signer) as a MSP or BSP with the data provided in its request.
+        ///
+        /// Emits `MspSignUpSuccess` or `BspSignUpSuccess` event for `provider_account` when successful, depending on the type of sign up.
+        ///
+        /// Notes:
+        /// - No funds are moved from the caller, since the deposit was already held from `provider_account` when it requested to sign up
+        /// - If this extrinsic is successful, it will be free for the caller, to incentive state debloating
+        #[pallet::call_index(10)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1))]
+        pub fn confirm_sign_up_for(
+            origin: OriginFor<T>,
+            provider_account: T::AccountId,
+        ) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed. The signer can be any account
+            ensure_signed(origin)?;
+
+            // Execute checks and logic, update storage and emit event for the provider account
+            Self::do_confirm_sign_up(&provider_account)?;
+
+            // Return a successful DispatchResultWithPostInfo. If the extrinsic executed correctly, it will be free for the caller
+            Ok(Pays::No.into())
+        }
+
+        /// Dispatchable extrinsic that allows a user to transfer one of its buckets to another Main Storage Provider, for
+        /// example because the MSP storing it is shutting down.
+        ///
+        /// The dispatch origin for this call must be Signed.
+        /// The origin must be the user that owns the bucket.
+        ///
+        /// Parameters:
+        /// - `bucket_id`: The id of the bucket to transfer. It is kept after the transfer, even if it was derived from
+        /// the id of the MSP that was storing it.
+        /// - `new_msp_id`: The id of the MSP that will store the bucket.
+        ///
+        /// This extrinsic will perform the following checks and logic:
+        /// 1. Check that the extrinsic was signed and get the signer.
+        /// 2. Check that the bucket exists and that the signer owns it
+        /// 3. Check that the new MSP is registered and is not the one already storing the bucket
+        /// 4. Record the request, replacing any previous one for the bucket, so that the new MSP can accept it
+        ///
+        /// Emits `BucketTransferRequested` event when successful.
+        ///
+        /// Notes:
+        /// - The bucket is not moved until the new MSP accepts the transfer with
+        /// [accept_bucket_transfer](crate::dispatchables::accept_bucket_transfer), so a MSP never gets data assigned
+        /// to it without its consent
+        #[pallet::call_index(11)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(2, 1))]
+        pub fn transfer_bucket(
+            origin: OriginFor<T>,
+            bucket_id: BucketId<T>,
+            new_msp_id: MainStorageProviderId<T>,
+        ) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Execute checks and logic, update storage
+            Self::do_request_bucket_transfer(&who, &bucket_id, &new_msp_id)?;
+
+            // Emit the corresponding event
+            Self::deposit_event(Event::<T>::BucketTransferRequested {
+                bucket_id,
+                new_msp_id,
+            });
+
+            // Return a successful DispatchResultWithPostInfo
+            Ok(().into())
+        }
+
+        /// Dispatchable extrinsic that allows a Storage Provider to signal that it is still operational.
+        ///
+        /// The dispatch origin for this call must be Signed.
+        /// The origin must be the account of a registered Storage Provider, either a MSP or a BSP.
+        ///
+        /// This extrinsic will perform the following checks and logic:
+        /// 1. Check that the extrinsic was signed and get the signer.
+        /// 2. Check that the signer is registered as a SP
+        /// 3. Update the block in which the SP was last seen to the current one
+        ///
+        /// No event is emitted, to keep this extrinsic as cheap as possible.
+        ///
+        /// Notes:
+        /// - This extrinsic is not free, to discourage SPs from spamming it
+        /// - A SP that does not call this extrinsic for more than `LivenessTimeout` blocks is considered stale, see
+        /// [is_provider_stale](crate::Pallet::is_provider_stale)
+        #[pallet::call_index(12)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(2, 1))]
+        pub fn heartbeat(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Execute checks and logic, update storage
+            Self::do_heartbeat(&who)?;
+
+            // Return a successful DispatchResultWithPostInfo
+            Ok(().into())
+        }
+
+        /// Dispatchable extrinsic that allows a Main Storage Provider to accept the transfer of a bucket to it, requested
+        /// by the owner of the bucket.
+        ///
+        /// The dispatch origin for this call must be Signed.
+        /// The origin must be the account of the MSP the transfer was requested to.
+        ///
+        /// Parameters:
+        /// - `bucket_id`: The id of the bucket to accept.
+        ///
+        /// This extrinsic will perform the following checks and logic:
+        /// 1. Check that the extrinsic was signed and get the signer.
+        /// 2. Check that the signer is registered as a MSP and that the transfer of the bucket was requested to it
+        /// 3. Check that the bucket still exists and is not already stored by the signer
+        /// 4. Add the size of the bucket to the data used by the signer, checking that it has enough available capacity
+        /// and that it is not signing off
+        /// 5. Remove the size of the bucket from the data used by the old MSP
+        /// 6. Update the bucket and the buckets of both MSPs to reflect the new MSP, and remove the request
+        ///
+        /// Emits `BucketTransferred` event when successful.
+        #[pallet::call_index(13)]
+        #[pallet::weight(Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(6, 6))]
+        pub fn accept_bucket_transfer(
+            origin: OriginFor<T>,
+            bucket_id: BucketId<T>,
+        ) -> DispatchResultWithPostInfo {
+            // Check that the extrinsic was signed and get the signer.
+            let who = ensure_signed(origin)?;
+
+            // Execute checks and logic, update storage
+            let (old_msp_id, new_msp_id) = Self::do_accept_bucket_transfer(&who, &bucket_id)?;
+
+            // Emit the corresponding event
+            Self::deposit_event(Event::<T>::BucketTransferred {
+                bucket_id,
+                old_msp_id,
+                new_msp_id,
+            });
+
+            // Return a successful DispatchResultWithPostInfo
+            Ok(().into())
+        }
     }
 }
 
@@ -766,6 +1342,47 @@ impl<T: Config> Pallet<T> {
         SignUpRequests::<T>::get(who).ok_or(Error::<T>::SignUpNotRequested)
     }
 
+    /// A helper function to check if the randomness needed to confirm the sign up request of a user is already valid,
+    /// so that `confirm_sign_up` does not fail with `RandomnessNotValidYet`.
+    ///
+    /// It returns `None` if the user has no pending sign up request. Other reasons for the confirmation to fail, such
+    /// as the request having expired, are not checked.
+    pub fn sign_up_randomness_ready(who: &T::AccountId) -> Option<bool> {
+        let (_, request_block) = SignUpRequests::<T>::get(who)?;
+
+        // Get the randomness the same way the sign up confirmation does
+        let (_, block_number_when_random) = Self::provider_id_randomness(who);
+
+        Some(block_number_when_random >= request_block)
+    }
+
+    /// A helper function to get the randomness used to derive the Storage Provider ID of a user, along with the
+    /// latest block for which it is valid.
+    ///
+    /// The encoded AccountId of the user is used as subject, prefixed with [`PROVIDER_ID_RANDOMNESS_DOMAIN`].
+    pub fn provider_id_randomness(who: &T::AccountId) -> (HashId<T>, BlockNumberFor<T>) {
+        let subject = [PROVIDER_ID_RANDOMNESS_DOMAIN, who.encode().as_ref()].concat();
+        T::ProvidersRandomness::random(&subject)
+    }
+
+    /// A helper function to get the reputation of a Storage Provider.
+    ///
+    /// Storage Providers without any reputation change (or that are not registered) have a reputation of zero.
+    pub fn get_reputation(provider_id: &HashId<T>) -> Reputation {
+        ProviderReputation::<T>::get(provider_id)
+    }
+
+    /// A helper function to check if a Storage Provider is stale at `current_block`, that is, if more than
+    /// `LivenessTimeout` blocks have passed since it was last seen.
+    ///
+    /// Storage Providers that were never seen (for example, because they are not registered) are considered stale.
+    pub fn is_provider_stale(provider_id: &HashId<T>, current_block: BlockNumberFor<T>) -> bool {
+        match LastSeen::<T>::get(provider_id) {
+            Some(last_seen) => current_block > last_seen.saturating_add(T::LivenessTimeout::get()),
+            None => true,
+        }
+    }
+
     /// A helper function to get the total capacity of a storage provider.
     pub fn get_total_capacity_of_sp(who: &T::AccountId) -> Result<StorageData<T>, Error<T>> {
         if let Some(m_id) = AccountIdToMainStorageProviderId::<T>::get(who) {
@@ -779,11 +1396,36 @@ impl<T: Config> Pallet<T> {
         }
     }
 
+    /// A helper function to derive the id of a bucket from the Main Storage Provider that holds it, the user that owns it
+    /// and a salt, which allows a user to own multiple buckets under the same Main Storage Provider.
+    pub fn derive_bucket_id(
+        msp_id: &MainStorageProviderId<T>,
+        user_id: &T::AccountId,
+        salt: &[u8],
+    ) -> BucketId<T> {
+        T::Hashing::hash_of(&(msp_id, user_id, salt))
+    }
+
+    /// A helper function to get the ids of all the buckets held by a Main Storage Provider.
+    pub fn buckets_of_msp(msp_id: &MainStorageProviderId<T>) -> Vec<BucketId<T>> {
+        BucketsByMsp::<T>::iter_key_prefix(msp_id).collect()
+    }
+
+    /// A helper function to get the ids of all the buckets owned by a user.
+    pub fn buckets_of_user(who: &T::AccountId) -> Vec<BucketId<T>> {
+        BucketsByUser::<T>::iter_key_prefix(who).collect()
+    }
+
     /// A helper function to get the total capacity of all BSPs which is the total capacity of the network.
     pub fn get_total_bsp_capacity() -> StorageData<T> {
         TotalBspsCapacity::<T>::get()
     }
 
+    /// A helper function to get the total data used by all Backup Storage Providers.
+    pub fn get_used_bsp_capacity() -> StorageData<T> {
+        UsedBspsCapacity::<T>::get()
+    }
+
     /// A helper function to get the total data used by a Main Storage Provider.
     pub fn get_used_storage_of_msp(
         who: &MainStorageProviderId<T>,
@@ -6,12 +6,15 @@ use frame_support::{
     weights::constants::RocksDbWeight,
 };
 use frame_system as system;
-use sp_core::{hashing::blake2_256, ConstU128, ConstU32, ConstU64, H256};
+use sp_core::{hashing::blake2_256, ConstI32, ConstU128, ConstU32, ConstU64, H256};
 use sp_runtime::{
     traits::{BlakeTwo256, IdentityLookup},
-    BuildStorage, DispatchResult,
+    BuildStorage, DispatchError, DispatchResult,
+};
+use sp_trie::CompactProof;
+use storage_hub_traits::{
+    ReadStorageCommitmentsInterface, RootTransitionVerifier, SubscribeProvidersInterface,
 };
-use storage_hub_traits::SubscribeProvidersInterface;
 use system::pallet_prelude::BlockNumberFor;
 
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -20,9 +23,20 @@ const EPOCH_DURATION_IN_BLOCKS: BlockNumberFor<Test> = 10;
 
 // We mock the Randomness trait to use a simple randomness function when testing the pallet
 const BLOCKS_BEFORE_RANDOMNESS_VALID: BlockNumberFor<Test> = 3;
+parameter_types! {
+    /// Whether the mocked randomness has a seed available, returning the default hash if not
+    pub static RandomnessAvailable: bool = true;
+    /// Accounts of the BSPs whose sign up the subscribers were notified of
+    pub static SubscribedBspSignUps: Vec<u64> = vec![];
+}
 pub struct MockRandomness;
 impl Randomness<H256, BlockNumberFor<Test>> for MockRandomness {
     fn random(subject: &[u8]) -> (H256, BlockNumberFor<Test>) {
+        // Without a seed, the randomness is the default hash, invalid for every block
+        if !RandomnessAvailable::get() {
+            return (H256::default(), 0);
+        }
+
         // Simple randomness mock that changes each block but its randomness is only valid after 3 blocks
 
         // Concatenate the subject with the block number to get a unique hash for each block
@@ -121,6 +135,11 @@ impl crate::Config for Test {
     type MaxProtocols = ConstU32<100>;
     type MaxBlocksForRandomness = ConstU64<{ EPOCH_DURATION_IN_BLOCKS * 2 }>;
     type MinBlocksBetweenCapacityChanges = ConstU64<10>;
+    type SignOffCooldown = ConstU64<10>;
+    type MinReputation = ConstI32<-100>;
+    type MaxReputation = ConstI32<100>;
+    type SlashReputationPenalty = ConstI32<10>;
+    type LivenessTimeout = ConstU64<20>;
     type MaxBsps = ConstU32<100>;
     type MaxMsps = ConstU32<100>;
     type MaxBuckets = ConstU32<10000>;
@@ -128,6 +147,8 @@ impl crate::Config for Test {
     type SpMinCapacity = ConstU32<2>;
     type DepositPerData = ConstU128<2>;
     type Subscribers = MockedProvidersSubscriber;
+    type StorageCommitments = MockedStorageCommitments;
+    type RootTransitionVerifier = MockRootTransitionVerifier;
     type ProvidersRandomness = MockRandomness;
 }
 
@@ -143,6 +164,18 @@ pub fn _new_test_ext() -> sp_io::TestExternalities {
 pub struct ExtBuilder;
 impl ExtBuilder {
     pub fn build() -> sp_io::TestExternalities {
+        Self::build_with_genesis_providers(vec![])
+    }
+
+    pub fn build_with_genesis_providers(
+        providers: Vec<(
+            u64,
+            pallet_storage_providers::types::GenesisProviderKind,
+            u32,
+            Vec<Vec<u8>>,
+            Option<H256>,
+        )>,
+    ) -> sp_io::TestExternalities {
         let mut t = frame_system::GenesisConfig::<Test>::default()
             .build_storage()
             .unwrap();
@@ -159,6 +192,9 @@ impl ExtBuilder {
         }
         .assimilate_storage(&mut t)
         .unwrap();
+        pallet_storage_providers::GenesisConfig::<Test> { providers }
+            .assimilate_storage(&mut t)
+            .unwrap();
 
         let mut ext = sp_io::TestExternalities::new(t);
         ext.execute_with(|| System::set_block_number(1));
@@ -170,10 +206,48 @@ pub struct MockedProvidersSubscriber;
 impl SubscribeProvidersInterface for MockedProvidersSubscriber {
     type Provider = u64;
 
-    fn subscribe_bsp_sign_up(_who: &Self::Provider) -> DispatchResult {
+    fn subscribe_bsp_sign_up(who: &Self::Provider) -> DispatchResult {
+        SubscribedBspSignUps::mutate(|sign_ups| sign_ups.push(*who));
         Ok(())
     }
     fn subscribe_bsp_sign_off(_who: &Self::Provider) -> DispatchResult {
         Ok(())
     }
 }
+
+/// Mocked storage commitments, where BSPs never have pending volunteered storage
+pub struct MockedStorageCommitments;
+impl ReadStorageCommitmentsInterface for MockedStorageCommitments {
+    type AccountId = u64;
+    type StorageData = u32;
+
+    fn pending_volunteered_storage(_who: &Self::AccountId) -> Self::StorageData {
+        0
+    }
+}
+
+/// Mocked root transition verifier, where a proof shows a valid transition only if its encoded nodes are exactly the old
+/// root followed by the new root
+pub struct MockRootTransitionVerifier;
+impl RootTransitionVerifier for MockRootTransitionVerifier {
+    type Proof = CompactProof;
+    type Key = H256;
+
+    fn apply_transition(old_root: &H256, proof: &CompactProof) -> Result<H256, DispatchError> {
+        match proof.encoded_nodes.as_slice() {
+            [proof_old_root, new_root]
+                if proof_old_root.as_slice() == old_root.as_ref() && new_root.len() == 32 =>
+            {
+                Ok(H256::from_slice(new_root))
+            }
+            _ => Err("Invalid root transition".into()),
+        }
+    }
+}
+
+/// Build the proof of a transition from `old_root` to `new_root` accepted by the [`MockRootTransitionVerifier`]
+pub fn root_transition_proof(old_root: H256, new_root: H256) -> CompactProof {
+    CompactProof {
+        encoded_nodes: vec![old_root.as_ref().to_vec(), new_root.as_ref().to_vec()],
+    }
+}
@@ -0,0 +1,86 @@
+//! Lightweight, `no_std` compatible validation of the multiaddresses Storage Providers register with.
+//!
+//! Multiaddresses are expected in their human-readable form, such as `/ip4/127.0.0.1/udp/1234` or
+//! `/dns4/sp.example.com/tcp/30333/p2p/12D3KooW...`. This only checks that they are syntactically valid,
+//! not that they are reachable.
+
+use core::net::{Ipv4Addr, Ipv6Addr};
+
+/// Max length of a domain name, as per RFC 1035.
+const MAX_DNS_NAME_LENGTH: usize = 253;
+
+/// Max length of a label of a domain name, as per RFC 1035.
+const MAX_DNS_LABEL_LENGTH: usize = 63;
+
+/// Check that `multiaddress` is a syntactically valid multiaddress.
+///
+/// It has to be a UTF-8 string made of one or more `/`-prefixed protocol components, each followed by the
+/// value that protocol requires (if any). Empty components and unknown protocols are rejected.
+pub(crate) fn is_valid_multiaddress(multiaddress: &[u8]) -> bool {
+    let Ok(multiaddress) = core::str::from_utf8(multiaddress) else {
+        return false;
+    };
+    let Some(components) = multiaddress.strip_prefix('/') else {
+        return false;
+    };
+
+    let mut components = components.split('/');
+    let mut has_protocol = false;
+    while let Some(protocol) = components.next() {
+        let is_valid_component = match protocol {
+            "ip4" => components
+                .next()
+                .is_some_and(|value| value.parse::<Ipv4Addr>().is_ok()),
+            "ip6" => components
+                .next()
+                .is_some_and(|value| value.parse::<Ipv6Addr>().is_ok()),
+            "tcp" | "udp" | "dccp" | "sctp" => components.next().is_some_and(is_valid_port),
+            "dns" | "dns4" | "dns6" | "dnsaddr" => components.next().is_some_and(is_valid_dns_name),
+            "p2p" | "ipfs" | "certhash" => components.next().is_some_and(is_valid_encoded_value),
+            "quic" | "quic-v1" | "webtransport" | "webrtc" | "webrtc-direct" | "tls" | "noise"
+            | "http" | "https" | "ws" | "wss" | "p2p-circuit" => true,
+            _ => false,
+        };
+
+        if !is_valid_component {
+            return false;
+        }
+        has_protocol = true;
+    }
+
+    has_protocol
+}
+
+/// Check that `value` is a port number, written only with decimal digits.
+fn is_valid_port(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) && value.parse::<u16>().is_ok()
+}
+
+/// Check that `value` is a valid domain name, made of dot-separated labels of alphanumeric characters
+/// and hyphens, which can not start or end with a hyphen.
+fn is_valid_dns_name(value: &str) -> bool {
+    // A single trailing dot denotes a fully qualified domain name.
+    let name = value.strip_suffix('.').unwrap_or(value);
+
+    !name.is_empty()
+        && name.len() <= MAX_DNS_NAME_LENGTH
+        && name.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= MAX_DNS_LABEL_LENGTH
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        })
+}
+
+/// Check that `value` looks like a multibase encoded value, such as a peer ID or a certificate hash.
+///
+/// The exact encoding is not decoded, only its alphabet is checked.
+fn is_valid_encoded_value(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+}
@@ -1,10 +1,11 @@
 use crate::{
     mock::*,
     types::{
-        BackupStorageProvider, BalanceOf, MainStorageProvider, MaxMultiAddressAmount, MultiAddress,
-        StorageData, StorageProvider, ValuePropId, ValueProposition,
+        BackupStorageProvider, BalanceOf, GenesisProviderKind, MainStorageProvider,
+        MaxMultiAddressAmount, MultiAddress, StorageData, StorageProvider, ValuePropId,
+        ValueProposition,
     },
-    Error, Event,
+    Error, Event, ProviderDeposits,
 };
 
 use frame_support::pallet_prelude::Weight;
@@ -14,8 +15,10 @@ use frame_support::traits::{
 };
 use frame_support::{assert_noop, assert_ok, dispatch::Pays, BoundedVec};
 use frame_system::pallet_prelude::BlockNumberFor;
+use sp_runtime::{ArithmeticError, DispatchError};
 use storage_hub_traits::MutateProvidersInterface;
 use storage_hub_traits::ProvidersInterface;
+use storage_hub_traits::ReadProvidersInterface;
 
 type NativeBalance = <Test as crate::Config>::NativeBalance;
 type AccountId = <Test as frame_system::Config>::AccountId;
@@ -27,6 +30,7 @@ type DepositPerData = <Test as crate::Config>::DepositPerData;
 type MaxMsps = <Test as crate::Config>::MaxMsps;
 type MaxBsps = <Test as crate::Config>::MaxBsps;
 type MinBlocksBetweenCapacityChanges = <Test as crate::Config>::MinBlocksBetweenCapacityChanges;
+type SignOffCooldown = <Test as crate::Config>::SignOffCooldown;
 
 // Runtime constants:
 // This is the duration of an epoch in blocks, a constant from the runtime configuration that we mock here
@@ -821,6 +825,83 @@ mod sign_up {
                 });
             }
 
+            #[test]
+            fn bsp_confirm_sign_up_for_works_when_called_by_a_third_party() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let mut multiaddresses: BoundedVec<
+                        MultiAddress<Test>,
+                        MaxMultiAddressAmount<Test>,
+                    > = BoundedVec::new();
+                    multiaddresses.force_push(
+                        "/ip4/127.0.0.1/udp/1234"
+                            .as_bytes()
+                            .to_vec()
+                            .try_into()
+                            .unwrap(),
+                    );
+                    let storage_amount: StorageData<Test> = 100;
+
+                    // Get the Account Id of Alice, who signs up, and Bob, who confirms the sign up for her
+                    let alice: AccountId = 0;
+                    let bob: AccountId = 1;
+
+                    // Request sign up of Alice as a Backup Storage Provider
+                    assert_ok!(StorageProviders::request_bsp_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        storage_amount,
+                        multiaddresses.clone(),
+                    ));
+                    let alice_free_balance = NativeBalance::free_balance(&alice);
+                    let alice_held_balance =
+                        NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice);
+                    let bob_free_balance = NativeBalance::free_balance(&bob);
+
+                    // Advance enough blocks for randomness to be valid
+                    run_to_block(
+                        frame_system::Pallet::<Test>::block_number()
+                            + BLOCKS_BEFORE_RANDOMNESS_VALID,
+                    );
+
+                    // Bob confirms the sign up of Alice as a Backup Storage Provider
+                    assert_ok!(StorageProviders::confirm_sign_up_for(
+                        RuntimeOrigin::signed(bob),
+                        alice
+                    ));
+
+                    // Check that Alice is now a Storage Provider and Bob is not
+                    let alice_sp_id = StorageProviders::get_provider(alice);
+                    assert!(alice_sp_id.is_some());
+                    assert!(StorageProviders::is_provider(alice_sp_id.unwrap()));
+                    assert!(StorageProviders::get_provider(bob).is_none());
+
+                    // Check that the sign up request of Alice was removed
+                    assert!(StorageProviders::get_sign_up_request(&alice)
+                        .is_err_and(|err| { matches!(err, Error::<Test>::SignUpNotRequested) }));
+
+                    // Check that no funds were moved
+                    assert_eq!(NativeBalance::free_balance(&alice), alice_free_balance);
+                    assert_eq!(
+                        NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice),
+                        alice_held_balance
+                    );
+                    assert_eq!(NativeBalance::free_balance(&bob), bob_free_balance);
+
+                    // Check that the total capacity of the Backup Storage Providers has now increased
+                    assert_eq!(StorageProviders::get_total_bsp_capacity(), storage_amount);
+
+                    // Check that the confirm BSP sign up event was emitted for Alice
+                    System::assert_last_event(
+                        Event::<Test>::BspSignUpSuccess {
+                            who: alice,
+                            multiaddresses,
+                            capacity: storage_amount,
+                        }
+                        .into(),
+                    );
+                });
+            }
+
             #[test]
             fn multiple_users_can_request_to_sign_up_as_bsp() {
                 ExtBuilder::build().execute_with(|| {
@@ -1637,6 +1718,242 @@ mod sign_up {
                     );
                 });
             }
+
+            #[test]
+            fn expired_sign_up_requests_are_swept_and_deposits_released() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let mut multiaddresses: BoundedVec<
+                        MultiAddress<Test>,
+                        MaxMultiAddressAmount<Test>,
+                    > = BoundedVec::new();
+                    multiaddresses.force_push(
+                        "/ip4/127.0.0.1/udp/1234"
+                            .as_bytes()
+                            .to_vec()
+                            .try_into()
+                            .unwrap(),
+                    );
+                    let value_prop: ValueProposition<Test> = ValueProposition {
+                        identifier: ValuePropId::<Test>::default(),
+                        data_limit: 10,
+                        protocols: BoundedVec::new(),
+                    };
+
+                    // Alice requests to sign up as a Main Storage Provider and Bob as a Backup Storage Provider
+                    let alice: AccountId = 0;
+                    let bob: AccountId = 1;
+                    assert_ok!(StorageProviders::request_msp_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        100,
+                        multiaddresses.clone(),
+                        value_prop.clone()
+                    ));
+                    assert_ok!(StorageProviders::request_bsp_sign_up(
+                        RuntimeOrigin::signed(bob),
+                        300,
+                        multiaddresses.clone()
+                    ));
+                    assert!(
+                        NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice)
+                            > 0
+                    );
+                    assert!(
+                        NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &bob)
+                            > 0
+                    );
+
+                    // Advance to the block in which the requests expire. They can't be confirmed anymore, but are
+                    // not swept yet, so they can still be canceled
+                    let expiration_block = frame_system::Pallet::<Test>::block_number()
+                        + (EPOCH_DURATION_IN_BLOCKS * 2);
+                    run_to_block(expiration_block);
+                    assert!(StorageProviders::get_sign_up_request(&alice).is_ok());
+                    assert!(StorageProviders::get_sign_up_request(&bob).is_ok());
+
+                    // Advance one more block for the requests to be swept
+                    run_to_block(expiration_block + 1);
+
+                    // Check that both sign up requests no longer exist and their deposits were released
+                    for who in [alice, bob] {
+                        assert!(
+                            StorageProviders::get_sign_up_request(&who).is_err_and(|err| {
+                                matches!(err, Error::<Test>::SignUpNotRequested)
+                            })
+                        );
+                        assert_eq!(
+                            NativeBalance::balance_on_hold(
+                                &StorageProvidersHoldReason::get(),
+                                &who
+                            ),
+                            0
+                        );
+                        System::assert_has_event(
+                            Event::<Test>::SignUpRequestExpired { who }.into(),
+                        );
+                    }
+                    assert_eq!(NativeBalance::free_balance(&alice), 5_000_000);
+                    assert_eq!(NativeBalance::free_balance(&bob), 10_000_000);
+                });
+            }
+
+            #[test]
+            fn expired_sign_up_requests_sweep_is_bounded_by_weight() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let mut multiaddresses: BoundedVec<
+                        MultiAddress<Test>,
+                        MaxMultiAddressAmount<Test>,
+                    > = BoundedVec::new();
+                    multiaddresses.force_push(
+                        "/ip4/127.0.0.1/udp/1234"
+                            .as_bytes()
+                            .to_vec()
+                            .try_into()
+                            .unwrap(),
+                    );
+
+                    // Three users request to sign up as Backup Storage Providers
+                    let users: [AccountId; 3] = [0, 1, 2];
+                    for who in users {
+                        assert_ok!(StorageProviders::request_bsp_sign_up(
+                            RuntimeOrigin::signed(who),
+                            100,
+                            multiaddresses.clone()
+                        ));
+                    }
+
+                    // Let the requests expire without running the sweep
+                    System::set_block_number(
+                        System::block_number() + (EPOCH_DURATION_IN_BLOCKS * 2) + 1,
+                    );
+
+                    // Only two requests fit in the given weight
+                    let weight_for_two_requests = <Test as frame_system::Config>::DbWeight::get()
+                        .reads_writes(1, 1)
+                        .saturating_add(StorageProviders::sign_up_request_sweep_weight() * 2);
+                    assert_eq!(
+                        StorageProviders::do_sweep_expired_sign_up_requests(
+                            weight_for_two_requests
+                        ),
+                        weight_for_two_requests
+                    );
+                    let pending_requests = users
+                        .iter()
+                        .filter(|who| StorageProviders::get_sign_up_request(who).is_ok())
+                        .count();
+                    assert_eq!(pending_requests, 1);
+
+                    // Not enough weight to check any request
+                    assert_eq!(
+                        StorageProviders::do_sweep_expired_sign_up_requests(
+                            StorageProviders::sign_up_request_sweep_weight()
+                        ),
+                        Weight::zero()
+                    );
+
+                    // The sweep resumes from where it stopped
+                    StorageProviders::do_sweep_expired_sign_up_requests(weight_for_two_requests);
+                    for who in users {
+                        assert!(StorageProviders::get_sign_up_request(&who).is_err());
+                    }
+                    assert!(crate::SignUpRequestsSweepCursor::<Test>::get().is_none());
+                });
+            }
+
+            #[test]
+            fn sign_up_randomness_ready_reports_when_confirm_sign_up_can_succeed() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let mut multiaddresses: BoundedVec<
+                        MultiAddress<Test>,
+                        MaxMultiAddressAmount<Test>,
+                    > = BoundedVec::new();
+                    multiaddresses.force_push(
+                        "/ip4/127.0.0.1/udp/1234"
+                            .as_bytes()
+                            .to_vec()
+                            .try_into()
+                            .unwrap(),
+                    );
+                    let alice: AccountId = 0;
+
+                    // There is no pending sign up request for Alice
+                    assert_eq!(StorageProviders::sign_up_randomness_ready(&alice), None);
+
+                    // Request sign up of Alice as a Backup Storage Provider
+                    assert_ok!(StorageProviders::request_bsp_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        100,
+                        multiaddresses
+                    ));
+
+                    // The randomness is not valid yet
+                    assert_eq!(
+                        StorageProviders::sign_up_randomness_ready(&alice),
+                        Some(false)
+                    );
+
+                    // Advance enough blocks for randomness to be valid
+                    run_to_block(
+                        frame_system::Pallet::<Test>::block_number()
+                            + BLOCKS_BEFORE_RANDOMNESS_VALID,
+                    );
+                    assert_eq!(
+                        StorageProviders::sign_up_randomness_ready(&alice),
+                        Some(true)
+                    );
+
+                    // Confirming the sign up succeeds, after which there is no pending request anymore
+                    assert_ok!(StorageProviders::confirm_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        Some(alice)
+                    ));
+                    assert_eq!(StorageProviders::sign_up_randomness_ready(&alice), None);
+                });
+            }
+
+            #[test]
+            fn msp_and_bsp_request_sign_up_works_with_multiple_valid_multiaddresses() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let multiaddresses: BoundedVec<
+                        MultiAddress<Test>,
+                        MaxMultiAddressAmount<Test>,
+                    > = multiaddresses_from(&[
+                        "/ip4/127.0.0.1/udp/1234",
+                        "/ip6/::1/tcp/30333/ws",
+                        "/ip4/10.0.0.1/udp/30333/quic-v1",
+                        "/dns4/sp.example.com/tcp/30333/p2p/12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp",
+                        "/dnsaddr/bootstrap.example.com",
+                    ]);
+                    let value_prop: ValueProposition<Test> = ValueProposition {
+                        identifier: ValuePropId::<Test>::default(),
+                        data_limit: 10,
+                        protocols: BoundedVec::new(),
+                    };
+                    let storage_amount: StorageData<Test> = 100;
+
+                    // Get the Account Id of Alice and Bob
+                    let alice: AccountId = 0;
+                    let bob: AccountId = 1;
+
+                    // Request sign up of Alice as a Main Storage Provider
+                    assert_ok!(StorageProviders::request_msp_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        storage_amount,
+                        multiaddresses.clone(),
+                        value_prop.clone()
+                    ));
+
+                    // Request sign up of Bob as a Backup Storage Provider
+                    assert_ok!(StorageProviders::request_bsp_sign_up(
+                        RuntimeOrigin::signed(bob),
+                        storage_amount,
+                        multiaddresses.clone(),
+                    ));
+                });
+            }
         }
     }
 
@@ -1693,6 +2010,12 @@ mod sign_up {
                             - 1,
                     );
 
+                    // Check that the randomness is reported as not ready
+                    assert_eq!(
+                        StorageProviders::sign_up_randomness_ready(&alice),
+                        Some(false)
+                    );
+
                     // Try to confirm the sign up of the account as a Main Storage Provider
                     assert_noop!(
                         StorageProviders::confirm_sign_up(
@@ -1708,6 +2031,55 @@ mod sign_up {
                 });
             }
 
+            #[test]
+            fn msp_confirm_sign_up_fails_if_randomness_is_not_available() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let multiaddresses = multiaddresses_from(&["/ip4/127.0.0.1/udp/1234"]);
+                    let value_prop: ValueProposition<Test> = ValueProposition {
+                        identifier: ValuePropId::<Test>::default(),
+                        data_limit: 10,
+                        protocols: BoundedVec::new(),
+                    };
+                    let storage_amount: StorageData<Test> = 100;
+
+                    // Get the Account Id of Alice
+                    let alice: AccountId = 0;
+
+                    // Request sign up of Alice as a Main Storage Provider
+                    assert_ok!(StorageProviders::request_msp_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        storage_amount,
+                        multiaddresses,
+                        value_prop
+                    ));
+
+                    // Advance enough blocks for randomness to be valid, but make the chain have no randomness at all
+                    run_to_block(
+                        frame_system::Pallet::<Test>::block_number()
+                            + BLOCKS_BEFORE_RANDOMNESS_VALID
+                            + 1,
+                    );
+                    RandomnessAvailable::set(false);
+
+                    // Try to confirm the sign up of the account as a Main Storage Provider
+                    assert_noop!(
+                        StorageProviders::confirm_sign_up(
+                            RuntimeOrigin::signed(alice),
+                            Some(alice)
+                        ),
+                        Error::<Test>::RandomnessNotAvailable
+                    );
+
+                    // Once randomness is available, the sign up can be confirmed
+                    RandomnessAvailable::set(true);
+                    assert_ok!(StorageProviders::confirm_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        Some(alice)
+                    ));
+                });
+            }
+
             #[test]
             fn msp_confirm_sign_up_fails_if_randomness_is_too_old() {
                 ExtBuilder::build().execute_with(|| {
@@ -1962,10 +2334,34 @@ mod sign_up {
             use super::*;
 
             #[test]
-            fn bsp_confirm_sign_up_fails_if_randomness_request_is_too_recent() {
+            fn bsp_confirm_sign_up_for_fails_if_account_did_not_request_sign_up() {
                 ExtBuilder::build().execute_with(|| {
-                    // Initialize variables:
-                    let mut multiaddresses: BoundedVec<
+                    // Get the Account Id of Alice and Bob
+                    let alice: AccountId = 0;
+                    let bob: AccountId = 1;
+
+                    // Advance enough blocks for randomness to be valid
+                    run_to_block(
+                        frame_system::Pallet::<Test>::block_number()
+                            + BLOCKS_BEFORE_RANDOMNESS_VALID,
+                    );
+
+                    // Bob tries to confirm the sign up of Alice, who never requested to sign up
+                    assert_noop!(
+                        StorageProviders::confirm_sign_up_for(RuntimeOrigin::signed(bob), alice),
+                        Error::<Test>::SignUpNotRequested
+                    );
+
+                    // Check that Alice is still NOT a Storage Provider
+                    assert!(StorageProviders::get_provider(alice).is_none());
+                });
+            }
+
+            #[test]
+            fn bsp_confirm_sign_up_fails_if_randomness_request_is_too_recent() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let mut multiaddresses: BoundedVec<
                         MultiAddress<Test>,
                         MaxMultiAddressAmount<Test>,
                     > = BoundedVec::new();
@@ -2014,6 +2410,42 @@ mod sign_up {
                 });
             }
 
+            #[test]
+            fn bsp_confirm_sign_up_fails_if_randomness_is_not_available() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let multiaddresses = multiaddresses_from(&["/ip4/127.0.0.1/udp/1234"]);
+                    let storage_amount: StorageData<Test> = 100;
+
+                    // Get the Account Id of Alice
+                    let alice: AccountId = 0;
+
+                    // Request sign up of Alice as a Backup Storage Provider
+                    assert_ok!(StorageProviders::request_bsp_sign_up(
+                        RuntimeOrigin::signed(alice),
+                        storage_amount,
+                        multiaddresses,
+                    ));
+
+                    // Advance enough blocks for randomness to be valid, but make the chain have no randomness at all
+                    run_to_block(
+                        frame_system::Pallet::<Test>::block_number()
+                            + BLOCKS_BEFORE_RANDOMNESS_VALID
+                            + 1,
+                    );
+                    RandomnessAvailable::set(false);
+
+                    // Try to confirm the sign up of the account as a Backup Storage Provider
+                    assert_noop!(
+                        StorageProviders::confirm_sign_up(
+                            RuntimeOrigin::signed(alice),
+                            Some(alice)
+                        ),
+                        Error::<Test>::RandomnessNotAvailable
+                    );
+                });
+            }
+
             #[test]
             fn bsp_confirm_sign_up_fails_if_randomness_is_too_old() {
                 ExtBuilder::build().execute_with(|| {
@@ -2579,18 +3011,87 @@ mod sign_up {
                 });
             }
 
-            // TODO: Test invalid multiaddresses (after developing the multiaddress checking logic)
-            /* #[test]
+            #[test]
             fn msp_and_bsp_sign_up_fails_when_passing_an_invalid_multiaddress() {
                 ExtBuilder::build().execute_with(|| {
                     // Initialize variables:
-                    let mut multiaddresses: BoundedVec<MultiAddress<Test>, MaxMultiAddressAmount<Test>> =
-                        BoundedVec::new();
-                    let valid_multiaddress: Multiaddr = "/ip4/127.0.0.1/udp/1234".parse().unwrap();
-                    let invalid_multiaddress = "/ip4/127.0.0.1/udp/1234".as_bytes().to_vec();
-                    multiaddresses.force_push(valid_multiaddress.to_vec().try_into().unwrap());
-                    multiaddresses.force_push(invalid_multiaddress.try_into().unwrap());
+                    let value_prop: ValueProposition<Test> = ValueProposition {
+                        identifier: ValuePropId::<Test>::default(),
+                        data_limit: 10,
+                        protocols: BoundedVec::new(),
+                    };
+                    let storage_amount: StorageData<Test> = 100;
+                    let invalid_multiaddresses: Vec<&[u8]> = vec![
+                        // Missing the leading slash
+                        b"ip4/127.0.0.1/udp/1234",
+                        // Empty components
+                        b"/",
+                        b"/ip4/127.0.0.1//udp/1234",
+                        b"/ip4/127.0.0.1/udp/1234/",
+                        // Missing values
+                        b"/ip4/127.0.0.1/udp",
+                        b"/ip4",
+                        // Malformed values
+                        b"/ip4/256.0.0.1/udp/1234",
+                        b"/ip6/::g/tcp/30333",
+                        b"/ip4/127.0.0.1/udp/65536",
+                        b"/ip4/127.0.0.1/tcp/+1234",
+                        b"/dns4/-sp.example.com/tcp/30333",
+                        b"/dns4/sp..example.com/tcp/30333",
+                        // Unknown protocol
+                        b"/ip4/127.0.0.1/carrier-pigeon/1234",
+                        // Not UTF-8
+                        &[0xff, 0xfe, 0xfd],
+                    ];
+
+                    // Get the Account Id of Alice
+                    let alice: AccountId = 0;
+
+                    for invalid_multiaddress in invalid_multiaddresses {
+                        // Pass the invalid multiaddress along with a valid one
+                        let mut multiaddresses: BoundedVec<
+                            MultiAddress<Test>,
+                            MaxMultiAddressAmount<Test>,
+                        > = multiaddresses_from(&["/ip4/127.0.0.1/udp/1234"]);
+                        multiaddresses
+                            .force_push(invalid_multiaddress.to_vec().try_into().unwrap());
+
+                        // Try to sign up Alice as a Main Storage Provider with an invalid multiaddress
+                        assert_noop!(
+                            StorageProviders::request_msp_sign_up(
+                                RuntimeOrigin::signed(alice),
+                                storage_amount,
+                                multiaddresses.clone(),
+                                value_prop.clone()
+                            ),
+                            Error::<Test>::InvalidMultiAddress
+                        );
+
+                        // Try to sign up Alice as a Backup Storage Provider with an invalid multiaddress
+                        assert_noop!(
+                            StorageProviders::request_bsp_sign_up(
+                                RuntimeOrigin::signed(alice),
+                                storage_amount,
+                                multiaddresses.clone(),
+                            ),
+                            Error::<Test>::InvalidMultiAddress
+                        );
+                    }
+                });
+            }
 
+            #[test]
+            fn msp_and_bsp_sign_up_fails_when_passing_duplicated_multiaddresses() {
+                ExtBuilder::build().execute_with(|| {
+                    // Initialize variables:
+                    let multiaddresses: BoundedVec<
+                        MultiAddress<Test>,
+                        MaxMultiAddressAmount<Test>,
+                    > = multiaddresses_from(&[
+                        "/ip4/127.0.0.1/udp/1234",
+                        "/ip6/::1/tcp/30333",
+                        "/ip4/127.0.0.1/udp/1234",
+                    ]);
                     let value_prop: ValueProposition<Test> = ValueProposition {
                         identifier: ValuePropId::<Test>::default(),
                         data_limit: 10,
@@ -2601,9 +3102,9 @@ mod sign_up {
                     // Get the Account Id of Alice
                     let alice: AccountId = 0;
 
-                    // Try to sign up Alice as a Main Storage Provider with an invalid multiaddress
+                    // Try to sign up Alice as a Main Storage Provider with a repeated multiaddress
                     assert_noop!(
-                        StorageProviders::msp_sign_up(
+                        StorageProviders::request_msp_sign_up(
                             RuntimeOrigin::signed(alice),
                             storage_amount,
                             multiaddresses.clone(),
@@ -2612,9 +3113,9 @@ mod sign_up {
                         Error::<Test>::InvalidMultiAddress
                     );
 
-                    // Try to sign up Alice as a Backup Storage Provider with an invalid multiaddress
+                    // Try to sign up Alice as a Backup Storage Provider with a repeated multiaddress
                     assert_noop!(
-                        StorageProviders::bsp_sign_up(
+                        StorageProviders::request_bsp_sign_up(
                             RuntimeOrigin::signed(alice),
                             storage_amount,
                             multiaddresses.clone(),
@@ -2622,7 +3123,7 @@ mod sign_up {
                         Error::<Test>::InvalidMultiAddress
                     );
                 });
-            } */
+            }
         }
     }
 }
@@ -2662,8 +3163,28 @@ mod sign_off {
                     // Check the counter of registered MSPs
                     assert_eq!(StorageProviders::get_msp_count(), 1);
 
-                    // Sign off Alice as a Main Storage Provider
-                    assert_ok!(StorageProviders::msp_sign_off(RuntimeOrigin::signed(alice)));
+                    // Request to sign off Alice as a Main Storage Provider
+                    assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                        alice
+                    )));
+
+                    // Check the Sign Off Requested event was emitted
+                    let finalizable_at = System::block_number() + SignOffCooldown::get();
+                    System::assert_has_event(
+                        Event::<Test>::SignOffRequested {
+                            who: alice,
+                            finalizable_at,
+                        }
+                        .into(),
+                    );
+
+                    // Advance enough blocks for the sign off cooldown to elapse
+                    run_to_block(finalizable_at);
+
+                    // Finalize the sign off of Alice as a Main Storage Provider
+                    assert_ok!(StorageProviders::finalize_sign_off(RuntimeOrigin::signed(
+                        alice
+                    )));
 
                     // Check the new free and held balance of Alice
                     assert_eq!(NativeBalance::free_balance(&alice), 5_000_000);
@@ -2716,8 +3237,25 @@ mod sign_off {
                     // Check the counter of registered BSPs
                     assert_eq!(StorageProviders::get_bsp_count(), 1);
 
-                    // Sign off Alice as a Backup Storage Provider
-                    assert_ok!(StorageProviders::bsp_sign_off(RuntimeOrigin::signed(alice)));
+                    // Request to sign off Alice as a Backup Storage Provider
+                    assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                        alice
+                    )));
+
+                    // Check that Alice no longer counts as a BSP but its deposit is still held
+                    assert_eq!(StorageProviders::get_bsp_count(), 0);
+                    assert_eq!(
+                        NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice),
+                        deposit_amount
+                    );
+
+                    // Advance enough blocks for the sign off cooldown to elapse
+                    run_to_block(System::block_number() + SignOffCooldown::get());
+
+                    // Finalize the sign off of Alice as a Backup Storage Provider
+                    assert_ok!(StorageProviders::finalize_sign_off(RuntimeOrigin::signed(
+                        alice
+                    )));
 
                     // Check the new capacity of all BSPs
                     assert_eq!(StorageProviders::get_total_bsp_capacity(), 0);
@@ -2733,7 +3271,7 @@ mod sign_off {
                     let alice_sp_id = StorageProviders::get_provider(alice);
                     assert!(alice_sp_id.is_none());
 
-                    // Check that the counter of registered BSPs has decreased
+                    // Check that the counter of registered BSPs has not changed since the request
                     assert_eq!(StorageProviders::get_bsp_count(), 0);
 
                     // Check the BSP Sign Off event was emitted
@@ -2742,6 +3280,118 @@ mod sign_off {
                     );
                 });
             }
+
+            #[test]
+            fn bsp_sign_off_works_when_part_of_its_deposit_was_slashed() {
+                ExtBuilder::build().execute_with(|| {
+                    // Register Alice as BSP:
+                    let alice: AccountId = 0;
+                    let (deposit_amount, _alice_bsp) = register_account_as_bsp(alice, 100);
+                    let alice_bsp_id = StorageProviders::get_provider(alice).unwrap();
+
+                    // Reduce the deposit held from Alice by slashing part of it
+                    let amount_to_slash: BalanceOf<Test> = 5;
+                    assert_ok!(StorageProviders::slash(&alice_bsp_id, amount_to_slash));
+
+                    // Check that the deposit expected to be returned is still the one held at sign up
+                    assert_eq!(ProviderDeposits::<Test>::get(alice), deposit_amount);
+
+                    // Request to sign off Alice and finalize it once the cooldown elapses
+                    assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                        alice
+                    )));
+                    run_to_block(System::block_number() + SignOffCooldown::get());
+                    assert_ok!(StorageProviders::finalize_sign_off(RuntimeOrigin::signed(
+                        alice
+                    )));
+
+                    // Check that what was left of the deposit was returned to Alice
+                    assert_eq!(
+                        NativeBalance::free_balance(&alice),
+                        5_000_000 - amount_to_slash
+                    );
+                    assert_eq!(
+                        NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice),
+                        0
+                    );
+
+                    // Check that Alice is not a Backup Storage Provider anymore
+                    assert!(StorageProviders::get_provider(alice).is_none());
+                    assert_eq!(ProviderDeposits::<Test>::get(alice), 0);
+
+                    // Check that the events were emitted
+                    System::assert_has_event(
+                        Event::<Test>::PartialDepositReturned {
+                            who: alice,
+                            returned: deposit_amount - amount_to_slash,
+                            expected: deposit_amount,
+                        }
+                        .into(),
+                    );
+                    System::assert_has_event(
+                        Event::<Test>::BspSignOffSuccess { who: alice }.into(),
+                    );
+                });
+            }
+        }
+
+        #[test]
+        fn finalize_sign_off_works_exactly_when_cooldown_elapses() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as BSP and request to sign off
+                let alice: AccountId = 0;
+                let (deposit_amount, _alice_bsp) = register_account_as_bsp(alice, 100);
+                assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                    alice
+                )));
+                let finalizable_at = System::block_number() + SignOffCooldown::get();
+
+                // One block before the cooldown elapses, the sign off can not be finalized
+                run_to_block(finalizable_at - 1);
+                assert_noop!(
+                    StorageProviders::finalize_sign_off(RuntimeOrigin::signed(alice)),
+                    Error::<Test>::SignOffCooldownNotElapsed
+                );
+                assert_eq!(
+                    NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice),
+                    deposit_amount
+                );
+
+                // Once the cooldown elapses, it can
+                run_to_block(finalizable_at);
+                assert_ok!(StorageProviders::finalize_sign_off(RuntimeOrigin::signed(
+                    alice
+                )));
+                assert_eq!(
+                    NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice),
+                    0
+                );
+            });
+        }
+
+        #[test]
+        fn provider_is_signing_off_until_sign_off_is_finalized() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as BSP, which is not signing off yet
+                let alice: AccountId = 0;
+                register_account_as_bsp(alice, 100);
+                let alice_bsp = StorageProviders::get_provider(alice).unwrap();
+                assert!(!StorageProviders::is_signing_off(&alice_bsp));
+
+                // Alice is signing off during the whole cooldown
+                assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                    alice
+                )));
+                assert!(StorageProviders::is_signing_off(&alice_bsp));
+                run_to_block(System::block_number() + SignOffCooldown::get());
+                assert!(StorageProviders::is_signing_off(&alice_bsp));
+
+                // Once the sign off is finalized, Alice is not a provider anymore
+                assert_ok!(StorageProviders::finalize_sign_off(RuntimeOrigin::signed(
+                    alice
+                )));
+                assert!(!StorageProviders::is_signing_off(&alice_bsp));
+            });
         }
     }
 
@@ -2759,9 +3409,9 @@ mod sign_off {
                     // Get the Account Id of Alice
                     let alice: AccountId = 0;
 
-                    // Try to sign off Alice as a Main Storage Provider
+                    // Try to request to sign off Alice as a Main Storage Provider
                     assert_noop!(
-                        StorageProviders::msp_sign_off(RuntimeOrigin::signed(alice)),
+                        StorageProviders::request_sign_off(RuntimeOrigin::signed(alice)),
                         Error::<Test>::NotRegistered
                     );
                 });
@@ -2803,9 +3453,9 @@ mod sign_off {
                         )
                     );
 
-                    // Try to sign off Alice as a Main Storage Provider
+                    // Try to request to sign off Alice as a Main Storage Provider
                     assert_noop!(
-                        StorageProviders::msp_sign_off(RuntimeOrigin::signed(alice)),
+                        StorageProviders::request_sign_off(RuntimeOrigin::signed(alice)),
                         Error::<Test>::StorageStillInUse
                     );
 
@@ -2830,9 +3480,9 @@ mod sign_off {
                     // Get the Account Id of Alice
                     let alice: AccountId = 0;
 
-                    // Try to sign off Alice as a Backup Storage Provider
+                    // Try to request to sign off Alice as a Backup Storage Provider
                     assert_noop!(
-                        StorageProviders::bsp_sign_off(RuntimeOrigin::signed(alice)),
+                        StorageProviders::request_sign_off(RuntimeOrigin::signed(alice)),
                         Error::<Test>::NotRegistered
                     );
                 });
@@ -2877,9 +3527,9 @@ mod sign_off {
                         )
                     );
 
-                    // Try to sign off Alice as a Backup Storage Provider
+                    // Try to request to sign off Alice as a Backup Storage Provider
                     assert_noop!(
-                        StorageProviders::bsp_sign_off(RuntimeOrigin::signed(alice)),
+                        StorageProviders::request_sign_off(RuntimeOrigin::signed(alice)),
                         Error::<Test>::StorageStillInUse
                     );
 
@@ -2896,6 +3546,57 @@ mod sign_off {
                 });
             }
         }
+
+        #[test]
+        fn finalize_sign_off_fails_if_not_requested() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as BSP
+                let alice: AccountId = 0;
+                register_account_as_bsp(alice, 100);
+
+                // Try to finalize the sign off without requesting it first
+                assert_noop!(
+                    StorageProviders::finalize_sign_off(RuntimeOrigin::signed(alice)),
+                    Error::<Test>::SignOffNotRequested
+                );
+            });
+        }
+
+        #[test]
+        fn request_sign_off_fails_when_already_requested() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as MSP and request to sign off
+                let alice: AccountId = 0;
+                register_account_as_msp(alice, 100);
+                assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                    alice
+                )));
+
+                // Try to request to sign off again
+                assert_noop!(
+                    StorageProviders::request_sign_off(RuntimeOrigin::signed(alice)),
+                    Error::<Test>::SignOffAlreadyRequested
+                );
+            });
+        }
+
+        #[test]
+        fn sp_can_not_get_new_storage_while_signing_off() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as BSP and request to sign off
+                let alice: AccountId = 0;
+                register_account_as_bsp(alice, 100);
+                assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                    alice
+                )));
+
+                // Try to add used storage to Alice during the cooldown
+                assert_noop!(
+                    <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 10),
+                    Error::<Test>::SignOffPending
+                );
+            });
+        }
     }
 }
 
@@ -2962,6 +3663,12 @@ mod change_capacity {
                         deposit_for_increased_storage
                     );
 
+                    // Check that the new deposit is the one expected to be returned to Alice
+                    assert_eq!(
+                        ProviderDeposits::<Test>::get(alice),
+                        deposit_for_increased_storage
+                    );
+
                     // Check that the capacity changed event was emitted
                     System::assert_has_event(
                         Event::<Test>::CapacityChanged {
@@ -3849,7 +4556,1747 @@ mod change_capacity {
     }
 }
 
-// Helper functions for testing:
+/// This module holds the test cases for the enumeration of the buckets of Main Storage Providers and users
+mod buckets {
+
+    use super::*;
+
+    #[test]
+    fn buckets_can_be_enumerated_by_msp_and_user() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as Main Storage Providers
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+
+            // Add two buckets of Charlie to Alice and one bucket of Dave to Bob
+            let charlie: AccountId = 2;
+            let dave: AccountId = 3;
+            let first_bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"first",
+                Default::default(),
+                0,
+            )
+            .unwrap();
+            let second_bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"second",
+                Default::default(),
+                0,
+            )
+            .unwrap();
+            let third_bucket_id =
+                StorageProviders::add_bucket(bob_msp_id, dave, b"first", Default::default(), 0)
+                    .unwrap();
+
+            // Check that the buckets are listed under their MSP and user
+            let mut alice_buckets = StorageProviders::buckets_of_msp(&alice_msp_id);
+            alice_buckets.sort();
+            let mut expected_buckets = vec![first_bucket_id, second_bucket_id];
+            expected_buckets.sort();
+            assert_eq!(alice_buckets, expected_buckets);
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&bob_msp_id),
+                vec![third_bucket_id]
+            );
+            let mut charlie_buckets = StorageProviders::buckets_of_user(&charlie);
+            charlie_buckets.sort();
+            assert_eq!(charlie_buckets, expected_buckets);
+            assert_eq!(
+                StorageProviders::buckets_of_user(&dave),
+                vec![third_bucket_id]
+            );
+
+            // Changing the root of a bucket does not change who it belongs to
+            let new_root = sp_core::H256::from_low_u64_be(1234);
+            assert_ok!(StorageProviders::change_root_bucket(
+                bob_msp_id,
+                third_bucket_id,
+                new_root,
+                &root_transition_proof(Default::default(), new_root)
+            ));
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&bob_msp_id),
+                vec![third_bucket_id]
+            );
+            assert_eq!(
+                StorageProviders::buckets_of_user(&dave),
+                vec![third_bucket_id]
+            );
+
+            // Removing a bucket removes it from the buckets of its MSP and user
+            assert_ok!(StorageProviders::remove_root_bucket(first_bucket_id));
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&alice_msp_id),
+                vec![second_bucket_id]
+            );
+            assert_eq!(
+                StorageProviders::buckets_of_user(&charlie),
+                vec![second_bucket_id]
+            );
+        });
+    }
+
+    #[test]
+    fn change_root_bucket_works_with_a_valid_transition() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider and add a bucket of Charlie to her
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let charlie: AccountId = 2;
+            let old_root = sp_core::H256::from_low_u64_be(1);
+            let bucket_id =
+                StorageProviders::add_bucket(alice_msp_id, charlie, b"bucket", old_root, 0)
+                    .unwrap();
+
+            // Change the root of the bucket with a proof of the transition
+            let new_root = sp_core::H256::from_low_u64_be(2);
+            assert_ok!(StorageProviders::change_root_bucket(
+                alice_msp_id,
+                bucket_id,
+                new_root,
+                &root_transition_proof(old_root, new_root)
+            ));
+            assert_eq!(
+                crate::Buckets::<Test>::get(&bucket_id).unwrap().root,
+                new_root
+            );
+        });
+    }
+
+    #[test]
+    fn change_root_bucket_fails_with_a_forged_root() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider and add a bucket of Charlie to her
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let charlie: AccountId = 2;
+            let old_root = sp_core::H256::from_low_u64_be(1);
+            let bucket_id =
+                StorageProviders::add_bucket(alice_msp_id, charlie, b"bucket", old_root, 0)
+                    .unwrap();
+
+            // The proof is for a transition to another root than the one submitted
+            let new_root = sp_core::H256::from_low_u64_be(2);
+            let forged_root = sp_core::H256::from_low_u64_be(3);
+            assert_noop!(
+                StorageProviders::change_root_bucket(
+                    alice_msp_id,
+                    bucket_id,
+                    forged_root,
+                    &root_transition_proof(old_root, new_root)
+                ),
+                Error::<Test>::InvalidRootTransition
+            );
+
+            // The proof is for a transition from another root than the current one
+            assert_noop!(
+                StorageProviders::change_root_bucket(
+                    alice_msp_id,
+                    bucket_id,
+                    new_root,
+                    &root_transition_proof(forged_root, new_root)
+                ),
+                Error::<Test>::InvalidRootTransition
+            );
+
+            assert_eq!(
+                crate::Buckets::<Test>::get(&bucket_id).unwrap().root,
+                old_root
+            );
+        });
+    }
+
+    #[test]
+    fn change_root_bucket_fails_when_not_the_bucket_msp() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as Main Storage Providers and add a bucket of Charlie to Alice
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+            let charlie: AccountId = 2;
+            let old_root = sp_core::H256::from_low_u64_be(1);
+            let bucket_id =
+                StorageProviders::add_bucket(alice_msp_id, charlie, b"bucket", old_root, 0)
+                    .unwrap();
+
+            // Bob can not change the root of a bucket stored by Alice, even with a valid transition
+            let new_root = sp_core::H256::from_low_u64_be(2);
+            assert_noop!(
+                StorageProviders::change_root_bucket(
+                    bob_msp_id,
+                    bucket_id,
+                    new_root,
+                    &root_transition_proof(old_root, new_root)
+                ),
+                Error::<Test>::NotBucketMsp
+            );
+        });
+    }
+
+    #[test]
+    fn bucket_id_is_derived_from_msp_user_and_salt() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+            // Add a bucket of Charlie to Alice
+            let charlie: AccountId = 2;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                0,
+            )
+            .unwrap();
+            assert_eq!(
+                bucket_id,
+                StorageProviders::derive_bucket_id(&alice_msp_id, &charlie, b"bucket")
+            );
+
+            // A different salt allows Charlie to own another bucket under Alice
+            assert_ok!(StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"another bucket",
+                Default::default(),
+                0
+            ));
+
+            // Reusing the same salt derives the same id, which is rejected
+            assert_noop!(
+                StorageProviders::add_bucket(
+                    alice_msp_id,
+                    charlie,
+                    b"bucket",
+                    Default::default(),
+                    0
+                ),
+                Error::<Test>::BucketAlreadyExists
+            );
+        });
+    }
+
+    #[test]
+    fn add_file_to_bucket_keeps_many_files_in_one_bucket() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+            // Add a file of Charlie to Alice, which creates a bucket with an empty root and applies the transition
+            let charlie: AccountId = 2;
+            let first_root = sp_core::H256::from_low_u64_be(1);
+            let bucket_id = StorageProviders::add_file_to_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                10,
+                &root_transition_proof(Default::default(), first_root),
+            )
+            .unwrap();
+            assert_eq!(
+                bucket_id,
+                StorageProviders::derive_bucket_id(&alice_msp_id, &charlie, b"bucket")
+            );
+            let bucket = crate::Buckets::<Test>::get(&bucket_id).unwrap();
+            assert_eq!(bucket.root, first_root);
+            assert_eq!(bucket.size, 10);
+
+            // Adding another file with the same salt adds it to the same bucket, transitioning from its current root
+            let second_root = sp_core::H256::from_low_u64_be(2);
+            assert_eq!(
+                StorageProviders::add_file_to_bucket(
+                    alice_msp_id,
+                    charlie,
+                    b"bucket",
+                    20,
+                    &root_transition_proof(first_root, second_root),
+                ),
+                Ok(bucket_id)
+            );
+            let bucket = crate::Buckets::<Test>::get(&bucket_id).unwrap();
+            assert_eq!(bucket.root, second_root);
+            assert_eq!(bucket.size, 30);
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&alice_msp_id),
+                vec![bucket_id]
+            );
+            assert_eq!(StorageProviders::buckets_of_user(&charlie), vec![bucket_id]);
+        });
+    }
+
+    #[test]
+    fn add_file_to_bucket_fails_with_a_transition_from_another_root() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider and add a file of Charlie to her
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let charlie: AccountId = 2;
+            let root = sp_core::H256::from_low_u64_be(1);
+            let bucket_id = StorageProviders::add_file_to_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                10,
+                &root_transition_proof(Default::default(), root),
+            )
+            .unwrap();
+
+            // The transition has to start from the current root of the bucket
+            assert_noop!(
+                StorageProviders::add_file_to_bucket(
+                    alice_msp_id,
+                    charlie,
+                    b"bucket",
+                    10,
+                    &root_transition_proof(Default::default(), sp_core::H256::from_low_u64_be(2)),
+                ),
+                Error::<Test>::InvalidRootTransition
+            );
+            assert_eq!(crate::Buckets::<Test>::get(&bucket_id).unwrap().root, root);
+        });
+    }
+
+    #[test]
+    fn msp_sign_off_fails_when_it_still_has_buckets() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider and add a bucket to it
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bucket_id =
+                StorageProviders::add_bucket(alice_msp_id, 2, b"bucket", Default::default(), 0)
+                    .unwrap();
+
+            // Alice can not request to sign off while it holds a bucket
+            assert_noop!(
+                StorageProviders::request_sign_off(RuntimeOrigin::signed(alice)),
+                Error::<Test>::StorageStillInUse
+            );
+
+            // Once the bucket is removed, Alice can request to sign off
+            assert_ok!(StorageProviders::remove_root_bucket(bucket_id));
+            assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                alice
+            )));
+        });
+    }
+
+    #[test]
+    fn transfer_bucket_moves_the_bucket_and_its_data_to_the_new_msp() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as Main Storage Providers
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+
+            // Add a bucket of Charlie to Alice, storing its data
+            let charlie: AccountId = 2;
+            let bucket_size: StorageData<Test> = 30;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                bucket_size,
+            )
+            .unwrap();
+            assert_ok!(StorageProviders::increase_data_used(&alice, bucket_size));
+
+            // Charlie requests to transfer its bucket to Bob
+            assert_ok!(StorageProviders::transfer_bucket(
+                RuntimeOrigin::signed(charlie),
+                bucket_id,
+                bob_msp_id
+            ));
+            System::assert_last_event(
+                Event::<Test>::BucketTransferRequested {
+                    bucket_id,
+                    new_msp_id: bob_msp_id,
+                }
+                .into(),
+            );
+
+            // The bucket is not moved until Bob accepts the transfer
+            assert_eq!(
+                crate::Buckets::<Test>::get(&bucket_id).unwrap().msp_id,
+                alice_msp_id
+            );
+            assert_eq!(
+                StorageProviders::get_used_storage_of_msp(&bob_msp_id),
+                Ok(0)
+            );
+            assert_ok!(StorageProviders::accept_bucket_transfer(
+                RuntimeOrigin::signed(bob),
+                bucket_id
+            ));
+            assert!(crate::PendingBucketTransfers::<Test>::get(&bucket_id).is_none());
+
+            // Check that the bucket is now stored by Bob, keeping its id
+            assert_eq!(
+                crate::Buckets::<Test>::get(&bucket_id).unwrap().msp_id,
+                bob_msp_id
+            );
+            assert!(StorageProviders::buckets_of_msp(&alice_msp_id).is_empty());
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&bob_msp_id),
+                vec![bucket_id]
+            );
+            assert_eq!(StorageProviders::buckets_of_user(&charlie), vec![bucket_id]);
+
+            // Check that the data of the bucket moved from Alice to Bob
+            assert_eq!(
+                StorageProviders::get_used_storage_of_msp(&alice_msp_id),
+                Ok(0)
+            );
+            assert_eq!(
+                StorageProviders::get_used_storage_of_msp(&bob_msp_id),
+                Ok(bucket_size)
+            );
+
+            // Check that the event was emitted
+            System::assert_last_event(
+                Event::<Test>::BucketTransferred {
+                    bucket_id,
+                    old_msp_id: alice_msp_id,
+                    new_msp_id: bob_msp_id,
+                }
+                .into(),
+            );
+        });
+    }
+
+    #[test]
+    fn transfer_bucket_fails_when_the_new_msp_has_not_enough_capacity() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider with enough capacity for the bucket, and Bob without it
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 20);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+
+            // Add a bucket of Charlie to Alice, storing its data
+            let charlie: AccountId = 2;
+            let bucket_size: StorageData<Test> = 30;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                bucket_size,
+            )
+            .unwrap();
+            assert_ok!(StorageProviders::increase_data_used(&alice, bucket_size));
+
+            // Charlie can request the transfer, but the bucket does not fit in Bob's capacity
+            assert_ok!(StorageProviders::transfer_bucket(
+                RuntimeOrigin::signed(charlie),
+                bucket_id,
+                bob_msp_id
+            ));
+            assert_noop!(
+                StorageProviders::accept_bucket_transfer(RuntimeOrigin::signed(bob), bucket_id),
+                Error::<Test>::ExceedsCapacity
+            );
+
+            // Check that the bucket is still stored by Alice
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&alice_msp_id),
+                vec![bucket_id]
+            );
+            assert_eq!(
+                StorageProviders::get_used_storage_of_msp(&alice_msp_id),
+                Ok(bucket_size)
+            );
+        });
+    }
+
+    #[test]
+    fn transfer_bucket_fails_when_not_the_bucket_owner() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as Main Storage Providers, and add a bucket of Charlie to Alice
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+            let charlie: AccountId = 2;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                0,
+            )
+            .unwrap();
+
+            // Dave can not transfer Charlie's bucket
+            let dave: AccountId = 3;
+            assert_noop!(
+                StorageProviders::transfer_bucket(
+                    RuntimeOrigin::signed(dave),
+                    bucket_id,
+                    bob_msp_id
+                ),
+                Error::<Test>::NotBucketOwner
+            );
+
+            // Charlie can not transfer it to the MSP already storing it
+            assert_noop!(
+                StorageProviders::transfer_bucket(
+                    RuntimeOrigin::signed(charlie),
+                    bucket_id,
+                    alice_msp_id
+                ),
+                Error::<Test>::BucketAlreadyInMsp
+            );
+        });
+    }
+
+    #[test]
+    fn accept_bucket_transfer_fails_when_not_requested_to_the_signer() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice, Bob and Dave as Main Storage Providers, and add a bucket of Charlie to Alice
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            let dave: AccountId = 3;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 100);
+            register_account_as_msp(dave, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+            let charlie: AccountId = 2;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                0,
+            )
+            .unwrap();
+
+            // Bob can not accept a transfer that was not requested
+            assert_noop!(
+                StorageProviders::accept_bucket_transfer(RuntimeOrigin::signed(bob), bucket_id),
+                Error::<Test>::BucketTransferNotRequested
+            );
+
+            // Dave can not accept a transfer requested to Bob
+            assert_ok!(StorageProviders::transfer_bucket(
+                RuntimeOrigin::signed(charlie),
+                bucket_id,
+                bob_msp_id
+            ));
+            assert_noop!(
+                StorageProviders::accept_bucket_transfer(RuntimeOrigin::signed(dave), bucket_id),
+                Error::<Test>::NotBucketTransferTarget
+            );
+
+            // Charlie can not accept the transfer either, since it is not a MSP
+            assert_noop!(
+                StorageProviders::accept_bucket_transfer(RuntimeOrigin::signed(charlie), bucket_id),
+                Error::<Test>::NotRegistered
+            );
+        });
+    }
+
+    #[test]
+    fn accept_bucket_transfer_fails_when_the_new_msp_is_signing_off() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as Main Storage Providers, and add a bucket of Charlie to Alice
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_msp(bob, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_msp_id = StorageProviders::get_provider(bob).unwrap();
+            let charlie: AccountId = 2;
+            let bucket_size: StorageData<Test> = 30;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                bucket_size,
+            )
+            .unwrap();
+            assert_ok!(StorageProviders::increase_data_used(&alice, bucket_size));
+
+            // Charlie requests to transfer its bucket to Bob, who then requests to sign off
+            assert_ok!(StorageProviders::transfer_bucket(
+                RuntimeOrigin::signed(charlie),
+                bucket_id,
+                bob_msp_id
+            ));
+            assert_ok!(StorageProviders::request_sign_off(RuntimeOrigin::signed(
+                bob
+            )));
+
+            // Bob can not get the bucket assigned while signing off
+            assert_noop!(
+                StorageProviders::accept_bucket_transfer(RuntimeOrigin::signed(bob), bucket_id),
+                Error::<Test>::SignOffPending
+            );
+            assert_eq!(
+                StorageProviders::buckets_of_msp(&alice_msp_id),
+                vec![bucket_id]
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for changing the root of Backup Storage Providers
+mod change_root_bsp {
+
+    use super::*;
+
+    #[test]
+    fn change_root_bsp_works_with_a_valid_transition() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Bob as a Backup Storage Provider
+            let bob: AccountId = 1;
+            register_account_as_bsp(bob, 100);
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            let old_root = StorageProviders::get_root(bob_bsp_id).unwrap();
+
+            // Change the root of Bob with a proof of the transition, getting back the new root
+            let new_root = sp_core::H256::from_low_u64_be(2);
+            assert_eq!(
+                StorageProviders::change_root_bsp(
+                    bob_bsp_id,
+                    &root_transition_proof(old_root, new_root)
+                ),
+                Ok(new_root)
+            );
+            assert_eq!(StorageProviders::get_root(bob_bsp_id), Some(new_root));
+        });
+    }
+
+    #[test]
+    fn change_root_bsp_fails_with_a_transition_from_another_root() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Bob as a Backup Storage Provider
+            let bob: AccountId = 1;
+            register_account_as_bsp(bob, 100);
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            let old_root = StorageProviders::get_root(bob_bsp_id).unwrap();
+
+            // The proof is for a transition from another root than the current one
+            let forged_root = sp_core::H256::from_low_u64_be(3);
+            let new_root = sp_core::H256::from_low_u64_be(2);
+            assert_noop!(
+                StorageProviders::change_root_bsp(
+                    bob_bsp_id,
+                    &root_transition_proof(forged_root, new_root)
+                ),
+                Error::<Test>::InvalidRootTransition
+            );
+            assert_eq!(StorageProviders::get_root(bob_bsp_id), Some(old_root));
+        });
+    }
+
+    #[test]
+    fn change_root_bsp_fails_when_not_a_bsp() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+            let new_root = sp_core::H256::from_low_u64_be(2);
+            assert_noop!(
+                StorageProviders::change_root_bsp(
+                    alice_msp_id,
+                    &root_transition_proof(Default::default(), new_root)
+                ),
+                Error::<Test>::NotRegistered
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for getting the stake of Storage Providers and buckets
+mod get_stake {
+
+    use super::*;
+
+    #[test]
+    fn bsp_stake_is_its_capacity_driven_deposit() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Bob as a Backup Storage Provider with a custom capacity, which determines its deposit
+            let bob: AccountId = 1;
+            let storage_amount: StorageData<Test> = 350;
+            let (bob_deposit, _bob_bsp) = register_account_as_bsp(bob, storage_amount);
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+
+            // The deposit would be MinDeposit + DepositPerData * (storage_amount - MinCapacity)
+            assert_eq!(
+                bob_deposit,
+                <SpMinDeposit as Get<u128>>::get()
+                    + <DepositPerData as Get<u128>>::get()
+                        * (storage_amount - <SpMinCapacity as Get<u32>>::get()) as u128
+            );
+
+            // Check that the stake of Bob is its deposit
+            assert_eq!(StorageProviders::get_stake(bob_bsp_id), Some(bob_deposit));
+
+            // Check that the stake of Bob follows its deposit after a capacity change
+            run_to_block(
+                frame_system::Pallet::<Test>::block_number()
+                    + MinBlocksBetweenCapacityChanges::get(),
+            );
+            assert_ok!(StorageProviders::change_capacity(
+                RuntimeOrigin::signed(bob),
+                storage_amount * 2
+            ));
+            assert_eq!(
+                StorageProviders::get_stake(bob_bsp_id),
+                Some(NativeBalance::balance_on_hold(
+                    &StorageProvidersHoldReason::get(),
+                    &bob
+                ))
+            );
+            assert!(StorageProviders::get_stake(bob_bsp_id).unwrap() > bob_deposit);
+        });
+    }
+
+    #[test]
+    fn bucket_stake_is_the_stake_of_its_msp() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider
+            let alice: AccountId = 0;
+            let (alice_deposit, _alice_msp) = register_account_as_msp(alice, 200);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+            // Add a bucket of Charlie to Alice
+            let charlie: AccountId = 2;
+            let bucket_id = StorageProviders::add_bucket(
+                alice_msp_id,
+                charlie,
+                b"bucket",
+                Default::default(),
+                0,
+            )
+            .unwrap();
+
+            // Check that the stake of both Alice and the bucket is Alice's deposit
+            assert_eq!(
+                StorageProviders::get_stake(alice_msp_id),
+                Some(alice_deposit)
+            );
+            assert_eq!(StorageProviders::get_stake(bucket_id), Some(alice_deposit));
+        });
+    }
+
+    #[test]
+    fn get_stake_returns_none_when_not_registered() {
+        ExtBuilder::build().execute_with(|| {
+            assert_eq!(
+                StorageProviders::get_stake(sp_core::H256::from_low_u64_be(1234)),
+                None
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for reading the capacity of Main Storage Providers and Backup Storage Providers
+mod get_capacity {
+    use super::*;
+
+    #[test]
+    fn msp_and_bsp_capacity_and_available_capacity_are_readable() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as MSP and Bob as BSP
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_bsp(bob, 200);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+
+            // Add used storage to both of them
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 30)
+            );
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&bob, 50)
+            );
+
+            // Their capacity stays the same while their available capacity decreases
+            assert_eq!(StorageProviders::get_capacity(&alice_msp_id), Some(100));
+            assert_eq!(
+                StorageProviders::get_available_capacity(&alice_msp_id),
+                Some(70)
+            );
+            assert_eq!(StorageProviders::get_capacity(&bob_bsp_id), Some(200));
+            assert_eq!(
+                StorageProviders::get_available_capacity(&bob_bsp_id),
+                Some(150)
+            );
+        });
+    }
+
+    #[test]
+    fn get_capacity_returns_none_when_not_registered() {
+        ExtBuilder::build().execute_with(|| {
+            let unknown_provider = sp_core::H256::from_low_u64_be(1234);
+            assert_eq!(StorageProviders::get_capacity(&unknown_provider), None);
+            assert_eq!(
+                StorageProviders::get_available_capacity(&unknown_provider),
+                None
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for reading the total, used and available capacity of the network
+mod network_capacity {
+    use super::*;
+
+    #[test]
+    fn network_capacity_aggregates_the_bsps() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as BSPs, and Charlie as MSP
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            let charlie: AccountId = 2;
+            register_account_as_bsp(alice, 100);
+            register_account_as_bsp(bob, 200);
+            register_account_as_msp(charlie, 500);
+
+            // Only the capacity of the BSPs counts towards the network capacity
+            assert_eq!(StorageProviders::total_capacity(), 300);
+            assert_eq!(StorageProviders::used_capacity(), 0);
+            assert_eq!(StorageProviders::available_capacity(), 300);
+
+            // Add used storage to all of them
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 30)
+            );
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&bob, 50)
+            );
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&charlie, 70)
+            );
+
+            // The used capacity is the sum of the data used by the BSPs
+            assert_eq!(StorageProviders::total_capacity(), 300);
+            assert_eq!(StorageProviders::used_capacity(), 80);
+            assert_eq!(StorageProviders::available_capacity(), 220);
+
+            // Decreasing the data used of a BSP frees network capacity
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::decrease_data_used(&bob, 20)
+            );
+            assert_eq!(StorageProviders::used_capacity(), 60);
+            assert_eq!(StorageProviders::available_capacity(), 240);
+        });
+    }
+
+    #[test]
+    fn network_used_capacity_is_not_changed_by_failed_or_excessive_updates() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice and Bob as BSPs
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_bsp(alice, 100);
+            register_account_as_bsp(bob, 200);
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 60)
+            );
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&bob, 40)
+            );
+
+            // Going over the capacity of a BSP fails without changing the used capacity
+            assert_noop!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 50),
+                Error::<Test>::ExceedsCapacity
+            );
+            assert_eq!(StorageProviders::used_capacity(), 100);
+
+            // Decreasing the data used of a BSP by more than it uses fails without changing the used capacity
+            assert_noop!(
+                <StorageProviders as MutateProvidersInterface>::decrease_data_used(&alice, 80),
+                DispatchError::Arithmetic(ArithmeticError::Underflow)
+            );
+            assert_eq!(StorageProviders::used_capacity(), 100);
+            assert_eq!(StorageProviders::available_capacity(), 200);
+        });
+    }
+}
+
+/// This module holds the test cases for increasing the data used by Main Storage Providers and Backup Storage Providers
+mod increase_data_used {
+    use super::*;
+
+    #[test]
+    fn increase_data_used_works_when_under_capacity() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as BSP
+            let alice: AccountId = 0;
+            register_account_as_bsp(alice, 100);
+            let alice_bsp_id = StorageProviders::get_provider(alice).unwrap();
+
+            // Increase the data used by Alice to less than its capacity
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 40)
+            );
+            assert_eq!(
+                StorageProviders::get_used_storage_of_bsp(&alice_bsp_id).unwrap(),
+                40
+            );
+        });
+    }
+
+    #[test]
+    fn increase_data_used_works_when_exactly_filling_capacity() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as MSP
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+            // Fill the whole capacity of Alice in two steps
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 60)
+            );
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 40)
+            );
+            assert_eq!(
+                StorageProviders::get_used_storage_of_msp(&alice_msp_id).unwrap(),
+                100
+            );
+        });
+    }
+
+    #[test]
+    fn increase_data_used_fails_when_exceeding_capacity() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as MSP and Bob as BSP
+            let alice: AccountId = 0;
+            let bob: AccountId = 1;
+            register_account_as_msp(alice, 100);
+            register_account_as_bsp(bob, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 60)
+            );
+            assert_ok!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&bob, 60)
+            );
+
+            // Going over the capacity fails for both of them
+            assert_noop!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&alice, 41),
+                Error::<Test>::ExceedsCapacity
+            );
+            assert_noop!(
+                <StorageProviders as MutateProvidersInterface>::increase_data_used(&bob, 41),
+                Error::<Test>::ExceedsCapacity
+            );
+
+            // And their data used is left unchanged
+            assert_eq!(
+                StorageProviders::get_used_storage_of_msp(&alice_msp_id).unwrap(),
+                60
+            );
+            assert_eq!(
+                StorageProviders::get_used_storage_of_bsp(&bob_bsp_id).unwrap(),
+                60
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for slashing Main Storage Providers and Backup Storage Providers
+mod slash {
+
+    use super::*;
+
+    #[test]
+    fn bsp_slash_burns_part_of_its_deposit() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Bob as a Backup Storage Provider
+            let bob: AccountId = 1;
+            let (bob_deposit, _bob_bsp) = register_account_as_bsp(bob, 100);
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            let total_issuance = NativeBalance::total_issuance();
+
+            // Slash Bob
+            let amount_to_slash: BalanceOf<Test> = 5;
+            assert_eq!(
+                StorageProviders::slash(&bob_bsp_id, amount_to_slash),
+                Ok(amount_to_slash)
+            );
+
+            // Check that the slashed amount was burned from Bob's deposit
+            assert_eq!(
+                NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &bob),
+                bob_deposit - amount_to_slash
+            );
+            assert_eq!(
+                NativeBalance::total_issuance(),
+                total_issuance - amount_to_slash
+            );
+
+            // Check that Bob was not marked for removal, since its remaining deposit is still enough
+            assert!(!crate::InsolventProviders::<Test>::contains_key(
+                &bob_bsp_id
+            ));
+
+            // Check that the event was emitted
+            System::assert_last_event(
+                Event::<Test>::ProviderSlashed {
+                    provider_id: bob_bsp_id,
+                    amount_slashed: amount_to_slash,
+                    marked_for_removal: false,
+                }
+                .into(),
+            );
+        });
+    }
+
+    #[test]
+    fn msp_slash_is_capped_at_its_deposit_and_marks_it_for_removal() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider
+            let alice: AccountId = 0;
+            let (alice_deposit, _alice_msp) = register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let free_balance = NativeBalance::free_balance(&alice);
+
+            // Slash Alice for more than its deposit
+            assert_eq!(
+                StorageProviders::slash(&alice_msp_id, alice_deposit + 100),
+                Ok(alice_deposit)
+            );
+
+            // Check that only the deposit was slashed, leaving the free balance of Alice untouched
+            assert_eq!(
+                NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &alice),
+                0
+            );
+            assert_eq!(NativeBalance::free_balance(&alice), free_balance);
+
+            // Check that Alice was marked for removal, since it no longer has the minimum deposit
+            assert!(crate::InsolventProviders::<Test>::contains_key(
+                &alice_msp_id
+            ));
+
+            // Check that the event was emitted
+            System::assert_last_event(
+                Event::<Test>::ProviderSlashed {
+                    provider_id: alice_msp_id,
+                    amount_slashed: alice_deposit,
+                    marked_for_removal: true,
+                }
+                .into(),
+            );
+        });
+    }
+
+    #[test]
+    fn slash_fails_when_not_registered() {
+        ExtBuilder::build().execute_with(|| {
+            let alice: AccountId = 0;
+
+            // Try to slash a Storage Provider that does not exist
+            assert_noop!(
+                StorageProviders::slash(&sp_core::H256::from_low_u64_be(alice), 10),
+                Error::<Test>::NotRegistered
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for updating the multiaddresses of Main Storage Providers and Backup Storage Providers
+mod update_multiaddresses {
+
+    use super::*;
+
+    /// This module holds the success cases for updating the multiaddresses of Main Storage Providers and Backup Storage Providers
+    mod success {
+        use super::*;
+
+        #[test]
+        fn msp_update_multiaddresses_works() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as a Main Storage Provider
+                let alice: AccountId = 0;
+                let (_deposit, _alice_msp) = register_account_as_msp(alice, 100);
+                let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+                // Update the multiaddresses of Alice
+                let new_multiaddresses = multiaddresses_from(&[
+                    "/ip4/10.0.0.1/tcp/30333",
+                    "/dns4/msp.example.com/tcp/30333",
+                ]);
+                assert_ok!(StorageProviders::update_multiaddresses(
+                    RuntimeOrigin::signed(alice),
+                    new_multiaddresses.clone()
+                ));
+
+                // Check that the multiaddresses were replaced, keeping the same MSP id and capacity
+                let alice_msp = crate::MainStorageProviders::<Test>::get(&alice_msp_id).unwrap();
+                assert_eq!(alice_msp.multiaddresses, new_multiaddresses);
+                assert_eq!(alice_msp.capacity, 100);
+                assert_eq!(StorageProviders::get_provider(alice), Some(alice_msp_id));
+
+                // Check that the event was emitted
+                System::assert_last_event(
+                    Event::<Test>::MultiaddressesUpdated {
+                        who: alice,
+                        new_multiaddresses,
+                    }
+                    .into(),
+                );
+            });
+        }
+
+        #[test]
+        fn bsp_update_multiaddresses_works() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Bob as a Backup Storage Provider
+                let bob: AccountId = 1;
+                let (_deposit, _bob_bsp) = register_account_as_bsp(bob, 100);
+                let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+
+                // Update the multiaddresses of Bob
+                let new_multiaddresses = multiaddresses_from(&["/ip6/::1/udp/30333/quic-v1"]);
+                assert_ok!(StorageProviders::update_multiaddresses(
+                    RuntimeOrigin::signed(bob),
+                    new_multiaddresses.clone()
+                ));
+
+                // Check that the new multiaddresses are the ones peers get when reading them
+                assert_eq!(
+                    StorageProviders::get_bsp_multiaddresses(&bob_bsp_id).unwrap(),
+                    new_multiaddresses
+                );
+                assert_eq!(StorageProviders::get_provider(bob), Some(bob_bsp_id));
+
+                // Check that the event was emitted
+                System::assert_last_event(
+                    Event::<Test>::MultiaddressesUpdated {
+                        who: bob,
+                        new_multiaddresses,
+                    }
+                    .into(),
+                );
+            });
+        }
+    }
+
+    /// This module holds the failure cases for updating the multiaddresses of Main Storage Providers and Backup Storage Providers
+    mod failure {
+        use super::*;
+
+        #[test]
+        fn update_multiaddresses_fails_when_not_registered() {
+            ExtBuilder::build().execute_with(|| {
+                let alice: AccountId = 0;
+
+                // Try to update the multiaddresses of Alice, which is not a Storage Provider
+                assert_noop!(
+                    StorageProviders::update_multiaddresses(
+                        RuntimeOrigin::signed(alice),
+                        multiaddresses_from(&["/ip4/10.0.0.1/tcp/30333"])
+                    ),
+                    Error::<Test>::NotRegistered
+                );
+            });
+        }
+
+        #[test]
+        fn update_multiaddresses_fails_when_passing_no_multiaddresses() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as a Main Storage Provider and Bob as a Backup Storage Provider
+                let alice: AccountId = 0;
+                let bob: AccountId = 1;
+                register_account_as_msp(alice, 100);
+                register_account_as_bsp(bob, 100);
+
+                // Try to remove all the multiaddresses of Alice and Bob
+                for who in [alice, bob] {
+                    assert_noop!(
+                        StorageProviders::update_multiaddresses(
+                            RuntimeOrigin::signed(who),
+                            BoundedVec::new()
+                        ),
+                        Error::<Test>::NoMultiAddress
+                    );
+                }
+            });
+        }
+
+        #[test]
+        fn update_multiaddresses_fails_when_passing_an_invalid_multiaddress() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as a Main Storage Provider and Bob as a Backup Storage Provider
+                let alice: AccountId = 0;
+                let bob: AccountId = 1;
+                register_account_as_msp(alice, 100);
+                register_account_as_bsp(bob, 100);
+
+                // Try to update the multiaddresses of Alice and Bob with an invalid or a repeated one
+                for who in [alice, bob] {
+                    assert_noop!(
+                        StorageProviders::update_multiaddresses(
+                            RuntimeOrigin::signed(who),
+                            multiaddresses_from(&["/ip4/10.0.0.1/tcp/30333", "/ip4/10.0.0.1/tcp"])
+                        ),
+                        Error::<Test>::InvalidMultiAddress
+                    );
+                    assert_noop!(
+                        StorageProviders::update_multiaddresses(
+                            RuntimeOrigin::signed(who),
+                            multiaddresses_from(&[
+                                "/ip4/10.0.0.1/tcp/30333",
+                                "/ip4/10.0.0.1/tcp/30333"
+                            ])
+                        ),
+                        Error::<Test>::InvalidMultiAddress
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// This module holds the test cases for the reputation of Main Storage Providers and Backup Storage Providers
+mod reputation {
+
+    use super::*;
+
+    type MinReputation = <Test as crate::Config>::MinReputation;
+    type MaxReputation = <Test as crate::Config>::MaxReputation;
+    type SlashReputationPenalty = <Test as crate::Config>::SlashReputationPenalty;
+
+    #[test]
+    fn change_reputation_works_and_is_clamped() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Bob as a Backup Storage Provider, which starts with no reputation
+            let bob: AccountId = 1;
+            register_account_as_bsp(bob, 100);
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            assert_eq!(StorageProviders::get_reputation(&bob_bsp_id), 0);
+
+            // Increase the reputation of Bob
+            assert_eq!(StorageProviders::change_reputation(&bob_bsp_id, 1), Ok(1));
+            assert_eq!(StorageProviders::get_reputation(&bob_bsp_id), 1);
+            System::assert_last_event(
+                Event::<Test>::ReputationChanged {
+                    provider_id: bob_bsp_id,
+                    old_reputation: 0,
+                    new_reputation: 1,
+                }
+                .into(),
+            );
+
+            // The reputation can not go above the maximum
+            assert_eq!(
+                StorageProviders::change_reputation(&bob_bsp_id, i32::MAX),
+                Ok(MaxReputation::get())
+            );
+
+            // Nor below the minimum
+            assert_eq!(
+                StorageProviders::change_reputation(&bob_bsp_id, i32::MIN),
+                Ok(MinReputation::get())
+            );
+            assert_eq!(
+                StorageProviders::get_reputation(&bob_bsp_id),
+                MinReputation::get()
+            );
+        });
+    }
+
+    #[test]
+    fn slash_decreases_reputation() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Alice as a Main Storage Provider
+            let alice: AccountId = 0;
+            register_account_as_msp(alice, 100);
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+            // Slash Alice
+            assert_ok!(StorageProviders::slash(&alice_msp_id, 1));
+
+            // Check that the reputation of Alice was penalised
+            assert_eq!(
+                StorageProviders::get_reputation(&alice_msp_id),
+                -SlashReputationPenalty::get()
+            );
+            System::assert_has_event(
+                Event::<Test>::ReputationChanged {
+                    provider_id: alice_msp_id,
+                    old_reputation: 0,
+                    new_reputation: -SlashReputationPenalty::get(),
+                }
+                .into(),
+            );
+        });
+    }
+
+    #[test]
+    fn change_reputation_fails_when_not_registered() {
+        ExtBuilder::build().execute_with(|| {
+            assert_noop!(
+                StorageProviders::change_reputation(&sp_core::H256::zero(), 1),
+                Error::<Test>::NotRegistered
+            );
+        });
+    }
+}
+
+/// This module holds the test cases for the heartbeat of Main Storage Providers and Backup Storage Providers
+mod liveness {
+
+    use super::*;
+
+    type LivenessTimeout = <Test as crate::Config>::LivenessTimeout;
+
+    #[test]
+    fn provider_becomes_stale_without_heartbeat() {
+        ExtBuilder::build().execute_with(|| {
+            // Register Bob as a Backup Storage Provider, which counts as being seen
+            let bob: AccountId = 1;
+            register_account_as_bsp(bob, 100);
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            let registration_block = System::block_number();
+            assert!(!StorageProviders::is_provider_stale(
+                &bob_bsp_id,
+                registration_block
+            ));
+
+            // Bob is not stale until the timeout has passed
+            let timeout_block = registration_block + LivenessTimeout::get();
+            run_to_block(timeout_block);
+            assert!(!StorageProviders::is_provider_stale(
+                &bob_bsp_id,
+                timeout_block
+            ));
+
+            // But becomes stale right after it without a heartbeat
+            run_to_block(timeout_block + 1);
+            assert!(StorageProviders::is_provider_stale(
+                &bob_bsp_id,
+                timeout_block + 1
+            ));
+
+            // Sending a heartbeat makes Bob not stale again
+            assert_ok!(StorageProviders::heartbeat(RuntimeOrigin::signed(bob)));
+            assert!(!StorageProviders::is_provider_stale(
+                &bob_bsp_id,
+                timeout_block + 1
+            ));
+            assert!(StorageProviders::is_provider_stale(
+                &bob_bsp_id,
+                timeout_block + 2 + LivenessTimeout::get()
+            ));
+        });
+    }
+
+    #[test]
+    fn heartbeat_fails_when_not_registered() {
+        ExtBuilder::build().execute_with(|| {
+            let alice: AccountId = 0;
+            assert_noop!(
+                StorageProviders::heartbeat(RuntimeOrigin::signed(alice)),
+                Error::<Test>::NotRegistered
+            );
+
+            // Providers that were never seen are stale
+            assert!(StorageProviders::is_provider_stale(
+                &sp_core::H256::zero(),
+                System::block_number()
+            ));
+        });
+    }
+}
+
+/// This module holds the test cases for updating the value proposition of Main Storage Providers
+mod update_value_prop {
+
+    use super::*;
+
+    /// Build a value proposition with the given data limit and protocols.
+    fn value_prop_from(
+        data_limit: StorageData<Test>,
+        protocols: &[&str],
+    ) -> ValueProposition<Test> {
+        ValueProposition {
+            identifier: ValuePropId::<Test>::default(),
+            data_limit,
+            protocols: protocols
+                .iter()
+                .map(|protocol| protocol.as_bytes().to_vec().try_into().unwrap())
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap(),
+        }
+    }
+
+    /// This module holds the success cases for updating the value proposition of Main Storage Providers
+    mod success {
+        use super::*;
+
+        #[test]
+        fn msp_update_value_prop_works() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as a Main Storage Provider
+                let alice: AccountId = 0;
+                register_account_as_msp(alice, 100);
+                let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+
+                // Update the value proposition of Alice
+                let new_value_prop = value_prop_from(50, &["s3", "ipfs"]);
+                assert_ok!(StorageProviders::update_value_prop(
+                    RuntimeOrigin::signed(alice),
+                    new_value_prop.clone()
+                ));
+
+                // Check that the value proposition was replaced, keeping the same MSP id and capacity
+                let alice_msp = crate::MainStorageProviders::<Test>::get(&alice_msp_id).unwrap();
+                assert_eq!(alice_msp.value_prop, new_value_prop);
+                assert_eq!(alice_msp.capacity, 100);
+                assert_eq!(StorageProviders::get_provider(alice), Some(alice_msp_id));
+
+                // Check that the event was emitted
+                System::assert_last_event(
+                    Event::<Test>::ValuePropUpdated {
+                        who: alice,
+                        new_value_prop,
+                    }
+                    .into(),
+                );
+            });
+        }
+    }
+
+    /// This module holds the failure cases for updating the value proposition of Main Storage Providers
+    mod failure {
+        use super::*;
+
+        #[test]
+        fn update_value_prop_fails_when_not_registered_as_msp() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Bob as a Backup Storage Provider, while Alice is not a Storage Provider
+                let alice: AccountId = 0;
+                let bob: AccountId = 1;
+                register_account_as_bsp(bob, 100);
+
+                // Try to update the value proposition of Alice and Bob
+                for who in [alice, bob] {
+                    assert_noop!(
+                        StorageProviders::update_value_prop(
+                            RuntimeOrigin::signed(who),
+                            value_prop_from(50, &["s3"])
+                        ),
+                        Error::<Test>::NotRegistered
+                    );
+                }
+            });
+        }
+
+        #[test]
+        fn update_value_prop_fails_when_passing_an_invalid_protocol() {
+            ExtBuilder::build().execute_with(|| {
+                // Register Alice as a Main Storage Provider
+                let alice: AccountId = 0;
+                register_account_as_msp(alice, 100);
+
+                // Try to update the value proposition of Alice with an empty or a repeated protocol
+                for protocols in [&["s3", ""][..], &["s3", "s3"][..]] {
+                    assert_noop!(
+                        StorageProviders::update_value_prop(
+                            RuntimeOrigin::signed(alice),
+                            value_prop_from(50, protocols)
+                        ),
+                        Error::<Test>::InvalidValueProposition
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// This module holds the test cases for the registration of Storage Providers at genesis
+mod genesis {
+
+    use super::*;
+
+    #[test]
+    fn genesis_registered_providers_are_queryable_and_counted() {
+        let alice: AccountId = 0;
+        let bob: AccountId = 1;
+        let charlie: AccountId = 2;
+        let multiaddresses = vec!["/ip4/127.0.0.1/udp/1234".as_bytes().to_vec()];
+
+        ExtBuilder::build_with_genesis_providers(vec![
+            (
+                alice,
+                GenesisProviderKind::MainStorageProvider,
+                100,
+                multiaddresses.clone(),
+                None,
+            ),
+            (
+                bob,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                None,
+            ),
+            (
+                charlie,
+                GenesisProviderKind::BackupStorageProvider,
+                50,
+                multiaddresses.clone(),
+                None,
+            ),
+        ])
+        .execute_with(|| {
+            // Check that the providers are registered with the expected kind
+            let alice_msp_id = StorageProviders::get_provider(alice).unwrap();
+            let bob_bsp_id = StorageProviders::get_provider(bob).unwrap();
+            let charlie_bsp_id = StorageProviders::get_provider(charlie).unwrap();
+            assert!(StorageProviders::is_msp(&alice_msp_id));
+            assert!(StorageProviders::is_bsp(&bob_bsp_id));
+            assert!(StorageProviders::is_bsp(&charlie_bsp_id));
+
+            // Check that their information is stored correctly
+            assert_eq!(StorageProviders::get_total_capacity_of_sp(&alice), Ok(100));
+            assert_eq!(StorageProviders::get_total_capacity_of_sp(&bob), Ok(100));
+            assert_eq!(
+                StorageProviders::get_used_storage_of_bsp(&bob_bsp_id),
+                Ok(0)
+            );
+            assert_eq!(
+                StorageProviders::get_bsp_multiaddresses(&bob_bsp_id)
+                    .unwrap()
+                    .into_inner(),
+                vec![MultiAddress::<Test>::try_from(multiaddresses[0].clone()).unwrap()]
+            );
+
+            // Check that the counters and the total BSPs capacity were updated
+            assert_eq!(StorageProviders::get_msp_count(), 1);
+            assert_eq!(StorageProviders::get_bsp_count(), 2);
+            assert_eq!(StorageProviders::get_total_bsp_capacity(), 150);
+
+            // Check that no deposit was held from the providers
+            assert_eq!(
+                NativeBalance::balance_on_hold(&StorageProvidersHoldReason::get(), &bob),
+                0
+            );
+        });
+    }
+
+    #[test]
+    fn genesis_registered_bsps_are_notified_to_subscribers() {
+        let alice: AccountId = 0;
+        let bob: AccountId = 1;
+        let charlie: AccountId = 2;
+        let multiaddresses = vec!["/ip4/127.0.0.1/udp/1234".as_bytes().to_vec()];
+
+        ExtBuilder::build_with_genesis_providers(vec![
+            (
+                alice,
+                GenesisProviderKind::MainStorageProvider,
+                100,
+                multiaddresses.clone(),
+                None,
+            ),
+            (
+                bob,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                None,
+            ),
+            (
+                charlie,
+                GenesisProviderKind::BackupStorageProvider,
+                50,
+                multiaddresses.clone(),
+                None,
+            ),
+        ])
+        .execute_with(|| {
+            // Check that the subscribers were notified of the sign up of the BSPs, but not of the MSP
+            assert_eq!(SubscribedBspSignUps::get(), vec![bob, charlie]);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Genesis Storage Provider should be valid")]
+    fn genesis_provider_with_capacity_below_minimum_fails() {
+        let alice: AccountId = 0;
+        ExtBuilder::build_with_genesis_providers(vec![(
+            alice,
+            GenesisProviderKind::BackupStorageProvider,
+            SpMinCapacity::get() - 1,
+            vec!["/ip4/127.0.0.1/udp/1234".as_bytes().to_vec()],
+            None,
+        )]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Genesis Storage Provider should be valid")]
+    fn genesis_provider_with_invalid_multiaddress_fails() {
+        let alice: AccountId = 0;
+        ExtBuilder::build_with_genesis_providers(vec![(
+            alice,
+            GenesisProviderKind::BackupStorageProvider,
+            100,
+            vec!["/ip4/127.0.0.1/udp".as_bytes().to_vec()],
+            None,
+        )]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Genesis Storage Provider should be valid")]
+    fn genesis_provider_with_multiaddress_exceeding_max_size_fails() {
+        let alice: AccountId = 0;
+        let max_multiaddress_size: u32 = <Test as crate::Config>::MaxMultiAddressSize::get();
+        let dns_name = "a.".repeat(max_multiaddress_size as usize);
+        ExtBuilder::build_with_genesis_providers(vec![(
+            alice,
+            GenesisProviderKind::BackupStorageProvider,
+            100,
+            vec![
+                format!("/dns4/{}/tcp/30333", dns_name.trim_end_matches('.'))
+                    .as_bytes()
+                    .to_vec(),
+            ],
+            None,
+        )]);
+    }
+
+    #[test]
+    fn genesis_providers_with_given_ids_are_registered() {
+        let alice: AccountId = 0;
+        let bob: AccountId = 1;
+        let alice_bsp_id = sp_core::H256::repeat_byte(1);
+        let bob_bsp_id = sp_core::H256::repeat_byte(2);
+        let multiaddresses = vec!["/ip4/127.0.0.1/udp/1234".as_bytes().to_vec()];
+
+        ExtBuilder::build_with_genesis_providers(vec![
+            (
+                alice,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                Some(alice_bsp_id),
+            ),
+            (
+                bob,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                Some(bob_bsp_id),
+            ),
+        ])
+        .execute_with(|| {
+            // Check that the providers are registered as BSPs with the given ids
+            assert_eq!(StorageProviders::get_provider(alice), Some(alice_bsp_id));
+            assert_eq!(StorageProviders::get_provider(bob), Some(bob_bsp_id));
+            assert!(StorageProviders::is_bsp(&alice_bsp_id));
+            assert!(StorageProviders::is_bsp(&bob_bsp_id));
+            assert_eq!(StorageProviders::get_bsp_count(), 2);
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "Genesis Storage Provider should be valid")]
+    fn genesis_providers_with_repeated_id_fail() {
+        let alice: AccountId = 0;
+        let bob: AccountId = 1;
+        let multiaddresses = vec!["/ip4/127.0.0.1/udp/1234".as_bytes().to_vec()];
+
+        ExtBuilder::build_with_genesis_providers(vec![
+            (
+                alice,
+                GenesisProviderKind::MainStorageProvider,
+                100,
+                multiaddresses.clone(),
+                Some(sp_core::H256::repeat_byte(1)),
+            ),
+            (
+                bob,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                Some(sp_core::H256::repeat_byte(1)),
+            ),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Genesis Storage Provider should be valid")]
+    fn genesis_providers_with_repeated_account_fail() {
+        let alice: AccountId = 0;
+        let multiaddresses = vec!["/ip4/127.0.0.1/udp/1234".as_bytes().to_vec()];
+
+        ExtBuilder::build_with_genesis_providers(vec![
+            (
+                alice,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                Some(sp_core::H256::repeat_byte(1)),
+            ),
+            (
+                alice,
+                GenesisProviderKind::BackupStorageProvider,
+                100,
+                multiaddresses.clone(),
+                Some(sp_core::H256::repeat_byte(2)),
+            ),
+        ]);
+    }
+}
+
+// Helper functions for testing:
+
+/// Helper function that builds the bounded multiaddresses of a Storage Provider from their human-readable form
+fn multiaddresses_from(
+    multiaddresses: &[&str],
+) -> BoundedVec<MultiAddress<Test>, MaxMultiAddressAmount<Test>> {
+    multiaddresses
+        .iter()
+        .map(|multiaddress| multiaddress.as_bytes().to_vec().try_into().unwrap())
+        .collect::<Vec<_>>()
+        .try_into()
+        .unwrap()
+}
 
 /// Helper function that registers an account as a Main Storage Provider, with storage_amount StorageData units
 ///
@@ -3934,6 +6381,7 @@ fn register_account_as_msp(
             multiaddresses,
             value_prop,
             last_capacity_change: frame_system::Pallet::<Test>::block_number(),
+            owner_account: account,
         },
     )
 }
@@ -4012,6 +6460,7 @@ fn register_account_as_bsp(
             multiaddresses,
             root: Default::default(),
             last_capacity_change: frame_system::Pallet::<Test>::block_number(),
+            owner_account: account,
         },
     )
 }
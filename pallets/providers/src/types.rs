@@ -19,7 +19,7 @@ pub struct ValueProposition<T: Config> {
 }
 
 /// Structure that represents a Main Storage Provider. It holds the buckets that the MSP has, the total data that the MSP is able to store,
-/// the amount of data that it is storing, and its libp2p multiaddresses.
+/// the amount of data that it is storing, its libp2p multiaddresses, and the account that owns it.
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebugNoBound, PartialEq, Eq, Clone)]
 #[scale_info(skip_type_params(T))]
 pub struct MainStorageProvider<T: Config> {
@@ -29,10 +29,13 @@ pub struct MainStorageProvider<T: Config> {
     pub multiaddresses: BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>>,
     pub value_prop: ValueProposition<T>,
     pub last_capacity_change: BlockNumberFor<T>,
+    // No storage migration was needed to add this field: the pallet has not been deployed to a live chain,
+    // so there are no stored Storage Providers to translate.
+    pub owner_account: T::AccountId,
 }
 
 /// Structure that represents a Backup Storage Provider. It holds the total data that the BSP is able to store, the amount of data that it is storing,
-/// its libp2p multiaddresses, and the root of the Merkle Patricia Trie that it stores.
+/// its libp2p multiaddresses, the root of the Merkle Patricia Trie that it stores, and the account that owns it.
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebugNoBound, PartialEq, Eq, Clone)]
 #[scale_info(skip_type_params(T))]
 pub struct BackupStorageProvider<T: Config> {
@@ -41,16 +44,19 @@ pub struct BackupStorageProvider<T: Config> {
     pub multiaddresses: BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>>,
     pub root: MerklePatriciaRoot<T>,
     pub last_capacity_change: BlockNumberFor<T>,
+    // Added without a storage migration, see `MainStorageProvider::owner_account`.
+    pub owner_account: T::AccountId,
 }
 
 /// Structure that represents a Bucket. It holds the root of the Merkle Patricia Trie, the User ID that owns the bucket,
-/// and the MainStorageProviderId that the bucket belongs to.
+/// the MainStorageProviderId that the bucket belongs to and the size of the data stored in it.
 #[derive(Encode, Decode, MaxEncodedLen, TypeInfo, RuntimeDebugNoBound, PartialEq, Eq, Clone)]
 #[scale_info(skip_type_params(T))]
 pub struct Bucket<T: Config> {
     pub root: MerklePatriciaRoot<T>,
     pub user_id: T::AccountId,
     pub msp_id: MainStorageProviderId<T>,
+    pub size: StorageData<T>,
 }
 
 /// Enum that represents a Storage Provider. It holds either a BackupStorageProvider or a MainStorageProvider,
@@ -62,6 +68,25 @@ pub enum StorageProvider<T: Config> {
     MainStorageProvider(MainStorageProvider<T>),
 }
 
+/// Enum that represents the kind of a Storage Provider registered at genesis.
+#[derive(
+    Encode,
+    Decode,
+    TypeInfo,
+    RuntimeDebug,
+    PartialEq,
+    Eq,
+    Clone,
+    Copy,
+    sp_runtime::Serialize,
+    sp_runtime::Deserialize,
+)]
+#[serde(crate = "sp_runtime::serde")]
+pub enum GenesisProviderKind {
+    MainStorageProvider,
+    BackupStorageProvider,
+}
+
 // Type aliases:
 
 /// BalanceOf is the balance type of the runtime.
@@ -83,9 +108,16 @@ pub type MultiAddress<T> = BoundedVec<u8, MaxMultiAddressSize<T>>;
 
 /// MerklePatriciaRoot is the type of the root of a Merkle Patricia Trie, either the root of a BSP or a bucket from an MSP.
 pub type MerklePatriciaRoot<T> = <T as crate::Config>::MerklePatriciaRoot;
+/// RootTransitionProof is the type of the proof that the new root of a bucket is a valid successor of its current root.
+pub type RootTransitionProof<T> =
+    <<T as crate::Config>::RootTransitionVerifier as storage_hub_traits::RootTransitionVerifier>::Proof;
 /// HashId is the type that uniquely identifies either a Storage Provider (MSP or BSP) or a Bucket.
 pub type HashId<T> = <T as frame_system::Config>::Hash;
 
+/// Reputation is the type of the reputation of a Storage Provider, a signed integer that can go below zero
+/// when the Storage Provider gets penalised more than it gets rewarded.
+pub type Reputation = i32;
+
 /// StorageData is the type of the unit in which we measure data size. We define its required traits in the
 /// pallet configuration so the runtime can use any type that implements them.
 pub type StorageData<T> = <T as crate::Config>::StorageData;
@@ -1,20 +1,27 @@
-use crate::types::{Bucket, MainStorageProvider, MultiAddress, StorageProvider};
-use codec::Encode;
+use crate::types::{
+    Bucket, GenesisProviderKind, MainStorageProvider, MaxMultiAddressAmount, MultiAddress,
+    Reputation, StorageProvider, ValuePropId, ValueProposition,
+};
 use frame_support::ensure;
-use frame_support::pallet_prelude::DispatchResult;
+use frame_support::pallet_prelude::{DispatchResult, Weight};
 use frame_support::sp_runtime::{
-    traits::{CheckedAdd, CheckedMul, CheckedSub, One, Saturating, Zero},
+    traits::{CheckedAdd, CheckedMul, CheckedSub, Hash, One, Saturating, Zero},
     ArithmeticError, DispatchError,
 };
 use frame_support::traits::{
     fungible::{Inspect, InspectHold, MutateHold},
     tokens::{Fortitude, Precision, Preservation},
-    Get, Randomness,
+    Get,
 };
 use frame_system::pallet_prelude::BlockNumberFor;
+use scale_info::prelude::vec::Vec;
 use sp_runtime::BoundedVec;
-use storage_hub_traits::{MutateProvidersInterface, ProvidersInterface, ReadProvidersInterface};
+use storage_hub_traits::{
+    MutateProvidersInterface, ProvidersInterface, ReadProvidersInterface,
+    ReadStorageCommitmentsInterface, RootTransitionVerifier, SubscribeProvidersInterface,
+};
 
+use crate::multiaddress::is_valid_multiaddress;
 use crate::*;
 
 macro_rules! expect_or_err {
@@ -89,15 +96,8 @@ where
             Error::<T>::NoMultiAddress
         );
 
-        // TODO: Check that the multiaddresses are valid
-        /* for multiaddress in msp_info.multiaddresses.iter() {
-            let multiaddress_vec = multiaddress.to_vec();
-            let valid_multiaddress = Multiaddr::try_from(multiaddress_vec);
-            match valid_multiaddress {
-                Ok(_) => (),
-                Err(_) => return Err(Error::<T>::InvalidMultiAddress.into()),
-            }
-        } */
+        // Check that the multiaddresses are valid
+        Self::ensure_valid_multiaddresses(&msp_info.multiaddresses)?;
 
         // Check that the data to be stored is bigger than the minimum required by the runtime
         ensure!(
@@ -128,8 +128,9 @@ where
             Error::<T>::CannotHoldDeposit
         );
 
-        // Hold the deposit from the user
+        // Hold the deposit from the user, keeping track of it to know how much to return when it is released
         T::NativeBalance::hold(&HoldReason::StorageProviderDeposit.into(), who, deposit)?;
+        ProviderDeposits::<T>::insert(who, deposit);
 
         // Store the sign up request in the SignUpRequests mapping
         SignUpRequests::<T>::insert(
@@ -181,15 +182,8 @@ where
             Error::<T>::NoMultiAddress
         );
 
-        // TODO: Check that the multiaddresses are valid
-        /* for multiaddress in bsp_info.multiaddresses.iter() {
-            let multiaddress_vec = multiaddress.to_vec();
-            let valid_multiaddress = Multiaddr::try_from(multiaddress_vec);
-            match valid_multiaddress {
-                Ok(_) => (),
-                Err(_) => return Err(Error::<T>::InvalidMultiAddress.into()),
-            }
-        } */
+        // Check that the multiaddresses are valid
+        Self::ensure_valid_multiaddresses(&bsp_info.multiaddresses)?;
 
         // Check that the data to be stored is bigger than the minimum required by the runtime
         ensure!(
@@ -220,8 +214,9 @@ where
             Error::<T>::CannotHoldDeposit
         );
 
-        // Hold the deposit from the user
+        // Hold the deposit from the user, keeping track of it to know how much to return when it is released
         T::NativeBalance::hold(&HoldReason::StorageProviderDeposit.into(), who, deposit)?;
+        ProviderDeposits::<T>::insert(who, deposit);
 
         // Store the sign up request in the SignUpRequests mapping
         SignUpRequests::<T>::insert(
@@ -239,22 +234,86 @@ where
     /// and, if so, removes the request from the SignUpRequests mapping
     pub fn do_cancel_sign_up(who: &T::AccountId) -> DispatchResult {
         // Check that the signer has requested to sign up as a Storage Provider
-        SignUpRequests::<T>::get(who).ok_or(Error::<T>::SignUpNotRequested)?;
+        let (sp, _) = SignUpRequests::<T>::get(who).ok_or(Error::<T>::SignUpNotRequested)?;
 
         // Remove the sign up request from the SignUpRequests mapping
         SignUpRequests::<T>::remove(who);
 
         // Return the deposit to the signer
         // We return all held funds as there's no possibility of the user having another _valid_ hold with this pallet
-        T::NativeBalance::release_all(
-            &HoldReason::StorageProviderDeposit.into(),
-            who,
-            frame_support::traits::tokens::Precision::Exact,
-        )?;
+        Self::release_deposit(who)?;
 
         Ok(())
     }
 
+    /// This function holds the logic that removes the sign up requests that can no longer be confirmed, releasing their
+    /// deposits, within the given weight.
+    ///
+    /// A request is swept from the block after the one in which it expired, so a request that could still be confirmed
+    /// is never swept. Requests are checked in the order of the SignUpRequests mapping, resuming from the
+    /// SignUpRequestsSweepCursor, and the weight consumed is returned.
+    pub fn do_sweep_expired_sign_up_requests(remaining_weight: Weight) -> Weight {
+        let base_weight = T::DbWeight::get().reads_writes(1, 1);
+        let request_weight = Self::sign_up_request_sweep_weight();
+
+        // Check that there is enough weight left to check at least one request
+        if remaining_weight.any_lt(base_weight.saturating_add(request_weight)) {
+            return Weight::zero();
+        }
+
+        let current_block = frame_system::Pallet::<T>::block_number();
+        let mut requests = match SignUpRequestsSweepCursor::<T>::get() {
+            Some(last_checked) => {
+                SignUpRequests::<T>::iter_from(SignUpRequests::<T>::hashed_key_for(&last_checked))
+            }
+            None => SignUpRequests::<T>::iter(),
+        };
+
+        // Collect the expired requests first, since the mapping must not be modified while iterating it
+        let mut used_weight = base_weight;
+        let mut expired_requests = Vec::new();
+        let mut last_checked = None;
+        let mut sweep_completed = false;
+        while remaining_weight.all_gte(used_weight.saturating_add(request_weight)) {
+            let Some((who, (_, request_block))) = requests.next() else {
+                sweep_completed = true;
+                break;
+            };
+            used_weight = used_weight.saturating_add(request_weight);
+
+            if current_block > request_block.saturating_add(T::MaxBlocksForRandomness::get()) {
+                expired_requests.push(who.clone());
+            }
+            last_checked = Some(who);
+        }
+
+        // Resume from the last checked request in the next sweep, or start over if all requests were checked
+        match last_checked {
+            Some(last_checked) if !sweep_completed => {
+                SignUpRequestsSweepCursor::<T>::put(last_checked)
+            }
+            _ => SignUpRequestsSweepCursor::<T>::kill(),
+        }
+
+        for who in expired_requests {
+            // Remove the sign up request from the SignUpRequests mapping
+            SignUpRequests::<T>::remove(&who);
+
+            // Return the deposit to the user, as in do_cancel_sign_up. This is best effort, since a hook can't fail
+            let _ = Self::release_deposit(&who);
+
+            Self::deposit_event(Event::<T>::SignUpRequestExpired { who });
+        }
+
+        used_weight
+    }
+
+    /// The weight of checking a sign up request in the sweep of expired sign up requests, assuming it expired.
+    pub(crate) fn sign_up_request_sweep_weight() -> Weight {
+        // Read the request, then remove it and release its deposit (recorded deposit, holds and account)
+        T::DbWeight::get().reads_writes(4, 4)
+    }
+
     /// This function dispatches the logic to confirm the sign up of a user as a Storage Provider
     /// It checks if the user has requested to sign up, and if so, it dispatches the corresponding logic
     /// according to the type of Storage Provider that the user is trying to sign up as
@@ -303,8 +362,13 @@ where
         );
 
         // Get the MainStorageProviderId by using the AccountId as the seed for a random generator
-        let (msp_id, block_number_when_random) =
-            T::ProvidersRandomness::random(who.encode().as_ref());
+        let (msp_id, block_number_when_random) = Self::provider_id_randomness(who);
+
+        // Check that there is randomness available at all, as the default hash is returned when there is no seed yet
+        ensure!(
+            msp_id != HashId::<T>::default(),
+            Error::<T>::RandomnessNotAvailable
+        );
 
         // Check that the maximum block number after which the randomness is invalid is greater than or equal to the block number when the
         // request was made to ensure that the randomness was not known when the request was made
@@ -319,6 +383,9 @@ where
         // Save the MainStorageProvider information in storage
         MainStorageProviders::<T>::insert(&msp_id, msp_info);
 
+        // Registering counts as being seen, so the new MSP is not stale until it misses its heartbeats
+        LastSeen::<T>::insert(&msp_id, frame_system::Pallet::<T>::block_number());
+
         // Increment the counter of Main Storage Providers registered
         MspCount::<T>::set(new_amount_of_msps);
 
@@ -364,8 +431,13 @@ where
         );
 
         // Get the BackupStorageProviderId by using the AccountId as the seed for a random generator
-        let (bsp_id, block_number_when_random) =
-            T::ProvidersRandomness::random(who.encode().as_ref());
+        let (bsp_id, block_number_when_random) = Self::provider_id_randomness(who);
+
+        // Check that there is randomness available at all, as the default hash is returned when there is no seed yet
+        ensure!(
+            bsp_id != HashId::<T>::default(),
+            Error::<T>::RandomnessNotAvailable
+        );
 
         // Check that the maximum block number after which the randomness is invalid is greater than or equal to the block number when the
         // request was made to ensure that the randomness was not known when the request was made
@@ -380,6 +452,9 @@ where
         // Save the BackupStorageProvider information in storage
         BackupStorageProviders::<T>::insert(&bsp_id, bsp_info.clone());
 
+        // Registering counts as being seen, so the new BSP is not stale until it misses its heartbeats
+        LastSeen::<T>::insert(&bsp_id, frame_system::Pallet::<T>::block_number());
+
         // Increment the total capacity of the network (which is the sum of all BSPs capacities)
         TotalBspsCapacity::<T>::mutate(|n| match n.checked_add(&bsp_info.capacity) {
             Some(new_total_bsp_capacity) => {
@@ -392,6 +467,9 @@ where
         // Increment the counter of Backup Storage Providers registered
         BspCount::<T>::set(new_amount_of_bsps);
 
+        // Notify the subscribers that a new BSP joined the network
+        T::Subscribers::subscribe_bsp_sign_up(who)?;
+
         // Remove the sign up request from the SignUpRequests mapping
         SignUpRequests::<T>::remove(who);
 
@@ -405,6 +483,205 @@ where
         Ok(())
     }
 
+    /// This function holds the logic that registers a Storage Provider at genesis, skipping the sign up request process
+    /// It validates the provider's capacity and multiaddresses, uses the given id or derives it from the hash of its account (since no
+    /// randomness is available at genesis) and updates the storage and counters and notifies the subscribers exactly as a confirmed sign up would,
+    /// without holding any deposit
+    pub fn do_genesis_register_provider(
+        who: &T::AccountId,
+        kind: GenesisProviderKind,
+        capacity: StorageData<T>,
+        multiaddresses: Vec<Vec<u8>>,
+        sp_id: Option<HashId<T>>,
+    ) -> DispatchResult {
+        // Check that the account is not already registered either as a Main Storage Provider or a Backup Storage Provider
+        ensure!(
+            AccountIdToMainStorageProviderId::<T>::get(who).is_none()
+                && AccountIdToBackupStorageProviderId::<T>::get(who).is_none(),
+            Error::<T>::AlreadyRegistered
+        );
+
+        // Check that the data to be stored is bigger than the minimum required by the runtime
+        ensure!(
+            capacity >= T::SpMinCapacity::get(),
+            Error::<T>::StorageTooLow
+        );
+
+        // Convert the multiaddresses to their bounded form, checking that there is at least one
+        let multiaddresses = multiaddresses
+            .into_iter()
+            .map(|multiaddress| {
+                MultiAddress::<T>::try_from(multiaddress)
+                    .map_err(|_| Error::<T>::InvalidMultiAddress)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let multiaddresses: BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>> =
+            BoundedVec::try_from(multiaddresses).map_err(|_| Error::<T>::InvalidMultiAddress)?;
+        ensure!(!multiaddresses.is_empty(), Error::<T>::NoMultiAddress);
+        Self::ensure_valid_multiaddresses(&multiaddresses)?;
+
+        // Use the given id of the Storage Provider or derive it from its account, checking that no other one uses it
+        let sp_id = sp_id.unwrap_or_else(|| T::Hashing::hash_of(who));
+        ensure!(
+            !MainStorageProviders::<T>::contains_key(&sp_id)
+                && !BackupStorageProviders::<T>::contains_key(&sp_id),
+            Error::<T>::ProviderIdAlreadyInUse
+        );
+
+        match kind {
+            GenesisProviderKind::MainStorageProvider => {
+                let new_amount_of_msps = MspCount::<T>::get()
+                    .checked_add(&T::SpCount::one())
+                    .ok_or(DispatchError::Arithmetic(ArithmeticError::Overflow))?;
+                ensure!(
+                    new_amount_of_msps <= T::MaxMsps::get(),
+                    Error::<T>::MaxMspsReached
+                );
+
+                let msp_info = MainStorageProvider {
+                    buckets: BoundedVec::default(),
+                    capacity,
+                    data_used: StorageData::<T>::zero(),
+                    multiaddresses,
+                    value_prop: ValueProposition {
+                        identifier: ValuePropId::<T>::default(),
+                        data_limit: capacity,
+                        protocols: BoundedVec::default(),
+                    },
+                    last_capacity_change: BlockNumberFor::<T>::zero(),
+                    owner_account: who.clone(),
+                };
+
+                AccountIdToMainStorageProviderId::<T>::insert(who, sp_id);
+                MainStorageProviders::<T>::insert(&sp_id, msp_info);
+                LastSeen::<T>::insert(&sp_id, BlockNumberFor::<T>::zero());
+                MspCount::<T>::set(new_amount_of_msps);
+            }
+            GenesisProviderKind::BackupStorageProvider => {
+                let new_amount_of_bsps = BspCount::<T>::get()
+                    .checked_add(&T::SpCount::one())
+                    .ok_or(DispatchError::Arithmetic(ArithmeticError::Overflow))?;
+                ensure!(
+                    new_amount_of_bsps <= T::MaxBsps::get(),
+                    Error::<T>::MaxBspsReached
+                );
+
+                let bsp_info = BackupStorageProvider {
+                    capacity,
+                    data_used: StorageData::<T>::zero(),
+                    multiaddresses,
+                    root: MerklePatriciaRoot::<T>::default(),
+                    last_capacity_change: BlockNumberFor::<T>::zero(),
+                    owner_account: who.clone(),
+                };
+
+                AccountIdToBackupStorageProviderId::<T>::insert(who, sp_id);
+                BackupStorageProviders::<T>::insert(&sp_id, bsp_info);
+                LastSeen::<T>::insert(&sp_id, BlockNumberFor::<T>::zero());
+                TotalBspsCapacity::<T>::try_mutate(|total| -> DispatchResult {
+                    *total = total
+                        .checked_add(&capacity)
+                        .ok_or(DispatchError::Arithmetic(ArithmeticError::Overflow))?;
+                    Ok(())
+                })?;
+                BspCount::<T>::set(new_amount_of_bsps);
+
+                // Notify the subscribers that a new BSP joined the network, as a confirmed sign up would
+                T::Subscribers::subscribe_bsp_sign_up(who)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This function holds the logic that checks if a Storage Provider can request to sign off and, if so, records the
+    /// block of the request in the PendingSignOffs mapping. If the Storage Provider is a Backup Storage Provider, it also
+    /// decrements the counter of Backup Storage Providers and notifies the subscribers, so it stops counting towards the
+    /// assignment threshold while its deposit is still held. It returns the block from which the sign off can be finalized.
+    pub fn do_request_sign_off(who: &T::AccountId) -> Result<BlockNumberFor<T>, DispatchError> {
+        // Check that the signer does not already have a pending sign off request
+        ensure!(
+            !PendingSignOffs::<T>::contains_key(who),
+            Error::<T>::SignOffAlreadyRequested
+        );
+
+        // Check that the signer is registered as a SP and that it has no storage assigned to it
+        if let Some(msp_id) = AccountIdToMainStorageProviderId::<T>::get(who) {
+            let msp = expect_or_err!(
+                MainStorageProviders::<T>::get(&msp_id),
+                "MSP is registered (has a MSP ID), it should also have metadata",
+                Error::<T>::SpRegisteredButDataNotFound
+            );
+            ensure!(
+                msp.data_used == T::StorageData::zero()
+                    && BucketsByMsp::<T>::iter_key_prefix(&msp_id).next().is_none(),
+                Error::<T>::StorageStillInUse
+            );
+        } else if let Some(bsp_id) = AccountIdToBackupStorageProviderId::<T>::get(who) {
+            let bsp = expect_or_err!(
+                BackupStorageProviders::<T>::get(&bsp_id),
+                "BSP is registered (has a BSP ID), it should also have metadata",
+                Error::<T>::SpRegisteredButDataNotFound
+            );
+            ensure!(
+                bsp.data_used == T::StorageData::zero(),
+                Error::<T>::StorageStillInUse
+            );
+
+            // Decrement the storage that holds total amount of BSPs currently in the system
+            BspCount::<T>::mutate(|n| {
+                let new_amount_of_bsps = n.checked_sub(&T::SpCount::one());
+                match new_amount_of_bsps {
+                    Some(new_amount_of_bsps) => {
+                        *n = new_amount_of_bsps;
+                        Ok(())
+                    }
+                    None => Err(DispatchError::Arithmetic(ArithmeticError::Underflow)),
+                }
+            })?;
+
+            // Notify the subscribers that the BSP is leaving the network
+            T::Subscribers::subscribe_bsp_sign_off(who)?;
+        } else {
+            return Err(Error::<T>::NotRegistered.into());
+        }
+
+        // Record the block of the request, keeping the deposit held until the cooldown elapses
+        let current_block = frame_system::Pallet::<T>::block_number();
+        PendingSignOffs::<T>::insert(who, current_block);
+
+        Ok(current_block + T::SignOffCooldown::get())
+    }
+
+    /// This function dispatches the logic to finalize the sign off of a Storage Provider
+    /// It checks if the user has requested to sign off and if the sign off cooldown has elapsed, and if so, it dispatches
+    /// the corresponding logic according to the type of Storage Provider that the user is signing off as
+    pub fn do_finalize_sign_off(who: &T::AccountId) -> DispatchResult {
+        // Check that the signer has requested to sign off
+        let request_block =
+            PendingSignOffs::<T>::get(who).ok_or(Error::<T>::SignOffNotRequested)?;
+
+        // Check that the sign off cooldown has elapsed
+        ensure!(
+            frame_system::Pallet::<T>::block_number() >= request_block + T::SignOffCooldown::get(),
+            Error::<T>::SignOffCooldownNotElapsed
+        );
+
+        // Check what type of Storage Provider the signer is and dispatch the corresponding logic
+        if AccountIdToMainStorageProviderId::<T>::contains_key(who) {
+            Self::do_msp_sign_off(who)?;
+            Self::deposit_event(Event::<T>::MspSignOffSuccess { who: who.clone() });
+        } else {
+            Self::do_bsp_sign_off(who)?;
+            Self::deposit_event(Event::<T>::BspSignOffSuccess { who: who.clone() });
+        }
+
+        // Remove the sign off request from the PendingSignOffs mapping
+        PendingSignOffs::<T>::remove(who);
+
+        Ok(())
+    }
+
     /// This function holds the logic that checks if a user can sign off as a Main Storage Provider
     /// and, if so, updates the storage to remove the user as a Main Storage Provider, decrements the counter of Main Storage Providers,
     /// and returns the deposit to the user
@@ -421,20 +698,20 @@ where
 
         // Check that the MSP has no storage assigned to it (no buckets or data used by it)
         ensure!(
-            msp.data_used == T::StorageData::zero(),
+            msp.data_used == T::StorageData::zero()
+                && BucketsByMsp::<T>::iter_key_prefix(&msp_id).next().is_none(),
             Error::<T>::StorageStillInUse
         );
 
         // Update the MSPs storage, removing the signer as an MSP
         AccountIdToMainStorageProviderId::<T>::remove(who);
         MainStorageProviders::<T>::remove(&msp_id);
+        InsolventProviders::<T>::remove(&msp_id);
+        ProviderReputation::<T>::remove(&msp_id);
+        LastSeen::<T>::remove(&msp_id);
 
-        // Return the deposit to the signer (if all funds cannot be returned, it will fail and revert with the reason)
-        T::NativeBalance::release_all(
-            &HoldReason::StorageProviderDeposit.into(),
-            who,
-            frame_support::traits::tokens::Precision::Exact,
-        )?;
+        // Return the deposit to the signer (whatever is left of it, if part of it could not be returned)
+        Self::release_deposit(who)?;
 
         // Decrement the storage that holds total amount of MSPs currently in the system
         MspCount::<T>::mutate(|n| {
@@ -452,8 +729,9 @@ where
     }
 
     /// This function holds the logic that checks if a user can sign off as a Backup Storage Provider
-    /// and, if so, updates the storage to remove the user as a Backup Storage Provider, decrements the counter of Backup Storage Providers,
-    /// decrements the total capacity of the network (which is the sum of all BSPs capacities), and returns the deposit to the user
+    /// and, if so, updates the storage to remove the user as a Backup Storage Provider, decrements the total capacity of the
+    /// network (which is the sum of all BSPs capacities), and returns the deposit to the user.
+    /// The counter of Backup Storage Providers is not decremented here, since that is done when requesting to sign off
     pub fn do_bsp_sign_off(who: &T::AccountId) -> DispatchResult {
         // Check that the signer is registered as a BSP and get its info
         let bsp_id =
@@ -474,6 +752,9 @@ where
         // Update the BSPs storage, removing the signer as an BSP
         AccountIdToBackupStorageProviderId::<T>::remove(who);
         BackupStorageProviders::<T>::remove(&bsp_id);
+        InsolventProviders::<T>::remove(&bsp_id);
+        ProviderReputation::<T>::remove(&bsp_id);
+        LastSeen::<T>::remove(&bsp_id);
 
         // Update the total capacity of the network (which is the sum of all BSPs capacities)
         TotalBspsCapacity::<T>::mutate(|n| match n.checked_sub(&bsp.capacity) {
@@ -484,24 +765,8 @@ where
             None => Err(DispatchError::Arithmetic(ArithmeticError::Underflow)),
         })?;
 
-        // Return the deposit to the signer (if all funds cannot be returned, it will fail and revert with the reason)
-        T::NativeBalance::release_all(
-            &HoldReason::StorageProviderDeposit.into(),
-            who,
-            frame_support::traits::tokens::Precision::Exact,
-        )?;
-
-        // Decrement the storage that holds total amount of BSPs currently in the system
-        BspCount::<T>::mutate(|n| {
-            let new_amount_of_bsps = n.checked_sub(&T::SpCount::one());
-            match new_amount_of_bsps {
-                Some(new_amount_of_bsps) => {
-                    *n = new_amount_of_bsps;
-                    Ok(())
-                }
-                None => Err(DispatchError::Arithmetic(ArithmeticError::Underflow)),
-            }
-        })?;
+        // Return the deposit to the signer (whatever is left of it, if part of it could not be returned)
+        Self::release_deposit(who)?;
 
         Ok(())
     }
@@ -531,6 +796,232 @@ where
         Ok(old_capacity)
     }
 
+    /// This function holds the logic that slashes a Storage Provider, burning up to `amount` of its held deposit, and marks it
+    /// for removal if what remains of its deposit is below the minimum required by the runtime
+    ///
+    /// Returns the amount that was actually slashed, which is capped at the deposit currently held from the Storage Provider
+    pub fn do_slash_provider(
+        provider_id: &HashId<T>,
+        amount: BalanceOf<T>,
+    ) -> Result<BalanceOf<T>, DispatchError> {
+        // Get the account of the Storage Provider to slash
+        let account = if let Some(bsp) = BackupStorageProviders::<T>::get(provider_id) {
+            bsp.owner_account
+        } else if let Some(msp) = MainStorageProviders::<T>::get(provider_id) {
+            msp.owner_account
+        } else {
+            return Err(Error::<T>::NotRegistered.into());
+        };
+
+        // Cap the amount to slash at the deposit currently held from the Storage Provider
+        let current_deposit =
+            T::NativeBalance::balance_on_hold(&HoldReason::StorageProviderDeposit.into(), &account);
+        let amount_to_slash = amount.min(current_deposit);
+
+        // Burn the slashed amount from the held deposit
+        let amount_slashed = T::NativeBalance::burn_held(
+            &HoldReason::StorageProviderDeposit.into(),
+            &account,
+            amount_to_slash,
+            Precision::BestEffort,
+            Fortitude::Force,
+        )?;
+
+        // Mark the Storage Provider for removal if its remaining deposit is below the minimum required by the runtime
+        let marked_for_removal =
+            current_deposit.saturating_sub(amount_slashed) < T::SpMinDeposit::get();
+        if marked_for_removal {
+            InsolventProviders::<T>::insert(provider_id, ());
+        }
+
+        // Penalise the reputation of the Storage Provider
+        Self::do_change_reputation(
+            provider_id,
+            T::SlashReputationPenalty::get().saturating_neg(),
+        )?;
+
+        Self::deposit_event(Event::<T>::ProviderSlashed {
+            provider_id: *provider_id,
+            amount_slashed,
+            marked_for_removal,
+        });
+
+        Ok(amount_slashed)
+    }
+
+    /// This function holds the logic that checks if a user can request to transfer one of its buckets to another Main Storage
+    /// Provider and, if so, records the request in the PendingBucketTransfers mapping so that the new MSP can accept it
+    pub fn do_request_bucket_transfer(
+        who: &T::AccountId,
+        bucket_id: &BucketId<T>,
+        new_msp_id: &MainStorageProviderId<T>,
+    ) -> DispatchResult {
+        // Check that the bucket exists and that the signer owns it
+        let bucket = Buckets::<T>::get(bucket_id).ok_or(Error::<T>::BucketNotFound)?;
+        ensure!(&bucket.user_id == who, Error::<T>::NotBucketOwner);
+
+        // Check that the new MSP is registered and is not the one already storing the bucket
+        ensure!(
+            MainStorageProviders::<T>::contains_key(new_msp_id),
+            Error::<T>::NotRegistered
+        );
+        ensure!(&bucket.msp_id != new_msp_id, Error::<T>::BucketAlreadyInMsp);
+
+        // Record the request, so that the new MSP can accept it
+        PendingBucketTransfers::<T>::insert(bucket_id, new_msp_id);
+
+        Ok(())
+    }
+
+    /// This function holds the logic that checks if a Main Storage Provider can accept the transfer of a bucket to it and,
+    /// if so, moves the bucket and its data from the MSP that was storing it.
+    ///
+    /// Returns the ids of the MSP that was storing the bucket and of the one storing it now.
+    pub fn do_accept_bucket_transfer(
+        who: &T::AccountId,
+        bucket_id: &BucketId<T>,
+    ) -> Result<(MainStorageProviderId<T>, MainStorageProviderId<T>), DispatchError> {
+        // Check that the signer is registered as a MSP and that the transfer of the bucket was requested to it
+        let new_msp_id =
+            AccountIdToMainStorageProviderId::<T>::get(who).ok_or(Error::<T>::NotRegistered)?;
+        let requested_msp_id = PendingBucketTransfers::<T>::get(bucket_id)
+            .ok_or(Error::<T>::BucketTransferNotRequested)?;
+        ensure!(
+            requested_msp_id == new_msp_id,
+            Error::<T>::NotBucketTransferTarget
+        );
+
+        // Check that the bucket still exists and is not already stored by the signer
+        let bucket = Buckets::<T>::get(bucket_id).ok_or(Error::<T>::BucketNotFound)?;
+        ensure!(bucket.msp_id != new_msp_id, Error::<T>::BucketAlreadyInMsp);
+
+        // Add the size of the bucket to the data used by the new MSP, which fails if it does not have enough available
+        // capacity or if it is signing off
+        <Self as MutateProvidersInterface>::increase_data_used(who, bucket.size)?;
+
+        // Remove the size of the bucket from the data used by the old MSP
+        let old_msp_id = bucket.msp_id;
+        MainStorageProviders::<T>::mutate(&old_msp_id, |old_msp| {
+            if let Some(old_msp) = old_msp {
+                old_msp.data_used = old_msp.data_used.saturating_sub(bucket.size);
+            }
+        });
+
+        // Move the bucket to the new MSP
+        PendingBucketTransfers::<T>::remove(bucket_id);
+        BucketsByMsp::<T>::remove(&old_msp_id, bucket_id);
+        BucketsByMsp::<T>::insert(&new_msp_id, bucket_id, ());
+        Buckets::<T>::insert(
+            bucket_id,
+            Bucket {
+                msp_id: new_msp_id,
+                ..bucket
+            },
+        );
+
+        Ok((old_msp_id, new_msp_id))
+    }
+
+    /// This function holds the logic that checks if a Storage Provider can update its multiaddresses and, if so,
+    /// replaces them in its stored information
+    pub fn do_update_multiaddresses(
+        who: &T::AccountId,
+        new_multiaddresses: BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>>,
+    ) -> DispatchResult {
+        // Check that the new multiaddresses vector is not empty (SPs have to be reachable through at least one)
+        ensure!(!new_multiaddresses.is_empty(), Error::<T>::NoMultiAddress);
+
+        // Check that the new multiaddresses are valid
+        Self::ensure_valid_multiaddresses(&new_multiaddresses)?;
+
+        // Check that the signer is registered as a SP and replace its multiaddresses
+        if let Some(msp_id) = AccountIdToMainStorageProviderId::<T>::get(who) {
+            MainStorageProviders::<T>::try_mutate(&msp_id, |msp| -> DispatchResult {
+                let msp = msp.as_mut().ok_or(Error::<T>::NotRegistered)?;
+                msp.multiaddresses = new_multiaddresses;
+                Ok(())
+            })
+        } else if let Some(bsp_id) = AccountIdToBackupStorageProviderId::<T>::get(who) {
+            BackupStorageProviders::<T>::try_mutate(&bsp_id, |bsp| -> DispatchResult {
+                let bsp = bsp.as_mut().ok_or(Error::<T>::NotRegistered)?;
+                bsp.multiaddresses = new_multiaddresses;
+                Ok(())
+            })
+        } else {
+            Err(Error::<T>::NotRegistered.into())
+        }
+    }
+
+    /// This function holds the logic that checks if a user is registered as a Storage Provider and, if so, records
+    /// the current block as the last one in which it was seen
+    pub fn do_heartbeat(who: &T::AccountId) -> DispatchResult {
+        // Check that the signer is registered as a SP and get its ID
+        let provider_id = AccountIdToMainStorageProviderId::<T>::get(who)
+            .or_else(|| AccountIdToBackupStorageProviderId::<T>::get(who))
+            .ok_or(Error::<T>::NotRegistered)?;
+
+        LastSeen::<T>::insert(&provider_id, frame_system::Pallet::<T>::block_number());
+
+        Ok(())
+    }
+
+    /// This function holds the logic that changes the reputation of a registered Storage Provider by `delta`,
+    /// clamping it to the range between MinReputation and MaxReputation, and returns its new reputation
+    pub fn do_change_reputation(
+        provider_id: &HashId<T>,
+        delta: Reputation,
+    ) -> Result<Reputation, DispatchError> {
+        // Check that the Storage Provider is registered
+        ensure!(
+            MainStorageProviders::<T>::contains_key(provider_id)
+                || BackupStorageProviders::<T>::contains_key(provider_id),
+            Error::<T>::NotRegistered
+        );
+
+        let old_reputation = ProviderReputation::<T>::get(provider_id);
+        let new_reputation = old_reputation
+            .saturating_add(delta)
+            .clamp(T::MinReputation::get(), T::MaxReputation::get());
+
+        // Only update the storage and emit the event if the reputation actually changed (it could be at a limit already)
+        if new_reputation != old_reputation {
+            ProviderReputation::<T>::insert(provider_id, new_reputation);
+            Self::deposit_event(Event::<T>::ReputationChanged {
+                provider_id: *provider_id,
+                old_reputation,
+                new_reputation,
+            });
+        }
+
+        Ok(new_reputation)
+    }
+
+    /// This function holds the logic that checks if a user can update its value proposition as a Main Storage Provider
+    /// and, if so, replaces it in the MainStorageProviders mapping
+    pub fn do_update_value_prop(
+        who: &T::AccountId,
+        new_value_prop: ValueProposition<T>,
+    ) -> DispatchResult {
+        // Check that the signer is registered as a MSP
+        let msp_id =
+            AccountIdToMainStorageProviderId::<T>::get(who).ok_or(Error::<T>::NotRegistered)?;
+
+        // Check that the protocols are not empty nor repeated
+        for (index, protocol) in new_value_prop.protocols.iter().enumerate() {
+            ensure!(
+                !protocol.is_empty() && !new_value_prop.protocols[..index].contains(protocol),
+                Error::<T>::InvalidValueProposition
+            );
+        }
+
+        // Replace the value proposition of the MSP
+        MainStorageProviders::<T>::try_mutate(&msp_id, |msp| -> DispatchResult {
+            let msp = msp.as_mut().ok_or(Error::<T>::NotRegistered)?;
+            msp.value_prop = new_value_prop;
+            Ok(())
+        })
+    }
+
     /// This function holds the logic that checks if a user can change its capacity as a Main Storage Provider
     /// and, if so, updates the storage to reflect the new capacity, modifying the user's deposit accordingly
     /// and returning the old capacity if successful
@@ -594,6 +1085,7 @@ where
             // If the new deposit is smaller than the current deposit, some balance has to be released to the user
             Self::release_balance(account_id, current_deposit, new_deposit)?;
         }
+        ProviderDeposits::<T>::insert(account_id, new_deposit);
 
         // Get the MSP's old capacity
         let old_capacity = msp.capacity;
@@ -643,6 +1135,15 @@ where
             Error::<T>::NewCapacityLessThanUsedStorage
         );
 
+        // Check that the new capacity is also enough for the storage requests the BSP volunteered for but has not
+        // confirmed yet, so it can not shrink its capacity to avoid storing them
+        let pending_volunteered_storage =
+            T::StorageCommitments::pending_volunteered_storage(account_id);
+        ensure!(
+            new_capacity >= bsp.data_used.saturating_add(pending_volunteered_storage),
+            Error::<T>::CapacityBelowCommitment
+        );
+
         // Calculate how much deposit will the signer have to pay to register with this amount of data
         let capacity_over_minimum = new_capacity
             .checked_sub(&T::SpMinCapacity::get())
@@ -670,6 +1171,7 @@ where
             // If the new deposit is smaller than the current deposit, some balance has to be released to the user
             Self::release_balance(account_id, current_deposit, new_deposit)?;
         }
+        ProviderDeposits::<T>::insert(account_id, new_deposit);
 
         // Get the BSP's old capacity
         let old_capacity = bsp.capacity;
@@ -772,6 +1274,61 @@ where
 
         Ok(())
     }
+
+    /// Release all the deposit held from `who`, emitting a `PartialDepositReturned` event if less than the deposit
+    /// recorded in [`ProviderDeposits`] could be returned (for example, because part of it was slashed).
+    ///
+    /// Whatever is left of the deposit is released, so a Storage Provider whose deposit was partially consumed can still
+    /// leave the network.
+    fn release_deposit(who: &T::AccountId) -> DispatchResult {
+        let expected = ProviderDeposits::<T>::take(who);
+        let returned = T::NativeBalance::release_all(
+            &HoldReason::StorageProviderDeposit.into(),
+            who,
+            Precision::BestEffort,
+        )?;
+
+        if returned < expected {
+            Self::deposit_event(Event::<T>::PartialDepositReturned {
+                who: who.clone(),
+                returned,
+                expected,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// This function adds `delta` to the data used by a Storage Provider, making sure that the result does not exceed its capacity
+    fn checked_increase_data_used(
+        data_used: StorageData<T>,
+        delta: StorageData<T>,
+        capacity: StorageData<T>,
+    ) -> Result<StorageData<T>, DispatchError> {
+        let new_data_used = data_used
+            .checked_add(&delta)
+            .ok_or(DispatchError::Arithmetic(ArithmeticError::Overflow))?;
+        ensure!(new_data_used <= capacity, Error::<T>::ExceedsCapacity);
+        Ok(new_data_used)
+    }
+
+    /// Check that every multiaddress of a Storage Provider is syntactically valid and that none of them is repeated
+    fn ensure_valid_multiaddresses(
+        multiaddresses: &BoundedVec<MultiAddress<T>, MaxMultiAddressAmount<T>>,
+    ) -> DispatchResult {
+        for (index, multiaddress) in multiaddresses.iter().enumerate() {
+            ensure!(
+                is_valid_multiaddress(multiaddress),
+                Error::<T>::InvalidMultiAddress
+            );
+            ensure!(
+                !multiaddresses[..index].contains(multiaddress),
+                Error::<T>::InvalidMultiAddress
+            );
+        }
+
+        Ok(())
+    }
 }
 
 impl<T: Config> From<MainStorageProvider<T>> for BackupStorageProvider<T> {
@@ -782,6 +1339,7 @@ impl<T: Config> From<MainStorageProvider<T>> for BackupStorageProvider<T> {
             multiaddresses: msp.multiaddresses,
             root: MerklePatriciaRoot::<T>::default(),
             last_capacity_change: msp.last_capacity_change,
+            owner_account: msp.owner_account,
         }
     }
 }
@@ -793,19 +1351,31 @@ impl<T: pallet::Config> MutateProvidersInterface for pallet::Pallet<T> {
     type StorageData = T::StorageData;
     type BucketId = HashId<T>;
     type MerklePatriciaRoot = T::MerklePatriciaRoot;
+    type RootTransitionProof = RootTransitionProof<T>;
 
     fn increase_data_used(who: &T::AccountId, delta: T::StorageData) -> DispatchResult {
-        // TODO: refine this logic, add checks
+        // A Storage Provider that is signing off can not get new storage assigned to it
+        ensure!(
+            !PendingSignOffs::<T>::contains_key(who),
+            Error::<T>::SignOffPending
+        );
         if let Some(msp_id) = AccountIdToMainStorageProviderId::<T>::get(who) {
             let mut msp =
                 MainStorageProviders::<T>::get(&msp_id).ok_or(Error::<T>::NotRegistered)?;
-            msp.data_used = msp.data_used.saturating_add(delta);
+            msp.data_used = Self::checked_increase_data_used(msp.data_used, delta, msp.capacity)?;
             MainStorageProviders::<T>::insert(&msp_id, msp);
         } else if let Some(bsp_id) = AccountIdToBackupStorageProviderId::<T>::get(who) {
             let mut bsp =
                 BackupStorageProviders::<T>::get(&bsp_id).ok_or(Error::<T>::NotRegistered)?;
-            bsp.data_used = bsp.data_used.saturating_add(delta);
+            bsp.data_used = Self::checked_increase_data_used(bsp.data_used, delta, bsp.capacity)?;
             BackupStorageProviders::<T>::insert(&bsp_id, bsp);
+            // The data used by a BSP never exceeds its capacity, so neither does the total
+            UsedBspsCapacity::<T>::try_mutate(|used| -> DispatchResult {
+                *used = used
+                    .checked_add(&delta)
+                    .ok_or(DispatchError::Arithmetic(ArithmeticError::Overflow))?;
+                Ok(())
+            })?;
         } else {
             return Err(Error::<T>::NotRegistered.into());
         }
@@ -813,17 +1383,25 @@ impl<T: pallet::Config> MutateProvidersInterface for pallet::Pallet<T> {
     }
 
     fn decrease_data_used(who: &Self::AccountId, delta: Self::StorageData) -> DispatchResult {
-        // TODO: refine this logic, add checks
+        // A Storage Provider can not release more data than it uses, which would mean it is released twice
         if let Some(msp_id) = AccountIdToMainStorageProviderId::<T>::get(who) {
             let mut msp =
                 MainStorageProviders::<T>::get(&msp_id).ok_or(Error::<T>::NotRegistered)?;
-            msp.data_used = msp.data_used.saturating_sub(delta);
+            msp.data_used = msp
+                .data_used
+                .checked_sub(&delta)
+                .ok_or(DispatchError::Arithmetic(ArithmeticError::Underflow))?;
             MainStorageProviders::<T>::insert(&msp_id, msp);
         } else if let Some(bsp_id) = AccountIdToBackupStorageProviderId::<T>::get(who) {
             let mut bsp =
                 BackupStorageProviders::<T>::get(&bsp_id).ok_or(Error::<T>::NotRegistered)?;
-            bsp.data_used = bsp.data_used.saturating_sub(delta);
+            bsp.data_used = bsp
+                .data_used
+                .checked_sub(&delta)
+                .ok_or(DispatchError::Arithmetic(ArithmeticError::Underflow))?;
             BackupStorageProviders::<T>::insert(&bsp_id, bsp);
+            // The data used by a BSP is part of the total, so the total is at least as big
+            UsedBspsCapacity::<T>::mutate(|used| *used = used.saturating_sub(delta));
         } else {
             return Err(Error::<T>::NotRegistered.into());
         }
@@ -834,25 +1412,92 @@ impl<T: pallet::Config> MutateProvidersInterface for pallet::Pallet<T> {
     fn add_bucket(
         msp_id: MainStorageProviderId<T>,
         user_id: T::AccountId,
-        bucket_id: BucketId<T>,
+        salt: &[u8],
         bucket_root: MerklePatriciaRoot<T>,
-    ) -> DispatchResult {
-        // TODO: Check that the bucket does not exist yet
-        // TODO: Get BucketId by hashing Bucket with salt, add it to the MSP vector of buckets
+        size: StorageData<T>,
+    ) -> Result<BucketId<T>, DispatchError> {
+        // Derive the id of the bucket, checking that it is not already taken
+        let bucket_id = Self::derive_bucket_id(&msp_id, &user_id, salt);
+        ensure!(
+            !Buckets::<T>::contains_key(&bucket_id),
+            Error::<T>::BucketAlreadyExists
+        );
+
+        BucketsByMsp::<T>::insert(&msp_id, &bucket_id, ());
+        BucketsByUser::<T>::insert(&user_id, &bucket_id, ());
         let bucket = Bucket {
             root: bucket_root,
             user_id,
             msp_id,
+            size,
         };
         Buckets::<T>::insert(&bucket_id, &bucket);
-        Ok(())
+        Ok(bucket_id)
+    }
+
+    fn add_file_to_bucket(
+        msp_id: MainStorageProviderId<T>,
+        user_id: T::AccountId,
+        salt: &[u8],
+        size: StorageData<T>,
+        proof: &RootTransitionProof<T>,
+    ) -> Result<BucketId<T>, DispatchError> {
+        // Get the bucket with the derived id, or a new empty one if it does not exist yet
+        let bucket_id = Self::derive_bucket_id(&msp_id, &user_id, salt);
+        let bucket = match Buckets::<T>::get(&bucket_id) {
+            Some(bucket) => {
+                // Only the MSP storing the bucket can add files to it, which may not be the one it was created with
+                // if the bucket was transferred
+                ensure!(bucket.msp_id == msp_id, Error::<T>::NotBucketMsp);
+                bucket
+            }
+            None => {
+                BucketsByMsp::<T>::insert(&msp_id, &bucket_id, ());
+                BucketsByUser::<T>::insert(&user_id, &bucket_id, ());
+                Bucket {
+                    root: MerklePatriciaRoot::<T>::default(),
+                    user_id,
+                    msp_id,
+                    size: StorageData::<T>::zero(),
+                }
+            }
+        };
+
+        // The new root is computed from the current one, so the committed state of the bucket can not be
+        // arbitrarily rewritten
+        let new_root = T::RootTransitionVerifier::apply_transition(&bucket.root, proof)
+            .map_err(|_| Error::<T>::InvalidRootTransition)?;
+        let new_size = bucket
+            .size
+            .checked_add(&size)
+            .ok_or(ArithmeticError::Overflow)?;
+
+        Buckets::<T>::insert(
+            &bucket_id,
+            Bucket {
+                root: new_root,
+                size: new_size,
+                ..bucket
+            },
+        );
+        Ok(bucket_id)
     }
 
     fn change_root_bucket(
+        msp_id: MainStorageProviderId<T>,
         bucket_id: BucketId<T>,
         new_root: MerklePatriciaRoot<T>,
+        proof: &RootTransitionProof<T>,
     ) -> DispatchResult {
         if let Some(bucket) = Buckets::<T>::get(&bucket_id) {
+            // Only the MSP storing the bucket can change its root
+            ensure!(bucket.msp_id == msp_id, Error::<T>::NotBucketMsp);
+
+            // Check that the new root is a valid successor of the current one, so the committed state of the bucket
+            // can not be arbitrarily rewritten
+            T::RootTransitionVerifier::verify_transition(&bucket.root, &new_root, proof)
+                .map_err(|_| Error::<T>::InvalidRootTransition)?;
+
             Buckets::<T>::insert(
                 &bucket_id,
                 Bucket {
@@ -867,16 +1512,25 @@ impl<T: pallet::Config> MutateProvidersInterface for pallet::Pallet<T> {
     }
 
     fn remove_root_bucket(bucket_id: BucketId<T>) -> DispatchResult {
-        Buckets::<T>::remove(&bucket_id);
+        if let Some(bucket) = Buckets::<T>::take(&bucket_id) {
+            BucketsByMsp::<T>::remove(&bucket.msp_id, &bucket_id);
+            BucketsByUser::<T>::remove(&bucket.user_id, &bucket_id);
+            PendingBucketTransfers::<T>::remove(&bucket_id);
+        }
         Ok(())
     }
 
     // BSP specific functions:
     fn change_root_bsp(
         who: BackupStorageProviderId<T>,
-        new_root: MerklePatriciaRoot<T>,
-    ) -> DispatchResult {
+        proof: &RootTransitionProof<T>,
+    ) -> Result<MerklePatriciaRoot<T>, DispatchError> {
         if let Some(b) = BackupStorageProviders::<T>::get(&who) {
+            // The new root is computed from the current one, so the committed state of the BSP can not be
+            // arbitrarily rewritten
+            let new_root = T::RootTransitionVerifier::apply_transition(&b.root, proof)
+                .map_err(|_| Error::<T>::InvalidRootTransition)?;
+
             BackupStorageProviders::<T>::insert(
                 who,
                 BackupStorageProvider {
@@ -884,10 +1538,15 @@ impl<T: pallet::Config> MutateProvidersInterface for pallet::Pallet<T> {
                     ..b
                 },
             );
+
+            Ok(new_root)
         } else {
-            return Err(Error::<T>::NotRegistered.into());
+            Err(Error::<T>::NotRegistered.into())
         }
-        Ok(())
+    }
+
+    fn change_reputation(who: &HashId<T>, delta: Reputation) -> Result<Reputation, DispatchError> {
+        Self::do_change_reputation(who, delta)
     }
 }
 
@@ -895,6 +1554,7 @@ impl<T: pallet::Config> ReadProvidersInterface for pallet::Pallet<T> {
     type SpCount = T::SpCount;
     type MultiAddress = MultiAddress<T>;
     type MaxNumberOfMultiAddresses = T::MaxMultiAddressAmount;
+    type StorageData = T::StorageData;
 
     fn is_bsp(who: &Self::Provider) -> bool {
         BackupStorageProviders::<T>::contains_key(&who)
@@ -904,6 +1564,23 @@ impl<T: pallet::Config> ReadProvidersInterface for pallet::Pallet<T> {
         MainStorageProviders::<T>::contains_key(&who)
     }
 
+    fn get_owner_account(who: &Self::Provider) -> Option<Self::AccountId> {
+        if let Some(bsp) = BackupStorageProviders::<T>::get(&who) {
+            Some(bsp.owner_account)
+        } else if let Some(msp) = MainStorageProviders::<T>::get(&who) {
+            Some(msp.owner_account)
+        } else {
+            None
+        }
+    }
+
+    fn is_signing_off(who: &Self::Provider) -> bool {
+        let Some(owner_account) = Self::get_owner_account(who) else {
+            return false;
+        };
+        PendingSignOffs::<T>::contains_key(&owner_account)
+    }
+
     fn get_number_of_bsps() -> Self::SpCount {
         Self::get_bsp_count()
     }
@@ -918,6 +1595,34 @@ impl<T: pallet::Config> ReadProvidersInterface for pallet::Pallet<T> {
             Err(Error::<T>::NotRegistered.into())
         }
     }
+
+    fn get_capacity(who: &Self::Provider) -> Option<Self::StorageData> {
+        if let Some(msp) = MainStorageProviders::<T>::get(who) {
+            Some(msp.capacity)
+        } else if let Some(bsp) = BackupStorageProviders::<T>::get(who) {
+            Some(bsp.capacity)
+        } else {
+            None
+        }
+    }
+
+    fn get_available_capacity(who: &Self::Provider) -> Option<Self::StorageData> {
+        if let Some(msp) = MainStorageProviders::<T>::get(who) {
+            Some(msp.capacity.saturating_sub(msp.data_used))
+        } else if let Some(bsp) = BackupStorageProviders::<T>::get(who) {
+            Some(bsp.capacity.saturating_sub(bsp.data_used))
+        } else {
+            None
+        }
+    }
+
+    fn total_capacity() -> Self::StorageData {
+        Self::get_total_bsp_capacity()
+    }
+
+    fn used_capacity() -> Self::StorageData {
+        Self::get_used_bsp_capacity()
+    }
 }
 
 impl<T: pallet::Config> ProvidersInterface for pallet::Pallet<T> {
@@ -954,14 +1659,24 @@ impl<T: pallet::Config> ProvidersInterface for pallet::Pallet<T> {
     }
 
     fn get_stake(who: Self::Provider) -> Option<BalanceOf<T>> {
-        // TODO: This is not the stake, this logic will be done later down the line
-        if let Some(bucket) = Buckets::<T>::get(&who) {
-            let _related_msp = MainStorageProviders::<T>::get(bucket.msp_id);
-            Some(T::SpMinDeposit::get())
-        } else if let Some(_bsp) = BackupStorageProviders::<T>::get(&who) {
-            Some(T::SpMinDeposit::get())
+        // The stake of a Storage Provider is the deposit held from its account, and the stake of a bucket is the one of its MSP
+        let account = if let Some(bucket) = Buckets::<T>::get(&who) {
+            MainStorageProviders::<T>::get(bucket.msp_id)?.owner_account
+        } else if let Some(bsp) = BackupStorageProviders::<T>::get(&who) {
+            bsp.owner_account
+        } else if let Some(msp) = MainStorageProviders::<T>::get(&who) {
+            msp.owner_account
         } else {
-            None
-        }
+            return None;
+        };
+
+        Some(T::NativeBalance::balance_on_hold(
+            &HoldReason::StorageProviderDeposit.into(),
+            &account,
+        ))
+    }
+
+    fn slash(who: &Self::Provider, amount: BalanceOf<T>) -> Result<BalanceOf<T>, DispatchError> {
+        Self::do_slash_provider(who, amount)
     }
 }
@@ -0,0 +1,56 @@
+//! Benchmarking setup for pallet-randomness
+
+use super::*;
+
+#[allow(unused)]
+use crate::Pallet as Randomness;
+use frame_benchmarking::{benchmarks, impl_benchmark_test_suite};
+use frame_support::{
+    traits::{Get, Hooks},
+    BoundedVec,
+};
+use frame_system::{pallet_prelude::BlockNumberFor, RawOrigin};
+use sp_runtime::traits::Zero;
+use sp_std::vec::Vec;
+
+/// Fill the randomness history up to its bound, which is the worst case for both pushing a new seed
+/// and pruning the history
+fn fill_randomness_history<T: Config>() {
+    let history: Vec<_> = (0..RandomnessHistoryBound::<T>::get() as u64)
+        .map(|epoch| (epoch, T::Hash::default(), BlockNumberFor::<T>::zero()))
+        .collect();
+    RandomnessHistory::<T>::put(BoundedVec::truncate_from(history));
+}
+
+benchmarks! {
+    set_babe_randomness {
+        // Make sure the relay epoch changed so a new seed is processed and stored
+        RelayEpoch::<T>::put(0);
+        fill_randomness_history::<T>();
+        RandomnessHistory::<T>::mutate(|history| history.pop());
+    }: _(RawOrigin::None)
+    verify {
+        assert!(Randomness::<T>::inherent_included().is_some());
+    }
+
+    on_finalize {
+        InherentIncluded::<T>::put(());
+        fill_randomness_history::<T>();
+        let block_number = frame_system::Pallet::<T>::block_number();
+    }: {
+        Randomness::<T>::on_finalize(block_number);
+    }
+    verify {
+        assert!(Randomness::<T>::inherent_included().is_none());
+        assert_eq!(
+            Randomness::<T>::randomness_history().len(),
+            T::RandomnessHistoryLength::get() as usize
+        );
+    }
+}
+
+impl_benchmark_test_suite!(
+    Randomness,
+    crate::mock::ExtBuilder::build(),
+    crate::mock::Test
+);
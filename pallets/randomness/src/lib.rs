@@ -15,28 +15,48 @@
 //!
 //! `Config::BabeDataGetter` is responsible for reading the epoch index and epoch randomness
 //! from the relay chain state proof
+//!
+//! ## Fallback Randomness
+//! If the BABE epoch randomness is not available for a new epoch, `Config::FallbackRandomness` is used
+//! as the source of randomness for that epoch instead. The source of the latest random seed is stored
+//! alongside it, so consumers can know whether it came from BABE or from the fallback source.
+//!
+//! ## Randomness History
+//! The random seeds of the last `Config::RandomnessHistoryLength` processed epochs are kept in the
+//! `RandomnessHistory` storage, so consumers that missed the window of the latest seed can still
+//! resolve their commitments deterministically through `random_at_epoch`. Older entries are pruned
+//! in `on_finalize`.
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use frame_support::pallet;
 pub use pallet::*;
+pub use weights::WeightInfo;
 
 #[cfg(test)]
 mod mock;
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+pub mod weights;
+
+#[cfg(feature = "runtime-api")]
+pub mod runtime_api;
+
 /// Read babe randomness info from the relay chain state proof
 pub trait GetBabeData<EpochIndex, Randomness> {
     fn get_epoch_index() -> EpochIndex;
     fn get_epoch_randomness() -> Randomness;
+    /// Get the block number of the current relay parent
+    fn get_relay_block_number() -> u32;
 }
 
 #[pallet]
 pub mod pallet {
     use super::*;
-    use frame_support::pallet_prelude::*;
+    use frame_support::{pallet_prelude::*, traits::Randomness};
     use frame_system::pallet_prelude::{BlockNumberFor, *};
-    use frame_system::WeightInfo;
     use scale_info::prelude::vec::Vec;
     use session_keys_primitives::{InherentError, INHERENT_IDENTIFIER};
     use sp_runtime::traits::{Hash, Saturating};
@@ -44,6 +64,18 @@ pub mod pallet {
     #[pallet::pallet]
     pub struct Pallet<T>(PhantomData<T>);
 
+    /// The source a random seed was obtained from.
+    #[derive(
+        Encode, Decode, MaxEncodedLen, TypeInfo, Debug, PartialEq, Eq, Clone, Copy, Default,
+    )]
+    pub enum RandomnessSource {
+        /// The BABE epoch randomness of the relay chain.
+        #[default]
+        Babe,
+        /// The configured fallback randomness source, used when the BABE epoch randomness is unavailable.
+        Fallback,
+    }
+
     /// Configuration trait of this pallet.
     #[pallet::config]
     pub trait Config: frame_system::Config {
@@ -53,10 +85,26 @@ pub mod pallet {
         /// Get the BABE data from the runtime
         type BabeDataGetter: GetBabeData<u64, Option<Self::Hash>>;
 
+        /// Source of randomness used for an epoch when the BABE epoch randomness is unavailable
+        type FallbackRandomness: Randomness<Self::Hash, BlockNumberFor<Self>>;
+
+        /// Number of processed epochs whose random seeds are kept in `RandomnessHistory`
+        #[pallet::constant]
+        type RandomnessHistoryLength: Get<u32>;
+
         /// Weight info
         type WeightInfo: WeightInfo;
     }
 
+    /// Bound of the `RandomnessHistory` storage. One more than `Config::RandomnessHistoryLength`, since a new
+    /// seed is pushed during the block before the oldest one is pruned in `on_finalize`.
+    pub struct RandomnessHistoryBound<T>(PhantomData<T>);
+    impl<T: Config> Get<u32> for RandomnessHistoryBound<T> {
+        fn get() -> u32 {
+            T::RandomnessHistoryLength::get().saturating_add(1)
+        }
+    }
+
     #[pallet::event]
     #[pallet::generate_deposit(pub(crate) fn deposit_event)]
     pub enum Event<T: Config> {
@@ -65,13 +113,35 @@ pub mod pallet {
             randomness_seed: T::Hash,
             from_epoch: u64,
             valid_until_block: BlockNumberFor<T>,
+            source: RandomnessSource,
         },
+
+        /// Event emitted when the BABE epoch randomness of a new relay chain epoch is unavailable, so
+        /// monitoring can detect missed epochs. The fallback randomness source is used for that epoch, if available
+        RandomnessUnavailable { epoch: u64 },
     }
 
     /// Latest random seed obtained from BABE and the latest block that it can process randomness requests from
     #[pallet::storage]
     pub type LatestBabeRandomness<T: Config> = StorageValue<_, (T::Hash, BlockNumberFor<T>)>;
 
+    /// Source of the latest random seed, stored alongside it in `LatestBabeRandomness`
+    #[pallet::storage]
+    pub type LatestRandomnessSource<T: Config> = StorageValue<_, RandomnessSource, ValueQuery>;
+
+    /// Random seeds of the latest processed epochs, with the epoch index they were obtained for and
+    /// the latest block that they can process randomness requests from, ordered from oldest to newest
+    #[pallet::storage]
+    pub type RandomnessHistory<T: Config> = StorageValue<
+        _,
+        BoundedVec<(u64, T::Hash, BlockNumberFor<T>), RandomnessHistoryBound<T>>,
+        ValueQuery,
+    >;
+
+    /// Relay parent block number of the last block in which the mandatory inherent was included
+    #[pallet::storage]
+    pub(crate) type LastRelayBlockNumber<T: Config> = StorageValue<_, u32, ValueQuery>;
+
     /// Current relay epoch
     #[pallet::storage]
     pub(crate) type RelayEpoch<T: Config> = StorageValue<_, u64, ValueQuery>;
@@ -87,7 +157,7 @@ pub mod pallet {
         /// the previous relay chain epoch
         #[pallet::call_index(0)]
         #[pallet::weight((
-			Weight::from_parts(10_000, 0) + T::DbWeight::get().writes(1),
+			T::WeightInfo::set_babe_randomness(),
 			DispatchClass::Mandatory
 		))]
         pub fn set_babe_randomness(origin: OriginFor<T>) -> DispatchResultWithPostInfo {
@@ -100,19 +170,77 @@ pub mod pallet {
             // Get the current epoch of the relay chain
             let relay_epoch_index = T::BabeDataGetter::get_epoch_index();
 
+            // Get the relay parent block number of this block and the one of the previous block
+            let relay_block_number = T::BabeDataGetter::get_relay_block_number();
+            let last_relay_block_number = <LastRelayBlockNumber<T>>::get();
+
             // If the current epoch is greater than the one for which the randomness was last processed for
             if relay_epoch_index > last_relay_epoch_index {
-                // Get the new randomness of this new epoch
-                if let Some(randomness) = T::BabeDataGetter::get_epoch_randomness() {
-                    // The latest BABE randomness is predictable during the current epoch and this inherent
-                    // must be executed and included in every block, which means that iff this logic is being
-                    // executed, the epoch JUST changed, so the obtained randomness is valid for every previous block.
-                    // TODO: add logic to check parent relay block (ideally, we make it valid for `curr_relay_block - 2`)
-                    let latest_valid_block = frame_system::Pallet::<T>::block_number()
-                        .saturating_sub(sp_runtime::traits::One::one());
+                // The latest BABE randomness is predictable during the current epoch and this inherent
+                // must be executed and included in every block, which means that iff this logic is being
+                // executed, the epoch JUST changed. The obtained randomness is only unpredictable for commitments
+                // made on top of relay blocks up to `relay_block_number - 2`, so if the previous block was built
+                // on top of a more recent relay block, its commitments could already know the seed and it is
+                // excluded from the validity window as well.
+                let blocks_to_exclude: BlockNumberFor<T> =
+                    if last_relay_block_number.saturating_add(2) <= relay_block_number {
+                        1u32.into()
+                    } else {
+                        2u32.into()
+                    };
+                let latest_valid_block =
+                    frame_system::Pallet::<T>::block_number().saturating_sub(blocks_to_exclude);
+
+                // Get the new randomness of this new epoch, falling back to the configured source if BABE's is unavailable
+                let new_randomness = match T::BabeDataGetter::get_epoch_randomness() {
+                    Some(randomness) => {
+                        Some((randomness, latest_valid_block, RandomnessSource::Babe))
+                    }
+                    None => {
+                        log::warn!(
+                            "Failed to fill BABE epoch randomness for epoch {:?}, using fallback randomness",
+                            relay_epoch_index
+                        );
+
+                        // Emit an event signaling that the BABE randomness of this epoch is missing
+                        Self::deposit_event(Event::RandomnessUnavailable {
+                            epoch: relay_epoch_index,
+                        });
 
+                        let (randomness, fallback_valid_block) =
+                            T::FallbackRandomness::random(&relay_epoch_index.to_le_bytes());
+
+                        // A default hash means that the fallback source could not produce usable entropy either
+                        if randomness == T::Hash::default() {
+                            None
+                        } else {
+                            Some((
+                                randomness,
+                                fallback_valid_block.min(latest_valid_block),
+                                RandomnessSource::Fallback,
+                            ))
+                        }
+                    }
+                };
+
+                if let Some((randomness, valid_until_block, source)) = new_randomness {
                     // Save it to be readily available for use
-                    LatestBabeRandomness::<T>::put((randomness, latest_valid_block));
+                    LatestBabeRandomness::<T>::put((randomness, valid_until_block));
+                    LatestRandomnessSource::<T>::put(source);
+
+                    // Keep it in the history as well. This never fails since the history is pruned to
+                    // `RandomnessHistoryLength` entries on every block and a seed is added at most once per block.
+                    RandomnessHistory::<T>::mutate(|history| {
+                        if history
+                            .try_push((relay_epoch_index, randomness, valid_until_block))
+                            .is_err()
+                        {
+                            log::error!(
+                                "Randomness history is full, failed to keep the randomness of epoch {:?}",
+                                relay_epoch_index
+                            );
+                        }
+                    });
 
                     // Update storage with the latest epoch for which randomness was processed for
                     <RelayEpoch<T>>::put(relay_epoch_index);
@@ -121,17 +249,21 @@ pub mod pallet {
                     Self::deposit_event(Event::NewRandomnessAvailable {
                         randomness_seed: randomness,
                         from_epoch: relay_epoch_index,
-                        valid_until_block: latest_valid_block,
+                        valid_until_block,
+                        source,
                     });
                 } else {
                     log::warn!(
-                        "Failed to fill BABE epoch randomness \
+                        "Failed to fill epoch randomness from both BABE and the fallback source \
 							REQUIRE HOTFIX TO FILL EPOCH RANDOMNESS FOR EPOCH {:?}",
                         relay_epoch_index
                     );
                 }
             }
 
+            // Keep the relay parent block number of this block to compute the validity window of the next epoch
+            <LastRelayBlockNumber<T>>::put(relay_block_number);
+
             // Update storage to reflect that this inherent was included in the block (so the block is valid)
             <InherentIncluded<T>>::put(());
 
@@ -170,12 +302,12 @@ pub mod pallet {
     impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
         /// This hook is called on block initialization and returns the Weight of the `on_finalize` hook to
         /// let block builders know how much weight to reserve for it
-        /// TODO: Benchmark on_finalize to get its weight and replace the placeholder weight for that
         fn on_initialize(_now: BlockNumberFor<T>) -> Weight {
-            Weight::from_parts(10_000, 0) + T::DbWeight::get().reads_writes(1, 1)
+            T::WeightInfo::on_finalize()
         }
         /// This hook checks, on block finalization, that the required inherent was included and clears
-        /// storage to make it necessary to include it in future blocks as well
+        /// storage to make it necessary to include it in future blocks as well. It also prunes the
+        /// randomness history so it only keeps the seeds of the last `RandomnessHistoryLength` epochs
         fn on_finalize(_now: BlockNumberFor<T>) {
             // Ensure the mandatory inherent was included in the block or the block is invalid
             // We use take() to make sure this is storage is not set for the next block
@@ -183,6 +315,15 @@ pub mod pallet {
 				<InherentIncluded<T>>::take().is_some(),
 				"Mandatory randomness inherent not included; InherentIncluded storage item is empty"
 			);
+
+            // Prune the oldest entries of the randomness history that exceed its configured length
+            let history_length = T::RandomnessHistoryLength::get() as usize;
+            RandomnessHistory::<T>::mutate(|history| {
+                let excess = history.len().saturating_sub(history_length);
+                if excess > 0 {
+                    history.drain(..excess);
+                }
+            });
         }
     }
 
@@ -197,13 +338,7 @@ pub mod pallet {
         fn random(subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
             // If there's randomness available
             if let Some((babe_randomness, latest_valid_block)) = LatestBabeRandomness::<T>::get() {
-                let hashed_subject = T::Hashing::hash(subject);
-                let mut digest = Vec::new();
-                // Concatenate the latest randomness with the hashed subject
-                digest.extend_from_slice(babe_randomness.as_ref());
-                digest.extend_from_slice(hashed_subject.as_ref());
-                // Hash it
-                let randomness = T::Hashing::hash(digest.as_slice());
+                let randomness = Pallet::<T>::hash_with_seed(&babe_randomness, subject);
                 // Return the randomness for this subject and the latest block for which this randomness is useful
                 // `subject` commitments done after `latest_valid_block` are predictable, and as such MUST be discarded
                 (randomness, latest_valid_block)
@@ -216,6 +351,53 @@ pub mod pallet {
         }
     }
 
+    impl<T: Config> Pallet<T> {
+        /// Same as `random`, but the `subject` is tagged with a `domain` separator before being hashed
+        ///
+        /// Consumers (e.g. different pallets) using different domains get unrelated randomness even for identical
+        /// subjects. The domain and the subject are SCALE encoded together, which length-prefixes both, so no pair of
+        /// `domain` and `subject` yields the same randomness as another one.
+        pub fn random_with_domain(domain: &[u8], subject: &[u8]) -> (T::Hash, BlockNumberFor<T>) {
+            <Self as Randomness<T::Hash, BlockNumberFor<T>>>::random(&(domain, subject).encode())
+        }
+
+        /// Same as `random`, but using the latest seed regardless of the latest block for which it's valid
+        ///
+        /// `subject` commitments done after that block are predictable, so this MUST only be used by consumers for
+        /// which predictable randomness is acceptable, e.g. to keep degraded functionality while no new seed is
+        /// available. Every other consumer should use `random` and discard randomness that is no longer valid
+        ///
+        /// Returns `None` if no seed is available
+        pub fn random_unchecked(subject: &[u8]) -> Option<T::Hash> {
+            Self::latest_seed_unchecked().map(|seed| Self::hash_with_seed(&seed, subject))
+        }
+
+        /// Uses the random seed obtained for a past epoch that is still kept in the randomness history
+        /// to generate a random seed for the `subject`, following the same logic as `random`
+        ///
+        /// Returns the randomness for this subject and the latest block for which the seed of that
+        /// epoch was valid, or `None` if the epoch is not in the randomness history
+        pub fn random_at_epoch(subject: &[u8], epoch: u64) -> Option<(T::Hash, BlockNumberFor<T>)> {
+            RandomnessHistory::<T>::get()
+                .into_iter()
+                .find(|(history_epoch, _, _)| *history_epoch == epoch)
+                .map(|(_, seed, latest_valid_block)| {
+                    (Self::hash_with_seed(&seed, subject), latest_valid_block)
+                })
+        }
+
+        /// Concatenates the seed with the hashed subject and hashes the result
+        fn hash_with_seed(seed: &T::Hash, subject: &[u8]) -> T::Hash {
+            let hashed_subject = T::Hashing::hash(subject);
+            let mut digest = Vec::new();
+            // Concatenate the seed with the hashed subject
+            digest.extend_from_slice(seed.as_ref());
+            digest.extend_from_slice(hashed_subject.as_ref());
+            // Hash it
+            T::Hashing::hash(digest.as_slice())
+        }
+    }
+
     // Read-only functions
     impl<T: Config> Pallet<T> {
         /// Get the latest BABE randomness seed and the latest block for which it's valid
@@ -223,11 +405,43 @@ pub mod pallet {
             LatestBabeRandomness::<T>::get()
         }
 
+        /// Get the latest random seed, without the latest block for which it's valid
+        ///
+        /// See `random_unchecked` for when using a seed that may no longer be valid is acceptable
+        pub fn latest_seed_unchecked() -> Option<T::Hash> {
+            LatestBabeRandomness::<T>::get().map(|(seed, _)| seed)
+        }
+
+        /// Get the source of the latest random seed
+        pub fn latest_randomness_source() -> RandomnessSource {
+            LatestRandomnessSource::<T>::get()
+        }
+
         /// Get the latest relay epoch processed
         pub fn relay_epoch() -> u64 {
             RelayEpoch::<T>::get()
         }
 
+        /// Get the (oldest, newest) relay epoch indices whose randomness is still retained by this pallet
+        ///
+        /// A commitment whose epoch falls outside of this window can no longer be resolved.
+        pub fn retained_epochs() -> (u64, u64) {
+            let history = RandomnessHistory::<T>::get();
+            match (history.first(), history.last()) {
+                (Some((oldest, _, _)), Some((newest, _, _))) => (*oldest, *newest),
+                _ => {
+                    let newest = RelayEpoch::<T>::get();
+                    (newest, newest)
+                }
+            }
+        }
+
+        /// Get the random seeds kept for the latest processed epochs
+        pub fn randomness_history(
+        ) -> BoundedVec<(u64, T::Hash, BlockNumberFor<T>), RandomnessHistoryBound<T>> {
+            RandomnessHistory::<T>::get()
+        }
+
         /// Get the variable that's used to check if the mandatory BABE inherent was included in the block
         pub fn inherent_included() -> Option<()> {
             InherentIncluded::<T>::get()
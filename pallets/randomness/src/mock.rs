@@ -2,7 +2,9 @@
 use super::*;
 use crate as pallet_randomness;
 use frame_support::{
-    construct_runtime, derive_impl, parameter_types, traits::Everything, weights::Weight,
+    construct_runtime, derive_impl, parameter_types,
+    traits::{ConstU32, Everything},
+    weights::Weight,
 };
 use sp_core::{blake2_256, H160, H256};
 use sp_runtime::{
@@ -80,24 +82,72 @@ impl pallet_balances::Config for Test {
     type RuntimeFreezeReason = ();
 }
 
+parameter_types! {
+    /// Whether the mocked BABE epoch randomness is available
+    pub static BabeRandomnessAvailable: bool = true;
+    /// Whether the mocked fallback randomness produces usable entropy
+    pub static FallbackRandomnessAvailable: bool = true;
+    /// Number of relay blocks produced for each parachain block in the mock
+    pub static RelayBlocksPerBlock: u64 = 2;
+}
+
 pub struct BabeDataGetter;
 impl crate::GetBabeData<u64, Option<H256>> for BabeDataGetter {
     fn get_epoch_index() -> u64 {
         frame_system::Pallet::<Test>::block_number()
     }
     fn get_epoch_randomness() -> Option<H256> {
+        if !BabeRandomnessAvailable::get() {
+            return None;
+        }
         Some(H256::from_slice(&blake2_256(
             &Self::get_epoch_index().to_le_bytes(),
         )))
     }
+    fn get_relay_block_number() -> u32 {
+        (frame_system::Pallet::<Test>::block_number() * RelayBlocksPerBlock::get()) as u32
+    }
+}
+
+/// Mocked fallback randomness, which is the Blake2 256 bit hash of the subject and is valid until
+/// two blocks before the current one
+pub struct FallbackRandomness;
+impl frame_support::traits::Randomness<H256, u64> for FallbackRandomness {
+    fn random(subject: &[u8]) -> (H256, u64) {
+        if !FallbackRandomnessAvailable::get() {
+            return (H256::default(), 0);
+        }
+        (
+            H256::from_slice(&blake2_256(subject)),
+            frame_system::Pallet::<Test>::block_number().saturating_sub(2),
+        )
+    }
 }
 
 impl Config for Test {
     type RuntimeEvent = RuntimeEvent;
     type BabeDataGetter = BabeDataGetter;
+    type FallbackRandomness = FallbackRandomness;
+    type RandomnessHistoryLength = ConstU32<3>;
     type WeightInfo = ();
 }
 
+/// Mocked runtime API implementation backed by the pallet's read-only functions
+#[cfg(feature = "runtime-api")]
+pub struct MockRuntimeApi;
+#[cfg(feature = "runtime-api")]
+sp_api::mock_impl_runtime_apis! {
+    impl crate::runtime_api::RandomnessApi<Block, H256, u64> for MockRuntimeApi {
+        fn latest_randomness() -> Option<(H256, u64)> {
+            Randomness::latest_babe_randomness()
+        }
+
+        fn relay_epoch() -> u64 {
+            Randomness::relay_epoch()
+        }
+    }
+}
+
 /// Panics if an event is not found in the system log of events
 #[macro_export]
 macro_rules! assert_event_emitted {
@@ -0,0 +1,20 @@
+//! Runtime API to query the randomness provided by this pallet
+//!
+//! Allows external tooling and node-side tasks to read the latest random seed and its validity window
+//! without reaching into the pallet storage directly.
+use codec::Codec;
+
+sp_api::decl_runtime_apis! {
+    /// API to query the latest randomness and the relay epoch it was obtained from
+    pub trait RandomnessApi<Hash, BlockNumber>
+    where
+        Hash: Codec,
+        BlockNumber: Codec,
+    {
+        /// Get the latest random seed and the latest block for which it's valid
+        fn latest_randomness() -> Option<(Hash, BlockNumber)>;
+
+        /// Get the latest relay epoch processed
+        fn relay_epoch() -> u64;
+    }
+}
@@ -1,7 +1,7 @@
-use crate::{mock::*, Event};
+use crate::{mock::*, Event, RandomnessSource};
 use frame_support::{
     assert_ok,
-    pallet_prelude::Weight,
+    pallet_prelude::{Get, Weight},
     traits::{OnFinalize, OnIdle, OnInitialize},
 };
 use sp_core::blake2_256;
@@ -54,6 +54,7 @@ fn set_babe_randomness_works() {
                 )),
                 from_epoch: 1,
                 valid_until_block: System::block_number() - 1,
+                source: RandomnessSource::Babe,
             }
             .into(),
         );
@@ -109,6 +110,7 @@ fn set_babe_randomness_works_after_a_few_epochs() {
                     )),
                     from_epoch: i,
                     valid_until_block: System::block_number() - 1,
+                    source: RandomnessSource::Babe,
                 }
                 .into(),
             );
@@ -124,3 +126,261 @@ fn set_babe_randomness_works_after_a_few_epochs() {
         }
     });
 }
+
+#[test]
+fn set_babe_randomness_excludes_blocks_built_on_recent_relay_blocks() {
+    ExtBuilder::build().execute_with(|| {
+        // Parachain blocks are built on top of consecutive relay blocks, so the previous block
+        // was always built less than two relay blocks ago
+        RelayBlocksPerBlock::set(1);
+
+        for _ in 1..4 {
+            assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+            // The randomness should not be valid for the previous block
+            let (_, valid_until_block) =
+                Randomness::latest_babe_randomness().expect("Randomness should be set");
+            assert_eq!(valid_until_block, System::block_number().saturating_sub(2));
+
+            // Advance a block, which for the mock is also advancing an epoch
+            AllPalletsWithSystem::on_finalize(System::block_number());
+            System::set_block_number(System::block_number() + 1);
+            AllPalletsWithSystem::on_initialize(System::block_number());
+        }
+
+        // Once the relay chain advances more than two blocks since the previous block, it is valid again
+        RelayBlocksPerBlock::set(3);
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+        let (_, valid_until_block) =
+            Randomness::latest_babe_randomness().expect("Randomness should be set");
+        assert_eq!(valid_until_block, System::block_number() - 1);
+    });
+}
+
+#[test]
+fn set_babe_randomness_uses_fallback_when_babe_unavailable() {
+    ExtBuilder::build().execute_with(|| {
+        BabeRandomnessAvailable::set(false);
+        System::set_block_number(5);
+
+        // Include the inherent in the block to set the randomness.
+        // For mock, the fallback randomness is the Blake2 256 bit hash of the relay epoch
+        // and its valid block is current block number - 2
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+        // The epoch should have been processed using the fallback randomness.
+        let last_processed_relay_epoch = Randomness::relay_epoch();
+        assert_eq!(last_processed_relay_epoch, 5);
+
+        let fallback_randomness =
+            H256::from_slice(&blake2_256(&last_processed_relay_epoch.to_le_bytes()));
+        assert_eq!(
+            Randomness::latest_babe_randomness(),
+            Some((fallback_randomness, System::block_number() - 2))
+        );
+        assert_eq!(
+            Randomness::latest_randomness_source(),
+            RandomnessSource::Fallback
+        );
+
+        // Check that the event was emitted with the fallback source
+        System::assert_last_event(
+            Event::<Test>::NewRandomnessAvailable {
+                randomness_seed: fallback_randomness,
+                from_epoch: 5,
+                valid_until_block: System::block_number() - 2,
+                source: RandomnessSource::Fallback,
+            }
+            .into(),
+        );
+    });
+}
+
+#[test]
+fn set_babe_randomness_emits_event_when_babe_unavailable() {
+    ExtBuilder::build().execute_with(|| {
+        BabeRandomnessAvailable::set(false);
+        FallbackRandomnessAvailable::set(false);
+        System::set_block_number(5);
+
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+        // Check that the missing randomness of the new epoch was signaled
+        System::assert_last_event(Event::<Test>::RandomnessUnavailable { epoch: 5 }.into());
+
+        // The event is not emitted when the BABE randomness is available
+        BabeRandomnessAvailable::set(true);
+        System::reset_events();
+        System::set_block_number(6);
+
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+        assert!(
+            !System::events()
+                .iter()
+                .any(|record| record.event
+                    == Event::<Test>::RandomnessUnavailable { epoch: 6 }.into())
+        );
+    });
+}
+
+#[test]
+fn random_is_invalid_when_no_source_is_available() {
+    use frame_support::traits::Randomness as RandomnessT;
+
+    ExtBuilder::build().execute_with(|| {
+        BabeRandomnessAvailable::set(false);
+        FallbackRandomnessAvailable::set(false);
+
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+        // No randomness should have been stored and the epoch should not be marked as processed.
+        assert_eq!(Randomness::latest_babe_randomness(), None);
+        assert_eq!(Randomness::relay_epoch(), 0);
+
+        // The randomness returned must be invalid for every block.
+        let (randomness, valid_until_block) = Randomness::random(b"subject");
+        assert_eq!(randomness, H256::default());
+        assert_eq!(valid_until_block, 0);
+    });
+}
+
+#[test]
+fn random_with_domain_separates_identical_subjects() {
+    use frame_support::traits::Randomness as RandomnessT;
+
+    ExtBuilder::build().execute_with(|| {
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+        let subject = b"subject";
+        let (first_randomness, first_valid_until_block) =
+            Randomness::random_with_domain(b"first-domain", subject);
+        let (second_randomness, second_valid_until_block) =
+            Randomness::random_with_domain(b"second-domain", subject);
+
+        // Different domains yield different randomness for the same subject, valid for the same blocks
+        assert_ne!(first_randomness, second_randomness);
+        assert_eq!(first_valid_until_block, second_valid_until_block);
+
+        // Moving bytes between the domain and the subject yields different randomness
+        assert_ne!(
+            Randomness::random_with_domain(b"ab", b"c").0,
+            Randomness::random_with_domain(b"a", b"bc").0
+        );
+        assert_ne!(
+            Randomness::random_with_domain(&[], subject).0,
+            Randomness::random(subject).0
+        );
+    });
+}
+
+#[test]
+fn random_unchecked_ignores_seed_validity() {
+    use frame_support::traits::Randomness as RandomnessT;
+
+    ExtBuilder::build().execute_with(|| {
+        // There's no seed to fall back to before one is set
+        assert_eq!(Randomness::latest_seed_unchecked(), None);
+        assert_eq!(Randomness::random_unchecked(b"subject"), None);
+
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+        // Move well past the latest block for which the seed is valid, without a new seed being set
+        System::set_block_number(System::block_number() + 10);
+        let (randomness, valid_until_block) = Randomness::random(b"subject");
+        assert!(valid_until_block < System::block_number());
+
+        // The stale seed is still used
+        let unchecked_randomness = Randomness::random_unchecked(b"subject").unwrap();
+        assert_ne!(unchecked_randomness, H256::default());
+        assert_eq!(unchecked_randomness, randomness);
+        assert_eq!(
+            Randomness::latest_seed_unchecked(),
+            Randomness::latest_babe_randomness().map(|(seed, _)| seed)
+        );
+    });
+}
+
+#[test]
+fn retained_epochs_reflects_latest_epochs() {
+    ExtBuilder::build().execute_with(|| {
+        for _ in 1..10 {
+            assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+            // Advance a block, which for the mock is also advancing an epoch
+            AllPalletsWithSystem::on_finalize(System::block_number());
+            System::set_block_number(System::block_number() + 1);
+            AllPalletsWithSystem::on_initialize(System::block_number());
+        }
+
+        // Only the last `RandomnessHistoryLength` processed epochs should be retained
+        let latest_epoch = Randomness::relay_epoch();
+        assert_eq!(latest_epoch, 9);
+        let history_length = <Test as crate::Config>::RandomnessHistoryLength::get() as u64;
+        assert_eq!(
+            Randomness::retained_epochs(),
+            (latest_epoch - history_length + 1, latest_epoch)
+        );
+    });
+}
+
+#[test]
+fn random_at_epoch_resolves_retained_epochs() {
+    use frame_support::traits::Randomness as RandomnessT;
+
+    ExtBuilder::build().execute_with(|| {
+        let subject = b"commitment";
+
+        // Process the randomness of a few epochs, keeping the expected randomness of the subject for each of them
+        let mut expected = Vec::new();
+        for _ in 1..6 {
+            assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+            expected.push((Randomness::relay_epoch(), Randomness::random(subject)));
+
+            // Advance a block, which for the mock is also advancing an epoch
+            AllPalletsWithSystem::on_finalize(System::block_number());
+            System::set_block_number(System::block_number() + 1);
+            AllPalletsWithSystem::on_initialize(System::block_number());
+        }
+
+        // The history should have been pruned to its configured length
+        let history_length = <Test as crate::Config>::RandomnessHistoryLength::get() as usize;
+        assert_eq!(Randomness::randomness_history().len(), history_length);
+
+        // Retained epochs should resolve to the same randomness that `random` returned during that epoch
+        for (epoch, randomness) in expected.iter().skip(expected.len() - history_length) {
+            assert_eq!(
+                Randomness::random_at_epoch(subject, *epoch),
+                Some(*randomness)
+            );
+        }
+
+        // Pruned epochs can no longer be resolved
+        for (epoch, _) in expected.iter().take(expected.len() - history_length) {
+            assert_eq!(Randomness::random_at_epoch(subject, *epoch), None);
+        }
+    });
+}
+
+#[cfg(feature = "runtime-api")]
+#[test]
+fn randomness_api_returns_latest_randomness_and_epoch() {
+    use crate::runtime_api::RandomnessApi;
+
+    ExtBuilder::build().execute_with(|| {
+        let api = MockRuntimeApi;
+        let at = System::parent_hash();
+
+        // Nothing has been processed yet
+        assert_eq!(api.latest_randomness(at).unwrap(), None);
+        assert_eq!(api.relay_epoch(at).unwrap(), 0);
+
+        assert_ok!(Randomness::set_babe_randomness(RuntimeOrigin::none()));
+
+        // The API should return the same values as the pallet's read-only functions
+        assert_eq!(
+            api.latest_randomness(at).unwrap(),
+            Randomness::latest_babe_randomness()
+        );
+        assert_eq!(api.relay_epoch(at).unwrap(), 1);
+    });
+}
@@ -0,0 +1,59 @@
+//! Weights for pallet-randomness
+//!
+//! THESE ARE PLACEHOLDER WEIGHTS, NOT BENCHMARK OUTPUT. The storage accesses listed for each operation
+//! were counted by hand from the code exercised by the benchmarks in `benchmarking.rs`, and the
+//! `ref_time` and `proof_size` components are rough estimates. Replace this file with the output of
+//! the `benchmark pallet` subcommand of the node (built with `--features runtime-benchmarks`), run on
+//! reference hardware, before relying on these weights.
+
+#![cfg_attr(rustfmt, rustfmt_skip)]
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{traits::Get, weights::{Weight, constants::RocksDbWeight}};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet-randomness.
+pub trait WeightInfo {
+	fn set_babe_randomness() -> Weight;
+	fn on_finalize() -> Weight;
+}
+
+/// Placeholder weights for pallet-randomness, see the module documentation.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+	/// Storage: `ParachainSystem::ValidationData` (r:1 w:0)
+	/// Storage: `ParachainSystem::RelayStateProof` (r:1 w:0)
+	/// Storage: `Randomness::RelayEpoch` (r:1 w:1)
+	/// Storage: `Randomness::LastRelayBlockNumber` (r:1 w:1)
+	/// Storage: `Randomness::RandomnessHistory` (r:1 w:1)
+	/// Storage: `Randomness::LatestBabeRandomness` (r:0 w:1)
+	/// Storage: `Randomness::LatestRandomnessSource` (r:0 w:1)
+	/// Storage: `Randomness::InherentIncluded` (r:0 w:1)
+	fn set_babe_randomness() -> Weight {
+		Weight::from_parts(25_000_000, 2_000)
+			.saturating_add(T::DbWeight::get().reads(5_u64))
+			.saturating_add(T::DbWeight::get().writes(6_u64))
+	}
+	/// Storage: `Randomness::InherentIncluded` (r:1 w:1)
+	/// Storage: `Randomness::RandomnessHistory` (r:1 w:1)
+	fn on_finalize() -> Weight {
+		Weight::from_parts(8_000_000, 1_500)
+			.saturating_add(T::DbWeight::get().reads(2_u64))
+			.saturating_add(T::DbWeight::get().writes(2_u64))
+	}
+}
+
+// For backwards compatibility and tests.
+impl WeightInfo for () {
+	fn set_babe_randomness() -> Weight {
+		Weight::from_parts(25_000_000, 2_000)
+			.saturating_add(RocksDbWeight::get().reads(5_u64))
+			.saturating_add(RocksDbWeight::get().writes(6_u64))
+	}
+	fn on_finalize() -> Weight {
+		Weight::from_parts(8_000_000, 1_500)
+			.saturating_add(RocksDbWeight::get().reads(2_u64))
+			.saturating_add(RocksDbWeight::get().writes(2_u64))
+	}
+}
@@ -32,7 +32,7 @@ use frame_support::{
     derive_impl,
     dispatch::DispatchClass,
     parameter_types,
-    traits::{ConstBool, ConstU32, ConstU64, ConstU8, EitherOfDiverse, TransformOrigin},
+    traits::{ConstBool, ConstI32, ConstU32, ConstU64, ConstU8, EitherOfDiverse, TransformOrigin},
     weights::{ConstantMultiplier, Weight},
     PalletId,
 };
@@ -40,17 +40,19 @@ use frame_system::{
     limits::{BlockLength, BlockWeights},
     EnsureRoot,
 };
-use pallet_proofs_dealer::CompactProof;
 use pallet_xcm::{EnsureXcm, IsVoiceOfBody};
 use parachains_common::message_queue::{NarrowOriginToSibling, ParaIdToSibling};
 use polkadot_runtime_common::{
     prod_or_fast, xcm_sender::NoPriceForMessageDelivery, BlockHashCount, SlowAdjustingFeeUpdate,
 };
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_core::{ConstU128, Get, H256};
-use sp_runtime::{AccountId32, DispatchResult, FixedU128, Perbill};
+use sp_core::{ConstU128, Get};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash as HashT},
+    AccountId32, FixedU128, Perbill,
+};
 use sp_version::RuntimeVersion;
-use storage_hub_traits::CommitmentVerifier;
+use storage_hub_primitives::TrieVerifier;
 use xcm::latest::prelude::BodyId;
 
 use crate::ParachainInfo;
@@ -355,10 +357,38 @@ impl pallet_randomness::GetBabeData<u64, Option<Hash>> for BabeDataGetter {
             .ok()
             .flatten()
     }
+    fn get_relay_block_number() -> u32 {
+        if cfg!(feature = "runtime-benchmarks") {
+            // storage reads as per actual reads
+            let _relay_storage_root = ParachainSystem::validation_data();
+            const BENCHMARKING_RELAY_BLOCK_NUMBER: u32 = 10u32;
+            return BENCHMARKING_RELAY_BLOCK_NUMBER;
+        }
+        ParachainSystem::validation_data()
+            .expect("set in `set_validation_data`")
+            .relay_parent_number
+    }
+}
+
+/// Fallback randomness used when the relay chain BABE epoch randomness is unavailable.
+///
+/// It is derived from the parent block hash, which is known by the block author in advance, so it is
+/// weaker than BABE randomness and only meant to keep consumers working until BABE randomness is back.
+pub struct ParentBlockRandomness;
+impl frame_support::traits::Randomness<Hash, BlockNumber> for ParentBlockRandomness {
+    fn random(subject: &[u8]) -> (Hash, BlockNumber) {
+        let parent_hash = System::parent_hash();
+        let randomness = BlakeTwo256::hash_of(&(parent_hash, subject));
+        // The parent block hash is known from the previous block, so it is only unpredictable for commitments before it.
+        let latest_valid_block = System::block_number().saturating_sub(1);
+        (randomness, latest_valid_block)
+    }
 }
 
 parameter_types! {
     pub const MaxBlocksForRandomness: BlockNumber = prod_or_fast!(2 * HOURS, 2 * MINUTES);
+    pub const SignOffCooldown: BlockNumber = prod_or_fast!(24 * HOURS, 2 * MINUTES);
+    pub const LivenessTimeout: BlockNumber = prod_or_fast!(2 * HOURS, 2 * MINUTES);
 }
 
 // TODO: If the next line is uncommented (which should be eventually), compilation breaks (most likely because of mismatched dependency issues)
@@ -370,7 +400,9 @@ parameter_types! {
 impl pallet_randomness::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
     type BabeDataGetter = BabeDataGetter;
-    type WeightInfo = ();
+    type FallbackRandomness = ParentBlockRandomness;
+    type RandomnessHistoryLength = ConstU32<10>;
+    type WeightInfo = pallet_randomness::weights::SubstrateWeight<Runtime>;
 }
 
 impl pallet_storage_providers::Config for Runtime {
@@ -391,9 +423,16 @@ impl pallet_storage_providers::Config for Runtime {
     type DepositPerData = ConstU128<2>;
     type RuntimeHoldReason = RuntimeHoldReason;
     type Subscribers = FileSystem;
+    type StorageCommitments = FileSystem;
+    type RootTransitionVerifier = TrieVerifier<BlakeTwo256>;
     type ProvidersRandomness = Randomness;
     type MaxBlocksForRandomness = MaxBlocksForRandomness;
     type MinBlocksBetweenCapacityChanges = ConstU32<10>;
+    type SignOffCooldown = SignOffCooldown;
+    type MinReputation = ConstI32<-100>;
+    type MaxReputation = ConstI32<100>;
+    type SlashReputationPenalty = ConstI32<10>;
+    type LivenessTimeout = LivenessTimeout;
 }
 
 // TODO: remove this and replace with pallet treasury
@@ -409,36 +448,16 @@ impl pallet_proofs_dealer::Config for Runtime {
     type ProvidersPallet = Providers;
     type NativeBalance = Balances;
     type MerkleHash = Hash;
-    type KeyVerifier = ProofTrieVerifier;
+    type KeyVerifier = TrieVerifier<BlakeTwo256>;
     type MaxChallengesPerBlock = ConstU32<10>;
+    type RandomChallengesPerBlock = ConstU32<10>;
     type MaxProvidersChallengedPerBlock = ConstU32<10>;
     type ChallengeHistoryLength = ConstU32<10>;
     type ChallengesQueueLength = ConstU32<10>;
     type CheckpointChallengePeriod = ConstU32<10>;
     type ChallengesFee = ConstU128<1_000_000>;
     type Treasury = TreasuryAccount;
-}
-
-/// Structure to mock a verifier that returns `true` when `proof` is not empty
-/// and `false` otherwise.
-pub struct ProofTrieVerifier;
-
-/// Implement the `TrieVerifier` trait for the `MockVerifier` struct.
-impl CommitmentVerifier for ProofTrieVerifier {
-    type Proof = CompactProof;
-    type Key = H256;
-
-    fn verify_proof(
-        _root: &Self::Key,
-        _challenges: &[Self::Key],
-        proof: &CompactProof,
-    ) -> DispatchResult {
-        if proof.encoded_nodes.len() > 0 {
-            Ok(())
-        } else {
-            Err("Proof is empty".into())
-        }
-    }
+    type SlashAmountPerFailedProof = ConstU128<5>;
 }
 
 type ThresholdType = FixedU128;
@@ -452,8 +471,11 @@ parameter_types! {
 /// Configure the pallet template in pallets/template.
 impl pallet_file_system::Config for Runtime {
     type RuntimeEvent = RuntimeEvent;
+    type NativeBalance = Balances;
+    type RuntimeHoldReason = RuntimeHoldReason;
     type Providers = Providers;
     type ProofDealer = ProofsDealer;
+    type FileProofInspector = TrieVerifier<BlakeTwo256>;
     type ThresholdType = ThresholdType;
     type AssignmentThresholdDecayFactor = ThresholdAsymptoticDecayFactor;
     type AssignmentThresholdAsymptote = ThresholdAsymptote;
@@ -462,10 +484,19 @@ impl pallet_file_system::Config for Runtime {
     type StorageRequestBspsRequiredType = u32;
     type TargetBspsRequired = ConstU32<1>;
     type MaxBspsPerStorageRequest = ConstU32<5>;
+    type MaxVolunteersPerRequest = ConstU32<5>;
+    type VolunteersPerRequiredBsp = ConstU32<3>;
+    type MaxReplicationTarget = ConstU32<5>;
     type MaxFilePathSize = ConstU32<512u32>;
+    type FileChunkSize = ConstU64<{ 1024 * 1024 }>;
+    type MinStorageRequestSize = ConstU32<1024>;
     type MaxPeerIdSize = ConstU32<100>;
     type MaxNumberOfPeerIds = ConstU32<5>;
     type MaxDataServerMultiAddresses = ConstU32<10>;
     type StorageRequestTtl = ConstU32<40>;
     type MaxExpiredStorageRequests = ConstU32<100>;
+    type MaxAutoRenewals = ConstU32<10>;
+    type MaxBatchStorageRequests = ConstU32<20>;
+    type MaxConfirmBatch = ConstU32<20>;
+    type StorageRequestDepositPerData = ConstU128<2>;
 }
@@ -35,11 +35,13 @@ use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
 use sp_runtime::{
     traits::Block as BlockT,
     transaction_validity::{TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult, ExtrinsicInclusionMode,
+    ApplyExtrinsicResult, ExtrinsicInclusionMode, FixedU128,
 };
 pub use sp_runtime::{MultiAddress, Perbill, Permill};
 use sp_std::prelude::Vec;
 
+use pallet_file_system::types::FileLocation;
+
 #[cfg(any(feature = "std", test))]
 pub use sp_runtime::BuildStorage;
 
@@ -274,6 +276,7 @@ mod benches {
         [pallet_collator_selection, CollatorSelection]
         [cumulus_pallet_parachain_system, ParachainSystem]
         [cumulus_pallet_xcmp_queue, XcmpQueue]
+        [pallet_randomness, Randomness]
     );
 }
 
@@ -382,6 +385,26 @@ impl_runtime_apis! {
         }
     }
 
+    impl pallet_randomness::runtime_api::RandomnessApi<Block, Hash, BlockNumber> for Runtime {
+        fn latest_randomness() -> Option<(Hash, BlockNumber)> {
+            Randomness::latest_babe_randomness()
+        }
+
+        fn relay_epoch() -> u64 {
+            Randomness::relay_epoch()
+        }
+    }
+
+    impl pallet_file_system::runtime_api::FileSystemApi<Block, Hash, FileLocation<Runtime>, FixedU128> for Runtime {
+        fn is_bsp_eligible(bsp_id: Hash, location: FileLocation<Runtime>) -> bool {
+            FileSystem::is_bsp_eligible(&bsp_id, &location)
+        }
+
+        fn current_assignment_threshold() -> FixedU128 {
+            FileSystem::current_assignment_threshold()
+        }
+    }
+
     impl pallet_transaction_payment_rpc_runtime_api::TransactionPaymentApi<Block, Balance> for Runtime {
         fn query_info(
             uxt: <Block as BlockT>::Extrinsic,
@@ -1,11 +1,10 @@
 use std::fmt::Debug;
 
+use codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
-use sp_core::H256;
+use sp_core::{hashing::blake2_256, H256};
 use sp_trie::CompactProof;
 
-use crate::constants::FILE_CHUNK_SIZE;
-
 // TODO: this is currently a placeholder in order to define Storage interface.
 /// FileKey is the identifier for a file.
 /// Computed as the hash of the FileMetadata.
@@ -14,26 +13,86 @@ pub type Key = H256;
 // TODO: this is currently a placeholder in order to define Storage interface.
 /// Metadata contains information about a file.
 /// Most importantly, the fingerprint which is the root Merkle hash of the file.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+///
+/// Built with [`Metadata::new`], which derives the chunking of the file from its size.
+#[derive(Serialize, Deserialize, Encode, Decode, Clone, Debug)]
 pub struct Metadata {
     pub owner: String,
     pub location: String,
     pub size: u64,
     pub fingerprint: H256,
+    /// Size in bytes of every chunk of the file, except for the last one which can be shorter.
+    pub chunk_size: u64,
 }
 
 impl Metadata {
+    /// Create the metadata of a file of `size` bytes split in chunks of `chunk_size` bytes.
+    ///
+    /// The owner and location are empty, and can be set with [`Metadata::with_owner`] and
+    /// [`Metadata::with_location`].
+    ///
+    /// Panics if `chunk_size` is zero.
+    pub fn new(fingerprint: H256, size: u64, chunk_size: u64) -> Self {
+        assert!(chunk_size > 0, "Chunk size must be greater than zero");
+
+        Self {
+            owner: String::new(),
+            location: String::new(),
+            size,
+            fingerprint,
+            chunk_size,
+        }
+    }
+
+    pub fn with_owner(mut self, owner: String) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    pub fn with_location(mut self, location: String) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Number of chunks of the file, the last one being possibly shorter than `chunk_size`.
+    ///
+    /// It is derived from `size` and `chunk_size` every time, so it can't get out of sync with them.
+    /// Metadata with a zero `chunk_size`, which [`Metadata::new`] rejects but could still be decoded,
+    /// has no chunks.
     pub fn chunk_count(&self) -> u64 {
-        let full_chunks = self.size / (FILE_CHUNK_SIZE as u64);
-        if self.size % (FILE_CHUNK_SIZE as u64) > 0 {
-            return full_chunks + 1;
+        if self.chunk_size == 0 {
+            return 0;
+        }
+        self.size.div_ceil(self.chunk_size)
+    }
+
+    /// Size in bytes of the last chunk of the file, or zero for a file without chunks.
+    pub fn last_chunk_size(&self) -> u64 {
+        match self.chunk_count() {
+            0 => 0,
+            chunk_count => self.size - (chunk_count - 1) * self.chunk_size,
+        }
+    }
+
+    /// Expected size in bytes of a chunk of the file.
+    ///
+    /// All chunks are `chunk_size` bytes long, except for the last one which holds the remaining bytes of the file.
+    pub fn expected_chunk_size(&self, chunk_id: ChunkId) -> u64 {
+        if chunk_id + 1 == self.chunk_count() {
+            return self.last_chunk_size();
         }
-        full_chunks
+        self.chunk_size
     }
 
     pub fn chunk_ids(&self) -> impl Iterator<Item = ChunkId> {
         0..self.chunk_count()
     }
+
+    /// Hash of the SCALE encoded metadata, committing to the owner, location, size, fingerprint
+    /// and chunking of the file.
+    pub fn hash(&self) -> H256 {
+        H256(blake2_256(&self.encode()))
+    }
 }
 
 /// Typed u64 representing the index of a file [`Chunk`]. Indexed from 0.
@@ -44,6 +103,7 @@ pub type ChunkId = u64;
 pub type Chunk = Vec<u8>;
 
 /// Leaf in the Forest or File trie.
+#[derive(Encode, Decode)]
 pub struct Leaf<K, D: Debug> {
     pub key: K,
     pub data: D,
@@ -81,11 +141,90 @@ pub struct ForestProof<K: AsRef<[u8]>> {
     pub root: H256,
 }
 
+#[derive(Encode, Decode)]
 pub struct FileProof {
-    /// The file chunk (and id) that was proven.
-    pub proven: Leaf<ChunkId, Chunk>,
+    /// The file chunks (and ids) that were proven.
+    pub proven: Vec<Leaf<ChunkId, Chunk>>,
     /// The compact proof.
     pub proof: CompactProof,
     /// The root hash of the trie, also known as the fingerprint of the file.
     pub root: H256,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHUNK_SIZE: u64 = 1024;
+
+    #[test]
+    fn chunking_of_size_multiple_of_chunk_size() {
+        let metadata = Metadata::new(H256::zero(), 4 * CHUNK_SIZE, CHUNK_SIZE);
+
+        assert_eq!(metadata.chunk_count(), 4);
+        assert_eq!(metadata.last_chunk_size(), CHUNK_SIZE);
+        assert!(metadata
+            .chunk_ids()
+            .all(|chunk_id| metadata.expected_chunk_size(chunk_id) == CHUNK_SIZE));
+    }
+
+    #[test]
+    fn chunking_of_size_not_multiple_of_chunk_size() {
+        let metadata = Metadata::new(H256::zero(), 4 * CHUNK_SIZE + 1, CHUNK_SIZE);
+
+        assert_eq!(metadata.chunk_count(), 5);
+        assert_eq!(metadata.last_chunk_size(), 1);
+        assert_eq!(metadata.expected_chunk_size(3), CHUNK_SIZE);
+        assert_eq!(metadata.expected_chunk_size(4), 1);
+
+        let metadata = Metadata::new(H256::zero(), CHUNK_SIZE - 1, CHUNK_SIZE);
+        assert_eq!(metadata.chunk_count(), 1);
+        assert_eq!(metadata.last_chunk_size(), CHUNK_SIZE - 1);
+    }
+
+    #[test]
+    fn chunking_of_empty_file() {
+        let metadata = Metadata::new(H256::zero(), 0, CHUNK_SIZE);
+
+        assert_eq!(metadata.chunk_count(), 0);
+        assert_eq!(metadata.last_chunk_size(), 0);
+        assert_eq!(metadata.chunk_ids().count(), 0);
+    }
+
+    #[test]
+    fn chunk_sizes_add_up_to_file_size() {
+        for size in [
+            1,
+            CHUNK_SIZE - 1,
+            CHUNK_SIZE,
+            CHUNK_SIZE + 1,
+            7 * CHUNK_SIZE + 13,
+        ] {
+            let metadata = Metadata::new(H256::zero(), size, CHUNK_SIZE);
+            let total: u64 = metadata
+                .chunk_ids()
+                .map(|chunk_id| metadata.expected_chunk_size(chunk_id))
+                .sum();
+            assert_eq!(total, size);
+        }
+    }
+
+    #[test]
+    fn chunking_of_zero_chunk_size() {
+        let mut metadata = Metadata::new(H256::zero(), 4 * CHUNK_SIZE, CHUNK_SIZE);
+        metadata.chunk_size = 0;
+
+        assert_eq!(metadata.chunk_count(), 0);
+        assert_eq!(metadata.chunk_ids().count(), 0);
+        assert_eq!(metadata.last_chunk_size(), 0);
+    }
+
+    #[test]
+    fn chunking_follows_updated_fields() {
+        let mut metadata = Metadata::new(H256::zero(), 4 * CHUNK_SIZE, CHUNK_SIZE);
+        metadata.size = CHUNK_SIZE + 1;
+
+        assert_eq!(metadata.chunk_count(), 2);
+        assert_eq!(metadata.last_chunk_size(), 1);
+    }
+}
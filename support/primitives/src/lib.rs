@@ -1,10 +1,17 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+use alloc::vec::Vec;
+use codec::{Decode, Encode};
+use scale_info::TypeInfo;
 use sp_core::Hasher;
-use sp_trie::{CompactProof, LayoutV1, TrieDBBuilder};
-use storage_hub_traits::CommitmentVerifier;
+use sp_trie::{
+    empty_trie_root, CompactProof, LayoutV1, MemoryDB, TrieDBBuilder, TrieDBMutBuilder, TrieMut,
+};
+use storage_hub_traits::{CommitmentVerifier, FileProofInspector, RootTransitionVerifier};
 
-use frame_support::dispatch::DispatchResult;
+use frame_support::dispatch::{DispatchError, DispatchResult};
 use trie_db::TrieIterator;
 
 #[cfg(test)]
@@ -152,3 +159,79 @@ impl<H: Hasher> CommitmentVerifier for TrieVerifier<H> {
         return Ok(());
     }
 }
+
+/// Implement the `FileProofInspector` trait for the `TrieVerifier` struct, where the proof is a proof of
+/// the trie of a file whose leaves are its chunks, keyed by their big-endian encoded chunk id.
+impl<H: Hasher> FileProofInspector for TrieVerifier<H> {
+    type Proof = CompactProof;
+
+    /// Infers the number of chunks of a file from the last leaf included in the proof.
+    ///
+    /// Since chunks are keyed by their id, the last leaf of the trie is the chunk with the highest id.
+    /// Returns `None` if the proof doesn't include the last leaf of the trie.
+    fn chunk_count(proof: &Self::Proof) -> Option<u64> {
+        let (memdb, root) = proof.to_memory_db::<H>(None).ok()?;
+
+        let trie = TrieDBBuilder::<LayoutV1<H>>::new(&memdb, &root).build();
+
+        let mut trie_de_iter = trie.into_double_ended_iter().ok()?;
+
+        // If the rightmost branch of the trie is not part of the proof, this yields an error for the missing node.
+        let (last_key, _) = trie_de_iter.next_back()?.ok()?;
+
+        let last_chunk_id = u64::from_be_bytes(last_key.try_into().ok()?);
+
+        last_chunk_id.checked_add(1)
+    }
+}
+
+/// A proof that modifying the trie with a given root through a set of mutations results in the trie with another root.
+#[derive(Encode, Decode, TypeInfo, Clone, PartialEq, Eq, Debug)]
+pub struct TrieMutationProof {
+    /// Proof of the nodes of the original trie that are accessed when applying the mutations.
+    pub proof: CompactProof,
+    /// The mutations to apply to the original trie, in order. A `None` value removes the key from the trie.
+    pub mutations: Vec<(Vec<u8>, Option<Vec<u8>>)>,
+}
+
+/// Implement the `RootTransitionVerifier` trait for the `TrieVerifier` struct, where a transition is valid if applying
+/// the mutations of the proof to the trie with the old root results in the trie with the new root.
+impl<H: Hasher> RootTransitionVerifier for TrieVerifier<H> {
+    type Proof = TrieMutationProof;
+    type Key = H::Out;
+
+    /// Computes the new root by rebuilding the part of the trie with root `old_root` included in the proof
+    /// and applying the mutations to it.
+    ///
+    /// Fails if any of the mutations accesses a node of the trie not included in the proof.
+    fn apply_transition(
+        old_root: &Self::Key,
+        proof: &Self::Proof,
+    ) -> Result<Self::Key, DispatchError> {
+        // The empty trie has no nodes, so there is nothing to prove about it.
+        let (mut memdb, mut root) = if *old_root == empty_trie_root::<LayoutV1<H>>() {
+            (MemoryDB::<H>::default(), *old_root)
+        } else {
+            proof.proof.to_memory_db(Some(old_root)).map_err(|_| {
+                "Failed to convert proof to memory DB, root doesn't match with expected."
+            })?
+        };
+
+        {
+            // The root is updated with the mutations applied once the trie is dropped.
+            let mut trie =
+                TrieDBMutBuilder::<LayoutV1<H>>::from_existing(&mut memdb, &mut root).build();
+
+            for (key, value) in proof.mutations.iter() {
+                let result = match value {
+                    Some(value) => trie.insert(key, value),
+                    None => trie.remove(key),
+                };
+
+                result.map_err(|_| "Failed to apply mutation, proof is incomplete.")?;
+            }
+        }
+
+        Ok(root)
+    }
+}
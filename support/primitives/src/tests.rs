@@ -4,13 +4,13 @@ use frame_support::assert_ok;
 use reference_trie::RefHasher;
 use serde::Serialize;
 use sp_trie::{
-    recorder::Recorder, CompactProof, LayoutV1, MemoryDB, TrieDBBuilder, TrieDBMutBuilder,
+    recorder::Recorder, CompactProof, LayoutV1, MemoryDB, Trie, TrieDBBuilder, TrieDBMutBuilder,
     TrieLayout, TrieMut,
 };
-use storage_hub_traits::CommitmentVerifier;
+use storage_hub_traits::{CommitmentVerifier, FileProofInspector, RootTransitionVerifier};
 use trie_db::{Hasher, TrieIterator};
 
-use crate::TrieVerifier;
+use crate::{TrieMutationProof, TrieVerifier};
 
 /// The hash type of trie node keys
 type HashT<T> = <<T as TrieLayout>::Hash as Hasher>::Out;
@@ -1159,3 +1159,186 @@ fn commitment_verifier_wrong_proof_skip_actual_next_leaf_failure() {
         Err("Failed to get next leaf.".into())
     );
 }
+
+/// Build the trie of a file with `chunk_count` chunks, keyed by their big-endian encoded chunk id.
+fn build_file_trie(chunk_count: u64) -> (MemoryDB<RefHasher>, HashT<LayoutV1<RefHasher>>) {
+    let mut memdb = MemoryDB::<RefHasher>::default();
+    let mut root = Default::default();
+
+    {
+        let mut file_trie =
+            TrieDBMutBuilder::<LayoutV1<RefHasher>>::new(&mut memdb, &mut root).build();
+
+        for chunk_id in 0..chunk_count {
+            file_trie
+                .insert(&chunk_id.to_be_bytes(), &[chunk_id as u8; 64])
+                .unwrap();
+        }
+    }
+
+    (memdb, root)
+}
+
+/// Generate a proof of the chunks with ids `chunk_ids` of the file trie with root `root`.
+fn prove_chunks(
+    memdb: &MemoryDB<RefHasher>,
+    root: HashT<LayoutV1<RefHasher>>,
+    chunk_ids: &[u64],
+) -> CompactProof {
+    let recorder: Recorder<RefHasher> = Recorder::default();
+
+    {
+        // Creating trie inside of closure to drop it before generating proof.
+        let mut trie_recorder = recorder.as_trie_recorder(root);
+        let trie = TrieDBBuilder::<LayoutV1<RefHasher>>::new(memdb, &root)
+            .with_recorder(&mut trie_recorder)
+            .build();
+
+        for chunk_id in chunk_ids {
+            // Chunks not in the trie are proven to be missing from it.
+            trie.get(&chunk_id.to_be_bytes()).unwrap();
+        }
+    }
+
+    recorder
+        .drain_storage_proof()
+        .to_compact_proof::<RefHasher>(root)
+        .expect("Failed to create compact proof from recorder")
+}
+
+#[test]
+fn file_proof_inspector_single_chunk_file_success() {
+    let (memdb, root) = build_file_trie(1);
+
+    let proof = prove_chunks(&memdb, root, &[0]);
+
+    assert_eq!(TrieVerifier::<RefHasher>::chunk_count(&proof), Some(1));
+}
+
+#[test]
+fn file_proof_inspector_last_chunk_proven_success() {
+    let (memdb, root) = build_file_trie(300);
+
+    let proof = prove_chunks(&memdb, root, &[0, 299]);
+
+    assert_eq!(TrieVerifier::<RefHasher>::chunk_count(&proof), Some(300));
+}
+
+#[test]
+fn file_proof_inspector_last_chunk_not_proven_failure() {
+    let (memdb, root) = build_file_trie(300);
+
+    let proof = prove_chunks(&memdb, root, &[0]);
+
+    assert_eq!(TrieVerifier::<RefHasher>::chunk_count(&proof), None);
+}
+
+#[test]
+fn file_proof_inspector_empty_proof_failure() {
+    let proof = CompactProof {
+        encoded_nodes: vec![],
+    };
+
+    assert_eq!(TrieVerifier::<RefHasher>::chunk_count(&proof), None);
+}
+
+#[test]
+fn root_transition_verifier_insert_chunk_success() {
+    let (memdb, old_root) = build_file_trie(10);
+    let (_, new_root) = build_file_trie(11);
+
+    let proof = TrieMutationProof {
+        proof: prove_chunks(&memdb, old_root, &[10]),
+        mutations: vec![(10u64.to_be_bytes().to_vec(), Some(vec![10; 64]))],
+    };
+
+    assert_ok!(TrieVerifier::<RefHasher>::verify_transition(
+        &old_root, &new_root, &proof
+    ));
+}
+
+#[test]
+fn root_transition_verifier_remove_chunk_success() {
+    let (memdb, old_root) = build_file_trie(11);
+    let (_, new_root) = build_file_trie(10);
+
+    // Removing a leaf might merge its siblings, so the proof includes all of them.
+    let proof = TrieMutationProof {
+        proof: prove_chunks(&memdb, old_root, &(0..11).collect::<Vec<_>>()),
+        mutations: vec![(10u64.to_be_bytes().to_vec(), None)],
+    };
+
+    assert_ok!(TrieVerifier::<RefHasher>::verify_transition(
+        &old_root, &new_root, &proof
+    ));
+}
+
+#[test]
+fn root_transition_verifier_from_empty_trie_success() {
+    let (_, old_root) = build_file_trie(0);
+    let (_, new_root) = build_file_trie(2);
+
+    let proof = TrieMutationProof {
+        proof: CompactProof {
+            encoded_nodes: vec![],
+        },
+        mutations: vec![
+            (0u64.to_be_bytes().to_vec(), Some(vec![0; 64])),
+            (1u64.to_be_bytes().to_vec(), Some(vec![1; 64])),
+        ],
+    };
+
+    assert_ok!(TrieVerifier::<RefHasher>::verify_transition(
+        &old_root, &new_root, &proof
+    ));
+}
+
+#[test]
+fn root_transition_verifier_wrong_new_root_failure() {
+    let (memdb, old_root) = build_file_trie(10);
+    let (_, new_root) = build_file_trie(12);
+
+    let proof = TrieMutationProof {
+        proof: prove_chunks(&memdb, old_root, &[10]),
+        mutations: vec![(10u64.to_be_bytes().to_vec(), Some(vec![10; 64]))],
+    };
+
+    assert_eq!(
+        TrieVerifier::<RefHasher>::verify_transition(&old_root, &new_root, &proof),
+        Err("New root doesn't match the root resulting from the transition.".into())
+    );
+}
+
+#[test]
+fn root_transition_verifier_wrong_old_root_failure() {
+    let (memdb, old_root) = build_file_trie(10);
+    let (_, other_root) = build_file_trie(9);
+    let (_, new_root) = build_file_trie(11);
+
+    let proof = TrieMutationProof {
+        proof: prove_chunks(&memdb, old_root, &[10]),
+        mutations: vec![(10u64.to_be_bytes().to_vec(), Some(vec![10; 64]))],
+    };
+
+    assert_eq!(
+        TrieVerifier::<RefHasher>::verify_transition(&other_root, &new_root, &proof),
+        Err("Failed to convert proof to memory DB, root doesn't match with expected.".into())
+    );
+}
+
+#[test]
+fn root_transition_verifier_incomplete_proof_failure() {
+    let (memdb, old_root) = build_file_trie(300);
+    let (_, new_root) = build_file_trie(301);
+
+    // The proof doesn't include the part of the trie where the new chunk is inserted.
+    let proof = TrieMutationProof {
+        proof: prove_chunks(&memdb, old_root, &[0]),
+        mutations: vec![(300u64.to_be_bytes().to_vec(), Some(vec![300u64 as u8; 64]))],
+    };
+
+    assert_eq!(
+        TrieVerifier::<RefHasher>::verify_transition(&old_root, &new_root, &proof),
+        Err("Failed to apply mutation, proof is incomplete.".into())
+    );
+}
@@ -8,7 +8,7 @@ use frame_support::traits::fungible;
 use frame_support::Parameter;
 use scale_info::prelude::fmt::Debug;
 use sp_core::Get;
-use sp_runtime::traits::AtLeast32BitUnsigned;
+use sp_runtime::traits::{AtLeast32BitUnsigned, Saturating};
 use sp_runtime::{BoundedVec, DispatchError};
 
 /// A trait to lookup registered Providers.
@@ -49,6 +49,14 @@ pub trait ProvidersInterface {
     fn get_stake(
         who: Self::Provider,
     ) -> Option<<Self::Balance as fungible::Inspect<Self::AccountId>>::Balance>;
+
+    /// Slash a registered Provider, taking up to `amount` from its stake.
+    ///
+    /// Returns the amount that was actually slashed, which can be less than `amount` if the stake of the Provider is not enough.
+    fn slash(
+        who: &Self::Provider,
+        amount: <Self::Balance as fungible::Inspect<Self::AccountId>>::Balance,
+    ) -> Result<<Self::Balance as fungible::Inspect<Self::AccountId>>::Balance, DispatchError>;
 }
 
 /// A trait to lookup registered Providers, their Merkle Patricia Trie roots and their stake.
@@ -80,12 +88,29 @@ pub trait ReadProvidersInterface: ProvidersInterface {
     /// Maximum number of multiaddresses a provider can have.
     type MaxNumberOfMultiAddresses: Get<u32>;
 
+    /// Data type for the measurement of storage size
+    type StorageData: Parameter
+        + Member
+        + MaybeSerializeDeserialize
+        + Default
+        + MaybeDisplay
+        + AtLeast32BitUnsigned
+        + Copy
+        + MaxEncodedLen
+        + HasCompact;
+
     /// Check if provider is a BSP.
     fn is_bsp(who: &Self::Provider) -> bool;
 
     /// Check if provider is a MSP.
     fn is_msp(who: &Self::Provider) -> bool;
 
+    /// Get the account that owns a registered MSP or BSP.
+    fn get_owner_account(who: &Self::Provider) -> Option<Self::AccountId>;
+
+    /// Check if provider has requested to sign off and is waiting for its sign off cooldown to elapse.
+    fn is_signing_off(who: &Self::Provider) -> bool;
+
     /// Get number of registered BSPs.
     fn get_number_of_bsps() -> Self::SpCount;
 
@@ -93,6 +118,23 @@ pub trait ReadProvidersInterface: ProvidersInterface {
     fn get_bsp_multiaddresses(
         who: &Self::Provider,
     ) -> Result<BoundedVec<Self::MultiAddress, Self::MaxNumberOfMultiAddresses>, DispatchError>;
+
+    /// Get the total capacity of a MSP or BSP, if it is registered.
+    fn get_capacity(who: &Self::Provider) -> Option<Self::StorageData>;
+
+    /// Get the capacity of a MSP or BSP that is not used yet (its capacity minus its data used), if it is registered.
+    fn get_available_capacity(who: &Self::Provider) -> Option<Self::StorageData>;
+
+    /// Get the total capacity of the network, which is the sum of the capacities of all BSPs.
+    fn total_capacity() -> Self::StorageData;
+
+    /// Get the used capacity of the network, which is the sum of the data used by all BSPs.
+    fn used_capacity() -> Self::StorageData;
+
+    /// Get the capacity of the network that is not used yet (its total capacity minus its used capacity).
+    fn available_capacity() -> Self::StorageData {
+        Self::total_capacity().saturating_sub(Self::used_capacity())
+    }
 }
 
 /// Interface to allow the File System pallet to modify the data used by the Storage Providers pallet.
@@ -141,6 +183,8 @@ pub trait MutateProvidersInterface {
         + AsMut<[u8]>
         + MaxEncodedLen
         + FullCodec;
+    /// The type of the proof that the root of a bucket or a BSP is a valid successor of its current root.
+    type RootTransitionProof: Parameter + Member + Debug;
 
     /// Increase the used data of a Storage Provider (generic, MSP or BSP).
     fn increase_data_used(who: &Self::AccountId, delta: Self::StorageData) -> DispatchResult;
@@ -148,28 +192,54 @@ pub trait MutateProvidersInterface {
     /// Decrease the used data of a Storage Provider (generic, MSP or BSP).
     fn decrease_data_used(who: &Self::AccountId, delta: Self::StorageData) -> DispatchResult;
 
-    /// Add a new Bucket as a Provider
+    /// Add a new Bucket as a Provider, deriving its ID from the MSP, the user and a salt chosen by the caller
+    ///
+    /// `size` is the size of the data stored in the bucket. Returns the ID of the new Bucket.
     fn add_bucket(
         msp_id: Self::Provider,
         user_id: Self::AccountId,
-        bucket_id: Self::BucketId,
+        salt: &[u8],
         bucket_root: Self::MerklePatriciaRoot,
-    ) -> DispatchResult;
+        size: Self::StorageData,
+    ) -> Result<Self::BucketId, DispatchError>;
+
+    /// Add a file of `size` to the bucket of the MSP `msp_id` owned by `user_id` whose ID is derived from `salt`, creating
+    /// the bucket with an empty root if it does not exist yet, so a single bucket can hold many files
+    ///
+    /// The root of the bucket is changed to the one resulting from applying the transition shown by `proof` to its
+    /// current root. Returns the ID of the bucket.
+    fn add_file_to_bucket(
+        msp_id: Self::Provider,
+        user_id: Self::AccountId,
+        salt: &[u8],
+        size: Self::StorageData,
+        proof: &Self::RootTransitionProof,
+    ) -> Result<Self::BucketId, DispatchError>;
 
-    /// Change the root of a bucket
+    /// Change the root of a bucket of the MSP `msp_id`, once `proof` shows that the new root is a valid successor of the
+    /// current one
     fn change_root_bucket(
+        msp_id: Self::Provider,
         bucket_id: Self::BucketId,
         new_root: Self::MerklePatriciaRoot,
+        proof: &Self::RootTransitionProof,
     ) -> DispatchResult;
 
-    /// Change the root of a BSP
+    /// Change the root of a BSP to the one resulting from applying the transition shown by `proof` to its current root
+    ///
+    /// Returns the new root of the BSP.
     fn change_root_bsp(
         bsp_id: Self::Provider,
-        new_root: Self::MerklePatriciaRoot,
-    ) -> DispatchResult;
+        proof: &Self::RootTransitionProof,
+    ) -> Result<Self::MerklePatriciaRoot, DispatchError>;
 
     /// Remove a root from a bucket of a MSP, removing the whole bucket from storage
     fn remove_root_bucket(bucket_id: Self::BucketId) -> DispatchResult;
+
+    /// Change the reputation of a registered Provider by `delta`, clamped to the range allowed by the runtime.
+    ///
+    /// Returns the new reputation of the Provider.
+    fn change_reputation(who: &Self::Provider, delta: i32) -> Result<i32, DispatchError>;
 }
 
 /// The interface to subscribe to updates on the Storage Providers pallet.
@@ -184,6 +254,29 @@ pub trait SubscribeProvidersInterface {
     fn subscribe_bsp_sign_up(who: &Self::Provider) -> DispatchResult;
 }
 
+/// The interface to read the storage that Storage Providers have committed to but that is not yet reflected
+/// in their used data.
+pub trait ReadStorageCommitmentsInterface {
+    /// The type which can be used to identify accounts.
+    type AccountId: Parameter + Member + MaybeSerializeDeserialize + Debug + Ord + MaxEncodedLen;
+    /// Data type for the measurement of storage size
+    type StorageData: Parameter
+        + Member
+        + MaybeSerializeDeserialize
+        + Default
+        + MaybeDisplay
+        + AtLeast32BitUnsigned
+        + Copy
+        + MaxEncodedLen
+        + HasCompact;
+
+    /// Get the total size of the files of the open storage requests that a BSP has volunteered for but has not
+    /// confirmed storing yet.
+    ///
+    /// This is read when a BSP changes its capacity, so it should not iterate over the storage requests.
+    fn pending_volunteered_storage(who: &Self::AccountId) -> Self::StorageData;
+}
+
 /// The interface for the ProofsDealer pallet.
 ///
 /// It is abstracted over the `Provider` type, `Proof` type and `MerkleHash` type.
@@ -210,10 +303,22 @@ pub trait ProofsDealerInterface {
         + MaxEncodedLen
         + FullCodec;
 
-    /// Verify a proof for a given Provider, who should have a given Root.
+    /// Verify a proof for a given Provider, who should have a given Root, answering the given challenges.
+    ///
+    /// The challenges should be sorted in ascending order and not repeated.
     fn verify_proof(
         who: &Self::Provider,
         root: &Self::MerkleHash,
+        challenges: &[Self::MerkleHash],
+        proof: &Self::Proof,
+    ) -> DispatchResult;
+
+    /// Verify a proof for a given Provider, who should have a given Root, showing that `key` is part of the data
+    /// committed to by that Root.
+    fn verify_key_proof(
+        who: &Self::Provider,
+        root: &Self::MerkleHash,
+        key: &Self::MerkleHash,
         proof: &Self::Proof,
     ) -> DispatchResult;
 
@@ -241,3 +346,45 @@ pub trait CommitmentVerifier {
         proof: &Self::Proof,
     ) -> DispatchResult;
 }
+
+/// A trait to verify that a commitment (e.g. a Merkle root) is a valid successor of another one.
+///
+/// It is abstracted over the `Proof` and `Key` type.
+pub trait RootTransitionVerifier {
+    /// The type that represents the proof.
+    type Proof: Parameter + Member + Debug;
+    /// The type that represents the commitments (e.g. Merkle roots).
+    type Key: Debug + Ord + Default + Copy + AsRef<[u8]> + AsMut<[u8]>;
+
+    /// Compute the commitment that results from modifying the data committed to by `old_root` as described by `proof`.
+    fn apply_transition(
+        old_root: &Self::Key,
+        proof: &Self::Proof,
+    ) -> Result<Self::Key, DispatchError>;
+
+    /// Verify a proof that the commitment `new_root` results from modifying the data committed to by `old_root`.
+    fn verify_transition(
+        old_root: &Self::Key,
+        new_root: &Self::Key,
+        proof: &Self::Proof,
+    ) -> DispatchResult {
+        if Self::apply_transition(old_root, proof)? == *new_root {
+            Ok(())
+        } else {
+            Err("New root doesn't match the root resulting from the transition.".into())
+        }
+    }
+}
+
+/// A trait to inspect the structure of a file as implied by a proof of it.
+///
+/// It is abstracted over the `Proof` type.
+pub trait FileProofInspector {
+    /// The type that represents the proof.
+    type Proof: Parameter + Member + Debug;
+
+    /// Get the number of chunks of the file the proof was generated for.
+    ///
+    /// Returns `None` if the number of chunks cannot be inferred from the proof.
+    fn chunk_count(proof: &Self::Proof) -> Option<u64>;
+}
@@ -0,0 +1,52 @@
+import { expect, test, describe, beforeAll, afterAll } from "bun:test";
+import type { Subprocess } from "bun";
+import { createClient, type PolkadotClient } from "polkadot-api";
+import { WebSocketProvider } from "polkadot-api/ws-provider/node";
+import { waitForChain } from "../../util";
+
+// Requires the node to be built first e.g. `cargo build --release`
+const NODE_BINARY = process.env.SH_BINARY ?? "../target/release/storage-hub-node";
+const RPC_PORT = 9988;
+const SEALING_INTERVAL_MS = 2000;
+
+describe("Dev node interval sealing", () => {
+  let node: Subprocess;
+  let client: PolkadotClient;
+
+  beforeAll(async () => {
+    node = Bun.spawn(
+      [
+        NODE_BINARY,
+        "--dev",
+        "--tmp",
+        "--no-hardware-benchmarks",
+        `--rpc-port=${RPC_PORT}`,
+        `--sealing=${SEALING_INTERVAL_MS}`,
+      ],
+      { stdout: "ignore", stderr: "ignore" },
+    );
+    client = createClient(WebSocketProvider(`ws://127.0.0.1:${RPC_PORT}`));
+    await waitForChain(client);
+  });
+
+  afterAll(() => {
+    client?.destroy();
+    node?.kill();
+  });
+
+  test(
+    "Blocks are authored roughly every sealing interval",
+    async () => {
+      const intervals = 5;
+      const startHeight = (await client.getBlockHeader()).number;
+      await new Promise((resolve) => setTimeout(resolve, intervals * SEALING_INTERVAL_MS));
+      const endHeight = (await client.getBlockHeader()).number;
+
+      // Allow one block of slack on either side for timer alignment.
+      const produced = endHeight - startHeight;
+      expect(produced).toBeGreaterThanOrEqual(intervals - 1);
+      expect(produced).toBeLessThanOrEqual(intervals + 1);
+    },
+    { timeout: 30000 },
+  );
+});